@@ -0,0 +1,120 @@
+//! `--agent-mode`: a stable output contract for programmatic callers.
+//!
+//! With `--agent-mode`:
+//! - stdout carries only the wrapped command's stdout, byte for byte.
+//! - shnote's own stderr output is one JSON object per line, tagged by `type`:
+//!   `{"type":"header","what":...,"why":...}` before the command starts, and
+//!   `{"type":"error","message":...,"exit_code":125}` when shnote itself fails,
+//!   and `{"type":"warning","message":...}` / `{"type":"info","message":...}`
+//!   for shnote's other notes (a failed hook, `--measure` results, ...).
+//!   The wrapped command's stderr passes through untouched.
+//! - The exit code is the wrapped command's exit code, or [`EXIT_SHNOTE_ERROR`]
+//!   when shnote fails before or while launching it (bad arguments, missing
+//!   interpreter, spawn failure).
+
+use std::process::ExitCode;
+
+use serde_json::json;
+
+pub const AGENT_MODE_FLAG: &str = "--agent-mode";
+
+/// Exit code reserved for shnote's own failures in agent mode.
+pub const EXIT_SHNOTE_ERROR: u8 = 125;
+
+/// Whether `--agent-mode` appears in the raw arguments (before clap parsing,
+/// so argument errors can already be reported as JSON).
+pub fn requested(args: &[String]) -> bool {
+    args.iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == AGENT_MODE_FLAG)
+}
+
+pub fn header_line(what: &str, why: &str) -> String {
+    json!({ "type": "header", "what": what, "why": why }).to_string()
+}
+
+pub fn error_line(message: &str) -> String {
+    json!({
+        "type": "error",
+        "message": message,
+        "exit_code": EXIT_SHNOTE_ERROR,
+    })
+    .to_string()
+}
+
+/// Print one of shnote's warnings on stderr: as is, or in agent mode as a
+/// `warning` line.
+pub fn warn(agent_mode: bool, message: &str) {
+    eprintln!("{}", note_line(agent_mode, "warning", message));
+}
+
+/// Print one of shnote's informational notes on stderr: as is, or in agent
+/// mode as an `info` line.
+pub fn info(agent_mode: bool, message: &str) {
+    eprintln!("{}", note_line(agent_mode, "info", message));
+}
+
+fn note_line(agent_mode: bool, kind: &str, message: &str) -> String {
+    if agent_mode {
+        json!({ "type": kind, "message": message }).to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+/// Report a shnote failure on stderr and return the agent-mode error exit code.
+pub fn report_error(message: &str) -> ExitCode {
+    eprintln!("{}", error_line(message));
+    ExitCode::from(EXIT_SHNOTE_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn requested_detects_flag_before_separator() {
+        assert!(requested(&args(&["shnote", "--agent-mode", "run", "ls"])));
+        assert!(!requested(&args(&["shnote", "run", "ls"])));
+        assert!(!requested(&args(&[
+            "shnote",
+            "run",
+            "--",
+            "x",
+            "--agent-mode"
+        ])));
+        assert!(!requested(&args(&["--agent-mode"])));
+    }
+
+    #[test]
+    fn header_line_is_single_line_json() {
+        let line = header_line("List \"files\"", "Check\nlayout");
+        assert!(!line.contains('\n'));
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["type"], "header");
+        assert_eq!(value["what"], "List \"files\"");
+        assert_eq!(value["why"], "Check\nlayout");
+    }
+
+    #[test]
+    fn note_line_is_json_only_in_agent_mode() {
+        assert_eq!(note_line(false, "warning", "hook failed"), "hook failed");
+        let value: Value = serde_json::from_str(&note_line(true, "warning", "a\nb")).unwrap();
+        assert_eq!(value["type"], "warning");
+        assert_eq!(value["message"], "a\nb");
+    }
+
+    #[test]
+    fn error_line_carries_message_and_exit_code() {
+        let value: Value = serde_json::from_str(&error_line("boom")).unwrap();
+        assert_eq!(value["type"], "error");
+        assert_eq!(value["message"], "boom");
+        assert_eq!(value["exit_code"], 125);
+    }
+}
@@ -0,0 +1,153 @@
+//! `run --announce`: mirror the WHAT/WHY to a channel outside the terminal,
+//! so a human can follow what an agent is doing.
+//!
+//! The channel is the `announce_to` config value: empty (or `notify`) sends a
+//! desktop notification, anything else is a file or named pipe that gets one
+//! JSON line per command. Announcing is best-effort: a failure prints a
+//! warning and the command runs anyway.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::agent;
+use crate::history::unix_now;
+use crate::i18n::I18n;
+
+/// `announce_to` value for a desktop notification.
+pub const NOTIFY: &str = "notify";
+
+/// Announce `what`/`why` on `target`, warning on stderr if that fails.
+pub fn announce(i18n: &I18n, target: &str, what: &str, why: &str, agent_mode: bool) {
+    let result = match target.trim() {
+        "" | NOTIFY => notify(what, why),
+        path => append_line(Path::new(path), what, why),
+    };
+    if let Err(err) = result {
+        agent::warn(agent_mode, &i18n.warn_announce_failed(&format!("{err:#}")));
+    }
+}
+
+/// Append `{"ts":..,"what":..,"why":..}` to `path`. A named pipe without a
+/// reader fails right away instead of blocking the command.
+fn append_line(path: &Path, what: &str, why: &str) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.custom_flags(libc::O_NONBLOCK);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let line = json!({ "ts": unix_now(), "what": what, "why": why });
+    writeln!(file, "{line}").with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Start the platform's notifier without waiting for it, so a slow or
+/// hanging notifier never delays the command.
+fn notify(what: &str, why: &str) -> Result<()> {
+    let mut cmd = notify_command(what, why);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {:?}", cmd.get_program()))?;
+    // Reap it in the background; whatever it reports is not our concern.
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn notify_command(what: &str, why: &str) -> Command {
+    let mut cmd = Command::new("osascript");
+    // Passed as arguments rather than spliced into the script, so quotes in
+    // the notes need no escaping.
+    cmd.args([
+        "-e",
+        "on run argv",
+        "-e",
+        "display notification (item 2 of argv) with title (item 1 of argv)",
+        "-e",
+        "end run",
+        what,
+        why,
+    ]);
+    cmd
+}
+
+#[cfg(windows)]
+fn notify_command(what: &str, why: &str) -> Command {
+    const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+        $n = New-Object System.Windows.Forms.NotifyIcon; \
+        $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; \
+        $n.ShowBalloonTip(5000, $env:SHNOTE_ANNOUNCE_WHAT, $env:SHNOTE_ANNOUNCE_WHY, 'Info'); \
+        Start-Sleep -Seconds 5; $n.Dispose()";
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("SHNOTE_ANNOUNCE_WHAT", what)
+        .env("SHNOTE_ANNOUNCE_WHY", why);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn notify_command(what: &str, why: &str) -> Command {
+    let mut cmd = Command::new("notify-send");
+    cmd.args(["--app-name=shnote", "--", what, why]);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn announce_appends_json_lines_to_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("announce.log");
+
+        append_line(&path, "Build", "Check \"quotes\"").unwrap();
+        append_line(&path, "Test", "CI").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["what"], "Build");
+        assert_eq!(lines[0]["why"], "Check \"quotes\"");
+        assert_eq!(lines[1]["what"], "Test");
+        assert!(lines[1]["ts"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn append_line_reports_unwritable_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let err = append_line(&temp_dir.path().join("missing/dir/log"), "a", "b").unwrap_err();
+        assert!(err.to_string().contains("failed to open"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn append_line_does_not_block_on_pipe_without_reader() {
+        use std::ffi::CString;
+
+        let temp_dir = TempDir::new().unwrap();
+        let fifo = temp_dir.path().join("fifo");
+        let c_path = CString::new(fifo.to_str().unwrap()).unwrap();
+        // SAFETY: `c_path` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        assert!(append_line(&fifo, "a", "b").is_err());
+    }
+}
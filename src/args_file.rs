@@ -0,0 +1,158 @@
+//! `run --args-from <FILE>`: read extra arguments from a file, one per line.
+//!
+//! Leading/trailing whitespace is trimmed and blank lines are skipped. A line
+//! wrapped in single quotes is taken literally (keeping inner whitespace); one
+//! wrapped in double quotes also understands `\"`, `\\`, `\n` and `\t`. The
+//! arguments are appended to the inline command before history is recorded,
+//! so `history` and `replay` see the full command.
+
+use std::ffi::OsString;
+use std::fs;
+
+use anyhow::{Context, Result};
+
+use crate::cli::{Cli, Command};
+use crate::exit_code;
+use crate::i18n::I18n;
+
+/// Append the arguments from `run --args-from` to the command and clear the
+/// flag. Other commands are left untouched.
+pub fn expand(i18n: &I18n, cli: &mut Cli) -> Result<()> {
+    let Command::Run(args) = &mut cli.command else {
+        return Ok(());
+    };
+    let Some(path) = args.args_from.take() else {
+        return Ok(());
+    };
+    let display = path.display().to_string();
+    let contents = fs::read_to_string(&path).with_context(|| i18n.err_args_from_read(&display))?;
+    let extra = parse_args(&contents)
+        .map_err(|line| exit_code::validation(i18n.err_args_from_quote(&display, line)))?;
+    args.command.extend(extra.into_iter().map(OsString::from));
+    Ok(())
+}
+
+/// Split `contents` into arguments; on a malformed quoted line, returns its
+/// 1-based line number.
+fn parse_args(contents: &str) -> Result<Vec<String>, usize> {
+    let mut args = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let arg = match line.as_bytes()[0] {
+            b'\'' => line
+                .strip_prefix('\'')
+                .and_then(|rest| rest.strip_suffix('\''))
+                .map(str::to_string),
+            b'"' => line
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .and_then(unescape),
+            _ => Some(line.to_string()),
+        };
+        args.push(arg.ok_or(index + 1)?);
+    }
+    Ok(args)
+}
+
+/// Resolve backslash escapes inside a double-quoted argument. Returns `None`
+/// for an unescaped `"` or a dangling backslash.
+fn unescape(quoted: &str) -> Option<String> {
+    let mut out = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                escaped @ ('"' | '\\') => out.push(escaped),
+                // Unknown escapes stay as written, so Windows paths work
+                other => {
+                    out.push('\\');
+                    out.push(other);
+                }
+            },
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RunArgs;
+    use crate::i18n::Lang;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_args_reads_one_argument_per_line() {
+        let args = parse_args("--name\n  value with spaces  \n\n-x\r\n").unwrap();
+        assert_eq!(args, ["--name", "value with spaces", "-x"]);
+    }
+
+    #[test]
+    fn parse_args_handles_quotes() {
+        let contents = "'  keep  '\n\"a \\\"b\\\" \\\\ c\\td\"\n''\n\"C:\\dir\"\n'it's'\n";
+        let args = parse_args(contents).unwrap();
+        assert_eq!(args, ["  keep  ", "a \"b\" \\ c\td", "", "C:\\dir", "it's"]);
+    }
+
+    #[test]
+    fn parse_args_rejects_malformed_quotes() {
+        assert_eq!(parse_args("ok\n'open\n"), Err(2));
+        assert_eq!(parse_args("\"a\"b\"\n"), Err(1));
+        assert_eq!(parse_args("\"dangling\\\"\n"), Err(1));
+    }
+
+    fn run_cli(args_from: Option<PathBuf>) -> Cli {
+        Cli {
+            what: Some("w".to_string()),
+            why: Some("y".to_string()),
+            lang: None,
+            header_stream: None,
+            plain: false,
+            agent_mode: false,
+            allow_nonzero: false,
+            note_tag: None,
+            capture: false,
+            offline: false,
+            version_check_interval: None,
+            command: Command::Run(RunArgs {
+                args_from,
+                command: vec![OsString::from("echo"), OsString::from("inline")],
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn expand_appends_file_arguments() {
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("args.txt");
+        fs::write(&path, "one\n'two words'\n").unwrap();
+
+        let mut cli = run_cli(Some(path));
+        expand(&i18n, &mut cli).unwrap();
+        let Command::Run(args) = &cli.command else {
+            panic!("expected run");
+        };
+        assert_eq!(args.command, ["echo", "inline", "one", "two words"]);
+        assert!(args.args_from.is_none());
+    }
+
+    #[test]
+    fn expand_reports_missing_file() {
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let mut cli = run_cli(Some(temp_dir.path().join("missing.txt")));
+
+        let err = expand(&i18n, &mut cli).unwrap_err().to_string();
+        assert!(err.contains("missing.txt"), "{err}");
+    }
+}
@@ -0,0 +1,468 @@
+//! `shnote batch FILE`: run shell commands from a file, one per line, and
+//! summarize which steps passed.
+//!
+//! Each step runs like a single-string `run` (through the configured shell).
+//! By default the batch stops at the first failing step (`--fail-fast`);
+//! `--keep-going` runs every step. The summary goes to stderr, as text or as
+//! one JSON object with `--json`, so stdout carries only the steps' output.
+//! The exit code is 0 when every step succeeded, otherwise the exit code of
+//! the first failing step.
+//!
+//! With `--parallel N`, up to N steps run at once with stdin closed. Each
+//! step's output is captured and written out in step order once that step
+//! and all before it have finished, so output never interleaves. After a
+//! failure under fail-fast, steps already running finish and the rest are
+//! skipped.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::capture::{Excerpts, OutputCapture};
+use crate::cli::{BatchArgs, RunArgs};
+use crate::config::Config;
+use crate::executor::{exec_captured, exec_run, forward_captured, CapturedRun, RunOutcome};
+use crate::i18n::{status_marker, I18n, Marker};
+
+/// Version of the `batch --json` summary layout; bump it when fields are
+/// renamed, removed or change meaning.
+const BATCH_JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StepStatus {
+    Succeeded,
+    Failed(u8),
+    /// Not run because an earlier step failed (fail-fast)
+    Skipped,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct StepResult {
+    command: String,
+    status: StepStatus,
+}
+
+/// `capture_bytes` keeps the end of the steps' combined output (`--capture`).
+pub fn run_batch(
+    i18n: &I18n,
+    config: &Config,
+    args: BatchArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let contents = read_batch_file(i18n, &args.file)?;
+    let fail_fast = !args.keep_going;
+    let mut excerpts = capture_bytes.map(|_| Excerpts::default());
+    let mut keep = |step: Excerpts| {
+        if let (Some(excerpts), Some(limit)) = (&mut excerpts, capture_bytes) {
+            excerpts.append(step, limit);
+        }
+    };
+    let mut run_time = None;
+    let results = match args.parallel {
+        Some(jobs) if jobs > 1 => {
+            // Steps overlap, so their run time is the span of the whole batch
+            let started = Instant::now();
+            let results = run_steps_parallel(
+                parse_steps(&contents),
+                fail_fast,
+                jobs as usize,
+                |command| exec_captured(i18n, config, command),
+                |run| {
+                    forward_captured(config, run);
+                    let capture = OutputCapture::new(capture_bytes.unwrap_or(0));
+                    capture.record(&run.stdout, &run.stderr);
+                    keep(capture.excerpts());
+                },
+            )?;
+            run_time = Some(started.elapsed());
+            results
+        }
+        _ => run_steps(parse_steps(&contents), fail_fast, |command| {
+            let outcome = exec_run(
+                i18n,
+                config,
+                RunArgs {
+                    command: vec![OsString::from(command)],
+                    ..Default::default()
+                },
+                capture_bytes,
+            )?;
+            if let Some(step) = outcome.run_time {
+                run_time = Some(run_time.unwrap_or_default() + step);
+            }
+            keep(outcome.excerpts.unwrap_or_default());
+            Ok(outcome.code)
+        })?,
+    };
+
+    if args.json {
+        eprintln!("{}", summary_json(&results));
+    } else {
+        for line in summary_lines(i18n, &results, config.plain) {
+            eprintln!("{line}");
+        }
+    }
+    Ok(RunOutcome {
+        code: batch_exit_code(&results),
+        run_time,
+        excerpts,
+        cwd: None,
+    })
+}
+
+fn read_batch_file(i18n: &I18n, path: &Path) -> Result<String> {
+    let display = path.display().to_string();
+    if path == Path::new("-") {
+        let mut contents = String::new();
+        io::stdin()
+            .read_to_string(&mut contents)
+            .context(i18n.err_batch_read(&display))?;
+        return Ok(contents);
+    }
+    fs::read_to_string(path).with_context(|| i18n.err_batch_read(&display))
+}
+
+/// One command per non-blank line; lines starting with `#` are comments.
+fn parse_steps(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Run each step with `run_step`; after a failure, the remaining steps are
+/// skipped when `fail_fast` is set.
+fn run_steps(
+    steps: Vec<String>,
+    fail_fast: bool,
+    mut run_step: impl FnMut(&str) -> Result<u8>,
+) -> Result<Vec<StepResult>> {
+    let mut results = Vec::with_capacity(steps.len());
+    let mut failed = false;
+    for command in steps {
+        let status = if failed && fail_fast {
+            StepStatus::Skipped
+        } else {
+            match run_step(&command)? {
+                0 => StepStatus::Succeeded,
+                code => {
+                    failed = true;
+                    StepStatus::Failed(code)
+                }
+            }
+        };
+        results.push(StepResult { command, status });
+    }
+    Ok(results)
+}
+
+/// Run up to `jobs` steps at a time with `run_step`, handing each finished
+/// step to `show` in step order. Under `fail_fast`, steps not yet started
+/// when one fails are skipped. An error from `run_step` is returned once the
+/// running steps are done.
+fn run_steps_parallel(
+    steps: Vec<String>,
+    fail_fast: bool,
+    jobs: usize,
+    run_step: impl Fn(&str) -> Result<CapturedRun> + Sync,
+    mut show: impl FnMut(&CapturedRun),
+) -> Result<Vec<StepResult>> {
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let mut finished: Vec<Option<Result<Option<CapturedRun>>>> =
+        steps.iter().map(|_| None).collect();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(steps.len()) {
+            let sender = sender.clone();
+            let (steps, next, stop, run_step) = (&steps, &next, &stop, &run_step);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(command) = steps.get(index) else {
+                    break;
+                };
+                let result = if stop.load(Ordering::SeqCst) {
+                    Ok(None)
+                } else {
+                    run_step(command).map(Some)
+                };
+                match &result {
+                    Ok(Some(run)) if run.code != 0 && fail_fast => {
+                        stop.store(true, Ordering::SeqCst)
+                    }
+                    Err(_) => stop.store(true, Ordering::SeqCst),
+                    _ => {}
+                }
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut shown = 0;
+        for (index, result) in receiver {
+            finished[index] = Some(result);
+            while let Some(Some(result)) = finished.get(shown) {
+                if let Ok(Some(run)) = result {
+                    show(run);
+                }
+                shown += 1;
+            }
+        }
+    });
+
+    let mut results = Vec::with_capacity(steps.len());
+    for (command, result) in steps.into_iter().zip(finished) {
+        let status = match result.expect("every step reports back")? {
+            Some(CapturedRun { code: 0, .. }) => StepStatus::Succeeded,
+            Some(run) => StepStatus::Failed(run.code),
+            None => StepStatus::Skipped,
+        };
+        results.push(StepResult { command, status });
+    }
+    Ok(results)
+}
+
+fn batch_exit_code(results: &[StepResult]) -> u8 {
+    results
+        .iter()
+        .find_map(|result| match result.status {
+            StepStatus::Failed(code) => Some(code),
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+fn count(results: &[StepResult], matches: fn(StepStatus) -> bool) -> usize {
+    results
+        .iter()
+        .filter(|result| matches(result.status))
+        .count()
+}
+
+fn summary_lines(i18n: &I18n, results: &[StepResult], plain: bool) -> Vec<String> {
+    let mut lines = vec![i18n.batch_summary(
+        results.len(),
+        count(results, |status| status == StepStatus::Succeeded),
+        count(results, |status| matches!(status, StepStatus::Failed(_))),
+        count(results, |status| status == StepStatus::Skipped),
+    )];
+    for (index, result) in results.iter().enumerate() {
+        let (marker, detail) = match result.status {
+            StepStatus::Succeeded => (status_marker(Marker::Ok, plain), String::new()),
+            StepStatus::Failed(code) => (
+                status_marker(Marker::Fail, plain),
+                format!(" ({})", i18n.batch_step_exit(code)),
+            ),
+            StepStatus::Skipped => (
+                status_marker(Marker::Skip, plain),
+                format!(" ({})", i18n.batch_step_skipped()),
+            ),
+        };
+        lines.push(format!(
+            "  {marker} [{}] {}{detail}",
+            index + 1,
+            result.command
+        ));
+    }
+    lines
+}
+
+fn summary_json(results: &[StepResult]) -> serde_json::Value {
+    let steps: Vec<_> = results
+        .iter()
+        .map(|result| {
+            let (status, exit_code) = match result.status {
+                StepStatus::Succeeded => ("succeeded", Some(0)),
+                StepStatus::Failed(code) => ("failed", Some(code)),
+                StepStatus::Skipped => ("skipped", None),
+            };
+            json!({ "command": result.command, "status": status, "exit_code": exit_code })
+        })
+        .collect();
+    json!({
+        "schema_version": BATCH_JSON_SCHEMA_VERSION,
+        "total": results.len(),
+        "succeeded": count(results, |status| status == StepStatus::Succeeded),
+        "failed": count(results, |status| matches!(status, StepStatus::Failed(_))),
+        "skipped": count(results, |status| status == StepStatus::Skipped),
+        "steps": steps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+
+    fn steps(list: &[&str]) -> Vec<String> {
+        list.iter().map(|step| step.to_string()).collect()
+    }
+
+    /// Exit code of a fake step: the number in its command.
+    fn fake_step(command: &str) -> Result<u8> {
+        Ok(command.trim_start_matches("exit ").parse().unwrap())
+    }
+
+    #[test]
+    fn parse_steps_skips_blank_lines_and_comments() {
+        let parsed = parse_steps("# setup\necho one\n\n   \n  make test  \n#done\n");
+        assert_eq!(parsed, ["echo one", "make test"]);
+    }
+
+    #[test]
+    fn fail_fast_skips_steps_after_a_failure() {
+        let results = run_steps(steps(&["exit 0", "exit 2", "exit 0"]), true, fake_step).unwrap();
+        let statuses: Vec<_> = results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                StepStatus::Succeeded,
+                StepStatus::Failed(2),
+                StepStatus::Skipped
+            ]
+        );
+        assert_eq!(batch_exit_code(&results), 2);
+    }
+
+    #[test]
+    fn keep_going_runs_every_step() {
+        let results = run_steps(
+            steps(&["exit 0", "exit 3", "exit 0", "exit 4"]),
+            false,
+            fake_step,
+        )
+        .unwrap();
+        let statuses: Vec<_> = results.iter().map(|result| result.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                StepStatus::Succeeded,
+                StepStatus::Failed(3),
+                StepStatus::Succeeded,
+                StepStatus::Failed(4)
+            ]
+        );
+        assert_eq!(batch_exit_code(&results), 3);
+    }
+
+    #[test]
+    fn batch_exit_code_is_zero_when_all_succeed() {
+        let results = run_steps(steps(&["exit 0", "exit 0"]), true, fake_step).unwrap();
+        assert_eq!(batch_exit_code(&results), 0);
+        assert_eq!(batch_exit_code(&[]), 0);
+    }
+
+    #[test]
+    fn summary_lists_totals_and_each_step() {
+        let i18n = I18n::new(Lang::En);
+        let results = run_steps(steps(&["exit 0", "exit 1", "exit 0"]), true, fake_step).unwrap();
+
+        let lines = summary_lines(&i18n, &results, true);
+        assert_eq!(
+            lines,
+            [
+                "batch: 3 steps, 1 succeeded, 1 failed, 1 skipped",
+                "  [ok] [1] exit 0",
+                "  [fail] [2] exit 1 (exit code 1)",
+                "  [skip] [3] exit 0 (skipped)",
+            ]
+        );
+
+        let report = summary_json(&results);
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["total"], 3);
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["steps"][1]["exit_code"], 1);
+        assert_eq!(report["steps"][2]["status"], "skipped");
+        assert!(report["steps"][2]["exit_code"].is_null());
+    }
+
+    /// A fake captured step: `"<sleep ms> <exit code>"`, echoing its command.
+    fn fake_captured(command: &str) -> Result<CapturedRun> {
+        let (ms, code) = command.split_once(' ').unwrap();
+        thread::sleep(std::time::Duration::from_millis(ms.parse().unwrap()));
+        Ok(CapturedRun {
+            code: code.parse().unwrap(),
+            stdout: command.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn run_parallel(list: &[&str], fail_fast: bool) -> (Vec<StepStatus>, Vec<String>) {
+        let mut shown = Vec::new();
+        let results = run_steps_parallel(steps(list), fail_fast, 3, fake_captured, |run| {
+            shown.push(String::from_utf8(run.stdout.clone()).unwrap())
+        })
+        .unwrap();
+        assert_eq!(results.len(), list.len());
+        (results.iter().map(|result| result.status).collect(), shown)
+    }
+
+    #[test]
+    fn parallel_shows_output_in_step_order() {
+        let (statuses, shown) = run_parallel(&["150 0", "0 2", "50 1", "0 0"], false);
+        assert_eq!(shown, ["150 0", "0 2", "50 1", "0 0"]);
+        assert_eq!(
+            statuses,
+            [
+                StepStatus::Succeeded,
+                StepStatus::Failed(2),
+                StepStatus::Failed(1),
+                StepStatus::Succeeded
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_fail_fast_skips_steps_not_yet_started() {
+        let (statuses, shown) = run_parallel(&["50 4", "200 0", "200 0", "0 0", "0 0"], true);
+        assert_eq!(
+            statuses,
+            [
+                StepStatus::Failed(4),
+                StepStatus::Succeeded,
+                StepStatus::Succeeded,
+                StepStatus::Skipped,
+                StepStatus::Skipped
+            ]
+        );
+        assert_eq!(shown, ["50 4", "200 0", "200 0"]);
+        let results: Vec<_> = statuses
+            .into_iter()
+            .map(|status| StepResult {
+                command: String::new(),
+                status,
+            })
+            .collect();
+        assert_eq!(batch_exit_code(&results), 4);
+    }
+
+    #[test]
+    fn parallel_returns_step_errors() {
+        let err = run_steps_parallel(
+            steps(&["a", "b"]),
+            false,
+            2,
+            |command| match command {
+                "a" => anyhow::bail!("cannot spawn"),
+                _ => Ok(CapturedRun::default()),
+            },
+            |_| {},
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "cannot spawn");
+    }
+}
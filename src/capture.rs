@@ -0,0 +1,212 @@
+//! `--capture`: keep the end of the command's stdout and stderr for its
+//! history record.
+//!
+//! With a capture, the executor pipes the child's output instead of
+//! inheriting it and copies every chunk here on its way to the terminal.
+//! Only the last `history_capture_bytes` of each stream are kept, cut at a
+//! UTF-8 character boundary; invalid UTF-8 is replaced, so the excerpt is
+//! always valid JSON text. Without `--capture` nothing is piped, so
+//! interactive commands behave as before.
+//!
+//! `run --measure-output-size` uses the same pipe to count the bytes of each
+//! stream; without `--capture` it keeps no excerpt.
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+pub struct OutputCapture {
+    limit: usize,
+    stdout: Mutex<Vec<u8>>,
+    stderr: Mutex<Vec<u8>>,
+    stdout_bytes: AtomicU64,
+    stderr_bytes: AtomicU64,
+}
+
+/// Tails of the captured streams.
+#[derive(Debug, Default, PartialEq)]
+pub struct Excerpts {
+    pub stdout: String,
+    pub stderr: String,
+    /// Total bytes the command wrote to stdout
+    pub stdout_bytes: u64,
+    /// Total bytes the command wrote to stderr
+    pub stderr_bytes: u64,
+}
+
+impl Excerpts {
+    /// Follow these excerpts with those of a later command, keeping at most
+    /// `limit` bytes per stream.
+    pub fn append(&mut self, later: Excerpts, limit: usize) {
+        let join = |earlier: &str, later: &str| {
+            let joined = [earlier.as_bytes(), later.as_bytes()].concat();
+            String::from_utf8_lossy(tail(&joined, limit)).into_owned()
+        };
+        self.stdout = join(&self.stdout, &later.stdout);
+        self.stderr = join(&self.stderr, &later.stderr);
+        self.stdout_bytes += later.stdout_bytes;
+        self.stderr_bytes += later.stderr_bytes;
+    }
+}
+
+impl OutputCapture {
+    /// A capture keeping at most `limit` bytes per stream (none with a
+    /// `limit` of 0, which only counts bytes).
+    pub fn new(limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            ..Self::default()
+        })
+    }
+
+    /// Record output the command already finished writing.
+    pub fn record(&self, stdout: &[u8], stderr: &[u8]) {
+        self.add(false, stdout);
+        self.add(true, stderr);
+    }
+
+    /// What was kept so far.
+    pub fn excerpts(&self) -> Excerpts {
+        let (stdout_bytes, stderr_bytes) = self.byte_counts();
+        Excerpts {
+            stdout: self.excerpt(&self.stdout),
+            stderr: self.excerpt(&self.stderr),
+            stdout_bytes,
+            stderr_bytes,
+        }
+    }
+
+    /// Bytes seen so far on stdout and stderr.
+    pub fn byte_counts(&self) -> (u64, u64) {
+        (
+            self.stdout_bytes.load(Ordering::Relaxed),
+            self.stderr_bytes.load(Ordering::Relaxed),
+        )
+    }
+
+    fn add(&self, stderr: bool, bytes: &[u8]) {
+        let (stream, count) = if stderr {
+            (&self.stderr, &self.stderr_bytes)
+        } else {
+            (&self.stdout, &self.stdout_bytes)
+        };
+        count.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        self.push(stream, bytes);
+    }
+
+    fn push(&self, stream: &Mutex<Vec<u8>>, bytes: &[u8]) {
+        if self.limit == 0 {
+            return;
+        }
+        let Ok(mut buffer) = stream.lock() else {
+            return;
+        };
+        buffer.extend_from_slice(bytes);
+        // Trim in batches rather than on every chunk
+        if buffer.len() > self.limit.saturating_mul(2).max(4096) {
+            let excess = buffer.len() - self.limit;
+            buffer.drain(..excess);
+        }
+    }
+
+    fn excerpt(&self, stream: &Mutex<Vec<u8>>) -> String {
+        let Ok(buffer) = stream.lock() else {
+            return String::new();
+        };
+        String::from_utf8_lossy(tail(&buffer, self.limit)).into_owned()
+    }
+}
+
+/// The last `limit` bytes of `bytes` or fewer, starting at a character
+/// boundary (never inside a UTF-8 sequence).
+fn tail(bytes: &[u8], limit: usize) -> &[u8] {
+    let mut start = bytes.len().saturating_sub(limit);
+    while start < bytes.len() && bytes[start] & 0xC0 == 0x80 {
+        start += 1;
+    }
+    &bytes[start..]
+}
+
+/// A reader that copies what it passes on into a capture.
+pub struct CaptureReader<R> {
+    inner: R,
+    capture: Arc<OutputCapture>,
+    stderr: bool,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(inner: R, capture: Arc<OutputCapture>, stderr: bool) -> Self {
+        Self {
+            inner,
+            capture,
+            stderr,
+        }
+    }
+}
+
+impl<R: Read> Read for CaptureReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.capture.add(self.stderr, &buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_starts_on_a_char_boundary() {
+        let text = "aé€x".as_bytes(); // 1 + 2 + 3 + 1 bytes
+        assert_eq!(tail(text, 100), text);
+        assert_eq!(tail(text, 4), "€x".as_bytes());
+        assert_eq!(tail(text, 3), "x".as_bytes());
+        assert_eq!(tail(text, 0), b"");
+    }
+
+    #[test]
+    fn reader_keeps_the_end_of_each_stream() {
+        let capture = OutputCapture::new(5);
+        let input = "line one\nline two\n".repeat(1000);
+        let mut out = Vec::new();
+        CaptureReader::new(input.as_bytes(), Arc::clone(&capture), false)
+            .read_to_end(&mut out)
+            .unwrap();
+        CaptureReader::new(&b"oops"[..], Arc::clone(&capture), true)
+            .read_to_end(&mut Vec::new())
+            .unwrap();
+
+        assert_eq!(out, input.as_bytes());
+        assert_eq!(capture.excerpt(&capture.stdout), " two\n");
+        assert_eq!(capture.excerpt(&capture.stderr), "oops");
+        assert_eq!(capture.byte_counts(), (input.len() as u64, 4));
+    }
+
+    #[test]
+    fn zero_limit_only_counts_bytes() {
+        let capture = OutputCapture::new(0);
+        CaptureReader::new(&b"hello world"[..], Arc::clone(&capture), false)
+            .read_to_end(&mut Vec::new())
+            .unwrap();
+
+        assert_eq!(capture.byte_counts(), (11, 0));
+        assert_eq!(capture.excerpt(&capture.stdout), "");
+    }
+
+    #[test]
+    fn append_keeps_the_end_of_both_commands() {
+        let capture = OutputCapture::new(4);
+        capture.record(b"first", b"");
+        let mut excerpts = capture.excerpts();
+
+        let later = OutputCapture::new(4);
+        later.record(b"ab", b"oops");
+        excerpts.append(later.excerpts(), 4);
+
+        assert_eq!(excerpts.stdout, "stab");
+        assert_eq!(excerpts.stderr, "oops");
+        assert_eq!((excerpts.stdout_bytes, excerpts.stderr_bytes), (7, 4));
+    }
+}
@@ -1,9 +1,11 @@
 use std::ffi::OsString;
 use std::path::PathBuf;
 
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 
-use crate::i18n::I18n;
+use crate::exit_code;
+use crate::i18n::{I18n, Lang};
+use crate::update_notice;
 
 #[derive(Parser, Debug)]
 #[command(name = "shnote")]
@@ -11,11 +13,11 @@ use crate::i18n::I18n;
 #[command(subcommand_required = true)]
 #[command(arg_required_else_help = true)]
 pub struct Cli {
-    /// What this task does (required for run/py/node/pip/npm/npx, must appear before subcommand)
+    /// What this task does (required for run/py/node/sh/pip/npm/npx/batch, must appear before subcommand)
     #[arg(long, global = true)]
     pub what: Option<String>,
 
-    /// Why this task is being executed (required for run/py/node/pip/npm/npx, must appear before subcommand)
+    /// Why this task is being executed (required for run/py/node/sh/pip/npm/npx/batch, must appear before subcommand)
     #[arg(long, global = true)]
     pub why: Option<String>,
 
@@ -27,10 +29,41 @@ pub struct Cli {
     #[arg(long, global = true, value_enum)]
     pub header_stream: Option<HeaderStream>,
 
+    /// Plain mode: no WHAT/WHY header, no color, no glyphs
+    #[arg(long, global = true)]
+    pub plain: bool,
+
+    /// Agent mode: JSON WHAT/WHY and errors on stderr, untouched stdout, exit 125 on shnote errors
+    #[arg(long, global = true)]
+    pub agent_mode: bool,
+
+    /// With `run_strict` on, let a nonzero exit code pass through instead of failing
+    #[arg(long, global = true)]
+    pub allow_nonzero: bool,
+
+    /// Label stored with the history record to group related commands
+    #[arg(long, global = true, value_name = "TAG")]
+    pub note_tag: Option<String>,
+
+    /// Keep the end of the command's stdout/stderr in its history record (see history_capture_bytes)
+    #[arg(long, global = true)]
+    pub capture: bool,
+
+    /// Never use the network (skips doctor --check-updates and the new-version notice)
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// How often to look for a new shnote release after a command, e.g. 12h or 7d; "off" disables [default: config version_check_interval]
+    #[arg(long, global = true, value_name = "INTERVAL")]
+    pub version_check_interval: Option<String>,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+// Parsed once per process; boxing `RunArgs` would only get in the way of
+// the many `Command::Run(RunArgs { .. })` patterns.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Execute a shell command (passthrough)
@@ -42,6 +75,9 @@ pub enum Command {
     /// Execute a Node.js script
     Node(ScriptArgs),
 
+    /// Execute a shell script with the configured shell
+    Sh(ScriptArgs),
+
     /// Execute pip (Python package manager)
     Pip(PassthroughArgs),
 
@@ -51,6 +87,9 @@ pub enum Command {
     /// Execute npx (Node.js package runner)
     Npx(PassthroughArgs),
 
+    /// Run shell commands from a file one after another and summarize the results
+    Batch(BatchArgs),
+
     /// External subcommand fallback (treated as `run`)
     #[command(external_subcommand)]
     External(Vec<OsString>),
@@ -62,10 +101,10 @@ pub enum Command {
     Init(InitArgs),
 
     /// Initialize environment (extract pueue binaries, etc.)
-    Setup,
+    Setup(SetupArgs),
 
     /// Check environment dependencies (python/node/pueue)
-    Doctor,
+    Doctor(DoctorArgs),
 
     /// Generate shell completion scripts
     Completions(CompletionsArgs),
@@ -73,11 +112,47 @@ pub enum Command {
     /// Show installation information
     Info,
 
+    /// Show or manage command history
+    History(HistoryArgs),
+
+    /// Re-run a command from the history log
+    Replay(ReplayArgs),
+
     /// Update shnote to the latest version
     Update(UpdateArgs),
 
+    /// Remove leftover backups and downloads from earlier updates
+    Prune,
+
     /// Uninstall shnote
     Uninstall(UninstallArgs),
+
+    /// Check that the built-in rules render for every target and language (for development)
+    #[command(hide = true)]
+    TestRules,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct BatchArgs {
+    /// File with one shell command per line ("-" reads stdin); blank lines and # comments are skipped
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Stop at the first failing step (default)
+    #[arg(long, overrides_with = "keep_going")]
+    pub fail_fast: bool,
+
+    /// Run every step even after a failure
+    #[arg(long, overrides_with = "fail_fast")]
+    pub keep_going: bool,
+
+    /// Run up to N steps at a time; each step's output is shown in step order once it finishes
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub parallel: Option<u64>,
+
+    /// Print the summary as JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Args, Debug)]
@@ -89,6 +164,101 @@ pub struct UpdateArgs {
     /// Force update even if already up to date
     #[arg(long)]
     pub force: bool,
+
+    /// Install the latest release even if it is older than this version
+    #[arg(long)]
+    pub allow_downgrade: bool,
+
+    /// Checksum from the release manifest to verify the download against
+    /// (default: network.checksum_algo)
+    #[arg(long, value_enum, value_name = "ALGO")]
+    pub hash: Option<ChecksumAlgo>,
+}
+
+/// Digests `update` can verify a downloaded release with.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgo {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Digest size, as `shasum -a` takes it.
+    pub fn bits(self) -> u16 {
+        match self {
+            Self::Sha256 => 256,
+            Self::Sha512 => 512,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Report how the message language was detected
+    #[arg(long)]
+    pub check_locale: bool,
+
+    /// Also check whether a newer shnote release exists (uses the network)
+    #[arg(long)]
+    pub check_updates: bool,
+
+    /// Also check that the history log is writable and parseable
+    #[arg(long)]
+    pub check_history: bool,
+
+    /// Set from the global --offline flag
+    #[arg(skip)]
+    pub offline: bool,
+
+    /// Only check that installed shnote rules match this version (for CI); fails if any drifted
+    #[arg(long, conflicts_with = "check_locale")]
+    pub check_rules_drift: bool,
+
+    /// Also check an AI tool: whether it is installed and its shnote rules are current (repeatable)
+    #[arg(long, value_enum, value_name = "TOOL")]
+    pub check: Vec<AgentTool>,
+
+    /// Print the results as JSON
+    #[arg(long, conflicts_with = "check_locale")]
+    pub json: bool,
+}
+
+/// AI tools `doctor --check` can look for.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgentTool {
+    Claude,
+    Codex,
+    Gemini,
+}
+
+impl AgentTool {
+    /// The `init` target that writes this tool's rules.
+    pub fn target(self) -> InitTarget {
+        match self {
+            Self::Claude => InitTarget::Claude,
+            Self::Codex => InitTarget::Codex,
+            Self::Gemini => InitTarget::Gemini,
+        }
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct SetupArgs {
+    /// Download without asking, even when setup_confirm_download is enabled
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Only verify the SHA-256 of installed pueue binaries; exit 1 on mismatch
+    #[arg(long, conflicts_with = "yes")]
+    pub check_only: bool,
 }
 
 #[derive(Args, Debug)]
@@ -104,17 +274,23 @@ impl Command {
             Self::Run(_) => Some("run"),
             Self::Py(_) => Some("py"),
             Self::Node(_) => Some("node"),
+            Self::Sh(_) => Some("sh"),
             Self::Pip(_) => Some("pip"),
             Self::Npm(_) => Some("npm"),
             Self::Npx(_) => Some("npx"),
+            Self::Batch(_) => Some("batch"),
             Self::External(_) => Some("run"),
             Self::Config(_)
             | Self::Init(_)
-            | Self::Setup
-            | Self::Doctor
+            | Self::Setup(_)
+            | Self::Doctor(_)
             | Self::Completions(_)
             | Self::Info
+            | Self::History(_)
+            | Self::Replay(_)
             | Self::Update(_)
+            | Self::Prune
+            | Self::TestRules
             | Self::Uninstall(_) => None,
         }
     }
@@ -122,13 +298,39 @@ impl Command {
     pub fn requires_what_why(&self) -> bool {
         self.what_why_command_name().is_some()
     }
+
+    /// Whether `$VAR` references in config values are expanded before the
+    /// command runs. Commands that manage the configuration or the install
+    /// see the values as written, so a strict `expand_env` with an unset
+    /// variable cannot lock the user out of fixing it.
+    pub fn expands_config_env(&self) -> bool {
+        !matches!(
+            self,
+            Self::Config(_) | Self::Init(_) | Self::Setup(_) | Self::Doctor(_) | Self::Uninstall(_)
+        )
+    }
+
+    /// Whether `--json` was asked for, so stdout must carry only JSON.
+    pub fn prints_json(&self) -> bool {
+        match self {
+            Self::Batch(args) => args.json,
+            Self::Doctor(args) => args.json,
+            Self::History(args) => args.json,
+            Self::Config(args) => matches!(args.action, ConfigAction::Diff { json: true }),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Args, Debug)]
 pub struct CompletionsArgs {
     /// Shell to generate completions for
-    #[arg(value_enum)]
-    pub shell: Shell,
+    #[arg(value_enum, required_unless_present = "list")]
+    pub shell: Option<Shell>,
+
+    /// List the supported shells, one per line
+    #[arg(long, conflicts_with = "shell")]
+    pub list: bool,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -157,8 +359,166 @@ pub enum HeaderStream {
     Stderr,
 }
 
-#[derive(Args, Debug)]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigListFormat {
+    /// One `key = value` line per key
+    Plain,
+    /// Keys and values in aligned columns
+    Table,
+}
+
+#[derive(Args, Debug, Default)]
+#[command(group(ArgGroup::new("redirect").args(["redirect_stdout", "redirect_stderr"]).multiple(true)))]
 pub struct RunArgs {
+    /// Shell command to run when the wrapped command exits nonzero
+    #[arg(long, value_name = "COMMAND")]
+    pub on_failure: Option<String>,
+
+    /// Shell command to run when the wrapped command succeeds
+    #[arg(long, value_name = "COMMAND")]
+    pub on_success: Option<String>,
+
+    /// Directory to run the command in
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Run the command in the root of the enclosing git repository
+    #[arg(long, conflicts_with = "cwd")]
+    pub chdir_to_git_root: bool,
+
+    /// Run the command in a fresh temporary directory, removed afterwards
+    #[arg(long, conflicts_with_all = ["cwd", "chdir_to_git_root"])]
+    pub no_cwd_inherit: bool,
+
+    /// Wrapper program placed before the command, e.g. "time -v" (overrides config; "" disables)
+    #[arg(long, value_name = "PREFIX")]
+    pub exec_prefix: Option<String>,
+
+    /// Limit the command's address space to this many MiB (Unix only)
+    #[arg(long, value_name = "MB", value_parser = clap::value_parser!(u64).range(1..))]
+    pub mem_limit: Option<u64>,
+
+    /// Limit the command's CPU time to this many seconds (Unix only)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub cpu_limit: Option<u64>,
+
+    /// Map child exit codes to another exit code, e.g. "1=>0" or "0,1=>0" (repeatable)
+    #[arg(long, value_name = "CODES=>CODE")]
+    pub exit_on: Vec<String>,
+
+    /// Run the command on a pseudo-terminal so it behaves interactively (Unix only)
+    #[arg(long, conflicts_with = "no_tty")]
+    pub tty: bool,
+
+    /// Base64-encode the command's stdout, for binary output read by other programs
+    #[arg(long, conflicts_with = "tty")]
+    pub binary_safe: bool,
+
+    /// Report the command's peak memory and CPU time on stderr when it finishes (Unix only)
+    #[arg(long)]
+    pub measure: bool,
+
+    /// Report how many bytes the command wrote to stdout and stderr on stderr when it finishes
+    #[arg(long, conflicts_with_all = ["tty", "binary_safe", "until", "stdin_tty_passthrough"])]
+    pub measure_output_size: bool,
+
+    /// Also send WHAT/WHY to the announce_to channel (desktop notification or file)
+    #[arg(long)]
+    pub announce: bool,
+
+    /// Print the environment the command will receive (secrets masked) to stderr before running it
+    #[arg(long)]
+    pub print_env: bool,
+
+    /// Wait until the command's output contains this text, then leave it running in the background
+    #[arg(long, value_name = "PATTERN", conflicts_with_all = ["tty", "binary_safe", "measure"])]
+    pub until: Option<String>,
+
+    /// With --until, stop the command and fail if the text has not appeared after this many seconds [default: 60]
+    #[arg(long, value_name = "SECS", requires = "until", value_parser = clap::value_parser!(u64).range(1..))]
+    pub until_timeout: Option<u64>,
+
+    /// Give the command no stdin, so it cannot wait for input
+    #[arg(long)]
+    pub no_tty: bool,
+
+    /// Leave the terminal to the command: header on stderr, no output prefix (automatic for REPLs on a terminal)
+    #[arg(long, conflicts_with_all = ["no_tty", "binary_safe", "until"])]
+    pub stdin_tty_passthrough: bool,
+
+    /// Run the command as if typed directly: no header, output prefix, exec prefix or other run options; history is still recorded
+    #[arg(long, conflicts_with_all = [
+        "on_failure", "on_success", "exec_prefix", "mem_limit", "cpu_limit", "exit_on", "tty",
+        "no_tty", "binary_safe", "measure", "measure_output_size", "print_env", "until", "stdin_tty_passthrough", "once",
+        "announce", "log_file", "group", "confirm_each", "redirect_stdout", "redirect_stderr",
+        "print_pid", "pid_fd",
+    ])]
+    pub raw: bool,
+
+    /// Skip the command if the run labelled by --label already succeeded
+    #[arg(long, requires = "label")]
+    pub once: bool,
+
+    /// Key identifying the command for --once
+    #[arg(long, value_name = "KEY", requires = "once")]
+    pub label: Option<String>,
+
+    /// With --once, run again even if the label already succeeded
+    #[arg(long, requires = "once")]
+    pub force: bool,
+
+    /// Append arguments read from a file, one per line ('...' or "..." keep whitespace)
+    #[arg(long, value_name = "FILE")]
+    pub args_from: Option<PathBuf>,
+
+    /// Show the command with its WHAT/WHY and ask before running it (always on with run_confirm_each)
+    #[arg(long)]
+    pub confirm_each: bool,
+
+    /// Run without asking, even with --confirm-each or run_confirm_each
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Run `pueue add` in this pueue group (inserts `-g NAME`; the group must exist or is created on confirmation)
+    #[arg(long, value_name = "NAME")]
+    pub group: Option<String>,
+
+    /// Append a transcript of the run (WHAT/WHY, command, timestamped output, exit code) to this file
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["tty", "binary_safe", "until"])]
+    pub log_file: Option<PathBuf>,
+
+    /// With --log-file, keep the transcript only when the command exits nonzero (output held in memory, last 1 MiB)
+    #[arg(long, requires = "log_file")]
+    pub capture_on_failure_only: bool,
+
+    /// Write the command's stdout straight to this file instead of the terminal (truncated, like `>`)
+    #[arg(long, value_name = "FILE", conflicts_with_all = [
+        "tty", "binary_safe", "until", "stdin_tty_passthrough", "measure_output_size",
+    ])]
+    pub redirect_stdout: Option<PathBuf>,
+
+    /// Write the command's stderr straight to this file instead of the terminal (truncated, like `2>`)
+    #[arg(long, value_name = "FILE", conflicts_with_all = [
+        "tty", "binary_safe", "until", "stdin_tty_passthrough", "measure_output_size",
+    ])]
+    pub redirect_stderr: Option<PathBuf>,
+
+    /// With --redirect-stdout/--redirect-stderr, append to the files instead of truncating them (like `>>`)
+    #[arg(long, requires = "redirect")]
+    pub redirect_append: bool,
+
+    /// Write the command's PID to this file as soon as it starts
+    #[arg(long, value_name = "FILE", conflicts_with = "until")]
+    pub print_pid: Option<PathBuf>,
+
+    /// Write the command's PID to this open file descriptor as soon as it starts (Unix only)
+    #[arg(long, value_name = "N", conflicts_with = "until")]
+    pub pid_fd: Option<i32>,
+
+    /// WHAT/WHY for --log-file and --confirm-each, filled in from the global flags
+    #[arg(skip)]
+    pub note: Option<(String, String)>,
+
     /// Command and arguments to execute
     #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
     pub command: Vec<OsString>,
@@ -166,25 +526,34 @@ pub struct RunArgs {
 
 #[derive(Args, Debug)]
 pub struct PassthroughArgs {
+    /// Directory to run the command in (must appear before the passthrough arguments)
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
     /// Arguments to pass through to the underlying command
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub args: Vec<OsString>,
 }
 
 #[derive(Args, Debug)]
+#[command(group(ArgGroup::new("source").args(["code", "file", "stdin"]).multiple(false)))]
 pub struct ScriptArgs {
     /// Inline script code
-    #[arg(short = 'c', long = "code", conflicts_with_all = ["file", "stdin"])]
+    #[arg(short = 'c', long = "code")]
     pub code: Option<String>,
 
     /// Script file path
-    #[arg(short = 'f', long = "file", conflicts_with_all = ["code", "stdin"])]
+    #[arg(short = 'f', long = "file")]
     pub file: Option<PathBuf>,
 
     /// Read script from stdin (supports heredoc)
-    #[arg(long = "stdin", conflicts_with_all = ["code", "file"])]
+    #[arg(long = "stdin")]
     pub stdin: bool,
 
+    /// Interpreter to use for this run instead of the configured one (absolute path or name in PATH)
+    #[arg(long, value_name = "PATH")]
+    pub interpreter: Option<String>,
+
     /// Arguments passed to the script
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub args: Vec<OsString>,
@@ -192,7 +561,14 @@ pub struct ScriptArgs {
 
 impl ScriptArgs {
     pub fn has_source(&self) -> bool {
-        self.code.is_some() || self.file.is_some() || self.stdin
+        self.source_count() > 0
+    }
+
+    /// Number of script sources given (`--code`, `--file`, `--stdin`).
+    pub fn source_count(&self) -> usize {
+        usize::from(self.code.is_some())
+            + usize::from(self.file.is_some())
+            + usize::from(self.stdin)
     }
 }
 
@@ -206,8 +582,20 @@ pub struct ConfigArgs {
 pub enum ConfigAction {
     /// Get a configuration value
     Get {
-        /// Configuration key (e.g., python, node, shell, language, output, header_stream, header_timing, run_string_shell_mode, color, what_color, why_color)
+        /// Configuration key (e.g., python, node, shell, python_min_version, node_min_version, language, history, output, output_prefix, header_stream, header_timing, run_string_shell_mode, color, what_color, why_color, what_label, why_label)
         key: String,
+
+        /// Also show where the value comes from (default or user config file)
+        #[arg(long, conflicts_with = "raw")]
+        show_source: bool,
+
+        /// Write the stored value byte for byte, followed by a single newline
+        #[arg(long)]
+        raw: bool,
+
+        /// With --raw, leave out the trailing newline
+        #[arg(long, requires = "raw")]
+        no_newline: bool,
     },
 
     /// Set a configuration value
@@ -216,10 +604,25 @@ pub enum ConfigAction {
         key: String,
         /// Configuration value
         value: String,
+
+        /// Only validate the value and show what would be stored; don't save
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// List all configuration values
-    List,
+    List {
+        /// Output format: `plain` (key = value) or `table` (aligned columns)
+        #[arg(long, value_enum, default_value_t = ConfigListFormat::Plain)]
+        format: ConfigListFormat,
+    },
+
+    /// Show the keys whose values differ from the defaults
+    Diff {
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Reset configuration to defaults
     Reset,
@@ -228,14 +631,125 @@ pub enum ConfigAction {
     Path,
 }
 
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Only show commands recorded with this --note-tag
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// Only show commands run within this long ago, e.g. 2h or 3d
+    #[arg(long, value_name = "DURATION")]
+    pub since: Option<String>,
+
+    /// Show at most this many of the most recent commands
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub limit: usize,
+
+    /// Print the records as JSON, with a schema_version
+    #[arg(long)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub action: Option<HistoryAction>,
+}
+
+#[derive(Args, Debug)]
+pub struct ReplayArgs {
+    /// Number of the history entry, as shown by `shnote history`
+    pub id: usize,
+
+    /// Reuse the recorded WHAT/WHY instead of requiring new ones
+    #[arg(long)]
+    pub reuse_note: bool,
+
+    /// Skip the confirmation for destructive-looking commands
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// Delete the history file
+    Clear {
+        /// Skip confirmation prompt
+        #[arg(long, short = 'y')]
+        yes: bool,
+
+        /// Move the history file aside instead of deleting it
+        #[arg(long)]
+        archive: bool,
+    },
+
+    /// Write the history as CSV (all records matching --tag/--since)
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(long, short = 'o', value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Args, Debug)]
 pub struct InitArgs {
     /// Scope: user-level or project-level
     #[arg(short = 's', long = "scope", default_value = "user")]
     pub scope: Scope,
 
+    /// Show which files would be created or changed without writing anything
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Leave the pueue section out of the rules (the default)
+    #[arg(long, global = true, conflicts_with = "force_pueue")]
+    pub no_pueue: bool,
+
+    /// Add a section on running long commands in the background with pueue
+    #[arg(long, global = true)]
+    pub force_pueue: bool,
+
+    /// Copy an existing file to <file>.shnote.bak before changing it
+    #[arg(long, global = true)]
+    pub backup: bool,
+
+    /// List the supported targets, where each writes its rules, and whether the tool is installed
+    #[arg(long, conflicts_with = "remove")]
+    pub list_targets: bool,
+
+    /// Install rules for every target whose tool is installed, continuing past failures
+    #[arg(long, conflicts_with_all = ["list_targets", "remove"])]
+    pub all: bool,
+
+    /// Remove shnote's rules from the target's file instead of writing them, keeping everything else
+    #[arg(long, global = true, conflicts_with_all = ["force_pueue", "rules_lang", "set_var"])]
+    pub remove: bool,
+
+    /// Language of the installed rules (default: the message language)
+    #[arg(long, global = true, value_enum)]
+    pub rules_lang: Option<RulesLang>,
+
+    /// Replace {{KEY}} in the rules with VALUE (repeatable); EXTRA_RULES adds a paragraph after the tool list
+    #[arg(long, global = true, value_name = "KEY=VALUE")]
+    pub set_var: Vec<String>,
+
+    /// Required unless --list-targets is given
     #[command(subcommand)]
-    pub target: InitTarget,
+    pub target: Option<InitTarget>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RulesLang {
+    Zh,
+    En,
+    Ja,
+}
+
+impl RulesLang {
+    pub fn lang(self) -> Lang {
+        match self {
+            Self::Zh => Lang::Zh,
+            Self::En => Lang::En,
+            Self::Ja => Lang::Ja,
+        }
+    }
 }
 
 #[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
@@ -249,7 +763,7 @@ pub enum Scope {
     Project,
 }
 
-#[derive(Subcommand, Debug, Clone, Copy)]
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InitTarget {
     /// Install shnote rules for Claude Code (>= 2.0.64: ~/.claude/rules/shnote.md; otherwise: ~/.claude/CLAUDE.md)
     Claude,
@@ -259,19 +773,72 @@ pub enum InitTarget {
 
     /// Install or update shnote rules for Gemini (~/.gemini/GEMINI.md)
     Gemini,
+
+    /// Install or update shnote rules in the project-root AGENTS.md (project scope only)
+    Agents,
+
+    /// Install or update shnote rules for GitHub Copilot (.github/copilot-instructions.md, project scope only)
+    Copilot,
+
+    /// Install or update shnote rules for Cursor (.cursor/rules/shnote.mdc, project scope only)
+    Cursor,
+
+    /// Install or update shnote rules for Windsurf (~/.codeium/windsurf/memories/global_rules.md)
+    Windsurf,
+
+    /// Install or update shnote rules for Aider (~/.aider/CONVENTIONS.md; project scope: ./CONVENTIONS.md)
+    Aider,
+}
+
+impl InitTarget {
+    /// Every target, in `init --help` order.
+    pub const ALL: [InitTarget; 8] = [
+        InitTarget::Claude,
+        InitTarget::Codex,
+        InitTarget::Gemini,
+        InitTarget::Agents,
+        InitTarget::Copilot,
+        InitTarget::Cursor,
+        InitTarget::Windsurf,
+        InitTarget::Aider,
+    ];
+
+    /// Subcommand name, as typed after `shnote init`.
+    pub fn name(self) -> &'static str {
+        match self {
+            InitTarget::Claude => "claude",
+            InitTarget::Codex => "codex",
+            InitTarget::Gemini => "gemini",
+            InitTarget::Agents => "agents",
+            InitTarget::Copilot => "copilot",
+            InitTarget::Cursor => "cursor",
+            InitTarget::Windsurf => "windsurf",
+            InitTarget::Aider => "aider",
+        }
+    }
 }
 
 pub fn validate_what_why(i18n: &I18n, cli: &Cli) -> anyhow::Result<()> {
     if let Some(cmd_name) = cli.command.what_why_command_name() {
         if cli.what.is_none() || cli.why.is_none() {
-            anyhow::bail!("{}", i18n.err_missing_what_why(cmd_name));
+            return Err(exit_code::validation(i18n.err_missing_what_why(cmd_name)));
         }
     } else if cli.what.is_some() || cli.why.is_some() {
-        anyhow::bail!("{}", i18n.err_reject_root_meta());
+        return Err(exit_code::validation(i18n.err_reject_root_meta()));
     }
     Ok(())
 }
 
+/// Reject a `--version-check-interval` that `update_notice` cannot parse.
+pub fn validate_version_check_interval(i18n: &I18n, cli: &Cli) -> anyhow::Result<()> {
+    match cli.version_check_interval.as_deref() {
+        Some(value) if update_notice::parse_interval(value).is_none() => Err(
+            exit_code::validation(i18n.err_invalid_check_interval(value)),
+        ),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,6 +854,7 @@ mod tests {
 
         let run_cmd = Command::Run(RunArgs {
             command: vec![OsString::from("ls")],
+            ..Default::default()
         });
         assert!(run_cmd.requires_what_why());
 
@@ -294,6 +862,7 @@ mod tests {
             code: Some("print('hello')".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         });
         assert!(py_cmd.requires_what_why());
@@ -302,26 +871,42 @@ mod tests {
             code: Some("console.log('hello')".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         });
         assert!(node_cmd.requires_what_why());
 
         let config_cmd = Command::Config(ConfigArgs {
-            action: ConfigAction::List,
+            action: ConfigAction::List {
+                format: ConfigListFormat::Plain,
+            },
         });
         assert!(!config_cmd.requires_what_why());
 
         let external_cmd = Command::External(vec![OsString::from("echo"), OsString::from("hi")]);
         assert!(external_cmd.requires_what_why());
 
-        let setup_cmd = Command::Setup;
+        let setup_cmd = Command::Setup(SetupArgs::default());
         assert!(!setup_cmd.requires_what_why());
 
-        let doctor_cmd = Command::Doctor;
+        let doctor_cmd = Command::Doctor(DoctorArgs {
+            check_locale: false,
+            check_updates: false,
+            check_history: false,
+            offline: false,
+            check_rules_drift: false,
+            check: Vec::new(),
+            json: false,
+        });
         assert!(!doctor_cmd.requires_what_why());
 
-        let completions_cmd = Command::Completions(CompletionsArgs { shell: Shell::Bash });
+        let completions_cmd = Command::Completions(CompletionsArgs {
+            shell: Some(Shell::Bash),
+            list: false,
+        });
         assert!(!completions_cmd.requires_what_why());
+
+        assert!(!Command::Prune.requires_what_why());
     }
 
     #[test]
@@ -330,6 +915,7 @@ mod tests {
             code: Some("print('hello')".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
         assert!(with_code.has_source());
@@ -338,6 +924,7 @@ mod tests {
             code: None,
             file: Some(std::path::PathBuf::from("test.py")),
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
         assert!(with_file.has_source());
@@ -346,6 +933,7 @@ mod tests {
             code: None,
             file: None,
             stdin: true,
+            interpreter: None,
             args: vec![],
         };
         assert!(with_stdin.has_source());
@@ -354,11 +942,47 @@ mod tests {
             code: None,
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
         assert!(!no_source.has_source());
     }
 
+    #[test]
+    fn script_args_source_group_rejects_two_sources() {
+        use clap::error::ErrorKind;
+
+        for sources in [
+            ["-c", "1", "-f", "a.py"].as_slice(),
+            &["--code", "1", "--stdin"],
+            &["--file", "a.py", "--stdin"],
+        ] {
+            let argv = ["shnote", "--what", "w", "--why", "y", "py"]
+                .into_iter()
+                .chain(sources.iter().copied());
+            let err = Cli::try_parse_from(argv).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn prints_json_follows_json_flags() {
+        for argv in [
+            ["shnote", "doctor", "--json"].as_slice(),
+            &["shnote", "history", "--json"],
+            &["shnote", "config", "diff", "--json"],
+        ] {
+            assert!(Cli::try_parse_from(argv).unwrap().command.prints_json());
+        }
+        for argv in [
+            ["shnote", "doctor"].as_slice(),
+            &["shnote", "config", "diff"],
+            &["shnote", "info"],
+        ] {
+            assert!(!Cli::try_parse_from(argv).unwrap().command.prints_json());
+        }
+    }
+
     #[test]
     fn validate_what_why_missing() {
         use std::ffi::OsString;
@@ -369,8 +993,16 @@ mod tests {
             why: None,
             lang: None,
             header_stream: None,
+            plain: false,
+            agent_mode: false,
+            allow_nonzero: false,
+            note_tag: None,
+            capture: false,
+            offline: false,
+            version_check_interval: None,
             command: Command::Run(RunArgs {
                 command: vec![OsString::from("ls")],
+                ..Default::default()
             }),
         };
         assert!(validate_what_why(&i18n, &cli).is_err());
@@ -386,8 +1018,16 @@ mod tests {
             why: Some("testing".to_string()),
             lang: None,
             header_stream: None,
+            plain: false,
+            agent_mode: false,
+            allow_nonzero: false,
+            note_tag: None,
+            capture: false,
+            offline: false,
+            version_check_interval: None,
             command: Command::Run(RunArgs {
                 command: vec![OsString::from("ls")],
+                ..Default::default()
             }),
         };
         assert!(validate_what_why(&i18n, &cli).is_ok());
@@ -401,7 +1041,22 @@ mod tests {
             why: Some("testing".to_string()),
             lang: None,
             header_stream: None,
-            command: Command::Doctor,
+            plain: false,
+            agent_mode: false,
+            allow_nonzero: false,
+            note_tag: None,
+            capture: false,
+            offline: false,
+            version_check_interval: None,
+            command: Command::Doctor(DoctorArgs {
+                check_locale: false,
+                check_updates: false,
+                check_history: false,
+                offline: false,
+                check_rules_drift: false,
+                check: Vec::new(),
+                json: false,
+            }),
         };
         assert!(validate_what_why(&i18n, &cli).is_err());
     }
@@ -1,11 +1,16 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::i18n::I18n;
+use crate::semver::parse_semver_from_text;
+use crate::update_notice;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Config {
@@ -15,10 +20,34 @@ pub struct Config {
     #[serde(default)]
     pub i18n: I18nConfig,
 
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    #[serde(default)]
+    pub setup: SetupConfig,
+
+    #[serde(default)]
+    pub run: RunConfig,
+
+    #[serde(default)]
+    pub network: NetworkConfig,
+
     /// Output mode: default | quiet
     #[serde(default = "Config::default_output")]
     pub output: String,
 
+    /// Prefix prepended to every line of child output (supports `{what}`); empty disables
+    #[serde(default)]
+    pub output_prefix: String,
+
+    /// Flushing of child output when shnote pipes it (output prefix, `--tty`): line | block | none
+    #[serde(default = "Config::default_output_buffering")]
+    pub output_buffering: String,
+
+    /// Print a one-line exit summary (WHAT, exit code, duration) after execution commands
+    #[serde(default)]
+    pub output_summary: bool,
+
     /// Header stream routing: auto | stdout | stderr
     #[serde(default = "Config::default_header_stream")]
     pub header_stream: String,
@@ -42,6 +71,26 @@ pub struct Config {
     /// Color for WHY label
     #[serde(default = "Config::default_why_color")]
     pub why_color: String,
+
+    /// Text of the WHAT label; `auto` uses the display language
+    #[serde(default = "Config::default_what_label")]
+    pub what_label: String,
+
+    /// Text of the WHY label; `auto` uses the display language
+    #[serde(default = "Config::default_why_label")]
+    pub why_label: String,
+
+    /// Expand `$VAR`/`${VAR}` in string values when shnote runs: off | on | strict
+    #[serde(default = "Config::default_expand_env")]
+    pub expand_env: String,
+
+    /// Runtime-only: set by `--plain`, never persisted
+    #[serde(skip)]
+    pub plain: bool,
+
+    /// Runtime-only: set by `--agent-mode`, never persisted
+    #[serde(skip)]
+    pub agent_mode: bool,
 }
 
 impl Default for Config {
@@ -49,13 +98,25 @@ impl Default for Config {
         Self {
             paths: PathsConfig::default(),
             i18n: I18nConfig::default(),
+            history: HistoryConfig::default(),
+            setup: SetupConfig::default(),
+            run: RunConfig::default(),
+            network: NetworkConfig::default(),
             output: Self::default_output(),
+            output_prefix: String::new(),
+            output_buffering: Self::default_output_buffering(),
+            output_summary: false,
             header_stream: Self::default_header_stream(),
             header_timing: Self::default_header_timing(),
             run_string_shell_mode: Self::default_run_string_shell_mode(),
             color: Self::default_color(),
             what_color: Self::default_what_color(),
             why_color: Self::default_why_color(),
+            what_label: Self::default_what_label(),
+            why_label: Self::default_why_label(),
+            expand_env: Self::default_expand_env(),
+            plain: false,
+            agent_mode: false,
         }
     }
 }
@@ -78,6 +139,22 @@ impl HeaderStreamMode {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HistorySink {
+    File,
+    Http,
+}
+
+impl HistorySink {
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "file" => Some(Self::File),
+            "http" => Some(Self::Http),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum HeaderTiming {
     Head,
@@ -96,6 +173,44 @@ impl HeaderTiming {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExpandEnv {
+    Off,
+    /// Unset variables are left as written
+    On,
+    /// Unset variables are an error
+    Strict,
+}
+
+impl ExpandEnv {
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(Self::Off),
+            "on" => Some(Self::On),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputBuffering {
+    Line,
+    Block,
+    None,
+}
+
+impl OutputBuffering {
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "line" => Some(Self::Line),
+            "block" => Some(Self::Block),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RunStringShellMode {
     Lc,
@@ -132,6 +247,189 @@ const VALID_COLOR_NAMES: [&str; 17] = [
     "bright_white",
 ];
 
+/// A settable config key, as documented by `config -h`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigKeySpec {
+    pub key: &'static str,
+    /// Accepted values; empty for free-form values (paths, URLs, ...)
+    pub values: &'static [&'static str],
+}
+
+const BOOL_VALUES: &[&str] = &["true", "false"];
+
+/// A boolean config value: `true`/`false`, or `yes`/`no`, `on`/`off` and
+/// `1`/`0`, in any case. `config set` saves the result as `true`/`false`.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// [`parse_bool`] for `key`, with an error listing the canonical values.
+fn parse_bool_key(i18n: &I18n, key: &str, value: &str) -> Result<bool> {
+    parse_bool(value).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{}",
+            i18n.err_invalid_bool_value(key, value, &BOOL_VALUES.join(", "))
+        )
+    })
+}
+
+/// Every key accepted by [`Config::get`] and [`Config::set`], in `config list`
+/// order. The `config -h` key table is generated from this list.
+pub const CONFIG_KEYS: &[ConfigKeySpec] = &[
+    ConfigKeySpec {
+        key: "python",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "node",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "shell",
+        values: &["auto", "sh", "bash", "zsh", "pwsh", "cmd"],
+    },
+    ConfigKeySpec {
+        key: "python_min_version",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "node_min_version",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "language",
+        values: &["auto", "zh", "en", "ja"],
+    },
+    ConfigKeySpec {
+        key: "history",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "history_sink",
+        values: &["file", "http"],
+    },
+    ConfigKeySpec {
+        key: "history_http_url",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "history_http_auth_header",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "history_max_size_mb",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "history_capture_bytes",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "history_redact",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "setup_confirm_download",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "download_progress",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "exec_prefix",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "run_strict",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "announce_to",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "run_confirm_each",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "run_warn_shell_injection",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "run_log_dir",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "github_proxy",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "version_check_interval",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "checksum_algo",
+        values: &["sha256", "sha512"],
+    },
+    ConfigKeySpec {
+        key: "output",
+        values: &["default", "quiet"],
+    },
+    ConfigKeySpec {
+        key: "output_prefix",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "output_buffering",
+        values: &["line", "block", "none"],
+    },
+    ConfigKeySpec {
+        key: "output_summary",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "header_stream",
+        values: &["auto", "stdout", "stderr"],
+    },
+    ConfigKeySpec {
+        key: "header_timing",
+        values: &["head", "tail", "both"],
+    },
+    ConfigKeySpec {
+        key: "run_string_shell_mode",
+        values: &["lc", "ilc"],
+    },
+    ConfigKeySpec {
+        key: "color",
+        values: BOOL_VALUES,
+    },
+    ConfigKeySpec {
+        key: "what_color",
+        values: &VALID_COLOR_NAMES,
+    },
+    ConfigKeySpec {
+        key: "why_color",
+        values: &VALID_COLOR_NAMES,
+    },
+    ConfigKeySpec {
+        key: "what_label",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "why_label",
+        values: &[],
+    },
+    ConfigKeySpec {
+        key: "expand_env",
+        values: &["off", "on", "strict"],
+    },
+];
+
 fn is_valid_color_name(name: &str) -> bool {
     VALID_COLOR_NAMES.contains(&name)
 }
@@ -172,6 +470,14 @@ pub struct PathsConfig {
     /// Shell type: auto | sh | bash | zsh | pwsh | cmd
     #[serde(default = "PathsConfig::default_shell")]
     pub shell: String,
+
+    /// Minimum Python version required by py/pip (empty = no check)
+    #[serde(default)]
+    pub python_min_version: String,
+
+    /// Minimum Node.js version required by node (empty = no check)
+    #[serde(default)]
+    pub node_min_version: String,
 }
 
 impl Default for PathsConfig {
@@ -180,6 +486,8 @@ impl Default for PathsConfig {
             python: Self::default_python(),
             node: Self::default_node(),
             shell: Self::default_shell(),
+            python_min_version: String::new(),
+            node_min_version: String::new(),
         }
     }
 }
@@ -219,6 +527,161 @@ impl I18nConfig {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryConfig {
+    /// Record executed commands to `<data_dir>/history.jsonl`
+    #[serde(default = "HistoryConfig::default_enabled")]
+    pub enabled: bool,
+    /// Where records go: file | http (POST to `http_url`, falling back to the file)
+    #[serde(default = "HistoryConfig::default_sink")]
+    pub sink: String,
+    /// Endpoint receiving one JSON record per POST when `sink = "http"`
+    #[serde(default)]
+    pub http_url: String,
+    /// Extra request header for the endpoint, e.g. "Authorization: Bearer <token>"
+    #[serde(default)]
+    pub http_auth_header: String,
+    /// Size in MiB at which `history.jsonl` is rotated to `history.jsonl.1`; 0 = never
+    #[serde(default = "HistoryConfig::default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Bytes of stdout and of stderr kept per record with `--capture`
+    #[serde(default = "HistoryConfig::default_capture_bytes")]
+    pub capture_bytes: usize,
+    /// Mask credentials in argv and output excerpts before a record is
+    /// written; records sent to the http sink are always masked
+    #[serde(default = "HistoryConfig::default_redact")]
+    pub redact: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            sink: Self::default_sink(),
+            http_url: String::new(),
+            http_auth_header: String::new(),
+            max_size_mb: Self::default_max_size_mb(),
+            capture_bytes: Self::default_capture_bytes(),
+            redact: Self::default_redact(),
+        }
+    }
+}
+
+impl HistoryConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_max_size_mb() -> u64 {
+        10
+    }
+
+    fn default_capture_bytes() -> usize {
+        8 * 1024
+    }
+
+    fn default_redact() -> bool {
+        true
+    }
+
+    fn default_sink() -> String {
+        "file".to_string()
+    }
+
+    /// Parse the history sink.
+    /// Falls back to File for invalid or unknown values.
+    pub fn sink_mode(&self) -> HistorySink {
+        HistorySink::from_str(self.sink.as_str()).unwrap_or(HistorySink::File)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SetupConfig {
+    /// Ask before `setup` downloads pueue binaries
+    #[serde(default)]
+    pub confirm_download: bool,
+    /// Show a progress bar while `setup`/`update` download on a terminal
+    #[serde(default = "SetupConfig::default_download_progress")]
+    pub download_progress: bool,
+}
+
+impl Default for SetupConfig {
+    fn default() -> Self {
+        Self {
+            confirm_download: false,
+            download_progress: Self::default_download_progress(),
+        }
+    }
+}
+
+impl SetupConfig {
+    fn default_download_progress() -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunConfig {
+    /// Wrapper program and arguments prepended to every `run` command (e.g. ["time", "-v"])
+    #[serde(default)]
+    pub exec_prefix: Vec<String>,
+
+    /// Treat a nonzero exit of an execution command as a shnote error
+    #[serde(default)]
+    pub strict: bool,
+
+    /// Channel for `run --announce`: `notify` or a file/pipe path; empty = notify
+    #[serde(default)]
+    pub announce_to: String,
+
+    /// Ask before every execution command, as with `run --confirm-each`
+    #[serde(default)]
+    pub confirm_each: bool,
+
+    /// Warn when `run` hands a shell an inline script with `$(...)`, backticks, `eval` or `| sh`
+    #[serde(default)]
+    pub warn_shell_injection: bool,
+
+    /// Directory for `run --until` logs (`~/` = home, relative = under the data dir); empty = `<data_dir>/run-logs`
+    #[serde(default)]
+    pub log_dir: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NetworkConfig {
+    /// Proxy prefixed to GitHub download URLs (e.g. https://ghfast.top); empty = none
+    #[serde(default)]
+    pub github_proxy: String,
+
+    /// How often to look for a new release after a command (e.g. 1d, 12h); `off` = never
+    #[serde(default = "NetworkConfig::default_version_check_interval")]
+    pub version_check_interval: String,
+
+    /// Checksum from the release manifest that `update` verifies: sha256 | sha512
+    #[serde(default = "NetworkConfig::default_checksum_algo")]
+    pub checksum_algo: String,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            github_proxy: String::new(),
+            version_check_interval: Self::default_version_check_interval(),
+            checksum_algo: Self::default_checksum_algo(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    fn default_version_check_interval() -> String {
+        "1d".to_string()
+    }
+
+    fn default_checksum_algo() -> String {
+        "sha256".to_string()
+    }
+}
+
 impl Config {
     fn default_output() -> String {
         "default".to_string()
@@ -228,6 +691,10 @@ impl Config {
         "auto".to_string()
     }
 
+    fn default_output_buffering() -> String {
+        "line".to_string()
+    }
+
     fn default_header_timing() -> String {
         "tail".to_string()
     }
@@ -248,17 +715,80 @@ impl Config {
         "magenta".to_string()
     }
 
+    fn default_what_label() -> String {
+        "WHAT".to_string()
+    }
+
+    fn default_why_label() -> String {
+        "WHY".to_string()
+    }
+
+    fn default_expand_env() -> String {
+        "off".to_string()
+    }
+
+    /// Parse `expand_env`; invalid values turn expansion off.
+    pub fn expand_env_mode(&self) -> ExpandEnv {
+        ExpandEnv::from_str(self.expand_env.as_str()).unwrap_or(ExpandEnv::Off)
+    }
+
+    /// With `expand_env` on, replace `$VAR` and `${VAR}` in every string
+    /// value with the environment variable's value; `$$` is a literal `$`.
+    /// Only the in-memory config changes, so `config get` and the file keep
+    /// what was written. Under `strict`, an unset variable is an error.
+    pub fn expand_env_vars(&mut self, i18n: &I18n) -> Result<()> {
+        let strict = match self.expand_env_mode() {
+            ExpandEnv::Off => return Ok(()),
+            ExpandEnv::On => false,
+            ExpandEnv::Strict => true,
+        };
+        let mut value = toml::Value::try_from(&*self)?;
+        expand_env_in_value(&mut value, "", &|name| env::var(name).ok(), strict, i18n)?;
+        let (plain, agent_mode) = (self.plain, self.agent_mode);
+        *self = value.try_into()?;
+        self.plain = plain;
+        self.agent_mode = agent_mode;
+        Ok(())
+    }
+
     /// Check if WHAT/WHY header should be printed
     pub fn should_print_header(&self) -> bool {
         self.output != "quiet"
     }
 
+    /// Strip all shnote decoration for `--plain`: no header, no color, no
+    /// output prefix, ASCII-only status markers.
+    pub fn apply_plain(&mut self) {
+        self.output = "quiet".to_string();
+        self.color = false;
+        self.output_prefix.clear();
+        self.plain = true;
+    }
+
+    /// Overrides for `--agent-mode`: everything `--plain` strips, since the
+    /// WHAT/WHY header is emitted as JSON on stderr instead.
+    pub fn apply_agent_mode(&mut self) {
+        self.apply_plain();
+        self.agent_mode = true;
+    }
+
+    /// Render the output line prefix, expanding `{what}` with the WHAT note.
+    pub fn render_output_prefix(&self, what: &str) -> String {
+        self.output_prefix.replace("{what}", what)
+    }
+
     /// Parse header stream routing mode.
     /// Falls back to Auto for invalid or unknown values.
     pub fn header_stream_mode(&self) -> HeaderStreamMode {
         HeaderStreamMode::from_str(self.header_stream.as_str()).unwrap_or(HeaderStreamMode::Auto)
     }
 
+    /// Parse output buffering mode.
+    /// Falls back to Line for invalid or unknown values.
+    pub fn output_buffering_mode(&self) -> OutputBuffering {
+        OutputBuffering::from_str(self.output_buffering.as_str()).unwrap_or(OutputBuffering::Line)
+    }
+
     /// Parse header print timing mode.
     /// Falls back to Tail for invalid or unknown values.
     pub fn header_timing_mode(&self) -> HeaderTiming {
@@ -277,6 +807,18 @@ impl Config {
         self.color
     }
 
+    /// Whether downloads show progress: enabled in config, not `--plain`,
+    /// and stderr (where the progress bar goes) is a terminal.
+    pub fn show_download_progress(&self, stderr_is_terminal: bool) -> bool {
+        self.setup.download_progress && !self.plain && stderr_is_terminal
+    }
+
+    /// Whether the exit summary is printed: enabled in config, not `--plain`,
+    /// and stderr (where the summary goes) is a terminal.
+    pub fn show_exit_summary(&self, stderr_is_terminal: bool) -> bool {
+        self.output_summary && !self.plain && stderr_is_terminal
+    }
+
     pub fn what_color_escape(&self) -> Option<&'static str> {
         color_escape(self.what_color.as_str(), "36")
     }
@@ -285,15 +827,69 @@ impl Config {
         color_escape(self.why_color.as_str(), "35")
     }
 
+    /// The WHAT and WHY header labels, uncolored. `auto` picks the label for
+    /// the display language; an empty value falls back to the default.
+    pub fn header_labels(&self, i18n: &I18n) -> (String, String) {
+        let resolve = |value: &str, localized: &str, default: String| match value {
+            "auto" => localized.to_string(),
+            "" => default,
+            other => other.to_string(),
+        };
+        (
+            resolve(
+                &self.what_label,
+                i18n.header_what_label(),
+                Self::default_what_label(),
+            ),
+            resolve(
+                &self.why_label,
+                i18n.header_why_label(),
+                Self::default_why_label(),
+            ),
+        )
+    }
+
+    /// The WHAT and WHY labels as printed, colon and padding included, so both
+    /// notes start in the same column (`WHAT: ` / `WHY:  ` by default).
+    pub fn header_prefixes(&self, i18n: &I18n) -> (String, String) {
+        let (what, why) = self.header_labels(i18n);
+        let width = what.chars().count().max(why.chars().count());
+        let use_color = self.should_color_header();
+        let render = |label: &str, escape: Option<&str>| {
+            let padding = " ".repeat(width - label.chars().count() + 1);
+            match escape.filter(|_| use_color) {
+                Some(code) => format!("\x1b[{code}m{label}\x1b[0m:{padding}"),
+                None => format!("{label}:{padding}"),
+            }
+        };
+        (
+            render(&what, self.what_color_escape()),
+            render(&why, self.why_color_escape()),
+        )
+    }
+
     pub fn load() -> Result<Self> {
+        Self::load_with_sources().map(|(config, _)| config)
+    }
+
+    /// Load the config and record which keys the user file sets, so
+    /// `config get --show-source` can tell them apart from built-in defaults.
+    pub fn load_with_sources() -> Result<(Self, ConfigSources)> {
         let path = config_path()?;
         if !path.exists() {
-            return Ok(Config::default());
+            return Ok((Config::default(), ConfigSources::default()));
         }
         let contents = fs::read_to_string(&path)
             .context(format!("failed to read config file: {}", path.display()))?;
-        toml::from_str(&contents)
-            .context(format!("failed to parse config file: {}", path.display()))
+        let parse_context = || format!("failed to parse config file: {}", path.display());
+        let config = toml::from_str(&contents).with_context(parse_context)?;
+        let table = toml::from_str(&contents).with_context(parse_context)?;
+        Ok((
+            config,
+            ConfigSources {
+                user: Some((path, table)),
+            },
+        ))
     }
 
     pub fn save(&self, i18n: &I18n) -> Result<()> {
@@ -312,14 +908,40 @@ impl Config {
             "python" => Some(self.paths.python.clone()),
             "node" => Some(self.paths.node.clone()),
             "shell" => Some(self.paths.shell.clone()),
+            "python_min_version" => Some(self.paths.python_min_version.clone()),
+            "node_min_version" => Some(self.paths.node_min_version.clone()),
             "language" => Some(self.i18n.language.clone()),
+            "history" => Some(self.history.enabled.to_string()),
+            "history_sink" => Some(self.history.sink.clone()),
+            "history_http_url" => Some(self.history.http_url.clone()),
+            "history_http_auth_header" => Some(self.history.http_auth_header.clone()),
+            "history_max_size_mb" => Some(self.history.max_size_mb.to_string()),
+            "history_capture_bytes" => Some(self.history.capture_bytes.to_string()),
+            "history_redact" => Some(self.history.redact.to_string()),
+            "setup_confirm_download" => Some(self.setup.confirm_download.to_string()),
+            "download_progress" => Some(self.setup.download_progress.to_string()),
+            "exec_prefix" => Some(self.run.exec_prefix.join(" ")),
+            "run_strict" => Some(self.run.strict.to_string()),
+            "announce_to" => Some(self.run.announce_to.clone()),
+            "run_confirm_each" => Some(self.run.confirm_each.to_string()),
+            "run_warn_shell_injection" => Some(self.run.warn_shell_injection.to_string()),
+            "run_log_dir" => Some(self.run.log_dir.clone()),
+            "github_proxy" => Some(self.network.github_proxy.clone()),
+            "version_check_interval" => Some(self.network.version_check_interval.clone()),
+            "checksum_algo" => Some(self.network.checksum_algo.clone()),
             "output" => Some(self.output.clone()),
+            "output_prefix" => Some(self.output_prefix.clone()),
+            "output_buffering" => Some(self.output_buffering.clone()),
+            "output_summary" => Some(self.output_summary.to_string()),
             "header_stream" => Some(self.header_stream.clone()),
             "header_timing" => Some(self.header_timing.clone()),
             "run_string_shell_mode" => Some(self.run_string_shell_mode.clone()),
             "color" => Some(self.color.to_string()),
             "what_color" => Some(self.what_color.clone()),
             "why_color" => Some(self.why_color.clone()),
+            "what_label" => Some(self.what_label.clone()),
+            "why_label" => Some(self.why_label.clone()),
+            "expand_env" => Some(self.expand_env.clone()),
             _ => None,
         }
     }
@@ -342,8 +964,19 @@ impl Config {
                 self.paths.shell = value.to_string();
                 Ok(true)
             }
+            "python_min_version" | "node_min_version" => {
+                if !value.is_empty() && parse_semver_from_text(value).is_none() {
+                    anyhow::bail!("{}", i18n.err_invalid_min_version_value(value));
+                }
+                if key == "python_min_version" {
+                    self.paths.python_min_version = value.to_string();
+                } else {
+                    self.paths.node_min_version = value.to_string();
+                }
+                Ok(true)
+            }
             "language" => {
-                let valid = ["auto", "zh", "en"];
+                let valid = ["auto", "zh", "en", "ja"];
                 if !valid.contains(&value) {
                     anyhow::bail!(
                         "{}",
@@ -353,6 +986,107 @@ impl Config {
                 self.i18n.language = value.to_string();
                 Ok(true)
             }
+            "history" => {
+                self.history.enabled = parse_bool(value).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}",
+                        i18n.err_invalid_history_value(value, &BOOL_VALUES.join(", "))
+                    )
+                })?;
+                Ok(true)
+            }
+            "history_sink" => {
+                let valid = ["file", "http"];
+                if !valid.contains(&value) {
+                    anyhow::bail!(
+                        "{}",
+                        i18n.err_invalid_history_sink_value(value, &valid.join(", "))
+                    );
+                }
+                self.history.sink = value.to_string();
+                Ok(true)
+            }
+            "history_http_url" => {
+                self.history.http_url = value.to_string();
+                Ok(true)
+            }
+            "history_http_auth_header" => {
+                self.history.http_auth_header = value.to_string();
+                Ok(true)
+            }
+            "history_max_size_mb" => {
+                self.history.max_size_mb = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("{}", i18n.err_invalid_history_max_size(value)))?;
+                Ok(true)
+            }
+            "history_capture_bytes" => {
+                self.history.capture_bytes = value.trim().parse().map_err(|_| {
+                    anyhow::anyhow!("{}", i18n.err_invalid_history_capture_bytes(value))
+                })?;
+                Ok(true)
+            }
+            "setup_confirm_download" => {
+                self.setup.confirm_download = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
+            "download_progress" => {
+                self.setup.download_progress = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
+            "exec_prefix" => {
+                self.run.exec_prefix = value.split_whitespace().map(str::to_string).collect();
+                Ok(true)
+            }
+            "run_strict" => {
+                self.run.strict = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
+            "announce_to" => {
+                self.run.announce_to = value.trim().to_string();
+                Ok(true)
+            }
+            "run_confirm_each" => {
+                self.run.confirm_each = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
+            "history_redact" => {
+                self.history.redact = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
+            "run_warn_shell_injection" => {
+                self.run.warn_shell_injection = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
+            "run_log_dir" => {
+                self.run.log_dir = value.trim().to_string();
+                Ok(true)
+            }
+            "github_proxy" => {
+                self.network.github_proxy = value.trim().to_string();
+                Ok(true)
+            }
+            "version_check_interval" => {
+                let value = value.trim().to_lowercase();
+                if update_notice::parse_interval(&value).is_none() {
+                    anyhow::bail!("{}", i18n.err_invalid_check_interval(&value));
+                }
+                self.network.version_check_interval = value;
+                Ok(true)
+            }
+            "checksum_algo" => {
+                let normalized = value.trim().to_lowercase();
+                let valid = ["sha256", "sha512"];
+                if !valid.contains(&normalized.as_str()) {
+                    anyhow::bail!(
+                        "{}",
+                        i18n.err_invalid_checksum_algo_value(value, &valid.join(", "))
+                    );
+                }
+                self.network.checksum_algo = normalized;
+                Ok(true)
+            }
             "output" => {
                 let valid = ["default", "quiet"];
                 if !valid.contains(&value) {
@@ -364,6 +1098,26 @@ impl Config {
                 self.output = value.to_string();
                 Ok(true)
             }
+            "output_prefix" => {
+                self.output_prefix = value.to_string();
+                Ok(true)
+            }
+            "output_buffering" => {
+                let normalized = value.to_lowercase();
+                let valid = ["line", "block", "none"];
+                if !valid.contains(&normalized.as_str()) {
+                    anyhow::bail!(
+                        "{}",
+                        i18n.err_invalid_output_buffering_value(value, &valid.join(", "))
+                    );
+                }
+                self.output_buffering = normalized;
+                Ok(true)
+            }
+            "output_summary" => {
+                self.output_summary = parse_bool_key(i18n, key, value)?;
+                Ok(true)
+            }
             "header_stream" => {
                 let normalized = value.to_lowercase();
                 let valid = ["auto", "stdout", "stderr"];
@@ -401,16 +1155,12 @@ impl Config {
                 Ok(true)
             }
             "color" => {
-                let normalized = value.to_lowercase();
-                let parsed = match normalized.as_str() {
-                    "true" => true,
-                    "false" => false,
-                    _ => {
-                        let valid = ["true", "false"];
-                        anyhow::bail!("{}", i18n.err_invalid_color_value(value, &valid.join(", ")));
-                    }
-                };
-                self.color = parsed;
+                self.color = parse_bool(value).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}",
+                        i18n.err_invalid_color_value(value, &BOOL_VALUES.join(", "))
+                    )
+                })?;
                 Ok(true)
             }
             "what_color" => {
@@ -435,6 +1185,26 @@ impl Config {
                 self.why_color = normalized;
                 Ok(true)
             }
+            "what_label" => {
+                self.what_label = value.trim().to_string();
+                Ok(true)
+            }
+            "why_label" => {
+                self.why_label = value.trim().to_string();
+                Ok(true)
+            }
+            "expand_env" => {
+                let normalized = value.to_lowercase();
+                let valid = ["off", "on", "strict"];
+                if !valid.contains(&normalized.as_str()) {
+                    anyhow::bail!(
+                        "{}",
+                        i18n.err_invalid_expand_env_value(value, &valid.join(", "))
+                    );
+                }
+                self.expand_env = normalized;
+                Ok(true)
+            }
             _ => Ok(false),
         }
     }
@@ -444,20 +1214,105 @@ impl Config {
             ("python".to_string(), self.paths.python.clone()),
             ("node".to_string(), self.paths.node.clone()),
             ("shell".to_string(), self.paths.shell.clone()),
+            (
+                "python_min_version".to_string(),
+                self.paths.python_min_version.clone(),
+            ),
+            (
+                "node_min_version".to_string(),
+                self.paths.node_min_version.clone(),
+            ),
             ("language".to_string(), self.i18n.language.clone()),
-            ("output".to_string(), self.output.clone()),
-            ("header_stream".to_string(), self.header_stream.clone()),
-            ("header_timing".to_string(), self.header_timing.clone()),
+            ("history".to_string(), self.history.enabled.to_string()),
+            ("history_sink".to_string(), self.history.sink.clone()),
             (
-                "run_string_shell_mode".to_string(),
-                self.run_string_shell_mode.clone(),
+                "history_http_url".to_string(),
+                self.history.http_url.clone(),
             ),
-            ("color".to_string(), self.color.to_string()),
-            ("what_color".to_string(), self.what_color.clone()),
+            (
+                "history_http_auth_header".to_string(),
+                self.history.http_auth_header.clone(),
+            ),
+            (
+                "history_max_size_mb".to_string(),
+                self.history.max_size_mb.to_string(),
+            ),
+            (
+                "history_capture_bytes".to_string(),
+                self.history.capture_bytes.to_string(),
+            ),
+            (
+                "history_redact".to_string(),
+                self.history.redact.to_string(),
+            ),
+            (
+                "setup_confirm_download".to_string(),
+                self.setup.confirm_download.to_string(),
+            ),
+            (
+                "download_progress".to_string(),
+                self.setup.download_progress.to_string(),
+            ),
+            ("exec_prefix".to_string(), self.run.exec_prefix.join(" ")),
+            ("run_strict".to_string(), self.run.strict.to_string()),
+            ("announce_to".to_string(), self.run.announce_to.clone()),
+            (
+                "run_confirm_each".to_string(),
+                self.run.confirm_each.to_string(),
+            ),
+            (
+                "run_warn_shell_injection".to_string(),
+                self.run.warn_shell_injection.to_string(),
+            ),
+            ("run_log_dir".to_string(), self.run.log_dir.clone()),
+            (
+                "github_proxy".to_string(),
+                self.network.github_proxy.clone(),
+            ),
+            (
+                "version_check_interval".to_string(),
+                self.network.version_check_interval.clone(),
+            ),
+            (
+                "checksum_algo".to_string(),
+                self.network.checksum_algo.clone(),
+            ),
+            ("output".to_string(), self.output.clone()),
+            ("output_prefix".to_string(), self.output_prefix.clone()),
+            (
+                "output_buffering".to_string(),
+                self.output_buffering.clone(),
+            ),
+            (
+                "output_summary".to_string(),
+                self.output_summary.to_string(),
+            ),
+            ("header_stream".to_string(), self.header_stream.clone()),
+            ("header_timing".to_string(), self.header_timing.clone()),
+            (
+                "run_string_shell_mode".to_string(),
+                self.run_string_shell_mode.clone(),
+            ),
+            ("color".to_string(), self.color.to_string()),
+            ("what_color".to_string(), self.what_color.clone()),
             ("why_color".to_string(), self.why_color.clone()),
+            ("what_label".to_string(), self.what_label.clone()),
+            ("why_label".to_string(), self.why_label.clone()),
+            ("expand_env".to_string(), self.expand_env.clone()),
         ]
     }
 
+    /// Keys whose value differs from the default, as `(key, value, default)`
+    /// in [`Config::list`] order.
+    pub fn diff_from_default(&self) -> Vec<(String, String, String)> {
+        self.list()
+            .into_iter()
+            .zip(Config::default().list())
+            .filter(|((_, value), (_, default))| value != default)
+            .map(|((key, value), (_, default))| (key, value, default))
+            .collect()
+    }
+
     pub fn reset(i18n: &I18n) -> Result<Self> {
         let config = Config::default();
         config.save(i18n)?;
@@ -465,6 +1320,142 @@ impl Config {
     }
 }
 
+/// Expand every string inside `value`; `path` names it in errors
+/// (`paths.python`, `run.exec_prefix`, ...).
+fn expand_env_in_value(
+    value: &mut toml::Value,
+    path: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    strict: bool,
+    i18n: &I18n,
+) -> Result<()> {
+    match value {
+        toml::Value::String(text) => match expand_env_str(text, lookup) {
+            Ok(expanded) => *text = expanded,
+            Err(name) if strict => {
+                return Err(crate::exit_code::validation(
+                    i18n.err_config_env_var_unset(&name, path),
+                ))
+            }
+            Err(_) => {}
+        },
+        toml::Value::Array(items) => {
+            for item in items {
+                expand_env_in_value(item, path, lookup, strict, i18n)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                expand_env_in_value(item, &path, lookup, strict, i18n)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expand `$NAME`, `${NAME}` and `$$` in `text`. A `$` not followed by a
+/// name or `{NAME}` is kept. `Err` carries the first unset variable; the
+/// caller then leaves the whole value as written.
+fn expand_env_str(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let is_name_char = |c: char| c == '_' || c.is_ascii_alphanumeric();
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, consumed) = if let Some(tail) = after.strip_prefix('$') {
+            out.push('$');
+            rest = tail;
+            continue;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) if end > 0 && braced[..end].chars().all(is_name_char) => {
+                    (&braced[..end], end + 2)
+                }
+                _ => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !is_name_char(c))
+                .unwrap_or(after.len());
+            match after[..end].chars().next() {
+                Some(first) if !first.is_ascii_digit() => (&after[..end], end),
+                _ => ("", 0),
+            }
+        };
+        if name.is_empty() {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        out.push_str(&lookup(name).ok_or_else(|| name.to_string())?);
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Where a configuration value comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    /// The user config file at the given path
+    User(PathBuf),
+}
+
+/// Provenance recorded by [`Config::load_with_sources`].
+#[derive(Debug, Default)]
+pub struct ConfigSources {
+    /// The user config file and its raw contents, if it exists
+    user: Option<(PathBuf, toml::Table)>,
+}
+
+impl ConfigSources {
+    /// Source of the flat config `key` (as accepted by [`Config::get`]).
+    pub fn source(&self, key: &str) -> ConfigSource {
+        match &self.user {
+            Some((path, table)) if table_has_key(table, key) => ConfigSource::User(path.clone()),
+            _ => ConfigSource::Default,
+        }
+    }
+}
+
+/// Whether the raw config file `table` sets the flat config `key`.
+fn table_has_key(table: &toml::Table, key: &str) -> bool {
+    let (section, field) = match key {
+        "python" | "node" | "shell" | "python_min_version" | "node_min_version" => ("paths", key),
+        "language" => ("i18n", key),
+        "history" => ("history", "enabled"),
+        "history_sink" => ("history", "sink"),
+        "history_http_url" => ("history", "http_url"),
+        "history_http_auth_header" => ("history", "http_auth_header"),
+        "history_max_size_mb" => ("history", "max_size_mb"),
+        "history_capture_bytes" => ("history", "capture_bytes"),
+        "history_redact" => ("history", "redact"),
+        "setup_confirm_download" => ("setup", "confirm_download"),
+        "download_progress" => ("setup", key),
+        "exec_prefix" => ("run", key),
+        "run_strict" => ("run", "strict"),
+        "announce_to" => ("run", key),
+        "run_confirm_each" => ("run", "confirm_each"),
+        "run_warn_shell_injection" => ("run", "warn_shell_injection"),
+        "run_log_dir" => ("run", "log_dir"),
+        "github_proxy" | "version_check_interval" | "checksum_algo" => ("network", key),
+        _ => return table.contains_key(key),
+    };
+    table
+        .get(section)
+        .and_then(|value| value.as_table())
+        .is_some_and(|section| section.contains_key(field))
+}
+
 pub fn config_path() -> Result<PathBuf> {
     Ok(shnote_home()?.join("config.toml"))
 }
@@ -474,6 +1465,125 @@ pub fn shnote_home() -> Result<PathBuf> {
     Ok(home.join(".shnote"))
 }
 
+/// Directory for data shnote writes at runtime (history, caches, ...).
+/// Defaults to the shnote home; `SHNOTE_DATA_DIR` relocates it.
+pub fn data_dir() -> Result<PathBuf> {
+    match env::var_os("SHNOTE_DATA_DIR") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => shnote_home(),
+    }
+}
+
+/// How long a `config set`/`reset` waits for another one to finish.
+const CONFIG_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A lock file older than this was left by a crashed process. Config
+/// writes take milliseconds, and this is below the timeout so a waiter
+/// gets past a stale lock.
+const CONFIG_LOCK_STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// Held around a config read-modify-write so concurrent `config set` calls
+/// do not overwrite each other. The lock is `config.toml.lock`, created
+/// exclusively and removed on drop.
+#[derive(Debug)]
+pub struct ConfigLock {
+    path: PathBuf,
+}
+
+impl ConfigLock {
+    pub fn acquire(i18n: &I18n) -> Result<Self> {
+        let home = shnote_home()?;
+        fs::create_dir_all(&home)
+            .context(i18n.err_create_config_dir(&home.display().to_string()))?;
+        Self::acquire_at(
+            i18n,
+            home.join("config.toml.lock"),
+            CONFIG_LOCK_TIMEOUT,
+            CONFIG_LOCK_STALE_AFTER,
+        )
+    }
+
+    fn acquire_at(
+        i18n: &I18n,
+        path: PathBuf,
+        timeout: Duration,
+        stale_after: Duration,
+    ) -> Result<Self> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Self { path }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    return Err(err).context(i18n.err_write_config(&path.display().to_string()))
+                }
+            }
+
+            if is_stale_lock(&path, stale_after) {
+                break_stale_lock(&path, stale_after);
+                continue;
+            }
+            if Instant::now() >= deadline {
+                anyhow::bail!("{}", i18n.err_config_locked(&path.display().to_string()));
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+fn is_stale_lock(path: &Path, stale_after: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= stale_after)
+}
+
+/// Remove a stale lock without racing other waiters: the lock is renamed to
+/// a path only this process uses, so just one waiter gets it. If another
+/// process replaced the stale lock with a fresh one in between, that fresh
+/// lock is what got renamed, and it is put back instead of deleted.
+fn break_stale_lock(path: &Path, stale_after: Duration) {
+    let mut claimed = path.as_os_str().to_owned();
+    claimed.push(format!(".stale.{}", std::process::id()));
+    let claimed = PathBuf::from(claimed);
+    // A failure means another waiter claimed it first
+    if fs::rename(path, &claimed).is_err() {
+        return;
+    }
+    if !is_stale_lock(&claimed, stale_after) {
+        // `hard_link` never replaces a lock taken since then
+        let _ = fs::hard_link(&claimed, path);
+    }
+    let _ = fs::remove_file(&claimed);
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Whether `SHNOTE_CONFIG_READONLY` forbids changing the config file
+/// (`1` or `true`, case-insensitive).
+pub fn config_readonly() -> bool {
+    env::var("SHNOTE_CONFIG_READONLY")
+        .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
+/// The GitHub proxy for downloads: `GITHUB_PROXY` when set, otherwise the
+/// `github_proxy` config value. An empty value in either means no proxy, so
+/// `GITHUB_PROXY=` disables a configured proxy for one call.
+pub fn resolve_proxy(config: &Config) -> Option<String> {
+    let proxy = env::var("GITHUB_PROXY").unwrap_or_else(|_| config.network.github_proxy.clone());
+    let proxy = proxy.trim();
+    (!proxy.is_empty()).then(|| proxy.to_string())
+}
+
 pub fn home_dir() -> Result<PathBuf> {
     let home = env::var("HOME")
         .or_else(|_| env::var("USERPROFILE"))
@@ -513,6 +1623,7 @@ mod tests {
     use crate::i18n::Lang;
     use crate::test_support::{env_lock, EnvVarGuard};
     use std::fs;
+    use tempfile::TempDir;
 
     fn test_i18n() -> I18n {
         I18n::new(Lang::En)
@@ -525,7 +1636,11 @@ mod tests {
         assert_eq!(config.paths.node, "node");
         assert_eq!(config.paths.shell, "auto");
         assert_eq!(config.i18n.language, "auto");
+        assert!(config.history.enabled);
+        assert!(!config.setup.confirm_download);
+        assert!(config.run.exec_prefix.is_empty());
         assert_eq!(config.output, "default");
+        assert_eq!(config.output_prefix, "");
         assert_eq!(config.header_stream, "auto");
         assert_eq!(config.header_timing, "tail");
         assert_eq!(config.run_string_shell_mode, "lc");
@@ -559,6 +1674,9 @@ mod tests {
         config.set(&i18n, "output", "quiet").unwrap();
         assert_eq!(config.get("output"), Some("quiet".to_string()));
 
+        config.set(&i18n, "output_prefix", "[{what}] ").unwrap();
+        assert_eq!(config.get("output_prefix"), Some("[{what}] ".to_string()));
+
         config.set(&i18n, "header_stream", "stderr").unwrap();
         assert_eq!(config.get("header_stream"), Some("stderr".to_string()));
 
@@ -590,6 +1708,292 @@ mod tests {
         assert!(config.set(&i18n, "shell", "invalid").is_err());
     }
 
+    #[test]
+    fn config_set_history_toggle() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+
+        config.set(&i18n, "history", "FALSE").unwrap();
+        assert_eq!(config.get("history"), Some("false".to_string()));
+        assert!(!config.history.enabled);
+        assert!(config.set(&i18n, "history", "maybe").is_err());
+    }
+
+    #[test]
+    fn config_set_history_sink() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.history.sink_mode(), HistorySink::File);
+
+        config.set(&i18n, "history_sink", "http").unwrap();
+        config
+            .set(&i18n, "history_http_url", "https://audit.example/records")
+            .unwrap();
+        assert_eq!(config.history.sink_mode(), HistorySink::Http);
+        assert_eq!(
+            config.get("history_http_url"),
+            Some("https://audit.example/records".to_string())
+        );
+        assert!(config.set(&i18n, "history_sink", "s3").is_err());
+
+        config.history.sink = "bogus".to_string();
+        assert_eq!(config.history.sink_mode(), HistorySink::File);
+    }
+
+    #[test]
+    fn config_set_setup_confirm_download() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+
+        config.set(&i18n, "setup_confirm_download", "true").unwrap();
+        assert!(config.setup.confirm_download);
+        assert_eq!(
+            config.get("setup_confirm_download"),
+            Some("true".to_string())
+        );
+        assert!(config
+            .set(&i18n, "setup_confirm_download", "maybe")
+            .is_err());
+    }
+
+    #[test]
+    fn config_set_download_progress() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert!(config.setup.download_progress);
+        config.set(&i18n, "download_progress", "false").unwrap();
+        assert!(!config.setup.download_progress);
+        assert_eq!(config.get("download_progress"), Some("false".to_string()));
+        assert!(config.set(&i18n, "download_progress", "maybe").is_err());
+    }
+
+    #[test]
+    fn config_set_exec_prefix_splits_words() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+
+        config.set(&i18n, "exec_prefix", "time  -v").unwrap();
+        assert_eq!(config.run.exec_prefix, ["time", "-v"]);
+        assert_eq!(config.get("exec_prefix"), Some("time -v".to_string()));
+
+        config.set(&i18n, "exec_prefix", "").unwrap();
+        assert!(config.run.exec_prefix.is_empty());
+    }
+
+    #[test]
+    fn config_set_run_strict() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert!(!config.run.strict);
+        config.set(&i18n, "run_strict", "true").unwrap();
+        assert!(config.run.strict);
+        assert_eq!(config.get("run_strict"), Some("true".to_string()));
+        assert!(config.set(&i18n, "run_strict", "maybe").is_err());
+    }
+
+    #[test]
+    fn config_set_run_confirm_each() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert!(!config.run.confirm_each);
+        config.set(&i18n, "run_confirm_each", "TRUE").unwrap();
+        assert!(config.run.confirm_each);
+        assert_eq!(config.get("run_confirm_each"), Some("true".to_string()));
+        assert!(config.set(&i18n, "run_confirm_each", "maybe").is_err());
+    }
+
+    #[test]
+    fn config_set_history_max_size_mb() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.history.max_size_mb, 10);
+        config.set(&i18n, "history_max_size_mb", " 50 ").unwrap();
+        assert_eq!(config.get("history_max_size_mb"), Some("50".to_string()));
+        config.set(&i18n, "history_max_size_mb", "0").unwrap();
+        assert_eq!(config.history.max_size_mb, 0);
+        for invalid in ["-1", "1.5", "big"] {
+            assert!(config.set(&i18n, "history_max_size_mb", invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn config_set_history_capture_bytes() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.history.capture_bytes, 8192);
+        config.set(&i18n, "history_capture_bytes", "1024").unwrap();
+        assert_eq!(
+            config.get("history_capture_bytes"),
+            Some("1024".to_string())
+        );
+        assert!(config.set(&i18n, "history_capture_bytes", "8k").is_err());
+    }
+
+    fn fake_env(name: &str) -> Option<String> {
+        match name {
+            "MY_PY" => Some("/opt/py".to_string()),
+            "CI_TOKEN" => Some("s3cr3t".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_env_str_replaces_vars_and_keeps_escapes() {
+        let expand = |text: &str| expand_env_str(text, &fake_env);
+        assert_eq!(expand("$MY_PY/bin/python"), Ok("/opt/py/bin/python".into()));
+        assert_eq!(expand("${MY_PY}3"), Ok("/opt/py3".into()));
+        assert_eq!(expand("cost: $$5 $$MY_PY"), Ok("cost: $5 $MY_PY".into()));
+        assert_eq!(
+            expand("a $ b ${} $1 ${x-y}"),
+            Ok("a $ b ${} $1 ${x-y}".into())
+        );
+        assert_eq!(expand("$CI_TOKEN-$NOPE"), Err("NOPE".into()));
+        assert_eq!(expand("no vars"), Ok("no vars".into()));
+    }
+
+    #[test]
+    fn expand_env_in_value_is_lenient_or_strict() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        config.paths.python = "$MY_PY/bin/python".to_string();
+        config.run.exec_prefix = vec!["env".into(), "T=${CI_TOKEN}".into()];
+        config.why_label = "$NOPE".to_string();
+        let mut value = toml::Value::try_from(&config).unwrap();
+
+        let mut lenient = value.clone();
+        expand_env_in_value(&mut lenient, "", &fake_env, false, &i18n).unwrap();
+        let lenient: Config = lenient.try_into().unwrap();
+        assert_eq!(lenient.paths.python, "/opt/py/bin/python");
+        assert_eq!(lenient.run.exec_prefix, vec!["env", "T=s3cr3t"]);
+        assert_eq!(lenient.why_label, "$NOPE");
+
+        let err = expand_env_in_value(&mut value, "", &fake_env, true, &i18n)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("why_label"), "{err}");
+        assert!(err.contains("$NOPE"), "{err}");
+    }
+
+    #[test]
+    fn config_set_expand_env() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.expand_env_mode(), ExpandEnv::Off);
+        config.set(&i18n, "expand_env", "Strict").unwrap();
+        assert_eq!(config.get("expand_env"), Some("strict".to_string()));
+        assert_eq!(config.expand_env_mode(), ExpandEnv::Strict);
+        assert!(config.set(&i18n, "expand_env", "yes").is_err());
+
+        // Off leaves `$` alone
+        config.expand_env = "off".to_string();
+        config.paths.python = "$HOME/py".to_string();
+        config.expand_env_vars(&i18n).unwrap();
+        assert_eq!(config.paths.python, "$HOME/py");
+    }
+
+    #[test]
+    fn config_set_checksum_algo() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.network.checksum_algo, "sha256");
+        config.set(&i18n, "checksum_algo", "SHA512").unwrap();
+        assert_eq!(config.get("checksum_algo"), Some("sha512".to_string()));
+        assert!(config.set(&i18n, "checksum_algo", "blake3").is_err());
+    }
+
+    #[test]
+    fn config_set_run_log_dir() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.get("run_log_dir"), Some(String::new()));
+        config.set(&i18n, "run_log_dir", " ~/logs/shnote ").unwrap();
+        assert_eq!(config.run.log_dir, "~/logs/shnote");
+    }
+
+    #[test]
+    fn config_set_history_redact() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert!(config.history.redact);
+        config.set(&i18n, "history_redact", "false").unwrap();
+        assert!(!config.history.redact);
+        assert_eq!(config.get("history_redact"), Some("false".to_string()));
+        assert!(config.set(&i18n, "history_redact", "maybe").is_err());
+    }
+
+    #[test]
+    fn config_set_run_warn_shell_injection() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert!(!config.run.warn_shell_injection);
+        config
+            .set(&i18n, "run_warn_shell_injection", "true")
+            .unwrap();
+        assert!(config.run.warn_shell_injection);
+        assert_eq!(
+            config.get("run_warn_shell_injection"),
+            Some("true".to_string())
+        );
+        assert!(config
+            .set(&i18n, "run_warn_shell_injection", "maybe")
+            .is_err());
+    }
+
+    #[test]
+    fn resolve_proxy_prefers_env_over_config() {
+        let _lock = env_lock();
+        let mut config = Config::default();
+        config.network.github_proxy = "https://config.example".to_string();
+
+        let _proxy_guard = EnvVarGuard::remove("GITHUB_PROXY");
+        assert_eq!(
+            resolve_proxy(&config).as_deref(),
+            Some("https://config.example")
+        );
+
+        let _proxy_guard = EnvVarGuard::set("GITHUB_PROXY", "https://env.example");
+        assert_eq!(
+            resolve_proxy(&config).as_deref(),
+            Some("https://env.example")
+        );
+
+        // An empty env var switches the configured proxy off
+        let _proxy_guard = EnvVarGuard::set("GITHUB_PROXY", "");
+        assert_eq!(resolve_proxy(&config), None);
+    }
+
+    #[test]
+    fn resolve_proxy_treats_empty_config_as_no_proxy() {
+        let _lock = env_lock();
+        let _proxy_guard = EnvVarGuard::remove("GITHUB_PROXY");
+        let mut config = Config::default();
+        assert_eq!(resolve_proxy(&config), None);
+
+        let i18n = test_i18n();
+        config
+            .set(&i18n, "github_proxy", " https://ghfast.top ")
+            .unwrap();
+        assert_eq!(
+            config.get("github_proxy"),
+            Some("https://ghfast.top".to_string())
+        );
+        config.set(&i18n, "github_proxy", "").unwrap();
+        assert_eq!(resolve_proxy(&config), None);
+    }
+
+    #[test]
+    fn config_set_validates_min_versions() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+
+        assert!(config.set(&i18n, "python_min_version", "3.10").is_ok());
+        assert_eq!(config.get("python_min_version"), Some("3.10".to_string()));
+        assert!(config.set(&i18n, "node_min_version", "v20.1.0").is_ok());
+        assert_eq!(config.get("node_min_version"), Some("v20.1.0".to_string()));
+        assert!(config.set(&i18n, "node_min_version", "").is_ok());
+        assert!(config.set(&i18n, "python_min_version", "latest").is_err());
+    }
+
     #[test]
     fn config_set_validates_language() {
         let i18n = test_i18n();
@@ -620,6 +2024,22 @@ mod tests {
         assert!(config.set(&i18n, "header_stream", "invalid").is_err());
     }
 
+    #[test]
+    fn config_set_validates_output_buffering() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert_eq!(config.output_buffering_mode(), OutputBuffering::Line);
+
+        config.set(&i18n, "output_buffering", "Block").unwrap();
+        assert_eq!(config.output_buffering_mode(), OutputBuffering::Block);
+        config.set(&i18n, "output_buffering", "none").unwrap();
+        assert_eq!(config.output_buffering_mode(), OutputBuffering::None);
+        assert!(config.set(&i18n, "output_buffering", "full").is_err());
+
+        config.output_buffering = "bogus".to_string();
+        assert_eq!(config.output_buffering_mode(), OutputBuffering::Line);
+    }
+
     #[test]
     fn config_set_validates_header_timing() {
         let i18n = test_i18n();
@@ -653,6 +2073,34 @@ mod tests {
         assert!(config.set(&i18n, "color", "invalid").is_err());
     }
 
+    #[test]
+    fn config_set_accepts_boolean_spellings() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+
+        for (value, expected) in [
+            ("true", "true"),
+            ("FALSE", "false"),
+            ("yes", "true"),
+            ("No", "false"),
+            ("on", "true"),
+            ("OFF", "false"),
+            ("1", "true"),
+            ("0", "false"),
+            (" yes ", "true"),
+        ] {
+            for key in ["color", "history", "run_strict", "output_summary"] {
+                config.set(&i18n, key, value).unwrap();
+                assert_eq!(config.get(key).as_deref(), Some(expected), "{key}={value}");
+            }
+        }
+
+        let err = config.set(&i18n, "run_strict", "maybe").unwrap_err();
+        assert!(err.to_string().contains("true, false"), "{err}");
+        let err = config.set(&i18n, "color", "2").unwrap_err();
+        assert!(err.to_string().contains("true, false"), "{err}");
+    }
+
     #[test]
     fn config_set_validates_label_colors() {
         let i18n = test_i18n();
@@ -692,6 +2140,195 @@ mod tests {
         assert!(!config.should_print_header());
     }
 
+    #[test]
+    fn render_output_prefix_expands_what() {
+        let config = Config {
+            output_prefix: "[{what}] ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.render_output_prefix("build"), "[build] ");
+        assert_eq!(Config::default().render_output_prefix("build"), "");
+    }
+
+    #[test]
+    fn config_lock_waits_then_times_out_while_held() {
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml.lock");
+        let long = Duration::from_secs(60);
+
+        let held = ConfigLock::acquire_at(&i18n, path.clone(), long, long).unwrap();
+        let err = ConfigLock::acquire_at(&i18n, path.clone(), Duration::from_millis(100), long)
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+
+        drop(held);
+        assert!(!path.exists());
+        ConfigLock::acquire_at(&i18n, path, Duration::ZERO, long).unwrap();
+    }
+
+    #[test]
+    fn config_lock_breaks_stale_lock() {
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml.lock");
+        let file = fs::File::create(&path).unwrap();
+        file.set_modified(std::time::SystemTime::now() - Duration::from_secs(600))
+            .unwrap();
+
+        let lock =
+            ConfigLock::acquire_at(&i18n, path.clone(), Duration::ZERO, Duration::from_secs(60))
+                .unwrap();
+        assert!(path.exists());
+        drop(lock);
+    }
+
+    #[test]
+    fn break_stale_lock_keeps_a_fresh_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml.lock");
+        fs::write(&path, "").unwrap();
+
+        // Another waiter replaced the stale lock before this one claimed it
+        break_stale_lock(&path, Duration::from_secs(60));
+        assert!(path.exists());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(std::time::SystemTime::now() - Duration::from_secs(600))
+            .unwrap();
+        break_stale_lock(&path, Duration::from_secs(60));
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn diff_from_default_lists_only_changed_keys() {
+        let i18n = I18n::new(Lang::En);
+        let mut config = Config::default();
+        assert!(config.diff_from_default().is_empty());
+
+        config.set(&i18n, "what_color", "yellow").unwrap();
+        assert_eq!(
+            config.diff_from_default(),
+            [(
+                "what_color".to_string(),
+                "yellow".to_string(),
+                "cyan".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn header_labels_align_the_notes() {
+        let i18n = I18n::new(Lang::En);
+        let mut config = Config {
+            color: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.header_prefixes(&i18n),
+            ("WHAT: ".to_string(), "WHY:  ".to_string())
+        );
+
+        config.what_label = "Do".to_string();
+        config.why_label = "Because".to_string();
+        assert_eq!(
+            config.header_prefixes(&i18n),
+            ("Do:      ".to_string(), "Because: ".to_string())
+        );
+
+        config.color = true;
+        assert_eq!(config.header_prefixes(&i18n).0, "\x1b[36mDo\x1b[0m:      ");
+    }
+
+    #[test]
+    fn header_labels_default_auto_and_custom() {
+        let zh = I18n::new(Lang::Zh);
+        let mut config = Config::default();
+        assert_eq!(
+            config.header_labels(&zh),
+            ("WHAT".to_string(), "WHY".to_string())
+        );
+
+        config.set(&zh, "what_label", "auto").unwrap();
+        config.set(&zh, "why_label", " Reason ").unwrap();
+        assert_eq!(config.get("why_label"), Some("Reason".to_string()));
+        assert_eq!(
+            config.header_labels(&zh),
+            ("做什么".to_string(), "Reason".to_string())
+        );
+        assert_eq!(config.header_labels(&I18n::new(Lang::En)).0, "WHAT");
+
+        config.set(&zh, "why_label", "").unwrap();
+        assert_eq!(config.header_labels(&zh).1, "WHY");
+    }
+
+    #[test]
+    fn apply_plain_disables_decoration() {
+        let mut config = Config {
+            output_prefix: "[x] ".to_string(),
+            ..Default::default()
+        };
+        config.apply_plain();
+        assert!(!config.should_print_header());
+        assert!(!config.should_color_header());
+        assert!(config.output_prefix.is_empty());
+        assert!(config.plain);
+    }
+
+    #[test]
+    fn apply_agent_mode_leaves_stdout_to_the_child() {
+        let mut config = Config {
+            output_prefix: "[x] ".to_string(),
+            header_stream: "stdout".to_string(),
+            ..Default::default()
+        };
+        config.apply_agent_mode();
+        assert!(!config.should_print_header());
+        assert!(!config.should_color_header());
+        assert!(config.output_prefix.is_empty());
+    }
+
+    #[test]
+    fn download_progress_needs_terminal_and_no_plain() {
+        let mut config = Config::default();
+        assert!(config.show_download_progress(true));
+        assert!(!config.show_download_progress(false));
+
+        config.apply_plain();
+        assert!(!config.show_download_progress(true));
+
+        let mut config = Config::default();
+        config.setup.download_progress = false;
+        assert!(!config.show_download_progress(true));
+    }
+
+    #[test]
+    fn exit_summary_is_opt_in_and_needs_terminal() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        assert!(!config.show_exit_summary(true));
+
+        config.set(&i18n, "output_summary", "true").unwrap();
+        assert_eq!(config.get("output_summary"), Some("true".to_string()));
+        assert!(config.show_exit_summary(true));
+        assert!(!config.show_exit_summary(false));
+        assert!(config.set(&i18n, "output_summary", "maybe").is_err());
+
+        config.apply_plain();
+        assert!(!config.show_exit_summary(true));
+    }
+
+    #[test]
+    fn plain_is_not_persisted() {
+        let mut config = Config::default();
+        config.apply_plain();
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        assert!(!toml_str.contains("plain"));
+        let parsed: Config = toml::from_str(&toml_str).unwrap();
+        assert!(!parsed.plain);
+    }
+
     #[test]
     fn header_stream_mode_defaults_to_auto_for_invalid() {
         let config = Config {
@@ -727,14 +2364,77 @@ mod tests {
         assert_eq!(parsed.paths.python, config.paths.python);
     }
 
+    #[test]
+    fn config_sources_prefer_user_file_over_default() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let shnote_dir = temp_dir.path().join(".shnote");
+        fs::create_dir_all(&shnote_dir).unwrap();
+        let path = shnote_dir.join("config.toml");
+        fs::write(
+            &path,
+            "output_prefix = \"> \"\n[paths]\npython = \"py3\"\n[run]\nstrict = true\n",
+        )
+        .unwrap();
+
+        let (config, sources) = Config::load_with_sources().unwrap();
+        assert_eq!(config.paths.python, "py3");
+        for key in ["python", "output_prefix", "run_strict"] {
+            assert_eq!(
+                sources.source(key),
+                ConfigSource::User(path.clone()),
+                "{key}"
+            );
+        }
+        for key in ["node", "output", "exec_prefix", "history"] {
+            assert_eq!(sources.source(key), ConfigSource::Default, "{key}");
+        }
+    }
+
+    #[test]
+    fn config_sources_default_without_user_file() {
+        use tempfile::TempDir;
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+
+        let (config, sources) = Config::load_with_sources().unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(sources.source("python"), ConfigSource::Default);
+    }
+
+    #[test]
+    fn config_keys_match_get_set_and_list() {
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        let listed: Vec<String> = config.list().into_iter().map(|(key, _)| key).collect();
+        let documented: Vec<&str> = CONFIG_KEYS.iter().map(|spec| spec.key).collect();
+        assert_eq!(listed, documented);
+
+        for spec in CONFIG_KEYS {
+            assert!(config.get(spec.key).is_some(), "{}", spec.key);
+            for value in spec.values {
+                assert!(config.set(&i18n, spec.key, value).unwrap(), "{}", spec.key);
+            }
+        }
+    }
+
     #[test]
     fn config_list() {
         let config = Config::default();
         let list = config.list();
-        assert_eq!(list.len(), 11);
+        assert_eq!(list.len(), 37);
+        assert!(list.contains(&("download_progress".to_string(), "true".to_string())));
+        assert!(list.contains(&("history".to_string(), "true".to_string())));
+        assert!(list.contains(&("history_sink".to_string(), "file".to_string())));
         assert!(list.contains(&("python".to_string(), "python3".to_string())));
         assert!(list.contains(&("node".to_string(), "node".to_string())));
         assert!(list.contains(&("output".to_string(), "default".to_string())));
+        assert!(list.contains(&("output_prefix".to_string(), String::new())));
+        assert!(list.contains(&("output_buffering".to_string(), "line".to_string())));
+        assert!(list.contains(&("output_summary".to_string(), "false".to_string())));
+        assert!(list.contains(&("run_strict".to_string(), "false".to_string())));
         assert!(list.contains(&("header_stream".to_string(), "auto".to_string())));
         assert!(list.contains(&("header_timing".to_string(), "tail".to_string())));
         assert!(list.contains(&("run_string_shell_mode".to_string(), "lc".to_string())));
@@ -779,6 +2479,40 @@ mod tests {
         assert_eq!(bin_dir, temp_dir.path().join(".shnote/bin"));
     }
 
+    #[test]
+    fn data_dir_defaults_to_shnote_home() {
+        use tempfile::TempDir;
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let _data_guard = EnvVarGuard::remove("SHNOTE_DATA_DIR");
+
+        assert_eq!(data_dir().unwrap(), temp_dir.path().join(".shnote"));
+    }
+
+    #[test]
+    fn data_dir_honors_env_override() {
+        use tempfile::TempDir;
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", data.path());
+
+        assert_eq!(data_dir().unwrap(), data.path());
+    }
+
+    #[test]
+    fn data_dir_ignores_empty_env_override() {
+        use tempfile::TempDir;
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", "");
+
+        assert_eq!(data_dir().unwrap(), temp_dir.path().join(".shnote"));
+    }
+
     #[test]
     fn pueue_binary_names_are_platform_specific() {
         #[cfg(windows)]
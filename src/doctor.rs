@@ -1,19 +1,33 @@
-use std::path::PathBuf;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
 use which::which;
 
-use crate::config::Config;
-use crate::i18n::I18n;
+use crate::cli::AgentTool;
+use crate::config::{resolve_proxy, Config};
+use crate::history::{history_path, HistoryRecord};
+use crate::i18n::{detect_lang_with_source, status_marker, I18n, Lang, Marker};
+use crate::info::{get_install_path, VERSION};
+use crate::init::{detect_cli_tool, target_tool, ToolProbe};
 use crate::pueue::{find_pueue, find_pueued};
+use crate::semver::parse_semver_from_text;
 use crate::shell::{detect_shell, get_shell_version};
+use crate::update::{fetch_latest_version, find_rules_files, RulesFile};
 
+#[derive(Serialize)]
 pub struct CheckResult {
     pub name: String,
     pub ok: bool,
     pub path: Option<PathBuf>,
     pub version: Option<String>,
     pub error: Option<String>,
+    /// Optional checks (e.g. the network update check) never fail doctor
+    pub optional: bool,
 }
 
 impl CheckResult {
@@ -24,6 +38,7 @@ impl CheckResult {
             path: Some(path),
             version,
             error: None,
+            optional: false,
         }
     }
 
@@ -34,8 +49,19 @@ impl CheckResult {
             path: None,
             version: None,
             error: Some(error.to_string()),
+            optional: false,
         }
     }
+
+    fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Whether this result counts as passing for the overall doctor verdict.
+    pub fn passed(&self) -> bool {
+        self.ok || self.optional
+    }
 }
 
 pub fn run_doctor(i18n: &I18n, config: &Config) -> Vec<CheckResult> {
@@ -48,7 +74,7 @@ pub fn run_doctor(i18n: &I18n, config: &Config) -> Vec<CheckResult> {
     ]
 }
 
-pub fn print_doctor_results(i18n: &I18n, results: &[CheckResult]) {
+pub fn print_doctor_results(i18n: &I18n, results: &[CheckResult], plain: bool) {
     let mut all_ok = true;
 
     for result in results {
@@ -63,11 +89,22 @@ pub fn print_doctor_results(i18n: &I18n, results: &[CheckResult]) {
                 .as_ref()
                 .map(|v| format!(" ({})", v))
                 .unwrap_or_default();
-            println!("✓ {}: {}{}", result.name, path_str, version_str);
+            println!(
+                "{} {}: {}{}",
+                status_marker(Marker::Ok, plain),
+                result.name,
+                path_str,
+                version_str
+            );
         } else {
-            all_ok = false;
+            let marker = if result.optional {
+                status_marker(Marker::Warn, plain)
+            } else {
+                all_ok = false;
+                status_marker(Marker::Fail, plain)
+            };
             let error_str = result.error.as_deref().unwrap_or("unknown error");
-            println!("✗ {}: {}", result.name, error_str);
+            println!("{} {}: {}", marker, result.name, error_str);
         }
     }
 
@@ -79,6 +116,209 @@ pub fn print_doctor_results(i18n: &I18n, results: &[CheckResult]) {
     }
 }
 
+/// Version of the `doctor --json` layout; bump it when fields are renamed,
+/// removed or change meaning so consumers can tell the formats apart.
+const DOCTOR_JSON_SCHEMA_VERSION: u32 = 1;
+
+/// `--json`: the overall verdict and every check as one JSON object.
+pub fn print_doctor_json(results: &[CheckResult]) {
+    println!("{}", doctor_json(results));
+}
+
+fn doctor_json(results: &[CheckResult]) -> serde_json::Value {
+    json!({
+        "schema_version": DOCTOR_JSON_SCHEMA_VERSION,
+        "ok": results.iter().all(CheckResult::passed),
+        "checks": results,
+    })
+}
+
+/// Lines describing each language-detection input and which one won.
+pub fn locale_report(i18n: &I18n, cli_lang: Option<&str>, config: &Config) -> Vec<String> {
+    let unset = i18n.doctor_locale_unset();
+    let mut lines = vec![i18n.doctor_locale_title().to_string()];
+    lines.push(format!("  --lang: {}", cli_lang.unwrap_or(unset)));
+    lines.push(format!("  config language: {}", config.i18n.language));
+    for key in ["SHNOTE_LANG", "LC_ALL", "LC_MESSAGES", "LANGUAGE", "LANG"] {
+        let value = std::env::var(key).unwrap_or_else(|_| unset.to_string());
+        lines.push(format!("  {key}: {value}"));
+    }
+
+    let (lang, source) = detect_lang_with_source(cli_lang, &config.i18n.language);
+    let tag = match lang {
+        Lang::En => "en",
+        Lang::Zh => "zh",
+        Lang::Ja => "ja",
+    };
+    lines.push(i18n.doctor_locale_result(tag, &source.label()));
+    lines
+}
+
+pub fn print_locale_report(i18n: &I18n, cli_lang: Option<&str>, config: &Config) {
+    for line in locale_report(i18n, cli_lang, config) {
+        println!("{line}");
+    }
+}
+
+/// Optional, network-backed check comparing this build with the latest release.
+pub fn check_updates(i18n: &I18n, config: &Config, offline: bool) -> CheckResult {
+    if offline {
+        return CheckResult::failure("shnote", i18n.doctor_update_skipped_offline()).optional();
+    }
+    let github_proxy = resolve_proxy(config);
+    update_check_result(
+        i18n,
+        VERSION,
+        fetch_latest_version(i18n, github_proxy.as_deref()),
+    )
+}
+
+fn update_check_result(i18n: &I18n, current: &str, latest: Result<String>) -> CheckResult {
+    let latest = match latest {
+        Ok(latest) => latest,
+        Err(e) => {
+            return CheckResult::failure("shnote", &i18n.doctor_update_check_failed(&e.to_string()))
+                .optional()
+        }
+    };
+    let path = get_install_path().unwrap_or_else(|| PathBuf::from("shnote"));
+    let newer = match (
+        parse_semver_from_text(current),
+        parse_semver_from_text(&latest),
+    ) {
+        (Some(current), Some(latest)) => latest > current,
+        _ => current != latest,
+    };
+    let detail = if newer {
+        i18n.doctor_update_available(current, &latest)
+    } else {
+        i18n.doctor_update_up_to_date(current)
+    };
+    CheckResult::success("shnote", path, Some(detail))
+}
+
+/// Optional checks for an AI tool: whether its CLI is in PATH (and its
+/// version), and whether the user-scope shnote rules match this build.
+pub fn check_agent(i18n: &I18n, tool: AgentTool) -> Vec<CheckResult> {
+    let target = tool.target();
+    let name = target_tool(target).expect("agent targets have a CLI tool");
+    let rules: Vec<RulesFile> = find_rules_files()
+        .into_iter()
+        .filter(|file| file.target == target)
+        .collect();
+    agent_check_results(i18n, &detect_cli_tool(name), &rules)
+}
+
+fn agent_check_results(i18n: &I18n, probe: &ToolProbe, rules: &[RulesFile]) -> Vec<CheckResult> {
+    let tool = probe.tool.as_str();
+    let mut results = vec![match &probe.path {
+        Some(path) => CheckResult::success(tool, path.clone(), probe.version.clone()),
+        None => CheckResult::failure(tool, i18n.doctor_tool_not_found()),
+    }];
+
+    let rules_name = format!("{tool} rules");
+    if rules.is_empty() {
+        results.push(CheckResult::failure(
+            &rules_name,
+            &i18n.doctor_rules_missing(tool),
+        ));
+    }
+    for file in rules {
+        results.push(if file.matches_template(i18n) {
+            CheckResult::success(
+                &rules_name,
+                file.path.clone(),
+                Some(i18n.doctor_rules_current().to_string()),
+            )
+        } else {
+            CheckResult::failure(
+                &rules_name,
+                &i18n.doctor_rules_differ(&file.path.display().to_string(), tool),
+            )
+        });
+    }
+    results.into_iter().map(CheckResult::optional).collect()
+}
+
+/// `--check-rules-drift`: one check per installed user-scope rules file,
+/// failing when it differs from the rules this build writes.
+pub fn check_rules_drift(i18n: &I18n) -> Vec<CheckResult> {
+    rules_drift_results(i18n, &find_rules_files())
+}
+
+fn rules_drift_results(i18n: &I18n, files: &[RulesFile]) -> Vec<CheckResult> {
+    files
+        .iter()
+        .map(|file| {
+            let target = file.target.name();
+            let name = format!("{target} rules");
+            if file.matches_template(i18n) {
+                CheckResult::success(
+                    &name,
+                    file.path.clone(),
+                    Some(i18n.doctor_rules_current().to_string()),
+                )
+            } else {
+                let path = file.path.display().to_string();
+                let lines = file.drift(i18n).max(1);
+                CheckResult::failure(&name, &i18n.doctor_rules_drifted(&path, lines, target))
+            }
+        })
+        .collect()
+}
+
+/// Check the history log; only counts against doctor when history is enabled.
+pub fn check_history(i18n: &I18n, config: &Config) -> CheckResult {
+    let result = match history_path() {
+        Ok(path) => history_check_result(i18n, &path),
+        Err(e) => CheckResult::failure("history", &e.to_string()),
+    };
+    if config.history.enabled {
+        result
+    } else {
+        result.optional()
+    }
+}
+
+/// More than one malformed line in this many fails the check.
+const HISTORY_MALFORMED_RATIO: usize = 10;
+
+fn history_check_result(i18n: &I18n, path: &Path) -> CheckResult {
+    if let Err(e) = probe_history_writable(path) {
+        let detail = i18n.doctor_history_not_writable(&path.display().to_string(), &e.to_string());
+        return CheckResult::failure("history", &detail);
+    }
+
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+    let bad = lines
+        .iter()
+        .filter(|line| serde_json::from_str::<HistoryRecord>(line).is_err())
+        .count();
+    if bad * HISTORY_MALFORMED_RATIO > lines.len() {
+        return CheckResult::failure("history", &i18n.doctor_history_malformed(bad, lines.len()));
+    }
+
+    let bytes = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let summary = i18n.doctor_history_summary(lines.len() - bad, bytes);
+    CheckResult::success("history", path.to_path_buf(), Some(summary))
+}
+
+/// Check that new records could be appended without touching existing ones:
+/// open the file for append if it exists, otherwise create a scratch file in
+/// the nearest existing ancestor directory.
+fn probe_history_writable(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return OpenOptions::new().append(true).open(path).map(|_| ());
+    }
+    let dir = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.is_dir())
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    tempfile::NamedTempFile::new_in(dir).map(|_| ())
+}
+
 fn check_python(i18n: &I18n, config: &Config) -> CheckResult {
     let python_cmd = &config.paths.python;
 
@@ -185,8 +425,10 @@ fn get_interpreter_version(path: &PathBuf, flag: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::InitTarget;
     use crate::config::{pueue_binary_name, pueued_binary_name, shnote_bin_dir};
     use crate::i18n::Lang;
+    use crate::init::{rules_for_target_with_pueue, SHNOTE_MARKER_END, SHNOTE_MARKER_START};
     use crate::test_support::{env_lock, EnvVarGuard};
     use tempfile::TempDir;
 
@@ -211,6 +453,144 @@ mod tests {
         assert!(result.error.is_none());
     }
 
+    #[test]
+    fn history_check_reports_record_count() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.jsonl");
+        let line = r#"{"ts":1,"command":"run","argv":["ls"],"what":"w","why":"y","cwd":"/","success":true}"#;
+        fs::write(&path, format!("{line}\n{line}\n")).unwrap();
+
+        let result = history_check_result(&i18n, &path);
+        assert!(result.ok);
+        assert!(result.version.unwrap().starts_with("2 records"));
+    }
+
+    #[test]
+    fn history_check_passes_before_first_record() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data").join("history.jsonl");
+
+        let result = history_check_result(&i18n, &path);
+        assert!(result.ok);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn history_check_fails_on_malformed_lines() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.jsonl");
+        fs::write(&path, "not json\n{}\n").unwrap();
+
+        let result = history_check_result(&i18n, &path);
+        assert!(!result.ok);
+        assert_eq!(result.error.unwrap(), i18n.doctor_history_malformed(2, 2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn history_check_fails_for_read_only_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("data");
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+        if fs::write(dir.join("probe"), "").is_ok() {
+            // Permissions are not enforced (e.g. running as root).
+            return;
+        }
+
+        let result = history_check_result(&i18n, &dir.join("history.jsonl"));
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(!result.ok);
+        assert!(result.error.unwrap().contains("not writable"));
+    }
+
+    #[test]
+    fn agent_checks_report_missing_tool_and_rules_as_optional() {
+        let i18n = test_i18n();
+        let probe = ToolProbe {
+            tool: "codex".to_string(),
+            path: None,
+            version: None,
+        };
+
+        let results = agent_check_results(&i18n, &probe, &[]);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| !r.ok && r.passed()));
+        assert_eq!(results[1].name, "codex rules");
+        assert!(results[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("shnote init codex"));
+    }
+
+    #[test]
+    fn check_rules_drift_fails_only_for_drifted_files() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let home_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", home_dir.path());
+        let _userprofile_guard = EnvVarGuard::remove("USERPROFILE");
+        assert!(check_rules_drift(&i18n).is_empty());
+
+        let write_rules = |dir: &str, file: &str, rules: &str| {
+            let dir = home_dir.path().join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            let content = format!("{SHNOTE_MARKER_START}{rules}{SHNOTE_MARKER_END}");
+            fs::write(dir.join(file), content).unwrap();
+        };
+        let codex = rules_for_target_with_pueue(i18n.lang(), InitTarget::Codex, false);
+        write_rules(".codex", "AGENTS.md", &codex);
+        let results = check_rules_drift(&i18n);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "codex rules");
+        assert!(results[0].ok && !results[0].optional);
+
+        let gemini = rules_for_target_with_pueue(i18n.lang(), InitTarget::Gemini, false);
+        write_rules(".gemini", "GEMINI.md", &gemini.replace("shnote", "shn0te"));
+        let results = check_rules_drift(&i18n);
+        let gemini = results.iter().find(|r| r.name == "gemini rules").unwrap();
+        assert!(!gemini.passed());
+        let error = gemini.error.as_deref().unwrap();
+        assert!(error.contains("shnote init gemini"), "{error}");
+        assert!(!doctor_json(&results)["ok"].as_bool().unwrap());
+    }
+
+    #[test]
+    fn doctor_json_includes_verdict_and_checks() {
+        let results = vec![
+            CheckResult::success("python", PathBuf::from("/usr/bin/python3"), None),
+            CheckResult::failure("pueue", "missing"),
+        ];
+
+        let report = doctor_json(&results);
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["ok"], false);
+        assert_eq!(report["checks"][0]["path"], "/usr/bin/python3");
+        assert_eq!(report["checks"][1]["error"], "missing");
+        assert_eq!(report["checks"][1]["optional"], false);
+    }
+
+    #[test]
+    fn check_history_is_optional_when_history_disabled() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", temp_dir.path());
+        fs::write(temp_dir.path().join("history.jsonl"), "garbage\n").unwrap();
+
+        let mut config = Config::default();
+        assert!(!check_history(&i18n, &config).passed());
+        config.history.enabled = false;
+        assert!(check_history(&i18n, &config).passed());
+    }
+
     #[test]
     fn check_result_failure() {
         let result = CheckResult::failure("test", "not found");
@@ -252,7 +632,61 @@ mod tests {
         ];
 
         // This will print to stdout, we just test it doesn't panic
-        print_doctor_results(&i18n, &results);
+        print_doctor_results(&i18n, &results, false);
+    }
+
+    #[test]
+    fn update_check_reports_newer_release() {
+        let i18n = test_i18n();
+        let result = update_check_result(&i18n, "0.3.2", Ok("0.10.0".to_string()));
+        assert!(result.ok);
+        assert_eq!(
+            result.version,
+            Some(i18n.doctor_update_available("0.3.2", "0.10.0"))
+        );
+
+        let result = update_check_result(&i18n, "0.3.2", Ok("0.3.2".to_string()));
+        assert_eq!(result.version, Some(i18n.doctor_update_up_to_date("0.3.2")));
+    }
+
+    #[test]
+    fn update_check_failure_is_optional() {
+        let i18n = test_i18n();
+        let result = update_check_result(&i18n, "0.3.2", Err(anyhow::anyhow!("offline")));
+        assert!(!result.ok);
+        assert!(result.passed());
+
+        let result = check_updates(&i18n, &Config::default(), true);
+        assert!(!result.ok && result.optional);
+        assert_eq!(
+            result.error.as_deref(),
+            Some(i18n.doctor_update_skipped_offline())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_updates_reads_latest_version_from_manifest() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let curl = temp_dir.path().join("curl");
+        write_executable(
+            &curl,
+            "#!/bin/sh\n\
+            while [ \"$1\" != \"-o\" ]; do shift; done\n\
+            echo '{\"announcement_tag\":\"v99.0.0\",\"artifacts\":[]}' > \"$2\"\n",
+        )
+        .unwrap();
+        let _path_guard = EnvVarGuard::set("PATH", temp_dir.path());
+        let _proxy_guard = EnvVarGuard::remove("GITHUB_PROXY");
+
+        let result = check_updates(&i18n, &Config::default(), false);
+        assert!(result.ok);
+        assert_eq!(
+            result.version,
+            Some(i18n.doctor_update_available(VERSION, "99.0.0"))
+        );
     }
 
     #[test]
@@ -264,7 +698,49 @@ mod tests {
             Some("1.0".to_string()),
         )];
 
-        print_doctor_results(&i18n, &results);
+        print_doctor_results(&i18n, &results, false);
+    }
+
+    #[test]
+    fn locale_report_shows_env_source() {
+        let _lock = env_lock();
+        let _shnote_lang = EnvVarGuard::remove("SHNOTE_LANG");
+        let _lc_all = EnvVarGuard::remove("LC_ALL");
+        let _lc_messages = EnvVarGuard::remove("LC_MESSAGES");
+        let _language = EnvVarGuard::set("LANGUAGE", "zh_CN:en_US");
+        let _lang = EnvVarGuard::set("LANG", "en_US.UTF-8");
+
+        let i18n = test_i18n();
+        let lines = locale_report(&i18n, None, &Config::default());
+        assert!(lines.contains(&"  --lang: (unset)".to_string()));
+        assert!(lines.contains(&"  LANGUAGE: zh_CN:en_US".to_string()));
+        assert_eq!(
+            lines.last().unwrap(),
+            "  result: zh (source: env (LANGUAGE))"
+        );
+    }
+
+    #[test]
+    fn locale_report_prefers_cli_then_config() {
+        let _lock = env_lock();
+        let _shnote_lang = EnvVarGuard::set("SHNOTE_LANG", "en");
+
+        let i18n = test_i18n();
+        let config = Config {
+            i18n: crate::config::I18nConfig {
+                language: "zh".to_string(),
+            },
+            ..Default::default()
+        };
+
+        let lines = locale_report(&i18n, Some("en"), &config);
+        assert_eq!(lines.last().unwrap(), "  result: en (source: --lang)");
+
+        let lines = locale_report(&i18n, None, &config);
+        assert_eq!(
+            lines.last().unwrap(),
+            "  result: zh (source: config (language))"
+        );
     }
 
     #[test]
@@ -0,0 +1,25 @@
+//! Human-readable durations for the exit summary, transcripts and history.
+
+use std::time::Duration;
+
+/// `0.04s` under a minute, `2m05s` from then on.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_elapsed_switches_to_minutes() {
+        assert_eq!(format_elapsed(Duration::from_millis(40)), "0.04s");
+        assert_eq!(format_elapsed(Duration::from_secs(59)), "59.00s");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m05s");
+    }
+}
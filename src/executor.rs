@@ -1,20 +1,42 @@
+use std::collections::BTreeMap;
 use std::ffi::OsString;
-use std::io::{self, Read};
-use std::path::PathBuf;
-use std::process::{Command, ExitCode, Stdio};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
+use base64::prelude::BASE64_STANDARD;
+use base64::write::EncoderWriter;
 use which::which;
 
+use crate::agent;
+use crate::capture::{CaptureReader, Excerpts, OutputCapture};
 use crate::cli::{PassthroughArgs, RunArgs, ScriptArgs};
-use crate::config::{Config, RunStringShellMode};
+use crate::config::{Config, OutputBuffering, RunStringShellMode};
+use crate::exit_code;
+use crate::history::redact_env_value;
 use crate::i18n::I18n;
+use crate::limits::ResourceLimits;
+use crate::measure::{self, Usage};
+use crate::once;
+use crate::prompt::prompt_yes_no_to;
+use crate::pueue;
+use crate::semver::parse_semver_from_text;
 use crate::shell::{detect_shell, ShellType};
+use crate::shell_lint;
+use crate::transcript::{command_line, TeeLines, Transcript};
+use crate::tty::TtyMode;
+use crate::until::{self, ReadyWait};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum ScriptType {
     Py,
     Node,
+    Shell(ShellType),
 }
 
 impl ScriptType {
@@ -22,6 +44,27 @@ impl ScriptType {
         match self {
             Self::Py => "-c",
             Self::Node => "-e",
+            Self::Shell(shell) => shell.code_flag(),
+        }
+    }
+
+    /// Flag placed before a script file, for interpreters that need one.
+    fn file_flag(self) -> Option<&'static str> {
+        match self {
+            Self::Shell(ShellType::Pwsh) => Some("-File"),
+            Self::Shell(ShellType::Cmd) => Some("/C"),
+            _ => None,
+        }
+    }
+
+    /// `$0` for inline POSIX shell code, so script arguments start at `$1`
+    /// as they do for a script file.
+    fn inline_arg0(self) -> Option<&'static str> {
+        match self {
+            Self::Shell(shell @ (ShellType::Sh | ShellType::Bash | ShellType::Zsh)) => {
+                Some(shell.command_name())
+            }
+            _ => None,
         }
     }
 
@@ -30,34 +73,701 @@ impl ScriptType {
     }
 }
 
-/// Execute a command directly (run subcommand) - true passthrough
-pub fn exec_run(i18n: &I18n, config: &Config, args: RunArgs) -> Result<ExitCode> {
+/// How long `run --until` waits for its pattern by default.
+const DEFAULT_UNTIL_TIMEOUT_SECS: u64 = 60;
+
+/// How a wrapped command ended, with what its history record needs.
+#[derive(Debug, Default)]
+pub struct RunOutcome {
+    pub code: u8,
+    /// Time from spawn to exit; `None` when nothing was spawned (a cached
+    /// `run --once`, a `--until` launch). Hooks are not counted.
+    pub run_time: Option<Duration>,
+    /// The captured output, with `--capture` or `run --measure-output-size`
+    pub excerpts: Option<Excerpts>,
+    /// Absolute directory the command ran in when it was not shnote's own
+    /// (`--cwd`, `--chdir-to-git-root`, `--no-cwd-inherit`)
+    pub cwd: Option<PathBuf>,
+}
+
+impl RunOutcome {
+    /// The outcome of a command that spawned nothing.
+    pub fn exited(code: u8) -> Self {
+        Self {
+            code,
+            ..Self::default()
+        }
+    }
+}
+
+/// Per-invocation `run` settings layered on top of the persistent config.
+#[derive(Debug, Default)]
+struct RunOptions {
+    limits: ResourceLimits,
+    /// Working directory for the command (`--cwd`)
+    cwd: Option<PathBuf>,
+    /// Wrapper program and arguments placed before the command (e.g. `time -v`)
+    exec_prefix: Vec<String>,
+    /// Exit code remappings from `--exit-on`, first match wins
+    exit_on: Vec<ExitRemap>,
+    /// Terminal handling from `--tty`/`--no-tty`
+    tty: TtyMode,
+    /// Base64-encode the child's stdout (`--binary-safe`)
+    binary_safe: bool,
+    /// Print the child's environment before spawning it (`--print-env`)
+    print_env: bool,
+    /// Report the child's resource usage afterwards (`--measure`)
+    measure: bool,
+    /// Report the child's output byte counts afterwards (`--measure-output-size`)
+    measure_output_size: bool,
+    /// Copy of the output for history and `--measure-output-size`
+    capture: Option<Arc<OutputCapture>>,
+    /// Return once the child's output shows this, leaving it running (`--until`)
+    until: Option<ReadyWait>,
+    /// Transcript of the run (`--log-file`)
+    transcript: Option<Arc<Transcript>>,
+    /// Files replacing the child's stdout/stderr (`--redirect-stdout`/`--redirect-stderr`)
+    redirects: Redirects,
+    /// Where to report the child's PID (`--print-pid`/`--pid-fd`)
+    pid_report: PidReport,
+    /// WHAT/WHY for the transcript and the confirmation
+    note: Option<(String, String)>,
+    /// Ask before spawning (`--confirm-each` or `run_confirm_each`, unless `--yes`)
+    confirm: bool,
+}
+
+/// `--redirect-stdout`/`--redirect-stderr`: files the child writes to
+/// directly, as with the shell's `>` (or `>>` with `--redirect-append`).
+#[derive(Debug, Default)]
+struct Redirects {
+    stdout: Option<PathBuf>,
+    stderr: Option<PathBuf>,
+    append: bool,
+}
+
+impl Redirects {
+    /// Check that each file's directory exists, so a typo fails before the
+    /// command runs rather than after.
+    fn new(
+        i18n: &I18n,
+        stdout: Option<PathBuf>,
+        stderr: Option<PathBuf>,
+        append: bool,
+    ) -> Result<Self> {
+        for path in [&stdout, &stderr].into_iter().flatten() {
+            let parent = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+            if parent.is_some_and(|dir| !dir.is_dir()) {
+                return Err(exit_code::validation(
+                    i18n.err_redirect_dir_missing(&path.display().to_string()),
+                ));
+            }
+        }
+        Ok(Self {
+            stdout,
+            stderr,
+            append,
+        })
+    }
+
+    /// Open (create, then truncate or append) the files. The same path for
+    /// both streams is opened once, so the two share one file offset.
+    fn open(&self, i18n: &I18n) -> Result<(Option<File>, Option<File>)> {
+        let open = |path: &PathBuf| {
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(self.append)
+                .truncate(!self.append)
+                .open(path)
+                .with_context(|| i18n.err_redirect_open(&path.display().to_string()))
+        };
+        let stdout = self.stdout.as_ref().map(open).transpose()?;
+        let stderr = match (&stdout, &self.stdout, &self.stderr) {
+            (Some(file), Some(out), Some(err)) if out == err => Some(
+                file.try_clone()
+                    .with_context(|| i18n.err_redirect_open(&err.display().to_string()))?,
+            ),
+            _ => self.stderr.as_ref().map(open).transpose()?,
+        };
+        Ok((stdout, stderr))
+    }
+}
+
+/// `--print-pid`/`--pid-fd`: where the child's PID is written right after
+/// it is spawned, for supervisors that track or signal it. The file is the
+/// caller's and is left in place afterwards.
+#[derive(Debug, Default)]
+struct PidReport {
+    file: Option<PathBuf>,
+    fd: Option<i32>,
+    /// Report write failures as JSON (`--agent-mode`)
+    agent_mode: bool,
+}
+
+impl PidReport {
+    /// As with redirects, a missing directory fails before the command runs.
+    fn new(i18n: &I18n, file: Option<PathBuf>, fd: Option<i32>, agent_mode: bool) -> Result<Self> {
+        if let Some(path) = &file {
+            let parent = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+            if parent.is_some_and(|dir| !dir.is_dir()) {
+                return Err(exit_code::validation(
+                    i18n.err_pid_file_dir_missing(&path.display().to_string()),
+                ));
+            }
+        }
+        if cfg!(not(unix)) && fd.is_some() {
+            return Err(exit_code::validation(i18n.err_pid_fd_unsupported()));
+        }
+        Ok(Self {
+            file,
+            fd,
+            agent_mode,
+        })
+    }
+
+    /// Write `pid` followed by a newline. The child is already running, so a
+    /// failure only warns.
+    fn write(&self, i18n: &I18n, pid: u32) {
+        if let Some(path) = &self.file {
+            if let Err(err) = fs::write(path, format!("{pid}\n")) {
+                agent::warn(
+                    self.agent_mode,
+                    &i18n.warn_pid_write_failed(&path.display().to_string(), &err.to_string()),
+                );
+            }
+        }
+        if let Some(fd) = self.fd {
+            if let Err(err) = write_pid_to_fd(fd, pid) {
+                agent::warn(
+                    self.agent_mode,
+                    &i18n.warn_pid_write_failed(&format!("fd {fd}"), &err.to_string()),
+                );
+            }
+        }
+    }
+}
+
+/// Write `pid` to the inherited descriptor `fd`, leaving it open.
+#[cfg(unix)]
+fn write_pid_to_fd(fd: i32, pid: u32) -> io::Result<()> {
+    use std::os::fd::BorrowedFd;
+
+    // SAFETY: fcntl only queries the descriptor, failing with EBADF if it is not open
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the descriptor was just checked to be open, and it is only
+    // borrowed long enough to duplicate it
+    let owned = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+    writeln!(File::from(owned), "{pid}")
+}
+
+#[cfg(not(unix))]
+fn write_pid_to_fd(_fd: i32, _pid: u32) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Where a spawned child's output goes besides shnote's own stdout/stderr.
+#[derive(Debug, Default)]
+struct OutputRouting<'a> {
+    /// Transcript of the run (`--log-file`)
+    transcript: Option<&'a Arc<Transcript>>,
+    /// File taking the place of the child's stdout
+    stdout_file: Option<File>,
+    /// File taking the place of the child's stderr
+    stderr_file: Option<File>,
+    /// Where to report the child's PID once spawned
+    pid_report: Option<&'a PidReport>,
+    /// Keeps the end of the output (`--capture`)
+    capture: Option<&'a Arc<OutputCapture>>,
+}
+
+/// One `--exit-on CODES=>CODE` mapping.
+#[derive(Debug, PartialEq, Eq)]
+struct ExitRemap {
+    from: Vec<i32>,
+    to: u8,
+}
+
+/// Execute a command directly (run subcommand) - true passthrough.
+/// `capture_bytes` keeps that much of each output stream (`--capture`).
+pub fn exec_run(
+    i18n: &I18n,
+    config: &Config,
+    mut args: RunArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let once = match args.label.as_deref() {
+        Some(label) if args.once => Some((label, once::once_path()?)),
+        _ => None,
+    };
+    if let Some((label, path)) = &once {
+        if !args.force {
+            if let Some(code) = once::cached_exit_code(path, label) {
+                agent::info(config.agent_mode, &i18n.run_once_skipped(label));
+                return Ok(RunOutcome::exited(code));
+            }
+        }
+    }
+
+    // Kept alive until the command and its hooks are done; dropping it
+    // removes the directory and whatever the command left in it.
+    let scratch_dir = if args.no_cwd_inherit {
+        Some(
+            tempfile::Builder::new()
+                .prefix("shnote-run-")
+                .tempdir()
+                .context(i18n.err_scratch_dir_create())?,
+        )
+    } else {
+        None
+    };
+    let cwd = if args.chdir_to_git_root {
+        Some(current_git_root(i18n)?)
+    } else if let Some(dir) = &scratch_dir {
+        Some(dir.path().to_path_buf())
+    } else {
+        args.cwd.map(absolute_dir)
+    };
+    if let Some(group) = &args.group {
+        args.command = pueue::apply_group(i18n, std::mem::take(&mut args.command), group)?;
+    }
+    if args.raw {
+        let outcome = exec_run_raw(i18n, config, args.command, cwd.as_deref())?;
+        return Ok(RunOutcome { cwd, ..outcome });
+    }
+    if config.run.warn_shell_injection {
+        let risks = shell_lint::shell_injection_risks(&args.command);
+        if !risks.is_empty() {
+            agent::warn(
+                config.agent_mode,
+                &i18n.warn_shell_injection(&risks.join(", ")),
+            );
+        }
+    }
+
+    let redirects = Redirects::new(
+        i18n,
+        args.redirect_stdout,
+        args.redirect_stderr,
+        args.redirect_append,
+    )?;
+    let pid_report = PidReport::new(i18n, args.print_pid, args.pid_fd, config.agent_mode)?;
+    let options = RunOptions {
+        limits: ResourceLimits {
+            mem_mb: args.mem_limit,
+            cpu_secs: args.cpu_limit,
+        },
+        cwd,
+        exec_prefix: match &args.exec_prefix {
+            Some(prefix) => split_exec_prefix(prefix),
+            None => config.run.exec_prefix.clone(),
+        },
+        exit_on: args
+            .exit_on
+            .iter()
+            .map(|spec| parse_exit_remap(i18n, spec))
+            .collect::<Result<_>>()?,
+        tty: TtyMode::from_flags(args.tty, args.no_tty),
+        binary_safe: args.binary_safe,
+        print_env: args.print_env,
+        measure: args.measure,
+        measure_output_size: args.measure_output_size,
+        capture: (capture_bytes.is_some() || args.measure_output_size)
+            .then(|| OutputCapture::new(capture_bytes.unwrap_or(0))),
+        until: args
+            .until
+            .map(|pattern| -> Result<ReadyWait> {
+                Ok(ReadyWait {
+                    pattern,
+                    timeout: Duration::from_secs(
+                        args.until_timeout.unwrap_or(DEFAULT_UNTIL_TIMEOUT_SECS),
+                    ),
+                    log_dir: until::log_dir(config)?,
+                })
+            })
+            .transpose()?,
+        transcript: args
+            .log_file
+            .as_deref()
+            .map(|path| Transcript::open(i18n, path, args.capture_on_failure_only))
+            .transpose()?,
+        redirects,
+        pid_report,
+        note: args.note,
+        confirm: (args.confirm_each || config.run.confirm_each) && !args.yes,
+    };
+    let outcome = RunOutcome {
+        cwd: options.cwd.clone(),
+        ..exec_run_command(i18n, config, args.command, &options)?
+    };
+    let code = outcome.code;
+
+    if let Some((label, path)) = &once {
+        if code == 0 {
+            if let Err(err) = once::record(path, label, 0) {
+                agent::warn(
+                    config.agent_mode,
+                    &i18n.warn_once_record_failed(&format!("{err:#}")),
+                );
+            }
+        }
+    }
+
+    let hook = if code == 0 {
+        args.on_success
+    } else {
+        args.on_failure
+    };
+    if let Some(hook) = hook {
+        run_hook(i18n, config, &hook);
+    }
+
+    Ok(outcome)
+}
+
+/// `run --raw`: spawn the command with inherited stdio and nothing else
+/// applied, not even the configured exec prefix or output prefix. A single
+/// string still goes through the configured shell, as in a normal `run`.
+fn exec_run_raw(
+    i18n: &I18n,
+    config: &Config,
+    mut command: Vec<OsString>,
+    cwd: Option<&Path>,
+) -> Result<RunOutcome> {
+    let (mut cmd, program) = if command.len() == 1 {
+        let (cmd, shell_path) = shell_command(i18n, config, &command[0].to_string_lossy())?;
+        (cmd, shell_path.display().to_string())
+    } else {
+        let program = command.remove(0);
+        let mut cmd = Command::new(&program);
+        cmd.args(&command);
+        (cmd, program.to_string_lossy().into_owned())
+    };
+    apply_cwd(i18n, &mut cmd, cwd)?;
+    let started = Instant::now();
+    let status = cmd
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|err| exit_code::spawn_failed(i18n.err_failed_to_execute(&program), err))?;
+    Ok(RunOutcome {
+        code: exit_code_from_status(status),
+        run_time: Some(started.elapsed()),
+        excerpts: None,
+        cwd: None,
+    })
+}
+
+/// Output of a command run by [`exec_captured`].
+#[derive(Debug, Default)]
+pub(crate) struct CapturedRun {
+    pub code: u8,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Run a single-string command through the configured shell and exec prefix
+/// with its output captured instead of forwarded, so concurrent commands
+/// (`batch --parallel`) can be shown one after another. Stdin is closed,
+/// since concurrent commands cannot share it.
+pub(crate) fn exec_captured(i18n: &I18n, config: &Config, command: &str) -> Result<CapturedRun> {
+    let (cmd, shell_path) = shell_command(i18n, config, command)?;
+    let (mut cmd, program) = with_exec_prefix(
+        i18n,
+        &config.run.exec_prefix,
+        cmd,
+        &shell_path.display().to_string(),
+    )?;
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| exit_code::spawn_failed(i18n.err_failed_to_execute(&program), err))?;
+    Ok(CapturedRun {
+        code: exit_code_from_status(output.status),
+        stdout: output.stdout,
+        stderr: output.stderr,
+    })
+}
+
+/// Write a captured run's output to stdout and stderr, with the configured
+/// output prefix as for a live command.
+pub(crate) fn forward_captured(config: &Config, run: &CapturedRun) {
+    let prefix = config.output_prefix.as_bytes();
+    let buffering = config.output_buffering_mode();
+    // As with live output, a closed stdout must not fail the batch.
+    let _ = prefix_lines(&run.stdout[..], io::stdout().lock(), prefix, buffering);
+    let _ = prefix_lines(&run.stderr[..], io::stderr().lock(), prefix, buffering);
+}
+
+fn exec_run_command(
+    i18n: &I18n,
+    config: &Config,
+    mut command: Vec<OsString>,
+    options: &RunOptions,
+) -> Result<RunOutcome> {
     // Single-string command goes through configured shell so operators like &&/; work.
-    if args.command.len() == 1 {
-        return exec_run_string_command(i18n, config, &args.command[0]);
+    if command.len() == 1 {
+        return exec_run_string_command(i18n, config, &command[0], options);
     }
 
     // `RunArgs.command` is `required = true` in clap, so it is always non-empty in CLI usage.
-    let mut command = args.command;
     let program = command.remove(0);
     let program_args = command;
 
     let mut cmd = Command::new(&program);
     cmd.args(&program_args);
 
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
+    spawn_run(i18n, config, cmd, &program.to_string_lossy(), options)
+}
 
-    let status = cmd
-        .status()
-        .context(i18n.err_failed_to_execute(&program.to_string_lossy()))?;
+fn exec_run_string_command(
+    i18n: &I18n,
+    config: &Config,
+    command: &OsString,
+    options: &RunOptions,
+) -> Result<RunOutcome> {
+    let command_str = command.to_string_lossy().to_string();
+    let (cmd, shell_path) = shell_command(i18n, config, &command_str)?;
+    spawn_run(
+        i18n,
+        config,
+        cmd,
+        &shell_path.display().to_string(),
+        options,
+    )
+}
+
+/// Spawn a `run` command with its per-invocation options applied, and explain
+/// afterwards if a resource limit killed it.
+fn spawn_run(
+    i18n: &I18n,
+    config: &Config,
+    cmd: Command,
+    program: &str,
+    options: &RunOptions,
+) -> Result<RunOutcome> {
+    let (mut cmd, program) = with_exec_prefix(i18n, &options.exec_prefix, cmd, program)?;
+    apply_cwd(i18n, &mut cmd, options.cwd.as_deref())?;
+    if cfg!(not(unix)) && !options.limits.is_empty() {
+        agent::warn(config.agent_mode, i18n.warn_resource_limits_unsupported());
+    }
+    options.limits.apply(&mut cmd);
+    if options.print_env {
+        for (name, value) in child_env(&cmd) {
+            let line = format!("{name}={}", redact_env_value(&name, &value));
+            agent::info(config.agent_mode, &line);
+        }
+    }
+    if options.confirm {
+        confirm_run(
+            i18n,
+            config,
+            options.note.as_ref(),
+            &cmd,
+            &mut io::stdin().lock(),
+            io::stdin().is_terminal(),
+        )?;
+    }
+    if let Some(wait) = &options.until {
+        return until::spawn_until_ready(i18n, config, &mut cmd, &program, wait)
+            .map(RunOutcome::exited);
+    }
+    if let Some(transcript) = &options.transcript {
+        transcript.start(options.note.as_ref(), &cmd);
+    }
+    let (stdout_file, stderr_file) = options.redirects.open(i18n)?;
+    let routing = OutputRouting {
+        transcript: options.transcript.as_ref(),
+        stdout_file,
+        stderr_file,
+        pid_report: Some(&options.pid_report),
+        capture: options.capture.as_ref(),
+    };
+    // The CPU limit check needs the measured CPU time too.
+    let usage_before = (options.measure || options.limits.cpu_secs.is_some())
+        .then(Usage::of_children)
+        .flatten();
+    let started = Instant::now();
+    let status = spawn_and_wait_status(
+        i18n,
+        config,
+        &mut cmd,
+        &program,
+        options.tty,
+        options.binary_safe,
+        routing,
+    )?;
+    let elapsed = started.elapsed();
+    let cpu_used = measure::cpu_time_since(usage_before);
+    if let Some(explanation) = options.limits.explain(i18n, &status, cpu_used) {
+        agent::warn(config.agent_mode, &explanation);
+    }
+    if options.measure {
+        agent::info(config.agent_mode, &measure::summary(i18n, usage_before));
+    }
+    let excerpts = options.capture.as_ref().map(|capture| capture.excerpts());
+    if let (true, Some(excerpts)) = (options.measure_output_size, &excerpts) {
+        agent::info(
+            config.agent_mode,
+            &i18n.run_output_size(excerpts.stdout_bytes, excerpts.stderr_bytes),
+        );
+    }
+    let code = remap_exit_code(&options.exit_on, status);
+    if let Some(transcript) = &options.transcript {
+        transcript.finish(code, elapsed);
+    }
+    Ok(RunOutcome {
+        code,
+        run_time: Some(elapsed),
+        excerpts,
+        cwd: None,
+    })
+}
 
-    Ok(exit_code_from_status(status))
+/// Show the WHAT/WHY and the command as it will be spawned on stderr, then
+/// ask. Without a terminal there is nobody to ask, so only `--yes` can proceed.
+fn confirm_run(
+    i18n: &I18n,
+    config: &Config,
+    note: Option<&(String, String)>,
+    cmd: &Command,
+    reader: &mut dyn BufRead,
+    interactive: bool,
+) -> Result<()> {
+    if !interactive {
+        return Err(exit_code::validation(i18n.err_run_confirm_requires_tty()));
+    }
+    let mut stderr = io::stderr().lock();
+    if let Some((what, why)) = note {
+        let (what_label, why_label) = config.header_prefixes(i18n);
+        writeln!(stderr, "{what_label}{what}")?;
+        writeln!(stderr, "{why_label}{why}")?;
+    }
+    writeln!(stderr, "$ {}", command_line(cmd))?;
+    if !prompt_yes_no_to(i18n.run_confirm(), reader, &mut stderr)? {
+        anyhow::bail!("{}", i18n.err_run_declined());
+    }
+    Ok(())
 }
 
-fn exec_run_string_command(i18n: &I18n, config: &Config, command: &OsString) -> Result<ExitCode> {
-    let command_str = command.to_string_lossy().to_string();
+/// Parse `CODES=>CODE`, where CODES is a comma-separated list of exit codes.
+fn parse_exit_remap(i18n: &I18n, spec: &str) -> Result<ExitRemap> {
+    let parsed = spec.split_once("=>").and_then(|(from, to)| {
+        let from = from
+            .split(',')
+            .map(|code| code.trim().parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let to = to.trim().parse::<u8>().ok()?;
+        Some(ExitRemap { from, to })
+    });
+    match parsed {
+        Some(remap) => Ok(remap),
+        None => Err(exit_code::validation(i18n.err_invalid_exit_on(spec))),
+    }
+}
+
+/// Apply `--exit-on` mappings; unmapped codes (and signal deaths) pass through.
+fn remap_exit_code(exit_on: &[ExitRemap], status: ExitStatus) -> u8 {
+    let mapped = status.code().and_then(|code| {
+        exit_on
+            .iter()
+            .find(|remap| remap.from.contains(&code))
+            .map(|remap| remap.to)
+    });
+    match mapped {
+        Some(code) => code,
+        None => exit_code_from_status(status),
+    }
+}
+
+/// The environment `cmd` will run with: shnote's own, plus the variables set
+/// or removed on `cmd`, sorted by name.
+fn child_env(cmd: &Command) -> BTreeMap<String, String> {
+    let mut vars: BTreeMap<String, String> = std::env::vars_os()
+        .map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    for (name, value) in cmd.get_envs() {
+        let name = name.to_string_lossy().into_owned();
+        match value {
+            Some(value) => vars.insert(name, value.to_string_lossy().into_owned()),
+            None => vars.remove(&name),
+        };
+    }
+    vars
+}
+
+/// Run `cmd` in `cwd` (from `--cwd`), which must be an existing directory.
+fn apply_cwd(i18n: &I18n, cmd: &mut Command, cwd: Option<&Path>) -> Result<()> {
+    let Some(dir) = cwd else {
+        return Ok(());
+    };
+    if !dir.is_dir() {
+        return Err(exit_code::validation(
+            i18n.err_invalid_cwd(&dir.display().to_string()),
+        ));
+    }
+    cmd.current_dir(dir);
+    Ok(())
+}
+
+/// `dir` resolved against shnote's working directory, as the child sees it.
+fn absolute_dir(dir: PathBuf) -> PathBuf {
+    std::env::current_dir()
+        .map(|here| here.join(&dir))
+        .unwrap_or(dir)
+}
+
+/// The nearest directory at or above `start` containing `.git` (a directory,
+/// or a file in worktrees and submodules).
+pub(crate) fn git_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// The git root above the current directory, for `--chdir-to-git-root`.
+fn current_git_root(i18n: &I18n) -> Result<PathBuf> {
+    let cwd = std::env::current_dir().context("failed to determine current directory")?;
+    git_root(&cwd)
+        .ok_or_else(|| exit_code::validation(i18n.err_not_in_git_repo(&cwd.display().to_string())))
+}
+
+/// Split an `--exec-prefix` value into program and arguments.
+fn split_exec_prefix(prefix: &str) -> Vec<String> {
+    prefix.split_whitespace().map(str::to_string).collect()
+}
+
+/// Rebuild `cmd` as `<prefix...> <program> <args...>`; returns the program
+/// name to use in error messages.
+fn with_exec_prefix(
+    i18n: &I18n,
+    prefix: &[String],
+    cmd: Command,
+    program: &str,
+) -> Result<(Command, String)> {
+    let Some((wrapper, wrapper_args)) = prefix.split_first() else {
+        return Ok((cmd, program.to_string()));
+    };
+    let resolved = which(wrapper)
+        .map_err(|_| exit_code::not_found(i18n.err_exec_prefix_not_found(wrapper)))?;
+
+    let mut wrapped = Command::new(resolved);
+    wrapped.args(wrapper_args);
+    wrapped.arg(cmd.get_program());
+    wrapped.args(cmd.get_args());
+    Ok((wrapped, wrapper.clone()))
+}
+
+/// Build a command that runs `command_str` through the configured shell.
+fn shell_command(i18n: &I18n, config: &Config, command_str: &str) -> Result<(Command, PathBuf)> {
     let (shell_type, shell_path) = detect_shell(i18n, &config.paths.shell)?;
 
     let mut cmd = Command::new(&shell_path);
@@ -67,61 +777,144 @@ fn exec_run_string_command(i18n: &I18n, config: &Config, command: &OsString) ->
                 RunStringShellMode::Lc => "-lc",
                 RunStringShellMode::Ilc => "-ilc",
             };
-            cmd.arg(mode_flag).arg(&command_str);
+            cmd.arg(mode_flag).arg(command_str);
         }
         ShellType::Pwsh => {
             // Keep behavior non-interactive on PowerShell; ilc is Unix-shell specific.
-            cmd.arg("-Command").arg(&command_str);
+            cmd.arg("-Command").arg(command_str);
         }
         ShellType::Cmd => {
-            cmd.arg("/C").arg(&command_str);
+            cmd.arg("/C").arg(command_str);
         }
     }
 
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-
-    let status = cmd
-        .status()
-        .context(i18n.err_failed_to_execute(&shell_path.display().to_string()))?;
+    Ok((cmd, shell_path))
+}
 
-    Ok(exit_code_from_status(status))
+/// Run an `--on-success`/`--on-failure` hook through the shell.
+///
+/// Hooks are best-effort: a failing hook only prints a warning so the wrapped
+/// command's exit code is always what shnote returns.
+fn run_hook(i18n: &I18n, config: &Config, hook: &str) {
+    let result = shell_command(i18n, config, hook).and_then(|(mut cmd, shell_path)| {
+        cmd.stdin(Stdio::inherit());
+        cmd.stdout(Stdio::inherit());
+        cmd.stderr(Stdio::inherit());
+        cmd.status()
+            .context(i18n.err_failed_to_execute(&shell_path.display().to_string()))
+    });
+
+    match result {
+        Ok(status) if status.success() => {}
+        Ok(status) => agent::warn(
+            config.agent_mode,
+            &i18n.warn_hook_failed(hook, &status.to_string()),
+        ),
+        Err(e) => agent::warn(
+            config.agent_mode,
+            &i18n.warn_hook_failed(hook, &format!("{e:#}")),
+        ),
+    }
 }
 
 /// Execute a Python script (py subcommand)
-pub fn exec_py(i18n: &I18n, config: &Config, args: ScriptArgs) -> Result<ExitCode> {
-    let python = resolve_interpreter(i18n, &config.paths.python, &["python3", "python"])?;
-    exec_script(i18n, &python, args, ScriptType::Py)
+pub fn exec_py(
+    i18n: &I18n,
+    config: &Config,
+    args: ScriptArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let python = match args.interpreter.as_deref() {
+        Some(interpreter) => resolve_interpreter(i18n, interpreter, None, &[])?,
+        None => resolve_interpreter(
+            i18n,
+            &config.paths.python,
+            Some("python"),
+            &["python3", "python"],
+        )?,
+    };
+    ensure_min_version(i18n, &python, &config.paths.python_min_version)?;
+    exec_script(i18n, config, &python, args, ScriptType::Py, capture_bytes)
 }
 
 /// Execute a Node.js script (node subcommand)
-pub fn exec_node(i18n: &I18n, config: &Config, args: ScriptArgs) -> Result<ExitCode> {
-    let node = resolve_interpreter(i18n, &config.paths.node, &["node"])?;
-    exec_script(i18n, &node, args, ScriptType::Node)
+pub fn exec_node(
+    i18n: &I18n,
+    config: &Config,
+    args: ScriptArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let node = match args.interpreter.as_deref() {
+        Some(interpreter) => resolve_interpreter(i18n, interpreter, None, &[])?,
+        None => resolve_interpreter(i18n, &config.paths.node, Some("node"), &["node"])?,
+    };
+    ensure_min_version(i18n, &node, &config.paths.node_min_version)?;
+    exec_script(i18n, config, &node, args, ScriptType::Node, capture_bytes)
+}
+
+/// Execute a shell script (sh subcommand) with the configured shell
+pub fn exec_sh(
+    i18n: &I18n,
+    config: &Config,
+    args: ScriptArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let (shell_type, shell) = match args.interpreter.as_deref() {
+        Some(interpreter) => {
+            let path = resolve_interpreter(i18n, interpreter, None, &[])?;
+            let shell_type = path
+                .file_stem()
+                .and_then(|stem| ShellType::from_str(&stem.to_string_lossy()))
+                .unwrap_or(ShellType::Sh);
+            (shell_type, path)
+        }
+        None => detect_shell(i18n, &config.paths.shell)?,
+    };
+    exec_script(
+        i18n,
+        config,
+        &shell,
+        args,
+        ScriptType::Shell(shell_type),
+        capture_bytes,
+    )
 }
 
 /// Execute pip (pip subcommand)
 /// Uses `python -m pip` to ensure we use the correct pip for the configured Python
-pub fn exec_pip(i18n: &I18n, config: &Config, args: PassthroughArgs) -> Result<ExitCode> {
-    let python = resolve_interpreter(i18n, &config.paths.python, &["python3", "python"])?;
+pub fn exec_pip(
+    i18n: &I18n,
+    config: &Config,
+    args: PassthroughArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let python = resolve_interpreter(
+        i18n,
+        &config.paths.python,
+        Some("python"),
+        &["python3", "python"],
+    )?;
+    ensure_min_version(i18n, &python, &config.paths.python_min_version)?;
 
     let mut cmd = Command::new(&python);
     cmd.arg("-m").arg("pip");
     cmd.args(&args.args);
+    apply_cwd(i18n, &mut cmd, args.cwd.as_deref())?;
 
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-
-    let status = cmd.status().context(i18n.err_failed_to_execute("pip"))?;
-
-    Ok(exit_code_from_status(status))
+    Ok(RunOutcome {
+        cwd: args.cwd.map(absolute_dir),
+        ..spawn_and_wait(i18n, config, &mut cmd, "pip", capture_bytes)?
+    })
 }
 
 /// Execute npm (npm subcommand)
 /// Finds npm relative to the configured node path
-pub fn exec_npm(i18n: &I18n, config: &Config, args: PassthroughArgs) -> Result<ExitCode> {
+pub fn exec_npm(
+    i18n: &I18n,
+    config: &Config,
+    args: PassthroughArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
     let npm = resolve_node_tool(i18n, config, "npm")?;
 
     // On Windows, .cmd files must be executed through cmd.exe
@@ -135,19 +928,22 @@ pub fn exec_npm(i18n: &I18n, config: &Config, args: PassthroughArgs) -> Result<E
     let mut cmd = Command::new(&npm);
 
     cmd.args(&args.args);
+    apply_cwd(i18n, &mut cmd, args.cwd.as_deref())?;
 
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-
-    let status = cmd.status().context(i18n.err_failed_to_execute("npm"))?;
-
-    Ok(exit_code_from_status(status))
+    Ok(RunOutcome {
+        cwd: args.cwd.map(absolute_dir),
+        ..spawn_and_wait(i18n, config, &mut cmd, "npm", capture_bytes)?
+    })
 }
 
 /// Execute npx (npx subcommand)
 /// Finds npx relative to the configured node path
-pub fn exec_npx(i18n: &I18n, config: &Config, args: PassthroughArgs) -> Result<ExitCode> {
+pub fn exec_npx(
+    i18n: &I18n,
+    config: &Config,
+    args: PassthroughArgs,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
     let npx = resolve_node_tool(i18n, config, "npx")?;
 
     // On Windows, .cmd files must be executed through cmd.exe
@@ -161,23 +957,23 @@ pub fn exec_npx(i18n: &I18n, config: &Config, args: PassthroughArgs) -> Result<E
     let mut cmd = Command::new(&npx);
 
     cmd.args(&args.args);
+    apply_cwd(i18n, &mut cmd, args.cwd.as_deref())?;
 
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
-
-    let status = cmd.status().context(i18n.err_failed_to_execute("npx"))?;
-
-    Ok(exit_code_from_status(status))
+    Ok(RunOutcome {
+        cwd: args.cwd.map(absolute_dir),
+        ..spawn_and_wait(i18n, config, &mut cmd, "npx", capture_bytes)?
+    })
 }
 
 /// Resolve npm/npx path relative to the configured node
 fn resolve_node_tool(i18n: &I18n, config: &Config, tool: &str) -> Result<PathBuf> {
-    let node = resolve_interpreter(i18n, &config.paths.node, &["node"])?;
+    let node = resolve_interpreter(i18n, &config.paths.node, Some("node"), &["node"])?;
+    let mut tried = Vec::new();
 
     // Try to find the tool in the same directory as node
     if let Some(node_dir) = node.parent() {
         let tool_path = node_dir.join(tool);
+        tried.push(tool_path.display().to_string());
         if tool_path.exists() {
             return Ok(tool_path);
         }
@@ -186,6 +982,7 @@ fn resolve_node_tool(i18n: &I18n, config: &Config, tool: &str) -> Result<PathBuf
         #[cfg(windows)]
         {
             let tool_cmd = node_dir.join(format!("{}.cmd", tool));
+            tried.push(tool_cmd.display().to_string());
             if tool_cmd.exists() {
                 return Ok(tool_cmd);
             }
@@ -197,28 +994,52 @@ fn resolve_node_tool(i18n: &I18n, config: &Config, tool: &str) -> Result<PathBuf
         return Ok(resolved);
     }
 
-    anyhow::bail!("{}", i18n.err_interpreter_not_found(tool))
+    tried.push(tool.to_string());
+    let tried: Vec<&str> = tried.iter().map(String::as_str).collect();
+    Err(interpreter_not_found(
+        i18n,
+        tool,
+        Some("node"),
+        &config.paths.node,
+        &tried,
+    ))
 }
 
 fn exec_script(
     i18n: &I18n,
+    config: &Config,
     interpreter: &PathBuf,
     args: ScriptArgs,
     script_type: ScriptType,
-) -> Result<ExitCode> {
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
     let mut stdin = io::stdin();
-    exec_script_with_reader(i18n, interpreter, args, script_type, &mut stdin)
+    exec_script_with_reader(
+        i18n,
+        config,
+        interpreter,
+        args,
+        script_type,
+        capture_bytes,
+        &mut stdin,
+    )
 }
 
 fn exec_script_with_reader(
     i18n: &I18n,
+    config: &Config,
     interpreter: &PathBuf,
     args: ScriptArgs,
     script_type: ScriptType,
+    capture_bytes: Option<usize>,
     stdin_reader: &mut dyn Read,
-) -> Result<ExitCode> {
+) -> Result<RunOutcome> {
     if !args.has_source() {
-        anyhow::bail!("{}", i18n.err_script_source_required());
+        return Err(exit_code::validation(i18n.err_script_source_required()));
+    }
+    // clap's `source` group rejects this on the command line; guard direct callers too.
+    if args.source_count() > 1 {
+        return Err(exit_code::validation(i18n.err_script_source_conflict()));
     }
 
     let mut cmd = Command::new(interpreter);
@@ -232,13 +1053,15 @@ fn exec_script_with_reader(
     if let Some(code) = &args.code {
         // Inline code: interpreter -c "code"
         cmd.arg(script_type.code_flag()).arg(code);
+        cmd.args(script_type.inline_arg0());
     } else if let Some(file) = &args.file {
         // File: interpreter file.py
-        cmd.arg(file);
+        cmd.args(script_type.file_flag()).arg(file);
     } else {
         // Stdin: read code and pass via -c
         let code = read_to_string(i18n, stdin_reader)?;
         cmd.arg(script_type.code_flag()).arg(&code);
+        cmd.args(script_type.inline_arg0());
     }
 
     // Add script arguments
@@ -246,25 +1069,344 @@ fn exec_script_with_reader(
         cmd.arg(arg);
     }
 
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
+    spawn_and_wait(
+        i18n,
+        config,
+        &mut cmd,
+        &interpreter.display().to_string(),
+        capture_bytes,
+    )
+}
 
-    let status = cmd
-        .status()
-        .context(i18n.err_failed_to_execute(&interpreter.display().to_string()))?;
+/// Spawn the child and wait for it. With an output prefix configured, stdout/stderr
+/// are piped and forwarded line by line; otherwise stdio is inherited untouched.
+fn spawn_and_wait(
+    i18n: &I18n,
+    config: &Config,
+    cmd: &mut Command,
+    program: &str,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
+    let capture = capture_bytes.map(OutputCapture::new);
+    let started = Instant::now();
+    let status = spawn_and_wait_status(
+        i18n,
+        config,
+        cmd,
+        program,
+        TtyMode::Inherit,
+        false,
+        OutputRouting {
+            capture: capture.as_ref(),
+            ..OutputRouting::default()
+        },
+    )?;
+    Ok(RunOutcome {
+        code: exit_code_from_status(status),
+        run_time: Some(started.elapsed()),
+        excerpts: capture.map(|capture| capture.excerpts()),
+        cwd: None,
+    })
+}
+
+fn spawn_and_wait_status(
+    i18n: &I18n,
+    config: &Config,
+    cmd: &mut Command,
+    program: &str,
+    tty: TtyMode,
+    binary_safe: bool,
+    routing: OutputRouting,
+) -> Result<ExitStatus> {
+    let OutputRouting {
+        transcript,
+        stdout_file,
+        stderr_file,
+        pid_report,
+        capture,
+    } = routing;
+    match tty {
+        TtyMode::Pty => return spawn_in_pty_and_wait(i18n, config, cmd, program, pid_report),
+        TtyMode::Detached => cmd.stdin(Stdio::null()),
+        TtyMode::Inherit => cmd.stdin(Stdio::inherit()),
+    };
+
+    if binary_safe {
+        return spawn_with_base64_stdout(i18n, config, cmd, program, pid_report);
+    }
+
+    if config.output_prefix.is_empty() && transcript.is_none() && capture.is_none() {
+        cmd.stdout(stdout_file.map_or_else(Stdio::inherit, Stdio::from));
+        cmd.stderr(stderr_file.map_or_else(Stdio::inherit, Stdio::from));
+        let mut child = spawn_reporting_pid(i18n, cmd, program, pid_report)?;
+        return child.wait().context(i18n.err_failed_to_execute(program));
+    }
+
+    // A redirected stream goes straight to its file; only the others are piped
+    cmd.stdout(stdout_file.map_or_else(Stdio::piped, Stdio::from));
+    cmd.stderr(stderr_file.map_or_else(Stdio::piped, Stdio::from));
+    let mut child = spawn_reporting_pid(i18n, cmd, program, pid_report)?;
+
+    let prefix = config.output_prefix.clone().into_bytes();
+    let buffering = config.output_buffering_mode();
+    let stdout_pump = child.stdout.take().map(|out| {
+        let prefix = prefix.clone();
+        let out = tee_to_capture(tee_to_transcript(out, transcript, "out"), capture, false);
+        thread::spawn(move || prefix_lines(out, io::stdout(), &prefix, buffering))
+    });
+    let stderr_pump = child.stderr.take().map(|err| {
+        let err = tee_to_capture(tee_to_transcript(err, transcript, "err"), capture, true);
+        thread::spawn(move || prefix_lines(err, io::stderr(), &prefix, buffering))
+    });
+
+    let status = child.wait().context(i18n.err_failed_to_execute(program))?;
+    for pump in [stdout_pump, stderr_pump].into_iter().flatten() {
+        // Forwarding errors (e.g. a closed stdout) must not mask the child's exit code.
+        let _ = pump.join();
+    }
+
+    Ok(status)
+}
+
+/// Spawn `cmd` and report its PID, if asked to.
+fn spawn_reporting_pid(
+    i18n: &I18n,
+    cmd: &mut Command,
+    program: &str,
+    pid_report: Option<&PidReport>,
+) -> Result<Child> {
+    let child = cmd
+        .spawn()
+        .map_err(|err| exit_code::spawn_failed(i18n.err_failed_to_execute(program), err))?;
+    if let Some(report) = pid_report {
+        report.write(i18n, child.id());
+    }
+    Ok(child)
+}
 
-    Ok(exit_code_from_status(status))
+/// With `--log-file`, copy the lines read from `reader` into the transcript.
+fn tee_to_transcript<R: Read + Send + 'static>(
+    reader: R,
+    transcript: Option<&Arc<Transcript>>,
+    stream: &'static str,
+) -> Box<dyn Read + Send> {
+    match transcript {
+        Some(transcript) => Box::new(TeeLines::new(reader, Arc::clone(transcript), stream)),
+        None => Box::new(reader),
+    }
+}
+
+/// With `--capture`, keep a copy of what is read from `reader` for history.
+fn tee_to_capture(
+    reader: Box<dyn Read + Send>,
+    capture: Option<&Arc<OutputCapture>>,
+    stderr: bool,
+) -> Box<dyn Read + Send> {
+    match capture {
+        Some(capture) => Box::new(CaptureReader::new(reader, Arc::clone(capture), stderr)),
+        None => reader,
+    }
+}
+
+/// Run the child on a pseudo-terminal: its output is copied to stdout (with
+/// the output prefix, if any) and shnote's stdin is forwarded to it.
+#[cfg(unix)]
+fn spawn_in_pty_and_wait(
+    i18n: &I18n,
+    config: &Config,
+    cmd: &mut Command,
+    program: &str,
+    pid_report: Option<&PidReport>,
+) -> Result<ExitStatus> {
+    let (mut child, master) = crate::tty::spawn_in_pty(cmd)
+        .map_err(|err| exit_code::spawn_failed(i18n.err_failed_to_execute(program), err))?;
+    if let Some(report) = pid_report {
+        report.write(i18n, child.id());
+    }
+
+    if let Ok(mut input) = master.try_clone() {
+        // Detached: it may stay blocked on stdin after the child exits.
+        thread::spawn(move || {
+            if io::copy(&mut io::stdin().lock(), &mut input).is_ok() {
+                // End of our stdin becomes end-of-file (Ctrl-D) on the terminal.
+                let _ = input.write_all(&[0x04]);
+            }
+        });
+    }
+
+    // Reading fails with EIO once the child's side is closed; that is the
+    // end of its output, not an error worth reporting.
+    let _ = prefix_lines(
+        &master,
+        io::stdout(),
+        config.output_prefix.as_bytes(),
+        config.output_buffering_mode(),
+    );
+    let _ = io::stdout().flush();
+
+    child.wait().context(i18n.err_failed_to_execute(program))
+}
+
+#[cfg(not(unix))]
+fn spawn_in_pty_and_wait(
+    i18n: &I18n,
+    config: &Config,
+    cmd: &mut Command,
+    program: &str,
+    pid_report: Option<&PidReport>,
+) -> Result<ExitStatus> {
+    agent::warn(config.agent_mode, i18n.warn_tty_unsupported());
+    spawn_and_wait_status(
+        i18n,
+        config,
+        cmd,
+        program,
+        TtyMode::Inherit,
+        false,
+        OutputRouting {
+            pid_report,
+            ..OutputRouting::default()
+        },
+    )
+}
+
+/// `--binary-safe`: pipe stdout and forward it base64-encoded, so binary output
+/// reaches callers as plain ASCII (decode with `base64 -d`). Stderr is handled
+/// as usual; the output prefix applies to it only, since it would corrupt the
+/// encoded stream.
+fn spawn_with_base64_stdout(
+    i18n: &I18n,
+    config: &Config,
+    cmd: &mut Command,
+    program: &str,
+    pid_report: Option<&PidReport>,
+) -> Result<ExitStatus> {
+    let prefix = config.output_prefix.clone().into_bytes();
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(if prefix.is_empty() {
+        Stdio::inherit()
+    } else {
+        Stdio::piped()
+    });
+    let mut child = spawn_reporting_pid(i18n, cmd, program, pid_report)?;
+
+    let buffering = config.output_buffering_mode();
+    let stderr_pump = child
+        .stderr
+        .take()
+        .map(|err| thread::spawn(move || prefix_lines(err, io::stderr(), &prefix, buffering)));
+    if let Some(out) = child.stdout.take() {
+        // As with the prefix pumps, a closed stdout must not mask the exit code.
+        let _ = encode_base64(out, io::stdout().lock());
+    }
+
+    let status = child.wait().context(i18n.err_failed_to_execute(program))?;
+    if let Some(pump) = stderr_pump {
+        let _ = pump.join();
+    }
+    Ok(status)
+}
+
+/// Copy `reader` to `writer` as standard base64, ending with a newline unless
+/// the input was empty.
+fn encode_base64<R: Read, W: Write>(mut reader: R, writer: W) -> io::Result<()> {
+    let mut encoder = EncoderWriter::new(writer, &BASE64_STANDARD);
+    let copied = io::copy(&mut reader, &mut encoder)?;
+    let mut writer = encoder.finish()?;
+    if copied > 0 {
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Copy `reader` to `writer`, prepending `prefix` to every line.
+///
+/// Works on raw bytes so non-UTF-8 output passes through unchanged, and a
+/// trailing partial line (no final newline) is still prefixed and flushed.
+/// `buffering` decides when `writer` is flushed: after every line, only when a
+/// block fills up, or after every read so partial lines (prompts, progress)
+/// show up at once.
+fn prefix_lines<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    prefix: &[u8],
+    buffering: OutputBuffering,
+) -> io::Result<()> {
+    match buffering {
+        OutputBuffering::Line => copy_prefixed_lines(reader, writer, prefix, true),
+        OutputBuffering::Block => {
+            copy_prefixed_lines(reader, BufWriter::new(writer), prefix, false)
+        }
+        OutputBuffering::None => copy_prefixed_chunks(reader, writer, prefix),
+    }
+}
+
+fn copy_prefixed_lines<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+    prefix: &[u8],
+    flush_each_line: bool,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return writer.flush();
+        }
+        writer.write_all(prefix)?;
+        writer.write_all(&line)?;
+        if flush_each_line {
+            writer.flush()?;
+        }
+    }
+}
+
+fn copy_prefixed_chunks<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    prefix: &[u8],
+) -> io::Result<()> {
+    let mut buf = [0u8; 8192];
+    let mut at_line_start = true;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return writer.flush();
+        }
+        for piece in buf[..n].split_inclusive(|&byte| byte == b'\n') {
+            if at_line_start {
+                writer.write_all(prefix)?;
+            }
+            writer.write_all(piece)?;
+            at_line_start = piece.ends_with(b"\n");
+        }
+        writer.flush()?;
+    }
 }
 
-fn resolve_interpreter(i18n: &I18n, configured: &str, fallbacks: &[&str]) -> Result<PathBuf> {
+/// Find `configured` (an absolute path or a name in PATH), then each of
+/// `fallbacks`. `key` is the config key `configured` came from, used to
+/// suggest a fix when nothing is found; `None` for `--interpreter`.
+fn resolve_interpreter(
+    i18n: &I18n,
+    configured: &str,
+    key: Option<&str>,
+    fallbacks: &[&str],
+) -> Result<PathBuf> {
     // If configured path is absolute, use it directly
     let path = PathBuf::from(configured);
     if path.is_absolute() {
         if path.exists() {
             return Ok(path);
         }
-        anyhow::bail!("{}", i18n.err_interpreter_not_found(configured));
+        return Err(interpreter_not_found(
+            i18n,
+            configured,
+            key,
+            configured,
+            &[configured],
+        ));
     }
 
     // Try to find in PATH
@@ -279,7 +1421,61 @@ fn resolve_interpreter(i18n: &I18n, configured: &str, fallbacks: &[&str]) -> Res
         }
     }
 
-    anyhow::bail!("{}", i18n.err_interpreter_not_found(configured))
+    let mut tried = vec![configured];
+    tried.extend(fallbacks.iter().filter(|name| **name != configured));
+    Err(interpreter_not_found(
+        i18n, configured, key, configured, &tried,
+    ))
+}
+
+/// The "interpreter not found" error for `name`, followed by what was tried
+/// and how to fix it.
+fn interpreter_not_found(
+    i18n: &I18n,
+    name: &str,
+    key: Option<&str>,
+    configured: &str,
+    tried: &[&str],
+) -> anyhow::Error {
+    exit_code::not_found(format!(
+        "{}\n{}",
+        i18n.err_interpreter_not_found(name),
+        i18n.err_interpreter_not_found_hint(key, configured, &tried.join(", "))
+    ))
+}
+
+/// Refuse to use `interpreter` when it reports a version below `min_version`.
+/// An empty `min_version` disables the check.
+fn ensure_min_version(i18n: &I18n, interpreter: &Path, min_version: &str) -> Result<()> {
+    if min_version.trim().is_empty() {
+        return Ok(());
+    }
+    let display = interpreter.display().to_string();
+    let required = parse_semver_from_text(min_version)
+        .ok_or_else(|| anyhow::anyhow!("{}", i18n.err_invalid_min_version_value(min_version)))?;
+
+    let found = Command::new(interpreter)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()
+        .and_then(|output| {
+            // Python 2 prints its version to stderr.
+            parse_semver_from_text(&String::from_utf8_lossy(&output.stdout))
+                .or_else(|| parse_semver_from_text(&String::from_utf8_lossy(&output.stderr)))
+        });
+
+    match found {
+        Some(found) if found >= required => Ok(()),
+        Some(found) => anyhow::bail!(
+            "{}",
+            i18n.err_interpreter_too_old(&display, &found.to_string(), &required.to_string())
+        ),
+        None => anyhow::bail!(
+            "{}",
+            i18n.err_interpreter_version_unknown(&display, &required.to_string())
+        ),
+    }
 }
 
 fn read_to_string(i18n: &I18n, reader: &mut dyn Read) -> Result<String> {
@@ -290,13 +1486,13 @@ fn read_to_string(i18n: &I18n, reader: &mut dyn Read) -> Result<String> {
     Ok(buffer)
 }
 
-fn exit_code_from_status(status: std::process::ExitStatus) -> ExitCode {
+fn exit_code_from_status(status: ExitStatus) -> u8 {
     #[cfg(unix)]
     {
         if let Some(code) = status.code() {
-            ExitCode::from(code as u8)
+            code as u8
         } else {
-            ExitCode::from(1)
+            1
         }
     }
 
@@ -306,7 +1502,7 @@ fn exit_code_from_status(status: std::process::ExitStatus) -> ExitCode {
             .code()
             .and_then(|c| u8::try_from(c).ok())
             .unwrap_or(1);
-        ExitCode::from(code)
+        code
     }
 }
 
@@ -314,7 +1510,7 @@ fn exit_code_from_status(status: std::process::ExitStatus) -> ExitCode {
 mod tests {
     use super::*;
     use crate::i18n::Lang;
-    use crate::test_support::{env_lock, EnvVarGuard};
+    use crate::test_support::{env_lock, CurrentDirGuard, EnvVarGuard};
     use std::ffi::OsString;
     use tempfile::TempDir;
 
@@ -326,24 +1522,75 @@ mod tests {
     }
 
     #[test]
-    fn exec_run_executes_command() {
-        let _lock = env_lock();
+    fn confirm_run_accepts_yes() {
         let i18n = test_i18n();
-        let config = Config::default();
-        #[cfg(unix)]
-        let args = RunArgs {
-            command: vec![OsString::from("/usr/bin/true")],
-        };
-        #[cfg(windows)]
-        let args = RunArgs {
-            command: vec![
-                OsString::from("cmd"),
-                OsString::from("/C"),
-                OsString::from("exit"),
-                OsString::from("0"),
-            ],
-        };
-        let result = exec_run(&i18n, &config, args);
+        let note = ("List".to_string(), "Check".to_string());
+        let mut input = io::Cursor::new("y\n");
+        confirm_run(
+            &i18n,
+            &Config::default(),
+            Some(&note),
+            &Command::new("ls"),
+            &mut input,
+            true,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn confirm_run_errors_when_declined() {
+        let i18n = test_i18n();
+        let mut input = io::Cursor::new("\n");
+        let err = confirm_run(
+            &i18n,
+            &Config::default(),
+            None,
+            &Command::new("ls"),
+            &mut input,
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), i18n.err_run_declined());
+    }
+
+    #[test]
+    fn confirm_run_requires_terminal() {
+        let i18n = test_i18n();
+        let mut input = io::Cursor::new("y\n");
+        let err = confirm_run(
+            &i18n,
+            &Config::default(),
+            None,
+            &Command::new("ls"),
+            &mut input,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), i18n.err_run_confirm_requires_tty());
+        assert_eq!(exit_code::for_error(&err), exit_code::VALIDATION_ERROR);
+    }
+
+    #[test]
+    fn exec_run_executes_command() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let config = Config::default();
+        #[cfg(unix)]
+        let args = RunArgs {
+            command: vec![OsString::from("/usr/bin/true")],
+            ..Default::default()
+        };
+        #[cfg(windows)]
+        let args = RunArgs {
+            command: vec![
+                OsString::from("cmd"),
+                OsString::from("/C"),
+                OsString::from("exit"),
+                OsString::from("0"),
+            ],
+            ..Default::default()
+        };
+        let result = exec_run(&i18n, &config, args, None);
         assert!(result.is_ok());
     }
 
@@ -355,9 +1602,10 @@ mod tests {
             code: None,
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
-        let result = exec_py(&i18n, &config, args);
+        let result = exec_py(&i18n, &config, args, None);
         assert!(result.is_err());
     }
 
@@ -369,10 +1617,11 @@ mod tests {
             code: Some("print('hello')".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
         // This test may fail if python is not installed, but that's ok
-        let result = exec_py(&i18n, &config, args);
+        let result = exec_py(&i18n, &config, args, None);
         // Just ensure it doesn't panic and returns some result
         let _ = result;
     }
@@ -385,9 +1634,10 @@ mod tests {
             code: None,
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
-        let result = exec_node(&i18n, &config, args);
+        let result = exec_node(&i18n, &config, args, None);
         assert!(result.is_err());
     }
 
@@ -396,9 +1646,9 @@ mod tests {
         let i18n = test_i18n();
         // Use a path that exists on all Unix systems
         #[cfg(unix)]
-        let result = resolve_interpreter(&i18n, "/bin/sh", &[]);
+        let result = resolve_interpreter(&i18n, "/bin/sh", None, &[]);
         #[cfg(windows)]
-        let result = resolve_interpreter(&i18n, "C:\\Windows\\System32\\cmd.exe", &[]);
+        let result = resolve_interpreter(&i18n, "C:\\Windows\\System32\\cmd.exe", None, &[]);
 
         assert!(result.is_ok());
     }
@@ -406,7 +1656,7 @@ mod tests {
     #[test]
     fn resolve_interpreter_nonexistent_absolute() {
         let i18n = test_i18n();
-        let result = resolve_interpreter(&i18n, "/nonexistent/binary", &[]);
+        let result = resolve_interpreter(&i18n, "/nonexistent/binary", None, &[]);
         assert!(result.is_err());
     }
 
@@ -422,7 +1672,7 @@ mod tests {
             let _path_guard = EnvVarGuard::set("PATH", temp_dir.path());
 
             // Try with a nonexistent primary, but existing fallback
-            let result = resolve_interpreter(&i18n, "nonexistent_binary_xyz", &["sh"]);
+            let result = resolve_interpreter(&i18n, "nonexistent_binary_xyz", None, &["sh"]);
             assert_eq!(result.unwrap(), sh);
         }
     }
@@ -434,7 +1684,7 @@ mod tests {
         {
             let status = Command::new("/usr/bin/true").status().unwrap();
             let code = exit_code_from_status(status);
-            assert_eq!(code, ExitCode::SUCCESS);
+            assert_eq!(code, 0);
         }
         #[cfg(windows)]
         {
@@ -443,7 +1693,7 @@ mod tests {
                 .status()
                 .unwrap();
             let code = exit_code_from_status(status);
-            assert_eq!(code, ExitCode::SUCCESS);
+            assert_eq!(code, 0);
         }
     }
 
@@ -454,7 +1704,7 @@ mod tests {
         {
             let status = Command::new("/usr/bin/false").status().unwrap();
             let code = exit_code_from_status(status);
-            assert_ne!(code, ExitCode::SUCCESS);
+            assert_ne!(code, 0);
         }
         #[cfg(windows)]
         {
@@ -463,7 +1713,7 @@ mod tests {
                 .status()
                 .unwrap();
             let code = exit_code_from_status(status);
-            assert_ne!(code, ExitCode::SUCCESS);
+            assert_ne!(code, 0);
         }
     }
 
@@ -474,7 +1724,7 @@ mod tests {
 
         let status = std::process::ExitStatus::from_raw(9);
         let code = exit_code_from_status(status);
-        assert_eq!(code, ExitCode::from(1));
+        assert_eq!(code, 1);
     }
 
     #[cfg(unix)]
@@ -536,9 +1786,13 @@ mod tests {
         config.paths.node = node.display().to_string();
 
         let err = resolve_node_tool(&i18n, &config, "npm").unwrap_err();
-        assert!(err
-            .to_string()
-            .contains(&i18n.err_interpreter_not_found("npm")));
+        let message = err.to_string();
+        assert!(message.contains(&i18n.err_interpreter_not_found("npm")));
+        assert!(message.contains(&format!(
+            "tried: {}, npm",
+            node_dir.path().join("npm").display()
+        )));
+        assert!(message.contains("`shnote config set node <path>`"));
     }
 
     #[cfg(unix)]
@@ -584,7 +1838,8 @@ mod tests {
         let empty_path = TempDir::new().unwrap();
         let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
 
-        let err = resolve_interpreter(&i18n, "definitely_not_a_real_binary", &[]).unwrap_err();
+        let err =
+            resolve_interpreter(&i18n, "definitely_not_a_real_binary", None, &[]).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_interpreter_not_found("definitely_not_a_real_binary")));
@@ -628,15 +1883,60 @@ mod tests {
             code: Some("print('x')".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
 
-        let err = exec_py(&i18n, &config, args).unwrap_err();
+        let err = exec_py(&i18n, &config, args, None).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_interpreter_not_found("definitely_not_a_real_python")));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn exec_py_and_node_interpreter_override_wins_over_config() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let dir = TempDir::new().unwrap();
+        let configured = dir.path().join("configured");
+        write_executable(&configured, "#!/bin/sh\nexit 3\n").unwrap();
+        let override_path = dir.path().join("override");
+        write_executable(&override_path, "#!/bin/sh\nexit 7\n").unwrap();
+        let mut config = Config::default();
+        config.paths.python = configured.display().to_string();
+        config.paths.node = configured.display().to_string();
+
+        let args = |interpreter: Option<&Path>| ScriptArgs {
+            code: Some("x".to_string()),
+            file: None,
+            stdin: false,
+            interpreter: interpreter.map(|path| path.display().to_string()),
+            args: vec![],
+        };
+
+        assert_eq!(exec_py(&i18n, &config, args(None), None).unwrap().code, 3);
+        assert_eq!(
+            exec_py(&i18n, &config, args(Some(&override_path)), None)
+                .unwrap()
+                .code,
+            7
+        );
+        assert_eq!(
+            exec_node(&i18n, &config, args(Some(&override_path)), None)
+                .unwrap()
+                .code,
+            7
+        );
+
+        // No fallback to the configured interpreter when the override is missing
+        let missing = dir.path().join("missing");
+        let err = exec_py(&i18n, &config, args(Some(&missing)), None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&i18n.err_interpreter_not_found(&missing.display().to_string())));
+    }
+
     #[test]
     fn exec_node_errors_when_interpreter_not_found() {
         let _lock = env_lock();
@@ -651,10 +1951,11 @@ mod tests {
             code: Some("console.log('x')".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
 
-        let err = exec_node(&i18n, &config, args).unwrap_err();
+        let err = exec_node(&i18n, &config, args, None).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_interpreter_not_found("definitely_not_a_real_node")));
@@ -670,8 +1971,11 @@ mod tests {
         let empty_path = TempDir::new().unwrap();
         let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
 
-        let args = PassthroughArgs { args: vec![] };
-        let err = exec_pip(&i18n, &config, args).unwrap_err();
+        let args = PassthroughArgs {
+            cwd: None,
+            args: vec![],
+        };
+        let err = exec_pip(&i18n, &config, args, None).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_interpreter_not_found("definitely_not_a_real_python")));
@@ -687,8 +1991,11 @@ mod tests {
         let dir = TempDir::new().unwrap();
         config.paths.python = dir.path().display().to_string();
 
-        let args = PassthroughArgs { args: vec![] };
-        let err = exec_pip(&i18n, &config, args).unwrap_err();
+        let args = PassthroughArgs {
+            cwd: None,
+            args: vec![],
+        };
+        let err = exec_pip(&i18n, &config, args, None).unwrap_err();
         assert!(err.to_string().contains(&i18n.err_failed_to_execute("pip")));
     }
 
@@ -707,8 +2014,11 @@ mod tests {
         let empty_path = TempDir::new().unwrap();
         let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
 
-        let args = PassthroughArgs { args: vec![] };
-        let err = exec_npm(&i18n, &config, args).unwrap_err();
+        let args = PassthroughArgs {
+            cwd: None,
+            args: vec![],
+        };
+        let err = exec_npm(&i18n, &config, args, None).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_interpreter_not_found("npm")));
@@ -730,8 +2040,11 @@ mod tests {
 
         config.paths.node = node.display().to_string();
 
-        let args = PassthroughArgs { args: vec![] };
-        let err = exec_npm(&i18n, &config, args).unwrap_err();
+        let args = PassthroughArgs {
+            cwd: None,
+            args: vec![],
+        };
+        let err = exec_npm(&i18n, &config, args, None).unwrap_err();
         assert!(err.to_string().contains(&i18n.err_failed_to_execute("npm")));
     }
 
@@ -750,8 +2063,11 @@ mod tests {
         let empty_path = TempDir::new().unwrap();
         let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
 
-        let args = PassthroughArgs { args: vec![] };
-        let err = exec_npx(&i18n, &config, args).unwrap_err();
+        let args = PassthroughArgs {
+            cwd: None,
+            args: vec![],
+        };
+        let err = exec_npx(&i18n, &config, args, None).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_interpreter_not_found("npx")));
@@ -773,8 +2089,11 @@ mod tests {
 
         config.paths.node = node.display().to_string();
 
-        let args = PassthroughArgs { args: vec![] };
-        let err = exec_npx(&i18n, &config, args).unwrap_err();
+        let args = PassthroughArgs {
+            cwd: None,
+            args: vec![],
+        };
+        let err = exec_npx(&i18n, &config, args, None).unwrap_err();
         assert!(err.to_string().contains(&i18n.err_failed_to_execute("npx")));
     }
 
@@ -786,10 +2105,32 @@ mod tests {
         let empty_path = TempDir::new().unwrap();
         let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
 
-        let err = resolve_interpreter(&i18n, "nope", &["also_nope"]).unwrap_err();
-        assert!(err
-            .to_string()
-            .contains(&i18n.err_interpreter_not_found("nope")));
+        let err = resolve_interpreter(&i18n, "nope", Some("python"), &["also_nope"]).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(&i18n.err_interpreter_not_found("nope")));
+        assert!(message.contains("config: python = nope"));
+        assert!(message.contains("tried: nope, also_nope"));
+        assert!(message.contains("`shnote config set python <path>`"));
+        assert!(message.contains("`shnote doctor`"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_interpreter_hint_lists_each_name_once_and_covers_override() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let empty_path = TempDir::new().unwrap();
+        let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
+
+        let err = resolve_interpreter(&i18n, "python3", Some("python"), &["python3", "python"])
+            .unwrap_err();
+        assert!(err.to_string().contains("tried: python3, python\n"));
+
+        let err = resolve_interpreter(&i18n, "/nonexistent/python", None, &[]).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("--interpreter: /nonexistent/python"));
+        assert!(message.contains("tried: /nonexistent/python"));
+        assert!(!message.contains("config set"));
     }
 
     #[cfg(unix)]
@@ -808,15 +2149,49 @@ mod tests {
             code: None,
             file: None,
             stdin: true,
+            interpreter: None,
             args: vec![],
         };
 
         let mut reader = FailingReader;
-        let err = exec_script_with_reader(&i18n, &interpreter, args, ScriptType::Py, &mut reader)
-            .unwrap_err();
+        let err = exec_script_with_reader(
+            &i18n,
+            &Config::default(),
+            &interpreter,
+            args,
+            ScriptType::Py,
+            None,
+            &mut reader,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(i18n.err_read_stdin()));
     }
 
+    #[test]
+    fn exec_script_with_reader_rejects_multiple_sources() {
+        let i18n = test_i18n();
+        let interpreter = PathBuf::from("/bin/sh");
+        let args = ScriptArgs {
+            code: Some("echo hi".to_string()),
+            file: Some(PathBuf::from("script.sh")),
+            stdin: false,
+            interpreter: None,
+            args: vec![],
+        };
+
+        let err = exec_script_with_reader(
+            &i18n,
+            &Config::default(),
+            &interpreter,
+            args,
+            ScriptType::Py,
+            None,
+            &mut std::io::empty(),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), i18n.err_script_source_conflict());
+    }
+
     #[cfg(unix)]
     #[test]
     fn exec_script_with_reader_runs_file_and_passes_args() {
@@ -831,14 +2206,23 @@ mod tests {
             code: None,
             file: Some(script),
             stdin: false,
+            interpreter: None,
             args: vec![OsString::from("arg0")],
         };
 
         let mut stdin_reader = std::io::Cursor::new("");
-        let code =
-            exec_script_with_reader(&i18n, &interpreter, args, ScriptType::Py, &mut stdin_reader)
-                .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        let code = exec_script_with_reader(
+            &i18n,
+            &Config::default(),
+            &interpreter,
+            args,
+            ScriptType::Py,
+            None,
+            &mut stdin_reader,
+        )
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
     }
 
     #[cfg(unix)]
@@ -856,19 +2240,23 @@ mod tests {
             code: None,
             file: Some(script),
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
 
         let mut stdin_reader = std::io::Cursor::new("");
         let code = exec_script_with_reader(
             &i18n,
+            &Config::default(),
             &interpreter,
             args,
             ScriptType::Node,
+            None,
             &mut stdin_reader,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
     }
 
     #[cfg(unix)]
@@ -882,6 +2270,7 @@ mod tests {
             code: None,
             file: None,
             stdin: true,
+            interpreter: None,
             args: vec![],
         };
 
@@ -889,21 +2278,782 @@ mod tests {
         let mut stdin_reader = std::io::Cursor::new("exit 0");
         let code = exec_script_with_reader(
             &i18n,
+            &Config::default(),
             &interpreter,
             args,
             ScriptType::Node,
+            None,
             &mut stdin_reader,
         )
-        .unwrap();
+        .unwrap()
+        .code;
         // Note: sh -e "exit 0" will fail because -e means "exit on error"
         // But we're testing the code path, not the actual execution result
         let _ = code;
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn exec_script_with_reader_runs_shell_code_with_positional_args() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("args.txt");
+        let args = ScriptArgs {
+            code: None,
+            file: None,
+            stdin: true,
+            interpreter: None,
+            args: vec![OsString::from("first"), out.clone().into_os_string()],
+        };
+
+        let mut stdin_reader = std::io::Cursor::new("echo \"$1\" > \"$2\"; exit 4");
+        let code = exec_script_with_reader(
+            &i18n,
+            &Config::default(),
+            &PathBuf::from("/bin/sh"),
+            args,
+            ScriptType::Shell(ShellType::Sh),
+            None,
+            &mut stdin_reader,
+        )
+        .unwrap()
+        .code;
+        assert_eq!(code, 4);
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "first\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_sh_uses_interpreter_override() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let config = Config {
+            paths: crate::config::PathsConfig {
+                shell: "no-such-shell".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let args = ScriptArgs {
+            code: Some("exit 5".to_string()),
+            file: None,
+            stdin: false,
+            interpreter: Some("/bin/sh".to_string()),
+            args: vec![],
+        };
+
+        assert_eq!(exec_sh(&i18n, &config, args, None).unwrap().code, 5);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_runs_on_failure_hook_and_keeps_exit_code() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join("hook.txt");
+        let i18n = test_i18n();
+        let config = Config::default();
+        let args = RunArgs {
+            on_failure: Some(format!("echo failed > '{}'", marker.display())),
+            on_success: Some(format!("echo ok > '{}'", marker.display())),
+            command: vec![
+                OsString::from("sh"),
+                OsString::from("-c"),
+                OsString::from("exit 4"),
+            ],
+            ..Default::default()
+        };
+
+        let code = exec_run(&i18n, &config, args, None).unwrap().code;
+        assert_eq!(code, 4);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap().trim(), "failed");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn with_exec_prefix_inserts_wrapper_before_program() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let time = temp_dir.path().join("time");
+        write_executable(&time, "#!/bin/sh\nexec \"$@\"\n").unwrap();
+
+        let mut cmd = Command::new("ls");
+        cmd.arg("-la");
+        let prefix = vec![time.display().to_string(), "-v".to_string()];
+        let (wrapped, program) = with_exec_prefix(&i18n, &prefix, cmd, "ls").unwrap();
+
+        assert_eq!(wrapped.get_program(), time.as_os_str());
+        let args: Vec<_> = wrapped.get_args().collect();
+        assert_eq!(args, ["-v", "ls", "-la"]);
+        assert_eq!(program, time.display().to_string());
+    }
+
+    #[test]
+    fn with_exec_prefix_is_identity_when_empty() {
+        let i18n = test_i18n();
+        let mut cmd = Command::new("ls");
+        cmd.arg("-la");
+        let (cmd, program) = with_exec_prefix(&i18n, &[], cmd, "ls").unwrap();
+        assert_eq!(cmd.get_program(), "ls");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["-la"]);
+        assert_eq!(program, "ls");
+    }
+
+    #[test]
+    fn with_exec_prefix_errors_when_wrapper_missing() {
+        let i18n = test_i18n();
+        let prefix = vec!["definitely_not_a_wrapper_xyz".to_string()];
+        let err = with_exec_prefix(&i18n, &prefix, Command::new("ls"), "ls").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            i18n.err_exec_prefix_not_found("definitely_not_a_wrapper_xyz")
+        );
+    }
+
+    #[test]
+    fn split_exec_prefix_splits_on_whitespace() {
+        assert_eq!(split_exec_prefix(" time  -v "), ["time", "-v"]);
+        assert!(split_exec_prefix("").is_empty());
+    }
+
+    #[test]
+    fn child_env_applies_command_overrides() {
+        let _lock = env_lock();
+        let _inherited = EnvVarGuard::set("SHNOTE_TEST_INHERITED", "parent");
+        let _removed = EnvVarGuard::set("SHNOTE_TEST_REMOVED", "parent");
+
+        let mut cmd = Command::new("true");
+        cmd.env("SHNOTE_TEST_INHERITED", "child")
+            .env("SHNOTE_TEST_ADDED", "new")
+            .env_remove("SHNOTE_TEST_REMOVED");
+        let vars = child_env(&cmd);
+
+        assert_eq!(vars["SHNOTE_TEST_INHERITED"], "child");
+        assert_eq!(vars["SHNOTE_TEST_ADDED"], "new");
+        assert!(!vars.contains_key("SHNOTE_TEST_REMOVED"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_uses_configured_exec_prefix() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("wrapped.txt");
+        let wrapper = temp_dir.path().join("wrap");
+        write_executable(
+            &wrapper,
+            &format!(
+                "#!/bin/sh\necho \"$@\" > '{}'\nexec \"$@\"\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.run.exec_prefix = vec![wrapper.display().to_string()];
+        let args = RunArgs {
+            command: vec![OsString::from("true"), OsString::from("arg")],
+            ..Default::default()
+        };
+
+        let code = exec_run(&i18n, &config, args, None).unwrap().code;
+        assert_eq!(code, 0);
+        assert_eq!(std::fs::read_to_string(&log).unwrap().trim(), "true arg");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_exec_prefix_flag_overrides_config() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        config.run.exec_prefix = vec!["definitely_not_a_wrapper_xyz".to_string()];
+        let args = RunArgs {
+            exec_prefix: Some(String::new()),
+            command: vec![OsString::from("true"), OsString::from("arg")],
+            ..Default::default()
+        };
+
+        assert_eq!(exec_run(&i18n, &config, args, None).unwrap().code, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_pip_runs_in_cwd() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        let python = temp_dir.path().join("python3");
+        // Fake python: record the directory `python -m pip` ran in.
+        write_executable(&python, "#!/bin/sh\npwd > pip-cwd.txt\n").unwrap();
+
+        let mut config = Config::default();
+        config.paths.python = python.display().to_string();
+        let args = PassthroughArgs {
+            cwd: Some(project.clone()),
+            args: vec![OsString::from("install"), OsString::from("-r")],
+        };
+
+        assert_eq!(exec_pip(&i18n, &config, args, None).unwrap().code, 0);
+        let recorded = std::fs::read_to_string(project.join("pip-cwd.txt")).unwrap();
+        assert_eq!(
+            PathBuf::from(recorded.trim()).canonicalize().unwrap(),
+            project.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn exec_pip_rejects_missing_cwd() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing");
+        let mut config = Config::default();
+        config.paths.python = "/bin/sh".to_string();
+        let args = PassthroughArgs {
+            cwd: Some(missing.clone()),
+            args: vec![],
+        };
+
+        let err = exec_pip(&i18n, &config, args, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            i18n.err_invalid_cwd(&missing.display().to_string())
+        );
+    }
+
+    #[test]
+    fn parse_exit_remap_accepts_code_lists() {
+        let i18n = test_i18n();
+        assert_eq!(
+            parse_exit_remap(&i18n, "1=>0").unwrap(),
+            ExitRemap {
+                from: vec![1],
+                to: 0
+            }
+        );
+        assert_eq!(
+            parse_exit_remap(&i18n, "0, 1 => 3").unwrap(),
+            ExitRemap {
+                from: vec![0, 1],
+                to: 3
+            }
+        );
+    }
+
+    #[test]
+    fn parse_exit_remap_rejects_malformed_specs() {
+        let i18n = test_i18n();
+        for spec in ["", "1", "1=>", "=>0", "a=>0", "1=>256", "1=>-1", "1,,2=>0"] {
+            let err = parse_exit_remap(&i18n, spec).unwrap_err();
+            assert_eq!(err.to_string(), i18n.err_invalid_exit_on(spec));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remap_exit_code_maps_listed_codes_only() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let exit_on = vec![
+            ExitRemap {
+                from: vec![0, 1],
+                to: 0,
+            },
+            ExitRemap {
+                from: vec![1, 2],
+                to: 7,
+            },
+        ];
+        let exited = |code: i32| ExitStatus::from_raw(code << 8);
+        assert_eq!(remap_exit_code(&exit_on, exited(1)), 0);
+        assert_eq!(remap_exit_code(&exit_on, exited(2)), 7);
+        assert_eq!(remap_exit_code(&exit_on, exited(3)), 3);
+        // Signal deaths have no exit code to match and keep mapping to 1.
+        assert_eq!(remap_exit_code(&exit_on, ExitStatus::from_raw(9)), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remap_exit_code_defaults_to_identity() {
+        use std::os::unix::process::ExitStatusExt;
+
+        for code in [0, 1, 2, 42] {
+            assert_eq!(
+                remap_exit_code(&[], ExitStatus::from_raw(code << 8)),
+                code as u8
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_applies_exit_on_before_hooks() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join("hook.txt");
+        let args = RunArgs {
+            on_success: Some(format!("touch '{}'", marker.display())),
+            exit_on: vec!["1=>0".to_string()],
+            command: vec![OsString::from("false")],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            exec_run(&i18n, &Config::default(), args, None)
+                .unwrap()
+                .code,
+            0
+        );
+        assert!(marker.exists());
+    }
+
+    #[test]
+    fn exec_run_rejects_invalid_exit_on_before_running() {
+        let i18n = test_i18n();
+        let args = RunArgs {
+            exit_on: vec!["nope".to_string()],
+            command: vec![OsString::from("definitely-not-a-command")],
+            ..Default::default()
+        };
+
+        let err = exec_run(&i18n, &Config::default(), args, None).unwrap_err();
+        assert_eq!(err.to_string(), i18n.err_invalid_exit_on("nope"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_once_skips_label_that_already_succeeded() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", temp_dir.path());
+        let counter = temp_dir.path().join("runs.txt");
+        let run = |force: bool| {
+            let args = RunArgs {
+                once: true,
+                label: Some("setup".to_string()),
+                force,
+                command: vec![OsString::from(format!(
+                    "echo run >> '{}'",
+                    counter.display()
+                ))],
+                ..Default::default()
+            };
+            exec_run(&i18n, &Config::default(), args, None)
+                .unwrap()
+                .code
+        };
+        let runs = || std::fs::read_to_string(&counter).unwrap().lines().count();
+
+        assert_eq!(run(false), 0);
+        assert_eq!(runs(), 1);
+        assert_eq!(run(false), 0);
+        assert_eq!(runs(), 1);
+        assert_eq!(run(true), 0);
+        assert_eq!(runs(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_once_does_not_remember_failures() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", temp_dir.path());
+        let args = RunArgs {
+            once: true,
+            label: Some("flaky".to_string()),
+            command: vec![OsString::from("false")],
+            ..Default::default()
+        };
+
+        assert_ne!(
+            exec_run(&i18n, &Config::default(), args, None)
+                .unwrap()
+                .code,
+            0
+        );
+        let path = temp_dir.path().join(once::ONCE_FILE_NAME);
+        assert_eq!(once::cached_exit_code(&path, "flaky"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_honors_cwd() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let args = RunArgs {
+            cwd: Some(temp_dir.path().to_path_buf()),
+            command: vec![
+                OsString::from("sh"),
+                OsString::from("-c"),
+                OsString::from("pwd > run-cwd.txt"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            exec_run(&i18n, &Config::default(), args, None)
+                .unwrap()
+                .code,
+            0
+        );
+        assert!(temp_dir.path().join("run-cwd.txt").exists());
+    }
+
+    #[test]
+    fn git_root_finds_nearest_parent_with_dot_git() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        let nested = repo.join("src/deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+
+        assert_eq!(git_root(&nested), Some(repo.clone()));
+        assert_eq!(git_root(&repo), Some(repo));
+        assert_eq!(git_root(temp_dir.path()), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_chdir_to_git_root_runs_at_repo_root() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        let nested = repo.join("sub");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+        let _cwd_guard = CurrentDirGuard::set(&nested).unwrap();
+
+        let args = RunArgs {
+            chdir_to_git_root: true,
+            command: vec![
+                OsString::from("sh"),
+                OsString::from("-c"),
+                OsString::from("pwd > run-cwd.txt"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            exec_run(&i18n, &Config::default(), args, None)
+                .unwrap()
+                .code,
+            0
+        );
+        assert!(repo.join("run-cwd.txt").exists());
+        assert!(!nested.join("run-cwd.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_no_cwd_inherit_uses_and_removes_a_temp_dir() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+        let record = temp_dir.path().join("run-cwd.txt");
+
+        let args = RunArgs {
+            no_cwd_inherit: true,
+            command: vec![
+                OsString::from("sh"),
+                OsString::from("-c"),
+                OsString::from(format!("touch junk; pwd -P > '{}'", record.display())),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            exec_run(&i18n, &Config::default(), args, None)
+                .unwrap()
+                .code,
+            0
+        );
+        let child_cwd = PathBuf::from(std::fs::read_to_string(&record).unwrap().trim());
+        assert_ne!(child_cwd, temp_dir.path().canonicalize().unwrap());
+        assert!(child_cwd
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("shnote-run-"));
+        assert!(!child_cwd.exists());
+        assert!(!temp_dir.path().join("junk").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_applies_cpu_limit_to_string_command() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let mut config = Config::default();
+        config.paths.shell = "sh".to_string();
+        let args = RunArgs {
+            cpu_limit: Some(1),
+            command: vec![OsString::from("while :; do :; done")],
+            ..Default::default()
+        };
+
+        let code = exec_run(&i18n, &config, args, None).unwrap().code;
+        assert_ne!(code, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_runs_on_success_hook_only_on_success() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join("hook.txt");
+        let i18n = test_i18n();
+        let config = Config::default();
+        let args = RunArgs {
+            on_failure: Some(format!("echo failed > '{}'", marker.display())),
+            on_success: Some(format!("echo ok > '{}'", marker.display())),
+            command: vec![OsString::from("true"), OsString::from("ignored")],
+            ..Default::default()
+        };
+
+        let code = exec_run(&i18n, &config, args, None).unwrap().code;
+        assert_eq!(code, 0);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap().trim(), "ok");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_ignores_failing_hook() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let config = Config::default();
+        let args = RunArgs {
+            on_success: Some("exit 9".to_string()),
+            command: vec![OsString::from("true"), OsString::from("ignored")],
+            ..Default::default()
+        };
+
+        let code = exec_run(&i18n, &config, args, None).unwrap().code;
+        assert_eq!(code, 0);
+    }
+
+    #[test]
+    fn prefix_lines_prefixes_every_line() {
+        let mut out = Vec::new();
+        prefix_lines(&b"one\ntwo\n"[..], &mut out, b"[x] ", OutputBuffering::Line).unwrap();
+        assert_eq!(out, b"[x] one\n[x] two\n");
+    }
+
+    #[test]
+    fn prefix_lines_keeps_partial_line_and_non_utf8() {
+        let mut out = Vec::new();
+        prefix_lines(
+            &b"ok\n\xff\xfepartial"[..],
+            &mut out,
+            b"> ",
+            OutputBuffering::Line,
+        )
+        .unwrap();
+        assert_eq!(out, b"> ok\n> \xff\xfepartial");
+    }
+
+    #[test]
+    fn encode_base64_handles_invalid_utf8() {
+        let mut out = Vec::new();
+        encode_base64(&b"\xff\xfeok"[..], &mut out).unwrap();
+        assert_eq!(out, b"//5vaw==\n");
+
+        let mut empty = Vec::new();
+        encode_base64(&b""[..], &mut empty).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    /// Writer recording how many bytes had been written at each flush.
+    #[derive(Default)]
+    struct FlushLog {
+        data: Vec<u8>,
+        flushes: Vec<usize>,
+    }
+
+    impl Write for &mut FlushLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes.push(self.data.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn prefix_lines_line_mode_flushes_each_newline() {
+        let mut log = FlushLog::default();
+        prefix_lines(&b"ab\ncd\n"[..], &mut log, b"> ", OutputBuffering::Line).unwrap();
+        assert_eq!(log.data, b"> ab\n> cd\n");
+        assert_eq!(log.flushes, [5, 10, 10]);
+    }
+
+    #[test]
+    fn prefix_lines_block_mode_flushes_at_end() {
+        let mut log = FlushLog::default();
+        prefix_lines(&b"ab\ncd\n"[..], &mut log, b"> ", OutputBuffering::Block).unwrap();
+        assert_eq!(log.data, b"> ab\n> cd\n");
+        assert_eq!(log.flushes.last(), Some(&10));
+        assert!(!log.flushes.contains(&5));
+    }
+
+    #[test]
+    fn prefix_lines_none_mode_flushes_partial_lines() {
+        // A reader returning "ab\nc" then "d\n", like a prompt followed by input.
+        let reader = Read::chain(&b"ab\nc"[..], &b"d\n"[..]);
+        let mut log = FlushLog::default();
+        prefix_lines(reader, &mut log, b"> ", OutputBuffering::None).unwrap();
+        assert_eq!(log.data, b"> ab\n> cd\n");
+        assert_eq!(log.flushes, [8, 10, 10]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_with_output_prefix_returns_child_exit_code() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let config = Config {
+            output_prefix: "[t] ".to_string(),
+            ..Default::default()
+        };
+        let args = RunArgs {
+            command: vec![
+                OsString::from("sh"),
+                OsString::from("-c"),
+                OsString::from("echo out; echo err >&2; exit 3"),
+            ],
+            ..Default::default()
+        };
+
+        let code = exec_run(&i18n, &config, args, None).unwrap().code;
+        assert_eq!(code, 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_run_redirects_both_streams_into_one_file_with_output_prefix() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("all.log");
+        std::fs::write(&out, "stale contents that are longer\n").unwrap();
+        let config = Config {
+            output_prefix: "[t] ".to_string(),
+            ..Default::default()
+        };
+        let args = RunArgs {
+            redirect_stdout: Some(out.clone()),
+            redirect_stderr: Some(out.clone()),
+            command: vec![
+                OsString::from("sh"),
+                OsString::from("-c"),
+                OsString::from("echo a; echo b >&2; echo c"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(exec_run(&i18n, &config, args, None).unwrap().code, 0);
+        // Truncated, unprefixed, and neither stream overwrote the other
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn redirects_append_or_truncate() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("out.log");
+        std::fs::write(&out, "old\n").unwrap();
+
+        let append = Redirects::new(&i18n, Some(out.clone()), None, true).unwrap();
+        let (mut file, stderr) = append.open(&i18n).unwrap();
+        assert!(stderr.is_none());
+        file.as_mut().unwrap().write_all(b"new\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "old\nnew\n");
+
+        let truncate = Redirects::new(&i18n, None, Some(out.clone()), false).unwrap();
+        truncate.open(&i18n).unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "");
+    }
+
+    #[test]
+    fn redirects_reject_a_missing_directory() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing/out.log");
+
+        let err = Redirects::new(&i18n, Some(path.clone()), None, false).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), 2);
+        assert!(err
+            .to_string()
+            .contains(&i18n.err_redirect_dir_missing(&path.display().to_string())));
+        // A bare file name is relative to the current directory, which exists
+        assert!(Redirects::new(&i18n, None, Some(PathBuf::from("err.log")), false).is_ok());
+    }
+
+    #[test]
+    fn pid_report_writes_file_and_rejects_a_missing_directory() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("child.pid");
+        std::fs::write(&path, "stale\n").unwrap();
+
+        PidReport::new(&i18n, Some(path.clone()), None, false)
+            .unwrap()
+            .write(&i18n, 4242);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "4242\n");
+
+        let missing = temp_dir.path().join("missing/child.pid");
+        let err = PidReport::new(&i18n, Some(missing), None, false).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_pid_to_fd_leaves_the_descriptor_open() {
+        use std::os::fd::AsRawFd;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("fd.out");
+        let mut file = File::create(&path).unwrap();
+
+        write_pid_to_fd(file.as_raw_fd(), 7).unwrap();
+        file.write_all(b"after\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "7\nafter\n");
+
+        drop(file);
+        assert!(write_pid_to_fd(-1, 7).is_err());
+    }
+
     #[test]
     fn script_type_code_flag_returns_correct_flags() {
         assert_eq!(ScriptType::Py.code_flag(), "-c");
         assert_eq!(ScriptType::Node.code_flag(), "-e");
+        assert_eq!(ScriptType::Shell(ShellType::Bash).code_flag(), "-c");
+        assert_eq!(ScriptType::Shell(ShellType::Pwsh).code_flag(), "-Command");
+    }
+
+    #[test]
+    fn script_type_shell_flags_for_files_and_inline_code() {
+        assert_eq!(ScriptType::Py.file_flag(), None);
+        assert_eq!(ScriptType::Shell(ShellType::Sh).file_flag(), None);
+        assert_eq!(
+            ScriptType::Shell(ShellType::Pwsh).file_flag(),
+            Some("-File")
+        );
+        assert_eq!(ScriptType::Shell(ShellType::Cmd).file_flag(), Some("/C"));
+
+        assert_eq!(ScriptType::Node.inline_arg0(), None);
+        assert_eq!(ScriptType::Shell(ShellType::Zsh).inline_arg0(), Some("zsh"));
+        assert_eq!(ScriptType::Shell(ShellType::Cmd).inline_arg0(), None);
     }
 
     #[test]
@@ -922,12 +3072,99 @@ mod tests {
             code: Some("true".to_string()),
             file: None,
             stdin: false,
+            interpreter: None,
             args: vec![],
         };
 
-        let err = exec_script(&i18n, &interpreter, args, ScriptType::Py).unwrap_err();
+        let err = exec_script(
+            &i18n,
+            &Config::default(),
+            &interpreter,
+            args,
+            ScriptType::Py,
+            None,
+        )
+        .unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_failed_to_execute(&interpreter.display().to_string())));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_min_version_accepts_new_enough_interpreter() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let python = temp_dir.path().join("python3");
+        write_executable(&python, "#!/bin/sh\necho 'Python 3.12.1'\n").unwrap();
+
+        assert!(ensure_min_version(&i18n, &python, "3.10").is_ok());
+        assert!(ensure_min_version(&i18n, &python, "3.12.1").is_ok());
+        assert!(ensure_min_version(&i18n, &python, "").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_min_version_rejects_old_interpreter() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let node = temp_dir.path().join("node");
+        write_executable(&node, "#!/bin/sh\necho 'v16.20.2'\n").unwrap();
+
+        let err = ensure_min_version(&i18n, &node, "20").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            i18n.err_interpreter_too_old(&node.display().to_string(), "16.20.2", "20.0.0")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_min_version_reads_version_from_stderr() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let python = temp_dir.path().join("python");
+        write_executable(&python, "#!/bin/sh\necho 'Python 2.7.18' >&2\n").unwrap();
+
+        let err = ensure_min_version(&i18n, &python, "3.8").unwrap_err();
+        assert!(err.to_string().contains("2.7.18"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ensure_min_version_errors_when_version_unknown() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let python = temp_dir.path().join("python3");
+        write_executable(&python, "#!/bin/sh\necho 'no version here'\n").unwrap();
+
+        let err = ensure_min_version(&i18n, &python, "3.8").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            i18n.err_interpreter_version_unknown(&python.display().to_string(), "3.8.0")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exec_py_refuses_old_python_when_min_version_set() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let python = temp_dir.path().join("python3");
+        write_executable(&python, "#!/bin/sh\necho 'Python 3.6.9'\nexit 0\n").unwrap();
+
+        let mut config = Config::default();
+        config.paths.python = python.display().to_string();
+        config.paths.python_min_version = "3.9".to_string();
+
+        let args = ScriptArgs {
+            code: Some("print(1)".to_string()),
+            file: None,
+            stdin: false,
+            interpreter: None,
+            args: vec![],
+        };
+        let err = exec_py(&i18n, &config, args, None).unwrap_err();
+        assert!(err.to_string().contains("3.6.9"));
+    }
 }
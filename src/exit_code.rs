@@ -0,0 +1,110 @@
+//! Exit codes for shnote's own failures, so callers can tell them apart.
+//!
+//! They follow shell conventions: `2` for invalid usage (as clap already does
+//! for argument errors), `126`/`127` when a command cannot be executed or
+//! found. Other failures exit with [`SHNOTE_ERROR`]. In `--agent-mode` all of
+//! them are reported as [`crate::agent::EXIT_SHNOTE_ERROR`] instead.
+
+use std::fmt;
+use std::io;
+
+/// shnote itself failed, or a check (`doctor`, `setup --check-only`) did not pass.
+pub const SHNOTE_ERROR: u8 = 1;
+
+/// Invalid usage: missing `--what`/`--why`, bad `--cwd`, `--exit-on`, ...
+pub const VALIDATION_ERROR: u8 = 2;
+
+/// The command or interpreter was found but could not be executed.
+pub const NOT_EXECUTABLE: u8 = 126;
+
+/// The command or interpreter was not found.
+pub const NOT_FOUND: u8 = 127;
+
+/// An error message tagged with the exit code shnote should end with.
+#[derive(Debug)]
+struct CodedError {
+    code: u8,
+    message: String,
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+/// An error exiting with [`VALIDATION_ERROR`].
+pub fn validation(message: impl fmt::Display) -> anyhow::Error {
+    coded(VALIDATION_ERROR, message)
+}
+
+/// An error exiting with [`NOT_FOUND`].
+pub fn not_found(message: impl fmt::Display) -> anyhow::Error {
+    coded(NOT_FOUND, message)
+}
+
+/// A failure to start a command, exiting with [`NOT_FOUND`] or
+/// [`NOT_EXECUTABLE`] when the OS error says why. `message` is shown first,
+/// with `err` as its cause.
+pub fn spawn_failed(message: impl fmt::Display, err: io::Error) -> anyhow::Error {
+    let code = match err.kind() {
+        io::ErrorKind::NotFound => NOT_FOUND,
+        io::ErrorKind::PermissionDenied => NOT_EXECUTABLE,
+        _ => SHNOTE_ERROR,
+    };
+    anyhow::Error::new(err).context(CodedError {
+        code,
+        message: message.to_string(),
+    })
+}
+
+fn coded(code: u8, message: impl fmt::Display) -> anyhow::Error {
+    anyhow::Error::new(CodedError {
+        code,
+        message: message.to_string(),
+    })
+}
+
+/// The exit code for a failed shnote invocation.
+pub fn for_error(err: &anyhow::Error) -> u8 {
+    err.downcast_ref::<CodedError>()
+        .map_or(SHNOTE_ERROR, |coded| coded.code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn for_error_defaults_to_shnote_error() {
+        assert_eq!(for_error(&anyhow::anyhow!("boom")), SHNOTE_ERROR);
+    }
+
+    #[test]
+    fn for_error_reads_tagged_codes() {
+        assert_eq!(for_error(&validation("bad")), VALIDATION_ERROR);
+        assert_eq!(for_error(&not_found("gone")), NOT_FOUND);
+
+        let wrapped = Err::<(), _>(validation("bad"))
+            .context("outer")
+            .unwrap_err();
+        assert_eq!(for_error(&wrapped), VALIDATION_ERROR);
+    }
+
+    #[test]
+    fn spawn_failed_maps_os_errors() {
+        let missing = spawn_failed("run x", io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(for_error(&missing), NOT_FOUND);
+        assert_eq!(missing.to_string(), "run x");
+        assert!(format!("{missing:#}").starts_with("run x: "));
+
+        let denied = spawn_failed("run x", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(for_error(&denied), NOT_EXECUTABLE);
+
+        let other = spawn_failed("run x", io::Error::from(io::ErrorKind::Other));
+        assert_eq!(for_error(&other), SHNOTE_ERROR);
+    }
+}
@@ -4,6 +4,7 @@ use std::env;
 pub enum Lang {
     En,
     Zh,
+    Ja,
 }
 
 impl Lang {
@@ -28,6 +29,9 @@ impl Lang {
         if raw.starts_with("zh") {
             return Some(Self::Zh);
         }
+        if raw.starts_with("ja") {
+            return Some(Self::Ja);
+        }
         if raw.starts_with("en") {
             return Some(Self::En);
         }
@@ -35,23 +39,58 @@ impl Lang {
     }
 }
 
+/// Kind of status marker put in front of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    Ok,
+    Fail,
+    Warn,
+    Skip,
+}
+
+/// Status marker for `marker`; `--plain` keeps output ASCII-only.
+pub fn status_marker(marker: Marker, plain: bool) -> &'static str {
+    match (marker, plain) {
+        (Marker::Ok, false) => "✓",
+        (Marker::Fail, false) => "✗",
+        (Marker::Warn, false) => "!",
+        (Marker::Skip, false) => "-",
+        (Marker::Ok, true) => "[ok]",
+        (Marker::Fail, true) => "[fail]",
+        (Marker::Warn, true) => "[warn]",
+        (Marker::Skip, true) => "[skip]",
+    }
+}
+
 pub struct I18n {
     lang: Lang,
+    plain: bool,
 }
 
 impl I18n {
     pub fn new(lang: Lang) -> Self {
-        Self { lang }
+        Self { lang, plain: false }
+    }
+
+    /// Use ASCII status markers in messages (`--plain`, `--agent-mode`).
+    pub fn with_plain(self, plain: bool) -> Self {
+        Self { plain, ..self }
     }
 
     pub fn lang(&self) -> Lang {
         self.lang
     }
 
+    /// Status marker for messages, following [`Self::with_plain`].
+    pub fn marker(&self, marker: Marker) -> &'static str {
+        status_marker(marker, self.plain)
+    }
+
     pub fn lang_tag(&self) -> &'static str {
         match self.lang {
             Lang::En => "en",
             Lang::Zh => "zh",
+            Lang::Ja => "ja",
         }
     }
 
@@ -66,13 +105,18 @@ impl I18n {
                 "`{cmd}` 需要 `--what` 和 `--why`，并且必须写在子命令之前。\n\
                 示例：shnote --what \"...\" --why \"...\" {cmd} ..."
             ),
+            Lang::Ja => format!(
+                "`{cmd}` には `--what` と `--why` が必要で、サブコマンドより前に指定する必要があります。\n\
+                例: shnote --what \"...\" --why \"...\" {cmd} ..."
+            ),
         }
     }
 
     pub fn err_reject_root_meta(&self) -> &'static str {
         match self.lang {
-            Lang::En => "`--what/--why` are only accepted for `run`, `py`, `node`, `pip`, `npm`, and `npx` commands",
-            Lang::Zh => "`--what/--why` 只允许用于 `run`、`py`、`node`、`pip`、`npm` 和 `npx` 命令",
+            Lang::En => "`--what/--why` are only accepted for `run`, `py`, `node`, `pip`, `npm`, `npx`, and `batch` commands",
+            Lang::Zh => "`--what/--why` 只允许用于 `run`、`py`、`node`、`pip`、`npm`、`npx` 和 `batch` 命令",
+            Lang::Ja => "`--what/--why` は `run`、`py`、`node`、`pip`、`npm`、`npx`、`batch` コマンドでのみ使用できます",
         }
     }
 
@@ -80,1070 +124,3749 @@ impl I18n {
         match self.lang {
             Lang::En => "exactly one of --stdin, -c/--code, -f/--file is required",
             Lang::Zh => "必须且只能指定一种脚本来源：--stdin、-c/--code、-f/--file",
+            Lang::Ja => "--stdin、-c/--code、-f/--file のいずれか 1 つを指定してください",
         }
     }
 
-    pub fn err_failed_to_execute(&self, cmd: &str) -> String {
+    pub fn err_script_source_conflict(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("failed to execute: {cmd}"),
-            Lang::Zh => format!("执行失败：{cmd}"),
+            Lang::En => "choose only one of --code/--file/--stdin",
+            Lang::Zh => "只能选择 --code/--file/--stdin 中的一种",
+            Lang::Ja => "--code/--file/--stdin はいずれか 1 つだけ指定してください",
         }
     }
 
-    pub fn err_interpreter_not_found(&self, name: &str) -> String {
+    pub fn err_failed_to_execute(&self, cmd: &str) -> String {
         match self.lang {
-            Lang::En => format!("interpreter not found: {name}"),
-            Lang::Zh => format!("未找到解释器：{name}"),
+            Lang::En => format!("failed to execute: {cmd}"),
+            Lang::Zh => format!("执行失败：{cmd}"),
+            Lang::Ja => format!("実行に失敗しました: {cmd}"),
         }
     }
 
-    // Config messages
-    pub fn config_key_not_found(&self, key: &str) -> String {
+    pub fn err_group_requires_pueue_add(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("unknown config key: {key}"),
-            Lang::Zh => format!("未知的配置项：{key}"),
+            Lang::En => "--group only applies to `pueue add ...` commands",
+            Lang::Zh => "--group 仅适用于 `pueue add ...` 命令",
+            Lang::Ja => "--group は `pueue add ...` コマンドにのみ使用できます",
         }
     }
 
-    pub fn config_updated(&self, key: &str, value: &str) -> String {
+    pub fn err_pueue_group_missing(&self, group: &str) -> String {
         match self.lang {
-            Lang::En => format!("config updated: {key} = {value}"),
-            Lang::Zh => format!("配置已更新：{key} = {value}"),
+            Lang::En => {
+                format!("pueue group '{group}' does not exist (create it with `pueue group add {group}`)")
+            }
+            Lang::Zh => {
+                format!("pueue 分组 '{group}' 不存在（可用 `pueue group add {group}` 创建）")
+            }
+            Lang::Ja => {
+                format!("pueue グループ '{group}' は存在しません（`pueue group add {group}` で作成できます）")
+            }
         }
     }
 
-    pub fn config_reset_done(&self) -> &'static str {
+    pub fn err_pueue_group_create_failed(&self, group: &str) -> String {
         match self.lang {
-            Lang::En => "configuration reset to defaults",
-            Lang::Zh => "配置已重置为默认值",
+            Lang::En => format!("failed to create pueue group '{group}'"),
+            Lang::Zh => format!("创建 pueue 分组 '{group}' 失败"),
+            Lang::Ja => format!("pueue グループ '{group}' の作成に失敗しました"),
         }
     }
 
-    // Doctor messages
-    pub fn doctor_all_ok(&self) -> &'static str {
+    pub fn prompt_create_pueue_group(&self, group: &str) -> String {
         match self.lang {
-            Lang::En => "All dependencies OK!",
-            Lang::Zh => "所有依赖检查通过！",
+            Lang::En => format!("pueue group '{group}' does not exist. Create it?"),
+            Lang::Zh => format!("pueue 分组 '{group}' 不存在，是否创建？"),
+            Lang::Ja => format!("pueue グループ '{group}' は存在しません。作成しますか？"),
         }
     }
 
-    pub fn doctor_has_issues(&self) -> &'static str {
+    pub fn err_log_file_open(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => "Some dependencies have issues. Please fix them before using shnote.",
-            Lang::Zh => "部分依赖存在问题，请先修复后再使用 shnote。",
+            Lang::En => format!("failed to open log file: {path}"),
+            Lang::Zh => format!("无法打开日志文件：{path}"),
+            Lang::Ja => format!("ログファイルを開けません: {path}"),
         }
     }
 
-    // Setup messages
-    pub fn setup_starting(&self) -> &'static str {
+    pub fn err_redirect_dir_missing(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => "Setting up shnote...",
-            Lang::Zh => "正在设置 shnote...",
+            Lang::En => format!("cannot redirect to {path}: its directory does not exist"),
+            Lang::Zh => format!("无法重定向到 {path}：所在目录不存在"),
+            Lang::Ja => format!("{path} にリダイレクトできません: ディレクトリが存在しません"),
         }
     }
 
-    pub fn setup_extracting(&self) -> &'static str {
+    pub fn err_pid_file_dir_missing(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => "Extracting embedded binaries...",
-            Lang::Zh => "正在解压内嵌二进制文件...",
+            Lang::En => format!("cannot write the PID to {path}: its directory does not exist"),
+            Lang::Zh => format!("无法将 PID 写入 {path}：所在目录不存在"),
+            Lang::Ja => format!("PID を {path} に書き込めません: ディレクトリが存在しません"),
         }
     }
 
-    pub fn setup_downloading(&self) -> &'static str {
+    pub fn err_pid_fd_unsupported(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Downloading pueue binaries...",
-            Lang::Zh => "正在下载 pueue 二进制文件...",
+            Lang::En => "--pid-fd is only supported on Unix; use --print-pid <FILE>",
+            Lang::Zh => "--pid-fd 仅支持 Unix；请改用 --print-pid <FILE>",
+            Lang::Ja => {
+                "--pid-fd は Unix でのみ使用できます。--print-pid <FILE> を使用してください"
+            }
         }
     }
 
-    pub fn setup_path_instruction(&self) -> &'static str {
+    pub fn warn_pid_write_failed(&self, target: &str, err: &str) -> String {
         match self.lang {
-            Lang::En => "To use pueue, add the following to your PATH:",
-            Lang::Zh => "要使用 pueue，请将以下路径添加到 PATH：",
+            Lang::En => format!("Warning: failed to write the PID to {target}: {err}"),
+            Lang::Zh => format!("警告：无法将 PID 写入 {target}：{err}"),
+            Lang::Ja => format!("警告: PID を {target} に書き込めませんでした: {err}"),
         }
     }
 
-    pub fn setup_complete(&self) -> &'static str {
+    pub fn err_redirect_open(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => "Setup complete! Run `shnote doctor` to verify.",
-            Lang::Zh => "设置完成！运行 `shnote doctor` 验证。",
+            Lang::En => format!("failed to open redirect file: {path}"),
+            Lang::Zh => format!("无法打开重定向文件：{path}"),
+            Lang::Ja => format!("リダイレクト先のファイルを開けません: {path}"),
         }
     }
 
-    // Executor messages
-    pub fn err_read_stdin(&self) -> &'static str {
+    pub fn warn_announce_failed(&self, detail: &str) -> String {
         match self.lang {
-            Lang::En => "failed to read from stdin",
-            Lang::Zh => "从 stdin 读取失败",
+            Lang::En => format!("warning: failed to announce WHAT/WHY: {detail}"),
+            Lang::Zh => format!("警告：发送 WHAT/WHY 通知失败：{detail}"),
+            Lang::Ja => format!("警告: WHAT/WHY の通知に失敗しました: {detail}"),
         }
     }
 
-    // Shell messages (Unix-specific methods may not be used on Windows and vice versa)
-    #[cfg_attr(windows, allow(dead_code))]
-    pub fn err_no_shell_unix(&self) -> &'static str {
+    pub fn warn_hook_failed(&self, hook: &str, detail: &str) -> String {
         match self.lang {
-            Lang::En => "no shell found in PATH (tried: zsh, bash, sh)",
-            Lang::Zh => "在 PATH 中未找到 shell（已尝试：zsh、bash、sh）",
+            Lang::En => format!("warning: hook `{hook}` failed: {detail}"),
+            Lang::Zh => format!("警告：钩子 `{hook}` 执行失败：{detail}"),
+            Lang::Ja => format!("警告: フック `{hook}` が失敗しました: {detail}"),
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn err_no_shell_windows(&self) -> &'static str {
+    pub fn err_interpreter_not_found(&self, name: &str) -> String {
         match self.lang {
-            Lang::En => "no shell found (tried: pwsh, powershell, cmd)",
-            Lang::Zh => "未找到 shell（已尝试：pwsh、powershell、cmd）",
+            Lang::En => format!("interpreter not found: {name}"),
+            Lang::Zh => format!("未找到解释器：{name}"),
+            Lang::Ja => format!("インタプリタが見つかりません: {name}"),
         }
     }
 
-    pub fn err_shell_not_in_path(&self, name: &str) -> String {
-        match self.lang {
-            Lang::En => format!("shell not found in PATH: {name}"),
-            Lang::Zh => format!("在 PATH 中未找到 shell：{name}"),
+    /// Next steps appended to [`Self::err_interpreter_not_found`]. `key` is the
+    /// config key the interpreter came from, or `None` for `--interpreter`.
+    pub fn err_interpreter_not_found_hint(
+        &self,
+        key: Option<&str>,
+        configured: &str,
+        tried: &str,
+    ) -> String {
+        match (self.lang, key) {
+            (Lang::En, Some(key)) => format!(
+                "  config: {key} = {configured}\n  tried: {tried}\n  fix: run `shnote config set {key} <path>` to point at an installed interpreter, or `shnote doctor` to check the environment"
+            ),
+            (Lang::En, None) => format!(
+                "  --interpreter: {configured}\n  tried: {tried}\n  fix: pass an installed interpreter to --interpreter, or run `shnote doctor` to check the environment"
+            ),
+            (Lang::Zh, Some(key)) => format!(
+                "  配置：{key} = {configured}\n  已尝试：{tried}\n  解决：运行 `shnote config set {key} <路径>` 指向已安装的解释器，或运行 `shnote doctor` 检查环境"
+            ),
+            (Lang::Zh, None) => format!(
+                "  --interpreter：{configured}\n  已尝试：{tried}\n  解决：为 --interpreter 指定已安装的解释器，或运行 `shnote doctor` 检查环境"
+            ),
+            (Lang::Ja, Some(key)) => format!(
+                "  設定: {key} = {configured}\n  試行: {tried}\n  対処: `shnote config set {key} <パス>` でインストール済みのインタプリタを指定するか、`shnote doctor` で環境を確認してください"
+            ),
+            (Lang::Ja, None) => format!(
+                "  --interpreter: {configured}\n  試行: {tried}\n  対処: --interpreter にインストール済みのインタプリタを指定するか、`shnote doctor` で環境を確認してください"
+            ),
         }
     }
 
-    // Config error messages (some only used in specific code paths)
-    #[allow(dead_code)]
-    pub fn err_read_config(&self, path: &str) -> String {
+    // Config messages
+    pub fn config_key_not_found(&self, key: &str) -> String {
         match self.lang {
-            Lang::En => format!("failed to read config file: {path}"),
-            Lang::Zh => format!("读取配置文件失败：{path}"),
+            Lang::En => format!("unknown config key: {key}"),
+            Lang::Zh => format!("未知的配置项：{key}"),
+            Lang::Ja => format!("不明な設定キー: {key}"),
         }
     }
 
-    #[allow(dead_code)]
-    pub fn err_parse_config(&self, path: &str) -> String {
+    pub fn err_config_locked(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => format!("failed to parse config file: {path}"),
-            Lang::Zh => format!("解析配置文件失败：{path}"),
+            Lang::En => format!(
+                "timed out waiting for another shnote to finish changing the config (lock: {path})"
+            ),
+            Lang::Zh => format!("等待其他 shnote 进程修改配置超时（锁文件：{path}）"),
+            Lang::Ja => {
+                format!("別の shnote による設定の変更待ちがタイムアウトしました（ロック: {path}）")
+            }
         }
     }
 
-    pub fn err_create_config_dir(&self, path: &str) -> String {
+    pub fn err_config_readonly(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("failed to create config directory: {path}"),
-            Lang::Zh => format!("创建配置目录失败：{path}"),
+            Lang::En => "config is read-only (SHNOTE_CONFIG_READONLY is set)",
+            Lang::Zh => "配置为只读（已设置 SHNOTE_CONFIG_READONLY）",
+            Lang::Ja => "設定は読み取り専用です（SHNOTE_CONFIG_READONLY が設定されています）",
         }
     }
 
-    pub fn err_serialize_config(&self) -> &'static str {
+    pub fn config_updated(&self, key: &str, value: &str) -> String {
         match self.lang {
-            Lang::En => "failed to serialize config",
-            Lang::Zh => "序列化配置失败",
+            Lang::En => format!("config updated: {key} = {value}"),
+            Lang::Zh => format!("配置已更新：{key} = {value}"),
+            Lang::Ja => format!("設定を更新しました: {key} = {value}"),
         }
     }
 
-    pub fn err_write_config(&self, path: &str) -> String {
+    pub fn config_dry_run(&self, key: &str, value: &str) -> String {
         match self.lang {
-            Lang::En => format!("failed to write config file: {path}"),
-            Lang::Zh => format!("写入配置文件失败：{path}"),
+            Lang::En => format!("dry run: {key} = {value} is valid (not saved)"),
+            Lang::Zh => format!("试运行：{key} = {value} 有效（未保存）"),
+            Lang::Ja => format!("ドライラン: {key} = {value} は有効です（保存していません）"),
         }
     }
 
-    pub fn err_invalid_shell_value(&self, value: &str, valid: &str) -> String {
+    pub fn config_source_default(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("invalid shell value: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的 shell 值：{value}。有效选项：{valid}"),
+            Lang::En => "default",
+            Lang::Zh => "默认值",
+            Lang::Ja => "デフォルト",
         }
     }
 
-    pub fn err_invalid_language_value(&self, value: &str, valid: &str) -> String {
+    pub fn config_source_user(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => format!("invalid language value: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的语言值：{value}。有效选项：{valid}"),
+            Lang::En => format!("user config: {path}"),
+            Lang::Zh => format!("用户配置：{path}"),
+            Lang::Ja => format!("ユーザー設定: {path}"),
         }
     }
 
-    pub fn err_invalid_output_value(&self, value: &str, valid: &str) -> String {
+    pub fn config_diff_none(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("invalid output value: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的输出模式：{value}。有效选项：{valid}"),
+            Lang::En => "all configuration values are the defaults",
+            Lang::Zh => "所有配置值均为默认值",
+            Lang::Ja => "すべての設定値がデフォルトです",
         }
     }
 
-    pub fn err_invalid_header_stream_value(&self, value: &str, valid: &str) -> String {
+    pub fn config_diff_line(&self, key: &str, value: &str, default: &str) -> String {
         match self.lang {
-            Lang::En => format!("invalid header stream value: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的头信息输出流：{value}。有效选项：{valid}"),
+            Lang::En => format!("{key} = {value} (default: {default})"),
+            Lang::Zh => format!("{key} = {value}（默认：{default}）"),
+            Lang::Ja => format!("{key} = {value}（デフォルト: {default}）"),
         }
     }
 
-    pub fn err_invalid_header_timing_value(&self, value: &str, valid: &str) -> String {
+    pub fn config_reset_done(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("invalid header timing value: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的头信息输出时机：{value}。有效选项：{valid}"),
+            Lang::En => "configuration reset to defaults",
+            Lang::Zh => "配置已重置为默认值",
+            Lang::Ja => "設定をデフォルトに戻しました",
         }
     }
 
-    pub fn err_invalid_run_string_shell_mode_value(&self, value: &str, valid: &str) -> String {
+    // Doctor messages
+    pub fn doctor_all_ok(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("invalid run string shell mode: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的字符串执行模式：{value}。有效选项：{valid}"),
+            Lang::En => "All dependencies OK!",
+            Lang::Zh => "所有依赖检查通过！",
+            Lang::Ja => "すべての依存関係に問題はありません！",
         }
     }
 
-    pub fn err_invalid_color_value(&self, value: &str, valid: &str) -> String {
+    pub fn doctor_has_issues(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("invalid color value: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的颜色开关：{value}。有效选项：{valid}"),
+            Lang::En => "Some dependencies have issues. Please fix them before using shnote.",
+            Lang::Zh => "部分依赖存在问题，请先修复后再使用 shnote。",
+            Lang::Ja => "一部の依存関係に問題があります。shnote を使う前に修正してください。",
         }
     }
 
-    pub fn err_invalid_color_name(&self, value: &str, valid: &str) -> String {
+    pub fn doctor_update_available(&self, current: &str, latest: &str) -> String {
         match self.lang {
-            Lang::En => format!("invalid color name: {value}. Valid options: {valid}"),
-            Lang::Zh => format!("无效的颜色名称：{value}。有效选项：{valid}"),
+            Lang::En => format!("v{current}, v{latest} available: run `shnote update`"),
+            Lang::Zh => format!("v{current}，可更新到 v{latest}：运行 `shnote update`"),
+            Lang::Ja => {
+                format!("v{current}、v{latest} が利用可能です: `shnote update` を実行してください")
+            }
         }
     }
 
-    #[allow(dead_code)]
-    pub fn err_home_dir(&self) -> &'static str {
+    pub fn doctor_update_up_to_date(&self, current: &str) -> String {
         match self.lang {
-            Lang::En => "failed to determine home directory",
-            Lang::Zh => "无法确定主目录",
+            Lang::En => format!("v{current}, up to date"),
+            Lang::Zh => format!("v{current}，已是最新版本"),
+            Lang::Ja => format!("v{current}、最新です"),
         }
     }
 
-    pub fn err_current_dir(&self) -> &'static str {
+    pub fn doctor_update_check_failed(&self, detail: &str) -> String {
         match self.lang {
-            Lang::En => "failed to determine current directory",
-            Lang::Zh => "无法确定当前目录",
+            Lang::En => format!("could not check for updates: {detail}"),
+            Lang::Zh => format!("无法检查更新：{detail}"),
+            Lang::Ja => format!("更新を確認できませんでした: {detail}"),
         }
     }
 
-    // Doctor error messages
-    pub fn doctor_not_found_in_path(&self) -> &'static str {
+    pub fn doctor_history_summary(&self, records: usize, bytes: u64) -> String {
         match self.lang {
-            Lang::En => "not found in PATH",
-            Lang::Zh => "在 PATH 中未找到",
+            Lang::En => format!("{records} records, {bytes} bytes"),
+            Lang::Zh => format!("{records} 条记录，{bytes} 字节"),
+            Lang::Ja => format!("{records} 件の記録、{bytes} バイト"),
         }
     }
 
-    pub fn doctor_pueue_not_found(&self) -> &'static str {
+    pub fn doctor_history_not_writable(&self, path: &str, detail: &str) -> String {
         match self.lang {
-            Lang::En => "not found (run `shnote setup` to install)",
-            Lang::Zh => "未找到（运行 `shnote setup` 安装）",
+            Lang::En => format!("history is not writable at {path}: {detail}"),
+            Lang::Zh => format!("历史记录不可写入 {path}：{detail}"),
+            Lang::Ja => format!("履歴 {path} に書き込めません: {detail}"),
         }
     }
 
-    // Setup/download error messages
-    pub fn err_create_dir(&self, path: &str) -> String {
+    pub fn doctor_history_malformed(&self, bad: usize, total: usize) -> String {
         match self.lang {
-            Lang::En => format!("failed to create directory: {path}"),
-            Lang::Zh => format!("创建目录失败：{path}"),
+            Lang::En => format!("{bad} of {total} history lines are malformed"),
+            Lang::Zh => format!("历史记录中 {total} 行有 {bad} 行格式错误"),
+            Lang::Ja => format!("履歴 {total} 行のうち {bad} 行の形式が不正です"),
         }
     }
 
-    pub fn err_download_failed(&self) -> &'static str {
+    pub fn doctor_update_skipped_offline(&self) -> &'static str {
         match self.lang {
-            Lang::En => "download failed",
-            Lang::Zh => "下载失败",
+            Lang::En => "update check skipped (--offline)",
+            Lang::Zh => "已跳过更新检查（--offline）",
+            Lang::Ja => "更新の確認をスキップしました（--offline）",
         }
     }
 
-    pub fn err_download_no_tool(&self) -> &'static str {
+    pub fn doctor_tool_not_found(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to download (neither curl nor wget available)",
-            Lang::Zh => "下载失败（curl 和 wget 都不可用）",
+            Lang::En => "not found in PATH",
+            Lang::Zh => "未在 PATH 中找到",
+            Lang::Ja => "PATH に見つかりません",
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn err_download_powershell(&self) -> &'static str {
+    pub fn doctor_rules_current(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to download using PowerShell",
-            Lang::Zh => "使用 PowerShell 下载失败",
+            Lang::En => "up to date",
+            Lang::Zh => "已是最新",
+            Lang::Ja => "最新です",
         }
     }
 
-    pub fn err_checksum_mismatch(&self, path: &str, expected: &str, actual: &str) -> String {
+    pub fn doctor_rules_drifted(&self, path: &str, lines: usize, target: &str) -> String {
         match self.lang {
             Lang::En => format!(
-                "SHA256 checksum mismatch for {path}\n  expected: {expected}\n  actual:   {actual}"
+                "{path} differs from this version's rules in {lines} line(s); run `shnote init {target}` to refresh"
+            ),
+            Lang::Zh => format!(
+                "{path} 与当前版本的规则有 {lines} 行不同，运行 `shnote init {target}` 更新"
+            ),
+            Lang::Ja => format!(
+                "{path} はこのバージョンのルールと {lines} 行異なります。`shnote init {target}` で更新してください"
             ),
-            Lang::Zh => format!("{path} 的 SHA256 校验失败\n  预期：{expected}\n  实际：{actual}"),
         }
     }
 
-    pub fn err_shasum_run(&self) -> &'static str {
+    pub fn doctor_rules_none(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to run shasum",
-            Lang::Zh => "运行 shasum 失败",
+            Lang::En => "No installed shnote rules found.",
+            Lang::Zh => "未找到已安装的 shnote 规则。",
+            Lang::Ja => "インストール済みの shnote ルールが見つかりません。",
         }
     }
 
-    pub fn err_shasum_failed(&self) -> &'static str {
+    pub fn doctor_rules_differ(&self, path: &str, tool: &str) -> String {
         match self.lang {
-            Lang::En => "shasum failed",
-            Lang::Zh => "shasum 执行失败",
+            Lang::En => format!(
+                "{path} differs from this version's rules (outdated or edited); run `shnote init {tool}` to refresh"
+            ),
+            Lang::Zh => format!("{path} 与当前版本的规则不一致（版本落后或已手动修改），运行 `shnote init {tool}` 更新"),
+            Lang::Ja => format!(
+                "{path} はこのバージョンのルールと一致しません（古いか手動で編集されています）。`shnote init {tool}` で更新してください"
+            ),
         }
     }
 
-    pub fn err_shasum_parse(&self) -> &'static str {
+    pub fn doctor_rules_missing(&self, tool: &str) -> String {
         match self.lang {
-            Lang::En => "failed to parse shasum output",
-            Lang::Zh => "解析 shasum 输出失败",
+            Lang::En => format!("no shnote rules installed; run `shnote init {tool}`"),
+            Lang::Zh => format!("未安装 shnote 规则，运行 `shnote init {tool}` 安装"),
+            Lang::Ja => format!("shnote ルールがインストールされていません。`shnote init {tool}` を実行してください"),
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn err_certutil_run(&self) -> &'static str {
+    pub fn doctor_locale_title(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to run certutil",
-            Lang::Zh => "运行 certutil 失败",
+            Lang::En => "Language detection:",
+            Lang::Zh => "语言检测：",
+            Lang::Ja => "言語の検出:",
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn err_certutil_failed(&self) -> &'static str {
+    pub fn doctor_locale_result(&self, tag: &str, source: &str) -> String {
         match self.lang {
-            Lang::En => "certutil failed",
-            Lang::Zh => "certutil 执行失败",
+            Lang::En => format!("  result: {tag} (source: {source})"),
+            Lang::Zh => format!("  结果：{tag}（来源：{source}）"),
+            Lang::Ja => format!("  結果: {tag}（ソース: {source}）"),
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn err_certutil_parse(&self) -> &'static str {
+    pub fn doctor_locale_unset(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to parse certutil output",
-            Lang::Zh => "解析 certutil 输出失败",
+            Lang::En => "(unset)",
+            Lang::Zh => "（未设置）",
+            Lang::Ja => "（未設定）",
         }
     }
 
-    pub fn err_create_file(&self, path: &str) -> String {
+    // Setup messages
+    pub fn setup_starting(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("failed to create file: {path}"),
-            Lang::Zh => format!("创建文件失败：{path}"),
+            Lang::En => "Setting up shnote...",
+            Lang::Zh => "正在设置 shnote...",
+            Lang::Ja => "shnote をセットアップしています...",
         }
     }
 
-    pub fn err_write_file(&self, path: &str) -> String {
+    pub fn setup_extracting(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("failed to write file: {path}"),
-            Lang::Zh => format!("写入文件失败：{path}"),
+            Lang::En => "Extracting embedded binaries...",
+            Lang::Zh => "正在解压内嵌二进制文件...",
+            Lang::Ja => "内蔵バイナリを展開しています...",
         }
     }
 
-    pub fn err_read_file(&self, path: &str) -> String {
+    pub fn setup_downloading(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("failed to read file: {path}"),
-            Lang::Zh => format!("读取文件失败：{path}"),
+            Lang::En => "Downloading pueue binaries...",
+            Lang::Zh => "正在下载 pueue 二进制文件...",
+            Lang::Ja => "pueue バイナリをダウンロードしています...",
         }
     }
 
-    // Init messages
-    pub fn init_claude_success(&self, path: &str) -> String {
+    pub fn setup_path_instruction(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("✓ shnote rules installed to: {path}"),
-            Lang::Zh => format!("✓ shnote 规则已安装到：{path}"),
+            Lang::En => "To use pueue, add the following to your PATH:",
+            Lang::Zh => "要使用 pueue，请将以下路径添加到 PATH：",
+            Lang::Ja => "pueue を使うには、次のパスを PATH に追加してください:",
         }
     }
 
-    pub fn init_codex_success(&self, path: &str) -> String {
+    pub fn setup_complete(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("✓ shnote rules written to: {path}"),
-            Lang::Zh => format!("✓ shnote 规则已写入到：{path}"),
+            Lang::En => "Setup complete! Run `shnote doctor` to verify.",
+            Lang::Zh => "设置完成！运行 `shnote doctor` 验证。",
+            Lang::Ja => "セットアップが完了しました！`shnote doctor` で確認してください。",
         }
     }
 
-    pub fn init_gemini_success(&self, path: &str) -> String {
+    pub fn setup_checking(&self, dir: &str) -> String {
         match self.lang {
-            Lang::En => format!("✓ shnote rules written to: {path}"),
-            Lang::Zh => format!("✓ shnote 规则已写入到：{path}"),
+            Lang::En => format!("Verifying pueue binaries in {dir}..."),
+            Lang::Zh => format!("正在校验 {dir} 中的 pueue 二进制文件..."),
+            Lang::Ja => format!("{dir} の pueue バイナリを検証しています..."),
         }
     }
 
-    pub fn init_rules_updated(&self) -> &'static str {
+    pub fn setup_check_ok(&self) -> &'static str {
         match self.lang {
-            Lang::En => "  (existing shnote rules were updated)",
-            Lang::Zh => "  （已更新现有的 shnote 规则）",
+            Lang::En => "checksum OK",
+            Lang::Zh => "校验通过",
+            Lang::Ja => "チェックサム OK",
         }
     }
 
-    pub fn init_rules_appended(&self) -> &'static str {
+    pub fn setup_check_unverified(&self) -> &'static str {
         match self.lang {
-            Lang::En => "  (rules appended to file)",
-            Lang::Zh => "  （规则已追加到文件）",
+            Lang::En => "installed, but no known checksum for this platform",
+            Lang::Zh => "已安装，但本平台没有可用的校验值",
+            Lang::Ja => {
+                "インストール済みですが、このプラットフォームの既知のチェックサムがありません"
+            }
         }
     }
 
-    pub fn init_migrated_from(&self, old_path: &str) -> String {
+    pub fn setup_check_missing(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => format!("  (migrated from {old_path})"),
-            Lang::Zh => format!("  （已从 {old_path} 迁移）"),
+            Lang::En => format!("not installed ({path})"),
+            Lang::Zh => format!("未安装（{path}）"),
+            Lang::Ja => format!("未インストール（{path}）"),
         }
     }
 
-    pub fn init_old_rules_cleaned(&self, path: &str) -> String {
+    pub fn setup_check_mismatch(&self, expected: &str, actual: &str) -> String {
         match self.lang {
-            Lang::En => format!("  (removed old rules from {path})"),
-            Lang::Zh => format!("  （已从 {path} 移除旧规则）"),
+            Lang::En => format!(
+                "checksum mismatch (corrupted or a different version)\n      expected: {expected}\n      actual:   {actual}"
+            ),
+            Lang::Zh => format!(
+                "校验失败（文件损坏或版本不同）\n      预期：{expected}\n      实际：{actual}"
+            ),
+            Lang::Ja => format!(
+                "チェックサムが一致しません（破損しているか、別のバージョンです）\n      期待値: {expected}\n      実際:   {actual}"
+            ),
         }
     }
 
-    pub fn init_tool_found(&self, tool: &str, path: &str, version: Option<&str>) -> String {
-        let version_str = version.map(|v| format!(" {v}")).unwrap_or_default();
+    pub fn setup_check_reinstall_hint(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("✓ Detected {tool}:{version_str} ({path})"),
-            Lang::Zh => format!("✓ 检测到 {tool}:{version_str}（{path}）"),
+            Lang::En => "Run `shnote setup` to reinstall pueue.",
+            Lang::Zh => "运行 `shnote setup` 重新安装 pueue。",
+            Lang::Ja => "`shnote setup` を実行して pueue を再インストールしてください。",
         }
     }
 
-    pub fn init_tool_not_found(&self, tool: &str) -> String {
+    // Executor messages
+    pub fn err_read_stdin(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to read from stdin",
+            Lang::Zh => "从 stdin 读取失败",
+            Lang::Ja => "stdin からの読み取りに失敗しました",
+        }
+    }
+
+    // Shell messages (Unix-specific methods may not be used on Windows and vice versa)
+    #[cfg_attr(windows, allow(dead_code))]
+    pub fn err_no_shell_unix(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "no shell found in PATH (tried: zsh, bash, sh)",
+            Lang::Zh => "在 PATH 中未找到 shell（已尝试：zsh、bash、sh）",
+            Lang::Ja => "PATH にシェルが見つかりません（試行: zsh、bash、sh）",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn err_no_shell_windows(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "no shell found (tried: pwsh, powershell, cmd)",
+            Lang::Zh => "未找到 shell（已尝试：pwsh、powershell、cmd）",
+            Lang::Ja => "シェルが見つかりません（試行: pwsh、powershell、cmd）",
+        }
+    }
+
+    pub fn err_shell_not_in_path(&self, name: &str) -> String {
+        match self.lang {
+            Lang::En => format!("shell not found in PATH: {name}"),
+            Lang::Zh => format!("在 PATH 中未找到 shell：{name}"),
+            Lang::Ja => format!("PATH にシェルが見つかりません: {name}"),
+        }
+    }
+
+    // Config error messages (some only used in specific code paths)
+    #[allow(dead_code)]
+    pub fn err_read_config(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to read config file: {path}"),
+            Lang::Zh => format!("读取配置文件失败：{path}"),
+            Lang::Ja => format!("設定ファイルの読み込みに失敗しました: {path}"),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn err_parse_config(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to parse config file: {path}"),
+            Lang::Zh => format!("解析配置文件失败：{path}"),
+            Lang::Ja => format!("設定ファイルの解析に失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_create_config_dir(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to create config directory: {path}"),
+            Lang::Zh => format!("创建配置目录失败：{path}"),
+            Lang::Ja => format!("設定ディレクトリの作成に失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_serialize_config(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to serialize config",
+            Lang::Zh => "序列化配置失败",
+            Lang::Ja => "設定のシリアライズに失敗しました",
+        }
+    }
+
+    pub fn err_write_config(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to write config file: {path}"),
+            Lang::Zh => format!("写入配置文件失败：{path}"),
+            Lang::Ja => format!("設定ファイルの書き込みに失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_invalid_shell_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid shell value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的 shell 值：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な shell の値: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_min_version_value(&self, value: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid minimum version: {value}. Expected e.g. 3.10 or 20.1.0 (empty disables the check)"),
+            Lang::Zh => format!("无效的最低版本：{value}。应为 3.10 或 20.1.0 这样的版本号（留空表示不检查）"),
+            Lang::Ja => format!("無効な最低バージョン: {value}。3.10 や 20.1.0 のように指定してください（空にするとチェックしません）"),
+        }
+    }
+
+    pub fn err_invalid_cwd(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("--cwd is not an existing directory: {path}"),
+            Lang::Zh => format!("--cwd 不是已存在的目录：{path}"),
+            Lang::Ja => format!("--cwd は既存のディレクトリではありません: {path}"),
+        }
+    }
+
+    pub fn run_until_ready(&self, pid: u32, elapsed: &str, log: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "shnote: ready after {elapsed}; running in the background (pid {pid}, output: {log})"
+            ),
+            Lang::Zh => format!("shnote：{elapsed} 后就绪，已在后台运行（pid {pid}，输出：{log}）"),
+            Lang::Ja => format!(
+                "shnote: {elapsed} 後に準備完了、バックグラウンドで実行中です（pid {pid}、出力: {log}）"
+            ),
+        }
+    }
+
+    pub fn err_until_exited(&self, pattern: &str, status: &str, log: &str) -> String {
+        match self.lang {
+            Lang::En => {
+                format!("command ended ({status}) before printing \"{pattern}\" (output: {log})")
+            }
+            Lang::Zh => format!("命令在输出 \"{pattern}\" 之前已结束（{status}；输出：{log}）"),
+            Lang::Ja => format!(
+                "\"{pattern}\" が出力される前にコマンドが終了しました（{status}、出力: {log}）"
+            ),
+        }
+    }
+
+    pub fn err_until_timeout(&self, pattern: &str, secs: u64, log: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "\"{pattern}\" did not appear within {secs}s; the command was stopped (output: {log})"
+            ),
+            Lang::Zh => format!("{secs} 秒内未出现 \"{pattern}\"，已终止命令（输出：{log}）"),
+            Lang::Ja => format!("{secs} 秒以内に \"{pattern}\" が現れなかったため、コマンドを停止しました（出力: {log}）"),
+        }
+    }
+
+    pub fn err_not_in_git_repo(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("--chdir-to-git-root: not inside a git repository: {path}"),
+            Lang::Zh => format!("--chdir-to-git-root：当前目录不在 git 仓库中：{path}"),
+            Lang::Ja => format!("--chdir-to-git-root: git リポジトリ内ではありません: {path}"),
+        }
+    }
+
+    pub fn err_scratch_dir_create(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "--no-cwd-inherit: failed to create a temporary directory",
+            Lang::Zh => "--no-cwd-inherit：无法创建临时目录",
+            Lang::Ja => "--no-cwd-inherit: 一時ディレクトリを作成できませんでした",
+        }
+    }
+
+    pub fn err_strict_nonzero(&self, code: u8) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "command exited with code {code} (run_strict is on; pass --allow-nonzero to accept it)"
+            ),
+            Lang::Zh => format!(
+                "命令以退出码 {code} 结束（已启用 run_strict；使用 --allow-nonzero 接受非零退出码）"
+            ),
+            Lang::Ja => format!(
+                "コマンドが終了コード {code} で終了しました（run_strict が有効です。受け入れるには --allow-nonzero を指定してください）"
+            ),
+        }
+    }
+
+    pub fn err_args_from_read(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to read --args-from file: {path}"),
+            Lang::Zh => format!("读取 --args-from 文件失败：{path}"),
+            Lang::Ja => format!("--args-from ファイルの読み込みに失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_args_from_quote(&self, path: &str, line: usize) -> String {
+        match self.lang {
+            Lang::En => {
+                format!("unterminated or stray quote in --args-from file {path}, line {line}")
+            }
+            Lang::Zh => format!("--args-from 文件 {path} 第 {line} 行的引号不完整或多余"),
+            Lang::Ja => format!("--args-from ファイル {path} の {line} 行目に閉じられていない、または余分な引用符があります"),
+        }
+    }
+
+    pub fn err_invalid_exit_on(&self, spec: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "invalid --exit-on value: {spec} (expected CODES=>CODE, e.g. \"0,1=>0\"; target code 0-255)"
+            ),
+            Lang::Zh => format!(
+                "无效的 --exit-on 值：{spec}（格式为 CODES=>CODE，例如 \"0,1=>0\"；目标退出码为 0-255）"
+            ),
+            Lang::Ja => format!(
+                "無効な --exit-on の値: {spec}（形式は CODES=>CODE、例: \"0,1=>0\"。変換先のコードは 0-255）"
+            ),
+        }
+    }
+
+    pub fn err_exec_prefix_not_found(&self, program: &str) -> String {
+        match self.lang {
+            Lang::En => format!("exec prefix program not found: {program}"),
+            Lang::Zh => format!("未找到 exec_prefix 指定的程序：{program}"),
+            Lang::Ja => format!("exec_prefix のプログラムが見つかりません: {program}"),
+        }
+    }
+
+    pub fn err_interpreter_too_old(
+        &self,
+        interpreter: &str,
+        found: &str,
+        required: &str,
+    ) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "{interpreter} version {found} is older than the required minimum {required}"
+            ),
+            Lang::Zh => format!("{interpreter} 的版本 {found} 低于要求的最低版本 {required}"),
+            Lang::Ja => format!(
+                "{interpreter} のバージョン {found} は必要な最低バージョン {required} より古いです"
+            ),
+        }
+    }
+
+    pub fn err_interpreter_version_unknown(&self, interpreter: &str, required: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "could not determine the version of {interpreter} (minimum required: {required})"
+            ),
+            Lang::Zh => format!("无法确定 {interpreter} 的版本（最低要求：{required}）"),
+            Lang::Ja => {
+                format!("{interpreter} のバージョンを判別できません（最低要件: {required}）")
+            }
+        }
+    }
+
+    pub fn err_invalid_language_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid language value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的语言值：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な言語の値: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_output_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid output value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的输出模式：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な出力モード: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_output_buffering_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid output buffering value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的输出缓冲模式：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な出力バッファリングモード: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_header_stream_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid header stream value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的头信息输出流：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効なヘッダー出力ストリーム: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_header_timing_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid header timing value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的头信息输出时机：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効なヘッダー出力タイミング: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_checksum_algo_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid checksum_algo value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的 checksum_algo 值：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な checksum_algo の値: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_expand_env_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid expand_env value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的 expand_env 值：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な expand_env の値: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_config_env_var_unset(&self, name: &str, key: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "config {key} refers to ${name}, which is not set (expand_env = strict; write $$ for a literal $)"
+            ),
+            Lang::Zh => format!(
+                "配置项 {key} 引用的 ${name} 未设置（expand_env = strict；字面量 $ 请写作 $$）"
+            ),
+            Lang::Ja => format!(
+                "設定 {key} が参照している ${name} は設定されていません（expand_env = strict。$ そのものは $$ と書いてください）"
+            ),
+        }
+    }
+
+    pub fn err_invalid_run_string_shell_mode_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid run string shell mode: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的字符串执行模式：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な文字列実行モード: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_history_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid history value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的历史记录开关：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な history の値: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_history_max_size(&self, value: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "invalid history_max_size_mb value: {value} (expected a whole number of MiB, 0 = never rotate)"
+            ),
+            Lang::Zh => format!("无效的 history_max_size_mb 值：{value}（应为整数 MiB，0 表示不轮转）"),
+            Lang::Ja => format!("無効な history_max_size_mb の値: {value}（MiB 単位の整数。0 はローテーションしない）"),
+        }
+    }
+
+    pub fn err_invalid_history_capture_bytes(&self, value: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "invalid history_capture_bytes value: {value} (expected a whole number of bytes)"
+            ),
+            Lang::Zh => format!("无效的 history_capture_bytes 值：{value}（应为整数字节数）"),
+            Lang::Ja => format!(
+                "無効な history_capture_bytes の値: {value}（バイト数の整数を指定してください）"
+            ),
+        }
+    }
+
+    pub fn err_invalid_history_sink_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid history sink: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的历史记录去向：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な履歴の出力先: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_bool_value(&self, key: &str, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid value for {key}: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("{key} 的值无效：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("{key} の値が無効です: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_color_value(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid color value: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的颜色开关：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な color の値: {value}。有効な値: {valid}"),
+        }
+    }
+
+    pub fn err_invalid_color_name(&self, value: &str, valid: &str) -> String {
+        match self.lang {
+            Lang::En => format!("invalid color name: {value}. Valid options: {valid}"),
+            Lang::Zh => format!("无效的颜色名称：{value}。有效选项：{valid}"),
+            Lang::Ja => format!("無効な色名: {value}。有効な値: {valid}"),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn err_home_dir(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to determine home directory",
+            Lang::Zh => "无法确定主目录",
+            Lang::Ja => "ホームディレクトリを特定できませんでした",
+        }
+    }
+
+    pub fn err_current_dir(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to determine current directory",
+            Lang::Zh => "无法确定当前目录",
+            Lang::Ja => "カレントディレクトリを特定できませんでした",
+        }
+    }
+
+    // Doctor error messages
+    pub fn doctor_not_found_in_path(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "not found in PATH",
+            Lang::Zh => "在 PATH 中未找到",
+            Lang::Ja => "PATH に見つかりません",
+        }
+    }
+
+    pub fn doctor_pueue_not_found(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "not found (run `shnote setup` to install)",
+            Lang::Zh => "未找到（运行 `shnote setup` 安装）",
+            Lang::Ja => "見つかりません（`shnote setup` でインストールしてください）",
+        }
+    }
+
+    pub fn setup_download_urls(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "setup will download pueue from:",
+            Lang::Zh => "setup 将从以下地址下载 pueue：",
+            Lang::Ja => "setup は次の場所から pueue をダウンロードします:",
+        }
+    }
+
+    pub fn setup_confirm_download(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Download these files?",
+            Lang::Zh => "下载这些文件？",
+            Lang::Ja => "これらのファイルをダウンロードしますか？",
+        }
+    }
+
+    pub fn err_setup_download_declined(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "setup cancelled: download was not confirmed",
+            Lang::Zh => "setup 已取消：未确认下载",
+            Lang::Ja => "setup を中止しました: ダウンロードが確認されませんでした",
+        }
+    }
+
+    pub fn run_confirm(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Run this command?",
+            Lang::Zh => "执行该命令？",
+            Lang::Ja => "このコマンドを実行しますか？",
+        }
+    }
+
+    pub fn err_run_declined(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "command skipped: it was not confirmed",
+            Lang::Zh => "已跳过命令：未确认执行",
+            Lang::Ja => "コマンドをスキップしました: 実行が確認されませんでした",
+        }
+    }
+
+    pub fn err_run_confirm_requires_tty(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "confirmation is required (--confirm-each or run_confirm_each) but stdin is not a terminal; re-run with --yes to run it",
+            Lang::Zh => "需要确认（--confirm-each 或 run_confirm_each），但标准输入不是终端；请使用 --yes 重新运行以执行",
+            Lang::Ja => "確認が必要です（--confirm-each または run_confirm_each）が、標準入力が端末ではありません。実行するには --yes を付けて再実行してください",
+        }
+    }
+
+    pub fn err_setup_confirm_requires_tty(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "setup_confirm_download is enabled but stdin is not a terminal; re-run with --yes to download",
+            Lang::Zh => "已启用 setup_confirm_download，但标准输入不是终端；请使用 --yes 重新运行以下载",
+            Lang::Ja => "setup_confirm_download が有効ですが、標準入力が端末ではありません。ダウンロードするには --yes を付けて再実行してください",
+        }
+    }
+
+    // Setup/download error messages
+    pub fn err_create_dir(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to create directory: {path}"),
+            Lang::Zh => format!("创建目录失败：{path}"),
+            Lang::Ja => format!("ディレクトリの作成に失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_download_failed(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "download failed",
+            Lang::Zh => "下载失败",
+            Lang::Ja => "ダウンロードに失敗しました",
+        }
+    }
+
+    pub fn err_download_no_tool(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to download (neither curl nor wget available)",
+            Lang::Zh => "下载失败（curl 和 wget 都不可用）",
+            Lang::Ja => "ダウンロードに失敗しました（curl も wget も利用できません）",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn err_download_powershell(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to download using PowerShell",
+            Lang::Zh => "使用 PowerShell 下载失败",
+            Lang::Ja => "PowerShell でのダウンロードに失敗しました",
+        }
+    }
+
+    pub fn err_checksum_mismatch(&self, path: &str, expected: &str, actual: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "SHA256 checksum mismatch for {path}\n  expected: {expected}\n  actual:   {actual}"
+            ),
+            Lang::Zh => format!("{path} 的 SHA256 校验失败\n  预期：{expected}\n  实际：{actual}"),
+            Lang::Ja => {
+                format!("{path} の SHA256 が一致しません\n  期待値: {expected}\n  実際: {actual}")
+            }
+        }
+    }
+
+    pub fn err_shasum_run(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to run shasum",
+            Lang::Zh => "运行 shasum 失败",
+            Lang::Ja => "shasum の実行に失敗しました",
+        }
+    }
+
+    pub fn err_shasum_failed(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "shasum failed",
+            Lang::Zh => "shasum 执行失败",
+            Lang::Ja => "shasum が失敗しました",
+        }
+    }
+
+    pub fn err_shasum_parse(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to parse shasum output",
+            Lang::Zh => "解析 shasum 输出失败",
+            Lang::Ja => "shasum の出力の解析に失敗しました",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn err_certutil_run(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to run certutil",
+            Lang::Zh => "运行 certutil 失败",
+            Lang::Ja => "certutil の実行に失敗しました",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn err_certutil_failed(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "certutil failed",
+            Lang::Zh => "certutil 执行失败",
+            Lang::Ja => "certutil が失敗しました",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn err_certutil_parse(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to parse certutil output",
+            Lang::Zh => "解析 certutil 输出失败",
+            Lang::Ja => "certutil の出力の解析に失敗しました",
+        }
+    }
+
+    pub fn err_create_file(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to create file: {path}"),
+            Lang::Zh => format!("创建文件失败：{path}"),
+            Lang::Ja => format!("ファイルの作成に失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_write_file(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to write file: {path}"),
+            Lang::Zh => format!("写入文件失败：{path}"),
+            Lang::Ja => format!("ファイルの書き込みに失敗しました: {path}"),
+        }
+    }
+
+    pub fn err_read_file(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to read file: {path}"),
+            Lang::Zh => format!("读取文件失败：{path}"),
+            Lang::Ja => format!("ファイルの読み込みに失敗しました: {path}"),
+        }
+    }
+
+    // Init messages
+    pub fn init_claude_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules installed to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已安装到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールをインストールしました: {path}"),
+        }
+    }
+
+    pub fn init_codex_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules written to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已写入到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールを書き込みました: {path}"),
+        }
+    }
+
+    pub fn init_agents_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules written to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已写入到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールを書き込みました: {path}"),
+        }
+    }
+
+    pub fn init_copilot_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules written to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已写入到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールを書き込みました: {path}"),
+        }
+    }
+
+    pub fn err_copilot_requires_project_scope(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "the copilot target writes .github/copilot-instructions.md in the project; rerun with --scope project",
+            Lang::Zh => "copilot 目标会写入项目中的 .github/copilot-instructions.md，请使用 --scope project 重新执行",
+            Lang::Ja => "copilot ターゲットはプロジェクトの .github/copilot-instructions.md に書き込みます。--scope project を付けて再実行してください",
+        }
+    }
+
+    pub fn init_cursor_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules written to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已写入到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールを書き込みました: {path}"),
+        }
+    }
+
+    pub fn onboarding_welcome(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Welcome to shnote! This looks like its first run here.",
+            Lang::Zh => "欢迎使用 shnote！这似乎是它在本机的首次运行。",
+            Lang::Ja => "shnote へようこそ！このマシンでの初回実行のようです。",
+        }
+    }
+
+    pub fn onboarding_prompt(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Check interpreters, install pueue and add rules for your AI tools now? Each step asks first.",
+            Lang::Zh => "现在检查解释器、安装 pueue 并为 AI 工具写入规则吗？每一步都会先询问。",
+            Lang::Ja => "インタプリタの確認、pueue のインストール、AI ツール向けルールの書き込みを今行いますか？各ステップの前に確認します。",
+        }
+    }
+
+    pub fn onboarding_skipped(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Skipped; this will not be asked again. Run `shnote doctor`, `shnote setup` or `shnote init <target>` any time.",
+            Lang::Zh => "已跳过，之后不会再询问。随时可以运行 `shnote doctor`、`shnote setup` 或 `shnote init <target>`。",
+            Lang::Ja => "スキップしました。今後は確認しません。`shnote doctor`、`shnote setup`、`shnote init <target>` はいつでも実行できます。",
+        }
+    }
+
+    pub fn onboarding_prompt_setup(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "pueue is not installed. Run `shnote setup` to install it?",
+            Lang::Zh => "未安装 pueue。运行 `shnote setup` 安装吗？",
+            Lang::Ja => {
+                "pueue がインストールされていません。`shnote setup` でインストールしますか？"
+            }
+        }
+    }
+
+    pub fn onboarding_prompt_init(&self, target: &str) -> String {
+        match self.lang {
+            Lang::En => {
+                format!("{target} found. Run `shnote init {target}` to add shnote rules for it?")
+            }
+            Lang::Zh => {
+                format!("已找到 {target}。运行 `shnote init {target}` 为其写入 shnote 规则吗？")
+            }
+            Lang::Ja => {
+                format!("{target} が見つかりました。`shnote init {target}` で shnote ルールを書き込みますか？")
+            }
+        }
+    }
+
+    pub fn onboarding_done(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Setup finished.",
+            Lang::Zh => "设置完成。",
+            Lang::Ja => "セットアップが完了しました。",
+        }
+    }
+
+    pub fn warn_onboarding_step_failed(&self, err: &str) -> String {
+        match self.lang {
+            Lang::En => format!("warning: first-run setup step failed: {err}"),
+            Lang::Zh => format!("警告：首次运行设置的某一步失败：{err}"),
+            Lang::Ja => format!("警告: 初回セットアップのステップが失敗しました: {err}"),
+        }
+    }
+
+    pub fn init_windsurf_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules written to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已写入到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールを書き込みました: {path}"),
+        }
+    }
+
+    pub fn init_aider_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!(
+                "{ok} shnote rules written to: {path}\n  Aider reads it only when loaded: add `read: {path}` to .aider.conf.yml or pass `--read {path}`"
+            ),
+            Lang::Zh => format!(
+                "{ok} shnote 规则已写入到：{path}\n  Aider 只在加载该文件时读取：在 .aider.conf.yml 中加入 `read: {path}` 或使用 `--read {path}`"
+            ),
+            Lang::Ja => format!(
+                "{ok} shnote ルールを書き込みました: {path}\n  Aider はこのファイルを読み込むよう指定された場合のみ読み取ります: .aider.conf.yml に `read: {path}` を追加するか、`--read {path}` を使用してください"
+            ),
+        }
+    }
+
+    pub fn err_cursor_requires_project_scope(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "the cursor target writes .cursor/rules/shnote.mdc in the project (Cursor keeps user rules in its settings); rerun with --scope project",
+            Lang::Zh => "cursor 目标会写入项目中的 .cursor/rules/shnote.mdc（Cursor 的用户级规则保存在其设置中），请使用 --scope project 重新执行",
+            Lang::Ja => "cursor ターゲットはプロジェクトの .cursor/rules/shnote.mdc に書き込みます（Cursor のユーザールールは設定内に保存されます）。--scope project を付けて再実行してください",
+        }
+    }
+
+    pub fn init_gemini_success(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} shnote rules written to: {path}"),
+            Lang::Zh => format!("{ok} shnote 规则已写入到：{path}"),
+            Lang::Ja => format!("{ok} shnote ルールを書き込みました: {path}"),
+        }
+    }
+
+    pub fn init_rules_updated(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "  (existing shnote rules were updated)",
+            Lang::Zh => "  （已更新现有的 shnote 规则）",
+            Lang::Ja => "  （既存の shnote ルールを更新しました）",
+        }
+    }
+
+    pub fn init_rules_appended(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "  (rules appended to file)",
+            Lang::Zh => "  （规则已追加到文件）",
+            Lang::Ja => "  （ルールをファイルに追記しました）",
+        }
+    }
+
+    pub fn init_backup_saved(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("  (original saved to {path})"),
+            Lang::Zh => format!("  （原文件已备份到 {path}）"),
+            Lang::Ja => format!("  （元のファイルを {path} にバックアップしました）"),
+        }
+    }
+
+    pub fn init_migrated_from(&self, old_path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("  (migrated from {old_path})"),
+            Lang::Zh => format!("  （已从 {old_path} 迁移）"),
+            Lang::Ja => format!("  （{old_path} から移行しました）"),
+        }
+    }
+
+    pub fn init_old_rules_cleaned(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("  (removed old rules from {path})"),
+            Lang::Zh => format!("  （已从 {path} 移除旧规则）"),
+            Lang::Ja => format!("  （{path} から古いルールを削除しました）"),
+        }
+    }
+
+    pub fn init_dry_run_create_dir(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would create directory: {path}"),
+            Lang::Zh => format!("[dry-run] 将创建目录：{path}"),
+            Lang::Ja => format!("[dry-run] ディレクトリを作成します: {path}"),
+        }
+    }
+
+    pub fn init_dry_run_create_file(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would create file: {path}"),
+            Lang::Zh => format!("[dry-run] 将创建文件：{path}"),
+            Lang::Ja => format!("[dry-run] ファイルを作成します: {path}"),
+        }
+    }
+
+    pub fn init_dry_run_overwrite_file(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would overwrite file: {path}"),
+            Lang::Zh => format!("[dry-run] 将覆盖文件：{path}"),
+            Lang::Ja => format!("[dry-run] ファイルを上書きします: {path}"),
+        }
+    }
+
+    pub fn init_dry_run_update_rules(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would update existing shnote rules in: {path}"),
+            Lang::Zh => format!("[dry-run] 将更新现有的 shnote 规则：{path}"),
+            Lang::Ja => format!("[dry-run] 既存の shnote ルールを更新します: {path}"),
+        }
+    }
+
+    pub fn init_dry_run_append_rules(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would append shnote rules to: {path}"),
+            Lang::Zh => format!("[dry-run] 将追加 shnote 规则到：{path}"),
+            Lang::Ja => format!("[dry-run] shnote ルールを追記します: {path}"),
+        }
+    }
+
+    pub fn init_dry_run_migrate(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would migrate and remove old shnote rules from: {path}"),
+            Lang::Zh => format!("[dry-run] 将从以下文件迁移并移除旧的 shnote 规则：{path}"),
+            Lang::Ja => {
+                format!("[dry-run] 次のファイルから移行し、古い shnote ルールを削除します: {path}")
+            }
+        }
+    }
+
+    pub fn init_dry_run_done(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Dry run: no files were changed.",
+            Lang::Zh => "演练模式：未修改任何文件。",
+            Lang::Ja => "ドライラン: ファイルは変更されていません。",
+        }
+    }
+
+    pub fn init_tool_found(&self, tool: &str, path: &str, version: Option<&str>) -> String {
+        let ok = self.marker(Marker::Ok);
+        let version_str = version.map(|v| format!(" {v}")).unwrap_or_default();
+        match self.lang {
+            Lang::En => format!("{ok} Detected {tool}:{version_str} ({path})"),
+            Lang::Zh => format!("{ok} 检测到 {tool}:{version_str}（{path}）"),
+            Lang::Ja => format!("{ok} {tool} を検出しました:{version_str}（{path}）"),
+        }
+    }
+
+    pub fn err_init_target_required(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "init needs a target (claude, codex, gemini, agents, copilot, cursor, windsurf, aider) or --all; see init --list-targets",
+            Lang::Zh => "init 需要指定目标（claude、codex、gemini、agents、copilot、cursor、windsurf、aider）或 --all；可用 init --list-targets 查看",
+            Lang::Ja => "init にはターゲット（claude、codex、gemini、agents、copilot、cursor、windsurf、aider）または --all が必要です。init --list-targets で一覧を確認できます",
+        }
+    }
+
+    pub fn err_init_all_with_target(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "init --all cannot be combined with a target",
+            Lang::Zh => "init --all 不能与具体目标同时使用",
+            Lang::Ja => "init --all は特定のターゲットと同時に使用できません",
+        }
+    }
+
+    pub fn init_all_written(&self, target: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} {target}: rules written"),
+            Lang::Zh => format!("{ok} {target}：规则已写入"),
+            Lang::Ja => format!("{ok} {target}: ルールを書き込みました"),
+        }
+    }
+
+    pub fn init_all_not_found(&self, target: &str, tool: &str) -> String {
+        let skip = self.marker(Marker::Skip);
+        match self.lang {
+            Lang::En => format!("{skip} {target}: skipped ({tool} not found in PATH)"),
+            Lang::Zh => format!("{skip} {target}：已跳过（未在 PATH 中找到 {tool}）"),
+            Lang::Ja => {
+                format!("{skip} {target}: スキップしました（PATH に {tool} が見つかりません）")
+            }
+        }
+    }
+
+    pub fn init_all_no_cli(&self, target: &str) -> String {
+        let skip = self.marker(Marker::Skip);
+        match self.lang {
+            Lang::En => format!(
+                "{skip} {target}: skipped (nothing to detect; run `shnote init -s project {target}`)"
+            ),
+            Lang::Zh => format!(
+                "{skip} {target}：已跳过（无可检测的工具；请运行 `shnote init -s project {target}`）"
+            ),
+            Lang::Ja => format!(
+                "{skip} {target}: スキップしました（検出できるツールがありません。`shnote init -s project {target}` を実行してください）"
+            ),
+        }
+    }
+
+    pub fn init_all_failed(&self, target: &str, err: &str) -> String {
+        let fail = self.marker(Marker::Fail);
+        match self.lang {
+            Lang::En => format!("{fail} {target}: failed: {err}"),
+            Lang::Zh => format!("{fail} {target}：失败：{err}"),
+            Lang::Ja => format!("{fail} {target}: 失敗しました: {err}"),
+        }
+    }
+
+    pub fn help_arg_init_all(&self) -> &'static str {
+        match self.lang {
+            Lang::En => {
+                "Install rules for every target whose tool is installed, continuing past failures"
+            }
+            Lang::Zh => "为所有已安装工具的目标写入规则，某个目标失败时继续处理其余目标",
+            Lang::Ja => "ツールがインストールされているすべてのターゲットにルールを書き込みます。失敗したターゲットがあっても残りを続行します",
+        }
+    }
+
+    pub fn help_arg_init_remove(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Remove shnote's rules from the target's file instead of writing them, keeping everything else",
+            Lang::Zh => "从目标文件中移除 shnote 规则（而非写入），其余内容保持不变",
+            Lang::Ja => "ターゲットのファイルから shnote ルールを削除します（書き込みの代わりに）。他の内容はそのまま残します",
+        }
+    }
+
+    pub fn init_remove_done(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} Removed shnote rules from {path}"),
+            Lang::Zh => format!("{ok} 已从 {path} 移除 shnote 规则"),
+            Lang::Ja => format!("{ok} {path} から shnote ルールを削除しました"),
+        }
+    }
+
+    pub fn init_remove_deleted(&self, path: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} Deleted {path} (it held only shnote rules)"),
+            Lang::Zh => format!("{ok} 已删除 {path}（其中只有 shnote 规则）"),
+            Lang::Ja => {
+                format!("{ok} {path} を削除しました（shnote ルールのみが含まれていました）")
+            }
+        }
+    }
+
+    pub fn init_remove_nothing(&self, target: &str) -> String {
+        match self.lang {
+            Lang::En => format!("No shnote rules found for {target}; nothing to remove."),
+            Lang::Zh => format!("未找到 {target} 的 shnote 规则，无需移除。"),
+            Lang::Ja => {
+                format!("{target} の shnote ルールが見つかりません。削除するものはありません。")
+            }
+        }
+    }
+
+    pub fn init_dry_run_remove_rules(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would remove shnote rules from: {path}"),
+            Lang::Zh => format!("[dry-run] 将从以下文件移除 shnote 规则：{path}"),
+            Lang::Ja => format!("[dry-run] 次のファイルから shnote ルールを削除します: {path}"),
+        }
+    }
+
+    pub fn init_dry_run_delete_file(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("[dry-run] would delete file: {path}"),
+            Lang::Zh => format!("[dry-run] 将删除文件：{path}"),
+            Lang::Ja => format!("[dry-run] ファイルを削除します: {path}"),
+        }
+    }
+
+    pub fn test_rules_ok(&self, target: &str, lang: &str) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} {target} ({lang})"),
+            Lang::Zh => format!("{ok} {target}（{lang}）"),
+            Lang::Ja => format!("{ok} {target}（{lang}）"),
+        }
+    }
+
+    pub fn test_rules_failed(&self, target: &str, lang: &str, problem: &str) -> String {
+        let fail = self.marker(Marker::Fail);
+        match self.lang {
+            Lang::En => format!("{fail} {target} ({lang}): {problem}"),
+            Lang::Zh => format!("{fail} {target}（{lang}）：{problem}"),
+            Lang::Ja => format!("{fail} {target}（{lang}）: {problem}"),
+        }
+    }
+
+    pub fn test_rules_leftover(&self, placeholders: &str, pueue: bool) -> String {
+        match (self.lang, pueue) {
+            (Lang::En, false) => format!("unfilled placeholders {placeholders}"),
+            (Lang::En, true) => {
+                format!("unfilled placeholders {placeholders} with the pueue section")
+            }
+            (Lang::Zh, false) => format!("存在未填充的占位符 {placeholders}"),
+            (Lang::Zh, true) => format!("包含 pueue 段落时存在未填充的占位符 {placeholders}"),
+            (Lang::Ja, false) => format!("埋められていないプレースホルダーがあります: {placeholders}"),
+            (Lang::Ja, true) => format!("pueue セクションを含めると埋められていないプレースホルダーがあります: {placeholders}"),
+        }
+    }
+
+    pub fn test_rules_round_trip(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "the marked section does not round-trip (insert, extract, update)",
+            Lang::Zh => "标记区块无法往返（插入、提取、更新）",
+            Lang::Ja => "マーク付きブロックの往復（挿入、抽出、更新）に失敗しました",
+        }
+    }
+
+    pub fn test_rules_summary(&self, failed: usize, total: usize) -> String {
+        match (self.lang, failed) {
+            (Lang::En, 0) => format!("All {total} rule templates render correctly."),
+            (Lang::En, _) => format!("{failed} of {total} rule templates have problems."),
+            (Lang::Zh, 0) => format!("全部 {total} 个规则模板渲染正确。"),
+            (Lang::Zh, _) => format!("{total} 个规则模板中有 {failed} 个存在问题。"),
+            (Lang::Ja, 0) => {
+                format!("{total} 個のルールテンプレートはすべて正しくレンダリングされました。")
+            }
+            (Lang::Ja, _) => {
+                format!("{total} 個のルールテンプレートのうち {failed} 個に問題があります。")
+            }
+        }
+    }
+
+    pub fn init_target_paths(&self, user: Option<&str>, project: &str) -> String {
+        match (self.lang, user) {
+            (Lang::En, Some(user)) => format!("  user: {user}\n  project: {project}"),
+            (Lang::En, None) => format!("  user: (project scope only)\n  project: {project}"),
+            (Lang::Zh, Some(user)) => format!("  用户级：{user}\n  项目级：{project}"),
+            (Lang::Zh, None) => format!("  用户级：（仅支持项目级）\n  项目级：{project}"),
+            (Lang::Ja, Some(user)) => format!("  ユーザー: {user}\n  プロジェクト: {project}"),
+            (Lang::Ja, None) => {
+                format!("  ユーザー:（プロジェクトスコープのみ）\n  プロジェクト: {project}")
+            }
+        }
+    }
+
+    pub fn init_target_detected(&self, tool: &str, path: Option<&str>) -> String {
+        match (self.lang, path) {
+            (Lang::En, Some(path)) => format!("  {tool}: found ({path})"),
+            (Lang::En, None) => format!("  {tool}: not found in PATH"),
+            (Lang::Zh, Some(path)) => format!("  {tool}：已安装（{path}）"),
+            (Lang::Zh, None) => format!("  {tool}：未在 PATH 中找到"),
+            (Lang::Ja, Some(path)) => format!("  {tool}: 検出済み（{path}）"),
+            (Lang::Ja, None) => format!("  {tool}: PATH に見つかりません"),
+        }
+    }
+
+    pub fn init_tool_not_found(&self, tool: &str) -> String {
+        let warn = self.marker(Marker::Warn);
+        match self.lang {
+            Lang::En => format!("{warn} {tool} not found in PATH (rules will still be written)"),
+            Lang::Zh => format!("{warn} 未在 PATH 中找到 {tool}（仍会写入规则）"),
+            Lang::Ja => format!("{warn} PATH に {tool} が見つかりません（ルールは書き込まれます）"),
+        }
+    }
+
+    // === Help text translations (for clap runtime i18n) ===
+
+    // App level
+    pub fn help_app_about(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "A lightweight command wrapper that enforces WHAT/WHY documentation",
+            Lang::Zh => "轻量级命令包装器，强制执行 WHAT/WHY 文档记录",
+            Lang::Ja => "WHAT/WHY の記録を必須にする軽量なコマンドラッパー",
+        }
+    }
+
+    // Global arguments
+    pub fn help_arg_what(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "What this task does (required for run/py/node/sh/pip/npm/npx/batch, must appear before subcommand)",
+            Lang::Zh => "这个任务做什么（run/py/node/sh/pip/npm/npx/batch 必需，必须在子命令之前）",
+            Lang::Ja => "このタスクで何をするか（run/py/node/sh/pip/npm/npx/batch で必須、サブコマンドより前に指定）",
+        }
+    }
+
+    pub fn help_arg_why(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Why this task is being executed (required for run/py/node/sh/pip/npm/npx/batch, must appear before subcommand)",
+            Lang::Zh => "为什么执行这个任务（run/py/node/sh/pip/npm/npx/batch 必需，必须在子命令之前）",
+            Lang::Ja => "なぜこのタスクを実行するか（run/py/node/sh/pip/npm/npx/batch で必須、サブコマンドより前に指定）",
+        }
+    }
+
+    pub fn help_arg_lang(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Language for messages (auto-detected by default)",
+            Lang::Zh => "消息语言（默认自动检测）",
+            Lang::Ja => "メッセージの言語（デフォルトは自動検出）",
+        }
+    }
+
+    pub fn help_arg_header_stream(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Header output stream: auto|stdout|stderr",
+            Lang::Zh => "头信息输出流：auto|stdout|stderr",
+            Lang::Ja => "ヘッダーの出力先ストリーム: auto|stdout|stderr",
+        }
+    }
+
+    pub fn help_arg_plain(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Plain mode: no WHAT/WHY header, no color, no glyphs",
+            Lang::Zh => "纯净模式：不输出 WHAT/WHY 头信息、颜色和符号",
+            Lang::Ja => "プレーンモード: WHAT/WHY ヘッダー、色、記号を出力しません",
+        }
+    }
+
+    pub fn help_arg_allow_nonzero(&self) -> &'static str {
+        match self.lang {
+            Lang::En => {
+                "With run_strict enabled, pass a nonzero exit code through instead of failing"
+            }
+            Lang::Zh => "启用 run_strict 时，直接透传非零退出码而不是报错",
+            Lang::Ja => "run_strict が有効なとき、0 以外の終了コードをエラーにせずそのまま返します",
+        }
+    }
+
+    pub fn help_arg_agent_mode(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Agent mode: WHAT/WHY and shnote errors go to stderr as JSON lines, stdout is the command's own; exit code is the command's, or 125 if shnote fails",
+            Lang::Zh => "代理模式：WHAT/WHY 与 shnote 自身错误以 JSON 行输出到 stderr，stdout 只包含命令自身输出；退出码为命令的退出码，shnote 自身出错时为 125",
+            Lang::Ja => "エージェントモード: WHAT/WHY と shnote 自身のエラーを JSON 行として stderr に出力し、stdout にはコマンド自身の出力のみを出します。終了コードはコマンドの終了コードで、shnote 自身のエラー時は 125 です",
+        }
+    }
+
+    // Subcommands
+    pub fn help_cmd_run(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute a shell command (passthrough)",
+            Lang::Zh => "执行 shell 命令（透传）",
+            Lang::Ja => "シェルコマンドを実行します（パススルー）",
+        }
+    }
+
+    pub fn help_cmd_py(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute a Python script",
+            Lang::Zh => "执行 Python 脚本",
+            Lang::Ja => "Python スクリプトを実行します",
+        }
+    }
+
+    pub fn help_cmd_node(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute a Node.js script",
+            Lang::Zh => "执行 Node.js 脚本",
+            Lang::Ja => "Node.js スクリプトを実行します",
+        }
+    }
+
+    pub fn help_cmd_sh(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute a shell script with the configured shell",
+            Lang::Zh => "使用配置的 shell 执行脚本",
+            Lang::Ja => "設定されたシェルでスクリプトを実行します",
+        }
+    }
+
+    pub fn help_cmd_pip(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute pip (Python package manager)",
+            Lang::Zh => "执行 pip（Python 包管理器）",
+            Lang::Ja => "pip（Python パッケージマネージャー）を実行します",
+        }
+    }
+
+    pub fn help_cmd_npm(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute npm (Node.js package manager)",
+            Lang::Zh => "执行 npm（Node.js 包管理器）",
+            Lang::Ja => "npm（Node.js パッケージマネージャー）を実行します",
+        }
+    }
+
+    pub fn help_cmd_npx(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Execute npx (Node.js package runner)",
+            Lang::Zh => "执行 npx（Node.js 包运行器）",
+            Lang::Ja => "npx（Node.js パッケージランナー）を実行します",
+        }
+    }
+
+    pub fn help_cmd_config(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Manage configuration",
+            Lang::Zh => "管理配置",
+            Lang::Ja => "設定を管理します",
+        }
+    }
+
+    pub fn help_config_keys_heading(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Available keys and suggested values:",
+            Lang::Zh => "可配置项与建议值：",
+            Lang::Ja => "設定キーと推奨値:",
+        }
+    }
+
+    pub fn help_config_values_with_note(&self, values: &str, note: &str) -> String {
+        match self.lang {
+            Lang::En => format!("{values} ({note})"),
+            Lang::Zh => format!("{values}（{note}）"),
+            Lang::Ja => format!("{values}（{note}）"),
+        }
+    }
+
+    /// Description of a config key for the `config -h` table; empty when the
+    /// accepted values say it all.
+    pub fn config_key_description(&self, key: &str) -> &'static str {
+        match (self.lang, key) {
+            (Lang::En, "python") => "Python interpreter path (e.g., python3, /usr/bin/python3)",
+            (Lang::Zh, "python") => "Python 解释器路径（例：python3，/usr/bin/python3）",
+            (Lang::Ja, "python") => "Python インタプリタのパス（例: python3、/usr/bin/python3）",
+            (Lang::En, "node") => "Node.js interpreter path (e.g., node, /usr/local/bin/node)",
+            (Lang::Zh, "node") => "Node.js 解释器路径（例：node，/usr/local/bin/node）",
+            (Lang::Ja, "node") => "Node.js インタプリタのパス（例: node、/usr/local/bin/node）",
+            (Lang::En, "python_min_version") => {
+                "minimum Python version for py/pip (e.g., 3.10; empty = no check)"
+            }
+            (Lang::Zh, "python_min_version") => {
+                "py/pip 要求的最低 Python 版本（例：3.10；留空 = 不检查）"
+            }
+            (Lang::Ja, "python_min_version") => {
+                "py/pip に必要な Python の最低バージョン（例: 3.10。空 = チェックしない）"
+            }
+            (Lang::En, "node_min_version") => {
+                "minimum Node.js version for node (e.g., 20; empty = no check)"
+            }
+            (Lang::Zh, "node_min_version") => {
+                "node 要求的最低 Node.js 版本（例：20；留空 = 不检查）"
+            }
+            (Lang::Ja, "node_min_version") => {
+                "node に必要な Node.js の最低バージョン（例: 20。空 = チェックしない）"
+            }
+            (Lang::En, "history") => "record executed commands",
+            (Lang::Zh, "history") => "记录执行过的命令",
+            (Lang::Ja, "history") => "実行したコマンドを記録します",
+            (Lang::En, "history_sink") => {
+                "http POSTs each record to history_http_url, falling back to the file"
+            }
+            (Lang::Zh, "history_sink") => {
+                "http 将每条记录 POST 到 history_http_url，失败时写入文件"
+            }
+            (Lang::Ja, "history_sink") => {
+                "http にすると各記録を history_http_url に POST し、失敗時はファイルに書き込みます"
+            }
+            (Lang::En, "history_http_url") => "endpoint for history_sink = http",
+            (Lang::Zh, "history_http_url") => "history_sink = http 时的接收地址",
+            (Lang::Ja, "history_http_url") => "history_sink = http のときの送信先",
+            (Lang::En, "history_http_auth_header") => {
+                "extra header for that endpoint (e.g., Authorization: Bearer <token>)"
+            }
+            (Lang::Zh, "history_http_auth_header") => {
+                "发送时附加的请求头（例：Authorization: Bearer <token>）"
+            }
+            (Lang::Ja, "history_http_auth_header") => {
+                "送信時に付けるリクエストヘッダー（例: Authorization: Bearer <token>）"
+            }
+            (Lang::En, "history_max_size_mb") => {
+                "rotate history.jsonl to history.jsonl.1 at this size in MiB (0 = never)"
+            }
+            (Lang::Zh, "history_max_size_mb") => {
+                "history.jsonl 达到该大小（MiB）时轮转为 history.jsonl.1（0 = 不轮转）"
+            }
+            (Lang::Ja, "history_max_size_mb") => {
+                "history.jsonl がこのサイズ（MiB）に達したら history.jsonl.1 にローテーションします（0 = ローテーションしない）"
+            }
+            (Lang::En, "history_capture_bytes") => {
+                "bytes of stdout and of stderr kept per record with --capture"
+            }
+            (Lang::Zh, "history_capture_bytes") => {
+                "使用 --capture 时每条记录保留的 stdout/stderr 字节数"
+            }
+            (Lang::Ja, "history_capture_bytes") => {
+                "--capture 使用時に記録ごとに保持する stdout/stderr のバイト数"
+            }
+            (Lang::En, "history_redact") => {
+                "mask passwords, tokens and URL credentials before writing history"
+            }
+            (Lang::Zh, "history_redact") => "写入历史记录前遮蔽密码、令牌和 URL 凭据",
+            (Lang::Ja, "history_redact") => "履歴に書き込む前にパスワード、トークン、URL の認証情報を伏せます",
+            (Lang::En, "setup_confirm_download") => "ask before setup downloads pueue",
+            (Lang::Zh, "setup_confirm_download") => "setup 下载 pueue 前先确认",
+            (Lang::Ja, "setup_confirm_download") => "setup で pueue をダウンロードする前に確認します",
+            (Lang::En, "download_progress") => {
+                "progress bar for setup/update downloads on a terminal"
+            }
+            (Lang::Zh, "download_progress") => "在终端中为 setup/update 下载显示进度条",
+            (Lang::Ja, "download_progress") => "端末では setup/update のダウンロードにプログレスバーを表示します",
+            (Lang::En, "exec_prefix") => {
+                "wrapper placed before every run command (e.g., time -v; empty = off)"
+            }
+            (Lang::Zh, "exec_prefix") => {
+                "放在每条 run 命令前的包装程序（例：time -v；留空 = 关闭）"
+            }
+            (Lang::Ja, "exec_prefix") => {
+                "各 run コマンドの前に付けるラッパープログラム（例: time -v。空 = オフ）"
+            }
+            (Lang::En, "run_strict") => {
+                "nonzero exit of an execution command is an error unless --allow-nonzero"
+            }
+            (Lang::Zh, "run_strict") => "执行命令非零退出视为错误，除非传入 --allow-nonzero",
+            (Lang::Ja, "run_strict") => "--allow-nonzero を指定しない限り、0 以外の終了をエラーとして扱います",
+            (Lang::En, "run_confirm_each") => "ask before every run, as with run --confirm-each",
+            (Lang::Zh, "run_confirm_each") => "每次执行前先确认，等同于 run --confirm-each",
+            (Lang::Ja, "run_confirm_each") => "実行の前に毎回確認します（run --confirm-each と同じ）",
+            (Lang::En, "run_warn_shell_injection") => {
+                "warn when run passes a shell an inline script with $(...), backticks, eval or | sh"
+            }
+            (Lang::Zh, "run_warn_shell_injection") => {
+                "run 向 shell 传入含 $(...)、反引号、eval 或 | sh 的内联脚本时给出提示"
+            }
+            (Lang::Ja, "run_warn_shell_injection") => {
+                "run が $(...)、バッククォート、eval、| sh を含むインラインスクリプトをシェルに渡すときに警告します"
+            }
+            (Lang::En, "run_log_dir") => {
+                "directory for run --until logs (~/ = home, relative = under the data dir; empty = run-logs)"
+            }
+            (Lang::Zh, "run_log_dir") => {
+                "run --until 日志目录（~/ 为主目录，相对路径位于数据目录下；留空 = run-logs）"
+            }
+            (Lang::Ja, "run_log_dir") => {
+                "run --until のログディレクトリ（~/ はホーム、相対パスはデータディレクトリ基準。空 = run-logs）"
+            }
+            (Lang::En, "announce_to") => {
+                "where run --announce sends WHAT/WHY: notify or a file/pipe path (empty = notify)"
+            }
+            (Lang::Zh, "announce_to") => {
+                "run --announce 发送 WHAT/WHY 的位置：notify 或文件/管道路径（留空 = notify）"
+            }
+            (Lang::Ja, "announce_to") => {
+                "run --announce が WHAT/WHY を送る先: notify またはファイル/パイプのパス（空 = notify）"
+            }
+            (Lang::En, "github_proxy") => {
+                "proxy for GitHub downloads, GITHUB_PROXY overrides (empty = none)"
+            }
+            (Lang::Zh, "github_proxy") => "GitHub 下载代理，GITHUB_PROXY 优先（留空 = 不使用）",
+            (Lang::Ja, "github_proxy") => "GitHub ダウンロード用のプロキシ。GITHUB_PROXY が優先されます（空 = 使用しない）",
+            (Lang::En, "version_check_interval") => {
+                "how often to look for a new release after a command, e.g. 1d or 12h (off = never)"
+            }
+            (Lang::Zh, "version_check_interval") => {
+                "命令结束后检查新版本的间隔，如 1d 或 12h（off = 从不）"
+            }
+            (Lang::Ja, "version_check_interval") => {
+                "コマンド終了後に新しいバージョンを確認する間隔。1d や 12h など（off = 確認しない）"
+            }
+            (Lang::En, "checksum_algo") => "checksum update verifies downloads with: sha256, sha512",
+            (Lang::Zh, "checksum_algo") => "update 校验下载所用的校验和：sha256、sha512",
+            (Lang::Ja, "checksum_algo") => "update がダウンロードの検証に使うチェックサム: sha256、sha512",
+            (Lang::En, "output_prefix") => {
+                "line prefix for child output, supports {what} (empty = off)"
+            }
+            (Lang::Zh, "output_prefix") => "子进程输出的行前缀，支持 {what}（留空 = 关闭）",
+            (Lang::Ja, "output_prefix") => "子プロセスの出力行に付けるプレフィックス。{what} を使用可（空 = オフ）",
+            (Lang::En, "output_buffering") => {
+                "flushing of piped child output: prefix mode, run --tty"
+            }
+            (Lang::Zh, "output_buffering") => {
+                "shnote 转发子进程输出时的刷新方式：行前缀模式、run --tty"
+            }
+            (Lang::Ja, "output_buffering") => {
+                "shnote が子プロセスの出力を転送するときのフラッシュ方法: 行プレフィックスモード、run --tty"
+            }
+            (Lang::En, "output_summary") => {
+                "one-line exit summary after each command on a terminal"
+            }
+            (Lang::Zh, "output_summary") => "在终端中于每条命令结束后输出一行退出摘要",
+            (Lang::Ja, "output_summary") => "端末では各コマンドの終了後に 1 行の終了サマリーを表示します",
+            (Lang::En, "what_label") => "text of the WHAT header label (auto = display language)",
+            (Lang::Zh, "what_label") => "WHAT 头部标签文字（auto = 跟随显示语言）",
+            (Lang::Ja, "what_label") => "WHAT ヘッダーのラベル（auto = 表示言語に従う）",
+            (Lang::En, "why_label") => "text of the WHY header label (auto = display language)",
+            (Lang::Zh, "why_label") => "WHY 头部标签文字（auto = 跟随显示语言）",
+            (Lang::Ja, "why_label") => "WHY ヘッダーのラベル（auto = 表示言語に従う）",
+            (Lang::En, "expand_env") => {
+                "expand $VAR/${VAR} in config values: off, on (unset kept as is), strict (unset is an error)"
+            }
+            (Lang::Zh, "expand_env") => {
+                "展开配置值中的 $VAR/${VAR}：off、on（未设置的保持原样）、strict（未设置时报错）"
+            }
+            (Lang::Ja, "expand_env") => {
+                "設定値の $VAR/${VAR} を展開します: off、on（未設定はそのまま）、strict（未設定ならエラー）"
+            }
+            (Lang::En, "run_string_shell_mode") => "single-string run mode",
+            (Lang::Zh, "run_string_shell_mode") => "单字符串命令执行模式",
+            (Lang::Ja, "run_string_shell_mode") => "単一文字列コマンドの実行モード",
+            _ => "",
+        }
+    }
+
+    pub fn help_cmd_init(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Initialize shnote rules for AI tools",
+            Lang::Zh => "为 AI 工具初始化 shnote 规则",
+            Lang::Ja => "AI ツール向けに shnote ルールを初期化します",
+        }
+    }
+
+    pub fn help_cmd_setup(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Initialize environment (extract pueue binaries, etc.)",
+            Lang::Zh => "初始化环境（解压 pueue 二进制文件等）",
+            Lang::Ja => "環境を初期化します（pueue バイナリの展開など）",
+        }
+    }
+
+    pub fn help_cmd_doctor(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Check environment dependencies (python/node/pueue)",
+            Lang::Zh => "检查环境依赖（python/node/pueue）",
+            Lang::Ja => "環境の依存関係を確認します（python/node/pueue）",
+        }
+    }
+
+    pub fn help_cmd_completions(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Generate shell completion scripts",
+            Lang::Zh => "生成 shell 补全脚本",
+            Lang::Ja => "シェル補完スクリプトを生成します",
+        }
+    }
+
+    // Config subcommands
+    pub fn help_cmd_config_get(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Get a configuration value",
+            Lang::Zh => "获取配置值",
+            Lang::Ja => "設定値を取得します",
+        }
+    }
+
+    pub fn help_cmd_config_set(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Set a configuration value",
+            Lang::Zh => "设置配置值",
+            Lang::Ja => "設定値を設定します",
+        }
+    }
+
+    pub fn help_cmd_config_list(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "List all configuration values",
+            Lang::Zh => "列出所有配置值",
+            Lang::Ja => "すべての設定値を一覧表示します",
+        }
+    }
+
+    pub fn help_cmd_config_reset(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Reset configuration to defaults",
+            Lang::Zh => "重置配置为默认值",
+            Lang::Ja => "設定をデフォルトに戻します",
+        }
+    }
+
+    pub fn help_cmd_config_diff(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Show the keys whose values differ from the defaults",
+            Lang::Zh => "显示与默认值不同的配置项",
+            Lang::Ja => "デフォルトと異なる設定値を表示します",
+        }
+    }
+
+    pub fn help_cmd_config_path(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Show configuration file path",
+            Lang::Zh => "显示配置文件路径",
+            Lang::Ja => "設定ファイルのパスを表示します",
+        }
+    }
+
+    // Init subcommands
+    pub fn help_cmd_init_claude(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install shnote rules for Claude Code (>= 2.0.64: ~/.claude/rules/shnote.md; otherwise: ~/.claude/CLAUDE.md)",
+            Lang::Zh => "为 Claude Code 安装 shnote 规则（>= 2.0.64: ~/.claude/rules/shnote.md；否则: ~/.claude/CLAUDE.md）",
+            Lang::Ja => "Claude Code 用の shnote ルールをインストールします（>= 2.0.64: ~/.claude/rules/shnote.md、それ以外: ~/.claude/CLAUDE.md）",
+        }
+    }
+
+    pub fn help_cmd_init_codex(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules for Codex (~/.codex/AGENTS.md)",
+            Lang::Zh => "为 Codex 安装或更新 shnote 规则（~/.codex/AGENTS.md）",
+            Lang::Ja => {
+                "Codex 用の shnote ルールをインストールまたは更新します（~/.codex/AGENTS.md）"
+            }
+        }
+    }
+
+    pub fn help_cmd_init_copilot(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules for GitHub Copilot (.github/copilot-instructions.md, project scope only)",
+            Lang::Zh => "为 GitHub Copilot 安装或更新 shnote 规则（.github/copilot-instructions.md，仅限项目级）",
+            Lang::Ja => "GitHub Copilot 用の shnote ルールをインストールまたは更新します（.github/copilot-instructions.md、プロジェクトスコープのみ）",
+        }
+    }
+
+    pub fn help_cmd_init_cursor(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules for Cursor (.cursor/rules/shnote.mdc, project scope only)",
+            Lang::Zh => "为 Cursor 安装或更新 shnote 规则（.cursor/rules/shnote.mdc，仅限项目级）",
+            Lang::Ja => "Cursor 用の shnote ルールをインストールまたは更新します（.cursor/rules/shnote.mdc、プロジェクトスコープのみ）",
+        }
+    }
+
+    pub fn help_cmd_init_windsurf(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules for Windsurf (~/.codeium/windsurf/memories/global_rules.md)",
+            Lang::Zh => "为 Windsurf 安装或更新 shnote 规则（~/.codeium/windsurf/memories/global_rules.md）",
+            Lang::Ja => "Windsurf 用の shnote ルールをインストールまたは更新します（~/.codeium/windsurf/memories/global_rules.md）",
+        }
+    }
+
+    pub fn help_cmd_init_aider(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules for Aider (~/.aider/CONVENTIONS.md; project scope: ./CONVENTIONS.md)",
+            Lang::Zh => "为 Aider 安装或更新 shnote 规则（~/.aider/CONVENTIONS.md；项目级：./CONVENTIONS.md）",
+            Lang::Ja => "Aider 用の shnote ルールをインストールまたは更新します（~/.aider/CONVENTIONS.md、プロジェクト: ./CONVENTIONS.md）",
+        }
+    }
+
+    pub fn help_cmd_init_agents(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules in a plain AGENTS.md (project root, or ~/.config/AGENTS.md for the user)",
+            Lang::Zh => "在通用 AGENTS.md 中安装或更新 shnote 规则（项目根目录；用户级为 ~/.config/AGENTS.md）",
+            Lang::Ja => "汎用の AGENTS.md に shnote ルールをインストールまたは更新します（プロジェクトのルート。ユーザースコープは ~/.config/AGENTS.md）",
+        }
+    }
+
+    pub fn help_cmd_init_gemini(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install or update shnote rules for Gemini (~/.gemini/GEMINI.md)",
+            Lang::Zh => "为 Gemini 安装或更新 shnote 规则（~/.gemini/GEMINI.md）",
+            Lang::Ja => {
+                "Gemini 用の shnote ルールをインストールまたは更新します（~/.gemini/GEMINI.md）"
+            }
+        }
+    }
+
+    // Script args
+    pub fn help_arg_code(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Inline script code",
+            Lang::Zh => "内联脚本代码",
+            Lang::Ja => "インラインのスクリプトコード",
+        }
+    }
+
+    pub fn help_arg_file(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Script file path",
+            Lang::Zh => "脚本文件路径",
+            Lang::Ja => "スクリプトファイルのパス",
+        }
+    }
+
+    pub fn help_arg_stdin(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Read script from stdin (supports heredoc)",
+            Lang::Zh => "从 stdin 读取脚本（支持 heredoc）",
+            Lang::Ja => "stdin からスクリプトを読み込みます（heredoc 対応）",
+        }
+    }
+
+    pub fn help_arg_interpreter(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Interpreter to use for this run instead of the configured one (absolute path or name in PATH)",
+            Lang::Zh => "本次运行使用的解释器，覆盖配置中的路径（绝对路径或 PATH 中的名称）",
+            Lang::Ja => "今回の実行で使うインタプリタ。設定のパスより優先されます（絶対パスまたは PATH 上の名前）",
+        }
+    }
+
+    pub fn help_arg_script_args(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Arguments passed to the script",
+            Lang::Zh => "传递给脚本的参数",
+            Lang::Ja => "スクリプトに渡す引数",
+        }
+    }
+
+    // Run/passthrough args
+    pub fn help_arg_on_failure(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Shell command to run when the wrapped command exits nonzero",
+            Lang::Zh => "被包装命令以非零状态退出时执行的 shell 命令",
+            Lang::Ja => "ラップしたコマンドが 0 以外で終了したときに実行するシェルコマンド",
+        }
+    }
+
+    pub fn help_arg_on_success(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Shell command to run when the wrapped command succeeds",
+            Lang::Zh => "被包装命令成功时执行的 shell 命令",
+            Lang::Ja => "ラップしたコマンドが成功したときに実行するシェルコマンド",
+        }
+    }
+
+    pub fn help_arg_command(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Command and arguments to execute",
+            Lang::Zh => "要执行的命令和参数",
+            Lang::Ja => "実行するコマンドと引数",
+        }
+    }
+
+    pub fn help_arg_passthrough(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Arguments to pass through to the underlying command",
+            Lang::Zh => "传递给底层命令的参数",
+            Lang::Ja => "基になるコマンドに渡す引数",
+        }
+    }
+
+    // Config args
+    pub fn help_arg_config_key(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Configuration key (see `shnote config -h` for all keys/values)",
+            Lang::Zh => "配置键（完整列表见 `shnote config -h`）",
+            Lang::Ja => "設定キー（一覧は `shnote config -h` を参照）",
+        }
+    }
+
+    pub fn help_arg_config_key_short(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Configuration key (see `shnote config -h`)",
+            Lang::Zh => "配置键（详见 `shnote config -h`）",
+            Lang::Ja => "設定キー（`shnote config -h` を参照）",
+        }
+    }
+
+    pub fn help_arg_config_value(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Configuration value (see `shnote config -h` for valid values)",
+            Lang::Zh => "配置值（可用值见 `shnote config -h`）",
+            Lang::Ja => "設定値（使用できる値は `shnote config -h` を参照）",
+        }
+    }
+
+    pub fn help_arg_config_show_source(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Also show where the value comes from (default or user config file)",
+            Lang::Zh => "同时显示配置值的来源（默认值或用户配置文件）",
+            Lang::Ja => "値の出所（デフォルトまたはユーザー設定ファイル）も表示します",
+        }
+    }
+
+    pub fn help_arg_config_raw(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Write the stored value byte for byte, followed by a single newline",
+            Lang::Zh => "按原样逐字节输出存储的值，末尾仅追加一个换行",
+            Lang::Ja => {
+                "保存されている値をバイト単位でそのまま出力し、末尾に改行を 1 つだけ付けます"
+            }
+        }
+    }
+
+    pub fn help_arg_config_no_newline(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "With --raw, leave out the trailing newline",
+            Lang::Zh => "配合 --raw 使用，不输出末尾换行",
+            Lang::Ja => "--raw と併用し、末尾の改行を出力しません",
+        }
+    }
+
+    pub fn help_arg_config_list_format(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Output format: plain (key = value) or table (aligned columns)",
+            Lang::Zh => "输出格式：plain（key = value）或 table（按列对齐）",
+            Lang::Ja => "出力形式: plain（key = value）または table（列を揃える）",
+        }
+    }
+
+    pub fn help_arg_config_diff_json(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Print the differences as JSON",
+            Lang::Zh => "以 JSON 格式输出差异",
+            Lang::Ja => "差分を JSON で出力します",
+        }
+    }
+
+    pub fn help_arg_config_dry_run(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Only validate the value and show what would be stored; don't save",
+            Lang::Zh => "仅校验配置值并显示将要保存的值，不写入配置文件",
+            Lang::Ja => "値を検証して保存される値を表示するだけで、設定ファイルには書き込みません",
+        }
+    }
+
+    // Completions args
+    pub fn help_arg_shell(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Shell to generate completions for",
+            Lang::Zh => "要生成补全脚本的 shell",
+            Lang::Ja => "補完スクリプトを生成するシェル",
+        }
+    }
+
+    pub fn help_arg_completions_list(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "List the supported shells, one per line",
+            Lang::Zh => "列出支持的 shell（每行一个）",
+            Lang::Ja => "対応しているシェルを 1 行に 1 つずつ一覧表示します",
+        }
+    }
+
+    // === Info command messages ===
+
+    pub fn info_paths(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Paths",
+            Lang::Zh => "路径",
+            Lang::Ja => "パス",
+        }
+    }
+
+    pub fn info_install_path(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Install",
+            Lang::Zh => "安装位置",
+            Lang::Ja => "インストール先",
+        }
+    }
+
+    pub fn info_config_path(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Config",
+            Lang::Zh => "配置文件",
+            Lang::Ja => "設定ファイル",
+        }
+    }
+
+    pub fn info_data_path(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Data",
+            Lang::Zh => "数据目录",
+            Lang::Ja => "データディレクトリ",
+        }
+    }
+
+    pub fn info_components(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Components",
+            Lang::Zh => "组件",
+            Lang::Ja => "コンポーネント",
+        }
+    }
+
+    pub fn info_installed(&self) -> String {
+        let ok = self.marker(Marker::Ok);
+        match self.lang {
+            Lang::En => format!("{ok} installed"),
+            Lang::Zh => format!("{ok} 已安装"),
+            Lang::Ja => format!("{ok} インストール済み"),
+        }
+    }
+
+    pub fn info_not_installed(&self) -> String {
+        let fail = self.marker(Marker::Fail);
+        match self.lang {
+            Lang::En => format!("{fail} not installed"),
+            Lang::Zh => format!("{fail} 未安装"),
+            Lang::Ja => format!("{fail} 未インストール"),
+        }
+    }
+
+    pub fn info_run_setup(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "(run `shnote setup`)",
+            Lang::Zh => "（运行 `shnote setup`）",
+            Lang::Ja => "（`shnote setup` を実行してください）",
+        }
+    }
+
+    pub fn info_unknown(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "unknown",
+            Lang::Zh => "未知",
+            Lang::Ja => "不明",
+        }
+    }
+
+    // === Update command messages ===
+
+    pub fn update_checking(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Checking for updates...",
+            Lang::Zh => "正在检查更新...",
+            Lang::Ja => "更新を確認しています...",
+        }
+    }
+
+    pub fn update_current_version(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Current version",
+            Lang::Zh => "当前版本",
+            Lang::Ja => "現在のバージョン",
+        }
+    }
+
+    pub fn update_latest_version(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Latest version",
+            Lang::Zh => "最新版本",
+            Lang::Ja => "最新バージョン",
+        }
+    }
+
+    pub fn update_already_latest(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Already up to date!",
+            Lang::Zh => "已是最新版本！",
+            Lang::Ja => "すでに最新バージョンです！",
+        }
+    }
+
+    pub fn update_available(&self, version: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Update available: {}", version),
+            Lang::Zh => format!("可用更新：{}", version),
+            Lang::Ja => format!("更新があります: {}", version),
+        }
+    }
+
+    pub fn update_downloading(&self, version: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Downloading {}...", version),
+            Lang::Zh => format!("正在下载 {}...", version),
+            Lang::Ja => format!("{} をダウンロードしています...", version),
+        }
+    }
+
+    pub fn update_using_proxy(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Using proxy",
+            Lang::Zh => "使用代理",
+            Lang::Ja => "プロキシを使用",
+        }
+    }
+
+    pub fn update_verifying(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Verifying checksum...",
+            Lang::Zh => "正在校验...",
+            Lang::Ja => "検証しています...",
+        }
+    }
+
+    pub fn update_installing(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Installing...",
+            Lang::Zh => "正在安装...",
+            Lang::Ja => "インストールしています...",
+        }
+    }
+
+    pub fn update_success(&self, version: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Successfully updated to {}!", version),
+            Lang::Zh => format!("成功更新到 {}！", version),
+            Lang::Ja => format!("{} への更新に成功しました！", version),
+        }
+    }
+
+    pub fn update_rules_checking(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Checking existing shnote rules...",
+            Lang::Zh => "正在检查已有的 shnote 提示词...",
+            Lang::Ja => "既存の shnote ルールを確認しています...",
+        }
+    }
+
+    pub fn update_rules_outdated(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Outdated shnote rules detected: {}", path),
+            Lang::Zh => format!("检测到提示词版本落后：{}", path),
+            Lang::Ja => format!("古いルールが見つかりました: {}", path),
+        }
+    }
+
+    pub fn update_rules_modified(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Modified shnote rules detected: {}", path),
+            Lang::Zh => format!("检测到提示词包含修改：{}", path),
+            Lang::Ja => format!("変更されたルールが見つかりました: {}", path),
+        }
+    }
+
+    pub fn update_rules_diff_header(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Rules diff (bundled vs current): {}", path),
+            Lang::Zh => format!("提示词差异（内置规则 vs 当前文件）：{}", path),
+            Lang::Ja => format!("ルールの差分（内蔵ルール vs 現在のファイル）: {}", path),
+        }
+    }
+
+    pub fn update_rules_diff_base(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "bundled",
+            Lang::Zh => "内置规则",
+            Lang::Ja => "内蔵ルール",
+        }
+    }
+
+    pub fn update_rules_diff_current(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "current",
+            Lang::Zh => "当前文件",
+            Lang::Ja => "現在のファイル",
+        }
+    }
+
+    pub fn update_rules_confirm_update(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Update shnote rules now?",
+            Lang::Zh => "是否更新提示词？",
+            Lang::Ja => "ルールを更新しますか？",
+        }
+    }
+
+    pub fn update_rules_confirm_overwrite(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Overwrite with latest shnote rules?",
+            Lang::Zh => "是否覆盖为最新提示词？",
+            Lang::Ja => "最新のルールで上書きしますか？",
+        }
+    }
+
+    pub fn update_rules_skipped(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Skipped updating rules.",
+            Lang::Zh => "已跳过提示词更新。",
+            Lang::Ja => "ルールの更新をスキップしました。",
+        }
+    }
+
+    pub fn update_rules_err_init(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to update shnote rules",
+            Lang::Zh => "更新提示词失败",
+            Lang::Ja => "ルールの更新に失敗しました",
+        }
+    }
+
+    pub fn update_err_install_path(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to determine install path",
+            Lang::Zh => "无法确定安装路径",
+            Lang::Ja => "インストール先を特定できませんでした",
+        }
+    }
+
+    pub fn update_err_temp_dir(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to create temp directory",
+            Lang::Zh => "创建临时目录失败",
+            Lang::Ja => "一時ディレクトリの作成に失敗しました",
+        }
+    }
+
+    pub fn update_err_read_version(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to read release metadata",
+            Lang::Zh => "读取发布元数据失败",
+            Lang::Ja => "リリースのメタデータの読み込みに失敗しました",
+        }
+    }
+
+    pub fn update_err_parse_manifest(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to parse release manifest",
+            Lang::Zh => "解析发布清单失败",
+            Lang::Ja => "リリースマニフェストの解析に失敗しました",
+        }
+    }
+
+    pub fn update_err_downgrade(&self, current: &str, latest: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "refusing to downgrade from {current} to {latest} (the release source may be stale); pass --allow-downgrade to install it anyway"
+            ),
+            Lang::Zh => format!(
+                "拒绝从 {current} 降级到 {latest}（发布源可能已过期）；如确需安装请传入 --allow-downgrade"
+            ),
+            Lang::Ja => format!(
+                "{current} から {latest} へのダウングレードを拒否しました（リリース元が古い可能性があります）。本当にインストールする場合は --allow-downgrade を指定してください"
+            ),
+        }
+    }
+
+    pub fn update_notice(&self, current: &str, latest: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "shnote {latest} is available (you have {current}); run `shnote update` to upgrade"
+            ),
+            Lang::Zh => {
+                format!("shnote {latest} 已发布（当前 {current}）；运行 `shnote update` 升级")
+            }
+            Lang::Ja => {
+                format!("shnote {latest} が公開されています（現在 {current}）。`shnote update` で更新してください")
+            }
+        }
+    }
+
+    pub fn err_invalid_check_interval(&self, value: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "invalid version check interval: {value} (expected a number with s/m/h/d, e.g. 12h or 7d, or off)"
+            ),
+            Lang::Zh => format!(
+                "无效的版本检查间隔：{value}（应为带 s/m/h/d 单位的数字，如 12h 或 7d，或 off）"
+            ),
+            Lang::Ja => format!(
+                "無効なバージョン確認間隔: {value}（12h や 7d のように s/m/h/d の単位付きの数値、または off を指定してください）"
+            ),
+        }
+    }
+
+    pub fn update_err_platform_artifact(&self, platform: &str) -> String {
+        match self.lang {
+            Lang::En => format!("no release artifact available for platform: {platform}"),
+            Lang::Zh => format!("当前平台没有可用的发布产物：{platform}"),
+            Lang::Ja => format!("このプラットフォーム向けのリリース成果物がありません: {platform}"),
+        }
+    }
+
+    pub fn update_err_missing_checksum(&self, algo: &str, archive: &str) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "the release manifest has no {algo} checksum for {archive}; try --hash sha256"
+            ),
+            Lang::Zh => format!("发布清单中没有 {archive} 的 {algo} 校验和；可改用 --hash sha256"),
+            Lang::Ja => format!("リリースマニフェストに {archive} の {algo} チェックサムがありません。--hash sha256 を試してください"),
+        }
+    }
+
+    pub fn update_err_executable_asset(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to locate executable in release artifact",
+            Lang::Zh => "无法在发布产物中定位可执行文件",
+            Lang::Ja => "リリース成果物内に実行ファイルが見つかりませんでした",
+        }
+    }
+
+    pub fn update_err_extract_archive(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to extract release artifact",
+            Lang::Zh => "解压发布产物失败",
+            Lang::Ja => "リリース成果物の展開に失敗しました",
+        }
+    }
+
+    pub fn update_err_replace_binary(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to replace binary",
+            Lang::Zh => "替换二进制文件失败",
+            Lang::Ja => "バイナリの置き換えに失敗しました",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn update_err_rename_old(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to rename old binary",
+            Lang::Zh => "重命名旧二进制文件失败",
+            Lang::Ja => "古いバイナリの名前変更に失敗しました",
+        }
+    }
+
+    // === Uninstall command messages ===
+
+    pub fn uninstall_will_remove(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "The following will be removed:",
+            Lang::Zh => "以下内容将被删除：",
+            Lang::Ja => "次のものが削除されます:",
+        }
+    }
+
+    pub fn uninstall_config_data(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "config and data",
+            Lang::Zh => "配置和数据",
+            Lang::Ja => "設定とデータ",
+        }
+    }
+
+    pub fn uninstall_manual_removal(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "The following require manual removal:",
+            Lang::Zh => "以下内容需要手动删除：",
+            Lang::Ja => "次のものは手動で削除する必要があります:",
+        }
+    }
+
+    pub fn uninstall_path_entry(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "PATH entry in your shell config",
+            Lang::Zh => "shell 配置中的 PATH 条目",
+            Lang::Ja => "シェル設定内の PATH エントリ",
+        }
+    }
+
+    pub fn uninstall_ai_rules(&self) -> &'static str {
+        match self.lang {
+            Lang::En => {
+                "AI rules files (`shnote init --remove <target>` takes the rules out for you)"
+            }
+            Lang::Zh => "AI 规则文件（可用 `shnote init --remove <target>` 自动移除其中的规则）",
+            Lang::Ja => "AI ルールファイル（`shnote init --remove <target>` でルールを自動的に削除できます）",
+        }
+    }
+
+    pub fn uninstall_confirm(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Continue?",
+            Lang::Zh => "继续？",
+            Lang::Ja => "続行しますか？",
+        }
+    }
+
+    pub fn uninstall_cancelled(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Uninstall cancelled.",
+            Lang::Zh => "已取消卸载。",
+            Lang::Ja => "アンインストールを中止しました。",
+        }
+    }
+
+    pub fn uninstall_removing(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Removing",
+            Lang::Zh => "正在删除",
+            Lang::Ja => "削除しています",
+        }
+    }
+
+    pub fn uninstall_success(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "shnote has been uninstalled.",
+            Lang::Zh => "shnote 已卸载。",
+            Lang::Ja => "shnote をアンインストールしました。",
+        }
+    }
+
+    pub fn uninstall_manual_steps(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Please complete the manual removal steps above.",
+            Lang::Zh => "请完成上述手动删除步骤。",
+            Lang::Ja => "上記の手動削除を完了してください。",
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn uninstall_windows_note(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Note: The binary will be removed after restart",
+            Lang::Zh => "注意：二进制文件将在重启后删除",
+            Lang::Ja => "注意: バイナリは再起動後に削除されます",
+        }
+    }
+
+    pub fn uninstall_err_remove_data(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to remove data directory",
+            Lang::Zh => "删除数据目录失败",
+            Lang::Ja => "データディレクトリの削除に失敗しました",
+        }
+    }
+
+    pub fn uninstall_err_remove_binary(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "failed to remove binary",
+            Lang::Zh => "删除二进制文件失败",
+            Lang::Ja => "バイナリの削除に失敗しました",
+        }
+    }
+
+    // === Batch messages ===
+
+    pub fn batch_summary(
+        &self,
+        total: usize,
+        succeeded: usize,
+        failed: usize,
+        skipped: usize,
+    ) -> String {
+        match self.lang {
+            Lang::En => format!(
+                "batch: {total} steps, {succeeded} succeeded, {failed} failed, {skipped} skipped"
+            ),
+            Lang::Zh => {
+                format!("batch：共 {total} 步，成功 {succeeded}，失败 {failed}，跳过 {skipped}")
+            }
+            Lang::Ja => {
+                format!("batch: 全 {total} ステップ、成功 {succeeded}、失敗 {failed}、スキップ {skipped}")
+            }
+        }
+    }
+
+    pub fn batch_step_exit(&self, code: u8) -> String {
+        match self.lang {
+            Lang::En => format!("exit code {code}"),
+            Lang::Zh => format!("退出码 {code}"),
+            Lang::Ja => format!("終了コード {code}"),
+        }
+    }
+
+    pub fn batch_step_skipped(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "skipped",
+            Lang::Zh => "已跳过",
+            Lang::Ja => "スキップ",
+        }
+    }
+
+    pub fn err_batch_read(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("failed to read batch file {path}"),
+            Lang::Zh => format!("读取 batch 文件 {path} 失败"),
+            Lang::Ja => format!("batch ファイル {path} の読み込みに失敗しました"),
+        }
+    }
+
+    // === Prune messages ===
+
+    pub fn prune_removed(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Removed",
+            Lang::Zh => "已删除",
+            Lang::Ja => "削除しました",
+        }
+    }
+
+    pub fn prune_nothing(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Nothing to prune",
+            Lang::Zh => "没有需要清理的文件",
+            Lang::Ja => "削除するファイルはありません",
+        }
+    }
+
+    pub fn warn_prune_failed(&self, path: &str, err: &str) -> String {
+        match self.lang {
+            Lang::En => format!("warning: failed to remove {path}: {err}"),
+            Lang::Zh => format!("警告：删除 {path} 失败：{err}"),
+            Lang::Ja => format!("警告: {path} の削除に失敗しました: {err}"),
+        }
+    }
+
+    // === Resource limit messages ===
+
+    pub fn run_killed_cpu_limit(&self, secs: u64) -> String {
+        match self.lang {
+            Lang::En => format!("shnote: command killed: CPU time limit of {secs}s exceeded"),
+            Lang::Zh => format!("shnote：命令被终止：超出 {secs} 秒的 CPU 时间限制"),
+            Lang::Ja => {
+                format!("shnote: コマンドを強制終了しました: CPU 時間の上限 {secs} 秒を超えました")
+            }
+        }
+    }
+
+    pub fn run_killed_mem_limit(&self, mb: u64) -> String {
+        match self.lang {
+            Lang::En => format!("shnote: command killed: memory limit of {mb} MB exceeded"),
+            Lang::Zh => format!("shnote：命令被终止：超出 {mb} MB 的内存限制"),
+            Lang::Ja => {
+                format!("shnote: コマンドを強制終了しました: メモリの上限 {mb} MB を超えました")
+            }
+        }
+    }
+
+    pub fn run_failed_mem_limit_hint(&self, mb: u64) -> String {
+        match self.lang {
+            Lang::En => format!("shnote: command failed under a {mb} MB memory limit; it may have run out of memory"),
+            Lang::Zh => format!("shnote：命令在 {mb} MB 内存限制下失败，可能是内存不足"),
+            Lang::Ja => format!("shnote: コマンドがメモリ上限 {mb} MB の下で失敗しました。メモリ不足の可能性があります"),
+        }
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn warn_tty_unsupported(&self) -> &'static str {
+        match self.lang {
+            Lang::En => {
+                "warning: --tty is only supported on Unix; running without a pseudo-terminal"
+            }
+            Lang::Zh => "警告：--tty 仅支持 Unix，将不分配伪终端运行",
+            Lang::Ja => "警告: --tty は Unix でのみ使用できます。疑似端末なしで実行します",
+        }
+    }
+
+    pub fn help_arg_tty(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("! {tool} not found in PATH (rules will still be written)"),
-            Lang::Zh => format!("! 未在 PATH 中找到 {tool}（仍会写入规则）"),
+            Lang::En => {
+                "Run the command on a pseudo-terminal so it behaves interactively (Unix only)"
+            }
+            Lang::Zh => "在伪终端中运行命令，使其按交互模式工作（仅 Unix）",
+            Lang::Ja => "コマンドを疑似端末で実行し、対話モードで動作させます（Unix のみ）",
         }
     }
 
-    // === Help text translations (for clap runtime i18n) ===
+    pub fn help_arg_binary_safe(&self) -> &'static str {
+        match self.lang {
+            Lang::En => {
+                "Base64-encode the command's stdout, for binary output read by other programs"
+            }
+            Lang::Zh => "将命令的 stdout 以 base64 编码输出，便于其他程序读取二进制输出",
+            Lang::Ja => "コマンドの stdout を base64 で出力し、他のプログラムがバイナリ出力を読み取れるようにします",
+        }
+    }
 
-    // App level
-    pub fn help_app_about(&self) -> &'static str {
+    pub fn help_arg_measure(&self) -> &'static str {
         match self.lang {
-            Lang::En => "A lightweight command wrapper that enforces WHAT/WHY documentation",
-            Lang::Zh => "轻量级命令包装器，强制执行 WHAT/WHY 文档记录",
+            Lang::En => "Report the command's peak memory and CPU time on stderr when it finishes (Unix only)",
+            Lang::Zh => "命令结束后在 stderr 输出其峰值内存与 CPU 时间（仅 Unix）",
+            Lang::Ja => "コマンド終了後、ピークメモリと CPU 時間を stderr に出力します（Unix のみ）",
         }
     }
 
-    // Global arguments
-    pub fn help_arg_what(&self) -> &'static str {
+    pub fn help_arg_measure_output_size(&self) -> &'static str {
         match self.lang {
-            Lang::En => "What this task does (required for run/py/node/pip/npm/npx, must appear before subcommand)",
-            Lang::Zh => "这个任务做什么（run/py/node/pip/npm/npx 必需，必须在子命令之前）",
+            Lang::En => "Report how many bytes the command wrote to stdout and stderr on stderr when it finishes",
+            Lang::Zh => "命令结束后在 stderr 输出其写入 stdout 与 stderr 的字节数",
+            Lang::Ja => "コマンド終了後、stdout と stderr に書き込んだバイト数を stderr に出力します",
         }
     }
 
-    pub fn help_arg_why(&self) -> &'static str {
+    pub fn help_arg_print_env(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Why this task is being executed (required for run/py/node/pip/npm/npx, must appear before subcommand)",
-            Lang::Zh => "为什么执行这个任务（run/py/node/pip/npm/npx 必需，必须在子命令之前）",
+            Lang::En => "Print the environment the command will receive (secrets masked) to stderr before running it",
+            Lang::Zh => "运行前将命令将收到的环境变量（隐去密钥）输出到 stderr",
+            Lang::Ja => "実行前に、コマンドが受け取る環境変数（秘密情報は伏せる）を stderr に出力します",
         }
     }
 
-    pub fn help_arg_lang(&self) -> &'static str {
+    pub fn help_arg_args_from(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Language for messages (auto-detected by default)",
-            Lang::Zh => "消息语言（默认自动检测）",
+            Lang::En => {
+                "Append arguments read from a file, one per line ('...' or \"...\" keep whitespace)"
+            }
+            Lang::Zh => "从文件追加参数，每行一个（用 '...' 或 \"...\" 保留空白）",
+            Lang::Ja => "ファイルから引数を 1 行に 1 つずつ追加します（空白を保つには '...' または \"...\" で囲みます）",
         }
     }
 
-    pub fn help_arg_header_stream(&self) -> &'static str {
+    pub fn help_arg_no_tty(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Header output stream: auto|stdout|stderr",
-            Lang::Zh => "头信息输出流：auto|stdout|stderr",
+            Lang::En => "Give the command no stdin, so it cannot wait for input",
+            Lang::Zh => "不为命令提供 stdin，避免其等待输入",
+            Lang::Ja => "コマンドに stdin を与えず、入力待ちにならないようにします",
         }
     }
 
-    // Subcommands
-    pub fn help_cmd_run(&self) -> &'static str {
+    pub fn help_arg_announce(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Execute a shell command (passthrough)",
-            Lang::Zh => "执行 shell 命令（透传）",
+            Lang::En => {
+                "Also send WHAT/WHY to the announce_to channel (desktop notification or file)"
+            }
+            Lang::Zh => "同时将 WHAT/WHY 发送到 announce_to 指定的渠道（桌面通知或文件）",
+            Lang::Ja => {
+                "WHAT/WHY を announce_to のチャネル（デスクトップ通知またはファイル）にも送ります"
+            }
         }
     }
 
-    pub fn help_cmd_py(&self) -> &'static str {
+    pub fn help_arg_run_group(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Execute a Python script",
-            Lang::Zh => "执行 Python 脚本",
+            Lang::En => "Run `pueue add` in this pueue group (inserts `-g NAME`; the group must exist or is created on confirmation)",
+            Lang::Zh => "在该 pueue 分组中执行 `pueue add`（插入 `-g NAME`；分组须已存在，或经确认后创建）",
+            Lang::Ja => "`pueue add` をこの pueue グループで実行します（`-g NAME` を挿入。グループは既存であるか、確認後に作成されます）",
         }
     }
 
-    pub fn help_cmd_node(&self) -> &'static str {
+    pub fn help_arg_capture_on_failure_only(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Execute a Node.js script",
-            Lang::Zh => "执行 Node.js 脚本",
+            Lang::En => "With --log-file, keep the transcript only when the command exits nonzero (output held in memory, last 1 MiB)",
+            Lang::Zh => "配合 --log-file：仅在命令非零退出时保留记录（输出暂存于内存，保留最后 1 MiB）",
+            Lang::Ja => "--log-file と併用: コマンドが 0 以外で終了したときだけ記録を残します（出力はメモリに保持され、最後の 1 MiB を残します）",
         }
     }
 
-    pub fn help_cmd_pip(&self) -> &'static str {
+    pub fn help_arg_redirect_stdout(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Execute pip (Python package manager)",
-            Lang::Zh => "执行 pip（Python 包管理器）",
+            Lang::En => "Write the command's stdout straight to this file instead of the terminal (truncated, like `>`)",
+            Lang::Zh => "将命令的 stdout 直接写入该文件而不输出到终端（先清空，同 `>`）",
+            Lang::Ja => "コマンドの stdout を端末ではなくこのファイルに直接書き込みます（`>` と同様に先に空にします）",
         }
     }
 
-    pub fn help_cmd_npm(&self) -> &'static str {
+    pub fn help_arg_redirect_stderr(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Execute npm (Node.js package manager)",
-            Lang::Zh => "执行 npm（Node.js 包管理器）",
+            Lang::En => "Write the command's stderr straight to this file instead of the terminal (truncated, like `2>`)",
+            Lang::Zh => "将命令的 stderr 直接写入该文件而不输出到终端（先清空，同 `2>`）",
+            Lang::Ja => "コマンドの stderr を端末ではなくこのファイルに直接書き込みます（`2>` と同様に先に空にします）",
         }
     }
 
-    pub fn help_cmd_npx(&self) -> &'static str {
+    pub fn help_arg_print_pid(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Execute npx (Node.js package runner)",
-            Lang::Zh => "执行 npx（Node.js 包运行器）",
+            Lang::En => "Write the command's PID to this file as soon as it starts",
+            Lang::Zh => "命令启动后立即将其 PID 写入该文件",
+            Lang::Ja => "コマンドの起動直後にその PID をこのファイルに書き込みます",
         }
     }
 
-    pub fn help_cmd_config(&self) -> &'static str {
+    pub fn help_arg_pid_fd(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Manage configuration\n\nAvailable keys and suggested values:\n  python                - Python interpreter path (e.g., python3, /usr/bin/python3)\n  node                  - Node.js interpreter path (e.g., node, /usr/local/bin/node)\n  shell                 - auto|sh|bash|zsh|pwsh|cmd\n  language              - auto|zh|en\n  output                - default|quiet\n  header_stream         - auto|stdout|stderr\n  header_timing         - head|tail|both\n  run_string_shell_mode - lc|ilc (single-string run mode)\n  color                 - true|false\n  what_color            - default|black|red|green|yellow|blue|magenta|cyan|white|bright_black|bright_red|bright_green|bright_yellow|bright_blue|bright_magenta|bright_cyan|bright_white\n  why_color             - same as what_color",
-            Lang::Zh => "管理配置\n\n可配置项与建议值：\n  python                - Python 解释器路径（例：python3，/usr/bin/python3）\n  node                  - Node.js 解释器路径（例：node，/usr/local/bin/node）\n  shell                 - auto|sh|bash|zsh|pwsh|cmd\n  language              - auto|zh|en\n  output                - default|quiet\n  header_stream         - auto|stdout|stderr\n  header_timing         - head|tail|both\n  run_string_shell_mode - lc|ilc（单字符串命令执行模式）\n  color                 - true|false\n  what_color            - default|black|red|green|yellow|blue|magenta|cyan|white|bright_black|bright_red|bright_green|bright_yellow|bright_blue|bright_magenta|bright_cyan|bright_white\n  why_color             - 同 what_color",
+            Lang::En => "Write the command's PID to this open file descriptor as soon as it starts (Unix only)",
+            Lang::Zh => "命令启动后立即将其 PID 写入该已打开的文件描述符（仅 Unix）",
+            Lang::Ja => "コマンドの起動直後にその PID をこの開いているファイルディスクリプタに書き込みます（Unix のみ）",
         }
     }
 
-    pub fn help_cmd_init(&self) -> &'static str {
+    pub fn help_arg_redirect_append(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Initialize shnote rules for AI tools",
-            Lang::Zh => "为 AI 工具初始化 shnote 规则",
+            Lang::En => "With --redirect-stdout/--redirect-stderr, append to the files instead of truncating them (like `>>`)",
+            Lang::Zh => "配合 --redirect-stdout/--redirect-stderr，追加到文件末尾而不是清空（同 `>>`）",
+            Lang::Ja => "--redirect-stdout/--redirect-stderr と併用し、ファイルを空にせず末尾に追記します（`>>` と同様）",
         }
     }
 
-    pub fn help_cmd_setup(&self) -> &'static str {
+    pub fn help_arg_log_file(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Initialize environment (extract pueue binaries, etc.)",
-            Lang::Zh => "初始化环境（解压 pueue 二进制文件等）",
+            Lang::En => "Append a transcript of the run (WHAT/WHY, command, timestamped output, exit code) to this file",
+            Lang::Zh => "将本次运行的记录（WHAT/WHY、命令、带时间戳的输出、退出码）追加到该文件",
+            Lang::Ja => "この実行の記録（WHAT/WHY、コマンド、タイムスタンプ付きの出力、終了コード）をこのファイルに追記します",
         }
     }
 
-    pub fn help_cmd_doctor(&self) -> &'static str {
+    pub fn help_arg_confirm_each(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Check environment dependencies (python/node/pueue)",
-            Lang::Zh => "检查环境依赖（python/node/pueue）",
+            Lang::En => "Show the command with its WHAT/WHY and ask before running it (always on with run_confirm_each)",
+            Lang::Zh => "执行前显示命令及其 WHAT/WHY 并请求确认（启用 run_confirm_each 时总是确认）",
+            Lang::Ja => "実行前にコマンドとその WHAT/WHY を表示して確認を求めます（run_confirm_each が有効なら常に確認）",
         }
     }
 
-    pub fn help_cmd_completions(&self) -> &'static str {
+    pub fn help_arg_run_yes(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Generate shell completion scripts",
-            Lang::Zh => "生成 shell 补全脚本",
+            Lang::En => "Run without asking, even with --confirm-each or run_confirm_each",
+            Lang::Zh => "不询问直接执行，即使指定了 --confirm-each 或启用了 run_confirm_each",
+            Lang::Ja => "--confirm-each や run_confirm_each が有効でも確認せずに実行します",
         }
     }
 
-    // Config subcommands
-    pub fn help_cmd_config_get(&self) -> &'static str {
+    pub fn help_arg_run_raw(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Get a configuration value",
-            Lang::Zh => "获取配置值",
+            Lang::En => "Run the command as if typed directly: no header, output prefix, exec prefix or other run options; history is still recorded",
+            Lang::Zh => "如同直接执行命令：不输出头部、不加输出前缀或 exec 前缀，也不应用其他 run 选项；仍记录历史",
+            Lang::Ja => "コマンドを直接実行したかのように動作します: ヘッダー、出力プレフィックス、exec プレフィックス、その他の run オプションを適用しません。履歴は記録します",
         }
     }
 
-    pub fn help_cmd_config_set(&self) -> &'static str {
+    pub fn help_arg_stdin_tty_passthrough(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Set a configuration value",
-            Lang::Zh => "设置配置值",
+            Lang::En => "Leave the terminal to the command: header on stderr, no output prefix (automatic for REPLs on a terminal)",
+            Lang::Zh => "把终端完整交给命令：头部输出到 stderr，不加输出前缀（在终端中运行 REPL 时自动启用）",
+            Lang::Ja => "端末をそのままコマンドに渡します: ヘッダーは stderr に出力し、出力プレフィックスは付けません（端末で REPL を実行すると自動で有効）",
         }
     }
 
-    pub fn help_cmd_config_list(&self) -> &'static str {
+    #[cfg_attr(unix, allow(dead_code))]
+    pub fn warn_resource_limits_unsupported(&self) -> &'static str {
         match self.lang {
-            Lang::En => "List all configuration values",
-            Lang::Zh => "列出所有配置值",
+            Lang::En => "warning: --mem-limit/--cpu-limit are only supported on Unix; running without limits",
+            Lang::Zh => "警告：--mem-limit/--cpu-limit 仅支持 Unix，将不加限制地运行",
+            Lang::Ja => "警告: --mem-limit/--cpu-limit は Unix でのみ使用できます。制限なしで実行します",
         }
     }
 
-    pub fn help_cmd_config_reset(&self) -> &'static str {
+    pub fn run_measure_summary(&self, max_rss: &str, user: &str, system: &str) -> String {
         match self.lang {
-            Lang::En => "Reset configuration to defaults",
-            Lang::Zh => "重置配置为默认值",
+            Lang::En => format!("shnote: max RSS {max_rss}, CPU {user} user, {system} system"),
+            Lang::Zh => format!("shnote：峰值内存 {max_rss}，CPU 用户态 {user}，内核态 {system}"),
+            Lang::Ja => {
+                format!("shnote: ピークメモリ {max_rss}、CPU ユーザー {user}、システム {system}")
+            }
         }
     }
 
-    pub fn help_cmd_config_path(&self) -> &'static str {
+    pub fn run_output_size(&self, stdout_bytes: u64, stderr_bytes: u64) -> String {
         match self.lang {
-            Lang::En => "Show configuration file path",
-            Lang::Zh => "显示配置文件路径",
+            Lang::En => {
+                format!("shnote: output {stdout_bytes} bytes stdout, {stderr_bytes} bytes stderr")
+            }
+            Lang::Zh => {
+                format!("shnote：输出 stdout {stdout_bytes} 字节，stderr {stderr_bytes} 字节")
+            }
+            Lang::Ja => {
+                format!("shnote: 出力 stdout {stdout_bytes} バイト、stderr {stderr_bytes} バイト")
+            }
         }
     }
 
-    // Init subcommands
-    pub fn help_cmd_init_claude(&self) -> &'static str {
+    pub fn warn_measure_unsupported(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Install shnote rules for Claude Code (>= 2.0.64: ~/.claude/rules/shnote.md; otherwise: ~/.claude/CLAUDE.md)",
-            Lang::Zh => "为 Claude Code 安装 shnote 规则（>= 2.0.64: ~/.claude/rules/shnote.md；否则: ~/.claude/CLAUDE.md）",
+            Lang::En => "warning: --measure is only supported on Unix; no usage reported",
+            Lang::Zh => "警告：--measure 仅支持 Unix，不输出资源用量",
+            Lang::Ja => "警告: --measure は Unix でのみ使用できます。リソース使用量は出力しません",
         }
     }
 
-    pub fn help_cmd_init_codex(&self) -> &'static str {
+    // === WHAT/WHY header ===
+
+    pub fn header_what_label(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Install or update shnote rules for Codex (~/.codex/AGENTS.md)",
-            Lang::Zh => "为 Codex 安装或更新 shnote 规则（~/.codex/AGENTS.md）",
+            Lang::En => "WHAT",
+            Lang::Zh => "做什么",
+            Lang::Ja => "何を",
         }
     }
 
-    pub fn help_cmd_init_gemini(&self) -> &'static str {
+    pub fn header_why_label(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Install or update shnote rules for Gemini (~/.gemini/GEMINI.md)",
-            Lang::Zh => "为 Gemini 安装或更新 shnote 规则（~/.gemini/GEMINI.md）",
+            Lang::En => "WHY",
+            Lang::Zh => "为什么",
+            Lang::Ja => "なぜ",
         }
     }
 
-    // Script args
-    pub fn help_arg_code(&self) -> &'static str {
+    // === run --once messages ===
+
+    pub fn exit_summary(&self, what: &str, exit_code: &str, elapsed: &str) -> String {
         match self.lang {
-            Lang::En => "Inline script code",
-            Lang::Zh => "内联脚本代码",
+            Lang::En => format!("shnote: {what}: exit {exit_code} in {elapsed}"),
+            Lang::Zh => format!("shnote：{what}：退出码 {exit_code}，耗时 {elapsed}"),
+            Lang::Ja => format!("shnote: {what}: 終了コード {exit_code}、所要時間 {elapsed}"),
         }
     }
 
-    pub fn help_arg_file(&self) -> &'static str {
+    pub fn run_once_skipped(&self, label: &str) -> String {
         match self.lang {
-            Lang::En => "Script file path",
-            Lang::Zh => "脚本文件路径",
+            Lang::En => {
+                format!("shnote: skipped: \"{label}\" already succeeded (use --force to run again)")
+            }
+            Lang::Zh => {
+                format!("shnote：已跳过：\"{label}\" 之前已成功执行（使用 --force 重新执行）")
+            }
+            Lang::Ja => {
+                format!("shnote: スキップしました: \"{label}\" は以前に成功しています（再実行するには --force）")
+            }
         }
     }
 
-    pub fn help_arg_stdin(&self) -> &'static str {
+    pub fn warn_shell_injection(&self, risks: &str) -> String {
         match self.lang {
-            Lang::En => "Read script from stdin (supports heredoc)",
-            Lang::Zh => "从 stdin 读取脚本（支持 heredoc）",
+            Lang::En => format!(
+                "warning: the inline shell script uses {risks}, which runs text as code; check where that text comes from"
+            ),
+            Lang::Zh => format!("警告：内联 shell 脚本使用了 {risks}，会把文本当作代码执行；请确认其来源可信"),
+            Lang::Ja => format!("警告: インラインのシェルスクリプトが {risks} を使用しており、テキストをコードとして実行します。出所が信頼できることを確認してください"),
         }
     }
 
-    pub fn help_arg_script_args(&self) -> &'static str {
+    pub fn warn_once_record_failed(&self, detail: &str) -> String {
         match self.lang {
-            Lang::En => "Arguments passed to the script",
-            Lang::Zh => "传递给脚本的参数",
+            Lang::En => format!("warning: failed to record --once label: {detail}"),
+            Lang::Zh => format!("警告：记录 --once 标签失败：{detail}"),
+            Lang::Ja => format!("警告: --once ラベルの記録に失敗しました: {detail}"),
         }
     }
 
-    // Run/passthrough args
-    pub fn help_arg_command(&self) -> &'static str {
+    // === History command messages ===
+
+    pub fn err_invalid_history_since(&self, value: &str) -> String {
         match self.lang {
-            Lang::En => "Command and arguments to execute",
-            Lang::Zh => "要执行的命令和参数",
+            Lang::En => format!(
+                "invalid --since value: {value} (expected a duration such as 90m, 2h or 3d)"
+            ),
+            Lang::Zh => format!("无效的 --since 值：{value}（应为时长，如 90m、2h、3d）"),
+            Lang::Ja => format!(
+                "無効な --since の値: {value}（90m、2h、3d のような期間を指定してください）"
+            ),
         }
     }
 
-    pub fn help_arg_passthrough(&self) -> &'static str {
+    pub fn history_empty(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Arguments to pass through to the underlying command",
-            Lang::Zh => "传递给底层命令的参数",
+            Lang::En => "No history yet.",
+            Lang::Zh => "暂无历史记录。",
+            Lang::Ja => "履歴はまだありません。",
         }
     }
 
-    // Config args
-    pub fn help_arg_config_key(&self) -> &'static str {
+    pub fn warn_history_disabled(&self, detail: &str) -> String {
         match self.lang {
-            Lang::En => "Configuration key (see `shnote config -h` for all keys/values)",
-            Lang::Zh => "配置键（完整列表见 `shnote config -h`）",
+            Lang::En => {
+                format!("warning: failed to write history, disabled it for this run: {detail}")
+            }
+            Lang::Zh => format!("警告：写入历史记录失败，本次运行已停用历史记录：{detail}"),
+            Lang::Ja => format!(
+                "警告: 履歴の書き込みに失敗したため、この実行では履歴を無効にしました: {detail}"
+            ),
         }
     }
 
-    pub fn help_arg_config_key_short(&self) -> &'static str {
+    pub fn warn_history_http_failed(&self, detail: &str) -> String {
         match self.lang {
-            Lang::En => "Configuration key (see `shnote config -h`)",
-            Lang::Zh => "配置键（详见 `shnote config -h`）",
+            Lang::En => format!(
+                "warning: failed to send history record, kept it in the history file: {detail}"
+            ),
+            Lang::Zh => format!("警告：发送历史记录失败，已改为写入历史文件：{detail}"),
+            Lang::Ja => {
+                format!("警告: 履歴の送信に失敗したため、履歴ファイルに書き込みました: {detail}")
+            }
         }
     }
 
-    pub fn help_arg_config_value(&self) -> &'static str {
+    pub fn err_history_http_url_missing(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Configuration value (see `shnote config -h` for valid values)",
-            Lang::Zh => "配置值（可用值见 `shnote config -h`）",
+            Lang::En => "history_sink is http but history_http_url is empty",
+            Lang::Zh => "history_sink 为 http，但 history_http_url 为空",
+            Lang::Ja => "history_sink が http ですが、history_http_url が空です",
         }
     }
 
-    // Completions args
-    pub fn help_arg_shell(&self) -> &'static str {
+    pub fn err_history_http_failed(&self, code: &str) -> String {
         match self.lang {
-            Lang::En => "Shell to generate completions for",
-            Lang::Zh => "要生成补全脚本的 shell",
+            Lang::En => format!("curl exited with {code}"),
+            Lang::Zh => format!("curl 退出码为 {code}"),
+            Lang::Ja => format!("curl が終了コード {code} で終了しました"),
         }
     }
 
-    // === Info command messages ===
+    pub fn history_clear_confirm(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Delete command history at {path}?"),
+            Lang::Zh => format!("删除命令历史 {path}？"),
+            Lang::Ja => format!("コマンド履歴 {path} を削除しますか？"),
+        }
+    }
 
-    pub fn info_paths(&self) -> &'static str {
+    pub fn replay_confirm(&self, command: &str) -> String {
         match self.lang {
-            Lang::En => "Paths",
-            Lang::Zh => "路径",
+            Lang::En => format!("Replay destructive-looking command `{command}`?"),
+            Lang::Zh => format!("重新执行可能具有破坏性的命令 `{command}`？"),
+            Lang::Ja => format!("破壊的な可能性のあるコマンド `{command}` を再実行しますか？"),
         }
     }
 
-    pub fn info_install_path(&self) -> &'static str {
+    pub fn replay_cancelled(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Install",
-            Lang::Zh => "安装位置",
+            Lang::En => "replay cancelled",
+            Lang::Zh => "已取消重新执行",
+            Lang::Ja => "再実行を中止しました",
         }
     }
 
-    pub fn info_config_path(&self) -> &'static str {
+    pub fn err_replay_not_found(&self, id: usize, count: usize) -> String {
         match self.lang {
-            Lang::En => "Config",
-            Lang::Zh => "配置文件",
+            Lang::En => format!(
+                "no history entry #{id} (history has {count} entries; see `shnote history`)"
+            ),
+            Lang::Zh => format!("不存在历史记录 #{id}（共 {count} 条；见 `shnote history`）"),
+            Lang::Ja => {
+                format!("履歴 #{id} は存在しません（全 {count} 件。`shnote history` を参照）")
+            }
         }
     }
 
-    pub fn info_data_path(&self) -> &'static str {
+    pub fn err_replay_unknown_command(&self, command: &str) -> String {
         match self.lang {
-            Lang::En => "Data",
-            Lang::Zh => "数据目录",
+            Lang::En => format!("history entry has unknown command: {command}"),
+            Lang::Zh => format!("历史记录中的命令无法识别：{command}"),
+            Lang::Ja => format!("履歴のコマンドを認識できません: {command}"),
         }
     }
 
-    pub fn info_components(&self) -> &'static str {
+    pub fn err_replay_masked(&self, id: usize) -> String {
         match self.lang {
-            Lang::En => "Components",
-            Lang::Zh => "组件",
+            Lang::En => format!(
+                "history entry #{id} had credentials masked (history_redact), so it cannot be replayed; run the command again yourself"
+            ),
+            Lang::Zh => format!(
+                "历史记录 #{id} 中的凭据已被遮蔽（history_redact），无法重放；请自行重新执行该命令"
+            ),
+            Lang::Ja => format!(
+                "履歴 #{id} の認証情報は伏せられている（history_redact）ため再実行できません。コマンドを自分で再実行してください"
+            ),
         }
     }
 
-    pub fn info_installed(&self) -> &'static str {
+    pub fn err_replay_cwd(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => "✓ installed",
-            Lang::Zh => "✓ 已安装",
+            Lang::En => format!("recorded working directory is not accessible: {path}"),
+            Lang::Zh => format!("记录的工作目录无法访问：{path}"),
+            Lang::Ja => format!("記録された作業ディレクトリにアクセスできません: {path}"),
         }
     }
 
-    pub fn info_not_installed(&self) -> &'static str {
+    pub fn history_clear_cancelled(&self) -> &'static str {
         match self.lang {
-            Lang::En => "✗ not installed",
-            Lang::Zh => "✗ 未安装",
+            Lang::En => "History clear cancelled.",
+            Lang::Zh => "已取消清除历史。",
+            Lang::Ja => "履歴の削除を中止しました。",
         }
     }
 
-    pub fn info_run_setup(&self) -> &'static str {
+    pub fn history_clear_nothing(&self) -> &'static str {
         match self.lang {
-            Lang::En => "(run `shnote setup`)",
-            Lang::Zh => "（运行 `shnote setup`）",
+            Lang::En => "No history to clear.",
+            Lang::Zh => "没有需要清除的历史。",
+            Lang::Ja => "削除する履歴はありません。",
         }
     }
 
-    pub fn info_unknown(&self) -> &'static str {
+    pub fn history_cleared(&self, path: &str) -> String {
         match self.lang {
-            Lang::En => "unknown",
-            Lang::Zh => "未知",
+            Lang::En => format!("Cleared history: {path}"),
+            Lang::Zh => format!("已清除历史：{path}"),
+            Lang::Ja => format!("履歴を削除しました: {path}"),
         }
     }
 
-    // === Update command messages ===
+    pub fn history_archived(&self, path: &str) -> String {
+        match self.lang {
+            Lang::En => format!("Archived history to: {path}"),
+            Lang::Zh => format!("历史已归档到：{path}"),
+            Lang::Ja => format!("履歴をアーカイブしました: {path}"),
+        }
+    }
 
-    pub fn update_checking(&self) -> &'static str {
+    // === Help text for new commands ===
+
+    pub fn help_cmd_info(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Checking for updates...",
-            Lang::Zh => "正在检查更新...",
+            Lang::En => "Show installation information",
+            Lang::Zh => "显示安装信息",
+            Lang::Ja => "インストール情報を表示します",
         }
     }
 
-    pub fn update_current_version(&self) -> &'static str {
+    pub fn help_cmd_update(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Current version",
-            Lang::Zh => "当前版本",
+            Lang::En => "Update shnote to the latest version",
+            Lang::Zh => "更新 shnote 到最新版本",
+            Lang::Ja => "shnote を最新バージョンに更新します",
         }
     }
 
-    pub fn update_latest_version(&self) -> &'static str {
+    pub fn help_cmd_history(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Latest version",
-            Lang::Zh => "最新版本",
+            Lang::En => "Show or manage command history",
+            Lang::Zh => "查看或管理命令历史",
+            Lang::Ja => "コマンド履歴を表示または管理します",
+        }
+    }
+
+    pub fn help_cmd_replay(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Re-run a command from the history log",
+            Lang::Zh => "重新执行历史记录中的命令",
+            Lang::Ja => "履歴のコマンドを再実行します",
+        }
+    }
+
+    pub fn help_cmd_history_clear(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Delete the history file",
+            Lang::Zh => "删除历史文件",
+            Lang::Ja => "履歴ファイルを削除します",
+        }
+    }
+
+    pub fn help_cmd_history_export(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Write the history as CSV (all records matching --tag/--since)",
+            Lang::Zh => "以 CSV 导出历史（匹配 --tag/--since 的全部记录）",
+            Lang::Ja => "履歴を CSV でエクスポートします（--tag/--since に一致するすべての記録）",
+        }
+    }
+
+    pub fn help_cmd_batch(&self) -> &'static str {
+        match self.lang {
+            Lang::En => {
+                "Run shell commands from a file one after another and summarize the results"
+            }
+            Lang::Zh => "依次执行文件中的 shell 命令并汇总结果",
+            Lang::Ja => "ファイル内のシェルコマンドを順に実行し、結果をまとめます",
+        }
+    }
+
+    pub fn help_arg_batch_file(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "File with one shell command per line (\"-\" reads stdin); blank lines and # comments are skipped",
+            Lang::Zh => "每行一条 shell 命令的文件（\"-\" 表示从 stdin 读取）；跳过空行和 # 注释",
+            Lang::Ja => "1 行に 1 つのシェルコマンドを書いたファイル（\"-\" は stdin から読み込み）。空行と # コメントはスキップします",
+        }
+    }
+
+    pub fn help_arg_batch_fail_fast(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Stop at the first failing step (default)",
+            Lang::Zh => "遇到第一个失败的步骤即停止（默认）",
+            Lang::Ja => "最初に失敗したステップで停止します（デフォルト）",
+        }
+    }
+
+    pub fn help_arg_batch_keep_going(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Run every step even after a failure",
+            Lang::Zh => "即使有步骤失败也继续执行所有步骤",
+            Lang::Ja => "失敗したステップがあってもすべてのステップを実行します",
+        }
+    }
+
+    pub fn help_arg_batch_parallel(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Run up to N steps at a time; each step's output is shown in step order once it finishes",
+            Lang::Zh => "最多同时执行 N 个步骤；各步骤的输出在其结束后按步骤顺序显示",
+            Lang::Ja => "最大 N 個のステップを同時に実行します。各ステップの出力は終了後にステップ順で表示されます",
+        }
+    }
+
+    pub fn help_arg_batch_json(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Print the summary as JSON",
+            Lang::Zh => "以 JSON 格式输出汇总",
+            Lang::Ja => "サマリーを JSON で出力します",
+        }
+    }
+
+    pub fn help_cmd_test_rules(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Check that the built-in rules render for every target and language (for development)",
+            Lang::Zh => "检查内置规则能否为每个目标和语言正确渲染（开发用）",
+            Lang::Ja => "内蔵ルールがすべてのターゲットと言語で正しくレンダリングされるか確認します（開発用）",
+        }
+    }
+
+    pub fn help_cmd_prune(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Remove leftover backups and downloads from earlier updates",
+            Lang::Zh => "清理历次更新遗留的备份文件和下载目录",
+            Lang::Ja => {
+                "過去の更新で残ったバックアップファイルとダウンロードディレクトリを削除します"
+            }
+        }
+    }
+
+    pub fn help_cmd_uninstall(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Uninstall shnote",
+            Lang::Zh => "卸载 shnote",
+            Lang::Ja => "shnote をアンインストールします",
+        }
+    }
+
+    pub fn help_arg_update_check(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Only check for updates, don't install",
+            Lang::Zh => "仅检查更新，不安装",
+            Lang::Ja => "更新の確認だけを行い、インストールしません",
+        }
+    }
+
+    pub fn help_arg_update_force(&self) -> &'static str {
+        match self.lang {
+            Lang::En => "Force update even if already up to date",
+            Lang::Zh => "即使已是最新版本也强制更新",
+            Lang::Ja => "すでに最新でも強制的に更新します",
         }
     }
 
-    pub fn update_already_latest(&self) -> &'static str {
+    pub fn help_arg_update_allow_downgrade(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Already up to date!",
-            Lang::Zh => "已是最新版本！",
+            Lang::En => "Install the latest release even if it is older than this version",
+            Lang::Zh => "即使最新发布版本低于当前版本也安装",
+            Lang::Ja => "最新のリリースが現在のバージョンより古くてもインストールします",
         }
     }
 
-    pub fn update_available(&self, version: &str) -> String {
+    pub fn help_arg_update_hash(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("Update available: {}", version),
-            Lang::Zh => format!("可用更新：{}", version),
+            Lang::En => {
+                "Checksum from the release manifest to verify the download against (default: network.checksum_algo)"
+            }
+            Lang::Zh => "用发布清单中的哪种校验和验证下载（默认取 network.checksum_algo）",
+            Lang::Ja => "ダウンロードの検証に使うリリースマニフェストのチェックサム（デフォルトは network.checksum_algo）",
         }
     }
 
-    pub fn update_downloading(&self, version: &str) -> String {
+    pub fn help_arg_init_rules_lang(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("Downloading {}...", version),
-            Lang::Zh => format!("正在下载 {}...", version),
+            Lang::En => "Language of the installed rules (default: the message language)",
+            Lang::Zh => "写入规则所用的语言（默认与界面语言一致）",
+            Lang::Ja => "書き込むルールの言語（デフォルトは表示言語と同じ）",
         }
     }
 
-    pub fn update_using_proxy(&self) -> &'static str {
+    pub fn help_arg_init_set_var(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Using proxy",
-            Lang::Zh => "使用代理",
+            Lang::En => "Replace {{KEY}} in the rules with VALUE (repeatable); EXTRA_RULES adds a paragraph after the tool list",
+            Lang::Zh => "将规则中的 {{KEY}} 替换为 VALUE（可重复）；EXTRA_RULES 会在工具列表后追加一段",
+            Lang::Ja => "ルール内の {{KEY}} を VALUE に置き換えます（繰り返し可）。EXTRA_RULES はツール一覧の後に段落を追加します",
         }
     }
 
-    pub fn update_verifying(&self) -> &'static str {
+    pub fn err_invalid_rules_var(&self, spec: &str) -> String {
         match self.lang {
-            Lang::En => "Verifying checksum...",
-            Lang::Zh => "正在校验...",
+            Lang::En => format!(
+                "invalid --set-var: {spec} (expected KEY=VALUE, KEY made of letters, digits and _)"
+            ),
+            Lang::Zh => {
+                format!("无效的 --set-var：{spec}（应为 KEY=VALUE，KEY 仅含字母、数字和 _）")
+            }
+            Lang::Ja => {
+                format!("無効な --set-var: {spec}（KEY=VALUE の形式で、KEY は英数字と _ のみ）")
+            }
         }
     }
 
-    pub fn update_installing(&self) -> &'static str {
+    pub fn warn_rules_placeholder_unset(&self, placeholder: &str) -> String {
         match self.lang {
-            Lang::En => "Installing...",
-            Lang::Zh => "正在安装...",
+            Lang::En => format!(
+                "warning: {placeholder} in the rules has no value; pass --set-var to fill it in"
+            ),
+            Lang::Zh => format!("警告：规则中的 {placeholder} 没有对应的值；可用 --set-var 填写"),
+            Lang::Ja => {
+                format!("警告: ルール内の {placeholder} に値がありません。--set-var で指定できます")
+            }
         }
     }
 
-    pub fn update_success(&self, version: &str) -> String {
+    pub fn help_arg_init_no_pueue(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("Successfully updated to {}!", version),
-            Lang::Zh => format!("成功更新到 {}！", version),
+            Lang::En => "Leave the pueue section out of the rules (the default)",
+            Lang::Zh => "规则中不包含 pueue 章节（默认）",
+            Lang::Ja => "ルールに pueue のセクションを含めません（デフォルト）",
         }
     }
 
-    pub fn update_rules_checking(&self) -> &'static str {
+    pub fn help_arg_init_backup(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Checking existing shnote rules...",
-            Lang::Zh => "正在检查已有的 shnote 提示词...",
+            Lang::En => "Copy an existing file to <file>.shnote.bak before changing it",
+            Lang::Zh => "修改已有文件前将其复制为 <file>.shnote.bak",
+            Lang::Ja => "既存のファイルを変更する前に <file>.shnote.bak にコピーします",
         }
     }
 
-    pub fn update_rules_outdated(&self, path: &str) -> String {
+    pub fn help_arg_init_list_targets(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("Outdated shnote rules detected: {}", path),
-            Lang::Zh => format!("检测到提示词版本落后：{}", path),
+            Lang::En => "List the supported targets, where each writes its rules, and whether the tool is installed",
+            Lang::Zh => "列出支持的目标、各自写入规则的位置，以及对应工具是否已安装",
+            Lang::Ja => "対応しているターゲット、ルールの書き込み先、対応ツールがインストールされているかを一覧表示します",
         }
     }
 
-    pub fn update_rules_modified(&self, path: &str) -> String {
+    pub fn help_arg_init_force_pueue(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("Modified shnote rules detected: {}", path),
-            Lang::Zh => format!("检测到提示词包含修改：{}", path),
+            Lang::En => "Add a section on running long commands in the background with pueue",
+            Lang::Zh => "在规则中加入使用 pueue 后台运行长时间命令的章节",
+            Lang::Ja => "長時間実行するコマンドを pueue でバックグラウンド実行するセクションをルールに含めます",
         }
     }
 
-    pub fn update_rules_diff_header(&self, path: &str) -> String {
+    pub fn help_arg_init_dry_run(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("Rules diff (bundled vs current): {}", path),
-            Lang::Zh => format!("提示词差异（内置规则 vs 当前文件）：{}", path),
+            Lang::En => "Show which files would be created or changed without writing anything",
+            Lang::Zh => "仅显示将创建或修改哪些文件，不实际写入",
+            Lang::Ja => "作成または変更されるファイルを表示するだけで、書き込みません",
         }
     }
 
-    pub fn update_rules_diff_base(&self) -> &'static str {
+    pub fn help_arg_doctor_check_updates(&self) -> &'static str {
         match self.lang {
-            Lang::En => "bundled",
-            Lang::Zh => "内置规则",
+            Lang::En => "Also check whether a newer shnote release exists (uses the network)",
+            Lang::Zh => "同时检查是否有更新的 shnote 版本（需要联网）",
+            Lang::Ja => "新しい shnote のバージョンがあるかも確認します（ネットワークが必要）",
         }
     }
 
-    pub fn update_rules_diff_current(&self) -> &'static str {
+    pub fn help_arg_doctor_check_history(&self) -> &'static str {
         match self.lang {
-            Lang::En => "current",
-            Lang::Zh => "当前文件",
+            Lang::En => "Also check that the history log is writable and parseable",
+            Lang::Zh => "同时检查历史记录是否可写、可解析",
+            Lang::Ja => "履歴が書き込み可能で解析できるかも確認します",
         }
     }
 
-    pub fn update_rules_confirm_update(&self) -> &'static str {
+    pub fn help_arg_capture(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Update shnote rules now?",
-            Lang::Zh => "是否更新提示词？",
+            Lang::En => "Keep the end of the command's stdout/stderr in its history record (see history_capture_bytes)",
+            Lang::Zh => "在历史记录中保存命令 stdout/stderr 的末尾部分（长度见 history_capture_bytes）",
+            Lang::Ja => "コマンドの stdout/stderr の末尾を履歴に保存します（長さは history_capture_bytes を参照）",
         }
     }
 
-    pub fn update_rules_confirm_overwrite(&self) -> &'static str {
+    pub fn help_arg_offline(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Overwrite with latest shnote rules?",
-            Lang::Zh => "是否覆盖为最新提示词？",
+            Lang::En => {
+                "Never use the network (skips doctor --check-updates and the new-version notice)"
+            }
+            Lang::Zh => "不使用网络（跳过 doctor --check-updates 与新版本提示）",
+            Lang::Ja => "ネットワークを使用しません（doctor --check-updates と新バージョンの通知をスキップ）",
         }
     }
 
-    pub fn update_rules_skipped(&self) -> &'static str {
+    pub fn help_arg_version_check_interval(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Skipped updating rules.",
-            Lang::Zh => "已跳过提示词更新。",
+            Lang::En => "How often to look for a new shnote release after a command, e.g. 12h or 7d; \"off\" disables [default: config version_check_interval]",
+            Lang::Zh => "命令结束后检查 shnote 新版本的间隔，如 12h 或 7d；\"off\" 表示关闭 [默认：配置项 version_check_interval]",
+            Lang::Ja => "コマンド終了後に新しい shnote を確認する間隔。12h や 7d など。\"off\" で無効 [デフォルト: 設定キー version_check_interval]",
         }
     }
 
-    pub fn update_rules_err_init(&self) -> &'static str {
+    pub fn help_arg_doctor_check(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to update shnote rules",
-            Lang::Zh => "更新提示词失败",
+            Lang::En => "Also check an AI tool: whether it is installed and its shnote rules are current (repeatable)",
+            Lang::Zh => "同时检查 AI 工具：是否已安装、shnote 规则是否为最新（可重复）",
+            Lang::Ja => "AI ツールも確認します: インストールされているか、shnote ルールが最新か（繰り返し可）",
         }
     }
 
-    pub fn update_err_install_path(&self) -> &'static str {
+    pub fn help_arg_doctor_check_rules_drift(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to determine install path",
-            Lang::Zh => "无法确定安装路径",
+            Lang::En => "Only check that installed shnote rules match this version (for CI); fails if any drifted",
+            Lang::Zh => "仅检查已安装的 shnote 规则是否与当前版本一致（适用于 CI）；有差异时失败",
+            Lang::Ja => "インストール済みの shnote ルールがこのバージョンと一致するかだけを確認します（CI 向け）。差異があれば失敗します",
         }
     }
 
-    pub fn update_err_temp_dir(&self) -> &'static str {
+    pub fn help_arg_doctor_json(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to create temp directory",
-            Lang::Zh => "创建临时目录失败",
+            Lang::En => "Print the results as JSON",
+            Lang::Zh => "以 JSON 格式输出检查结果",
+            Lang::Ja => "結果を JSON で出力します",
         }
     }
 
-    pub fn update_err_read_version(&self) -> &'static str {
+    pub fn help_arg_doctor_check_locale(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to read release metadata",
-            Lang::Zh => "读取发布元数据失败",
+            Lang::En => "Report how the message language was detected",
+            Lang::Zh => "报告消息语言的检测来源",
+            Lang::Ja => "メッセージ言語がどこから検出されたかを報告します",
         }
     }
 
-    pub fn update_err_parse_manifest(&self) -> &'static str {
+    pub fn help_arg_cwd(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to parse release manifest",
-            Lang::Zh => "解析发布清单失败",
+            Lang::En => "Directory to run the command in",
+            Lang::Zh => "执行命令时使用的工作目录",
+            Lang::Ja => "コマンドを実行する作業ディレクトリ",
         }
     }
 
-    pub fn update_err_platform_artifact(&self, platform: &str) -> String {
+    pub fn help_arg_until(&self) -> &'static str {
         match self.lang {
-            Lang::En => format!("no release artifact available for platform: {platform}"),
-            Lang::Zh => format!("当前平台没有可用的发布产物：{platform}"),
+            Lang::En => "Wait until the command's output contains this text, then leave it running in the background",
+            Lang::Zh => "等待命令输出中出现该文本后，让命令留在后台继续运行",
+            Lang::Ja => "コマンドの出力にこのテキストが現れるまで待ち、その後コマンドをバックグラウンドで実行させたままにします",
         }
     }
 
-    pub fn update_err_executable_asset(&self) -> &'static str {
+    pub fn help_arg_until_timeout(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to locate executable in release artifact",
-            Lang::Zh => "无法在发布产物中定位可执行文件",
+            Lang::En => "With --until, stop the command and fail if the text has not appeared after this many seconds [default: 60]",
+            Lang::Zh => "配合 --until：超过该秒数仍未出现文本时终止命令并报错［默认：60］",
+            Lang::Ja => "--until と併用: この秒数が経ってもテキストが現れなければコマンドを停止してエラーにします [デフォルト: 60]",
         }
     }
 
-    pub fn update_err_extract_archive(&self) -> &'static str {
+    pub fn help_arg_chdir_to_git_root(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to extract release artifact",
-            Lang::Zh => "解压发布产物失败",
+            Lang::En => "Run the command in the root of the enclosing git repository",
+            Lang::Zh => "在所在 git 仓库的根目录中执行命令",
+            Lang::Ja => "所属する git リポジトリのルートでコマンドを実行します",
         }
     }
 
-    pub fn update_err_replace_binary(&self) -> &'static str {
+    pub fn help_arg_no_cwd_inherit(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to replace binary",
-            Lang::Zh => "替换二进制文件失败",
+            Lang::En => "Run the command in a fresh temporary directory, removed afterwards",
+            Lang::Zh => "在新建的临时目录中执行命令，结束后删除该目录",
+            Lang::Ja => {
+                "新しい一時ディレクトリでコマンドを実行し、終了後にそのディレクトリを削除します"
+            }
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn update_err_rename_old(&self) -> &'static str {
+    pub fn help_arg_exec_prefix(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to rename old binary",
-            Lang::Zh => "重命名旧二进制文件失败",
+            Lang::En => "Wrapper program placed before the command, e.g. \"time -v\" (overrides config; \"\" disables)",
+            Lang::Zh => "放在命令前的包装程序，如 \"time -v\"（覆盖配置；\"\" 表示禁用）",
+            Lang::Ja => "コマンドの前に付けるラッパープログラム。\"time -v\" など（設定より優先。\"\" で無効）",
         }
     }
 
-    // === Uninstall command messages ===
-
-    pub fn uninstall_will_remove(&self) -> &'static str {
+    pub fn help_arg_mem_limit(&self) -> &'static str {
         match self.lang {
-            Lang::En => "The following will be removed:",
-            Lang::Zh => "以下内容将被删除：",
+            Lang::En => "Limit the command's address space to this many MiB (Unix only)",
+            Lang::Zh => "将命令的地址空间限制为指定 MiB（仅 Unix）",
+            Lang::Ja => "コマンドのアドレス空間をこの MiB 数に制限します（Unix のみ）",
         }
     }
 
-    pub fn uninstall_config_data(&self) -> &'static str {
+    pub fn help_arg_cpu_limit(&self) -> &'static str {
         match self.lang {
-            Lang::En => "config and data",
-            Lang::Zh => "配置和数据",
+            Lang::En => "Limit the command's CPU time to this many seconds (Unix only)",
+            Lang::Zh => "将命令的 CPU 时间限制为指定秒数（仅 Unix）",
+            Lang::Ja => "コマンドの CPU 時間をこの秒数に制限します（Unix のみ）",
         }
     }
 
-    pub fn uninstall_manual_removal(&self) -> &'static str {
+    pub fn help_arg_once(&self) -> &'static str {
         match self.lang {
-            Lang::En => "The following require manual removal:",
-            Lang::Zh => "以下内容需要手动删除：",
+            Lang::En => "Skip the command if the run labelled by --label already succeeded",
+            Lang::Zh => "若 --label 标记的命令之前已成功执行，则跳过",
+            Lang::Ja => "--label のコマンドが以前に成功していればスキップします",
         }
     }
 
-    pub fn uninstall_path_entry(&self) -> &'static str {
+    pub fn help_arg_label(&self) -> &'static str {
         match self.lang {
-            Lang::En => "PATH entry in your shell config",
-            Lang::Zh => "shell 配置中的 PATH 条目",
+            Lang::En => "Key identifying the command for --once",
+            Lang::Zh => "供 --once 使用的命令标识",
+            Lang::Ja => "--once で使うコマンドの識別子",
         }
     }
 
-    pub fn uninstall_ai_rules(&self) -> &'static str {
+    pub fn help_arg_once_force(&self) -> &'static str {
         match self.lang {
-            Lang::En => "AI rules files",
-            Lang::Zh => "AI 规则文件",
+            Lang::En => "With --once, run again even if the label already succeeded",
+            Lang::Zh => "配合 --once 使用：即使该标签已成功也重新执行",
+            Lang::Ja => "--once と併用: ラベルが成功済みでも再実行します",
         }
     }
 
-    pub fn uninstall_confirm(&self) -> &'static str {
+    pub fn help_arg_exit_on(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Continue?",
-            Lang::Zh => "继续？",
+            Lang::En => {
+                "Map child exit codes to another exit code, e.g. \"1=>0\" or \"0,1=>0\" (repeatable)"
+            }
+            Lang::Zh => "将子进程退出码映射为其他退出码，例如 \"1=>0\" 或 \"0,1=>0\"（可重复）",
+            Lang::Ja => "子プロセスの終了コードを別のコードに変換します。\"1=>0\" や \"0,1=>0\" など（繰り返し可）",
         }
     }
 
-    pub fn uninstall_cancelled(&self) -> &'static str {
+    pub fn help_arg_note_tag(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Uninstall cancelled.",
-            Lang::Zh => "已取消卸载。",
+            Lang::En => "Label stored with the history record to group related commands",
+            Lang::Zh => "写入历史记录的标签，用于归组相关命令",
+            Lang::Ja => "関連するコマンドをまとめるために履歴に書き込むタグ",
         }
     }
 
-    pub fn uninstall_removing(&self) -> &'static str {
+    pub fn help_arg_history_tag(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Removing",
-            Lang::Zh => "正在删除",
+            Lang::En => "Only show commands recorded with this --note-tag",
+            Lang::Zh => "仅显示带有该 --note-tag 的命令",
+            Lang::Ja => "この --note-tag を持つコマンドだけを表示します",
         }
     }
 
-    pub fn uninstall_success(&self) -> &'static str {
+    pub fn help_arg_history_since(&self) -> &'static str {
         match self.lang {
-            Lang::En => "shnote has been uninstalled.",
-            Lang::Zh => "shnote 已卸载。",
+            Lang::En => "Only show commands run within this long ago, e.g. 2h or 3d",
+            Lang::Zh => "仅显示该时长内执行的命令，如 2h、3d",
+            Lang::Ja => "この期間内に実行したコマンドだけを表示します。2h、3d など",
         }
     }
 
-    pub fn uninstall_manual_steps(&self) -> &'static str {
+    pub fn help_arg_history_limit(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Please complete the manual removal steps above.",
-            Lang::Zh => "请完成上述手动删除步骤。",
+            Lang::En => "Show at most this many of the most recent commands",
+            Lang::Zh => "最多显示最近的这么多条命令",
+            Lang::Ja => "最新のコマンドを最大この件数まで表示します",
         }
     }
 
-    #[cfg_attr(unix, allow(dead_code))]
-    pub fn uninstall_windows_note(&self) -> &'static str {
+    pub fn help_arg_history_json(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Note: The binary will be removed after restart",
-            Lang::Zh => "注意：二进制文件将在重启后删除",
+            Lang::En => "Print the records as JSON, with a schema_version",
+            Lang::Zh => "以 JSON 输出记录（含 schema_version）",
+            Lang::Ja => "記録を JSON で出力します（schema_version 付き）",
         }
     }
 
-    pub fn uninstall_err_remove_data(&self) -> &'static str {
+    pub fn help_arg_replay_id(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to remove data directory",
-            Lang::Zh => "删除数据目录失败",
+            Lang::En => "Number of the history entry, as shown by `shnote history`",
+            Lang::Zh => "历史记录编号（见 `shnote history`）",
+            Lang::Ja => "履歴の番号（`shnote history` を参照）",
         }
     }
 
-    pub fn uninstall_err_remove_binary(&self) -> &'static str {
+    pub fn help_arg_replay_reuse_note(&self) -> &'static str {
         match self.lang {
-            Lang::En => "failed to remove binary",
-            Lang::Zh => "删除二进制文件失败",
+            Lang::En => "Reuse the recorded WHAT/WHY instead of requiring new ones",
+            Lang::Zh => "沿用记录中的 WHAT/WHY，而不是要求重新提供",
+            Lang::Ja => "記録された WHAT/WHY を再利用し、改めて指定させません",
         }
     }
 
-    // === Help text for new commands ===
-
-    pub fn help_cmd_info(&self) -> &'static str {
+    pub fn help_arg_replay_yes(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Show installation information",
-            Lang::Zh => "显示安装信息",
+            Lang::En => "Skip the confirmation for destructive-looking commands",
+            Lang::Zh => "跳过对可能具有破坏性命令的确认",
+            Lang::Ja => "破壊的な可能性のあるコマンドの確認をスキップします",
         }
     }
 
-    pub fn help_cmd_update(&self) -> &'static str {
+    pub fn help_arg_history_output(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Update shnote to the latest version",
-            Lang::Zh => "更新 shnote 到最新版本",
+            Lang::En => "Write to this file instead of stdout",
+            Lang::Zh => "写入该文件而不是标准输出",
+            Lang::Ja => "標準出力ではなくこのファイルに書き込みます",
         }
     }
 
-    pub fn help_cmd_uninstall(&self) -> &'static str {
+    pub fn help_arg_history_archive(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Uninstall shnote",
-            Lang::Zh => "卸载 shnote",
+            Lang::En => "Move the history file aside instead of deleting it",
+            Lang::Zh => "将历史文件归档而不是删除",
+            Lang::Ja => "履歴ファイルを削除せずにアーカイブします",
         }
     }
 
-    pub fn help_arg_update_check(&self) -> &'static str {
+    pub fn help_arg_setup_check_only(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Only check for updates, don't install",
-            Lang::Zh => "仅检查更新，不安装",
+            Lang::En => "Only verify the SHA-256 of installed pueue binaries; exit 1 on mismatch",
+            Lang::Zh => "仅校验已安装 pueue 二进制文件的 SHA-256；不匹配时退出码为 1",
+            Lang::Ja => "インストール済みの pueue バイナリの SHA-256 を検証するだけです。一致しなければ終了コード 1",
         }
     }
 
-    pub fn help_arg_update_force(&self) -> &'static str {
+    pub fn help_arg_setup_yes(&self) -> &'static str {
         match self.lang {
-            Lang::En => "Force update even if already up to date",
-            Lang::Zh => "即使已是最新版本也强制更新",
+            Lang::En => "Download without asking, even when setup_confirm_download is enabled",
+            Lang::Zh => "直接下载，即使启用了 setup_confirm_download 也不询问",
+            Lang::Ja => "setup_confirm_download が有効でも確認せずにダウンロードします",
         }
     }
 
@@ -1151,26 +3874,69 @@ impl I18n {
         match self.lang {
             Lang::En => "Skip confirmation prompt",
             Lang::Zh => "跳过确认提示",
+            Lang::Ja => "確認プロンプトをスキップします",
+        }
+    }
+}
+
+/// Where the effective UI language was taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LangSource {
+    /// `--lang` flag
+    Cli,
+    /// `language` config key
+    Config,
+    /// Environment variable (SHNOTE_LANG, LC_ALL, LC_MESSAGES, LANGUAGE, LANG)
+    Env(&'static str),
+    /// macOS `defaults read -g AppleLocale`
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    MacosDefaults,
+    /// Windows `Get-Culture`
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    WindowsCulture,
+    /// Nothing matched; English fallback
+    Default,
+}
+
+impl LangSource {
+    pub fn label(self) -> String {
+        match self {
+            Self::Cli => "--lang".to_string(),
+            Self::Config => "config (language)".to_string(),
+            Self::Env(key) => format!("env ({key})"),
+            Self::MacosDefaults => "macOS defaults (AppleLocale)".to_string(),
+            Self::WindowsCulture => "Windows culture (Get-Culture)".to_string(),
+            Self::Default => "default".to_string(),
         }
     }
 }
 
 pub fn detect_lang(cli_lang: Option<&str>, config_lang: &str) -> Lang {
+    detect_lang_with_source(cli_lang, config_lang).0
+}
+
+/// Same as [`detect_lang`], but also reports which layer of the priority chain won.
+pub fn detect_lang_with_source(cli_lang: Option<&str>, config_lang: &str) -> (Lang, LangSource) {
     // Priority: CLI flag > config > environment > default
     if let Some(lang) = cli_lang.and_then(Lang::from_tag) {
-        return lang;
+        return (lang, LangSource::Cli);
     }
 
     if config_lang != "auto" {
         if let Some(lang) = Lang::from_tag(config_lang) {
-            return lang;
+            return (lang, LangSource::Config);
         }
     }
 
-    parse_env_lang().unwrap_or(Lang::En)
+    parse_env_lang_with_source().unwrap_or((Lang::En, LangSource::Default))
 }
 
+#[cfg(test)]
 fn parse_env_lang() -> Option<Lang> {
+    parse_env_lang_with_source().map(|(lang, _)| lang)
+}
+
+fn parse_env_lang_with_source() -> Option<(Lang, LangSource)> {
     let keys = ["SHNOTE_LANG", "LC_ALL", "LC_MESSAGES", "LANGUAGE", "LANG"];
     for k in keys {
         let Some(v) = env::var_os(k) else { continue };
@@ -1181,7 +3947,7 @@ fn parse_env_lang() -> Option<Lang> {
             }
         }
         if let Some(lang) = Lang::from_tag(&s) {
-            return Some(lang);
+            return Some((lang, LangSource::Env(k)));
         }
     }
 
@@ -1189,14 +3955,14 @@ fn parse_env_lang() -> Option<Lang> {
     #[cfg(target_os = "macos")]
     {
         if let Some(lang) = detect_macos_lang() {
-            return Some(lang);
+            return Some((lang, LangSource::MacosDefaults));
         }
     }
 
     #[cfg(target_os = "windows")]
     {
         if let Some(lang) = detect_windows_lang() {
-            return Some(lang);
+            return Some((lang, LangSource::WindowsCulture));
         }
     }
 
@@ -1251,6 +4017,24 @@ mod tests {
     #[cfg(target_os = "macos")]
     use tempfile::TempDir;
 
+    #[test]
+    fn status_marker_is_ascii_in_plain_mode() {
+        for marker in [Marker::Ok, Marker::Fail, Marker::Warn, Marker::Skip] {
+            assert!(status_marker(marker, true).is_ascii());
+        }
+        assert_eq!(status_marker(Marker::Ok, false), "✓");
+        assert_eq!(status_marker(Marker::Fail, false), "✗");
+    }
+
+    #[test]
+    fn plain_i18n_messages_are_ascii() {
+        let i18n = I18n::new(Lang::En).with_plain(true);
+        assert!(i18n.init_claude_success("/tmp/CLAUDE.md").is_ascii());
+        assert!(i18n.init_tool_not_found("claude").is_ascii());
+        assert!(i18n.init_remove_done("/tmp/x").is_ascii());
+        assert!(!I18n::new(Lang::En).init_remove_done("/tmp/x").is_ascii());
+    }
+
     #[test]
     fn lang_from_tag() {
         assert_eq!(Lang::from_tag("en"), Some(Lang::En));
@@ -1259,6 +4043,9 @@ mod tests {
         assert_eq!(Lang::from_tag("zh"), Some(Lang::Zh));
         assert_eq!(Lang::from_tag("zh_CN"), Some(Lang::Zh));
         assert_eq!(Lang::from_tag("zh-Hans"), Some(Lang::Zh));
+        assert_eq!(Lang::from_tag("ja"), Some(Lang::Ja));
+        assert_eq!(Lang::from_tag("ja-JP"), Some(Lang::Ja));
+        assert_eq!(Lang::from_tag("ja_JP.UTF-8"), Some(Lang::Ja));
         // C/POSIX should return None to allow fallback to system language
         assert_eq!(Lang::from_tag("C"), None);
         assert_eq!(Lang::from_tag("POSIX"), None);
@@ -1308,12 +4095,48 @@ mod tests {
 
         assert!(en.err_script_source_required().contains("stdin"));
         assert!(zh.err_script_source_required().contains("stdin"));
+        assert!(en.err_script_source_conflict().contains("--stdin"));
+        assert!(zh.err_script_source_conflict().contains("--stdin"));
 
         assert!(en.err_failed_to_execute("test").contains("test"));
         assert!(zh.err_failed_to_execute("test").contains("test"));
 
+        assert!(en
+            .warn_hook_failed("notify", "exit status: 1")
+            .contains("notify"));
+        assert!(zh
+            .warn_hook_failed("notify", "exit status: 1")
+            .contains("notify"));
+        assert!(!en.help_arg_on_failure().is_empty());
+        assert!(!zh.help_arg_on_success().is_empty());
+
         assert!(en.err_interpreter_not_found("python").contains("python"));
         assert!(zh.err_interpreter_not_found("python").contains("python"));
+        for lang in [Lang::En, Lang::Zh, Lang::Ja] {
+            let i18n = I18n::new(lang);
+            let hint = i18n.err_interpreter_not_found_hint(Some("python"), "py3", "py3, python3");
+            assert!(hint.contains("python = py3"));
+            assert!(hint.contains("py3, python3"));
+            assert!(hint.contains("`shnote config set python <"));
+            assert!(hint.contains("`shnote doctor`"));
+            let hint = i18n.err_interpreter_not_found_hint(None, "/opt/py", "/opt/py");
+            assert!(hint.contains("--interpreter"));
+            assert!(!hint.contains("config set"));
+        }
+        assert!(en
+            .err_interpreter_too_old("python3", "3.6.9", "3.10.0")
+            .contains("3.10.0"));
+        assert!(zh
+            .err_interpreter_too_old("python3", "3.6.9", "3.10.0")
+            .contains("3.6.9"));
+        assert!(en
+            .err_interpreter_version_unknown("node", "20.0.0")
+            .contains("node"));
+        assert!(zh
+            .err_interpreter_version_unknown("node", "20.0.0")
+            .contains("20.0.0"));
+        assert!(en.err_invalid_min_version_value("abc").contains("abc"));
+        assert!(zh.err_invalid_min_version_value("abc").contains("abc"));
     }
 
     #[test]
@@ -1347,6 +4170,42 @@ mod tests {
 
         assert!(!en.doctor_pueue_not_found().is_empty());
         assert!(!zh.doctor_pueue_not_found().is_empty());
+
+        assert!(!en.doctor_locale_title().is_empty());
+        assert!(!zh.doctor_locale_title().is_empty());
+        assert!(en.doctor_locale_result("zh", "--lang").contains("--lang"));
+        assert!(zh.doctor_locale_result("zh", "--lang").contains("--lang"));
+        assert!(!en.doctor_locale_unset().is_empty());
+        assert!(!zh.doctor_locale_unset().is_empty());
+
+        assert!(en
+            .doctor_update_available("0.1.0", "0.2.0")
+            .contains("0.2.0"));
+        assert!(zh
+            .doctor_update_available("0.1.0", "0.2.0")
+            .contains("0.2.0"));
+        assert!(en.doctor_update_up_to_date("0.1.0").contains("0.1.0"));
+        assert!(zh.doctor_update_up_to_date("0.1.0").contains("0.1.0"));
+        assert!(en.doctor_update_check_failed("boom").contains("boom"));
+        assert!(zh.doctor_update_check_failed("boom").contains("boom"));
+        assert!(!en.doctor_update_skipped_offline().is_empty());
+        assert!(!zh.doctor_update_skipped_offline().is_empty());
+        assert!(!en.help_arg_doctor_check_updates().is_empty());
+        assert!(!zh.help_arg_offline().is_empty());
+        assert!(!en.help_arg_capture().is_empty());
+        assert!(!zh.help_arg_capture().is_empty());
+        assert!(!en.help_arg_doctor_check_history().is_empty());
+        assert!(!zh.help_arg_doctor_check_history().is_empty());
+        assert!(en.doctor_history_summary(3, 120).contains("120"));
+        assert!(zh.doctor_history_summary(3, 120).contains("120"));
+        assert!(en
+            .doctor_history_not_writable("/h", "denied")
+            .contains("/h"));
+        assert!(zh
+            .doctor_history_not_writable("/h", "denied")
+            .contains("denied"));
+        assert!(en.doctor_history_malformed(2, 5).contains("2 of 5"));
+        assert!(zh.doctor_history_malformed(2, 5).contains('5'));
     }
 
     #[test]
@@ -1368,6 +4227,17 @@ mod tests {
 
         assert!(!en.setup_complete().is_empty());
         assert!(!zh.setup_complete().is_empty());
+
+        assert!(!en.setup_download_urls().is_empty());
+        assert!(!zh.setup_download_urls().is_empty());
+        assert!(!en.setup_confirm_download().is_empty());
+        assert!(!zh.setup_confirm_download().is_empty());
+        assert!(!en.err_setup_download_declined().is_empty());
+        assert!(!zh.err_setup_download_declined().is_empty());
+        assert!(en.err_setup_confirm_requires_tty().contains("--yes"));
+        assert!(zh.err_setup_confirm_requires_tty().contains("--yes"));
+        assert!(!en.help_arg_setup_yes().is_empty());
+        assert!(!zh.help_arg_setup_yes().is_empty());
     }
 
     #[test]
@@ -1402,6 +4272,100 @@ mod tests {
         assert!(en.init_gemini_success("/tmp/f").contains("/tmp/f"));
         assert!(zh.init_gemini_success("/tmp/f").contains("/tmp/f"));
 
+        assert!(en.init_agents_success("/tmp/f").contains("/tmp/f"));
+
+        for (en_msg, zh_msg) in [
+            (
+                en.init_dry_run_create_dir("/tmp/d"),
+                zh.init_dry_run_create_dir("/tmp/d"),
+            ),
+            (
+                en.init_dry_run_create_file("/tmp/d"),
+                zh.init_dry_run_create_file("/tmp/d"),
+            ),
+            (
+                en.init_dry_run_overwrite_file("/tmp/d"),
+                zh.init_dry_run_overwrite_file("/tmp/d"),
+            ),
+            (
+                en.init_dry_run_update_rules("/tmp/d"),
+                zh.init_dry_run_update_rules("/tmp/d"),
+            ),
+            (
+                en.init_dry_run_append_rules("/tmp/d"),
+                zh.init_dry_run_append_rules("/tmp/d"),
+            ),
+            (
+                en.init_dry_run_migrate("/tmp/d"),
+                zh.init_dry_run_migrate("/tmp/d"),
+            ),
+        ] {
+            assert!(en_msg.contains("/tmp/d"));
+            assert!(zh_msg.contains("/tmp/d"));
+        }
+        assert!(!en.init_dry_run_done().is_empty());
+        assert!(!zh.init_dry_run_done().is_empty());
+        assert!(zh.init_agents_success("/tmp/f").contains("/tmp/f"));
+
+        assert!(!en.help_cmd_init_agents().is_empty());
+        assert!(!zh.help_cmd_init_agents().is_empty());
+        assert!(en.init_copilot_success("/tmp/f").contains("/tmp/f"));
+        assert!(zh.init_copilot_success("/tmp/f").contains("/tmp/f"));
+        assert!(en
+            .err_copilot_requires_project_scope()
+            .contains("--scope project"));
+        assert!(zh
+            .err_copilot_requires_project_scope()
+            .contains("--scope project"));
+        assert!(!en.help_cmd_init_copilot().is_empty());
+        assert!(!zh.help_cmd_init_copilot().is_empty());
+        assert!(en.init_cursor_success("/tmp/f").contains("/tmp/f"));
+        assert!(zh.init_cursor_success("/tmp/f").contains("/tmp/f"));
+        assert!(en
+            .err_cursor_requires_project_scope()
+            .contains("--scope project"));
+        assert!(zh
+            .err_cursor_requires_project_scope()
+            .contains("--scope project"));
+        assert!(!en.help_cmd_init_cursor().is_empty());
+        assert!(!zh.help_cmd_init_cursor().is_empty());
+        assert!(en.init_windsurf_success("/tmp/f").contains("/tmp/f"));
+        assert!(en.onboarding_prompt_init("codex").contains("init codex"));
+        assert!(zh.onboarding_prompt_init("codex").contains("init codex"));
+        assert!(zh.warn_onboarding_step_failed("boom").contains("boom"));
+        assert!(!zh.onboarding_skipped().is_empty());
+        assert!(zh.init_windsurf_success("/tmp/f").contains("/tmp/f"));
+        assert!(!en.help_cmd_init_windsurf().is_empty());
+        assert!(!zh.help_cmd_init_windsurf().is_empty());
+        assert!(en.init_aider_success("/tmp/f").contains("--read /tmp/f"));
+        assert!(zh.init_aider_success("/tmp/f").contains("--read /tmp/f"));
+        assert!(!en.help_cmd_init_aider().is_empty());
+        assert!(en
+            .init_all_not_found("codex", "codex")
+            .contains("not found"));
+        assert!(zh.init_all_failed("codex", "boom").contains("boom"));
+        assert!(en
+            .init_all_no_cli("cursor")
+            .contains("init -s project cursor"));
+        assert!(!zh.init_all_written("codex").is_empty());
+        assert!(!zh.help_arg_init_all().is_empty());
+        assert!(!en.err_init_all_with_target().is_empty());
+        assert!(en
+            .test_rules_failed("codex", "en", "boom")
+            .contains("codex (en): boom"));
+        assert!(zh.test_rules_leftover("{{X}}", true).contains("{{X}}"));
+        assert!(en.test_rules_summary(2, 16).contains("2 of 16"));
+        assert!(!zh.test_rules_round_trip().is_empty());
+        assert!(!en.help_cmd_test_rules().is_empty());
+        assert!(en.init_remove_done("/a").contains("/a"));
+        assert!(zh.init_remove_deleted("/a").contains("/a"));
+        assert!(en
+            .init_remove_nothing("codex")
+            .contains("nothing to remove"));
+        assert!(zh.init_dry_run_remove_rules("/a").contains("/a"));
+        assert!(en.init_dry_run_delete_file("/a").contains("would delete"));
+        assert!(!zh.help_arg_init_remove().is_empty());
+
         assert!(!en.init_rules_updated().is_empty());
         assert!(!zh.init_rules_updated().is_empty());
 
@@ -1610,6 +4574,127 @@ mod tests {
 
         assert!(!en.help_arg_update_force().is_empty());
         assert!(!zh.help_arg_update_force().is_empty());
+        assert!(!en.help_arg_doctor_check_locale().is_empty());
+        assert!(!en.help_arg_plain().is_empty());
+        assert!(!zh.help_arg_plain().is_empty());
+        assert!(en.help_arg_agent_mode().contains("125"));
+        assert!(zh.help_arg_agent_mode().contains("125"));
+        assert!(!en.help_arg_init_dry_run().is_empty());
+        assert!(!zh.help_arg_init_dry_run().is_empty());
+        assert!(!zh.help_arg_doctor_check_locale().is_empty());
+        assert!(!en.help_arg_completions_list().is_empty());
+        assert!(!zh.help_arg_completions_list().is_empty());
+    }
+
+    #[test]
+    fn i18n_history_messages() {
+        let en = I18n::new(Lang::En);
+        let zh = I18n::new(Lang::Zh);
+
+        assert!(en.history_clear_confirm("/h.jsonl").contains("/h.jsonl"));
+        assert!(zh.history_clear_confirm("/h.jsonl").contains("/h.jsonl"));
+        assert!(!en.history_clear_cancelled().is_empty());
+        assert!(!zh.history_clear_cancelled().is_empty());
+        assert!(!en.history_clear_nothing().is_empty());
+        assert!(!zh.history_clear_nothing().is_empty());
+        assert!(en.history_cleared("/h.jsonl").contains("/h.jsonl"));
+        assert!(zh.history_cleared("/h.jsonl").contains("/h.jsonl"));
+        assert!(en.history_archived("/a.jsonl").contains("/a.jsonl"));
+        assert!(zh.history_archived("/a.jsonl").contains("/a.jsonl"));
+        assert!(!en.history_empty().is_empty());
+        assert!(!zh.history_empty().is_empty());
+        assert!(en.warn_history_disabled("denied").contains("denied"));
+        assert!(zh.warn_history_disabled("denied").contains("denied"));
+        assert!(en.warn_history_http_failed("timeout").contains("timeout"));
+        assert!(zh.warn_history_http_failed("timeout").contains("timeout"));
+        assert!(!en.help_arg_note_tag().is_empty());
+        assert!(!zh.help_arg_note_tag().is_empty());
+        assert!(!en.help_arg_history_tag().is_empty());
+        assert!(!zh.help_arg_history_tag().is_empty());
+        assert!(!en.help_arg_history_since().is_empty());
+        assert!(!zh.help_arg_history_since().is_empty());
+        assert!(!en.help_arg_history_limit().is_empty());
+        assert!(!zh.help_arg_history_limit().is_empty());
+        assert!(!en.help_arg_history_json().is_empty());
+        assert!(!zh.help_arg_history_json().is_empty());
+        assert!(!en.help_cmd_history().is_empty());
+        assert!(!zh.help_cmd_history_clear().is_empty());
+        assert!(!en.help_arg_history_archive().is_empty());
+        assert!(!zh.help_arg_history_archive().is_empty());
+        assert!(!en.help_arg_update_hash().is_empty());
+        assert!(!en.help_arg_history_output().is_empty());
+        assert!(!zh.help_cmd_history_export().is_empty());
+        assert!(!en.help_arg_measure_output_size().is_empty());
+        assert!(!zh.help_arg_measure_output_size().is_empty());
+        assert!(!en.help_arg_redirect_stdout().is_empty());
+        assert!(!zh.help_arg_redirect_stderr().is_empty());
+        assert!(!zh.help_arg_redirect_append().is_empty());
+        assert!(!en.help_arg_print_pid().is_empty());
+        assert!(!zh.help_arg_pid_fd().is_empty());
+        assert!(en.err_pid_file_dir_missing("/a/b").contains("/a/b"));
+        assert!(!zh.err_pid_fd_unsupported().is_empty());
+        assert!(en
+            .warn_pid_write_failed("fd 9", "bad")
+            .contains("fd 9: bad"));
+        assert!(zh.err_redirect_dir_missing("/x/y").contains("/x/y"));
+        assert!(en.err_redirect_open("/x/y").contains("/x/y"));
+    }
+
+    #[test]
+    fn i18n_resource_limit_messages() {
+        let en = I18n::new(Lang::En);
+        let zh = I18n::new(Lang::Zh);
+
+        assert!(en.run_killed_cpu_limit(3).contains('3'));
+        assert!(zh.run_killed_cpu_limit(3).contains('3'));
+        assert!(en.run_killed_mem_limit(64).contains("64"));
+        assert!(zh.run_killed_mem_limit(64).contains("64"));
+        assert!(en.run_failed_mem_limit_hint(64).contains("64"));
+        assert!(zh.run_failed_mem_limit_hint(64).contains("64"));
+        assert!(!en.warn_resource_limits_unsupported().is_empty());
+        assert!(!zh.warn_resource_limits_unsupported().is_empty());
+        assert!(!en.warn_measure_unsupported().is_empty());
+        assert!(!zh.warn_measure_unsupported().is_empty());
+        assert!(en.err_exec_prefix_not_found("strace").contains("strace"));
+        assert!(zh.err_exec_prefix_not_found("strace").contains("strace"));
+        assert!(en.err_invalid_cwd("/nope").contains("/nope"));
+        assert!(zh.err_invalid_cwd("/nope").contains("/nope"));
+        assert!(en.err_not_in_git_repo("/nope").contains("/nope"));
+        assert!(en
+            .run_until_ready(42, "0.3s", "/tmp/x.log")
+            .contains("pid 42"));
+        assert!(zh
+            .run_until_ready(42, "0.3s", "/tmp/x.log")
+            .contains("pid 42"));
+        assert!(en
+            .err_until_timeout("ready", 5, "/tmp/x.log")
+            .contains("5s"));
+        assert!(zh
+            .err_until_exited("ready", "exit status: 1", "/tmp/x.log")
+            .contains("ready"));
+        assert!(zh.err_not_in_git_repo("/nope").contains("/nope"));
+        assert!(!en.help_arg_chdir_to_git_root().is_empty());
+        assert!(!zh.help_arg_chdir_to_git_root().is_empty());
+        assert!(!en.help_arg_no_cwd_inherit().is_empty());
+        assert!(!zh.help_arg_no_cwd_inherit().is_empty());
+        assert!(!en.help_arg_cwd().is_empty());
+        assert!(!zh.help_arg_cwd().is_empty());
+        assert!(!en.help_arg_exec_prefix().is_empty());
+        assert!(!zh.help_arg_exec_prefix().is_empty());
+        assert!(!en.help_arg_mem_limit().is_empty());
+        assert!(!zh.help_arg_cpu_limit().is_empty());
+        assert!(en.err_invalid_exit_on("1=>x").contains("1=>x"));
+        assert!(zh.err_invalid_exit_on("1=>x").contains("1=>x"));
+        assert!(!en.help_arg_exit_on().is_empty());
+        assert!(!zh.help_arg_exit_on().is_empty());
+        assert!(en.run_once_skipped("setup").contains("setup"));
+        assert!(zh.run_once_skipped("setup").contains("setup"));
+        assert!(en.warn_once_record_failed("denied").contains("denied"));
+        assert!(zh.warn_once_record_failed("denied").contains("denied"));
+        assert!(!en.help_arg_once().is_empty());
+        assert!(!zh.help_arg_label().is_empty());
+        assert!(!en.help_arg_once_force().is_empty());
+        assert!(!zh.help_arg_once_force().is_empty());
     }
 
     #[test]
@@ -1642,6 +4727,18 @@ mod tests {
         assert!(zh
             .err_invalid_color_value("maybe", "true, false")
             .contains("maybe"));
+        assert!(en
+            .err_invalid_history_value("maybe", "true, false")
+            .contains("maybe"));
+        assert!(zh
+            .err_invalid_history_value("maybe", "true, false")
+            .contains("maybe"));
+        assert!(en
+            .err_invalid_bool_value("setup_confirm_download", "maybe", "true, false")
+            .contains("setup_confirm_download"));
+        assert!(zh
+            .err_invalid_bool_value("setup_confirm_download", "maybe", "true, false")
+            .contains("maybe"));
 
         assert!(en
             .err_invalid_color_name("orange", "red, green, blue")
@@ -1670,6 +4767,20 @@ mod tests {
         assert_eq!(parse_env_lang(), Some(Lang::Zh));
     }
 
+    #[test]
+    fn parse_env_lang_detects_japanese_locale() {
+        let _lock = env_lock();
+        let _prev_shnote_lang = EnvVarGuard::remove("SHNOTE_LANG");
+        let _prev_lc_all = EnvVarGuard::remove("LC_ALL");
+        let _prev_lc_messages = EnvVarGuard::remove("LC_MESSAGES");
+        let _prev_language = EnvVarGuard::remove("LANGUAGE");
+        let _prev_lang = EnvVarGuard::remove("LANG");
+
+        let _lang = EnvVarGuard::set("LANG", "ja_JP.UTF-8");
+        assert_eq!(parse_env_lang(), Some(Lang::Ja));
+        assert_eq!(I18n::new(Lang::Ja).lang_tag(), "ja");
+    }
+
     #[test]
     fn parse_env_lang_accepts_language_without_colon() {
         let _lock = env_lock();
@@ -1683,6 +4794,59 @@ mod tests {
         assert_eq!(parse_env_lang(), Some(Lang::En));
     }
 
+    #[test]
+    fn detect_lang_with_source_reports_winning_layer() {
+        let _lock = env_lock();
+        let _prev_shnote_lang = EnvVarGuard::remove("SHNOTE_LANG");
+        let _prev_lc_all = EnvVarGuard::remove("LC_ALL");
+        let _prev_lc_messages = EnvVarGuard::remove("LC_MESSAGES");
+        let _prev_language = EnvVarGuard::remove("LANGUAGE");
+        let _prev_lang = EnvVarGuard::remove("LANG");
+
+        let _lang = EnvVarGuard::set("LANG", "zh_CN.UTF-8");
+        assert_eq!(
+            detect_lang_with_source(Some("en"), "zh"),
+            (Lang::En, LangSource::Cli)
+        );
+        assert_eq!(
+            detect_lang_with_source(Some("fr"), "en"),
+            (Lang::En, LangSource::Config)
+        );
+        assert_eq!(
+            detect_lang_with_source(None, "auto"),
+            (Lang::Zh, LangSource::Env("LANG"))
+        );
+
+        let _lc_all = EnvVarGuard::set("LC_ALL", "en_US.UTF-8");
+        assert_eq!(
+            detect_lang_with_source(None, "auto"),
+            (Lang::En, LangSource::Env("LC_ALL"))
+        );
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn detect_lang_with_source_falls_back_to_default() {
+        let _lock = env_lock();
+        let _prev_shnote_lang = EnvVarGuard::remove("SHNOTE_LANG");
+        let _prev_lc_all = EnvVarGuard::set("LC_ALL", "C");
+        let _prev_lc_messages = EnvVarGuard::remove("LC_MESSAGES");
+        let _prev_language = EnvVarGuard::remove("LANGUAGE");
+        let _prev_lang = EnvVarGuard::remove("LANG");
+
+        assert_eq!(
+            detect_lang_with_source(None, "auto"),
+            (Lang::En, LangSource::Default)
+        );
+    }
+
+    #[test]
+    fn lang_source_labels_name_the_env_key() {
+        assert_eq!(LangSource::Env("LC_ALL").label(), "env (LC_ALL)");
+        assert_eq!(LangSource::Cli.label(), "--lang");
+        assert_eq!(LangSource::Default.label(), "default");
+    }
+
     #[test]
     fn detect_lang_ignores_invalid_config_value() {
         let _lock = env_lock();
@@ -2,7 +2,9 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 
-use crate::config::{home_dir, pueue_binary_name, pueued_binary_name, shnote_bin_dir, shnote_home};
+use crate::config::{
+    data_dir, home_dir, pueue_binary_name, pueued_binary_name, shnote_bin_dir, shnote_home,
+};
 use crate::i18n::I18n;
 use crate::pueue_embed::{embedded, PUEUE_VERSION};
 
@@ -23,7 +25,7 @@ pub fn run_info(i18n: &I18n) -> Result<()> {
     // Paths
     let install_path = get_install_path();
     let config_path = shnote_home().ok().map(|p| p.join("config.toml"));
-    let data_path = shnote_home().ok();
+    let data_path = data_dir().ok();
 
     println!("{}:", i18n.info_paths());
     println!(
@@ -7,7 +7,10 @@ use which::which;
 
 use crate::cli::{InitTarget, Scope};
 use crate::config::home_dir;
+use crate::exit_code;
 use crate::i18n::{I18n, Lang};
+use crate::semver::{parse_semver_from_text, SemVer};
+use crate::update::{extract_shnote_rules, render_diff};
 
 /// Embedded shnote rules content (shared by all targets)
 const SHNOTE_RULES_BASE: &str = r#"# 运行 shell 命令必须使用 shnote 包装
@@ -26,10 +29,10 @@ shnote 用于在执行 shell 命令前补充 WHAT/WHY，帮助用户理解命令
    - 把 `stderr` 非空当作失败信号
    - 直接解析整段终端日志（未区分 stdout/stderr）
 
-## 不需要通过 shnote 的操作
+{{PUEUE_SECTION}}## 不需要通过 shnote 的操作
 
 {{NON_SHNOTE_TOOLS}}
-"#;
+{{EXTRA_RULES}}"#;
 
 const SHNOTE_RULES_BASE_EN: &str = r#"# Wrap shell commands with shnote
 
@@ -47,9 +50,57 @@ shnote adds WHAT/WHY before shell execution so users can quickly understand inte
    - Systems treating any non-empty `stderr` as failure
    - Parsers that consume raw terminal logs without stream separation
 
-## Operations that do not need shnote
+{{PUEUE_SECTION}}## Operations that do not need shnote
+
+{{NON_SHNOTE_TOOLS}}
+{{EXTRA_RULES}}"#;
+
+const SHNOTE_RULES_BASE_JA: &str = r#"# shell コマンドは shnote でラップして実行する
+
+shnote は shell コマンドの実行前に WHAT/WHY を示し、ユーザーがコマンドの意図をすぐに理解できるようにします。
+
+## 基本ルール
+
+1. 実行系サブコマンド（`run` / `py` / `node` / `pip` / `npm` / `npx`）は次の形で使う:
+   `shnote --what "<何をするか>" --why "<なぜ行うか>" <subcommand> ...`
+2. WHAT には動作を、WHY には理由を書き、どちらも短く具体的にする。
+3. デフォルトは `header_stream=auto`: `stdout` をデータ用、`stderr` を説明用のチャネルとして扱う。
+4. コマンドにパイプ、リダイレクト、論理演算子、セミコロン（`|`、`|&`、`>`、`>>`、`<`、`2>&1`、`&&`、`||`、`;` など）が含まれる場合は、コマンド全体を 1 組の引用符で囲み、shnote への 1 つの引数として渡す（ダブルクォート推奨。変数展開を避けたい場合はシングルクォート）。
+5. 次の場合は WHAT/WHY の影響を受けることがあるため、`stderr` を適宜分離・除外する:
+   - 両ストリームをまとめる場合（`2>&1`、`|&` など）
+   - `stderr` が空でないことを失敗とみなす仕組み
+   - stdout/stderr を区別せずに端末ログ全体を解析する場合
+
+{{PUEUE_SECTION}}## shnote を通さなくてよい操作
 
 {{NON_SHNOTE_TOOLS}}
+{{EXTRA_RULES}}"#;
+
+/// Optional section for `{{PUEUE_SECTION}}`, included with `init --force-pueue` (ZH)
+const SHNOTE_RULES_PUEUE: &str = r#"## 长时间运行的命令（使用 pueue）
+
+1. 构建、完整测试、开发服务器等耗时命令，通过 pueue 放到后台执行：
+   `shnote --what "<做什么>" --why "<为什么>" run pueue add -- <command>`
+2. 用 `pueue status` 查看进度，用 `pueue log <id>` 查看输出（同样通过 shnote 执行）。
+
+"#;
+
+/// Optional section for `{{PUEUE_SECTION}}`, included with `init --force-pueue` (EN)
+const SHNOTE_RULES_PUEUE_EN: &str = r#"## Long-running commands (use pueue)
+
+1. Queue slow commands (builds, full test suites, dev servers) in the background with pueue:
+   `shnote --what "<what>" --why "<why>" run pueue add -- <command>`
+2. Check progress with `pueue status` and read output with `pueue log <id>` (also through shnote).
+
+"#;
+
+/// Optional section for `{{PUEUE_SECTION}}`, included with `init --force-pueue` (JA)
+const SHNOTE_RULES_PUEUE_JA: &str = r#"## 長時間実行するコマンド（pueue を使う）
+
+1. ビルド、全テスト、開発サーバーなど時間のかかるコマンドは、pueue でバックグラウンド実行する:
+   `shnote --what "<何をするか>" --why "<なぜ行うか>" run pueue add -- <command>`
+2. 進捗は `pueue status`、出力は `pueue log <id>` で確認する（これらも shnote 経由で実行する）。
+
 "#;
 
 /// Codex-specific additions to the shnote rules (ZH)
@@ -66,6 +117,13 @@ const SHNOTE_RULES_CODEX_EXTRA_EN: &str = r#"## Codex Extra Rules
 2. For edits, prefer `apply_patch` (or built-in edit tools).
 "#;
 
+/// Codex-specific additions to the shnote rules (JA)
+const SHNOTE_RULES_CODEX_EXTRA_JA: &str = r#"## Codex 追加ルール
+
+1. ファイルの内容を読むだけのときは shell（`cat` / `head` / `tail` / `sed` / `nl` / `rg` など）を直接使い、shnote は使わない。
+2. ファイルの編集には `apply_patch`（または組み込みの編集ツール）を優先して使う。
+"#;
+
 /// Claude-specific additions to the shnote rules (ZH)
 const SHNOTE_RULES_CLAUDE_EXTRA: &str = r#"## Claude Code 额外规则
 
@@ -78,6 +136,12 @@ const SHNOTE_RULES_CLAUDE_EXTRA_EN: &str = r#"## Claude Code Extra Rules
 **Only the Bash tool must use shnote**; Read / Write / Edit tools must not use shnote.
 "#;
 
+/// Claude-specific additions to the shnote rules (JA)
+const SHNOTE_RULES_CLAUDE_EXTRA_JA: &str = r#"## Claude Code 追加ルール
+
+**shnote が必須なのは Bash ツールだけ**です。Read / Write / Edit などのツールでは shnote を使わない。
+"#;
+
 /// Gemini-specific additions to the shnote rules (ZH)
 const SHNOTE_RULES_GEMINI_EXTRA: &str = r#"## Gemini 额外规则
 
@@ -90,62 +154,521 @@ const SHNOTE_RULES_GEMINI_EXTRA_EN: &str = r#"## Gemini Extra Rules
 **Only run_shell_command uses shnote**; list_directory / read_file / write_file / replace tools must not use shnote.
 "#;
 
+/// Gemini-specific additions to the shnote rules (JA)
+const SHNOTE_RULES_GEMINI_EXTRA_JA: &str = r#"## Gemini 追加ルール
+
+**shnote が必要なのは run_shell_command だけ**です。list_directory / read_file / write_file / replace などのツールでは shnote を使わない。
+"#;
+
+/// Cursor-specific additions to the shnote rules (ZH)
+const SHNOTE_RULES_CURSOR_EXTRA: &str = r#"## Cursor 额外规则
+
+**仅终端工具（run_terminal_cmd）需要使用 shnote**，后台运行的命令也一样；read_file / edit_file / list_dir / grep_search / codebase_search 等工具一律不使用 shnote。
+"#;
+
+/// Cursor-specific additions to the shnote rules (EN)
+const SHNOTE_RULES_CURSOR_EXTRA_EN: &str = r#"## Cursor Extra Rules
+
+**Only the terminal tool (run_terminal_cmd) uses shnote**, background commands included; read_file / edit_file / list_dir / grep_search / codebase_search tools must not use shnote.
+"#;
+
+/// Cursor-specific additions to the shnote rules (JA)
+const SHNOTE_RULES_CURSOR_EXTRA_JA: &str = r#"## Cursor 追加ルール
+
+**shnote が必要なのはターミナルツール（run_terminal_cmd）だけ**で、バックグラウンドで実行するコマンドも同様です。read_file / edit_file / list_dir / grep_search / codebase_search などのツールでは shnote を使わない。
+"#;
+
+/// Windsurf-specific additions to the shnote rules (ZH)
+const SHNOTE_RULES_WINDSURF_EXTRA: &str = r#"## Windsurf 额外规则
+
+**仅 Cascade 的终端工具（run_command）需要使用 shnote**；view_file / write_to_file / replace_file_content / grep_search / find_by_name / list_dir 等工具一律不使用 shnote。
+"#;
+
+/// Windsurf-specific additions to the shnote rules (EN)
+const SHNOTE_RULES_WINDSURF_EXTRA_EN: &str = r#"## Windsurf Extra Rules
+
+**Only Cascade's terminal tool (run_command) uses shnote**; view_file / write_to_file / replace_file_content / grep_search / find_by_name / list_dir tools must not use shnote.
+"#;
+
+/// Windsurf-specific additions to the shnote rules (JA)
+const SHNOTE_RULES_WINDSURF_EXTRA_JA: &str = r#"## Windsurf 追加ルール
+
+**shnote が必要なのは Cascade のターミナルツール（run_command）だけ**です。view_file / write_to_file / replace_file_content / grep_search / find_by_name / list_dir などのツールでは shnote を使わない。
+"#;
+
+/// Frontmatter Cursor expects at the top of a `.mdc` rules file; written
+/// only when shnote creates the file.
+const CURSOR_RULES_FRONTMATTER: &str =
+    "---\ndescription: Run shell commands through shnote with WHAT/WHY\nalwaysApply: true\n---\n";
+
+/// Frontmatter that makes a Windsurf workspace rule always apply; written
+/// only when shnote creates `.windsurf/rules/shnote.md`.
+const WINDSURF_RULES_FRONTMATTER: &str = "---\ntrigger: always_on\n---\n";
+
 /// Marker to identify shnote rules section in append mode
 pub(crate) const SHNOTE_MARKER_START: &str = "\n<!-- shnote rules start -->\n";
 pub(crate) const SHNOTE_MARKER_END: &str = "\n<!-- shnote rules end -->\n";
 
-fn non_shnote_tools_for_target(lang: Lang, target: InitTarget) -> &'static str {
-    match (lang, target) {
-        (Lang::Zh, InitTarget::Codex) => "1. **只读查看文件**：直接用 shell，不通过 shnote。\n2. **非 shell 的内建工具**（读文件、列目录、编辑文件等）不通过 shnote。",
-        (Lang::En, InitTarget::Codex) => "1. **Read-only file viewing**: use direct shell, not shnote.\n2. **Non-shell built-in tools** (read/list/edit operations) do not need shnote.",
-        (Lang::Zh, InitTarget::Claude) => "1. **仅 Bash 工具必须使用 shnote**：Read / Write / Edit 等工具不使用 shnote。",
-        (Lang::En, InitTarget::Claude) => "1. **Only the Bash tool must use shnote**: Read / Write / Edit tools do not use shnote.",
-        (Lang::Zh, InitTarget::Gemini) => "1. **仅 run_shell_command 需要使用 shnote**：list_directory / read_file / write_file / replace 等工具不使用 shnote。",
-        (Lang::En, InitTarget::Gemini) => "1. **Only run_shell_command needs shnote**: list_directory / read_file / write_file / replace do not use shnote.",
+/// Target-specific pieces of the rules text for one language.
+struct TargetRules {
+    /// Replaces `{{NON_SHNOTE_TOOLS}}` in the base template
+    non_shnote_tools: &'static str,
+    /// Appended after the base rules
+    extra: Option<&'static str>,
+}
+
+/// Everything `init` needs to know about a target, so adding a target is one entry here.
+struct TargetProfile {
+    /// CLI binary probed before installing rules
+    tool: Option<&'static str>,
+    /// Rules file for user scope, as shown by `init --list-targets`; `None` if project-only
+    user_path: Option<&'static str>,
+    /// Rules file for project scope, relative to the current directory
+    project_path: &'static str,
+    zh: TargetRules,
+    en: TargetRules,
+    ja: TargetRules,
+}
+
+impl TargetProfile {
+    fn rules(&self, lang: Lang) -> &TargetRules {
+        match lang {
+            Lang::Zh => &self.zh,
+            Lang::En => &self.en,
+            Lang::Ja => &self.ja,
+        }
+    }
+}
+
+const CLAUDE_PROFILE: TargetProfile = TargetProfile {
+    tool: Some("claude"),
+    user_path: Some("~/.claude/rules/shnote.md (< 2.0.64: ~/.claude/CLAUDE.md)"),
+    project_path: ".claude/rules/shnote.md (< 2.0.64: .claude/CLAUDE.md)",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅 Bash 工具必须使用 shnote**：Read / Write / Edit 等工具不使用 shnote。",
+        extra: Some(SHNOTE_RULES_CLAUDE_EXTRA),
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only the Bash tool must use shnote**: Read / Write / Edit tools do not use shnote.",
+        extra: Some(SHNOTE_RULES_CLAUDE_EXTRA_EN),
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必須なのは Bash ツールだけ**: Read / Write / Edit などのツールでは shnote を使わない。",
+        extra: Some(SHNOTE_RULES_CLAUDE_EXTRA_JA),
+    },
+};
+
+const CODEX_PROFILE: TargetProfile = TargetProfile {
+    tool: Some("codex"),
+    user_path: Some("~/.codex/AGENTS.md"),
+    project_path: ".codex/AGENTS.md",
+    zh: TargetRules {
+        non_shnote_tools: "1. **只读查看文件**：直接用 shell，不通过 shnote。\n2. **非 shell 的内建工具**（读文件、列目录、编辑文件等）不通过 shnote。",
+        extra: Some(SHNOTE_RULES_CODEX_EXTRA),
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Read-only file viewing**: use direct shell, not shnote.\n2. **Non-shell built-in tools** (read/list/edit operations) do not need shnote.",
+        extra: Some(SHNOTE_RULES_CODEX_EXTRA_EN),
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **ファイルを読むだけの操作**: shell を直接使い、shnote は通さない。\n2. **shell 以外の組み込みツール**（ファイルの読み込み、ディレクトリ一覧、ファイル編集など）は shnote を通さない。",
+        extra: Some(SHNOTE_RULES_CODEX_EXTRA_JA),
+    },
+};
+
+const GEMINI_PROFILE: TargetProfile = TargetProfile {
+    tool: Some("gemini"),
+    user_path: Some("~/.gemini/GEMINI.md"),
+    project_path: ".gemini/GEMINI.md",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅 run_shell_command 需要使用 shnote**：list_directory / read_file / write_file / replace 等工具不使用 shnote。",
+        extra: Some(SHNOTE_RULES_GEMINI_EXTRA),
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only run_shell_command needs shnote**: list_directory / read_file / write_file / replace do not use shnote.",
+        extra: Some(SHNOTE_RULES_GEMINI_EXTRA_EN),
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必要なのは run_shell_command だけ**: list_directory / read_file / write_file / replace などのツールでは shnote を使わない。",
+        extra: Some(SHNOTE_RULES_GEMINI_EXTRA_JA),
+    },
+};
+
+const AGENTS_PROFILE: TargetProfile = TargetProfile {
+    tool: None,
+    user_path: Some("~/.config/AGENTS.md"),
+    project_path: "AGENTS.md",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅执行 shell 命令的工具需要使用 shnote**：读文件、列目录、编辑文件等内建工具不使用 shnote。",
+        extra: None,
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only shell-execution tools need shnote**: built-in read/list/edit tools do not use shnote.",
+        extra: None,
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必要なのは shell コマンドを実行するツールだけ**: ファイルの読み込み、ディレクトリ一覧、ファイル編集などの組み込みツールでは shnote を使わない。",
+        extra: None,
+    },
+};
+
+const COPILOT_PROFILE: TargetProfile = TargetProfile {
+    tool: None,
+    user_path: None,
+    project_path: ".github/copilot-instructions.md",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅终端命令（run_in_terminal）需要使用 shnote**：读文件、搜索、编辑文件等工具不使用 shnote。",
+        extra: None,
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only terminal commands (run_in_terminal) need shnote**: file read/search/edit tools do not use shnote.",
+        extra: None,
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必要なのはターミナルコマンド（run_in_terminal）だけ**: ファイルの読み込み、検索、編集などのツールでは shnote を使わない。",
+        extra: None,
+    },
+};
+
+const CURSOR_PROFILE: TargetProfile = TargetProfile {
+    tool: None,
+    user_path: None,
+    project_path: ".cursor/rules/shnote.mdc",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅终端命令（run_terminal_cmd）需要使用 shnote**：read_file / edit_file / list_dir / grep_search 等工具不使用 shnote。",
+        extra: Some(SHNOTE_RULES_CURSOR_EXTRA),
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only terminal commands (run_terminal_cmd) need shnote**: read_file / edit_file / list_dir / grep_search tools do not use shnote.",
+        extra: Some(SHNOTE_RULES_CURSOR_EXTRA_EN),
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必要なのはターミナルコマンド（run_terminal_cmd）だけ**: read_file / edit_file / list_dir / grep_search などのツールでは shnote を使わない。",
+        extra: Some(SHNOTE_RULES_CURSOR_EXTRA_JA),
+    },
+};
+
+const WINDSURF_PROFILE: TargetProfile = TargetProfile {
+    tool: Some("windsurf"),
+    user_path: Some("~/.codeium/windsurf/memories/global_rules.md"),
+    project_path: ".windsurf/rules/shnote.md",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅终端命令（run_command）需要使用 shnote**：view_file / write_to_file / grep_search / list_dir 等工具不使用 shnote。",
+        extra: Some(SHNOTE_RULES_WINDSURF_EXTRA),
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only terminal commands (run_command) need shnote**: view_file / write_to_file / grep_search / list_dir tools do not use shnote.",
+        extra: Some(SHNOTE_RULES_WINDSURF_EXTRA_EN),
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必要なのはターミナルコマンド（run_command）だけ**: view_file / write_to_file / grep_search / list_dir などのツールでは shnote を使わない。",
+        extra: Some(SHNOTE_RULES_WINDSURF_EXTRA_JA),
+    },
+};
+
+const AIDER_PROFILE: TargetProfile = TargetProfile {
+    tool: Some("aider"),
+    user_path: Some("~/.aider/CONVENTIONS.md"),
+    project_path: "CONVENTIONS.md",
+    zh: TargetRules {
+        non_shnote_tools: "1. **仅建议执行的 shell 命令需要使用 shnote**：SEARCH/REPLACE 编辑块等文件修改不使用 shnote。",
+        extra: None,
+    },
+    en: TargetRules {
+        non_shnote_tools: "1. **Only suggested shell commands need shnote**: file edits (SEARCH/REPLACE blocks) do not use shnote.",
+        extra: None,
+    },
+    ja: TargetRules {
+        non_shnote_tools: "1. **shnote が必要なのは提案する shell コマンドだけ**: SEARCH/REPLACE 編集ブロックなどのファイル変更では shnote を使わない。",
+        extra: None,
+    },
+};
+
+fn target_profile(target: InitTarget) -> &'static TargetProfile {
+    match target {
+        InitTarget::Claude => &CLAUDE_PROFILE,
+        InitTarget::Codex => &CODEX_PROFILE,
+        InitTarget::Gemini => &GEMINI_PROFILE,
+        InitTarget::Agents => &AGENTS_PROFILE,
+        InitTarget::Copilot => &COPILOT_PROFILE,
+        InitTarget::Cursor => &CURSOR_PROFILE,
+        InitTarget::Windsurf => &WINDSURF_PROFILE,
+        InitTarget::Aider => &AIDER_PROFILE,
     }
 }
 
-fn extra_rules_for_target(lang: Lang, target: InitTarget) -> Option<&'static str> {
-    match (lang, target) {
-        (Lang::Zh, InitTarget::Codex) => Some(SHNOTE_RULES_CODEX_EXTRA),
-        (Lang::En, InitTarget::Codex) => Some(SHNOTE_RULES_CODEX_EXTRA_EN),
-        (Lang::Zh, InitTarget::Claude) => Some(SHNOTE_RULES_CLAUDE_EXTRA),
-        (Lang::En, InitTarget::Claude) => Some(SHNOTE_RULES_CLAUDE_EXTRA_EN),
-        (Lang::Zh, InitTarget::Gemini) => Some(SHNOTE_RULES_GEMINI_EXTRA),
-        (Lang::En, InitTarget::Gemini) => Some(SHNOTE_RULES_GEMINI_EXTRA_EN),
+/// `init --set-var` key whose value becomes a paragraph after the tool list.
+const EXTRA_RULES_VAR: &str = "EXTRA_RULES";
+
+/// How to render the rules for `init`.
+#[derive(Debug, Clone)]
+pub struct RulesOptions {
+    pub lang: Lang,
+    /// Include `{{PUEUE_SECTION}}` (`--force-pueue`)
+    pub include_pueue: bool,
+    /// `--set-var KEY=VALUE` pairs, substituted for `{{KEY}}` in order
+    pub vars: Vec<(String, String)>,
+}
+
+impl RulesOptions {
+    pub fn new(lang: Lang, include_pueue: bool) -> Self {
+        Self {
+            lang,
+            include_pueue,
+            vars: Vec::new(),
+        }
     }
 }
 
+/// Parse `--set-var KEY=VALUE` arguments; keys are letters, digits and `_`.
+pub fn parse_rules_vars(i18n: &I18n, specs: &[String]) -> Result<Vec<(String, String)>> {
+    specs
+        .iter()
+        .map(|spec| match spec.split_once('=') {
+            Some((key, value))
+                if !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                Ok((key.to_string(), value.to_string()))
+            }
+            _ => Err(exit_code::validation(i18n.err_invalid_rules_var(spec))),
+        })
+        .collect()
+}
+
 pub(crate) fn rules_for_target_with_pueue(
-    i18n: &I18n,
+    lang: Lang,
     target: InitTarget,
-    _include_pueue: bool,
+    include_pueue: bool,
 ) -> String {
-    let template = match i18n.lang() {
-        Lang::Zh => SHNOTE_RULES_BASE,
-        Lang::En => SHNOTE_RULES_BASE_EN,
+    render_rules(target, &RulesOptions::new(lang, include_pueue))
+}
+
+/// The rules for `target`, warning about `{{...}}` placeholders that no
+/// built-in or `--set-var` value filled in.
+fn rules_for_init(i18n: &I18n, target: InitTarget, options: &RulesOptions) -> String {
+    let rules = render_rules(target, options);
+    for placeholder in leftover_placeholders(&rules) {
+        eprintln!("{}", i18n.warn_rules_placeholder_unset(placeholder));
+    }
+    rules
+}
+
+fn render_rules(target: InitTarget, options: &RulesOptions) -> String {
+    let (template, pueue_section) = match options.lang {
+        Lang::Zh => (SHNOTE_RULES_BASE, SHNOTE_RULES_PUEUE),
+        Lang::En => (SHNOTE_RULES_BASE_EN, SHNOTE_RULES_PUEUE_EN),
+        Lang::Ja => (SHNOTE_RULES_BASE_JA, SHNOTE_RULES_PUEUE_JA),
     };
-    let mut rules = template.replace(
-        "{{NON_SHNOTE_TOOLS}}",
-        non_shnote_tools_for_target(i18n.lang(), target),
-    );
-    if let Some(extra) = extra_rules_for_target(i18n.lang(), target) {
+    let target_rules = target_profile(target).rules(options.lang);
+    let extra_rules = options
+        .vars
+        .iter()
+        .rev()
+        .find(|(key, _)| key == EXTRA_RULES_VAR)
+        .map(|(_, value)| format!("\n{value}\n"))
+        .unwrap_or_default();
+    let mut rules = template
+        .replace("{{NON_SHNOTE_TOOLS}}", target_rules.non_shnote_tools)
+        .replace(
+            "{{PUEUE_SECTION}}",
+            if options.include_pueue {
+                pueue_section
+            } else {
+                ""
+            },
+        )
+        .replace("{{EXTRA_RULES}}", &extra_rules);
+    for (key, value) in &options.vars {
+        rules = rules.replace(&format!("{{{{{key}}}}}"), value);
+    }
+    if let Some(extra) = target_rules.extra {
         rules.push_str("\n\n");
         rules.push_str(extra);
     }
     rules
 }
 
+/// `{{NAME}}` placeholders still present in `rules`, each listed once.
+fn leftover_placeholders(rules: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = rules;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = &after[..end];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let placeholder = &rest[start..start + end + 4];
+            if !found.contains(&placeholder) {
+                found.push(placeholder);
+            }
+            rest = &after[end + 2..];
+        } else {
+            rest = after;
+        }
+    }
+    found
+}
+
+/// `shnote test-rules`: render the rules of every target in every language
+/// and check them as `init` would use them. Prints one line per target and
+/// language; returns whether all passed.
+pub fn test_rules(i18n: &I18n) -> bool {
+    let mut total = 0;
+    let mut failed = 0;
+    for target in InitTarget::ALL {
+        for lang in [Lang::Zh, Lang::En, Lang::Ja] {
+            total += 1;
+            let problems = rules_template_problems(i18n, target, lang);
+            let lang_tag = I18n::new(lang).lang_tag();
+            if problems.is_empty() {
+                println!("{}", i18n.test_rules_ok(target.name(), lang_tag));
+            } else {
+                failed += 1;
+                for problem in problems {
+                    println!(
+                        "{}",
+                        i18n.test_rules_failed(target.name(), lang_tag, &problem)
+                    );
+                }
+            }
+        }
+    }
+    println!();
+    println!("{}", i18n.test_rules_summary(failed, total));
+    failed == 0
+}
+
+/// Problems with `target`'s rules in `lang`: `{{...}}` placeholders left
+/// after rendering (with and without the pueue section), and a marked
+/// section that does not survive insertion into a file, extraction as
+/// `update --diff-rules` reads it, and a second `init`.
+fn rules_template_problems(i18n: &I18n, target: InitTarget, lang: Lang) -> Vec<String> {
+    let mut problems = Vec::new();
+    for include_pueue in [false, true] {
+        let rules = render_rules(target, &RulesOptions::new(lang, include_pueue));
+        let leftover = leftover_placeholders(&rules);
+        if !leftover.is_empty() {
+            problems.push(i18n.test_rules_leftover(&leftover.join(", "), include_pueue));
+        }
+    }
+
+    let rules = render_rules(target, &RulesOptions::new(lang, false));
+    let round_trips = [rules_preamble(target, Scope::Project), "# Notes\n"]
+        .into_iter()
+        .all(|existing| {
+            let (inserted, _) = with_marked_rules(existing, &rules);
+            let (updated, replaced) = with_marked_rules(&inserted, &rules);
+            inserted.starts_with(existing)
+                && extract_shnote_rules(&inserted).as_deref() == Some(rules.as_str())
+                && replaced
+                && updated == inserted
+        });
+    if !round_trips {
+        problems.push(i18n.test_rules_round_trip().to_string());
+    }
+    problems
+}
+
+#[cfg(test)]
 fn rules_for_target(i18n: &I18n, target: InitTarget) -> String {
-    rules_for_target_with_pueue(i18n, target, false)
+    rules_for_target_with_pueue(i18n.lang(), target, false)
 }
 
-pub fn run_init(i18n: &I18n, target: InitTarget, scope: Scope) -> Result<()> {
+pub fn run_init(
+    i18n: &I18n,
+    target: InitTarget,
+    scope: Scope,
+    dry_run: bool,
+    rules: &RulesOptions,
+    backup: bool,
+) -> Result<()> {
+    if dry_run {
+        let destination = resolve_destination(i18n, target, scope)?;
+        let rules = rules_for_init(i18n, target, rules);
+        for line in preview_destination(i18n, &destination) {
+            println!("{line}");
+        }
+        for diff in preview_diffs(&destination, rules_preamble(target, scope), &rules) {
+            print!("{diff}");
+        }
+        println!("{}", i18n.init_dry_run_done());
+        return Ok(());
+    }
+
     match target {
-        InitTarget::Claude => init_claude(i18n, scope),
-        InitTarget::Codex => init_codex(i18n, scope),
-        InitTarget::Gemini => init_gemini(i18n, scope),
+        InitTarget::Claude => init_claude(i18n, scope, rules, backup),
+        InitTarget::Codex => init_codex(i18n, scope, rules, backup),
+        InitTarget::Gemini => init_gemini(i18n, scope, rules, backup),
+        InitTarget::Agents => init_agents(i18n, scope, rules, backup),
+        InitTarget::Copilot => init_copilot(i18n, scope, rules, backup),
+        InitTarget::Cursor => init_cursor(i18n, scope, rules, backup),
+        InitTarget::Windsurf => init_windsurf(i18n, scope, rules, backup),
+        InitTarget::Aider => init_aider(i18n, scope, rules, backup),
+    }
+}
+
+/// Result of one target in `init --all`.
+#[derive(Debug, PartialEq, Eq)]
+enum InitOutcome {
+    Written,
+    /// The target's CLI is not in PATH
+    NotFound(&'static str),
+    /// The target has no CLI to detect (project-only instruction files)
+    NoCli,
+    Failed(String),
+}
+
+/// `init --all`: install rules for every target whose CLI is installed,
+/// going on after a failure. Prints a status line per target and returns
+/// whether every attempted write succeeded.
+pub fn run_init_all(
+    i18n: &I18n,
+    scope: Scope,
+    dry_run: bool,
+    rules: &RulesOptions,
+    backup: bool,
+) -> bool {
+    let outcomes = init_all_with(
+        |tool| detect_cli_tool(tool).path.is_some(),
+        |target| run_init(i18n, target, scope, dry_run, rules, backup),
+    );
+    println!();
+    for (target, outcome) in &outcomes {
+        let name = target.name();
+        let line = match outcome {
+            InitOutcome::Written => i18n.init_all_written(name),
+            InitOutcome::NotFound(tool) => i18n.init_all_not_found(name, tool),
+            InitOutcome::NoCli => i18n.init_all_no_cli(name),
+            InitOutcome::Failed(err) => i18n.init_all_failed(name, err),
+        };
+        println!("{line}");
     }
+    !outcomes
+        .iter()
+        .any(|(_, outcome)| matches!(outcome, InitOutcome::Failed(_)))
+}
+
+fn init_all_with(
+    installed: impl Fn(&str) -> bool,
+    mut init: impl FnMut(InitTarget) -> Result<()>,
+) -> Vec<(InitTarget, InitOutcome)> {
+    InitTarget::ALL
+        .into_iter()
+        .map(|target| {
+            let outcome = match target_profile(target).tool {
+                None => InitOutcome::NoCli,
+                Some(tool) if !installed(tool) => InitOutcome::NotFound(tool),
+                Some(_) => match init(target) {
+                    Ok(()) => InitOutcome::Written,
+                    Err(err) => InitOutcome::Failed(format!("{err:#}")),
+                },
+            };
+            (target, outcome)
+        })
+        .collect()
 }
 
 /// Get base directory for the given scope
@@ -156,37 +679,236 @@ fn get_base_dir(i18n: &I18n, scope: Scope) -> Result<PathBuf> {
     }
 }
 
-fn init_claude(i18n: &I18n, scope: Scope) -> Result<()> {
-    let probe = probe_cli_tool(i18n, "claude");
-    let base = get_base_dir(i18n, scope)?;
-    let rules = rules_for_target(i18n, InitTarget::Claude);
+/// Where `init` writes rules for a target, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InitDestination {
+    /// Directory that must exist before writing
+    dir: PathBuf,
+    /// File receiving the rules
+    file: PathBuf,
+    /// Whether the whole file is owned by shnote (overwritten) instead of a marked section
+    overwrite: bool,
+    /// Legacy file whose shnote section moves into `file`
+    migrate_from: Option<PathBuf>,
+}
+
+fn resolve_destination(i18n: &I18n, target: InitTarget, scope: Scope) -> Result<InitDestination> {
+    let probe = target_profile(target)
+        .tool
+        .map(|tool| probe_cli_tool(i18n, tool));
+    let claude_rules = probe.as_ref().is_some_and(claude_supports_rules);
+    destination_for(i18n, target, scope, claude_rules)
+}
+
+/// Where `target`'s rules go, given whether the installed claude supports
+/// `~/.claude/rules` (ignored for other targets).
+fn destination_for(
+    i18n: &I18n,
+    target: InitTarget,
+    scope: Scope,
+    claude_rules: bool,
+) -> Result<InitDestination> {
+    match target {
+        InitTarget::Claude => {
+            let base = get_base_dir(i18n, scope)?;
+            Ok(claude_destination(&base, claude_rules))
+        }
+        InitTarget::Codex => {
+            let base = get_base_dir(i18n, scope)?;
+            Ok(marked_destination(base.join(".codex"), "AGENTS.md"))
+        }
+        InitTarget::Gemini => {
+            let base = get_base_dir(i18n, scope)?;
+            Ok(marked_destination(base.join(".gemini"), "GEMINI.md"))
+        }
+        InitTarget::Agents => {
+            let base = get_base_dir(i18n, scope)?;
+            Ok(match scope {
+                Scope::User => marked_destination(base.join(".config"), "AGENTS.md"),
+                Scope::Project => marked_destination(base, "AGENTS.md"),
+            })
+        }
+        InitTarget::Copilot => {
+            if scope != Scope::Project {
+                anyhow::bail!("{}", i18n.err_copilot_requires_project_scope());
+            }
+            let base = get_base_dir(i18n, scope)?;
+            Ok(marked_destination(
+                base.join(".github"),
+                "copilot-instructions.md",
+            ))
+        }
+        InitTarget::Cursor => {
+            if scope != Scope::Project {
+                anyhow::bail!("{}", i18n.err_cursor_requires_project_scope());
+            }
+            let base = get_base_dir(i18n, scope)?;
+            Ok(marked_destination(
+                base.join(".cursor").join("rules"),
+                "shnote.mdc",
+            ))
+        }
+        InitTarget::Windsurf => {
+            let base = get_base_dir(i18n, scope)?;
+            Ok(match scope {
+                Scope::User => marked_destination(
+                    base.join(".codeium").join("windsurf").join("memories"),
+                    "global_rules.md",
+                ),
+                Scope::Project => {
+                    marked_destination(base.join(".windsurf").join("rules"), "shnote.md")
+                }
+            })
+        }
+        InitTarget::Aider => {
+            let base = get_base_dir(i18n, scope)?;
+            Ok(match scope {
+                Scope::User => marked_destination(base.join(".aider"), "CONVENTIONS.md"),
+                Scope::Project => marked_destination(base, "CONVENTIONS.md"),
+            })
+        }
+    }
+}
+
+fn marked_destination(dir: PathBuf, file_name: &str) -> InitDestination {
+    InitDestination {
+        file: dir.join(file_name),
+        dir,
+        overwrite: false,
+        migrate_from: None,
+    }
+}
 
-    // Claude Code >= 2.0.64 supports ~/.claude/rules/*.md.
-    // For older versions (or when version cannot be determined), append rules to ~/.claude/CLAUDE.md.
-    let claude_supports_rules = probe
+/// Claude Code >= 2.0.64 supports ~/.claude/rules/*.md.
+/// For older versions (or when version cannot be determined), append rules to ~/.claude/CLAUDE.md.
+fn claude_supports_rules(probe: &ToolProbe) -> bool {
+    probe
         .version
         .as_deref()
         .and_then(parse_semver_from_text)
-        .is_some_and(|v| v >= SemVer::new(2, 0, 64));
+        .is_some_and(|v| v >= SemVer::new(2, 0, 64))
+}
+
+fn claude_destination(base: &Path, supports_rules: bool) -> InitDestination {
+    let claude_dir = base.join(".claude");
+    let old_claude_md = claude_dir.join("CLAUDE.md");
+    if supports_rules {
+        let rules_dir = claude_dir.join("rules");
+        InitDestination {
+            file: rules_dir.join("shnote.md"),
+            dir: rules_dir,
+            overwrite: true,
+            migrate_from: old_claude_md.exists().then_some(old_claude_md),
+        }
+    } else {
+        marked_destination(claude_dir, "CLAUDE.md")
+    }
+}
+
+/// Human-readable list of the changes `init` would make for `destination`.
+fn preview_destination(i18n: &I18n, destination: &InitDestination) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !destination.dir.exists() {
+        lines.push(i18n.init_dry_run_create_dir(&destination.dir.display().to_string()));
+    }
+
+    let file = destination.file.display().to_string();
+    let existing = fs::read_to_string(&destination.file).ok();
+    let line = match (destination.overwrite, existing) {
+        (_, None) => i18n.init_dry_run_create_file(&file),
+        (true, Some(_)) => i18n.init_dry_run_overwrite_file(&file),
+        (false, Some(content)) if content.contains(SHNOTE_MARKER_START) => {
+            i18n.init_dry_run_update_rules(&file)
+        }
+        (false, Some(_)) => i18n.init_dry_run_append_rules(&file),
+    };
+    lines.push(line);
+
+    if let Some(old) = &destination.migrate_from {
+        let has_rules = fs::read_to_string(old)
+            .map(|content| content.contains(SHNOTE_MARKER_START))
+            .unwrap_or(false);
+        if has_rules {
+            lines.push(i18n.init_dry_run_migrate(&old.display().to_string()));
+        }
+    }
+    lines
+}
+
+/// Text a new rules file starts with for `target`, before the marked section.
+fn rules_preamble(target: InitTarget, scope: Scope) -> &'static str {
+    match (target, scope) {
+        (InitTarget::Cursor, _) => CURSOR_RULES_FRONTMATTER,
+        (InitTarget::Windsurf, Scope::Project) => WINDSURF_RULES_FRONTMATTER,
+        _ => "",
+    }
+}
+
+/// Unified diffs of what `init` would write to `destination` (and remove
+/// from a migrated legacy file), in the `update --diff-rules` format.
+/// Unchanged files produce no diff.
+fn preview_diffs(destination: &InitDestination, preamble: &str, rules: &str) -> Vec<String> {
+    let mut diffs = Vec::new();
+    let existing = fs::read_to_string(&destination.file).ok();
+    let new_content = if destination.overwrite {
+        rules.to_string()
+    } else {
+        with_marked_rules(existing.as_deref().unwrap_or(preamble), rules).0
+    };
+    let file = destination.file.display().to_string();
+    let old_name = if existing.is_some() {
+        &file
+    } else {
+        "/dev/null"
+    };
+    diffs.extend(file_diff(
+        old_name,
+        &file,
+        existing.as_deref().unwrap_or(""),
+        &new_content,
+    ));
+
+    if let Some(old) = &destination.migrate_from {
+        if let Ok(content) = fs::read_to_string(old) {
+            if let Some(remaining) = without_shnote_section(&content) {
+                let old_file = old.display().to_string();
+                let new_name = if remaining.is_empty() {
+                    "/dev/null"
+                } else {
+                    &old_file
+                };
+                diffs.extend(file_diff(&old_file, new_name, &content, &remaining));
+            }
+        }
+    }
+    diffs
+}
+
+fn file_diff(old_name: &str, new_name: &str, old: &str, new: &str) -> Option<String> {
+    (old != new).then(|| format!("--- {old_name}\n+++ {new_name}\n{}", render_diff(old, new)))
+}
 
-    let old_claude_md = base.join(".claude").join("CLAUDE.md");
+fn init_claude(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Claude, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Claude, rules);
+    let rules_dir = &destination.dir;
+    let target_file = &destination.file;
 
-    if claude_supports_rules {
-        let rules_dir = base.join(".claude").join("rules");
-        fs::create_dir_all(&rules_dir)
+    if destination.overwrite {
+        fs::create_dir_all(rules_dir)
             .context(i18n.err_create_dir(&rules_dir.display().to_string()))?;
-        let target_file = rules_dir.join("shnote.md");
 
         // Check if old CLAUDE.md has shnote rules that need migration
-        let migrated = if old_claude_md.exists() {
-            migrate_shnote_rules(i18n, &old_claude_md, &target_file, &rules)?
-        } else {
-            false
+        let migrated = match &destination.migrate_from {
+            Some(old_claude_md) => {
+                migrate_shnote_rules(i18n, old_claude_md, target_file, &rules, backup)?
+            }
+            None => false,
         };
 
         if !migrated {
             // No migration needed, just write the rules file
-            fs::write(&target_file, &rules)
+            fs::write(target_file, &rules)
                 .context(i18n.err_write_file(&target_file.display().to_string()))?;
         }
 
@@ -195,21 +917,21 @@ fn init_claude(i18n: &I18n, scope: Scope) -> Result<()> {
             i18n.init_claude_success(&target_file.display().to_string())
         );
         if migrated {
-            println!(
-                "{}",
-                i18n.init_migrated_from(&old_claude_md.display().to_string())
-            );
-            println!(
-                "{}",
-                i18n.init_old_rules_cleaned(&old_claude_md.display().to_string())
-            );
+            if let Some(old_claude_md) = &destination.migrate_from {
+                println!(
+                    "{}",
+                    i18n.init_migrated_from(&old_claude_md.display().to_string())
+                );
+                println!(
+                    "{}",
+                    i18n.init_old_rules_cleaned(&old_claude_md.display().to_string())
+                );
+            }
         }
     } else {
-        let claude_dir = base.join(".claude");
-        fs::create_dir_all(&claude_dir)
-            .context(i18n.err_create_dir(&claude_dir.display().to_string()))?;
-        let target_file = claude_dir.join("CLAUDE.md");
-        append_rules(i18n, &target_file, &rules)?;
+        fs::create_dir_all(rules_dir)
+            .context(i18n.err_create_dir(&rules_dir.display().to_string()))?;
+        append_rules(i18n, target_file, &rules, backup)?;
         println!(
             "{}",
             i18n.init_claude_success(&target_file.display().to_string())
@@ -226,40 +948,23 @@ fn migrate_shnote_rules(
     old_file: &Path,
     new_file: &Path,
     rules: &str,
+    backup: bool,
 ) -> Result<bool> {
     let content = fs::read_to_string(old_file)
         .context(i18n.err_read_file(&old_file.display().to_string()))?;
 
     // Check if shnote rules exist in old file
-    let Some(start_idx) = content.find(SHNOTE_MARKER_START) else {
+    let Some(new_content) = without_shnote_section(&content) else {
         return Ok(false);
     };
 
-    // Extract the shnote rules content (between markers)
-    let rules_start = start_idx + SHNOTE_MARKER_START.len();
-    let rules_end = content[rules_start..]
-        .find(SHNOTE_MARKER_END)
-        .map(|i| rules_start + i)
-        .unwrap_or(content.len());
-
-    let old_rules = content[rules_start..rules_end].to_string();
-
-    // Write extracted rules to new file (use latest rules, not old content)
-    // This ensures we always have the latest version
+    // Write the latest rules to the new file rather than the old section,
+    // so the migrated rules are always up to date
     fs::write(new_file, rules).context(i18n.err_write_file(&new_file.display().to_string()))?;
 
-    // Remove shnote rules from old file
-    let marker_end_idx = content
-        .find(SHNOTE_MARKER_END)
-        .map(|i| i + SHNOTE_MARKER_END.len())
-        .unwrap_or(content.len());
-
-    let mut new_content = String::new();
-    new_content.push_str(&content[..start_idx]);
-    new_content.push_str(&content[marker_end_idx..]);
-
-    // Trim trailing newlines that might have been left behind
-    let new_content = new_content.trim_end().to_string();
+    if backup {
+        backup_original(i18n, old_file)?;
+    }
 
     if new_content.is_empty() {
         // If the file would be empty, just delete it
@@ -269,24 +974,120 @@ fn migrate_shnote_rules(
             .context(i18n.err_write_file(&old_file.display().to_string()))?;
     }
 
-    // Suppress unused variable warning - we extract it for potential future use
-    let _ = old_rules;
-
     Ok(true)
 }
 
-fn init_codex(i18n: &I18n, scope: Scope) -> Result<()> {
-    let _ = probe_cli_tool(i18n, "codex");
-    let base = get_base_dir(i18n, scope)?;
-    let rules = rules_for_target(i18n, InitTarget::Codex);
-    let codex_dir = base.join(".codex");
-    let target_file = codex_dir.join("AGENTS.md");
+/// `init --remove`: take shnote's rules out of `target`'s file in `scope`.
+/// The marked section is cut out and the rest kept; a file left with
+/// nothing else (or only the frontmatter `init` created it with) is deleted,
+/// as is Claude's `rules/shnote.md`, which shnote owns whole. Both Claude
+/// locations are checked, whichever the installed version uses.
+pub fn run_remove(
+    i18n: &I18n,
+    target: InitTarget,
+    scope: Scope,
+    dry_run: bool,
+    backup: bool,
+) -> Result<()> {
+    let files = match target {
+        InitTarget::Claude => {
+            let base = get_base_dir(i18n, scope)?;
+            vec![
+                claude_destination(&base, true).file,
+                claude_destination(&base, false).file,
+            ]
+        }
+        _ => vec![destination_for(i18n, target, scope, false)?.file],
+    };
+
+    let mut found = false;
+    for file in files {
+        if !file.exists() {
+            continue;
+        }
+        let display = file.display().to_string();
+        let content = fs::read_to_string(&file).context(i18n.err_read_file(&display))?;
+        let owned = target == InitTarget::Claude && file.ends_with("rules/shnote.md");
+        let Some(remaining) = without_rules(&content, owned, rules_preamble(target, scope)) else {
+            continue;
+        };
+        found = true;
+
+        if dry_run {
+            let (line, new_name) = if remaining.is_empty() {
+                (i18n.init_dry_run_delete_file(&display), "/dev/null")
+            } else {
+                (i18n.init_dry_run_remove_rules(&display), display.as_str())
+            };
+            println!("{line}");
+            print!(
+                "{}",
+                file_diff(&display, new_name, &content, &remaining).unwrap_or_default()
+            );
+            continue;
+        }
+
+        if backup {
+            backup_original(i18n, &file)?;
+        }
+        if remaining.is_empty() {
+            fs::remove_file(&file).context(i18n.err_write_file(&display))?;
+            println!("{}", i18n.init_remove_deleted(&display));
+        } else {
+            fs::write(&file, remaining).context(i18n.err_write_file(&display))?;
+            println!("{}", i18n.init_remove_done(&display));
+        }
+    }
+
+    if !found {
+        println!("{}", i18n.init_remove_nothing(target.name()));
+    } else if dry_run {
+        println!("{}", i18n.init_dry_run_done());
+    }
+    Ok(())
+}
+
+/// What is left of a rules file once shnote's rules are removed: empty when
+/// the file should be deleted, `None` when it holds no shnote rules. An
+/// `owned` file is entirely shnote's.
+fn without_rules(content: &str, owned: bool, preamble: &str) -> Option<String> {
+    if owned {
+        return Some(String::new());
+    }
+    let remaining = without_shnote_section(content)?;
+    if remaining.is_empty() || remaining == preamble.trim_end() {
+        Some(String::new())
+    } else {
+        Some(format!("{remaining}\n"))
+    }
+}
+
+/// `content` with its marked shnote section removed and trailing whitespace
+/// trimmed; `None` when it has no section.
+fn without_shnote_section(content: &str) -> Option<String> {
+    let start_idx = content.find(SHNOTE_MARKER_START)?;
+    let end_idx = content
+        .find(SHNOTE_MARKER_END)
+        .map(|i| i + SHNOTE_MARKER_END.len())
+        .unwrap_or(content.len());
+
+    let mut new_content = String::new();
+    new_content.push_str(&content[..start_idx]);
+    new_content.push_str(&content[end_idx..]);
+    // Trim trailing newlines that might have been left behind
+    Some(new_content.trim_end().to_string())
+}
+
+fn init_codex(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Codex, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Codex, rules);
+    let codex_dir = &destination.dir;
+    let target_file = &destination.file;
 
     // Create directory if needed
-    fs::create_dir_all(&codex_dir)
-        .context(i18n.err_create_dir(&codex_dir.display().to_string()))?;
+    fs::create_dir_all(codex_dir).context(i18n.err_create_dir(&codex_dir.display().to_string()))?;
 
-    append_rules(i18n, &target_file, &rules)?;
+    append_rules(i18n, target_file, &rules, backup)?;
 
     println!(
         "{}",
@@ -295,18 +1096,17 @@ fn init_codex(i18n: &I18n, scope: Scope) -> Result<()> {
     Ok(())
 }
 
-fn init_gemini(i18n: &I18n, scope: Scope) -> Result<()> {
-    let _ = probe_cli_tool(i18n, "gemini");
-    let base = get_base_dir(i18n, scope)?;
-    let rules = rules_for_target(i18n, InitTarget::Gemini);
-    let gemini_dir = base.join(".gemini");
-    let target_file = gemini_dir.join("GEMINI.md");
+fn init_gemini(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Gemini, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Gemini, rules);
+    let gemini_dir = &destination.dir;
+    let target_file = &destination.file;
 
     // Create directory if needed
-    fs::create_dir_all(&gemini_dir)
+    fs::create_dir_all(gemini_dir)
         .context(i18n.err_create_dir(&gemini_dir.display().to_string()))?;
 
-    append_rules(i18n, &target_file, &rules)?;
+    append_rules(i18n, target_file, &rules, backup)?;
 
     println!(
         "{}",
@@ -315,144 +1115,267 @@ fn init_gemini(i18n: &I18n, scope: Scope) -> Result<()> {
     Ok(())
 }
 
-fn append_rules(i18n: &I18n, target_file: &PathBuf, rules: &str) -> Result<()> {
-    let mut content = if target_file.exists() {
-        fs::read_to_string(target_file)
-            .context(i18n.err_read_file(&target_file.display().to_string()))?
-    } else {
-        String::new()
-    };
-
-    // Check if shnote rules already exist
-    if content.contains(SHNOTE_MARKER_START) {
-        // Replace existing rules
-        let start_idx = content.find(SHNOTE_MARKER_START).unwrap();
-        let end_idx = content
-            .find(SHNOTE_MARKER_END)
-            .map(|i| i + SHNOTE_MARKER_END.len())
-            .unwrap_or(content.len());
-
-        let mut new_content = String::new();
-        new_content.push_str(&content[..start_idx]);
-        new_content.push_str(SHNOTE_MARKER_START);
-        new_content.push_str(rules);
-        new_content.push_str(SHNOTE_MARKER_END);
-        new_content.push_str(&content[end_idx..]);
-
-        fs::write(target_file, new_content)
-            .context(i18n.err_write_file(&target_file.display().to_string()))?;
-
-        println!("{}", i18n.init_rules_updated());
-    } else {
-        // Append new rules (rewrite the file to keep behavior deterministic and testable)
-        content.push_str(SHNOTE_MARKER_START);
-        content.push_str(rules);
-        content.push_str(SHNOTE_MARKER_END);
-
-        fs::write(target_file, content)
-            .context(i18n.err_write_file(&target_file.display().to_string()))?;
+/// A plain AGENTS.md is read by several agents: at the repository root in
+/// project scope, or `~/.config/AGENTS.md` as the shared user-level file.
+fn init_agents(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Agents, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Agents, rules);
+    let target_file = &destination.file;
 
-        println!("{}", i18n.init_rules_appended());
-    }
+    fs::create_dir_all(&destination.dir)
+        .context(i18n.err_create_dir(&destination.dir.display().to_string()))?;
+    append_rules(i18n, target_file, &rules, backup)?;
 
+    println!(
+        "{}",
+        i18n.init_agents_success(&target_file.display().to_string())
+    );
     Ok(())
 }
 
-#[derive(Debug, Clone)]
-struct ToolProbe {
-    #[allow(dead_code)]
-    tool: String,
-    #[allow(dead_code)]
-    path: Option<PathBuf>,
-    version: Option<String>,
-}
+/// GitHub Copilot reads `.github/copilot-instructions.md` from the repository,
+/// and has no user-level equivalent, so this is project scope only.
+fn init_copilot(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Copilot, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Copilot, rules);
+    let github_dir = &destination.dir;
+    let target_file = &destination.file;
 
-fn probe_cli_tool(i18n: &I18n, tool: &str) -> ToolProbe {
-    let Ok(path) = which(tool) else {
-        println!("{}", i18n.init_tool_not_found(tool));
-        return ToolProbe {
-            tool: tool.to_string(),
-            path: None,
-            version: None,
-        };
-    };
+    // Create directory if needed
+    fs::create_dir_all(github_dir)
+        .context(i18n.err_create_dir(&github_dir.display().to_string()))?;
+
+    append_rules(i18n, target_file, &rules, backup)?;
 
-    let version = get_tool_version(&path, "--version");
     println!(
         "{}",
-        i18n.init_tool_found(tool, &path.display().to_string(), version.as_deref())
+        i18n.init_copilot_success(&target_file.display().to_string())
     );
-
-    ToolProbe {
-        tool: tool.to_string(),
-        path: Some(path),
-        version,
-    }
+    Ok(())
 }
 
-fn get_tool_version(path: &PathBuf, flag: &str) -> Option<String> {
-    let output = Command::new(path).arg(flag).output().ok()?;
-    if !output.status.success() {
-        return None;
-    }
+/// Cursor reads project rules from `.cursor/rules/*.mdc`. Its user rules
+/// live in the app's settings rather than in a file, so this is project
+/// scope only.
+fn init_cursor(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Cursor, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Cursor, rules);
+    let rules_dir = &destination.dir;
+    let target_file = &destination.file;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Create directory if needed
+    fs::create_dir_all(rules_dir).context(i18n.err_create_dir(&rules_dir.display().to_string()))?;
 
-    let version_str = if stdout.trim().is_empty() {
-        stderr.trim()
-    } else {
-        stdout.trim()
-    };
+    let preamble = rules_preamble(InitTarget::Cursor, scope);
+    write_marked_rules(i18n, target_file, preamble, &rules, backup)?;
 
-    version_str.lines().next().map(|s| s.to_string())
+    println!(
+        "{}",
+        i18n.init_cursor_success(&target_file.display().to_string())
+    );
+    Ok(())
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
-struct SemVer {
-    major: u64,
-    minor: u64,
-    patch: u64,
-}
+/// Windsurf reads global rules from `~/.codeium/windsurf/memories/global_rules.md`
+/// and workspace rules from `.windsurf/rules/*.md`. The global file is shared
+/// with the user's own rules, so shnote only manages its marked section there.
+fn init_windsurf(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Windsurf, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Windsurf, rules);
+    let rules_dir = &destination.dir;
+    let target_file = &destination.file;
 
-impl SemVer {
-    const fn new(major: u64, minor: u64, patch: u64) -> Self {
-        Self {
-            major,
-            minor,
-            patch,
-        }
-    }
+    // Create directory if needed
+    fs::create_dir_all(rules_dir).context(i18n.err_create_dir(&rules_dir.display().to_string()))?;
+
+    let preamble = rules_preamble(InitTarget::Windsurf, scope);
+    write_marked_rules(i18n, target_file, preamble, &rules, backup)?;
+
+    println!(
+        "{}",
+        i18n.init_windsurf_success(&target_file.display().to_string())
+    );
+    Ok(())
 }
 
-fn parse_semver_from_text(text: &str) -> Option<SemVer> {
-    let start = text.find(|c: char| c.is_ascii_digit())?;
-    let mut end = start;
-    for (idx, c) in text[start..].char_indices() {
-        if matches!(c, '0'..='9' | '.') {
-            end = start + idx + c.len_utf8();
-        } else {
-            break;
+/// Aider reads conventions from a `CONVENTIONS.md` passed with `--read`.
+/// The file usually holds the team's own conventions too, so only the marked
+/// section is managed and the text around it is kept.
+fn init_aider(i18n: &I18n, scope: Scope, rules: &RulesOptions, backup: bool) -> Result<()> {
+    let destination = resolve_destination(i18n, InitTarget::Aider, scope)?;
+    let rules = rules_for_init(i18n, InitTarget::Aider, rules);
+    let aider_dir = &destination.dir;
+    let target_file = &destination.file;
+
+    // Create directory if needed
+    fs::create_dir_all(aider_dir).context(i18n.err_create_dir(&aider_dir.display().to_string()))?;
+
+    append_rules(i18n, target_file, &rules, backup)?;
+
+    println!(
+        "{}",
+        i18n.init_aider_success(&target_file.display().to_string())
+    );
+    Ok(())
+}
+
+fn append_rules(i18n: &I18n, target_file: &PathBuf, rules: &str, backup: bool) -> Result<()> {
+    write_marked_rules(i18n, target_file, "", rules, backup)
+}
+
+/// Replace the marked shnote section of `target_file`, or append one.
+/// `preamble` starts the file when it does not exist yet.
+fn write_marked_rules(
+    i18n: &I18n,
+    target_file: &PathBuf,
+    preamble: &str,
+    rules: &str,
+    backup: bool,
+) -> Result<()> {
+    let original = if target_file.exists() {
+        Some(
+            fs::read_to_string(target_file)
+                .context(i18n.err_read_file(&target_file.display().to_string()))?,
+        )
+    } else {
+        None
+    };
+    let content = original.as_deref().unwrap_or(preamble);
+    let (new_content, replaced) = with_marked_rules(content, rules);
+    let message = if replaced {
+        i18n.init_rules_updated()
+    } else {
+        i18n.init_rules_appended()
+    };
+
+    if backup
+        && original
+            .as_deref()
+            .is_some_and(|original| original != new_content)
+    {
+        backup_original(i18n, target_file)?;
+    }
+    fs::write(target_file, new_content)
+        .context(i18n.err_write_file(&target_file.display().to_string()))?;
+
+    println!("{message}");
+    Ok(())
+}
+
+/// `content` with its marked shnote section replaced by `rules`, or with a
+/// new section appended; the flag tells whether a section was replaced.
+fn with_marked_rules(content: &str, rules: &str) -> (String, bool) {
+    let mut new_content = String::new();
+    let (before, after, replaced) = match content.find(SHNOTE_MARKER_START) {
+        Some(start_idx) => {
+            let end_idx = content
+                .find(SHNOTE_MARKER_END)
+                .map(|i| i + SHNOTE_MARKER_END.len())
+                .unwrap_or(content.len());
+            (&content[..start_idx], &content[end_idx..], true)
+        }
+        None => (content, "", false),
+    };
+    new_content.push_str(before);
+    new_content.push_str(SHNOTE_MARKER_START);
+    new_content.push_str(rules);
+    new_content.push_str(SHNOTE_MARKER_END);
+    new_content.push_str(after);
+    (new_content, replaced)
+}
+
+/// `<file>.shnote.bak`, where `init --backup` keeps the pre-change file.
+fn backup_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".shnote.bak");
+    PathBuf::from(name)
+}
+
+/// Copy `file` to its backup path before `init` changes it.
+fn backup_original(i18n: &I18n, file: &Path) -> Result<()> {
+    let backup = backup_path(file);
+    let display = backup.display().to_string();
+    fs::copy(file, &backup).context(i18n.err_write_file(&display))?;
+    println!("{}", i18n.init_backup_saved(&display));
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ToolProbe {
+    pub(crate) tool: String,
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) version: Option<String>,
+}
+
+/// CLI program of the agent a target writes rules for, if it has one.
+pub(crate) fn target_tool(target: InitTarget) -> Option<&'static str> {
+    target_profile(target).tool
+}
+
+/// `init --list-targets`: each target with its rules file per scope and
+/// whether its CLI is installed.
+pub fn print_targets(i18n: &I18n) {
+    for line in target_list_lines(i18n, detect_cli_tool) {
+        println!("{line}");
+    }
+}
+
+fn target_list_lines(i18n: &I18n, detect: impl Fn(&str) -> ToolProbe) -> Vec<String> {
+    let mut lines = Vec::new();
+    for target in InitTarget::ALL {
+        let profile = target_profile(target);
+        lines.push(target.name().to_string());
+        lines.push(i18n.init_target_paths(profile.user_path, profile.project_path));
+        if let Some(tool) = profile.tool {
+            let probe = detect(tool);
+            let path = probe.path.as_ref().map(|path| path.display().to_string());
+            lines.push(i18n.init_target_detected(tool, path.as_deref()));
         }
     }
+    lines
+}
 
-    // Since find() guarantees start points to a digit, and the loop includes
-    // that digit, raw will always contain at least one digit after trimming.
-    let raw = text[start..end].trim_matches('.');
-
-    let mut parts = raw.split('.');
-    // split() always yields at least one element, even for empty string
-    let major_str = parts
-        .next()
-        .expect("split always yields at least one element");
-    let major = major_str.parse().ok()?;
-    let minor = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
-    let patch = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
-    Some(SemVer {
-        major,
-        minor,
-        patch,
-    })
+/// Look up `tool` in PATH and ask it for its version.
+pub(crate) fn detect_cli_tool(tool: &str) -> ToolProbe {
+    let path = which(tool).ok();
+    let version = path
+        .as_ref()
+        .and_then(|path| get_tool_version(path, "--version"));
+    ToolProbe {
+        tool: tool.to_string(),
+        path,
+        version,
+    }
+}
+
+fn probe_cli_tool(i18n: &I18n, tool: &str) -> ToolProbe {
+    let probe = detect_cli_tool(tool);
+    match &probe.path {
+        Some(path) => println!(
+            "{}",
+            i18n.init_tool_found(tool, &path.display().to_string(), probe.version.as_deref())
+        ),
+        None => println!("{}", i18n.init_tool_not_found(tool)),
+    }
+    probe
+}
+
+fn get_tool_version(path: &PathBuf, flag: &str) -> Option<String> {
+    let output = Command::new(path).arg(flag).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let version_str = if stdout.trim().is_empty() {
+        stderr.trim()
+    } else {
+        stdout.trim()
+    };
+
+    version_str.lines().next().map(|s| s.to_string())
 }
 
 #[cfg(test)]
@@ -472,16 +1395,97 @@ mod tests {
     #[test]
     fn shnote_rules_has_content() {
         // Verify rules contain expected content
-        assert!(SHNOTE_RULES_BASE.contains("shnote"));
-        assert!(SHNOTE_RULES_BASE_EN.contains("shnote"));
-        assert!(SHNOTE_RULES_BASE.contains("--what"));
-        assert!(SHNOTE_RULES_BASE_EN.contains("--what"));
-        assert!(SHNOTE_RULES_BASE.contains("--why"));
-        assert!(SHNOTE_RULES_BASE_EN.contains("--why"));
-        assert!(SHNOTE_RULES_BASE.contains("header_stream=auto"));
-        assert!(SHNOTE_RULES_BASE_EN.contains("header_stream=auto"));
-        assert!(SHNOTE_RULES_BASE.len() > 200);
-        assert!(SHNOTE_RULES_BASE_EN.len() > 200);
+        for base in [
+            SHNOTE_RULES_BASE,
+            SHNOTE_RULES_BASE_EN,
+            SHNOTE_RULES_BASE_JA,
+        ] {
+            assert!(base.contains("shnote"));
+            assert!(base.contains("--what"));
+            assert!(base.contains("--why"));
+            assert!(base.contains("header_stream=auto"));
+            assert!(base.len() > 200);
+        }
+    }
+
+    #[test]
+    fn target_profiles_render_the_original_rules() {
+        // Rules as rendered before per-target text moved into `TargetProfile`.
+        let cases: [(Lang, InitTarget, &str, Option<&str>); 8] = [
+            (Lang::Zh, InitTarget::Codex, "1. **只读查看文件**：直接用 shell，不通过 shnote。\n2. **非 shell 的内建工具**（读文件、列目录、编辑文件等）不通过 shnote。", Some(SHNOTE_RULES_CODEX_EXTRA)),
+            (Lang::En, InitTarget::Codex, "1. **Read-only file viewing**: use direct shell, not shnote.\n2. **Non-shell built-in tools** (read/list/edit operations) do not need shnote.", Some(SHNOTE_RULES_CODEX_EXTRA_EN)),
+            (Lang::Zh, InitTarget::Claude, "1. **仅 Bash 工具必须使用 shnote**：Read / Write / Edit 等工具不使用 shnote。", Some(SHNOTE_RULES_CLAUDE_EXTRA)),
+            (Lang::En, InitTarget::Claude, "1. **Only the Bash tool must use shnote**: Read / Write / Edit tools do not use shnote.", Some(SHNOTE_RULES_CLAUDE_EXTRA_EN)),
+            (Lang::Zh, InitTarget::Gemini, "1. **仅 run_shell_command 需要使用 shnote**：list_directory / read_file / write_file / replace 等工具不使用 shnote。", Some(SHNOTE_RULES_GEMINI_EXTRA)),
+            (Lang::En, InitTarget::Gemini, "1. **Only run_shell_command needs shnote**: list_directory / read_file / write_file / replace do not use shnote.", Some(SHNOTE_RULES_GEMINI_EXTRA_EN)),
+            (Lang::Zh, InitTarget::Agents, "1. **仅执行 shell 命令的工具需要使用 shnote**：读文件、列目录、编辑文件等内建工具不使用 shnote。", None),
+            (Lang::En, InitTarget::Agents, "1. **Only shell-execution tools need shnote**: built-in read/list/edit tools do not use shnote.", None),
+        ];
+        for (lang, target, tools, extra) in cases {
+            let base = match lang {
+                Lang::Zh => SHNOTE_RULES_BASE,
+                Lang::En => SHNOTE_RULES_BASE_EN,
+                Lang::Ja => SHNOTE_RULES_BASE_JA,
+            };
+            let mut expected = base
+                .replace("{{NON_SHNOTE_TOOLS}}", tools)
+                .replace("{{PUEUE_SECTION}}", "")
+                .replace("{{EXTRA_RULES}}", "");
+            if let Some(extra) = extra {
+                expected.push_str("\n\n");
+                expected.push_str(extra);
+            }
+            assert_eq!(rules_for_target(&I18n::new(lang), target), expected);
+        }
+    }
+
+    #[test]
+    fn target_profiles_probe_the_matching_cli() {
+        assert_eq!(target_profile(InitTarget::Claude).tool, Some("claude"));
+        assert_eq!(target_profile(InitTarget::Codex).tool, Some("codex"));
+        assert_eq!(target_profile(InitTarget::Gemini).tool, Some("gemini"));
+        assert_eq!(target_profile(InitTarget::Agents).tool, None);
+        assert_eq!(target_profile(InitTarget::Copilot).tool, None);
+        assert_eq!(target_profile(InitTarget::Cursor).tool, None);
+        assert_eq!(target_profile(InitTarget::Windsurf).tool, Some("windsurf"));
+        assert_eq!(target_profile(InitTarget::Aider).tool, Some("aider"));
+    }
+
+    #[test]
+    fn init_all_keeps_going_after_a_failure() {
+        let mut attempted = Vec::new();
+        let outcomes = init_all_with(
+            |tool| tool != "gemini",
+            |target| {
+                attempted.push(target);
+                if target == InitTarget::Codex {
+                    anyhow::bail!("disk full");
+                }
+                Ok(())
+            },
+        );
+
+        assert_eq!(
+            attempted,
+            [
+                InitTarget::Claude,
+                InitTarget::Codex,
+                InitTarget::Windsurf,
+                InitTarget::Aider
+            ]
+        );
+        let outcome = |target| &outcomes.iter().find(|(t, _)| *t == target).unwrap().1;
+        assert_eq!(outcome(InitTarget::Claude), &InitOutcome::Written);
+        assert_eq!(
+            outcome(InitTarget::Codex),
+            &InitOutcome::Failed("disk full".to_string())
+        );
+        assert_eq!(
+            outcome(InitTarget::Gemini),
+            &InitOutcome::NotFound("gemini")
+        );
+        assert_eq!(outcome(InitTarget::Cursor), &InitOutcome::NoCli);
+        assert_eq!(outcomes.len(), InitTarget::ALL.len());
     }
 
     #[test]
@@ -494,43 +1498,145 @@ mod tests {
 
     #[test]
     fn rules_do_not_include_pueue_section_when_available() {
+        // Whether pueue is installed does not matter: the section is opt-in
+        // via `init --force-pueue`.
         let i18n = test_i18n();
-        let rules = rules_for_target_with_pueue(&i18n, InitTarget::Codex, true);
+        let rules = rules_for_target(&i18n, InitTarget::Codex);
         assert!(!rules.contains("Long-running commands (use pueue)"));
     }
 
     #[test]
     fn rules_do_not_include_pueue_section_when_missing() {
         let i18n = test_i18n();
-        let rules = rules_for_target_with_pueue(&i18n, InitTarget::Codex, false);
+        let rules = rules_for_target_with_pueue(i18n.lang(), InitTarget::Codex, false);
         assert!(!rules.contains("Long-running commands (use pueue)"));
     }
 
     #[test]
-    fn markers_are_valid() {
-        assert!(SHNOTE_MARKER_START.contains("shnote"));
-        assert!(SHNOTE_MARKER_END.contains("shnote"));
+    fn set_var_fills_extra_rules_and_custom_placeholders() {
+        let options = RulesOptions {
+            vars: vec![
+                (
+                    "EXTRA_RULES".to_string(),
+                    "Team docs: {{DOCS_URL}}".to_string(),
+                ),
+                ("DOCS_URL".to_string(), "https://wiki.example".to_string()),
+            ],
+            ..RulesOptions::new(Lang::En, false)
+        };
+        let rules = render_rules(InitTarget::Agents, &options);
+        assert!(
+            rules.ends_with("do not use shnote.\n\nTeam docs: https://wiki.example\n"),
+            "{rules}"
+        );
+        assert!(leftover_placeholders(&rules).is_empty());
+
+        let plain = render_rules(InitTarget::Agents, &RulesOptions::new(Lang::En, false));
+        assert!(plain.ends_with("do not use shnote.\n"), "{plain}");
     }
 
     #[test]
-    fn parse_semver_from_text_parses_first_version_token() {
-        assert_eq!(
-            parse_semver_from_text("2.0.69 (Claude Code)"),
-            Some(SemVer::new(2, 0, 69))
-        );
-        assert_eq!(
-            parse_semver_from_text("codex-cli 0.72.0"),
-            Some(SemVer::new(0, 72, 0))
-        );
+    fn leftover_placeholders_lists_unfilled_names_once() {
+        let rules = "See {{DOCS_URL}} and {{DOCS_URL}}, ask {{TEAM}}; {{not a name}} {{";
+        assert_eq!(leftover_placeholders(rules), ["{{DOCS_URL}}", "{{TEAM}}"]);
+    }
+
+    #[test]
+    fn every_rules_template_renders_and_round_trips() {
+        let i18n = test_i18n();
+        for target in InitTarget::ALL {
+            for lang in [Lang::Zh, Lang::En, Lang::Ja] {
+                let problems = rules_template_problems(&i18n, target, lang);
+                assert!(problems.is_empty(), "{}: {problems:?}", target.name());
+            }
+        }
+        assert!(test_rules(&i18n));
+    }
+
+    #[test]
+    fn japanese_rules_use_japanese_target_text() {
+        let rules = render_rules(InitTarget::Claude, &RulesOptions::new(Lang::Ja, true));
+        assert!(rules.starts_with("# shell コマンドは shnote でラップして実行する"));
+        assert!(rules.contains("## 長時間実行するコマンド（pueue を使う）"));
+        assert!(rules.contains("**shnote が必須なのは Bash ツールだけ**"));
+        assert!(rules.ends_with(SHNOTE_RULES_CLAUDE_EXTRA_JA));
+    }
+
+    #[test]
+    fn parse_rules_vars_splits_on_first_equals() {
+        let i18n = test_i18n();
+        let specs = [
+            "URL=https://x.example/?a=b".to_string(),
+            "EMPTY=".to_string(),
+        ];
         assert_eq!(
-            parse_semver_from_text("v2.0.64"),
-            Some(SemVer::new(2, 0, 64))
+            parse_rules_vars(&i18n, &specs).unwrap(),
+            [
+                ("URL".to_string(), "https://x.example/?a=b".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
         );
-        assert_eq!(parse_semver_from_text("no version here"), None);
-        // Test version string with only dots returns None (line 553)
-        assert_eq!(parse_semver_from_text("..."), None);
-        // Test version with number too large to parse as u32
-        assert_eq!(parse_semver_from_text("99999999999999999999.0.0"), None);
+        for bad in ["novalue", "=x", "BAD KEY=x", "{{K}}=x"] {
+            let err = parse_rules_vars(&i18n, &[bad.to_string()]).unwrap_err();
+            assert_eq!(err.to_string(), i18n.err_invalid_rules_var(bad));
+        }
+    }
+
+    #[test]
+    fn force_pueue_includes_pueue_section_before_tool_list() {
+        for (lang, heading, tools) in [
+            (
+                Lang::En,
+                "## Long-running commands (use pueue)",
+                "## Operations that do not need shnote",
+            ),
+            (
+                Lang::Zh,
+                "## 长时间运行的命令（使用 pueue）",
+                "## 不需要通过 shnote 的操作",
+            ),
+        ] {
+            let rules = rules_for_target_with_pueue(lang, InitTarget::Claude, true);
+            let section = rules.find(heading).expect("pueue section");
+            assert!(section < rules.find(tools).unwrap());
+            assert!(!rules.contains("{{PUEUE_SECTION}}"));
+        }
+    }
+
+    #[test]
+    fn init_codex_respects_pueue_flag() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let agents_md = temp_dir.path().join(".codex").join("AGENTS.md");
+
+        init_codex(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), true),
+            false,
+        )
+        .unwrap();
+        let content = fs::read_to_string(&agents_md).unwrap();
+        assert!(content.contains("Long-running commands (use pueue)"));
+
+        init_codex(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+        let content = fs::read_to_string(&agents_md).unwrap();
+        assert!(!content.contains("Long-running commands (use pueue)"));
+        assert_eq!(content.matches("shnote rules start").count(), 1);
+    }
+
+    #[test]
+    fn markers_are_valid() {
+        assert!(SHNOTE_MARKER_START.contains("shnote"));
+        assert!(SHNOTE_MARKER_END.contains("shnote"));
     }
 
     #[cfg(unix)]
@@ -571,7 +1677,7 @@ mod tests {
         let target_file = temp_dir.path().join("test.md");
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        append_rules(&i18n, &target_file, &rules).unwrap();
+        append_rules(&i18n, &target_file, &rules, false).unwrap();
 
         assert!(target_file.exists());
         let content = fs::read_to_string(&target_file).unwrap();
@@ -598,7 +1704,7 @@ mod tests {
         .unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        append_rules(&i18n, &target_file, &rules).unwrap();
+        append_rules(&i18n, &target_file, &rules, false).unwrap();
 
         let content = fs::read_to_string(&target_file).unwrap();
         assert!(content.contains("Some content"));
@@ -607,6 +1713,60 @@ mod tests {
         assert!(content.contains(&rules));
     }
 
+    #[test]
+    fn append_rules_backs_up_original_when_changing_it() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let target_file = temp_dir.path().join("AGENTS.md");
+        let backup = temp_dir.path().join("AGENTS.md.shnote.bak");
+        let original = "# Project notes\n";
+        fs::write(&target_file, original).unwrap();
+
+        let rules = rules_for_target(&i18n, InitTarget::Codex);
+        append_rules(&i18n, &target_file, &rules, true).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+
+        // Rerunning changes nothing, so the backup keeps the first original
+        let updated = fs::read_to_string(&target_file).unwrap();
+        append_rules(&i18n, &target_file, &rules, true).unwrap();
+        assert_eq!(fs::read_to_string(&target_file).unwrap(), updated);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+    }
+
+    #[test]
+    fn append_rules_skips_backup_for_new_file_or_without_flag() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let rules = rules_for_target(&i18n, InitTarget::Codex);
+
+        let new_file = temp_dir.path().join("new.md");
+        append_rules(&i18n, &new_file, &rules, true).unwrap();
+        assert!(!backup_path(&new_file).exists());
+
+        let existing = temp_dir.path().join("existing.md");
+        fs::write(&existing, "content").unwrap();
+        append_rules(&i18n, &existing, &rules, false).unwrap();
+        assert!(!backup_path(&existing).exists());
+    }
+
+    #[test]
+    fn migrate_shnote_rules_backs_up_old_file() {
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let old_file = temp_dir.path().join("CLAUDE.md");
+        let new_file = temp_dir.path().join("shnote.md");
+        let original = format!("Mine{SHNOTE_MARKER_START}OLD{SHNOTE_MARKER_END}");
+        fs::write(&old_file, &original).unwrap();
+
+        let rules = rules_for_target(&i18n, InitTarget::Claude);
+        assert!(migrate_shnote_rules(&i18n, &old_file, &new_file, &rules, true).unwrap());
+        assert_eq!(fs::read_to_string(&old_file).unwrap(), "Mine");
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("CLAUDE.md.shnote.bak")).unwrap(),
+            original
+        );
+    }
+
     #[cfg(unix)]
     #[test]
     fn init_claude_writes_rules_file_when_claude_is_new_enough() {
@@ -619,7 +1779,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::User).unwrap();
+        init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
         assert!(rules_file.exists());
@@ -636,7 +1802,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::User).unwrap();
+        init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         let target_file = temp_dir.path().join(".claude/CLAUDE.md");
         assert!(target_file.exists());
@@ -659,7 +1831,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::User).unwrap();
+        init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         let target_file = temp_dir.path().join(".claude/CLAUDE.md");
         assert!(target_file.exists());
@@ -676,7 +1854,13 @@ mod tests {
         let _userprofile_guard = EnvVarGuard::remove("USERPROFILE");
 
         let i18n = test_i18n();
-        let err = init_claude(&i18n, Scope::User).unwrap_err();
+        let err = init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(i18n.err_home_dir()));
     }
 
@@ -695,7 +1879,13 @@ mod tests {
         fs::write(temp_dir.path().join(".claude"), "not a dir").unwrap();
 
         let i18n = test_i18n();
-        let err = init_claude(&i18n, Scope::User).unwrap_err();
+        let err = init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(
             &i18n.err_create_dir(&temp_dir.path().join(".claude/rules").display().to_string())
         ));
@@ -715,7 +1905,13 @@ mod tests {
         fs::create_dir_all(temp_dir.path().join(".claude/rules/shnote.md")).unwrap();
 
         let i18n = test_i18n();
-        let err = init_claude(&i18n, Scope::User).unwrap_err();
+        let err = init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(
             &i18n.err_write_file(
                 &temp_dir
@@ -744,7 +1940,13 @@ mod tests {
         fs::create_dir_all(temp_dir.path().join(".claude/CLAUDE.md")).unwrap();
 
         let i18n = test_i18n();
-        let err = init_claude(&i18n, Scope::User).unwrap_err();
+        let err = init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         let err_debug = format!("{:?}", err);
         assert!(err_debug.contains("CLAUDE.md"));
     }
@@ -776,7 +1978,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::User).unwrap();
+        init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         // Check new rules file exists with latest content
         let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
@@ -820,7 +2028,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::User).unwrap();
+        init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         // Check new rules file exists
         let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
@@ -850,7 +2064,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::User).unwrap();
+        init_claude(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         // Check new rules file exists with latest content
         let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
@@ -873,7 +2093,7 @@ mod tests {
         fs::write(&old_file, "Some content without markers").unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let migrated = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules).unwrap();
+        let migrated = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules, false).unwrap();
         assert!(!migrated);
         assert!(!new_file.exists());
     }
@@ -893,7 +2113,7 @@ mod tests {
         .unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let migrated = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules).unwrap();
+        let migrated = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules, false).unwrap();
         assert!(migrated);
         assert!(new_file.exists());
 
@@ -910,7 +2130,7 @@ mod tests {
         let new_file = temp_dir.path().join("new.md");
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let err = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules).unwrap_err();
+        let err = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules, false).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_read_file(&old_file.display().to_string())));
@@ -932,7 +2152,7 @@ mod tests {
         .unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let err = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules).unwrap_err();
+        let err = migrate_shnote_rules(&i18n, &old_file, &new_file, &rules, false).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_write_file(&new_file.display().to_string())));
@@ -945,7 +2165,13 @@ mod tests {
         let _userprofile_guard = EnvVarGuard::remove("USERPROFILE");
 
         let i18n = test_i18n();
-        let err = init_codex(&i18n, Scope::User).unwrap_err();
+        let err = init_codex(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(i18n.err_home_dir()));
     }
 
@@ -956,7 +2182,13 @@ mod tests {
         let _userprofile_guard = EnvVarGuard::remove("USERPROFILE");
 
         let i18n = test_i18n();
-        let err = init_gemini(&i18n, Scope::User).unwrap_err();
+        let err = init_gemini(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(i18n.err_home_dir()));
     }
 
@@ -970,7 +2202,13 @@ mod tests {
         fs::write(temp_dir.path().join(".codex"), "not a dir").unwrap();
 
         let i18n = test_i18n();
-        let err = init_codex(&i18n, Scope::User).unwrap_err();
+        let err = init_codex(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_create_dir(&temp_dir.path().join(".codex").display().to_string())));
@@ -986,7 +2224,13 @@ mod tests {
         fs::write(temp_dir.path().join(".gemini"), "not a dir").unwrap();
 
         let i18n = test_i18n();
-        let err = init_gemini(&i18n, Scope::User).unwrap_err();
+        let err = init_gemini(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(
             &i18n.err_create_dir(&temp_dir.path().join(".gemini").display().to_string())
         ));
@@ -1001,7 +2245,13 @@ mod tests {
         fs::create_dir_all(temp_dir.path().join(".codex/AGENTS.md")).unwrap();
 
         let i18n = test_i18n();
-        let err = init_codex(&i18n, Scope::User).unwrap_err();
+        let err = init_codex(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         // Check error chain contains the read error context (use Debug format to see full chain)
         let err_debug = format!("{:?}", err);
         assert!(err_debug.contains("AGENTS.md"));
@@ -1016,7 +2266,13 @@ mod tests {
         fs::create_dir_all(temp_dir.path().join(".gemini/GEMINI.md")).unwrap();
 
         let i18n = test_i18n();
-        let err = init_gemini(&i18n, Scope::User).unwrap_err();
+        let err = init_gemini(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
         // Check error chain contains the read error context (use Debug format to see full chain)
         let err_debug = format!("{:?}", err);
         assert!(err_debug.contains("GEMINI.md"));
@@ -1035,7 +2291,7 @@ mod tests {
         .unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        append_rules(&i18n, &target_file, &rules).unwrap();
+        append_rules(&i18n, &target_file, &rules, false).unwrap();
 
         let content = fs::read_to_string(&target_file).unwrap();
         assert!(content.contains("before"));
@@ -1052,7 +2308,7 @@ mod tests {
         fs::create_dir_all(&target_file).unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let err = append_rules(&i18n, &target_file, &rules).unwrap_err();
+        let err = append_rules(&i18n, &target_file, &rules, false).unwrap_err();
         // Check error chain contains the file path (use Debug format to see full chain)
         let err_debug = format!("{:?}", err);
         assert!(err_debug.contains("dir-as-file"));
@@ -1078,7 +2334,7 @@ mod tests {
         fs::set_permissions(&target_file, fs::Permissions::from_mode(0o444)).unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let err = append_rules(&i18n, &target_file, &rules).unwrap_err();
+        let err = append_rules(&i18n, &target_file, &rules, false).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_write_file(&target_file.display().to_string())));
@@ -1097,7 +2353,7 @@ mod tests {
         fs::set_permissions(&target_file, fs::Permissions::from_mode(0o444)).unwrap();
 
         let rules = rules_for_target(&i18n, InitTarget::Codex);
-        let err = append_rules(&i18n, &target_file, &rules).unwrap_err();
+        let err = append_rules(&i18n, &target_file, &rules, false).unwrap_err();
         assert!(err
             .to_string()
             .contains(&i18n.err_write_file(&target_file.display().to_string())));
@@ -1117,7 +2373,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", empty_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::Project).unwrap();
+        init_claude(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         // Check that rules were written to project directory
         let target_file = temp_dir.path().join(".claude/CLAUDE.md");
@@ -1142,7 +2404,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", tools_dir.path());
 
         let i18n = test_i18n();
-        init_claude(&i18n, Scope::Project).unwrap();
+        init_claude(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         // Check that rules were written to rules directory
         let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
@@ -1167,7 +2435,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", empty_dir.path());
 
         let i18n = test_i18n();
-        init_codex(&i18n, Scope::Project).unwrap();
+        init_codex(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         let target_file = temp_dir.path().join(".codex/AGENTS.md");
         assert!(target_file.exists());
@@ -1189,7 +2463,13 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", empty_dir.path());
 
         let i18n = test_i18n();
-        init_gemini(&i18n, Scope::Project).unwrap();
+        init_gemini(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
 
         let target_file = temp_dir.path().join(".gemini/GEMINI.md");
         assert!(target_file.exists());
@@ -1198,6 +2478,528 @@ mod tests {
         assert!(content.contains("shnote"));
     }
 
+    #[test]
+    fn init_agents_writes_root_agents_md_idempotently() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let target_file = temp_dir.path().join("AGENTS.md");
+        fs::write(&target_file, "# Project agents\n\nUse cargo.\n").unwrap();
+
+        let i18n = test_i18n();
+        init_agents(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+        let first = fs::read_to_string(&target_file).unwrap();
+        init_agents(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+        let second = fs::read_to_string(&target_file).unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.starts_with("# Project agents\n\nUse cargo.\n"));
+        assert_eq!(first.matches(SHNOTE_MARKER_START).count(), 1);
+        assert!(first.contains(&rules_for_target(&i18n, InitTarget::Agents)));
+        assert!(!temp_dir.path().join(".codex").exists());
+    }
+
+    #[test]
+    fn init_agents_user_scope_writes_config_agents_md() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_agents(&i18n, Scope::User, &options, false).unwrap();
+        init_agents(&i18n, Scope::User, &options, false).unwrap();
+
+        let content = fs::read_to_string(temp_dir.path().join(".config/AGENTS.md")).unwrap();
+        assert_eq!(content.matches(SHNOTE_MARKER_START).count(), 1);
+        assert!(content.contains(&rules_for_target(&i18n, InitTarget::Agents)));
+        assert!(!temp_dir.path().join("AGENTS.md").exists());
+    }
+
+    #[test]
+    fn init_copilot_creates_github_dir() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let i18n = test_i18n();
+        init_copilot(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+
+        let target_file = temp_dir.path().join(".github/copilot-instructions.md");
+        let content = fs::read_to_string(target_file).unwrap();
+        assert!(content.contains(SHNOTE_MARKER_START));
+        assert!(content.contains("run_in_terminal"));
+    }
+
+    #[test]
+    fn init_copilot_updates_existing_instructions_idempotently() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let github_dir = temp_dir.path().join(".github");
+        fs::create_dir(&github_dir).unwrap();
+        let target_file = github_dir.join("copilot-instructions.md");
+        fs::write(&target_file, "# Team conventions\n\nPrefer small PRs.\n").unwrap();
+
+        let i18n = test_i18n();
+        init_copilot(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+        let first = fs::read_to_string(&target_file).unwrap();
+        init_copilot(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+        let second = fs::read_to_string(&target_file).unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.starts_with("# Team conventions\n\nPrefer small PRs.\n"));
+        assert_eq!(first.matches(SHNOTE_MARKER_START).count(), 1);
+        assert!(first.contains(&rules_for_target(&i18n, InitTarget::Copilot)));
+    }
+
+    #[test]
+    fn init_copilot_rejects_user_scope() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let i18n = test_i18n();
+        let err = init_copilot(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(i18n.err_copilot_requires_project_scope()));
+        assert!(!temp_dir.path().join(".github").exists());
+    }
+
+    #[test]
+    fn init_cursor_writes_mdc_with_frontmatter() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_cursor(&i18n, Scope::Project, &options, false).unwrap();
+        let target_file = temp_dir.path().join(".cursor/rules/shnote.mdc");
+        let first = fs::read_to_string(&target_file).unwrap();
+        assert!(first.starts_with(
+            "---\ndescription: Run shell commands through shnote with WHAT/WHY\nalwaysApply: true\n---\n"
+        ));
+        assert!(first.contains(SHNOTE_MARKER_START));
+        assert!(first.contains(&rules_for_target(&i18n, InitTarget::Cursor)));
+        assert!(first.contains("run_terminal_cmd"));
+
+        // Updating replaces the marked section and keeps the frontmatter
+        init_cursor(&i18n, Scope::Project, &options, false).unwrap();
+        let second = fs::read_to_string(&target_file).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(second.matches("alwaysApply").count(), 1);
+    }
+
+    #[test]
+    fn init_cursor_keeps_existing_mdc_content() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let rules_dir = temp_dir.path().join(".cursor/rules");
+        fs::create_dir_all(&rules_dir).unwrap();
+        let target_file = rules_dir.join("shnote.mdc");
+        let custom = "---\ndescription: team shell rules\nalwaysApply: false\n---\nUse bash.\n";
+        fs::write(&target_file, custom).unwrap();
+
+        let i18n = test_i18n();
+        init_cursor(
+            &i18n,
+            Scope::Project,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap();
+        let content = fs::read_to_string(&target_file).unwrap();
+        assert!(content.starts_with(custom));
+        assert_eq!(content.matches("---\ndescription").count(), 1);
+    }
+
+    #[test]
+    fn init_cursor_rejects_user_scope() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let i18n = test_i18n();
+        let err = init_cursor(
+            &i18n,
+            Scope::User,
+            &RulesOptions::new(i18n.lang(), false),
+            false,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(i18n.err_cursor_requires_project_scope()));
+        assert!(!temp_dir.path().join(".cursor").exists());
+    }
+
+    #[test]
+    fn init_aider_updates_existing_conventions() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+        let target_file = temp_dir.path().join("CONVENTIONS.md");
+        fs::write(
+            &target_file,
+            format!(
+                "# Conventions\nUse tabs.\n{}OLD RULES{}\n## Testing\nRun make test.\n",
+                SHNOTE_MARKER_START, SHNOTE_MARKER_END
+            ),
+        )
+        .unwrap();
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_aider(&i18n, Scope::Project, &options, false).unwrap();
+        init_aider(&i18n, Scope::Project, &options, false).unwrap();
+
+        let content = fs::read_to_string(&target_file).unwrap();
+        let rules = rules_for_target(&i18n, InitTarget::Aider);
+        assert!(content.starts_with("# Conventions\nUse tabs.\n"));
+        assert!(content.ends_with("\n## Testing\nRun make test.\n"));
+        assert!(!content.contains("OLD RULES"));
+        assert!(content.contains(&rules));
+        assert_eq!(content.matches(SHNOTE_MARKER_START).count(), 1);
+        assert_eq!(content.matches(SHNOTE_MARKER_END).count(), 1);
+    }
+
+    #[test]
+    fn remove_keeps_content_outside_the_markers() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+        let target_file = temp_dir.path().join("CONVENTIONS.md");
+        fs::write(
+            &target_file,
+            format!(
+                "# Conventions\nUse tabs.\n{}OLD RULES{}\n## Testing\nRun make test.\n",
+                SHNOTE_MARKER_START, SHNOTE_MARKER_END
+            ),
+        )
+        .unwrap();
+
+        let i18n = test_i18n();
+        run_remove(&i18n, InitTarget::Aider, Scope::Project, false, true).unwrap();
+
+        let content = fs::read_to_string(&target_file).unwrap();
+        assert_eq!(
+            content,
+            "# Conventions\nUse tabs.\n\n## Testing\nRun make test.\n"
+        );
+        assert!(backup_path(&target_file).exists());
+
+        // A second run finds nothing and leaves the file alone
+        run_remove(&i18n, InitTarget::Aider, Scope::Project, false, false).unwrap();
+        assert_eq!(fs::read_to_string(&target_file).unwrap(), content);
+    }
+
+    #[test]
+    fn remove_deletes_files_left_with_only_shnote_content() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_cursor(&i18n, Scope::Project, &options, false).unwrap();
+        let rules_dir = temp_dir.path().join(".claude/rules");
+        fs::create_dir_all(&rules_dir).unwrap();
+        fs::write(rules_dir.join("shnote.md"), "rules\n").unwrap();
+        fs::write(
+            temp_dir.path().join(".claude/CLAUDE.md"),
+            format!("{SHNOTE_MARKER_START}old{SHNOTE_MARKER_END}\n"),
+        )
+        .unwrap();
+
+        run_remove(&i18n, InitTarget::Cursor, Scope::Project, false, false).unwrap();
+        run_remove(&i18n, InitTarget::Claude, Scope::User, false, false).unwrap();
+
+        assert!(!temp_dir.path().join(".cursor/rules/shnote.mdc").exists());
+        assert!(!rules_dir.join("shnote.md").exists());
+        assert!(!temp_dir.path().join(".claude/CLAUDE.md").exists());
+    }
+
+    #[test]
+    fn remove_dry_run_changes_nothing() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_codex(&i18n, Scope::User, &options, false).unwrap();
+        let target_file = temp_dir.path().join(".codex/AGENTS.md");
+        let before = fs::read_to_string(&target_file).unwrap();
+
+        run_remove(&i18n, InitTarget::Codex, Scope::User, true, false).unwrap();
+        assert_eq!(fs::read_to_string(&target_file).unwrap(), before);
+    }
+
+    #[test]
+    fn without_rules_handles_owned_files_and_preambles() {
+        let section = format!("{SHNOTE_MARKER_START}rules{SHNOTE_MARKER_END}\n");
+        assert_eq!(without_rules("anything", true, ""), Some(String::new()));
+        assert_eq!(without_rules("# Mine\n", false, ""), None);
+        assert_eq!(
+            without_rules(&format!("# Mine\n{section}"), false, ""),
+            Some("# Mine\n".to_string())
+        );
+        assert_eq!(
+            without_rules(
+                &format!("{CURSOR_RULES_FRONTMATTER}{section}"),
+                false,
+                CURSOR_RULES_FRONTMATTER
+            ),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn init_aider_user_scope_writes_home_conventions() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_aider(&i18n, Scope::User, &options, false).unwrap();
+        init_aider(&i18n, Scope::User, &options, false).unwrap();
+
+        let content = fs::read_to_string(temp_dir.path().join(".aider/CONVENTIONS.md")).unwrap();
+        assert_eq!(content.matches(SHNOTE_MARKER_START).count(), 1);
+        assert!(content.contains("SEARCH/REPLACE"));
+    }
+
+    #[test]
+    fn init_windsurf_user_scope_keeps_global_rules_idempotently() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let memories = temp_dir.path().join(".codeium/windsurf/memories");
+        fs::create_dir_all(&memories).unwrap();
+        let target_file = memories.join("global_rules.md");
+        fs::write(&target_file, "Prefer pnpm.\n").unwrap();
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_windsurf(&i18n, Scope::User, &options, false).unwrap();
+        let first = fs::read_to_string(&target_file).unwrap();
+        assert!(first.starts_with("Prefer pnpm.\n"));
+        assert!(first.contains(&rules_for_target(&i18n, InitTarget::Windsurf)));
+        assert!(!first.contains("trigger:"));
+
+        init_windsurf(&i18n, Scope::User, &options, false).unwrap();
+        let second = fs::read_to_string(&target_file).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(second.matches("shnote rules start").count(), 1);
+    }
+
+    #[test]
+    fn init_windsurf_project_scope_writes_always_on_rule() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _cwd_guard = CurrentDirGuard::set(temp_dir.path()).unwrap();
+
+        let i18n = test_i18n();
+        let options = RulesOptions::new(i18n.lang(), false);
+        init_windsurf(&i18n, Scope::Project, &options, false).unwrap();
+        let target_file = temp_dir.path().join(".windsurf/rules/shnote.md");
+        let first = fs::read_to_string(&target_file).unwrap();
+        assert!(first.starts_with("---\ntrigger: always_on\n---\n"));
+        assert!(first.contains("run_command"));
+
+        init_windsurf(&i18n, Scope::Project, &options, false).unwrap();
+        assert_eq!(fs::read_to_string(&target_file).unwrap(), first);
+    }
+
+    #[test]
+    fn run_init_dry_run_creates_nothing() {
+        let _lock = env_lock();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let empty_dir = TempDir::new().unwrap();
+        let _path_guard = EnvVarGuard::set("PATH", empty_dir.path());
+
+        let i18n = test_i18n();
+        for target in [InitTarget::Claude, InitTarget::Codex, InitTarget::Gemini] {
+            run_init(
+                &i18n,
+                target,
+                Scope::User,
+                true,
+                &RulesOptions::new(i18n.lang(), false),
+                false,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn preview_destination_reports_create_append_and_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let i18n = test_i18n();
+        let destination = marked_destination(temp_dir.path().join(".codex"), "AGENTS.md");
+        let file = destination.file.display().to_string();
+
+        let lines = preview_destination(&i18n, &destination);
+        assert_eq!(
+            lines,
+            vec![
+                i18n.init_dry_run_create_dir(&destination.dir.display().to_string()),
+                i18n.init_dry_run_create_file(&file),
+            ]
+        );
+
+        fs::create_dir_all(&destination.dir).unwrap();
+        fs::write(&destination.file, "# notes\n").unwrap();
+        assert_eq!(
+            preview_destination(&i18n, &destination),
+            vec![i18n.init_dry_run_append_rules(&file)]
+        );
+
+        fs::write(
+            &destination.file,
+            format!("# notes\n{SHNOTE_MARKER_START}old{SHNOTE_MARKER_END}"),
+        )
+        .unwrap();
+        assert_eq!(
+            preview_destination(&i18n, &destination),
+            vec![i18n.init_dry_run_update_rules(&file)]
+        );
+    }
+
+    #[test]
+    fn preview_destination_reports_claude_migration() {
+        let temp_dir = TempDir::new().unwrap();
+        let i18n = test_i18n();
+        let claude_dir = temp_dir.path().join(".claude");
+        fs::create_dir_all(&claude_dir).unwrap();
+        let old_claude_md = claude_dir.join("CLAUDE.md");
+        fs::write(
+            &old_claude_md,
+            format!("# mine\n{SHNOTE_MARKER_START}old{SHNOTE_MARKER_END}"),
+        )
+        .unwrap();
+
+        let destination = claude_destination(temp_dir.path(), true);
+        let lines = preview_destination(&i18n, &destination);
+        assert_eq!(
+            lines,
+            vec![
+                i18n.init_dry_run_create_dir(&destination.dir.display().to_string()),
+                i18n.init_dry_run_create_file(&destination.file.display().to_string()),
+                i18n.init_dry_run_migrate(&old_claude_md.display().to_string()),
+            ]
+        );
+        assert!(!destination.dir.exists());
+    }
+
+    #[test]
+    fn preview_diffs_show_new_and_changed_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let destination = marked_destination(temp_dir.path().join(".cursor"), "shnote.mdc");
+        let file = destination.file.display().to_string();
+
+        let diffs = preview_diffs(&destination, CURSOR_RULES_FRONTMATTER, "RULES");
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].starts_with(&format!("--- /dev/null\n+++ {file}\n+---\n")));
+        assert!(diffs[0].contains("+<!-- shnote rules start -->\n+RULES\n"));
+
+        fs::create_dir_all(&destination.dir).unwrap();
+        fs::write(
+            &destination.file,
+            format!("# notes{SHNOTE_MARKER_START}OLD{SHNOTE_MARKER_END}# tail\n"),
+        )
+        .unwrap();
+        let diffs = preview_diffs(&destination, "", "RULES");
+        assert!(diffs[0].starts_with(&format!("--- {file}\n+++ {file}\n")));
+        assert!(diffs[0].contains("\n-OLD\n+RULES\n"));
+        assert!(diffs[0].contains("\n # tail\n"));
+
+        fs::write(
+            &destination.file,
+            format!("# notes{SHNOTE_MARKER_START}RULES{SHNOTE_MARKER_END}"),
+        )
+        .unwrap();
+        assert!(preview_diffs(&destination, "", "RULES").is_empty());
+    }
+
+    #[test]
+    fn preview_diffs_show_claude_md_removal_on_migration() {
+        let temp_dir = TempDir::new().unwrap();
+        let claude_dir = temp_dir.path().join(".claude");
+        fs::create_dir_all(&claude_dir).unwrap();
+        let old_claude_md = claude_dir.join("CLAUDE.md");
+        let old = old_claude_md.display().to_string();
+
+        fs::write(
+            &old_claude_md,
+            format!("# mine\n{SHNOTE_MARKER_START}OLD{SHNOTE_MARKER_END}"),
+        )
+        .unwrap();
+        let destination = claude_destination(temp_dir.path(), true);
+        let diffs = preview_diffs(&destination, "", "RULES");
+        assert_eq!(diffs.len(), 2);
+        assert!(diffs[0].ends_with("+RULES\n"));
+        assert!(diffs[1].starts_with(&format!("--- {old}\n+++ {old}\n # mine\n")));
+        assert!(diffs[1].contains("-OLD\n"));
+
+        // Only shnote rules in CLAUDE.md: the file would be deleted
+        fs::write(
+            &old_claude_md,
+            format!("{SHNOTE_MARKER_START}OLD{SHNOTE_MARKER_END}"),
+        )
+        .unwrap();
+        let diffs = preview_diffs(&destination, "", "RULES");
+        assert!(diffs[1].starts_with(&format!("--- {old}\n+++ /dev/null\n")));
+        assert!(!destination.dir.exists());
+    }
+
     #[test]
     fn get_base_dir_user_returns_home() {
         let _lock = env_lock();
@@ -1225,4 +3027,36 @@ mod tests {
             temp_dir.path().canonicalize().unwrap()
         );
     }
+
+    #[test]
+    fn init_target_all_matches_init_subcommands() {
+        use clap::CommandFactory;
+
+        let cmd = crate::cli::Cli::command();
+        let init = cmd.find_subcommand("init").unwrap();
+        let subcommands: Vec<_> = init.get_subcommands().map(|sub| sub.get_name()).collect();
+        let names: Vec<_> = InitTarget::ALL.iter().map(|target| target.name()).collect();
+        assert_eq!(subcommands, names);
+    }
+
+    #[test]
+    fn target_list_lines_cover_every_target() {
+        let i18n = I18n::new(Lang::En);
+        let lines = target_list_lines(&i18n, |tool| ToolProbe {
+            tool: tool.to_string(),
+            path: (tool == "codex").then(|| PathBuf::from("/bin/codex")),
+            version: None,
+        });
+
+        for target in InitTarget::ALL {
+            assert!(lines.iter().any(|line| line == target.name()), "{target:?}");
+        }
+        assert!(lines.contains(&"  codex: found (/bin/codex)".to_string()));
+        assert!(lines.contains(&"  claude: not found in PATH".to_string()));
+        assert!(lines.contains(
+            &"  user: (project scope only)\n  project: .github/copilot-instructions.md".to_string()
+        ));
+        // Targets without a CLI are not probed
+        assert!(!lines.iter().any(|line| line.contains("agents:")));
+    }
 }
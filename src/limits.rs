@@ -0,0 +1,265 @@
+//! Resource limits (`run --mem-limit/--cpu-limit`) applied to child processes.
+//!
+//! On Unix the limits are installed with `setrlimit` between `fork` and `exec`,
+//! so the child and everything it spawns inherits them. Other platforms run
+//! the command unrestricted, after a warning from the executor.
+
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+use crate::i18n::I18n;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Address-space limit in MiB (`RLIMIT_AS`)
+    pub mem_mb: Option<u64>,
+    /// CPU time limit in seconds (`RLIMIT_CPU`)
+    pub cpu_secs: Option<u64>,
+}
+
+/// Which limit most likely ended the child.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LimitBreach {
+    Cpu(u64),
+    Mem(u64),
+    MaybeMem(u64),
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.mem_mb.is_none() && self.cpu_secs.is_none()
+    }
+
+    /// Install the limits on `cmd` so they take effect in the child only.
+    #[cfg(unix)]
+    pub fn apply(&self, cmd: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        if self.is_empty() {
+            return;
+        }
+        let limits = *self;
+        // SAFETY: the closure runs between fork and exec and only calls
+        // setrlimit, which is async-signal-safe, and does not allocate.
+        unsafe {
+            cmd.pre_exec(move || limits.set_rlimits());
+        }
+    }
+
+    /// Other platforms cannot limit the child; the caller warns instead.
+    #[cfg(not(unix))]
+    pub fn apply(&self, _cmd: &mut Command) {}
+
+    #[cfg(unix)]
+    fn set_rlimits(self) -> std::io::Result<()> {
+        if let Some(mb) = self.mem_mb {
+            let bytes = mb.saturating_mul(1024 * 1024);
+            // SAFETY: the rlimit struct is valid for the duration of the call.
+            check_rlimit(unsafe { libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes, bytes)) })?;
+        }
+        if let Some(secs) = self.cpu_secs {
+            // The soft limit delivers SIGXCPU; the hard limit one second later is SIGKILL.
+            let limit = rlimit(secs, secs.saturating_add(1));
+            // SAFETY: the rlimit struct is valid for the duration of the call.
+            check_rlimit(unsafe { libc::setrlimit(libc::RLIMIT_CPU, &limit) })?;
+        }
+        Ok(())
+    }
+
+    /// A localized explanation when `status` looks like a limit kill;
+    /// `cpu_used` is the child's measured CPU time, if known.
+    pub fn explain(
+        &self,
+        i18n: &I18n,
+        status: &ExitStatus,
+        cpu_used: Option<Duration>,
+    ) -> Option<String> {
+        Some(
+            match self.breach(signal_of(status), status.success(), cpu_used)? {
+                LimitBreach::Cpu(secs) => i18n.run_killed_cpu_limit(secs),
+                LimitBreach::Mem(mb) => i18n.run_killed_mem_limit(mb),
+                LimitBreach::MaybeMem(mb) => i18n.run_failed_mem_limit_hint(mb),
+            },
+        )
+    }
+
+    fn breach(
+        &self,
+        signal: Option<i32>,
+        success: bool,
+        cpu_used: Option<Duration>,
+    ) -> Option<LimitBreach> {
+        if success {
+            return None;
+        }
+        if let (Some(secs), Some(sig)) = (self.cpu_secs, signal) {
+            if is_cpu_limit_kill(sig, secs, cpu_used) {
+                return Some(LimitBreach::Cpu(secs));
+            }
+        }
+        let mb = self.mem_mb?;
+        // Allocation failures surface either as a crash or as an ordinary error exit
+        // (e.g. Python's MemoryError), so only a crash signal is reported as certain.
+        Some(match signal {
+            Some(sig) if is_mem_limit_signal(sig) => LimitBreach::Mem(mb),
+            _ => LimitBreach::MaybeMem(mb),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn rlimit(soft: u64, hard: u64) -> libc::rlimit {
+    libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    }
+}
+
+#[cfg(unix)]
+fn check_rlimit(ret: libc::c_int) -> std::io::Result<()> {
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(unix)]
+fn signal_of(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal_of(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// SIGXCPU comes from the soft limit. SIGKILL can come from anywhere, so it
+/// only counts once the child has used up its CPU time.
+#[cfg(unix)]
+fn is_cpu_limit_kill(sig: i32, secs: u64, cpu_used: Option<Duration>) -> bool {
+    sig == libc::SIGXCPU
+        || (sig == libc::SIGKILL && cpu_used.is_some_and(|used| used >= Duration::from_secs(secs)))
+}
+
+#[cfg(not(unix))]
+fn is_cpu_limit_kill(_sig: i32, _secs: u64, _cpu_used: Option<Duration>) -> bool {
+    false
+}
+
+/// Signals a failed allocation typically ends in.
+#[cfg(unix)]
+fn is_mem_limit_signal(sig: i32) -> bool {
+    matches!(sig, libc::SIGSEGV | libc::SIGABRT | libc::SIGBUS)
+}
+
+#[cfg(not(unix))]
+fn is_mem_limit_signal(_sig: i32) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measure::{self, Usage};
+
+    #[test]
+    fn breach_ignores_success_and_unlimited_runs() {
+        let limits = ResourceLimits {
+            mem_mb: Some(64),
+            cpu_secs: Some(1),
+        };
+        assert_eq!(limits.breach(None, true, None), None);
+        assert_eq!(ResourceLimits::default().breach(Some(9), false, None), None);
+        assert_eq!(ResourceLimits::default().breach(None, false, None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn breach_distinguishes_memory_failures() {
+        let limits = ResourceLimits {
+            mem_mb: Some(64),
+            cpu_secs: None,
+        };
+        for sig in [libc::SIGSEGV, libc::SIGABRT, libc::SIGBUS] {
+            assert_eq!(
+                limits.breach(Some(sig), false, None),
+                Some(LimitBreach::Mem(64))
+            );
+        }
+        for sig in [libc::SIGINT, libc::SIGTERM, libc::SIGKILL] {
+            assert_eq!(
+                limits.breach(Some(sig), false, None),
+                Some(LimitBreach::MaybeMem(64))
+            );
+        }
+        assert_eq!(
+            limits.breach(None, false, None),
+            Some(LimitBreach::MaybeMem(64))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn breach_detects_cpu_limit_signals() {
+        let limits = ResourceLimits {
+            mem_mb: None,
+            cpu_secs: Some(2),
+        };
+        assert_eq!(
+            limits.breach(Some(libc::SIGXCPU), false, None),
+            Some(LimitBreach::Cpu(2))
+        );
+        assert_eq!(
+            limits.breach(Some(libc::SIGKILL), false, Some(Duration::from_secs(3))),
+            Some(LimitBreach::Cpu(2))
+        );
+        assert_eq!(
+            limits.breach(Some(libc::SIGKILL), false, Some(Duration::from_millis(10))),
+            None
+        );
+        assert_eq!(limits.breach(Some(libc::SIGKILL), false, None), None);
+        assert_eq!(limits.breach(Some(libc::SIGTERM), false, None), None);
+        assert_eq!(limits.breach(None, false, None), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn apply_kills_cpu_spinning_child() {
+        let limits = ResourceLimits {
+            mem_mb: None,
+            cpu_secs: Some(1),
+        };
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c").arg("while :; do :; done");
+        limits.apply(&mut cmd);
+
+        let before = Usage::of_children();
+        let status = cmd.status().unwrap();
+        assert!(!status.success());
+        let cpu_used = measure::cpu_time_since(before);
+        assert_eq!(
+            limits.breach(signal_of(&status), false, cpu_used),
+            Some(LimitBreach::Cpu(1))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn apply_caps_memory_of_child() {
+        let Ok(python) = which::which("python3") else {
+            return;
+        };
+        let limits = ResourceLimits {
+            mem_mb: Some(256),
+            cpu_secs: None,
+        };
+        let mut cmd = Command::new(python);
+        cmd.arg("-c").arg("b = bytearray(1024 * 1024 * 1024)");
+        limits.apply(&mut cmd);
+
+        let status = cmd.status().unwrap();
+        assert!(!status.success());
+    }
+}
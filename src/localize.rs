@@ -5,6 +5,7 @@
 
 use clap::Command;
 
+use crate::config::CONFIG_KEYS;
 use crate::i18n::I18n;
 
 /// Localize all help text in a Command tree.
@@ -21,7 +22,11 @@ pub fn localize_command(cmd: Command, i18n: &I18n) -> Command {
         .collect();
 
     // Start by localizing the current command's about text
-    let mut cmd = cmd.about(get_command_about(&name, i18n));
+    let mut cmd = if name == "config" {
+        cmd.about(config_about(i18n))
+    } else {
+        cmd.about(get_command_about(&name, i18n))
+    };
 
     // Localize arguments
     cmd = localize_args(cmd, &name, i18n);
@@ -34,12 +39,34 @@ pub fn localize_command(cmd: Command, i18n: &I18n) -> Command {
     cmd
 }
 
+/// About text for `config`: a table of every key in [`CONFIG_KEYS`] with its
+/// accepted values and localized description.
+fn config_about(i18n: &I18n) -> String {
+    let mut about = format!(
+        "{}\n\n{}",
+        i18n.help_cmd_config(),
+        i18n.help_config_keys_heading()
+    );
+    for spec in CONFIG_KEYS {
+        let values = spec.values.join("|");
+        let description = i18n.config_key_description(spec.key);
+        let text = match (values.is_empty(), description.is_empty()) {
+            (_, true) => values,
+            (true, false) => description.to_string(),
+            (false, false) => i18n.help_config_values_with_note(&values, description),
+        };
+        about.push_str(&format!("\n  {:<21} - {text}", spec.key));
+    }
+    about
+}
+
 fn get_command_about(name: &str, i18n: &I18n) -> &'static str {
     match name {
         "shnote" => i18n.help_app_about(),
         "run" => i18n.help_cmd_run(),
         "py" => i18n.help_cmd_py(),
         "node" => i18n.help_cmd_node(),
+        "sh" => i18n.help_cmd_sh(),
         "pip" => i18n.help_cmd_pip(),
         "npm" => i18n.help_cmd_npm(),
         "npx" => i18n.help_cmd_npx(),
@@ -49,18 +76,32 @@ fn get_command_about(name: &str, i18n: &I18n) -> &'static str {
         "doctor" => i18n.help_cmd_doctor(),
         "completions" => i18n.help_cmd_completions(),
         "info" => i18n.help_cmd_info(),
+        "history" => i18n.help_cmd_history(),
+        "replay" => i18n.help_cmd_replay(),
         "update" => i18n.help_cmd_update(),
+        "batch" => i18n.help_cmd_batch(),
+        "prune" => i18n.help_cmd_prune(),
+        "test-rules" => i18n.help_cmd_test_rules(),
         "uninstall" => i18n.help_cmd_uninstall(),
         // Config subcommands
         "get" => i18n.help_cmd_config_get(),
         "set" => i18n.help_cmd_config_set(),
         "list" => i18n.help_cmd_config_list(),
+        "diff" => i18n.help_cmd_config_diff(),
         "reset" => i18n.help_cmd_config_reset(),
         "path" => i18n.help_cmd_config_path(),
+        // History subcommands
+        "clear" => i18n.help_cmd_history_clear(),
+        "export" => i18n.help_cmd_history_export(),
         // Init subcommands
         "claude" => i18n.help_cmd_init_claude(),
         "codex" => i18n.help_cmd_init_codex(),
         "gemini" => i18n.help_cmd_init_gemini(),
+        "agents" => i18n.help_cmd_init_agents(),
+        "copilot" => i18n.help_cmd_init_copilot(),
+        "cursor" => i18n.help_cmd_init_cursor(),
+        "windsurf" => i18n.help_cmd_init_windsurf(),
+        "aider" => i18n.help_cmd_init_aider(),
         _ => "", // Keep original for unknown commands
     }
 }
@@ -73,23 +114,166 @@ fn localize_args(cmd: Command, cmd_name: &str, i18n: &I18n) -> Command {
             .mut_arg("lang", |arg| arg.help(i18n.help_arg_lang()))
             .mut_arg("header_stream", |arg| {
                 arg.help(i18n.help_arg_header_stream())
+            })
+            .mut_arg("plain", |arg| arg.help(i18n.help_arg_plain()))
+            .mut_arg("agent_mode", |arg| arg.help(i18n.help_arg_agent_mode()))
+            .mut_arg("allow_nonzero", |arg| {
+                arg.help(i18n.help_arg_allow_nonzero())
+            })
+            .mut_arg("note_tag", |arg| arg.help(i18n.help_arg_note_tag()))
+            .mut_arg("capture", |arg| arg.help(i18n.help_arg_capture()))
+            .mut_arg("offline", |arg| arg.help(i18n.help_arg_offline()))
+            .mut_arg("version_check_interval", |arg| {
+                arg.help(i18n.help_arg_version_check_interval())
             }),
-        "run" => cmd.mut_arg("command", |arg| arg.help(i18n.help_arg_command())),
-        "py" | "node" => cmd
+        "run" => cmd
+            .mut_arg("on_failure", |arg| arg.help(i18n.help_arg_on_failure()))
+            .mut_arg("on_success", |arg| arg.help(i18n.help_arg_on_success()))
+            .mut_arg("cwd", |arg| arg.help(i18n.help_arg_cwd()))
+            .mut_arg("chdir_to_git_root", |arg| {
+                arg.help(i18n.help_arg_chdir_to_git_root())
+            })
+            .mut_arg("no_cwd_inherit", |arg| {
+                arg.help(i18n.help_arg_no_cwd_inherit())
+            })
+            .mut_arg("until", |arg| arg.help(i18n.help_arg_until()))
+            .mut_arg("until_timeout", |arg| {
+                arg.help(i18n.help_arg_until_timeout())
+            })
+            .mut_arg("exec_prefix", |arg| arg.help(i18n.help_arg_exec_prefix()))
+            .mut_arg("mem_limit", |arg| arg.help(i18n.help_arg_mem_limit()))
+            .mut_arg("cpu_limit", |arg| arg.help(i18n.help_arg_cpu_limit()))
+            .mut_arg("exit_on", |arg| arg.help(i18n.help_arg_exit_on()))
+            .mut_arg("tty", |arg| arg.help(i18n.help_arg_tty()))
+            .mut_arg("no_tty", |arg| arg.help(i18n.help_arg_no_tty()))
+            .mut_arg("stdin_tty_passthrough", |arg| {
+                arg.help(i18n.help_arg_stdin_tty_passthrough())
+            })
+            .mut_arg("raw", |arg| arg.help(i18n.help_arg_run_raw()))
+            .mut_arg("binary_safe", |arg| arg.help(i18n.help_arg_binary_safe()))
+            .mut_arg("print_env", |arg| arg.help(i18n.help_arg_print_env()))
+            .mut_arg("announce", |arg| arg.help(i18n.help_arg_announce()))
+            .mut_arg("log_file", |arg| arg.help(i18n.help_arg_log_file()))
+            .mut_arg("capture_on_failure_only", |arg| {
+                arg.help(i18n.help_arg_capture_on_failure_only())
+            })
+            .mut_arg("redirect_stdout", |arg| {
+                arg.help(i18n.help_arg_redirect_stdout())
+            })
+            .mut_arg("redirect_stderr", |arg| {
+                arg.help(i18n.help_arg_redirect_stderr())
+            })
+            .mut_arg("redirect_append", |arg| {
+                arg.help(i18n.help_arg_redirect_append())
+            })
+            .mut_arg("print_pid", |arg| arg.help(i18n.help_arg_print_pid()))
+            .mut_arg("pid_fd", |arg| arg.help(i18n.help_arg_pid_fd()))
+            .mut_arg("group", |arg| arg.help(i18n.help_arg_run_group()))
+            .mut_arg("confirm_each", |arg| arg.help(i18n.help_arg_confirm_each()))
+            .mut_arg("yes", |arg| arg.help(i18n.help_arg_run_yes()))
+            .mut_arg("measure", |arg| arg.help(i18n.help_arg_measure()))
+            .mut_arg("measure_output_size", |arg| {
+                arg.help(i18n.help_arg_measure_output_size())
+            })
+            .mut_arg("args_from", |arg| arg.help(i18n.help_arg_args_from()))
+            .mut_arg("once", |arg| arg.help(i18n.help_arg_once()))
+            .mut_arg("label", |arg| arg.help(i18n.help_arg_label()))
+            .mut_arg("force", |arg| arg.help(i18n.help_arg_once_force()))
+            .mut_arg("command", |arg| arg.help(i18n.help_arg_command())),
+        "py" | "node" | "sh" => cmd
             .mut_arg("code", |arg| arg.help(i18n.help_arg_code()))
             .mut_arg("file", |arg| arg.help(i18n.help_arg_file()))
             .mut_arg("stdin", |arg| arg.help(i18n.help_arg_stdin()))
+            .mut_arg("interpreter", |arg| arg.help(i18n.help_arg_interpreter()))
             .mut_arg("args", |arg| arg.help(i18n.help_arg_script_args())),
-        "pip" | "npm" | "npx" => cmd.mut_arg("args", |arg| arg.help(i18n.help_arg_passthrough())),
+        "pip" | "npm" | "npx" => cmd
+            .mut_arg("cwd", |arg| arg.help(i18n.help_arg_cwd()))
+            .mut_arg("args", |arg| arg.help(i18n.help_arg_passthrough())),
         "update" => cmd
             .mut_arg("check", |arg| arg.help(i18n.help_arg_update_check()))
-            .mut_arg("force", |arg| arg.help(i18n.help_arg_update_force())),
+            .mut_arg("force", |arg| arg.help(i18n.help_arg_update_force()))
+            .mut_arg("allow_downgrade", |arg| {
+                arg.help(i18n.help_arg_update_allow_downgrade())
+            })
+            .mut_arg("hash", |arg| arg.help(i18n.help_arg_update_hash())),
+        "init" => cmd
+            .mut_arg("dry_run", |arg| arg.help(i18n.help_arg_init_dry_run()))
+            .mut_arg("no_pueue", |arg| arg.help(i18n.help_arg_init_no_pueue()))
+            .mut_arg("force_pueue", |arg| {
+                arg.help(i18n.help_arg_init_force_pueue())
+            })
+            .mut_arg("backup", |arg| arg.help(i18n.help_arg_init_backup()))
+            .mut_arg("list_targets", |arg| {
+                arg.help(i18n.help_arg_init_list_targets())
+            })
+            .mut_arg("all", |arg| arg.help(i18n.help_arg_init_all()))
+            .mut_arg("remove", |arg| arg.help(i18n.help_arg_init_remove()))
+            .mut_arg("rules_lang", |arg| {
+                arg.help(i18n.help_arg_init_rules_lang())
+            })
+            .mut_arg("set_var", |arg| arg.help(i18n.help_arg_init_set_var())),
+        "doctor" => cmd
+            .mut_arg("check_locale", |arg| {
+                arg.help(i18n.help_arg_doctor_check_locale())
+            })
+            .mut_arg("check_updates", |arg| {
+                arg.help(i18n.help_arg_doctor_check_updates())
+            })
+            .mut_arg("check_history", |arg| {
+                arg.help(i18n.help_arg_doctor_check_history())
+            })
+            .mut_arg("check_rules_drift", |arg| {
+                arg.help(i18n.help_arg_doctor_check_rules_drift())
+            })
+            .mut_arg("check", |arg| arg.help(i18n.help_arg_doctor_check()))
+            .mut_arg("json", |arg| arg.help(i18n.help_arg_doctor_json())),
+        "history" => cmd
+            .mut_arg("tag", |arg| arg.help(i18n.help_arg_history_tag()))
+            .mut_arg("since", |arg| arg.help(i18n.help_arg_history_since()))
+            .mut_arg("limit", |arg| arg.help(i18n.help_arg_history_limit()))
+            .mut_arg("json", |arg| arg.help(i18n.help_arg_history_json())),
+        "replay" => cmd
+            .mut_arg("id", |arg| arg.help(i18n.help_arg_replay_id()))
+            .mut_arg("reuse_note", |arg| {
+                arg.help(i18n.help_arg_replay_reuse_note())
+            })
+            .mut_arg("yes", |arg| arg.help(i18n.help_arg_replay_yes())),
+        "clear" => cmd
+            .mut_arg("yes", |arg| arg.help(i18n.help_arg_uninstall_yes()))
+            .mut_arg("archive", |arg| arg.help(i18n.help_arg_history_archive())),
+        "export" => cmd.mut_arg("output", |arg| arg.help(i18n.help_arg_history_output())),
+        "setup" => cmd
+            .mut_arg("yes", |arg| arg.help(i18n.help_arg_setup_yes()))
+            .mut_arg("check_only", |arg| {
+                arg.help(i18n.help_arg_setup_check_only())
+            }),
         "uninstall" => cmd.mut_arg("yes", |arg| arg.help(i18n.help_arg_uninstall_yes())),
-        "get" => cmd.mut_arg("key", |arg| arg.help(i18n.help_arg_config_key())),
+        "batch" => cmd
+            .mut_arg("file", |arg| arg.help(i18n.help_arg_batch_file()))
+            .mut_arg("fail_fast", |arg| arg.help(i18n.help_arg_batch_fail_fast()))
+            .mut_arg("keep_going", |arg| {
+                arg.help(i18n.help_arg_batch_keep_going())
+            })
+            .mut_arg("parallel", |arg| arg.help(i18n.help_arg_batch_parallel()))
+            .mut_arg("json", |arg| arg.help(i18n.help_arg_batch_json())),
+        "get" => cmd
+            .mut_arg("key", |arg| arg.help(i18n.help_arg_config_key()))
+            .mut_arg("show_source", |arg| {
+                arg.help(i18n.help_arg_config_show_source())
+            })
+            .mut_arg("raw", |arg| arg.help(i18n.help_arg_config_raw()))
+            .mut_arg("no_newline", |arg| {
+                arg.help(i18n.help_arg_config_no_newline())
+            }),
         "set" => cmd
             .mut_arg("key", |arg| arg.help(i18n.help_arg_config_key_short()))
-            .mut_arg("value", |arg| arg.help(i18n.help_arg_config_value())),
-        "completions" => cmd.mut_arg("shell", |arg| arg.help(i18n.help_arg_shell())),
+            .mut_arg("value", |arg| arg.help(i18n.help_arg_config_value()))
+            .mut_arg("dry_run", |arg| arg.help(i18n.help_arg_config_dry_run())),
+        "list" => cmd.mut_arg("format", |arg| arg.help(i18n.help_arg_config_list_format())),
+        "diff" => cmd.mut_arg("json", |arg| arg.help(i18n.help_arg_config_diff_json())),
+        "completions" => cmd
+            .mut_arg("shell", |arg| arg.help(i18n.help_arg_shell()))
+            .mut_arg("list", |arg| arg.help(i18n.help_arg_completions_list())),
         _ => cmd, // No args to localize for other commands
     }
 }
@@ -171,6 +355,31 @@ mod tests {
         assert!(py_help.contains("脚本文件路径"));
     }
 
+    #[test]
+    fn config_about_lists_every_settable_key() {
+        use crate::config::Config;
+
+        for lang in [Lang::En, Lang::Zh, Lang::Ja] {
+            let i18n = I18n::new(lang);
+            let about = config_about(&i18n);
+            for (key, _) in Config::default().list() {
+                let line = about
+                    .lines()
+                    .find(|line| line.trim_start().starts_with(&format!("{key} ")))
+                    .unwrap_or_else(|| panic!("{key} missing from config help"));
+                assert!(!line.trim_end().ends_with('-'), "{key} has no help: {line}");
+            }
+        }
+    }
+
+    #[test]
+    fn config_about_shows_values_and_descriptions() {
+        let about = config_about(&I18n::new(Lang::En));
+        assert!(about.contains("  shell                 - auto|sh|bash|zsh|pwsh|cmd\n"));
+        assert!(about.contains("  history               - true|false (record executed commands)\n"));
+        assert!(about.contains("  python                - Python interpreter path"));
+    }
+
     #[test]
     fn get_command_about_returns_empty_for_unknown() {
         let i18n = I18n::new(Lang::En);
@@ -1,28 +1,53 @@
+mod agent;
+mod announce;
+mod args_file;
+mod batch;
+mod capture;
 mod cli;
 mod config;
 mod doctor;
+mod elapsed;
 mod executor;
+mod exit_code;
+mod history;
 mod i18n;
 mod info;
 mod init;
+mod limits;
 mod localize;
+mod measure;
+mod onboarding;
+mod once;
+mod prompt;
+mod prune;
 mod pueue;
 mod pueue_embed;
+mod replay;
+mod semver;
 mod shell;
+mod shell_lint;
 #[cfg(test)]
 mod test_support;
+mod transcript;
+mod tty;
 mod uninstall;
+mod until;
 mod update;
+mod update_notice;
 
 use std::io::{self, IsTerminal, Write};
 use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use clap::{CommandFactory, FromArgMatches};
+use clap::{CommandFactory, FromArgMatches, ValueEnum};
 use clap_complete::{generate, Shell as CompletionShell};
 
-use crate::cli::{Cli, Command, ConfigAction, HeaderStream, Shell};
-use crate::config::{Config, HeaderStreamMode, HeaderTiming};
+use crate::cli::{Cli, Command, ConfigAction, ConfigListFormat, HeaderStream, RulesLang, Shell};
+use crate::config::{Config, ConfigSource, HeaderStreamMode, HeaderTiming};
+use crate::elapsed::format_elapsed;
+use crate::executor::RunOutcome;
+use crate::history::HistoryRecord;
 use crate::i18n::I18n;
 
 struct HeaderPlan {
@@ -38,9 +63,10 @@ fn main() -> ExitCode {
     // 1. Pre-parse to extract --lang argument (if any)
     let pre_args: Vec<String> = std::env::args().collect();
     let lang_override = extract_lang_arg(&pre_args);
+    let agent_requested = agent::requested(&pre_args);
 
     // 2. Load config (ignore errors, use defaults)
-    let config = Config::load().unwrap_or_default();
+    let mut config = Config::load().unwrap_or_default();
 
     // 3. Detect language
     let lang = i18n::detect_lang(lang_override.as_deref(), &config.i18n.language);
@@ -51,42 +77,84 @@ fn main() -> ExitCode {
     let cmd = localize::localize_command(cmd, &i18n);
 
     // 5. Parse arguments with localized command
-    // Note: get_matches() handles all parsing errors (exits on failure),
-    // so from_arg_matches cannot fail with a valid ArgMatches.
-    let cli = Cli::from_arg_matches(&cmd.get_matches())
-        .expect("clap derive should match parsed arguments");
-
-    // Validate --what/--why
-    if let Err(e) = cli::validate_what_why(&i18n, &cli) {
+    // Note: parse errors exit here (as JSON in agent mode), so from_arg_matches
+    // cannot fail with a valid ArgMatches.
+    let matches = match cmd.try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) if agent_requested && e.use_stderr() => {
+            return agent::report_error(e.to_string().trim());
+        }
+        Err(e) => e.exit(),
+    };
+    let mut cli =
+        Cli::from_arg_matches(&matches).expect("clap derive should match parsed arguments");
+    let i18n = i18n.with_plain(cli.plain || cli.agent_mode);
+
+    // Resolve `replay` into the recorded command, validate --what/--why, then
+    // append `run --args-from` arguments so history records the full command
+    if let Err(e) = replay::prepare(&i18n, &mut cli)
+        .and_then(|()| cli::validate_what_why(&i18n, &cli))
+        .and_then(|()| cli::validate_version_check_interval(&i18n, &cli))
+        .and_then(|()| args_file::expand(&i18n, &mut cli))
+        .and_then(|()| {
+            if cli.command.expands_config_env() {
+                config.expand_env_vars(&i18n)
+            } else {
+                Ok(())
+            }
+        })
+    {
+        if cli.agent_mode {
+            return agent::report_error(&e.to_string());
+        }
         eprintln!("error: {e}");
-        return ExitCode::from(1);
+        return ExitCode::from(exit_code::for_error(&e));
     }
 
-    let header_plan = if cli.command.requires_what_why() && config.should_print_header() {
+    if cli.agent_mode {
+        config.apply_agent_mode();
+    } else if cli.plain {
+        config.apply_plain();
+    }
+
+    onboarding::maybe_offer(&i18n, &config, &cli);
+
+    if cli.command.requires_what_why() {
+        if let Some(what) = cli.what.as_deref() {
+            config.output_prefix = config.render_output_prefix(what);
+        }
+    }
+
+    // `run --raw` skips every shnote decoration except the history record
+    let raw = matches!(&cli.command, Command::Run(args) if args.raw);
+
+    // Interactive commands get the terminal to themselves: no piped output
+    // prefix, and the header goes to stderr unless --header-stream says otherwise
+    let tty_passthrough = match &cli.command {
+        Command::Run(args) => {
+            tty::wants_passthrough(args, io::stdin().is_terminal(), io::stdout().is_terminal())
+        }
+        _ => false,
+    };
+    if tty_passthrough {
+        config.output_prefix.clear();
+    }
+
+    let header_plan = if cli.command.requires_what_why() && config.should_print_header() && !raw {
         // Safe: `validate_what_why` above guarantees these are present for execution commands.
         let what = cli.what.as_deref().expect("validated --what");
         let why = cli.why.as_deref().expect("validated --why");
         let stream_mode = cli
             .header_stream
             .map(header_stream_arg_to_mode)
-            .unwrap_or_else(|| config.header_stream_mode());
-        let use_color = config.should_color_header();
-        let what_label = if use_color {
-            match config.what_color_escape() {
-                Some(code) => format!("\x1b[{code}mWHAT\x1b[0m"),
-                None => "WHAT".to_string(),
-            }
-        } else {
-            "WHAT".to_string()
-        };
-        let why_label = if use_color {
-            match config.why_color_escape() {
-                Some(code) => format!("\x1b[{code}mWHY\x1b[0m"),
-                None => "WHY".to_string(),
-            }
-        } else {
-            "WHY".to_string()
-        };
+            .unwrap_or_else(|| {
+                if tty_passthrough {
+                    HeaderStreamMode::Stderr
+                } else {
+                    config.header_stream_mode()
+                }
+            });
+        let (what_label, why_label) = config.header_prefixes(&i18n);
         Some(HeaderPlan {
             stream_mode: resolve_header_stream(stream_mode),
             timing: config.header_timing_mode(),
@@ -105,8 +173,97 @@ fn main() -> ExitCode {
         }
     }
 
+    if let Command::Doctor(args) = &mut cli.command {
+        args.offline = cli.offline;
+    }
+
+    if let Command::Run(args) = &mut cli.command {
+        args.note = cli.what.clone().zip(cli.why.clone());
+        if args.announce {
+            let what = cli.what.as_deref().expect("validated --what");
+            let why = cli.why.as_deref().expect("validated --why");
+            announce::announce(&i18n, &config.run.announce_to, what, why, config.agent_mode);
+        }
+    }
+
+    if cli.agent_mode && cli.command.requires_what_why() && !raw {
+        let what = cli.what.as_deref().expect("validated --what");
+        let why = cli.why.as_deref().expect("validated --why");
+        eprintln!("{}", agent::header_line(what, why));
+    }
+
+    let history_record = if config.history.enabled {
+        cli.what
+            .as_deref()
+            .zip(cli.why.as_deref())
+            .and_then(|(what, why)| {
+                HistoryRecord::for_command(&cli.command, what, why, cli.note_tag.as_deref())
+            })
+    } else {
+        None
+    };
+
+    let summary_what = if cli.command.requires_what_why()
+        && !raw
+        && config.show_exit_summary(io::stderr().is_terminal())
+    {
+        cli.what.clone()
+    } else {
+        None
+    };
+    let strict = cli.command.requires_what_why() && config.run.strict && !cli.allow_nonzero && !raw;
+    // Look for a new release while the command runs. Only people at a
+    // terminal see the notice; agents and pipelines never do.
+    let update_check =
+        if cli.command.requires_what_why() && !cli.agent_mode && !raw && io::stderr().is_terminal()
+        {
+            update_notice::start(&config, cli.version_check_interval.as_deref(), cli.offline)
+        } else {
+            None
+        };
+
+    let measure_output_size =
+        matches!(&cli.command, Command::Run(args) if args.measure_output_size);
+    let capture_bytes =
+        (cli.capture && history_record.is_some()).then_some(config.history.capture_bytes);
+
+    let started = Instant::now();
+
     // Dispatch command
-    let run_result = run(&i18n, &config, cli.command);
+    let run_result = run(
+        &i18n,
+        &config,
+        cli.lang.as_deref(),
+        cli.command,
+        capture_bytes,
+    );
+
+    let elapsed = started.elapsed();
+
+    if let Some(mut record) = history_record {
+        let outcome = run_result.as_ref().ok();
+        record.exit_code = outcome.map(|outcome| outcome.code);
+        record.duration_ms = outcome
+            .and_then(|outcome| outcome.run_time)
+            .map(|run_time| run_time.as_millis() as u64);
+        if let Some(cwd) = outcome.and_then(|outcome| outcome.cwd.as_ref()) {
+            record.cwd = cwd.display().to_string();
+        }
+        if let Some(excerpts) = outcome.and_then(|outcome| outcome.excerpts.as_ref()) {
+            if capture_bytes.is_some() {
+                record.stdout_excerpt = Some(excerpts.stdout.clone());
+                record.stderr_excerpt = Some(excerpts.stderr.clone());
+            }
+            if measure_output_size {
+                record.stdout_bytes = Some(excerpts.stdout_bytes);
+                record.stderr_bytes = Some(excerpts.stderr_bytes);
+            }
+        }
+        record.success = record.exit_code == Some(0);
+        history::record_command(&i18n, &config.history, &record, config.agent_mode);
+    }
+
+    let run_result = run_result.map(|outcome| outcome.code);
 
     if let Some(plan) = &header_plan {
         if matches!(plan.timing, HeaderTiming::Tail | HeaderTiming::Both) {
@@ -114,11 +271,23 @@ fn main() -> ExitCode {
         }
     }
 
-    match run_result {
-        Ok(code) => code,
+    if let (Some(what), Ok(code)) = (summary_what.as_deref(), &run_result) {
+        eprintln!(
+            "{}",
+            format_exit_summary(&i18n, what, *code, elapsed, config.should_color_header())
+        );
+    }
+
+    if let Some(check) = update_check {
+        check.finish(&i18n);
+    }
+
+    match enforce_strict(&i18n, strict, run_result) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) if cli.agent_mode => agent::report_error(&format!("{e:#}")),
         Err(e) => {
             eprintln!("error: {e:?}");
-            ExitCode::from(1)
+            ExitCode::from(exit_code::for_error(&e))
         }
     }
 }
@@ -151,8 +320,8 @@ fn write_header<W: Write>(
     why_label: &str,
     why: &str,
 ) -> io::Result<()> {
-    writeln!(writer, "{what_label}: {what}")?;
-    writeln!(writer, "{why_label}:  {why}")?;
+    writeln!(writer, "{what_label}{what}")?;
+    writeln!(writer, "{why_label}{why}")?;
     writer.flush()
 }
 
@@ -175,71 +344,228 @@ fn emit_header(plan: &HeaderPlan) -> io::Result<()> {
     }
 }
 
-fn run(i18n: &I18n, config: &Config, command: Command) -> Result<ExitCode> {
+/// Under `run_strict`, a nonzero exit of the wrapped command becomes a shnote
+/// error.
+fn enforce_strict(i18n: &I18n, strict: bool, result: Result<u8>) -> Result<u8> {
+    match result {
+        Ok(code) if strict && code != 0 => anyhow::bail!("{}", i18n.err_strict_nonzero(code)),
+        other => other,
+    }
+}
+
+/// One-line banner printed after an execution command when `output_summary`
+/// is on; the exit code is green on success and red on failure.
+fn format_exit_summary(
+    i18n: &I18n,
+    what: &str,
+    exit_code: u8,
+    elapsed: Duration,
+    use_color: bool,
+) -> String {
+    let code = if use_color {
+        let color = if exit_code == 0 { "32" } else { "31" };
+        format!("\x1b[{color}m{exit_code}\x1b[0m")
+    } else {
+        exit_code.to_string()
+    };
+    i18n.exit_summary(what, &code, &format_elapsed(elapsed))
+}
+
+/// Dispatch `command`. Execution commands report their run time and
+/// captured output (`capture_bytes`, from `--capture`) for history.
+fn run(
+    i18n: &I18n,
+    config: &Config,
+    cli_lang: Option<&str>,
+    command: Command,
+    capture_bytes: Option<usize>,
+) -> Result<RunOutcome> {
     match command {
-        Command::Run(args) => executor::exec_run(i18n, config, args),
+        Command::Run(args) => executor::exec_run(i18n, config, args, capture_bytes),
+
+        Command::External(command) => executor::exec_run(
+            i18n,
+            config,
+            cli::RunArgs {
+                command,
+                ..Default::default()
+            },
+            capture_bytes,
+        ),
+
+        Command::Py(args) => executor::exec_py(i18n, config, args, capture_bytes),
 
-        Command::External(command) => executor::exec_run(i18n, config, cli::RunArgs { command }),
+        Command::Node(args) => executor::exec_node(i18n, config, args, capture_bytes),
 
-        Command::Py(args) => executor::exec_py(i18n, config, args),
+        Command::Sh(args) => executor::exec_sh(i18n, config, args, capture_bytes),
 
-        Command::Node(args) => executor::exec_node(i18n, config, args),
+        Command::Pip(args) => executor::exec_pip(i18n, config, args, capture_bytes),
 
-        Command::Pip(args) => executor::exec_pip(i18n, config, args),
+        Command::Npm(args) => executor::exec_npm(i18n, config, args, capture_bytes),
 
-        Command::Npm(args) => executor::exec_npm(i18n, config, args),
+        Command::Npx(args) => executor::exec_npx(i18n, config, args, capture_bytes),
 
-        Command::Npx(args) => executor::exec_npx(i18n, config, args),
+        Command::Batch(args) => batch::run_batch(i18n, config, args, capture_bytes),
+
+        command => run_command(i18n, config, cli_lang, command).map(RunOutcome::exited),
+    }
+}
 
+/// Dispatch a command that wraps nothing.
+fn run_command(
+    i18n: &I18n,
+    config: &Config,
+    cli_lang: Option<&str>,
+    command: Command,
+) -> Result<u8> {
+    match command {
         Command::Config(args) => {
             handle_config(i18n, args)?;
-            Ok(ExitCode::SUCCESS)
+            Ok(0)
+        }
+
+        Command::Init(args) if args.list_targets => {
+            init::print_targets(i18n);
+            Ok(0)
         }
 
         Command::Init(args) => {
-            init::run_init(i18n, args.target, args.scope)?;
-            Ok(ExitCode::SUCCESS)
+            if args.all && args.target.is_some() {
+                return Err(exit_code::validation(i18n.err_init_all_with_target()));
+            }
+            if args.target.is_none() && !args.all {
+                return Err(exit_code::validation(i18n.err_init_target_required()));
+            }
+            if let (true, Some(target)) = (args.remove, args.target) {
+                init::run_remove(i18n, target, args.scope, args.dry_run, args.backup)?;
+                return Ok(0);
+            }
+            let rules = init::RulesOptions {
+                vars: init::parse_rules_vars(i18n, &args.set_var)?,
+                ..init::RulesOptions::new(
+                    args.rules_lang.map_or(i18n.lang(), RulesLang::lang),
+                    args.force_pueue && !args.no_pueue,
+                )
+            };
+            match args.target {
+                Some(target) => {
+                    init::run_init(i18n, target, args.scope, args.dry_run, &rules, args.backup)?
+                }
+                None => {
+                    let all_ok =
+                        init::run_init_all(i18n, args.scope, args.dry_run, &rules, args.backup);
+                    if !all_ok {
+                        return Ok(exit_code::SHNOTE_ERROR);
+                    }
+                }
+            }
+            Ok(0)
         }
 
-        Command::Setup => {
-            pueue_embed::run_setup(i18n)?;
-            Ok(ExitCode::SUCCESS)
+        Command::Setup(args) if args.check_only => {
+            let all_ok = pueue_embed::check_installed(i18n)?;
+            Ok(if all_ok { 0 } else { exit_code::SHNOTE_ERROR })
         }
 
-        Command::Doctor => {
-            let results = doctor::run_doctor(i18n, config);
-            doctor::print_doctor_results(i18n, &results);
-            let all_ok = results.iter().all(|r| r.ok);
-            Ok(if all_ok {
-                ExitCode::SUCCESS
+        Command::Setup(args) => {
+            pueue_embed::run_setup(i18n, config, args)?;
+            Ok(0)
+        }
+
+        Command::Doctor(args) => {
+            if args.check_locale {
+                doctor::print_locale_report(i18n, cli_lang, config);
+                return Ok(0);
+            }
+            let mut results = if args.check_rules_drift {
+                let results = doctor::check_rules_drift(i18n);
+                if results.is_empty() && !args.json {
+                    println!("{}", i18n.doctor_rules_none());
+                }
+                results
             } else {
-                ExitCode::from(1)
-            })
+                doctor::run_doctor(i18n, config)
+            };
+            if args.check_updates {
+                results.push(doctor::check_updates(i18n, config, args.offline));
+            }
+            if args.check_history {
+                results.push(doctor::check_history(i18n, config));
+            }
+            for tool in args.check {
+                results.extend(doctor::check_agent(i18n, tool));
+            }
+            if args.json {
+                doctor::print_doctor_json(&results);
+            } else {
+                doctor::print_doctor_results(i18n, &results, config.plain);
+            }
+            let all_ok = results.iter().all(|r| r.passed());
+            Ok(if all_ok { 0 } else { exit_code::SHNOTE_ERROR })
         }
 
         Command::Completions(args) => {
-            generate_completions(args.shell);
-            Ok(ExitCode::SUCCESS)
+            match args.shell {
+                Some(shell) if !args.list => generate_completions(shell),
+                _ => {
+                    for name in completion_shell_names() {
+                        println!("{name}");
+                    }
+                }
+            }
+            Ok(0)
         }
 
         Command::Info => {
             info::run_info(i18n)?;
-            Ok(ExitCode::SUCCESS)
+            Ok(0)
+        }
+
+        Command::Replay(_) => unreachable!("replay is resolved before dispatch"),
+
+        Command::Run(_)
+        | Command::External(_)
+        | Command::Py(_)
+        | Command::Node(_)
+        | Command::Sh(_)
+        | Command::Pip(_)
+        | Command::Npm(_)
+        | Command::Npx(_)
+        | Command::Batch(_) => unreachable!("execution commands are dispatched by run"),
+
+        Command::History(args) => {
+            history::run_history(i18n, args)?;
+            Ok(0)
         }
 
         Command::Update(args) => {
-            update::run_update(i18n, args)?;
-            Ok(ExitCode::SUCCESS)
+            update::run_update(i18n, config, args)?;
+            Ok(0)
+        }
+
+        Command::Prune => {
+            prune::run_prune(i18n)?;
+            Ok(0)
         }
 
         Command::Uninstall(args) => {
             uninstall::run_uninstall(i18n, args)?;
-            Ok(ExitCode::SUCCESS)
+            Ok(0)
         }
+
+        Command::TestRules => Ok(if init::test_rules(i18n) {
+            0
+        } else {
+            exit_code::SHNOTE_ERROR
+        }),
     }
 }
 
 fn generate_completions(shell: Shell) {
+    write_completions(shell, &mut io::stdout());
+}
+
+fn write_completions(shell: Shell, out: &mut dyn Write) {
     let mut cmd = Cli::command();
     let shell = match shell {
         Shell::Bash => CompletionShell::Bash,
@@ -248,14 +574,59 @@ fn generate_completions(shell: Shell) {
         Shell::PowerShell => CompletionShell::PowerShell,
         Shell::Elvish => CompletionShell::Elvish,
     };
-    generate(shell, &mut cmd, "shnote", &mut io::stdout());
+    generate(shell, &mut cmd, "shnote", out);
+}
+
+/// Names accepted by `completions <SHELL>`, in declaration order.
+fn completion_shell_names() -> Vec<String> {
+    Shell::value_variants()
+        .iter()
+        .filter_map(|shell| shell.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
 }
 
 fn handle_config(i18n: &I18n, args: cli::ConfigArgs) -> Result<()> {
+    let mutates = matches!(
+        args.action,
+        ConfigAction::Set { dry_run: false, .. } | ConfigAction::Reset
+    );
+    if mutates && config::config_readonly() {
+        anyhow::bail!("{}", i18n.err_config_readonly());
+    }
+    // Serialize read-modify-write with other shnote processes
+    let _lock = if mutates {
+        Some(config::ConfigLock::acquire(i18n)?)
+    } else {
+        None
+    };
+
     match args.action {
-        ConfigAction::Get { key } => {
-            let config = Config::load()?;
+        ConfigAction::Get {
+            key,
+            show_source,
+            raw,
+            no_newline,
+        } => {
+            let (config, sources) = Config::load_with_sources()?;
             match config.get(&key) {
+                Some(value) if raw => {
+                    let mut stdout = io::stdout().lock();
+                    stdout.write_all(value.as_bytes())?;
+                    if !no_newline {
+                        stdout.write_all(b"\n")?;
+                    }
+                    stdout.flush()?;
+                }
+                Some(value) if show_source => {
+                    let source = match sources.source(&key) {
+                        ConfigSource::Default => i18n.config_source_default().to_string(),
+                        ConfigSource::User(path) => {
+                            i18n.config_source_user(&path.display().to_string())
+                        }
+                    };
+                    println!("{value} ({source})");
+                }
                 Some(value) => println!("{value}"),
                 None => {
                     anyhow::bail!("{}", i18n.config_key_not_found(&key));
@@ -263,20 +634,61 @@ fn handle_config(i18n: &I18n, args: cli::ConfigArgs) -> Result<()> {
             }
         }
 
-        ConfigAction::Set { key, value } => {
+        ConfigAction::Set {
+            key,
+            value,
+            dry_run,
+        } => {
             let mut config = Config::load()?;
-            if config.set(i18n, &key, &value)? {
-                config.save(i18n)?;
-                println!("{}", i18n.config_updated(&key, &value));
-            } else {
+            if !config.set(i18n, &key, &value)? {
                 anyhow::bail!("{}", i18n.config_key_not_found(&key));
             }
+            // Show the value as it is stored, e.g. a normalized color or boolean
+            let stored = config.get(&key).unwrap_or(value);
+            if dry_run {
+                println!("{}", i18n.config_dry_run(&key, &stored));
+            } else {
+                config.save(i18n)?;
+                println!("{}", i18n.config_updated(&key, &stored));
+            }
         }
 
-        ConfigAction::List => {
+        ConfigAction::List { format } => {
             let config = Config::load()?;
-            for (key, value) in config.list() {
-                println!("{key} = {value}");
+            let lines = match format {
+                ConfigListFormat::Plain => config
+                    .list()
+                    .into_iter()
+                    .map(|(key, value)| format!("{key} = {value}"))
+                    .collect(),
+                ConfigListFormat::Table => {
+                    let color = config.should_color_header()
+                        && std::env::var_os("NO_COLOR").is_none()
+                        && io::stdout().is_terminal();
+                    config_table_lines(&config.list(), color)
+                }
+            };
+            for line in lines {
+                println!("{line}");
+            }
+        }
+
+        ConfigAction::Diff { json } => {
+            let diff = Config::load()?.diff_from_default();
+            if json {
+                let entries: Vec<_> = diff
+                    .iter()
+                    .map(|(key, value, default)| {
+                        serde_json::json!({ "key": key, "value": value, "default": default })
+                    })
+                    .collect();
+                println!("{}", serde_json::Value::Array(entries));
+            } else if diff.is_empty() {
+                println!("{}", i18n.config_diff_none());
+            } else {
+                for (key, value, default) in &diff {
+                    println!("{}", i18n.config_diff_line(key, value, default));
+                }
             }
         }
 
@@ -294,6 +706,32 @@ fn handle_config(i18n: &I18n, args: cli::ConfigArgs) -> Result<()> {
     Ok(())
 }
 
+/// `config list --format table`: keys padded to the longest key, so the
+/// values line up; keys are bold when `color` is set.
+fn config_table_lines(entries: &[(String, String)], color: bool) -> Vec<String> {
+    let width = entries
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    entries
+        .iter()
+        .map(|(key, value)| {
+            // No trailing padding after a key whose value is empty
+            let padding = if value.is_empty() {
+                String::new()
+            } else {
+                " ".repeat(width - key.chars().count() + 2)
+            };
+            if color {
+                format!("\x1b[1m{key}\x1b[0m{padding}{value}")
+            } else {
+                format!("{key}{padding}{value}")
+            }
+        })
+        .collect()
+}
+
 /// Extract --lang argument from command line args before full parsing.
 ///
 /// This is needed because we need to know the language before parsing to
@@ -336,6 +774,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn completions_mention_every_subcommand_for_every_shell() {
+        let subcommands: Vec<String> = Cli::command()
+            .get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        for name in ["run", "py", "node", "sh", "init", "config", "completions"] {
+            assert!(subcommands.iter().any(|sub| sub == name), "{name}");
+        }
+
+        for shell in Shell::value_variants() {
+            let mut script = Vec::new();
+            write_completions(shell.clone(), &mut script);
+            let script = String::from_utf8(script).unwrap();
+            for name in &subcommands {
+                assert!(
+                    contains_word(&script, name),
+                    "{shell:?} completions are missing `{name}`"
+                );
+            }
+        }
+    }
+
+    /// `name` appears in `script` as a whole word, so `sh` inside `shnote`
+    /// does not count.
+    fn contains_word(script: &str, name: &str) -> bool {
+        let is_word = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        script.match_indices(name).any(|(at, _)| {
+            !script[..at].ends_with(is_word) && !script[at + name.len()..].starts_with(is_word)
+        })
+    }
+
+    #[test]
+    fn completion_shell_names_lists_every_variant() {
+        let names = completion_shell_names();
+        assert_eq!(names, ["bash", "zsh", "fish", "powershell", "elvish"]);
+        assert_eq!(names.len(), Shell::value_variants().len());
+        for shell in Shell::value_variants() {
+            let name = shell.to_possible_value().unwrap();
+            assert!(names.iter().any(|n| n == name.get_name()));
+        }
+    }
+
     #[test]
     fn handle_config_success_paths() {
         let _lock = env_lock();
@@ -359,6 +841,7 @@ mod tests {
                 action: ConfigAction::Set {
                     key: "python".to_string(),
                     value: "/bin/sh".to_string(),
+                    dry_run: false,
                 },
             },
         )
@@ -369,6 +852,9 @@ mod tests {
             cli::ConfigArgs {
                 action: ConfigAction::Get {
                     key: "python".to_string(),
+                    show_source: false,
+                    raw: false,
+                    no_newline: false,
                 },
             },
         )
@@ -379,6 +865,9 @@ mod tests {
             cli::ConfigArgs {
                 action: ConfigAction::Get {
                     key: "unknown_key".to_string(),
+                    show_source: false,
+                    raw: false,
+                    no_newline: false,
                 },
             },
         )
@@ -388,7 +877,9 @@ mod tests {
         handle_config(
             &i18n,
             cli::ConfigArgs {
-                action: ConfigAction::List,
+                action: ConfigAction::List {
+                    format: ConfigListFormat::Plain,
+                },
             },
         )
         .unwrap();
@@ -414,6 +905,7 @@ mod tests {
             action: ConfigAction::Set {
                 key: "shell".to_string(),
                 value: "invalid".to_string(),
+                dry_run: false,
             },
         };
 
@@ -471,100 +963,180 @@ mod tests {
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Run(cli::RunArgs {
                 command: vec![OsString::from("dummy")],
+                ..Default::default()
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Py(cli::ScriptArgs {
                 code: Some("print(1)".to_string()),
                 file: None,
                 stdin: false,
+                interpreter: None,
                 args: vec![],
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Node(cli::ScriptArgs {
                 code: Some("console.log(1)".to_string()),
                 file: None,
                 stdin: false,
+                interpreter: None,
                 args: vec![],
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
+            Command::Sh(cli::ScriptArgs {
+                code: Some("true".to_string()),
+                file: None,
+                stdin: false,
+                interpreter: None,
+                args: vec![],
+            }),
+            None,
+        )
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
+
+        let code = run(
+            &i18n,
+            &config,
+            None,
             Command::Pip(cli::PassthroughArgs {
+                cwd: None,
                 args: vec![OsString::from("--version")],
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Npm(cli::PassthroughArgs {
+                cwd: None,
                 args: vec![OsString::from("--version")],
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Npx(cli::PassthroughArgs {
+                cwd: None,
                 args: vec![OsString::from("--version")],
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Config(cli::ConfigArgs {
-                action: ConfigAction::List,
+                action: ConfigAction::List {
+                    format: ConfigListFormat::Plain,
+                },
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
-            Command::Completions(cli::CompletionsArgs { shell: Shell::Bash }),
+            None,
+            Command::Completions(cli::CompletionsArgs {
+                shell: Some(Shell::Bash),
+                list: false,
+            }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let code = run(
             &i18n,
             &config,
+            None,
             Command::Init(cli::InitArgs {
                 scope: cli::Scope::User,
-                target: cli::InitTarget::Claude,
+                dry_run: false,
+                no_pueue: false,
+                force_pueue: false,
+                backup: false,
+                list_targets: false,
+                all: false,
+                remove: false,
+                rules_lang: None,
+                set_var: Vec::new(),
+                target: Some(cli::InitTarget::Claude),
             }),
+            None,
         )
-        .unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
-        let code = run(&i18n, &config, Command::Doctor).unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        let code = run(
+            &i18n,
+            &config,
+            None,
+            Command::Doctor(cli::DoctorArgs {
+                check_locale: false,
+                check_updates: false,
+                check_history: false,
+                offline: false,
+                check_rules_drift: false,
+                check: Vec::new(),
+                json: false,
+            }),
+            None,
+        )
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
     }
 
     #[test]
@@ -579,7 +1151,7 @@ mod tests {
             action: ConfigAction::Path,
         });
 
-        let err = run(&i18n, &config, cmd).unwrap_err();
+        let err = run(&i18n, &config, None, cmd, None).unwrap_err();
         assert!(err
             .to_string()
             .contains("failed to determine home directory"));
@@ -596,6 +1168,7 @@ mod tests {
             action: ConfigAction::Set {
                 key: "unknown_key".to_string(),
                 value: "value".to_string(),
+                dry_run: false,
             },
         };
 
@@ -603,6 +1176,52 @@ mod tests {
         assert!(err.to_string().contains("unknown"));
     }
 
+    #[test]
+    fn handle_config_readonly_blocks_set_and_reset() {
+        let _lock = env_lock();
+        let home_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", home_dir.path());
+        let _userprofile_guard = EnvVarGuard::remove("USERPROFILE");
+        let _readonly_guard = EnvVarGuard::set("SHNOTE_CONFIG_READONLY", "1");
+
+        let i18n = I18n::new(Lang::En);
+        let set = |dry_run| cli::ConfigArgs {
+            action: ConfigAction::Set {
+                key: "python".to_string(),
+                value: "/usr/bin/python3".to_string(),
+                dry_run,
+            },
+        };
+
+        let err = handle_config(&i18n, set(false)).unwrap_err();
+        assert!(err.to_string().contains("read-only"));
+        let err = handle_config(
+            &i18n,
+            cli::ConfigArgs {
+                action: ConfigAction::Reset,
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("read-only"));
+        assert!(!home_dir.path().join(".shnote/config.toml").exists());
+
+        handle_config(&i18n, set(true)).unwrap();
+        for action in [
+            ConfigAction::List {
+                format: ConfigListFormat::Plain,
+            },
+            ConfigAction::Path,
+            ConfigAction::Get {
+                key: "python".to_string(),
+                show_source: false,
+                raw: false,
+                no_newline: false,
+            },
+        ] {
+            handle_config(&i18n, cli::ConfigArgs { action }).unwrap();
+        }
+    }
+
     #[test]
     fn handle_config_get_errors_when_config_load_fails() {
         let _lock = env_lock();
@@ -616,6 +1235,9 @@ mod tests {
         let args = cli::ConfigArgs {
             action: ConfigAction::Get {
                 key: "python".to_string(),
+                show_source: false,
+                raw: false,
+                no_newline: false,
             },
         };
 
@@ -637,6 +1259,7 @@ mod tests {
             action: ConfigAction::Set {
                 key: "python".to_string(),
                 value: "/bin/sh".to_string(),
+                dry_run: false,
             },
         };
 
@@ -655,7 +1278,9 @@ mod tests {
 
         let i18n = I18n::new(Lang::En);
         let args = cli::ConfigArgs {
-            action: ConfigAction::List,
+            action: ConfigAction::List {
+                format: ConfigListFormat::Plain,
+            },
         };
 
         let err = handle_config(&i18n, args).unwrap_err();
@@ -687,6 +1312,7 @@ mod tests {
             action: ConfigAction::Set {
                 key: "python".to_string(),
                 value: "/bin/sh".to_string(),
+                dry_run: false,
             },
         };
 
@@ -743,10 +1369,19 @@ mod tests {
         let config = Config::default();
         let cmd = Command::Init(cli::InitArgs {
             scope: cli::Scope::User,
-            target: cli::InitTarget::Claude,
+            dry_run: false,
+            no_pueue: false,
+            force_pueue: false,
+            backup: false,
+            list_targets: false,
+            all: false,
+            remove: false,
+            rules_lang: None,
+            set_var: Vec::new(),
+            target: Some(cli::InitTarget::Claude),
         });
 
-        let err = run(&i18n, &config, cmd).unwrap_err();
+        let err = run(&i18n, &config, None, cmd, None).unwrap_err();
         assert!(err.to_string().contains(i18n.err_home_dir()));
     }
 
@@ -762,7 +1397,14 @@ mod tests {
 
         let i18n = I18n::new(Lang::En);
         let config = Config::default();
-        let err = run(&i18n, &config, Command::Setup).unwrap_err();
+        let err = run(
+            &i18n,
+            &config,
+            None,
+            Command::Setup(cli::SetupArgs::default()),
+            None,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains("failed"));
     }
 
@@ -783,8 +1425,24 @@ mod tests {
         config.paths.node = "/nonexistent/node".to_string();
         config.paths.shell = "bash".to_string();
 
-        let code = run(&i18n, &config, Command::Doctor).unwrap();
-        assert_eq!(code, ExitCode::from(1));
+        let code = run(
+            &i18n,
+            &config,
+            None,
+            Command::Doctor(cli::DoctorArgs {
+                check_locale: false,
+                check_updates: false,
+                check_history: false,
+                offline: false,
+                check_rules_drift: false,
+                check: Vec::new(),
+                json: false,
+            }),
+            None,
+        )
+        .unwrap()
+        .code;
+        assert_eq!(code, 1);
     }
 
     #[cfg(unix)]
@@ -835,14 +1493,71 @@ exit 0\n"
 
         let i18n = I18n::new(Lang::En);
         let config = Config::default();
-        let code = run(&i18n, &config, Command::Setup).unwrap();
-        assert_eq!(code, ExitCode::SUCCESS);
+        let code = run(
+            &i18n,
+            &config,
+            None,
+            Command::Setup(cli::SetupArgs::default()),
+            None,
+        )
+        .unwrap()
+        .code;
+        assert_eq!(code, 0);
 
         let bin_dir = crate::config::shnote_bin_dir().unwrap();
         assert!(bin_dir.join(crate::config::pueue_binary_name()).exists());
         assert!(bin_dir.join(crate::config::pueued_binary_name()).exists());
     }
 
+    #[test]
+    fn enforce_strict_turns_nonzero_into_error() {
+        let i18n = I18n::new(Lang::En);
+
+        let err = enforce_strict(&i18n, true, Ok(3)).unwrap_err();
+        assert!(err.to_string().contains("exited with code 3"));
+        assert_eq!(enforce_strict(&i18n, true, Ok(0)).unwrap(), 0);
+        assert_eq!(enforce_strict(&i18n, false, Ok(3)).unwrap(), 3);
+    }
+
+    #[test]
+    fn format_exit_summary_reports_success_and_failure() {
+        let i18n = I18n::new(Lang::En);
+        let elapsed = Duration::from_millis(1250);
+
+        assert_eq!(
+            format_exit_summary(&i18n, "Build", 0, elapsed, false),
+            "shnote: Build: exit 0 in 1.25s"
+        );
+        assert_eq!(
+            format_exit_summary(&i18n, "Build", 2, elapsed, false),
+            "shnote: Build: exit 2 in 1.25s"
+        );
+        assert_eq!(
+            format_exit_summary(&i18n, "Build", 0, elapsed, true),
+            "shnote: Build: exit \x1b[32m0\x1b[0m in 1.25s"
+        );
+        assert_eq!(
+            format_exit_summary(&i18n, "Build", 1, elapsed, true),
+            "shnote: Build: exit \x1b[31m1\x1b[0m in 1.25s"
+        );
+    }
+
+    #[test]
+    fn config_table_lines_align_values() {
+        let entries = vec![
+            ("python".to_string(), "python3".to_string()),
+            ("history_sink".to_string(), "file".to_string()),
+        ];
+        assert_eq!(
+            config_table_lines(&entries, false),
+            ["python        python3", "history_sink  file"]
+        );
+        assert_eq!(
+            config_table_lines(&entries, true)[0],
+            "\x1b[1mpython\x1b[0m        python3"
+        );
+    }
+
     #[test]
     fn extract_lang_arg_with_equals_syntax() {
         let args = vec![
@@ -0,0 +1,138 @@
+//! `run --measure`: report the command's peak memory and CPU time.
+//!
+//! On Unix, `getrusage(RUSAGE_CHILDREN)` is read before and after the command.
+//! CPU time is the difference, so it covers only this command and the
+//! processes it waited for. Peak RSS is a maximum over all reaped children
+//! rather than a sum, but `run` starts no other child before the command, so
+//! it is the command's own. Other platforms print a note instead.
+
+use std::time::Duration;
+
+use crate::i18n::I18n;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Largest resident set size, in KiB
+    pub max_rss_kib: u64,
+    /// CPU time spent in user mode
+    pub user: Duration,
+    /// CPU time spent in the kernel
+    pub system: Duration,
+}
+
+impl Usage {
+    /// Resource usage of all terminated and waited-for children so far;
+    /// `None` where `getrusage` is unavailable.
+    #[cfg(unix)]
+    pub fn of_children() -> Option<Self> {
+        // SAFETY: an all-zero rusage is valid, and getrusage only writes into it.
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        // SAFETY: `usage` is a valid rusage for the call to fill.
+        if unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) } != 0 {
+            return None;
+        }
+        // macOS reports ru_maxrss in bytes, other Unixes in KiB.
+        let max_rss = usage.ru_maxrss.max(0) as u64;
+        let max_rss_kib = if cfg!(target_os = "macos") {
+            max_rss / 1024
+        } else {
+            max_rss
+        };
+        Some(Self {
+            max_rss_kib,
+            user: timeval_duration(usage.ru_utime),
+            system: timeval_duration(usage.ru_stime),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of_children() -> Option<Self> {
+        None
+    }
+
+    /// Usage added since the `earlier` snapshot.
+    fn since(self, earlier: Usage) -> Usage {
+        Usage {
+            max_rss_kib: self.max_rss_kib,
+            user: self.user.saturating_sub(earlier.user),
+            system: self.system.saturating_sub(earlier.system),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn timeval_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec.max(0) as u64)
+        + Duration::from_micros(time.tv_usec.max(0) as u64)
+}
+
+/// CPU time (user plus system) used since `before`, where measurable.
+pub fn cpu_time_since(before: Option<Usage>) -> Option<Duration> {
+    let usage = Usage::of_children()?.since(before?);
+    Some(usage.user + usage.system)
+}
+
+/// The usage added since `before` (taken just before the command was
+/// spawned), for stderr.
+pub fn summary(i18n: &I18n, before: Option<Usage>) -> String {
+    match (before, Usage::of_children()) {
+        (Some(before), Some(after)) => format_usage(i18n, after.since(before)),
+        _ => i18n.warn_measure_unsupported().to_string(),
+    }
+}
+
+fn format_usage(i18n: &I18n, usage: Usage) -> String {
+    i18n.run_measure_summary(
+        &format!("{:.1} MiB", usage.max_rss_kib as f64 / 1024.0),
+        &format!("{:.2}s", usage.user.as_secs_f64()),
+        &format!("{:.2}s", usage.system.as_secs_f64()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+
+    #[test]
+    fn since_subtracts_cpu_time_and_keeps_peak_rss() {
+        let earlier = Usage {
+            max_rss_kib: 100,
+            user: Duration::from_millis(300),
+            system: Duration::from_millis(100),
+        };
+        let later = Usage {
+            max_rss_kib: 2048,
+            user: Duration::from_millis(1300),
+            system: Duration::from_millis(50),
+        };
+
+        let usage = later.since(earlier);
+        assert_eq!(usage.max_rss_kib, 2048);
+        assert_eq!(usage.user, Duration::from_secs(1));
+        assert_eq!(usage.system, Duration::ZERO);
+    }
+
+    #[test]
+    fn format_usage_uses_mib_and_seconds() {
+        let usage = Usage {
+            max_rss_kib: 1536,
+            user: Duration::from_millis(420),
+            system: Duration::from_millis(50),
+        };
+        assert_eq!(
+            format_usage(&I18n::new(Lang::En), usage),
+            "shnote: max RSS 1.5 MiB, CPU 0.42s user, 0.05s system"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn of_children_counts_waited_children() {
+        let before = Usage::of_children().unwrap();
+        std::process::Command::new("true").status().unwrap();
+        let after = Usage::of_children().unwrap();
+        assert!(after.user >= before.user);
+        assert!(after.max_rss_kib > 0);
+    }
+}
@@ -0,0 +1,193 @@
+//! First-run onboarding.
+//!
+//! The first time shnote runs at a terminal (no config file, no data
+//! directory and no `onboarded` marker), it offers a short guided setup:
+//! check the interpreters as `doctor` does, install pueue with `setup` when
+//! it is missing, and write user-scope rules with `init` for each AI tool
+//! found in PATH. Every step asks first, and a failed step only warns.
+//!
+//! The marker is written as soon as the offer is answered, so shnote asks
+//! once whatever the answer. `--plain`, `--agent-mode`, `--json` and runs
+//! without a terminal never see the offer.
+
+use std::fs;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::{Cli, Command, InitTarget, Scope, SetupArgs};
+use crate::config::{config_path, data_dir, shnote_home, Config};
+use crate::doctor;
+use crate::history::unix_now;
+use crate::i18n::I18n;
+use crate::init::{self, detect_cli_tool, RulesOptions, ToolProbe};
+use crate::prompt::prompt_yes_no_with_reader;
+use crate::pueue_embed;
+
+/// File under the shnote home recording that onboarding was offered.
+const MARKER_FILE: &str = "onboarded";
+
+pub fn marker_path() -> Result<PathBuf> {
+    Ok(shnote_home()?.join(MARKER_FILE))
+}
+
+/// Whether to offer onboarding: at a terminal, with nothing left behind by
+/// an earlier run.
+fn should_offer(interactive: bool, config_file: &Path, data_dir: &Path, marker: &Path) -> bool {
+    interactive && !marker.exists() && !config_file.exists() && !data_dir.exists()
+}
+
+/// Offer onboarding before running `cli.command` if this is the first run.
+/// `init`, `setup` and `uninstall` are skipped: they are the setup itself.
+pub fn maybe_offer(i18n: &I18n, config: &Config, cli: &Cli) {
+    if cli.plain
+        || cli.agent_mode
+        || cli.command.prints_json()
+        || matches!(
+            cli.command,
+            Command::Init(_) | Command::Setup(_) | Command::Uninstall(_)
+        )
+    {
+        return;
+    }
+    let (Ok(config_file), Ok(data_dir), Ok(marker)) = (config_path(), data_dir(), marker_path())
+    else {
+        return;
+    };
+    let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+    if !should_offer(interactive, &config_file, &data_dir, &marker) {
+        return;
+    }
+    if let Err(err) = onboard(
+        i18n,
+        config,
+        &marker,
+        &mut io::stdin().lock(),
+        detect_cli_tool,
+    ) {
+        eprintln!("{}", i18n.warn_onboarding_step_failed(&err.to_string()));
+    }
+}
+
+fn onboard(
+    i18n: &I18n,
+    config: &Config,
+    marker: &Path,
+    reader: &mut dyn BufRead,
+    detect: impl Fn(&str) -> ToolProbe,
+) -> Result<()> {
+    println!("{}", i18n.onboarding_welcome());
+    let accepted = prompt_yes_no_with_reader(i18n.onboarding_prompt(), reader)?;
+    write_marker(i18n, marker)?;
+    if !accepted {
+        println!("{}", i18n.onboarding_skipped());
+        return Ok(());
+    }
+
+    println!();
+    let results = doctor::run_doctor(i18n, config);
+    doctor::print_doctor_results(i18n, &results, config.plain);
+    let pueue_missing = results
+        .iter()
+        .any(|result| result.name == "pueue" && !result.ok);
+    if pueue_missing && prompt_yes_no_with_reader(i18n.onboarding_prompt_setup(), reader)? {
+        // The prompt above already asked, so `setup` does not ask again
+        let args = SetupArgs {
+            yes: true,
+            check_only: false,
+        };
+        if let Err(err) = pueue_embed::run_setup(i18n, config, args) {
+            eprintln!("{}", i18n.warn_onboarding_step_failed(&err.to_string()));
+        }
+    }
+
+    for target in InitTarget::ALL {
+        let Some(tool) = init::target_tool(target) else {
+            continue;
+        };
+        if detect(tool).path.is_none() {
+            continue;
+        }
+        if !prompt_yes_no_with_reader(&i18n.onboarding_prompt_init(target.name()), reader)? {
+            continue;
+        }
+        let rules = RulesOptions::new(i18n.lang(), false);
+        if let Err(err) = init::run_init(i18n, target, Scope::User, false, &rules, false) {
+            eprintln!("{}", i18n.warn_onboarding_step_failed(&err.to_string()));
+        }
+    }
+
+    println!("{}", i18n.onboarding_done());
+    Ok(())
+}
+
+fn write_marker(i18n: &I18n, marker: &Path) -> Result<()> {
+    let display = marker.display().to_string();
+    if let Some(dir) = marker.parent() {
+        fs::create_dir_all(dir).context(i18n.err_create_dir(&dir.display().to_string()))?;
+    }
+    fs::write(marker, format!("{}\n", unix_now())).context(i18n.err_write_file(&display))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn no_tool(tool: &str) -> ToolProbe {
+        ToolProbe {
+            tool: tool.to_string(),
+            path: None,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn offered_only_on_a_fresh_interactive_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().join(".shnote");
+        let config_file = home.join("config.toml");
+        let marker = home.join(MARKER_FILE);
+        let data_dir = temp_dir.path().join("data");
+
+        assert!(should_offer(true, &config_file, &data_dir, &marker));
+        assert!(!should_offer(false, &config_file, &data_dir, &marker));
+
+        fs::create_dir_all(&data_dir).unwrap();
+        assert!(!should_offer(true, &config_file, &data_dir, &marker));
+    }
+
+    #[test]
+    fn marker_suppresses_another_offer() {
+        let temp_dir = TempDir::new().unwrap();
+        let home = temp_dir.path().join(".shnote");
+        let config_file = home.join("config.toml");
+        let marker = home.join(MARKER_FILE);
+        let data_dir = temp_dir.path().join("data");
+        let i18n = I18n::new(Lang::En);
+
+        let mut input = Cursor::new("n\n");
+        onboard(&i18n, &Config::default(), &marker, &mut input, no_tool).unwrap();
+
+        assert!(marker.exists());
+        assert!(!config_file.exists());
+        assert!(!should_offer(true, &config_file, &data_dir, &marker));
+    }
+
+    #[test]
+    fn onboarding_prompts_init_only_for_detected_tools() {
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join(MARKER_FILE);
+        let i18n = I18n::new(Lang::En);
+
+        // Accept, then decline setup if pueue is missing. No tool is found,
+        // so nothing else is asked and the input runs out cleanly.
+        let mut input = Cursor::new("y\nn\n");
+        onboard(&i18n, &Config::default(), &marker, &mut input, no_tool).unwrap();
+
+        assert!(marker.exists());
+    }
+}
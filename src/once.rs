@@ -0,0 +1,89 @@
+//! Labels of `run --once` commands that already succeeded, stored as a JSON
+//! object under the data directory.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::data_dir;
+use crate::history::unix_now;
+
+pub const ONCE_FILE_NAME: &str = "once.json";
+
+/// Outcome remembered for a label.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OnceRecord {
+    /// Unix timestamp (seconds) when the command finished
+    pub ts: u64,
+    pub exit_code: u8,
+}
+
+/// Path of the label store (`<data_dir>/once.json`).
+pub fn once_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join(ONCE_FILE_NAME))
+}
+
+/// Load all records; a missing or unreadable store counts as empty, so a
+/// damaged file only means commands run again.
+fn load(path: &Path) -> BTreeMap<String, OnceRecord> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// The recorded exit code for `label`, if it already ran successfully.
+pub fn cached_exit_code(path: &Path, label: &str) -> Option<u8> {
+    load(path).get(label).map(|record| record.exit_code)
+}
+
+/// Remember that `label` completed with `exit_code`.
+pub fn record(path: &Path, label: &str, exit_code: u8) -> Result<()> {
+    let mut records = load(path);
+    records.insert(
+        label.to_string(),
+        OnceRecord {
+            ts: unix_now(),
+            exit_code,
+        },
+    );
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&records)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn record_then_lookup_label() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state").join(ONCE_FILE_NAME);
+
+        assert_eq!(cached_exit_code(&path, "setup"), None);
+        record(&path, "setup", 0).unwrap();
+        record(&path, "build", 0).unwrap();
+
+        assert_eq!(cached_exit_code(&path, "setup"), Some(0));
+        assert_eq!(cached_exit_code(&path, "build"), Some(0));
+        assert_eq!(cached_exit_code(&path, "other"), None);
+    }
+
+    #[test]
+    fn damaged_store_counts_as_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(ONCE_FILE_NAME);
+        fs::write(&path, "not json").unwrap();
+
+        assert_eq!(cached_exit_code(&path, "setup"), None);
+        record(&path, "setup", 0).unwrap();
+        assert_eq!(cached_exit_code(&path, "setup"), Some(0));
+    }
+}
@@ -0,0 +1,51 @@
+//! Interactive confirmation prompts shared by commands that modify user data.
+
+use std::io::{self, BufRead, Write};
+
+use anyhow::Result;
+
+/// Print `prompt` followed by `[y/N]` and read a yes/no answer (default: no).
+pub fn prompt_yes_no_with_reader(prompt: &str, reader: &mut dyn BufRead) -> Result<bool> {
+    prompt_yes_no_to(prompt, reader, &mut io::stdout())
+}
+
+/// Like [`prompt_yes_no_with_reader`], but write the prompt to `writer`
+/// (e.g. stderr, when stdout belongs to the command being run).
+pub fn prompt_yes_no_to(
+    prompt: &str,
+    reader: &mut dyn BufRead,
+    writer: &mut dyn Write,
+) -> Result<bool> {
+    write!(writer, "{prompt} [y/N] ")?;
+    writer.flush()?;
+    let mut input = String::new();
+    reader.read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    Ok(input == "y" || input == "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn prompt_yes_no_with_reader_accepts_yes() {
+        let mut input = Cursor::new("y\n");
+        assert!(prompt_yes_no_with_reader("ok?", &mut input).unwrap());
+    }
+
+    #[test]
+    fn prompt_yes_no_with_reader_rejects_default() {
+        let mut input = Cursor::new("\n");
+        assert!(!prompt_yes_no_with_reader("ok?", &mut input).unwrap());
+    }
+
+    #[test]
+    fn prompt_yes_no_to_writes_prompt_to_writer() {
+        let mut input = Cursor::new("yes\n");
+        let mut output = Vec::new();
+        assert!(prompt_yes_no_to("ok?", &mut input, &mut output).unwrap());
+        assert_eq!(String::from_utf8(output).unwrap(), "ok? [y/N] ");
+    }
+}
@@ -0,0 +1,178 @@
+//! `shnote prune`: remove leftovers of earlier self-updates.
+//!
+//! On Windows the running binary cannot be overwritten, so `update` renames it
+//! to `<name>.old` (and `uninstall` to `<name>.old.delete`); when the file is
+//! still in use it stays behind. An interrupted update can also leave a
+//! `<name>.new` binary or its download directory in the temp dir. Backups are
+//! found by name next to the install path, download directories by the
+//! [`UPDATE_TEMP_PREFIX`] they are created with.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+
+use crate::i18n::I18n;
+use crate::info::get_install_path;
+
+/// Prefix of the temp directories `update` downloads into.
+pub const UPDATE_TEMP_PREFIX: &str = "shnote-update-";
+
+/// Suffixes appended to the binary's file name by update and uninstall.
+const BACKUP_SUFFIXES: &[&str] = &[".old", ".new", ".old.delete"];
+
+/// Download directories younger than this may belong to a running update.
+const STALE_TEMP_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub fn run_prune(i18n: &I18n) -> Result<()> {
+    let mut paths = get_install_path()
+        .map(|path| find_backups(&path))
+        .unwrap_or_default();
+    paths.extend(find_stale_temp_dirs(&env::temp_dir(), SystemTime::now()));
+
+    if remove_paths(i18n, &paths) == 0 {
+        println!("{}", i18n.prune_nothing());
+    }
+    Ok(())
+}
+
+/// Backup binaries next to `install_path` (`shnote.old`, `shnote.exe.old`, ...).
+fn find_backups(install_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (
+        install_path.parent(),
+        install_path.file_name().and_then(|name| name.to_str()),
+    ) else {
+        return Vec::new();
+    };
+    BACKUP_SUFFIXES
+        .iter()
+        .map(|suffix| dir.join(format!("{name}{suffix}")))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Update download directories in `temp_dir` last modified at least
+/// [`STALE_TEMP_AGE`] before `now`.
+fn find_stale_temp_dirs(temp_dir: &Path, now: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(temp_dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(UPDATE_TEMP_PREFIX))
+        })
+        .filter(|entry| {
+            entry
+                .metadata()
+                .ok()
+                .filter(|meta| meta.is_dir())
+                .and_then(|meta| meta.modified().ok())
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age >= STALE_TEMP_AGE)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Remove each path, reporting progress; failures (e.g. a backup still in use)
+/// are reported and skipped. Returns how many paths were removed.
+fn remove_paths(i18n: &I18n, paths: &[PathBuf]) -> usize {
+    let mut removed = 0;
+    for path in paths {
+        let result = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        let display = path.display().to_string();
+        match result {
+            Ok(()) => {
+                println!("{} {display}", i18n.prune_removed());
+                removed += 1;
+            }
+            Err(err) => eprintln!("{}", i18n.warn_prune_failed(&display, &err.to_string())),
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use tempfile::TempDir;
+
+    #[test]
+    fn find_backups_matches_naming_convention() {
+        let temp_dir = TempDir::new().unwrap();
+        let install = temp_dir.path().join("shnote");
+        for name in [
+            "shnote",
+            "shnote.old",
+            "shnote.new",
+            "shnote.old.delete",
+            "shnote.toml",
+            "other.old",
+        ] {
+            fs::write(temp_dir.path().join(name), b"").unwrap();
+        }
+
+        let found = find_backups(&install);
+        assert_eq!(
+            found,
+            ["shnote.old", "shnote.new", "shnote.old.delete"]
+                .map(|name| temp_dir.path().join(name))
+        );
+    }
+
+    #[test]
+    fn find_backups_handles_windows_names() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("shnote.exe.old"), b"").unwrap();
+
+        let found = find_backups(&temp_dir.path().join("shnote.exe"));
+        assert_eq!(found, [temp_dir.path().join("shnote.exe.old")]);
+    }
+
+    #[test]
+    fn find_stale_temp_dirs_skips_recent_and_foreign_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let ours = temp_dir.path().join(format!("{UPDATE_TEMP_PREFIX}abc"));
+        fs::create_dir(&ours).unwrap();
+        fs::create_dir(temp_dir.path().join("other-abc")).unwrap();
+        fs::write(
+            temp_dir.path().join(format!("{UPDATE_TEMP_PREFIX}file")),
+            b"",
+        )
+        .unwrap();
+
+        assert!(find_stale_temp_dirs(temp_dir.path(), SystemTime::now()).is_empty());
+        let later = SystemTime::now() + STALE_TEMP_AGE + Duration::from_secs(60);
+        assert_eq!(find_stale_temp_dirs(temp_dir.path(), later), [ours]);
+    }
+
+    #[test]
+    fn remove_paths_deletes_files_and_dirs() {
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let backup = temp_dir.path().join("shnote.old");
+        fs::write(&backup, b"old").unwrap();
+        let download = temp_dir.path().join(format!("{UPDATE_TEMP_PREFIX}abc"));
+        fs::create_dir(&download).unwrap();
+        fs::write(download.join("shnote.tar.xz"), b"archive").unwrap();
+        let missing = temp_dir.path().join("shnote.new");
+
+        let removed = remove_paths(&i18n, &[backup.clone(), download.clone(), missing]);
+        assert_eq!(removed, 2);
+        assert!(!backup.exists());
+        assert!(!download.exists());
+    }
+}
@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
+use anyhow::{Context, Result};
 use which::which;
 
 use crate::config::{pueue_binary_name, pueued_binary_name, shnote_bin_dir};
+use crate::i18n::I18n;
+use crate::prompt::prompt_yes_no_with_reader;
 
 fn find_in_shnote_bin(binary_name: &str) -> Option<PathBuf> {
     shnote_bin_dir()
@@ -18,3 +24,131 @@ pub fn find_pueue() -> Option<PathBuf> {
 pub fn find_pueued() -> Option<PathBuf> {
     find_in_shnote_bin(pueued_binary_name()).or_else(|| which("pueued").ok())
 }
+
+/// `run --group NAME`: turn `pueue add ...` into `pueue add -g NAME ...`,
+/// running the pueue that `find_pueue` resolves, after making sure the group
+/// exists.
+pub fn apply_group(i18n: &I18n, command: Vec<OsString>, group: &str) -> Result<Vec<OsString>> {
+    let Some(mut command) = insert_group_arg(&command, group) else {
+        anyhow::bail!("{}", i18n.err_group_requires_pueue_add());
+    };
+    if command[0] == "pueue" {
+        if let Some(path) = find_pueue() {
+            command[0] = path.into_os_string();
+        }
+    }
+    ensure_group(i18n, Path::new(&command[0]), group)?;
+    Ok(command)
+}
+
+/// `command` with `-g group` inserted after `add`, or `None` if it is not a
+/// `pueue add` invocation.
+fn insert_group_arg(command: &[OsString], group: &str) -> Option<Vec<OsString>> {
+    let [program, subcommand, rest @ ..] = command else {
+        return None;
+    };
+    let is_pueue = Path::new(program)
+        .file_stem()
+        .is_some_and(|stem| stem == "pueue");
+    if !is_pueue || subcommand != "add" {
+        return None;
+    }
+    let mut with_group = vec![
+        program.clone(),
+        subcommand.clone(),
+        "-g".into(),
+        group.into(),
+    ];
+    with_group.extend_from_slice(rest);
+    Some(with_group)
+}
+
+/// Create `group` if `pueue group` does not list it, asking first when stdin
+/// is a terminal. If the groups cannot be listed (daemon not running, older
+/// pueue), `pueue add` is left to report the problem itself.
+fn ensure_group(i18n: &I18n, pueue: &Path, group: &str) -> Result<()> {
+    let listed = Command::new(pueue)
+        .args(["group", "--json"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    let Some(groups) = listed
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_group_names(&String::from_utf8_lossy(&output.stdout)))
+    else {
+        return Ok(());
+    };
+    if groups.iter().any(|name| name == group) {
+        return Ok(());
+    }
+
+    let create = io::stdin().is_terminal()
+        && prompt_yes_no_with_reader(
+            &i18n.prompt_create_pueue_group(group),
+            &mut io::stdin().lock(),
+        )?;
+    if !create {
+        anyhow::bail!("{}", i18n.err_pueue_group_missing(group));
+    }
+    let status = Command::new(pueue)
+        .args(["group", "add", group])
+        .status()
+        .with_context(|| i18n.err_pueue_group_create_failed(group))?;
+    if !status.success() {
+        anyhow::bail!("{}", i18n.err_pueue_group_create_failed(group));
+    }
+    Ok(())
+}
+
+/// Group names from `pueue group --json`, which prints an object keyed by
+/// group name.
+fn parse_group_names(json: &str) -> Option<Vec<String>> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    Some(value.as_object()?.keys().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(items: &[&str]) -> Vec<OsString> {
+        items.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn insert_group_arg_goes_right_after_add() {
+        assert_eq!(
+            insert_group_arg(&args(&["pueue", "add", "--", "make", "-j4"]), "build"),
+            Some(args(&["pueue", "add", "-g", "build", "--", "make", "-j4"]))
+        );
+        assert_eq!(
+            insert_group_arg(&args(&["/opt/bin/pueue", "add", "sleep 1"]), "slow"),
+            Some(args(&["/opt/bin/pueue", "add", "-g", "slow", "sleep 1"]))
+        );
+    }
+
+    #[test]
+    fn insert_group_arg_rejects_other_commands() {
+        assert_eq!(insert_group_arg(&args(&["pueue", "status"]), "g"), None);
+        assert_eq!(insert_group_arg(&args(&["pueue"]), "g"), None);
+        assert_eq!(
+            insert_group_arg(&args(&["cargo", "add", "serde"]), "g"),
+            None
+        );
+        assert_eq!(insert_group_arg(&args(&["pueued", "add"]), "g"), None);
+    }
+
+    #[test]
+    fn parse_group_names_reads_object_keys() {
+        let json = r#"{"default":{"status":"Running","parallel_tasks":1},"build":{}}"#;
+        let mut names = parse_group_names(json).unwrap();
+        names.sort();
+        assert_eq!(names, ["build", "default"]);
+        assert_eq!(
+            parse_group_names("Group \"default\" (1 parallel): running"),
+            None
+        );
+        assert_eq!(parse_group_names("[]"), None);
+    }
+}
@@ -1,12 +1,14 @@
 use std::fs;
-use std::io::Write;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use anyhow::{Context, Result};
 
-use crate::config::{pueue_binary_name, pueued_binary_name, shnote_bin_dir};
-use crate::i18n::I18n;
+use crate::cli::SetupArgs;
+use crate::config::{pueue_binary_name, pueued_binary_name, resolve_proxy, shnote_bin_dir, Config};
+use crate::i18n::{I18n, Marker};
+use crate::prompt::prompt_yes_no_with_reader;
 
 /// Embedded pueue version
 pub const PUEUE_VERSION: &str = "4.0.1";
@@ -125,7 +127,7 @@ pub(crate) mod embedded {
     pub const PLATFORM: &str = "unsupported";
 }
 
-pub fn run_setup(i18n: &I18n) -> Result<()> {
+pub fn run_setup(i18n: &I18n, config: &Config, args: SetupArgs) -> Result<()> {
     let bin_dir = shnote_bin_dir()?;
 
     println!("{}", i18n.setup_starting());
@@ -137,7 +139,13 @@ pub fn run_setup(i18n: &I18n) -> Result<()> {
     fs::create_dir_all(&bin_dir)
         .with_context(|| i18n.err_create_dir(&bin_dir.display().to_string()))?;
 
-    install_binaries(i18n, &bin_dir, embedded::PUEUE, embedded::PUEUED)?;
+    let github_proxy = resolve_proxy(config);
+    let options = DownloadOptions {
+        confirm: config.setup.confirm_download && !args.yes,
+        progress: config.show_download_progress(io::stderr().is_terminal()),
+        github_proxy: github_proxy.as_deref(),
+    };
+    install_binaries(i18n, &bin_dir, embedded::PUEUE, embedded::PUEUED, options)?;
 
     // Print PATH instructions
     println!();
@@ -162,15 +170,100 @@ pub fn run_setup(i18n: &I18n) -> Result<()> {
     Ok(())
 }
 
+/// Outcome of checking one installed binary against its compiled-in checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BinaryCheck {
+    Ok,
+    Missing,
+    /// No checksum is known for this platform
+    Unverified,
+    Mismatch {
+        actual: String,
+    },
+}
+
+/// `setup --check-only`: re-hash the installed pueue binaries and compare them
+/// with the checksums of the pinned release. Returns whether both match.
+pub fn check_installed(i18n: &I18n) -> Result<bool> {
+    let bin_dir = shnote_bin_dir()?;
+    println!("{}", i18n.setup_checking(&bin_dir.display().to_string()));
+
+    let mut all_ok = true;
+    for (name, file, expected) in [
+        ("pueue", pueue_binary_name(), checksums::PUEUE_SHA256),
+        ("pueued", pueued_binary_name(), checksums::PUEUED_SHA256),
+    ] {
+        let path = bin_dir.join(file);
+        let line = match check_binary(i18n, &path, expected)? {
+            BinaryCheck::Ok => format!(
+                "  {} {name}: {}",
+                i18n.marker(Marker::Ok),
+                i18n.setup_check_ok()
+            ),
+            BinaryCheck::Unverified => format!("  ? {name}: {}", i18n.setup_check_unverified()),
+            BinaryCheck::Missing => {
+                all_ok = false;
+                format!(
+                    "  {} {name}: {}",
+                    i18n.marker(Marker::Fail),
+                    i18n.setup_check_missing(&path.display().to_string())
+                )
+            }
+            BinaryCheck::Mismatch { actual } => {
+                all_ok = false;
+                format!(
+                    "  {} {name}: {}",
+                    i18n.marker(Marker::Fail),
+                    i18n.setup_check_mismatch(expected, &actual)
+                )
+            }
+        };
+        println!("{line}");
+    }
+
+    if !all_ok {
+        println!();
+        println!("{}", i18n.setup_check_reinstall_hint());
+    }
+    Ok(all_ok)
+}
+
+fn check_binary(i18n: &I18n, path: &PathBuf, expected_sha256: &str) -> Result<BinaryCheck> {
+    if !path.is_file() {
+        return Ok(BinaryCheck::Missing);
+    }
+    if expected_sha256.is_empty() {
+        return Ok(BinaryCheck::Unverified);
+    }
+    let actual = compute_sha256(i18n, path)?;
+    Ok(if actual == expected_sha256 {
+        BinaryCheck::Ok
+    } else {
+        BinaryCheck::Mismatch { actual }
+    })
+}
+
+/// How `setup` downloads binaries when none are embedded.
+#[derive(Debug, Clone, Copy, Default)]
+struct DownloadOptions<'a> {
+    /// Ask before downloading
+    confirm: bool,
+    /// Show curl's progress bar
+    progress: bool,
+    /// GitHub proxy prefixed to download URLs
+    github_proxy: Option<&'a str>,
+}
+
 fn install_binaries(
     i18n: &I18n,
     bin_dir: &Path,
     pueue: Option<&[u8]>,
     pueued: Option<&[u8]>,
+    options: DownloadOptions<'_>,
 ) -> Result<()> {
     match (pueue, pueued) {
         (Some(pueue), Some(pueued)) => extract_embedded_binaries(i18n, bin_dir, pueue, pueued),
-        _ => download_binaries(i18n, bin_dir),
+        _ => download_binaries(i18n, bin_dir, options),
     }
 }
 
@@ -185,51 +278,112 @@ fn extract_embedded_binaries(
     // Extract pueue
     let pueue_path = bin_dir.join(pueue_binary_name());
     write_binary(i18n, &pueue_path, pueue)?;
-    println!("  ✓ pueue -> {}", pueue_path.display());
+    println!(
+        "  {} pueue -> {}",
+        i18n.marker(Marker::Ok),
+        pueue_path.display()
+    );
 
     // Extract pueued
     let pueued_path = bin_dir.join(pueued_binary_name());
     write_binary(i18n, &pueued_path, pueued)?;
-    println!("  ✓ pueued -> {}", pueued_path.display());
+    println!(
+        "  {} pueued -> {}",
+        i18n.marker(Marker::Ok),
+        pueued_path.display()
+    );
 
     Ok(())
 }
 
-fn download_binaries(i18n: &I18n, bin_dir: &Path) -> Result<()> {
-    println!("{}", i18n.setup_downloading());
-    println!();
-
-    let github_proxy = std::env::var("GITHUB_PROXY").ok();
+fn download_binaries(i18n: &I18n, bin_dir: &Path, options: DownloadOptions<'_>) -> Result<()> {
     let base_url = format!(
         "https://github.com/Nukesor/pueue/releases/download/v{}/",
         PUEUE_VERSION
     );
-    let base_url = apply_github_proxy(&github_proxy, &base_url);
+    let base_url = apply_github_proxy(options.github_proxy, &base_url);
 
-    if let Some(proxy) = &github_proxy {
+    if let Some(proxy) = options.github_proxy {
         println!("  Using GitHub proxy: {}", proxy);
         println!();
     }
 
     let (pueue_filename, pueued_filename) = get_release_filenames();
+    let pueue_url = format!("{}{}", base_url, pueue_filename);
+    let pueued_url = format!("{}{}", base_url, pueued_filename);
+
+    if options.confirm {
+        let stdin = io::stdin();
+        let interactive = stdin.is_terminal();
+        confirm_download(
+            i18n,
+            &[pueue_url.as_str(), pueued_url.as_str()],
+            &mut stdin.lock(),
+            interactive,
+        )?;
+    }
+
+    println!("{}", i18n.setup_downloading());
+    println!();
 
     println!("  Downloading pueue...");
-    let pueue_url = format!("{}{}", base_url, pueue_filename);
     let pueue_path = bin_dir.join(pueue_binary_name());
-    download_and_verify(i18n, &pueue_url, &pueue_path, checksums::PUEUE_SHA256)?;
-    println!("  ✓ pueue -> {}", pueue_path.display());
+    download_and_verify(
+        i18n,
+        &pueue_url,
+        &pueue_path,
+        checksums::PUEUE_SHA256,
+        options.progress,
+    )?;
+    println!(
+        "  {} pueue -> {}",
+        i18n.marker(Marker::Ok),
+        pueue_path.display()
+    );
 
     println!("  Downloading pueued...");
-    let pueued_url = format!("{}{}", base_url, pueued_filename);
     let pueued_path = bin_dir.join(pueued_binary_name());
-    download_and_verify(i18n, &pueued_url, &pueued_path, checksums::PUEUED_SHA256)?;
-    println!("  ✓ pueued -> {}", pueued_path.display());
+    download_and_verify(
+        i18n,
+        &pueued_url,
+        &pueued_path,
+        checksums::PUEUED_SHA256,
+        options.progress,
+    )?;
+    println!(
+        "  {} pueued -> {}",
+        i18n.marker(Marker::Ok),
+        pueued_path.display()
+    );
+
+    Ok(())
+}
+
+/// Show the download URLs and ask before fetching anything.
+/// Without a terminal there is nobody to ask, so only `--yes` can proceed.
+fn confirm_download(
+    i18n: &I18n,
+    urls: &[&str],
+    reader: &mut dyn BufRead,
+    interactive: bool,
+) -> Result<()> {
+    println!("{}", i18n.setup_download_urls());
+    for url in urls {
+        println!("  - {url}");
+    }
+    println!();
 
+    if !interactive {
+        anyhow::bail!("{}", i18n.err_setup_confirm_requires_tty());
+    }
+    if !prompt_yes_no_with_reader(i18n.setup_confirm_download(), reader)? {
+        anyhow::bail!("{}", i18n.err_setup_download_declined());
+    }
     Ok(())
 }
 
-/// Apply GitHub proxy prefix to URL if GITHUB_PROXY is set
-fn apply_github_proxy(proxy: &Option<String>, url: &str) -> String {
+/// Apply GitHub proxy prefix to URL if a proxy is configured
+fn apply_github_proxy(proxy: Option<&str>, url: &str) -> String {
     match proxy {
         Some(p) => {
             let proxy = p.trim_end_matches('/');
@@ -291,8 +445,9 @@ fn download_and_verify(
     url: &str,
     dest: &PathBuf,
     expected_sha256: &str,
+    progress: bool,
 ) -> Result<()> {
-    download_file(i18n, url, dest)?;
+    download_file(i18n, url, dest, progress)?;
 
     // Verify SHA256 checksum
     if expected_sha256.is_empty() {
@@ -366,12 +521,17 @@ fn compute_sha256(i18n: &I18n, path: &PathBuf) -> Result<String> {
     }
 }
 
-fn download_file(i18n: &I18n, url: &str, dest: &PathBuf) -> Result<()> {
+fn download_file(i18n: &I18n, url: &str, dest: &PathBuf, progress: bool) -> Result<()> {
     #[cfg(unix)]
     {
-        // Try curl first
+        // Try curl first; its progress bar goes to stderr
+        let flags: &[&str] = if progress {
+            &["-fL", "--progress-bar", "-o"]
+        } else {
+            &["-fsSL", "-o"]
+        };
         let status = Command::new("curl")
-            .args(["-fsSL", "-o"])
+            .args(flags)
             .arg(dest)
             .arg(url)
             .stderr(Stdio::inherit())
@@ -408,6 +568,8 @@ fn download_file(i18n: &I18n, url: &str, dest: &PathBuf) -> Result<()> {
 
     #[cfg(windows)]
     {
+        // Invoke-WebRequest draws its own progress
+        let _ = progress;
         // Use PowerShell to download
         let script = format!(
             "Invoke-WebRequest -Uri '{}' -OutFile '{}'",
@@ -469,6 +631,7 @@ mod tests {
     use super::*;
     use crate::i18n::Lang;
     use crate::test_support::{env_lock, EnvVarGuard};
+    use std::io::Cursor;
     use tempfile::TempDir;
 
     #[test]
@@ -506,7 +669,7 @@ mod tests {
     #[test]
     fn apply_github_proxy_without_proxy() {
         let url = "https://github.com/example/file";
-        assert_eq!(apply_github_proxy(&None, url), url);
+        assert_eq!(apply_github_proxy(None, url), url);
     }
 
     #[test]
@@ -514,7 +677,7 @@ mod tests {
         let proxy = Some("https://ghfast.top".to_string());
         let url = "https://github.com/example/file";
         assert_eq!(
-            apply_github_proxy(&proxy, url),
+            apply_github_proxy(proxy.as_deref(), url),
             "https://ghfast.top/https://github.com/example/file"
         );
     }
@@ -524,7 +687,7 @@ mod tests {
         let proxy = Some("https://ghfast.top/".to_string());
         let url = "https://github.com/example/file";
         assert_eq!(
-            apply_github_proxy(&proxy, url),
+            apply_github_proxy(proxy.as_deref(), url),
             "https://ghfast.top/https://github.com/example/file"
         );
     }
@@ -557,7 +720,18 @@ mod tests {
         let pueue = b"pueue-bytes";
         let pueued = b"pueued-bytes";
 
-        install_binaries(&i18n, temp_dir.path(), Some(pueue), Some(pueued)).unwrap();
+        install_binaries(
+            &i18n,
+            temp_dir.path(),
+            Some(pueue),
+            Some(pueued),
+            DownloadOptions {
+                confirm: true,
+                progress: false,
+                github_proxy: None,
+            },
+        )
+        .unwrap();
 
         let pueue_path = temp_dir.path().join(pueue_binary_name());
         let pueued_path = temp_dir.path().join(pueued_binary_name());
@@ -566,6 +740,89 @@ mod tests {
         assert_eq!(fs::read(&pueued_path).unwrap(), pueued);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn check_binary_reports_checksum_mismatch() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let tools = make_fake_tools_dir();
+        let _path_guard = setup_path_with(&tools);
+        write_tool(
+            &tools,
+            "shasum",
+            r#"#!/bin/sh
+echo "abc123  $3"
+exit 0
+"#,
+        );
+
+        let bin_dir = TempDir::new().unwrap();
+        let path = bin_dir.path().join(pueue_binary_name());
+        assert_eq!(
+            check_binary(&i18n, &path, "abc123").unwrap(),
+            BinaryCheck::Missing
+        );
+
+        fs::write(&path, b"truncated").unwrap();
+        assert_eq!(
+            check_binary(&i18n, &path, "abc123").unwrap(),
+            BinaryCheck::Ok
+        );
+        assert_eq!(
+            check_binary(&i18n, &path, "def456").unwrap(),
+            BinaryCheck::Mismatch {
+                actual: "abc123".to_string()
+            }
+        );
+        assert_eq!(
+            check_binary(&i18n, &path, "").unwrap(),
+            BinaryCheck::Unverified
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_installed_fails_for_tampered_binary() {
+        let _lock = env_lock();
+        let i18n = test_i18n();
+        let temp_dir = TempDir::new().unwrap();
+        let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
+        let bin_dir = shnote_bin_dir().unwrap();
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join(pueue_binary_name()), b"tampered").unwrap();
+        fs::write(bin_dir.join(pueued_binary_name()), b"tampered").unwrap();
+
+        // The real shasum hashes "tampered", which matches no release binary;
+        // on platforms without known checksums nothing can be verified.
+        let expect_ok = checksums::PUEUE_SHA256.is_empty() && checksums::PUEUED_SHA256.is_empty();
+        assert_eq!(check_installed(&i18n).unwrap(), expect_ok);
+    }
+
+    #[test]
+    fn confirm_download_accepts_yes() {
+        let i18n = test_i18n();
+        let mut input = Cursor::new("y\n");
+        confirm_download(&i18n, &["https://example.invalid/pueue"], &mut input, true).unwrap();
+    }
+
+    #[test]
+    fn confirm_download_errors_when_declined() {
+        let i18n = test_i18n();
+        let mut input = Cursor::new("n\n");
+        let err = confirm_download(&i18n, &["https://example.invalid/pueue"], &mut input, true)
+            .unwrap_err();
+        assert_eq!(err.to_string(), i18n.err_setup_download_declined());
+    }
+
+    #[test]
+    fn confirm_download_requires_terminal() {
+        let i18n = test_i18n();
+        let mut input = Cursor::new("y\n");
+        let err = confirm_download(&i18n, &["https://example.invalid/pueue"], &mut input, false)
+            .unwrap_err();
+        assert_eq!(err.to_string(), i18n.err_setup_confirm_requires_tty());
+    }
+
     #[test]
     fn run_setup_errors_when_home_dir_missing() {
         let _lock = env_lock();
@@ -573,7 +830,7 @@ mod tests {
         let _userprofile_guard = EnvVarGuard::remove("USERPROFILE");
 
         let i18n = test_i18n();
-        let err = run_setup(&i18n).unwrap_err();
+        let err = run_setup(&i18n, &Config::default(), SetupArgs::default()).unwrap_err();
         assert!(err
             .to_string()
             .contains("failed to determine home directory"));
@@ -591,7 +848,7 @@ mod tests {
         let empty_path = TempDir::new().unwrap();
         let _path_guard = EnvVarGuard::set("PATH", empty_path.path());
 
-        let err = run_setup(&i18n).unwrap_err();
+        let err = run_setup(&i18n, &Config::default(), SetupArgs::default()).unwrap_err();
         assert!(err.to_string().contains(i18n.err_download_no_tool()));
     }
 
@@ -651,7 +908,7 @@ exit 0
         );
 
         let bin_dir = TempDir::new().unwrap();
-        let err = download_binaries(&i18n, bin_dir.path()).unwrap_err();
+        let err = download_binaries(&i18n, bin_dir.path(), DownloadOptions::default()).unwrap_err();
         assert!(err.to_string().contains("checksum"));
     }
 
@@ -689,7 +946,7 @@ exit 0\n"
         );
 
         let bin_dir = TempDir::new().unwrap();
-        let err = download_binaries(&i18n, bin_dir.path()).unwrap_err();
+        let err = download_binaries(&i18n, bin_dir.path(), DownloadOptions::default()).unwrap_err();
         assert!(err.to_string().contains("checksum"));
     }
 
@@ -714,7 +971,7 @@ exit 0
 
         let temp_dir = TempDir::new().unwrap();
         let dest = temp_dir.path().join("pueue");
-        download_file(&i18n, "https://example.invalid/pueue", &dest).unwrap();
+        download_file(&i18n, "https://example.invalid/pueue", &dest, false).unwrap();
         assert_eq!(fs::read_to_string(dest).unwrap(), "bin");
     }
 
@@ -737,7 +994,7 @@ exit 0
         );
 
         let dest = PathBuf::from("/dev/null");
-        assert!(download_file(&i18n, "https://example.invalid/pueue", &dest).is_err());
+        assert!(download_file(&i18n, "https://example.invalid/pueue", &dest, false).is_err());
     }
 
     #[cfg(unix)]
@@ -760,7 +1017,7 @@ exit 0
         );
 
         let dest = PathBuf::from("/dev/null");
-        assert!(download_file(&i18n, "https://example.invalid/pueue", &dest).is_err());
+        assert!(download_file(&i18n, "https://example.invalid/pueue", &dest, false).is_err());
     }
 
     #[cfg(unix)]
@@ -775,7 +1032,7 @@ exit 0
 
         let temp_dir = TempDir::new().unwrap();
         let dest = temp_dir.path().join("pueue");
-        let err = download_file(&i18n, "https://example.invalid/pueue", &dest).unwrap_err();
+        let err = download_file(&i18n, "https://example.invalid/pueue", &dest, false).unwrap_err();
         assert!(err.to_string().contains(i18n.err_download_no_tool()));
     }
 
@@ -792,7 +1049,7 @@ exit 0
 
         let temp_dir = TempDir::new().unwrap();
         let dest = temp_dir.path().join("pueue");
-        let err = download_file(&i18n, "https://example.invalid/pueue", &dest).unwrap_err();
+        let err = download_file(&i18n, "https://example.invalid/pueue", &dest, false).unwrap_err();
         assert!(err.to_string().contains(i18n.err_download_failed()));
     }
 
@@ -829,6 +1086,7 @@ exit 0
             "https://example.invalid/pueue",
             &dest,
             "expectedhash",
+            false,
         )
         .unwrap_err();
 
@@ -847,8 +1105,8 @@ exit 0
         write_tool(&tools, "curl", "#!/bin/sh\nexit 1\n");
         let temp_dir = TempDir::new().unwrap();
         let dest = temp_dir.path().join("pueue");
-        let err =
-            download_and_verify(&i18n, "https://example.invalid/pueue", &dest, "").unwrap_err();
+        let err = download_and_verify(&i18n, "https://example.invalid/pueue", &dest, "", false)
+            .unwrap_err();
         assert!(err.to_string().contains(i18n.err_download_no_tool()));
     }
 
@@ -872,8 +1130,14 @@ exit 0
 
         let temp_dir = TempDir::new().unwrap();
         let dest = temp_dir.path().join("pueue");
-        let err = download_and_verify(&i18n, "https://example.invalid/pueue", &dest, "expected")
-            .unwrap_err();
+        let err = download_and_verify(
+            &i18n,
+            "https://example.invalid/pueue",
+            &dest,
+            "expected",
+            false,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains(i18n.err_shasum_run()));
     }
 
@@ -897,7 +1161,7 @@ exit 0
 
         let temp_dir = TempDir::new().unwrap();
         let dest = temp_dir.path().join("pueue");
-        download_and_verify(&i18n, "https://example.invalid/pueue", &dest, "").unwrap();
+        download_and_verify(&i18n, "https://example.invalid/pueue", &dest, "", false).unwrap();
         assert_eq!(fs::read_to_string(dest).unwrap(), "downloaded");
     }
 
@@ -1007,7 +1271,7 @@ exit 0
         let shnote_home = temp_dir.path().join(".shnote");
         fs::write(&shnote_home, "not a dir").unwrap();
 
-        let err = run_setup(&i18n).unwrap_err();
+        let err = run_setup(&i18n, &Config::default(), SetupArgs::default()).unwrap_err();
         let expected =
             i18n.err_create_dir(&temp_dir.path().join(".shnote/bin").display().to_string());
         assert!(err.to_string().contains(&expected));
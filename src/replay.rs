@@ -0,0 +1,318 @@
+//! `shnote replay <id>`: re-run a command recorded in the history log.
+//!
+//! Replay is resolved before dispatch: the recorded entry is turned back into
+//! the execution command it came from, so the WHAT/WHY header, history and
+//! exit-code handling apply exactly as for a fresh invocation.
+
+use std::ffi::OsString;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::{BatchArgs, Cli, Command, PassthroughArgs, ReplayArgs, RunArgs, ScriptArgs};
+use crate::exit_code;
+use crate::history::{history_path, read_records, HistoryRecord};
+use crate::i18n::I18n;
+use crate::prompt::prompt_yes_no_with_reader;
+
+/// Programs whose replay asks for confirmation.
+const DESTRUCTIVE_PROGRAMS: &[&str] = &[
+    "rm", "rmdir", "dd", "mkfs", "shred", "truncate", "kill", "pkill", "killall",
+];
+
+/// Arguments whose presence asks for confirmation (`git push --force`,
+/// `git reset --hard`, `rm -rf`, ...).
+const DESTRUCTIVE_ARGS: &[&str] = &["--force", "--hard", "-rf", "-fr", "--delete"];
+
+/// Replace a `replay` command in `cli` with the recorded command it refers to,
+/// taking WHAT/WHY from the record when `--reuse-note` is given, and move into
+/// the recorded working directory. Other commands are left untouched.
+pub fn prepare(i18n: &I18n, cli: &mut Cli) -> Result<()> {
+    let Command::Replay(args) = &cli.command else {
+        return Ok(());
+    };
+    if !args.reuse_note && (cli.what.is_none() || cli.why.is_none()) {
+        return Err(exit_code::validation(i18n.err_missing_what_why("replay")));
+    }
+    let records = read_records(&history_path()?)?;
+    let stdin = io::stdin();
+    let record = select_record(i18n, &records, args, &mut stdin.lock())?;
+
+    if args.reuse_note {
+        cli.what.get_or_insert_with(|| record.what.clone());
+        cli.why.get_or_insert_with(|| record.why.clone());
+    }
+    if !record.cwd.is_empty() {
+        std::env::set_current_dir(&record.cwd).with_context(|| i18n.err_replay_cwd(&record.cwd))?;
+    }
+    cli.command = rebuild_command(i18n, record)?;
+    Ok(())
+}
+
+/// Look up entry `args.id` (1-based) and confirm destructive-looking commands.
+fn select_record<'a>(
+    i18n: &I18n,
+    records: &'a [HistoryRecord],
+    args: &ReplayArgs,
+    reader: &mut dyn BufRead,
+) -> Result<&'a HistoryRecord> {
+    let record = args
+        .id
+        .checked_sub(1)
+        .and_then(|index| records.get(index))
+        .ok_or_else(|| anyhow::anyhow!(i18n.err_replay_not_found(args.id, records.len())))?;
+    if record.has_masked_args() {
+        anyhow::bail!("{}", i18n.err_replay_masked(args.id));
+    }
+
+    if !args.yes && looks_destructive(&record.argv) {
+        let command = format!("{} {}", record.command, record.argv.join(" "));
+        if !prompt_yes_no_with_reader(&i18n.replay_confirm(&command), reader)? {
+            anyhow::bail!("{}", i18n.replay_cancelled());
+        }
+    }
+    Ok(record)
+}
+
+/// Whether any word of the recorded arguments names a destructive program or
+/// flag. Single-string `run` commands are split on whitespace first.
+fn looks_destructive(argv: &[String]) -> bool {
+    argv.iter()
+        .flat_map(|arg| arg.split_whitespace())
+        .any(|word| {
+            let program = Path::new(word)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(word);
+            DESTRUCTIVE_PROGRAMS.contains(&program) || DESTRUCTIVE_ARGS.contains(&word)
+        })
+}
+
+/// Turn a history record back into the command that produced it.
+fn rebuild_command(i18n: &I18n, record: &HistoryRecord) -> Result<Command> {
+    let argv: Vec<OsString> = record.argv.iter().map(OsString::from).collect();
+    let passthrough = || PassthroughArgs {
+        cwd: None,
+        args: argv.clone(),
+    };
+    Ok(match record.command.as_str() {
+        "run" => Command::Run(RunArgs {
+            command: argv.clone(),
+            ..Default::default()
+        }),
+        "py" => Command::Py(script_args(&record.argv)),
+        "node" => Command::Node(script_args(&record.argv)),
+        "sh" => Command::Sh(script_args(&record.argv)),
+        "pip" => Command::Pip(passthrough()),
+        "npm" => Command::Npm(passthrough()),
+        "npx" => Command::Npx(passthrough()),
+        "batch" => Command::Batch(batch_args(&record.argv)),
+        other => anyhow::bail!("{}", i18n.err_replay_unknown_command(other)),
+    })
+}
+
+/// Inverse of the history's script argv: an optional `--interpreter PATH`,
+/// then `-c CODE`, `-f FILE` or `--stdin`, followed by the script arguments.
+fn script_args(argv: &[String]) -> ScriptArgs {
+    let mut args = ScriptArgs {
+        code: None,
+        file: None,
+        stdin: false,
+        interpreter: None,
+        args: Vec::new(),
+    };
+    let argv = match argv {
+        [flag, interpreter, rest @ ..] if flag == "--interpreter" => {
+            args.interpreter = Some(interpreter.clone());
+            rest
+        }
+        rest => rest,
+    };
+    let rest = match argv {
+        [flag, code, rest @ ..] if flag == "-c" => {
+            args.code = Some(code.clone());
+            rest
+        }
+        [flag, file, rest @ ..] if flag == "-f" => {
+            args.file = Some(PathBuf::from(file));
+            rest
+        }
+        [flag, rest @ ..] if flag == "--stdin" => {
+            args.stdin = true;
+            rest
+        }
+        rest => rest,
+    };
+    args.args = rest.iter().map(OsString::from).collect();
+    args
+}
+
+/// Inverse of the history's batch argv: flags, then the file.
+fn batch_args(argv: &[String]) -> BatchArgs {
+    let mut args = BatchArgs::default();
+    let mut argv = argv.iter();
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--keep-going" => args.keep_going = true,
+            "--json" => args.json = true,
+            "--parallel" => args.parallel = argv.next().and_then(|jobs| jobs.parse().ok()),
+            file => args.file = PathBuf::from(file),
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use std::io::Cursor;
+
+    fn test_i18n() -> I18n {
+        I18n::new(Lang::En)
+    }
+
+    fn record(command: &str, argv: &[&str]) -> HistoryRecord {
+        HistoryRecord {
+            ts: 0,
+            command: command.to_string(),
+            argv: argv.iter().map(|arg| arg.to_string()).collect(),
+            what: "w".to_string(),
+            why: "y".to_string(),
+            tag: None,
+            cwd: "/tmp".to_string(),
+            success: true,
+            exit_code: Some(0),
+            duration_ms: None,
+            stdout_excerpt: None,
+            stderr_excerpt: None,
+            stdout_bytes: None,
+            stderr_bytes: None,
+        }
+    }
+
+    fn replay_args(id: usize, yes: bool) -> ReplayArgs {
+        ReplayArgs {
+            id,
+            reuse_note: false,
+            yes,
+        }
+    }
+
+    #[test]
+    fn select_record_validates_id() {
+        let i18n = test_i18n();
+        let records = vec![
+            record("run", &["echo", "one"]),
+            record("run", &["echo", "two"]),
+        ];
+        let mut input = Cursor::new("");
+
+        let found = select_record(&i18n, &records, &replay_args(2, false), &mut input).unwrap();
+        assert_eq!(found.argv, ["echo", "two"]);
+        for id in [0, 3] {
+            let err = select_record(&i18n, &records, &replay_args(id, false), &mut input)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(&format!("#{id}")), "{err}");
+        }
+    }
+
+    #[test]
+    fn select_record_confirms_destructive_commands() {
+        let i18n = test_i18n();
+        let records = vec![record("run", &["rm -rf build"])];
+
+        let mut declined = Cursor::new("n\n");
+        let err = select_record(&i18n, &records, &replay_args(1, false), &mut declined)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cancelled"));
+
+        let mut accepted = Cursor::new("y\n");
+        assert!(select_record(&i18n, &records, &replay_args(1, false), &mut accepted).is_ok());
+
+        let mut untouched = Cursor::new("");
+        assert!(select_record(&i18n, &records, &replay_args(1, true), &mut untouched).is_ok());
+    }
+
+    #[test]
+    fn select_record_rejects_masked_arguments() {
+        let i18n = test_i18n();
+        let records = vec![record("run", &["curl -u admin:*** https://***@host/x"])];
+        let err = select_record(&i18n, &records, &replay_args(1, true), &mut Cursor::new(""))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("#1 had credentials masked"), "{err}");
+    }
+
+    #[test]
+    fn looks_destructive_matches_programs_and_flags() {
+        let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert!(looks_destructive(&words(&["/bin/rm", "x"])));
+        assert!(looks_destructive(&words(&["git", "push", "--force"])));
+        assert!(looks_destructive(&words(&["git reset --hard HEAD"])));
+        assert!(!looks_destructive(&words(&["ls", "-la"])));
+        assert!(!looks_destructive(&words(&["echo form"])));
+    }
+
+    #[test]
+    fn rebuild_command_restores_each_kind() {
+        let i18n = test_i18n();
+
+        let Command::Run(args) = rebuild_command(&i18n, &record("run", &["ls", "-la"])).unwrap()
+        else {
+            panic!("expected run");
+        };
+        assert_eq!(args.command, ["ls", "-la"]);
+
+        let Command::Py(args) =
+            rebuild_command(&i18n, &record("py", &["-c", "print(1)", "x"])).unwrap()
+        else {
+            panic!("expected py");
+        };
+        assert_eq!(args.code.as_deref(), Some("print(1)"));
+        assert_eq!(args.args, ["x"]);
+
+        let Command::Node(args) = rebuild_command(&i18n, &record("node", &["-f", "a.js"])).unwrap()
+        else {
+            panic!("expected node");
+        };
+        assert_eq!(args.file, Some(PathBuf::from("a.js")));
+
+        let Command::Npm(args) = rebuild_command(&i18n, &record("npm", &["install"])).unwrap()
+        else {
+            panic!("expected npm");
+        };
+        assert_eq!(args.args, ["install"]);
+
+        let Command::Batch(args) =
+            rebuild_command(&i18n, &record("batch", &["--keep-going", "steps.txt"])).unwrap()
+        else {
+            panic!("expected batch");
+        };
+        assert!(args.keep_going && !args.json);
+        assert_eq!(args.file, PathBuf::from("steps.txt"));
+
+        assert!(rebuild_command(&i18n, &record("config", &[])).is_err());
+    }
+
+    #[test]
+    fn script_args_reads_stdin_source() {
+        let args = script_args(&["--stdin".to_string(), "a".to_string()]);
+        assert!(args.stdin);
+        assert_eq!(args.args, ["a"]);
+        assert_eq!(args.source_count(), 1);
+    }
+
+    #[test]
+    fn script_args_reads_interpreter_override() {
+        let argv =
+            ["--interpreter", "/opt/py/bin/python", "-c", "print(1)", "a"].map(str::to_string);
+        let args = script_args(&argv);
+        assert_eq!(args.interpreter.as_deref(), Some("/opt/py/bin/python"));
+        assert_eq!(args.code.as_deref(), Some("print(1)"));
+        assert_eq!(args.args, ["a"]);
+    }
+}
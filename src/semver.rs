@@ -0,0 +1,89 @@
+//! Minimal semantic-version parsing shared by tool probing and version checks.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct SemVer {
+    major: u64,
+    minor: u64,
+    patch: u64,
+}
+
+impl SemVer {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Parse the first `X[.Y[.Z]]` token in `text` (e.g. `Python 3.12.1`, `v20.1.0`).
+pub fn parse_semver_from_text(text: &str) -> Option<SemVer> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let mut end = start;
+    for (idx, c) in text[start..].char_indices() {
+        if matches!(c, '0'..='9' | '.') {
+            end = start + idx + c.len_utf8();
+        } else {
+            break;
+        }
+    }
+
+    // Since find() guarantees start points to a digit, and the loop includes
+    // that digit, raw will always contain at least one digit after trimming.
+    let raw = text[start..end].trim_matches('.');
+
+    let mut parts = raw.split('.');
+    // split() always yields at least one element, even for empty string
+    let major_str = parts
+        .next()
+        .expect("split always yields at least one element");
+    let major = major_str.parse().ok()?;
+    let minor = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let patch = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    Some(SemVer {
+        major,
+        minor,
+        patch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_semver_from_text_parses_first_version_token() {
+        assert_eq!(
+            parse_semver_from_text("2.0.69 (Claude Code)"),
+            Some(SemVer::new(2, 0, 69))
+        );
+        assert_eq!(
+            parse_semver_from_text("codex-cli 0.72.0"),
+            Some(SemVer::new(0, 72, 0))
+        );
+        assert_eq!(
+            parse_semver_from_text("v2.0.64"),
+            Some(SemVer::new(2, 0, 64))
+        );
+        assert_eq!(parse_semver_from_text("no version here"), None);
+        // Test version string with only dots returns None
+        assert_eq!(parse_semver_from_text("..."), None);
+        // Test version with number too large to parse as u32
+        assert_eq!(parse_semver_from_text("99999999999999999999.0.0"), None);
+    }
+
+    #[test]
+    fn semver_orders_and_displays() {
+        assert!(SemVer::new(3, 10, 0) > SemVer::new(3, 9, 12));
+        assert_eq!(SemVer::new(20, 1, 0).to_string(), "20.1.0");
+    }
+}
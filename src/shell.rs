@@ -6,7 +6,7 @@ use which::which;
 
 use crate::i18n::I18n;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShellType {
     Sh,
     Bash,
@@ -38,7 +38,6 @@ impl ShellType {
     }
 
     /// Returns the argument to pass inline script code
-    #[allow(dead_code)]
     pub fn code_flag(&self) -> &'static str {
         match self {
             Self::Sh | Self::Bash | Self::Zsh => "-c",
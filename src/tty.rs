@@ -0,0 +1,226 @@
+//! Terminal handling for `run --tty/--no-tty`.
+//!
+//! By default the child inherits shnote's stdio. `--no-tty` detaches its stdin
+//! (reads see EOF immediately), and `--tty` runs it on a fresh pseudo-terminal
+//! so tools enable colors and prompts even when shnote's own stdio is piped.
+//! PTYs are Unix only; elsewhere `--tty` prints a warning and inherits stdio.
+//!
+//! Interactive programs such as REPLs need the terminal to themselves: an
+//! output prefix pipes their stdout, and a header written to stdout can land
+//! in the middle of a prompt. `--stdin-tty-passthrough` turns both off, and
+//! it is implied when stdin and stdout are terminals and the command looks
+//! like a REPL.
+
+use std::ffi::OsString;
+use std::path::Path;
+#[cfg(unix)]
+use std::process::{Child, Command};
+
+use crate::cli::RunArgs;
+
+/// Programs that start an interactive session when run without arguments
+/// (or with only `-i`).
+const REPL_PROGRAMS: &[&str] = &[
+    "python",
+    "python3",
+    "ipython",
+    "node",
+    "deno",
+    "irb",
+    "lua",
+    "ghci",
+    "R",
+    "sh",
+    "bash",
+    "zsh",
+    "fish",
+    "psql",
+    "mysql",
+    "sqlite3",
+    "redis-cli",
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TtyMode {
+    /// Inherit shnote's stdin/stdout/stderr
+    #[default]
+    Inherit,
+    /// Stdin from /dev/null (`--no-tty`)
+    Detached,
+    /// Stdin/stdout/stderr on a new pseudo-terminal (`--tty`)
+    Pty,
+}
+
+impl TtyMode {
+    pub fn from_flags(tty: bool, no_tty: bool) -> Self {
+        match (tty, no_tty) {
+            (true, _) => Self::Pty,
+            (false, true) => Self::Detached,
+            (false, false) => Self::Inherit,
+        }
+    }
+}
+
+/// Whether `run` should leave the terminal to the command: forced by
+/// `--stdin-tty-passthrough`, otherwise when both stdin and stdout are
+/// terminals and the command looks interactive.
+pub fn wants_passthrough(args: &RunArgs, stdin_is_tty: bool, stdout_is_tty: bool) -> bool {
+    if args.stdin_tty_passthrough {
+        return true;
+    }
+    let other_mode = args.no_tty || args.binary_safe || args.until.is_some();
+    stdin_is_tty && stdout_is_tty && !other_mode && looks_interactive(&args.command)
+}
+
+/// A known REPL program with no arguments other than `-i`.
+fn looks_interactive(command: &[OsString]) -> bool {
+    let Some((program, rest)) = command.split_first() else {
+        return false;
+    };
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    REPL_PROGRAMS.contains(&name) && rest.iter().all(|arg| arg == "-i")
+}
+
+/// Spawn `cmd` with a new pseudo-terminal as its controlling terminal and
+/// stdio. Returns the child and the master side, which yields everything the
+/// child writes (stdout and stderr merged, as on a real terminal) until the
+/// child's side is closed; after that, reads fail with `EIO`.
+#[cfg(unix)]
+pub fn spawn_in_pty(cmd: &mut Command) -> std::io::Result<(Child, std::fs::File)> {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::os::unix::process::CommandExt;
+    use std::process::Stdio;
+    use std::ptr;
+
+    let mut master = -1;
+    let mut slave = -1;
+    let winsize = terminal_size();
+    // SAFETY: the out-pointers are valid and `winsize` outlives the call; on
+    // success both descriptors are fresh and owned by us.
+    let ret = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null(),
+            winsize
+                .as_ref()
+                .map_or(ptr::null(), |size| size as *const _),
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openpty succeeded, so both descriptors are open and unowned.
+    let (master, slave) = unsafe { (File::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+    cmd.stdin(Stdio::from(slave.try_clone()?));
+    cmd.stdout(Stdio::from(slave.try_clone()?));
+    cmd.stderr(Stdio::from(slave));
+    // SAFETY: the closure runs between fork and exec and only calls setsid and
+    // ioctl, which are async-signal-safe, and does not allocate.
+    unsafe {
+        cmd.pre_exec(|| {
+            // A new session lets the PTY (now fd 0) become the controlling terminal.
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = cmd.spawn();
+
+    // Drop our copies of the child's side, or reading the master never ends.
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+    Ok((child?, master))
+}
+
+/// Size of the terminal on shnote's stdout, copied to the PTY so tools lay
+/// out their output for the real window.
+#[cfg(unix)]
+fn terminal_size() -> Option<libc::winsize> {
+    // SAFETY: an all-zero winsize is valid, and TIOCGWINSZ only writes into it.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    // SAFETY: `size` is a valid winsize for the ioctl to fill.
+    let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (ret == 0 && size.ws_col > 0).then_some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_flags_prefers_tty() {
+        assert_eq!(TtyMode::from_flags(false, false), TtyMode::Inherit);
+        assert_eq!(TtyMode::from_flags(false, true), TtyMode::Detached);
+        assert_eq!(TtyMode::from_flags(true, false), TtyMode::Pty);
+    }
+
+    fn run_args(command: &[&str]) -> RunArgs {
+        RunArgs {
+            command: command.iter().map(OsString::from).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn looks_interactive_matches_bare_repls() {
+        assert!(looks_interactive(&run_args(&["python3"]).command));
+        assert!(looks_interactive(&run_args(&["/usr/bin/node"]).command));
+        assert!(looks_interactive(&run_args(&["python", "-i"]).command));
+        assert!(!looks_interactive(
+            &run_args(&["python3", "app.py"]).command
+        ));
+        assert!(!looks_interactive(&run_args(&["ls"]).command));
+        assert!(!looks_interactive(&[]));
+    }
+
+    #[test]
+    fn wants_passthrough_needs_both_terminals() {
+        let repl = run_args(&["python3"]);
+        assert!(wants_passthrough(&repl, true, true));
+        assert!(!wants_passthrough(&repl, false, true));
+        assert!(!wants_passthrough(&repl, true, false));
+        assert!(!wants_passthrough(&run_args(&["make"]), true, true));
+    }
+
+    #[test]
+    fn wants_passthrough_flag_forces_it_and_other_modes_disable_it() {
+        let forced = RunArgs {
+            stdin_tty_passthrough: true,
+            ..run_args(&["make"])
+        };
+        assert!(wants_passthrough(&forced, false, false));
+
+        let detached = RunArgs {
+            no_tty: true,
+            ..run_args(&["python3"])
+        };
+        assert!(!wants_passthrough(&detached, true, true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_in_pty_gives_child_a_terminal() {
+        use std::io::Read;
+
+        let mut cmd = Command::new("/bin/sh");
+        cmd.arg("-c")
+            .arg("test -t 0 && test -t 1 && test -t 2 && echo all-tty");
+        let (mut child, mut master) = spawn_in_pty(&mut cmd).unwrap();
+
+        let mut output = Vec::new();
+        // EIO after the child exits ends the output.
+        let _ = master.read_to_end(&mut output);
+        assert!(child.wait().unwrap().success());
+        assert!(String::from_utf8_lossy(&output).contains("all-tty"));
+    }
+}
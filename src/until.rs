@@ -0,0 +1,322 @@
+//! `run --until PATTERN`: start a command, wait until its output contains
+//! PATTERN, then leave it running in the background.
+//!
+//! The child's stdout and stderr go to a log file (in `run_log_dir`, by
+//! default under the data directory) rather than a pipe, so the child can
+//! keep writing after shnote exits. Logs are named after their start time.
+//! shnote follows the log, forwarding it to stdout, until a line contains
+//! the pattern (plain text, not a regex). The child gets its own process
+//! group on Unix, so a Ctrl-C in the terminal later does not reach it.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::agent;
+use crate::config::{data_dir, home_dir, Config};
+use crate::exit_code;
+use crate::i18n::I18n;
+
+/// Default directory for `--until` logs (`<data_dir>/run-logs`).
+const LOG_DIR_NAME: &str = "run-logs";
+
+/// How often the log is checked for new output.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What `run --until` waits for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadyWait {
+    /// Text that marks the command as ready
+    pub pattern: String,
+    /// Give up (and stop the command) after this long
+    pub timeout: Duration,
+    /// Where the command's output is logged
+    pub log_dir: PathBuf,
+}
+
+/// The configured `run_log_dir`: `~/` is the home directory and relative
+/// paths are under the data directory; empty means `<data_dir>/run-logs`.
+pub fn log_dir(config: &Config) -> Result<PathBuf> {
+    let configured = config.run.log_dir.trim();
+    if let Some(rest) = configured.strip_prefix("~/") {
+        return Ok(home_dir()?.join(rest));
+    }
+    let data_dir = data_dir()?;
+    Ok(match configured {
+        "" => data_dir.join(LOG_DIR_NAME),
+        dir => data_dir.join(dir),
+    })
+}
+
+/// How waiting for the pattern ended.
+#[derive(Debug)]
+enum WaitOutcome {
+    Ready,
+    Exited(ExitStatus),
+    TimedOut,
+}
+
+/// Spawn `cmd` with its output going to a fresh log file and wait for
+/// `wait.pattern`. Returns 0 once it appears, leaving the command running.
+pub fn spawn_until_ready(
+    i18n: &I18n,
+    config: &Config,
+    cmd: &mut Command,
+    program: &str,
+    wait: &ReadyWait,
+) -> Result<u8> {
+    let (log, log_path) = create_log_in(&wait.log_dir)?;
+    cmd.stdin(Stdio::null());
+    cmd.stdout(log.try_clone().context("failed to open run log")?);
+    cmd.stderr(log);
+    detach(cmd);
+    let mut child = cmd
+        .spawn()
+        .map_err(|err| exit_code::spawn_failed(i18n.err_failed_to_execute(program), err))?;
+
+    let mut reader = File::open(&log_path).context("failed to open run log")?;
+    let started = Instant::now();
+    let outcome = wait_for_pattern(
+        &mut child,
+        &mut reader,
+        &mut io::stdout(),
+        &wait.pattern,
+        wait.timeout,
+    )?;
+
+    let log_display = log_path.display().to_string();
+    match outcome {
+        WaitOutcome::Ready => {
+            agent::info(
+                config.agent_mode,
+                &i18n.run_until_ready(
+                    child.id(),
+                    &format!("{:.1}s", started.elapsed().as_secs_f64()),
+                    &log_display,
+                ),
+            );
+            Ok(0)
+        }
+        WaitOutcome::Exited(status) => anyhow::bail!(
+            "{}",
+            i18n.err_until_exited(&wait.pattern, &status.to_string(), &log_display)
+        ),
+        WaitOutcome::TimedOut => {
+            stop(&mut child);
+            let _ = child.wait();
+            anyhow::bail!(
+                "{}",
+                i18n.err_until_timeout(&wait.pattern, wait.timeout.as_secs(), &log_display)
+            )
+        }
+    }
+}
+
+/// Create `run-<YYYYMMDD-HHMMSS>-<random>.log` in `dir`. Directories
+/// shnote creates are private to the user (0700 on Unix); the log itself is
+/// created 0600.
+fn create_log_in(dir: &Path) -> Result<(File, PathBuf)> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+
+        builder.mode(0o700);
+    }
+    builder
+        .create(dir)
+        .with_context(|| format!("failed to create directory: {}", dir.display()))?;
+    let started = crate::history::format_timestamp(crate::history::unix_now())
+        .replace('-', "")
+        .replace(' ', "-")
+        .replace(':', "");
+    tempfile::Builder::new()
+        .prefix(&format!("run-{started}-"))
+        .suffix(".log")
+        .tempfile_in(dir)
+        .context("failed to create run log")?
+        .keep()
+        .context("failed to create run log")
+}
+
+#[cfg(unix)]
+fn detach(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    cmd.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn detach(_cmd: &mut Command) {}
+
+/// Kill the command and, on Unix, everything it started: the child leads
+/// its own process group (see [`detach`]).
+#[cfg(unix)]
+fn stop(child: &mut Child) {
+    // SAFETY: kill takes no pointers; a negative PID names the child's group.
+    unsafe {
+        libc::kill(-(child.id() as i32), libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Forward everything the child writes to `log` into `out` until a line
+/// contains `pattern`, the child exits, or `timeout` passes.
+fn wait_for_pattern(
+    child: &mut Child,
+    log: &mut impl Read,
+    out: &mut impl Write,
+    pattern: &str,
+    timeout: Duration,
+) -> Result<WaitOutcome> {
+    let deadline = Instant::now() + timeout;
+    // Output after the last newline, kept so a pattern split across reads still matches
+    let mut partial_line = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        // Check for exit before draining, so output written just before
+        // exiting is still searched.
+        let exited = child.try_wait().context("failed to wait for command")?;
+        loop {
+            let n = log.read(&mut buf).context("failed to read run log")?;
+            if n == 0 {
+                break;
+            }
+            out.write_all(&buf[..n])?;
+            partial_line.extend_from_slice(&buf[..n]);
+            if String::from_utf8_lossy(&partial_line).contains(pattern) {
+                out.flush()?;
+                return Ok(WaitOutcome::Ready);
+            }
+            if let Some(pos) = partial_line.iter().rposition(|&byte| byte == b'\n') {
+                partial_line.drain(..=pos);
+            }
+        }
+        out.flush()?;
+
+        if let Some(status) = exited {
+            return Ok(WaitOutcome::Exited(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(WaitOutcome::TimedOut);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn spawn_logged(script: &str, dir: &Path) -> (Child, File) {
+        let (log, path) = create_log_in(dir).unwrap();
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .stdout(log.try_clone().unwrap())
+            .stderr(log)
+            .spawn()
+            .unwrap();
+        (child, File::open(path).unwrap())
+    }
+
+    #[test]
+    fn wait_for_pattern_returns_once_pattern_is_printed() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut child, mut log) = spawn_logged(
+            "echo starting; sleep 0.2; echo 'Listening on :8080' >&2; sleep 30",
+            temp_dir.path(),
+        );
+
+        let mut out = Vec::new();
+        let started = Instant::now();
+        let outcome = wait_for_pattern(
+            &mut child,
+            &mut log,
+            &mut out,
+            "Listening on",
+            Duration::from_secs(10),
+        )
+        .unwrap();
+        assert!(matches!(outcome, WaitOutcome::Ready));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(child.try_wait().unwrap(), None, "command keeps running");
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "starting\nListening on :8080\n"
+        );
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn create_log_in_makes_private_dir_and_timestamped_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("a/b");
+        let (_, path) = create_log_in(&dir).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&path), 0o600);
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let stamp = &name["run-".len()..name.len() - "-XXXXXX.log".len()];
+        assert_eq!(stamp.len(), "YYYYMMDD-HHMMSS".len(), "{name}");
+        assert!(
+            stamp.chars().all(|c| c.is_ascii_digit() || c == '-'),
+            "{name}"
+        );
+    }
+
+    #[test]
+    fn wait_for_pattern_reports_early_exit() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut child, mut log) = spawn_logged("echo nope; exit 3", temp_dir.path());
+
+        let outcome = wait_for_pattern(
+            &mut child,
+            &mut log,
+            &mut Vec::new(),
+            "ready",
+            Duration::from_secs(10),
+        )
+        .unwrap();
+        let WaitOutcome::Exited(status) = outcome else {
+            panic!("expected exit, got {outcome:?}");
+        };
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn wait_for_pattern_times_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut child, mut log) = spawn_logged("echo waiting; sleep 30", temp_dir.path());
+
+        let outcome = wait_for_pattern(
+            &mut child,
+            &mut log,
+            &mut Vec::new(),
+            "ready",
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        assert!(matches!(outcome, WaitOutcome::TimedOut));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
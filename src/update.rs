@@ -1,19 +1,22 @@
 use std::collections::BTreeMap;
-use std::env;
 use std::fs;
 use std::fs::File;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, IsTerminal};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Deserializer};
 
-use crate::cli::{InitTarget, UpdateArgs};
-use crate::config::home_dir;
+use crate::cli::{ChecksumAlgo, InitTarget, UpdateArgs};
+use crate::config::{home_dir, resolve_proxy, Config};
 use crate::i18n::I18n;
 use crate::info::{get_install_path, PLATFORM, REPO, VERSION};
 use crate::init::{rules_for_target_with_pueue, SHNOTE_MARKER_END, SHNOTE_MARKER_START};
+use crate::prompt::prompt_yes_no_with_reader;
+use crate::prune::UPDATE_TEMP_PREFIX;
+use crate::semver::parse_semver_from_text;
 
 /// URL pattern for cargo-dist manifest
 const DIST_MANIFEST_URL: &str =
@@ -43,6 +46,8 @@ struct DistArtifact {
 struct DistChecksums {
     #[serde(default)]
     sha256: String,
+    #[serde(default)]
+    sha512: String,
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -59,9 +64,21 @@ struct LatestRelease {
     tag: String,
     archive_name: String,
     archive_sha256: String,
+    archive_sha512: String,
     executable_path: String,
 }
 
+impl LatestRelease {
+    /// Published checksum of the archive for `algo`; empty if the manifest
+    /// has none.
+    fn archive_checksum(&self, algo: ChecksumAlgo) -> &str {
+        match algo {
+            ChecksumAlgo::Sha256 => &self.archive_sha256,
+            ChecksumAlgo::Sha512 => &self.archive_sha512,
+        }
+    }
+}
+
 fn deserialize_artifacts<'de, D>(
     deserializer: D,
 ) -> std::result::Result<Vec<DistArtifact>, D::Error>
@@ -81,7 +98,7 @@ where
     }
 }
 
-pub fn run_update(i18n: &I18n, args: UpdateArgs) -> Result<()> {
+pub fn run_update(i18n: &I18n, config: &Config, args: UpdateArgs) -> Result<()> {
     println!("{}", i18n.update_checking());
 
     // Get current version
@@ -89,7 +106,8 @@ pub fn run_update(i18n: &I18n, args: UpdateArgs) -> Result<()> {
     println!("  {}: v{}", i18n.update_current_version(), current_version);
 
     // Fetch latest release metadata
-    let latest_release = fetch_latest_release(i18n)?;
+    let github_proxy = resolve_proxy(config);
+    let latest_release = fetch_latest_release(i18n, github_proxy.as_deref())?;
     println!(
         "  {}: v{}",
         i18n.update_latest_version(),
@@ -103,6 +121,20 @@ pub fn run_update(i18n: &I18n, args: UpdateArgs) -> Result<()> {
         return Ok(());
     }
 
+    if let Err(e) = check_downgrade(
+        i18n,
+        current_version,
+        &latest_release.version,
+        args.allow_downgrade,
+    ) {
+        // `--check` installs nothing, so the refusal is only reported
+        if args.check {
+            println!("{e}");
+            return Ok(());
+        }
+        return Err(e);
+    }
+
     if args.check {
         if current_version != latest_release.version {
             println!(
@@ -121,7 +153,18 @@ pub fn run_update(i18n: &I18n, args: UpdateArgs) -> Result<()> {
 
     let install_path = get_install_path().context(i18n.update_err_install_path())?;
 
-    download_and_install(i18n, &latest_release, &install_path)?;
+    let progress = config.show_download_progress(io::stderr().is_terminal());
+    let algo = args.hash.unwrap_or_else(|| {
+        ChecksumAlgo::from_str(&config.network.checksum_algo, true).unwrap_or_default()
+    });
+    download_and_install(
+        i18n,
+        &latest_release,
+        &install_path,
+        github_proxy.as_deref(),
+        progress,
+        algo,
+    )?;
 
     println!();
     println!(
@@ -135,23 +178,87 @@ pub fn run_update(i18n: &I18n, args: UpdateArgs) -> Result<()> {
     Ok(())
 }
 
-fn fetch_latest_release(i18n: &I18n) -> Result<LatestRelease> {
-    let github_proxy = env::var("GITHUB_PROXY").ok();
+/// Refuse to install a release older than the running version unless
+/// `allow_downgrade` is set. Versions that do not parse are never treated as
+/// a downgrade.
+fn check_downgrade(i18n: &I18n, current: &str, latest: &str, allow_downgrade: bool) -> Result<()> {
+    let downgrade = match (
+        parse_semver_from_text(current),
+        parse_semver_from_text(latest),
+    ) {
+        (Some(current), Some(latest)) => latest < current,
+        _ => false,
+    };
+    if downgrade && !allow_downgrade {
+        anyhow::bail!(
+            "{}",
+            i18n.update_err_downgrade(&format!("v{current}"), &format!("v{latest}"))
+        );
+    }
+    Ok(())
+}
+
+fn fetch_latest_release(i18n: &I18n, github_proxy: Option<&str>) -> Result<LatestRelease> {
+    let content = fetch_dist_manifest(i18n, github_proxy)?;
+    latest_release_from_manifest(&content, PLATFORM, i18n)
+}
+
+/// Fetch the latest released version (without the leading `v`).
+pub fn fetch_latest_version(i18n: &I18n, github_proxy: Option<&str>) -> Result<String> {
+    let content = fetch_dist_manifest(i18n, github_proxy)?;
+    let manifest = parse_dist_manifest(&content, i18n)?;
+    Ok(manifest
+        .announcement_tag
+        .trim()
+        .trim_start_matches('v')
+        .to_string())
+}
+
+/// Like `fetch_latest_version`, but silent and bounded by `timeout_secs`, for
+/// the new-version notice after a command. Any failure is `None`.
+pub fn fetch_latest_version_quietly(
+    github_proxy: Option<&str>,
+    timeout_secs: u64,
+) -> Option<String> {
     let url = DIST_MANIFEST_URL.replace("{repo}", REPO);
-    let url = apply_github_proxy(&github_proxy, &url);
+    let url = apply_github_proxy(github_proxy, &url);
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", &timeout_secs.to_string()])
+        .arg(&url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let manifest: DistManifest = serde_json::from_slice(&output.stdout).ok()?;
+    Some(
+        manifest
+            .announcement_tag
+            .trim()
+            .trim_start_matches('v')
+            .to_string(),
+    )
+}
+
+fn fetch_dist_manifest(i18n: &I18n, github_proxy: Option<&str>) -> Result<String> {
+    let url = DIST_MANIFEST_URL.replace("{repo}", REPO);
+    let url = apply_github_proxy(github_proxy, &url);
 
-    if let Some(proxy) = &github_proxy {
+    if let Some(proxy) = github_proxy {
         println!("  {}: {}", i18n.update_using_proxy(), proxy);
     }
 
-    let temp_dir = tempfile::tempdir().context(i18n.update_err_temp_dir())?;
+    let temp_dir = tempfile::Builder::new()
+        .prefix(UPDATE_TEMP_PREFIX)
+        .tempdir()
+        .context(i18n.update_err_temp_dir())?;
     let manifest_file = temp_dir.path().join("dist-manifest.json");
 
-    download_file(i18n, &url, &manifest_file)?;
+    download_file(i18n, &url, &manifest_file, false)?;
 
-    let content = fs::read_to_string(&manifest_file).context(i18n.update_err_read_version())?;
-
-    latest_release_from_manifest(&content, PLATFORM, i18n)
+    fs::read_to_string(&manifest_file).context(i18n.update_err_read_version())
 }
 
 fn parse_dist_manifest(json: &str, i18n: &I18n) -> Result<DistManifest> {
@@ -170,6 +277,7 @@ fn latest_release_from_manifest(json: &str, platform: &str, i18n: &I18n) -> Resu
         tag,
         archive_name: artifact.name.clone(),
         archive_sha256: artifact.checksums.sha256.clone(),
+        archive_sha512: artifact.checksums.sha512.clone(),
         executable_path: executable_path.to_string(),
     })
 }
@@ -210,19 +318,30 @@ fn download_and_install(
     i18n: &I18n,
     release: &LatestRelease,
     install_path: &PathBuf,
+    github_proxy: Option<&str>,
+    progress: bool,
+    algo: ChecksumAlgo,
 ) -> Result<()> {
-    let github_proxy = env::var("GITHUB_PROXY").ok();
-
+    let expected = release.archive_checksum(algo);
+    if expected.is_empty() {
+        anyhow::bail!(
+            "{}",
+            i18n.update_err_missing_checksum(algo.as_str(), &release.archive_name)
+        );
+    }
     let archive_url = format!(
         "https://github.com/{repo}/releases/download/{tag}/{archive}",
         repo = REPO,
         tag = release.tag,
         archive = release.archive_name
     );
-    let archive_url = apply_github_proxy(&github_proxy, &archive_url);
+    let archive_url = apply_github_proxy(github_proxy, &archive_url);
 
     // Create temp directory
-    let temp_dir = tempfile::tempdir().context(i18n.update_err_temp_dir())?;
+    let temp_dir = tempfile::Builder::new()
+        .prefix(UPDATE_TEMP_PREFIX)
+        .tempdir()
+        .context(i18n.update_err_temp_dir())?;
     let temp_archive = temp_dir.path().join(&release.archive_name);
     let extracted_name = Path::new(&release.executable_path)
         .file_name()
@@ -230,20 +349,16 @@ fn download_and_install(
     let temp_binary = temp_dir.path().join(extracted_name);
 
     // Download archive
-    download_file(i18n, &archive_url, &temp_archive)?;
+    download_file(i18n, &archive_url, &temp_archive, progress)?;
 
     // Verify checksum
     println!("  {}", i18n.update_verifying());
-    let actual_hash = compute_sha256(i18n, &temp_archive)?;
+    let actual_hash = compute_checksum(i18n, &temp_archive, algo)?;
 
-    if actual_hash != release.archive_sha256 {
+    if actual_hash != expected {
         anyhow::bail!(
             "{}",
-            i18n.err_checksum_mismatch(
-                &temp_archive.display().to_string(),
-                &release.archive_sha256,
-                &actual_hash
-            )
+            i18n.err_checksum_mismatch(&temp_archive.display().to_string(), expected, &actual_hash)
         );
     }
 
@@ -323,7 +438,7 @@ fn extract_binary_from_zip(
     Ok(())
 }
 
-fn apply_github_proxy(proxy: &Option<String>, url: &str) -> String {
+fn apply_github_proxy(proxy: Option<&str>, url: &str) -> String {
     match proxy {
         Some(p) => {
             let proxy = p.trim_end_matches('/');
@@ -333,12 +448,17 @@ fn apply_github_proxy(proxy: &Option<String>, url: &str) -> String {
     }
 }
 
-fn download_file(i18n: &I18n, url: &str, dest: &PathBuf) -> Result<()> {
+fn download_file(i18n: &I18n, url: &str, dest: &PathBuf, progress: bool) -> Result<()> {
     #[cfg(unix)]
     {
-        // Try curl first
+        // Try curl first; its progress bar goes to stderr
+        let flags: &[&str] = if progress {
+            &["-fL", "--progress-bar", "-o"]
+        } else {
+            &["-fsSL", "-o"]
+        };
         let curl_status = Command::new("curl")
-            .args(["-fsSL", "-o"])
+            .args(flags)
             .arg(dest)
             .arg(url)
             .stderr(Stdio::inherit())
@@ -370,6 +490,8 @@ fn download_file(i18n: &I18n, url: &str, dest: &PathBuf) -> Result<()> {
 
     #[cfg(windows)]
     {
+        // Invoke-WebRequest draws its own progress
+        let _ = progress;
         // Use PowerShell to download
         let script = format!(
             "Invoke-WebRequest -Uri '{}' -OutFile '{}'",
@@ -393,11 +515,12 @@ fn download_file(i18n: &I18n, url: &str, dest: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn compute_sha256(i18n: &I18n, path: &PathBuf) -> Result<String> {
+fn compute_checksum(i18n: &I18n, path: &PathBuf, algo: ChecksumAlgo) -> Result<String> {
     #[cfg(unix)]
     {
         let output = Command::new("shasum")
-            .args(["-a", "256"])
+            .arg("-a")
+            .arg(algo.bits().to_string())
             .arg(path)
             .output()
             .context(i18n.err_shasum_run())?;
@@ -419,7 +542,7 @@ fn compute_sha256(i18n: &I18n, path: &PathBuf) -> Result<String> {
         let output = Command::new("certutil")
             .args(["-hashfile"])
             .arg(path)
-            .arg("SHA256")
+            .arg(algo.as_str().to_uppercase())
             .output()
             .context(i18n.err_certutil_run())?;
 
@@ -474,12 +597,34 @@ fn replace_binary(i18n: &I18n, src: &PathBuf, dest: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-struct RulesFile {
-    target: InitTarget,
-    path: PathBuf,
+pub(crate) struct RulesFile {
+    pub(crate) target: InitTarget,
+    pub(crate) path: PathBuf,
     rules: String,
 }
 
+impl RulesFile {
+    /// Whether the rules are exactly what this build's `init` writes, with or
+    /// without the pueue section (i.e. neither outdated nor edited by hand).
+    pub(crate) fn matches_template(&self, i18n: &I18n) -> bool {
+        [true, false].into_iter().any(|include_pueue| {
+            self.rules == rules_for_target_with_pueue(i18n.lang(), self.target, include_pueue)
+        })
+    }
+
+    /// How many lines differ from the closer of this build's templates.
+    pub(crate) fn drift(&self, i18n: &I18n) -> usize {
+        [true, false]
+            .into_iter()
+            .map(|include_pueue| {
+                let expected = rules_for_target_with_pueue(i18n.lang(), self.target, include_pueue);
+                diff_score(&expected, &self.rules)
+            })
+            .min()
+            .unwrap_or(0)
+    }
+}
+
 fn check_rules_after_update(i18n: &I18n, install_path: &PathBuf) -> Result<()> {
     let mut stdin = io::stdin().lock();
     check_rules_after_update_with_reader(i18n, install_path, &mut stdin)
@@ -498,17 +643,18 @@ fn check_rules_after_update_with_reader(
     println!("{}", i18n.update_rules_checking());
 
     for file in rules_files {
-        let expected_with_pueue = rules_for_target_with_pueue(i18n, file.target, true);
-        let expected_without_pueue = rules_for_target_with_pueue(i18n, file.target, false);
+        let expected_with_pueue = rules_for_target_with_pueue(i18n.lang(), file.target, true);
+        let expected_without_pueue = rules_for_target_with_pueue(i18n.lang(), file.target, false);
 
-        let unmodified = file.rules == expected_with_pueue || file.rules == expected_without_pueue;
-        if unmodified {
+        // Unedited rules from this (pre-update) build can be refreshed safely
+        if file.matches_template(i18n) {
             println!(
                 "{}",
                 i18n.update_rules_outdated(&file.path.display().to_string())
             );
             if prompt_yes_no_with_reader(i18n.update_rules_confirm_update(), reader)? {
-                run_init_with_binary(i18n, install_path, file.target)?;
+                let include_pueue = file.rules == expected_with_pueue;
+                run_init_with_binary(i18n, install_path, file.target, include_pueue)?;
             } else {
                 println!("{}", i18n.update_rules_skipped());
             }
@@ -530,7 +676,8 @@ fn check_rules_after_update_with_reader(
             &file.rules,
         );
         if prompt_yes_no_with_reader(i18n.update_rules_confirm_overwrite(), reader)? {
-            run_init_with_binary(i18n, install_path, file.target)?;
+            let include_pueue = reference == expected_with_pueue;
+            run_init_with_binary(i18n, install_path, file.target, include_pueue)?;
         } else {
             println!("{}", i18n.update_rules_skipped());
         }
@@ -540,7 +687,8 @@ fn check_rules_after_update_with_reader(
     Ok(())
 }
 
-fn find_rules_files() -> Vec<RulesFile> {
+/// User-scope rules files (under the home directory) containing a shnote section.
+pub(crate) fn find_rules_files() -> Vec<RulesFile> {
     let mut files = Vec::new();
     let Ok(home) = home_dir() else {
         return files;
@@ -590,7 +738,7 @@ fn push_rules_file(files: &mut Vec<RulesFile>, path: PathBuf, target: InitTarget
     });
 }
 
-fn extract_shnote_rules(content: &str) -> Option<String> {
+pub(crate) fn extract_shnote_rules(content: &str) -> Option<String> {
     let start_idx = content.find(SHNOTE_MARKER_START)?;
     let rules_start = start_idx + SHNOTE_MARKER_START.len();
     let rules_end = content[rules_start..]
@@ -618,7 +766,7 @@ fn print_rules_diff(i18n: &I18n, path: &str, expected: &str, actual: &str) {
     print!("{}", render_diff(expected, actual));
 }
 
-fn render_diff(old: &str, new: &str) -> String {
+pub(crate) fn render_diff(old: &str, new: &str) -> String {
     let old_lines: Vec<&str> = old.lines().collect();
     let new_lines: Vec<&str> = new.lines().collect();
     let dp = lcs_table(&old_lines, &new_lines);
@@ -698,23 +846,24 @@ fn lcs_table(old_lines: &[&str], new_lines: &[&str]) -> Vec<Vec<usize>> {
     dp
 }
 
-fn prompt_yes_no_with_reader(prompt: &str, reader: &mut dyn BufRead) -> Result<bool> {
-    print!("{prompt} [y/N] ");
-    io::stdout().flush()?;
-    let mut input = String::new();
-    reader.read_line(&mut input)?;
-    let input = input.trim().to_lowercase();
-    Ok(input == "y" || input == "yes")
-}
-
-fn run_init_with_binary(i18n: &I18n, install_path: &PathBuf, target: InitTarget) -> Result<()> {
-    let status = Command::new(install_path)
+/// Re-run `init` with the new binary, keeping the pueue section if the
+/// installed rules had it.
+fn run_init_with_binary(
+    i18n: &I18n,
+    install_path: &PathBuf,
+    target: InitTarget,
+    include_pueue: bool,
+) -> Result<()> {
+    let mut command = Command::new(install_path);
+    command
         .arg("--lang")
         .arg(i18n.lang_tag())
         .arg("init")
-        .arg(init_target_arg(target))
-        .status()
-        .context(i18n.update_rules_err_init())?;
+        .arg(init_target_arg(target));
+    if include_pueue {
+        command.arg("--force-pueue");
+    }
+    let status = command.status().context(i18n.update_rules_err_init())?;
 
     if !status.success() {
         anyhow::bail!("{}", i18n.update_rules_err_init());
@@ -728,6 +877,11 @@ fn init_target_arg(target: InitTarget) -> &'static str {
         InitTarget::Claude => "claude",
         InitTarget::Codex => "codex",
         InitTarget::Gemini => "gemini",
+        InitTarget::Agents => "agents",
+        InitTarget::Copilot => "copilot",
+        InitTarget::Cursor => "cursor",
+        InitTarget::Windsurf => "windsurf",
+        InitTarget::Aider => "aider",
     }
 }
 
@@ -739,7 +893,7 @@ mod tests {
     #[cfg(unix)]
     use crate::test_support::write_executable;
     use crate::test_support::{env_lock, EnvVarGuard};
-    use std::io::Cursor;
+    use std::io::{Cursor, Write};
     use tempfile::TempDir;
 
     const DIST_MANIFEST_FIXTURE: &str = r#"{
@@ -752,7 +906,7 @@ mod tests {
             {
                 "name": "shnote-x86_64-apple-darwin.tar.xz",
                 "target_triples": ["x86_64-apple-darwin"],
-                "checksums": { "sha256": "deadbeef" },
+                "checksums": { "sha256": "deadbeef", "sha512": "cafebabe" },
                 "assets": [
                     { "kind": "executable", "path": "shnote" }
                 ]
@@ -771,7 +925,7 @@ mod tests {
     #[test]
     fn apply_github_proxy_without_proxy() {
         let url = "https://github.com/example/file";
-        assert_eq!(apply_github_proxy(&None, url), url);
+        assert_eq!(apply_github_proxy(None, url), url);
     }
 
     #[test]
@@ -779,7 +933,7 @@ mod tests {
         let proxy = Some("https://ghfast.top".to_string());
         let url = "https://github.com/example/file";
         assert_eq!(
-            apply_github_proxy(&proxy, url),
+            apply_github_proxy(proxy.as_deref(), url),
             "https://ghfast.top/https://github.com/example/file"
         );
     }
@@ -789,11 +943,30 @@ mod tests {
         let proxy = Some("https://ghfast.top/".to_string());
         let url = "https://github.com/example/file";
         assert_eq!(
-            apply_github_proxy(&proxy, url),
+            apply_github_proxy(proxy.as_deref(), url),
             "https://ghfast.top/https://github.com/example/file"
         );
     }
 
+    #[test]
+    fn check_downgrade_refuses_older_release() {
+        let i18n = I18n::new(Lang::En);
+        let err = check_downgrade(&i18n, "0.3.2", "0.3.0", false).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("from v0.3.2 to v0.3.0"), "{message}");
+        assert!(message.contains("--allow-downgrade"));
+
+        assert!(check_downgrade(&i18n, "0.3.2", "0.3.0", true).is_ok());
+    }
+
+    #[test]
+    fn check_downgrade_allows_same_or_newer_release() {
+        let i18n = I18n::new(Lang::En);
+        assert!(check_downgrade(&i18n, "0.3.2", "0.3.2", false).is_ok());
+        assert!(check_downgrade(&i18n, "0.3.2", "0.10.0", false).is_ok());
+        assert!(check_downgrade(&i18n, "0.3.2", "nightly", false).is_ok());
+    }
+
     #[test]
     fn parse_dist_manifest_reads_latest_tag() {
         let i18n = I18n::new(Lang::En);
@@ -819,7 +992,13 @@ mod tests {
         assert_eq!(release.tag, "v0.3.1");
         assert_eq!(release.archive_name, "shnote-x86_64-apple-darwin.tar.xz");
         assert_eq!(release.archive_sha256, "deadbeef");
+        assert_eq!(release.archive_checksum(ChecksumAlgo::Sha512), "cafebabe");
         assert_eq!(release.executable_path, "shnote");
+
+        let windows =
+            latest_release_from_manifest(DIST_MANIFEST_FIXTURE, "x86_64-pc-windows-msvc", &i18n)
+                .unwrap();
+        assert_eq!(windows.archive_checksum(ChecksumAlgo::Sha512), "");
     }
 
     #[test]
@@ -854,6 +1033,21 @@ mod tests {
         assert!(err.to_string().contains("executable"));
     }
 
+    #[test]
+    fn rules_file_drift_counts_changed_lines() {
+        let i18n = I18n::new(Lang::En);
+        let rules = rules_for_target_with_pueue(i18n.lang(), InitTarget::Codex, true);
+        let mut file = RulesFile {
+            target: InitTarget::Codex,
+            path: PathBuf::from("AGENTS.md"),
+            rules,
+        };
+        assert_eq!(file.drift(&i18n), 0);
+
+        file.rules.push_str("\nextra line\n");
+        assert_eq!(file.drift(&i18n), 2);
+    }
+
     #[test]
     fn extract_shnote_rules_uses_markers() {
         let content = format!(
@@ -886,18 +1080,6 @@ mod tests {
         assert_eq!(chosen, a);
     }
 
-    #[test]
-    fn prompt_yes_no_with_reader_accepts_yes() {
-        let mut input = Cursor::new("y\n");
-        assert!(prompt_yes_no_with_reader("ok?", &mut input).unwrap());
-    }
-
-    #[test]
-    fn prompt_yes_no_with_reader_rejects_default() {
-        let mut input = Cursor::new("\n");
-        assert!(!prompt_yes_no_with_reader("ok?", &mut input).unwrap());
-    }
-
     #[test]
     fn extract_binary_from_tar_xz_uses_manifest_asset_path() {
         let i18n = I18n::new(Lang::En);
@@ -966,10 +1148,43 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", &tools_dir);
 
         let out = temp_dir.path().join("out.txt");
-        download_file(&i18n, "https://example.invalid/file", &out).unwrap();
+        download_file(&i18n, "https://example.invalid/file", &out, false).unwrap();
         assert_eq!(fs::read_to_string(&out).unwrap().trim(), "curl");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn download_file_shows_curl_progress_only_when_requested() {
+        let _lock = env_lock();
+        let i18n = I18n::new(Lang::En);
+
+        let temp_dir = TempDir::new().unwrap();
+        let tools_dir = temp_dir.path().join("tools");
+        fs::create_dir_all(&tools_dir).unwrap();
+
+        // Fake curl: write its flags (everything before -o) to the destination.
+        let curl = tools_dir.join("curl");
+        write_executable(
+            &curl,
+            "#!/bin/sh\n\
+            flags=\"\"\n\
+            while [ \"$1\" != \"-o\" ]; do flags=\"$flags $1\"; shift; done\n\
+            echo \"$flags\" > \"$2\"\n",
+        )
+        .unwrap();
+        let _path_guard = EnvVarGuard::set("PATH", &tools_dir);
+
+        let out = temp_dir.path().join("out.txt");
+        download_file(&i18n, "https://example.invalid/file", &out, false).unwrap();
+        assert_eq!(fs::read_to_string(&out).unwrap().trim(), "-fsSL");
+
+        download_file(&i18n, "https://example.invalid/file", &out, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap().trim(),
+            "-fL --progress-bar"
+        );
+    }
+
     #[cfg(unix)]
     #[test]
     fn download_file_falls_back_to_wget() {
@@ -1003,7 +1218,7 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", &tools_dir);
 
         let out = temp_dir.path().join("out.txt");
-        download_file(&i18n, "https://example.invalid/file", &out).unwrap();
+        download_file(&i18n, "https://example.invalid/file", &out, false).unwrap();
         assert_eq!(fs::read_to_string(&out).unwrap().trim(), "wget");
     }
 
@@ -1020,7 +1235,7 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", &tools_dir);
 
         let out = temp_dir.path().join("out.txt");
-        let err = download_file(&i18n, "https://example.invalid/file", &out).unwrap_err();
+        let err = download_file(&i18n, "https://example.invalid/file", &out, false).unwrap_err();
         assert!(err.to_string().contains(i18n.err_download_no_tool()));
     }
 
@@ -1046,14 +1261,14 @@ mod tests {
         let _path_guard = EnvVarGuard::set("PATH", &tools_dir);
 
         let out = temp_dir.path().join("out.txt");
-        let err = download_file(&i18n, "https://example.invalid/file", &out).unwrap_err();
+        let err = download_file(&i18n, "https://example.invalid/file", &out, false).unwrap_err();
         assert!(err.to_string().contains(i18n.err_download_failed()));
         assert!(!err.to_string().contains(i18n.err_download_no_tool()));
     }
 
     #[cfg(unix)]
     #[test]
-    fn compute_sha256_uses_shasum_output() {
+    fn compute_checksum_uses_shasum_output() {
         let _lock = env_lock();
         let i18n = I18n::new(Lang::En);
 
@@ -1068,10 +1283,63 @@ mod tests {
 
         let file = temp_dir.path().join("bin");
         fs::write(&file, "data").unwrap();
-        let hash = compute_sha256(&i18n, &file).unwrap();
+        let hash = compute_checksum(&i18n, &file, ChecksumAlgo::Sha256).unwrap();
         assert_eq!(hash, "deadbeef");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn compute_checksum_matches_known_digests() {
+        let _lock = env_lock();
+        // Uses the real shasum; skip on hosts without it
+        if which::which("shasum").is_err() {
+            return;
+        }
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("abc");
+        fs::write(&file, "abc").unwrap();
+
+        assert_eq!(
+            compute_checksum(&i18n, &file, ChecksumAlgo::Sha256).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            compute_checksum(&i18n, &file, ChecksumAlgo::Sha512).unwrap(),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39\
+             a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+    }
+
+    #[test]
+    fn download_and_install_requires_the_selected_checksum() {
+        let i18n = I18n::new(Lang::En);
+        let temp_dir = TempDir::new().unwrap();
+        let release = LatestRelease {
+            version: "0.3.1".to_string(),
+            tag: "v0.3.1".to_string(),
+            archive_name: "shnote-x86_64-apple-darwin.tar.xz".to_string(),
+            archive_sha256: "archivehash".to_string(),
+            archive_sha512: String::new(),
+            executable_path: "shnote".to_string(),
+        };
+
+        let err = download_and_install(
+            &i18n,
+            &release,
+            &temp_dir.path().join("shnote"),
+            None,
+            false,
+            ChecksumAlgo::Sha512,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("no sha512 checksum for shnote-x86_64"),
+            "{err}"
+        );
+    }
+
     #[cfg(unix)]
     #[test]
     fn fetch_latest_release_downloads_manifest_and_selects_platform_artifact() {
@@ -1122,7 +1390,7 @@ mod tests {
 
         let _path_guard = EnvVarGuard::set("PATH", &tools_dir);
 
-        let release = fetch_latest_release(&i18n).unwrap();
+        let release = fetch_latest_release(&i18n, None).unwrap();
         assert_eq!(release.version, "0.3.1");
         assert_eq!(release.archive_name, format!("shnote-{PLATFORM}.tar.xz"));
         assert_eq!(release.executable_path, "shnote");
@@ -1171,10 +1439,19 @@ mod tests {
             tag: "v0.3.1".to_string(),
             archive_name: "shnote-x86_64-apple-darwin.tar.xz".to_string(),
             archive_sha256: "archivehash".to_string(),
+            archive_sha512: String::new(),
             executable_path: "shnote".to_string(),
         };
 
-        download_and_install(&i18n, &release, &install_path).unwrap();
+        download_and_install(
+            &i18n,
+            &release,
+            &install_path,
+            None,
+            false,
+            ChecksumAlgo::Sha256,
+        )
+        .unwrap();
 
         assert_eq!(fs::read(&install_path).unwrap(), b"binary");
     }
@@ -1222,10 +1499,19 @@ mod tests {
             tag: "v0.3.1".to_string(),
             archive_name: "shnote-x86_64-apple-darwin.tar.xz".to_string(),
             archive_sha256: "archivehash".to_string(),
+            archive_sha512: String::new(),
             executable_path: "shnote".to_string(),
         };
 
-        let err = download_and_install(&i18n, &release, &install_path).unwrap_err();
+        let err = download_and_install(
+            &i18n,
+            &release,
+            &install_path,
+            None,
+            false,
+            ChecksumAlgo::Sha256,
+        )
+        .unwrap_err();
         assert!(err.to_string().contains("checksum"));
     }
 
@@ -1238,7 +1524,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let _home_guard = EnvVarGuard::set("HOME", temp_dir.path());
 
-        let rules = rules_for_target_with_pueue(&i18n, InitTarget::Codex, true);
+        let rules = rules_for_target_with_pueue(i18n.lang(), InitTarget::Codex, true);
         let codex_dir = temp_dir.path().join(".codex");
         fs::create_dir_all(&codex_dir).unwrap();
         let rules_path = codex_dir.join("AGENTS.md");
@@ -1271,6 +1557,7 @@ mod tests {
         assert!(args.contains("--lang"));
         assert!(args.contains("init"));
         assert!(args.contains("codex"));
+        assert!(args.contains("--force-pueue"));
     }
 
     #[cfg(unix)]
@@ -0,0 +1,251 @@
+//! "New version available" notice after an execution command.
+//!
+//! At most once per `version_check_interval` (default one day), the latest
+//! release is looked up in a background thread while the command runs, and a
+//! one-line notice is printed on stderr when it is newer than this build. The
+//! time of the last check and its result are cached in the data directory, so
+//! runs in between stay offline. `--offline` skips the check entirely.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, resolve_proxy, Config};
+use crate::history::unix_now;
+use crate::i18n::I18n;
+use crate::info::VERSION;
+use crate::semver::parse_semver_from_text;
+use crate::update::fetch_latest_version_quietly;
+
+const CACHE_FILE_NAME: &str = "update-check.json";
+
+/// Upper bound for the release lookup itself.
+const FETCH_TIMEOUT_SECS: u64 = 5;
+
+/// How long shnote waits for an unfinished lookup once the command is done;
+/// after that the notice is skipped rather than delaying the exit.
+const FINISH_WAIT: Duration = Duration::from_secs(1);
+
+/// Values of `version_check_interval` that turn the check off.
+const OFF_VALUES: &[&str] = &["off", "never", "0"];
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+struct CheckCache {
+    /// Unix time of the last check, successful or not
+    checked_at: u64,
+    /// Latest version seen by the last successful check
+    #[serde(default)]
+    latest: String,
+}
+
+/// A release lookup started by [`start`].
+pub struct PendingCheck {
+    path: PathBuf,
+    checked_at: u64,
+    lookup: JoinHandle<Option<String>>,
+}
+
+/// Parse an interval such as `90s`, `30m`, `12h` or `7d` into seconds; the
+/// off values give `Some(0)`. `None` means the value is invalid.
+pub fn parse_interval(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    if OFF_VALUES.contains(&value.as_str()) {
+        return Some(0);
+    }
+    let (number, unit) = value.split_at(value.len().checked_sub(1)?);
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .filter(|&n| n > 0)
+        .and_then(|n| n.checked_mul(scale))
+}
+
+/// Whether a check is due `interval` seconds after the cached one.
+fn check_due(cache: Option<&CheckCache>, now: u64, interval: u64) -> bool {
+    interval > 0 && cache.is_none_or(|cache| now.saturating_sub(cache.checked_at) >= interval)
+}
+
+/// Start a background lookup if one is due. `interval` overrides the
+/// configured `version_check_interval` (`--version-check-interval`).
+pub fn start(config: &Config, interval: Option<&str>, offline: bool) -> Option<PendingCheck> {
+    if offline {
+        return None;
+    }
+    let interval = parse_interval(interval.unwrap_or(&config.network.version_check_interval))?;
+    let path = data_dir().ok()?.join(CACHE_FILE_NAME);
+    let cache = read_cache(&path);
+    let now = unix_now();
+    if !check_due(cache.as_ref(), now, interval) {
+        return None;
+    }
+    // Record the attempt up front: a failed or unfinished lookup then waits
+    // for the next interval instead of being retried on every command. If
+    // the cache cannot be written, do not check at all.
+    let attempt = CheckCache {
+        checked_at: now,
+        latest: cache.map(|cache| cache.latest).unwrap_or_default(),
+    };
+    write_cache(&path, &attempt)?;
+
+    let proxy = resolve_proxy(config);
+    let lookup =
+        thread::spawn(move || fetch_latest_version_quietly(proxy.as_deref(), FETCH_TIMEOUT_SECS));
+    Some(PendingCheck {
+        path,
+        checked_at: now,
+        lookup,
+    })
+}
+
+impl PendingCheck {
+    /// Wait briefly for the lookup, cache its result and print the notice if
+    /// the latest release is newer than this build.
+    pub fn finish(self, i18n: &I18n) {
+        let deadline = Instant::now() + FINISH_WAIT;
+        while !self.lookup.is_finished() {
+            if Instant::now() >= deadline {
+                return;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let Ok(Some(latest)) = self.lookup.join() else {
+            return;
+        };
+        let cache = CheckCache {
+            checked_at: self.checked_at,
+            latest,
+        };
+        let _ = write_cache(&self.path, &cache);
+        if is_newer(VERSION, &cache.latest) {
+            eprintln!("{}", i18n.update_notice(VERSION, &cache.latest));
+        }
+    }
+}
+
+fn is_newer(current: &str, latest: &str) -> bool {
+    match (
+        parse_semver_from_text(current),
+        parse_semver_from_text(latest),
+    ) {
+        (Some(current), Some(latest)) => latest > current,
+        _ => false,
+    }
+}
+
+fn read_cache(path: &Path) -> Option<CheckCache> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn write_cache(path: &Path, cache: &CheckCache) -> Option<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).ok()?;
+    }
+    fs::write(path, serde_json::to_string(cache).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{env_lock, EnvVarGuard};
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    use crate::test_support::write_executable;
+
+    #[test]
+    fn parse_interval_accepts_units_and_off() {
+        assert_eq!(parse_interval("90s"), Some(90));
+        assert_eq!(parse_interval("30m"), Some(30 * 60));
+        assert_eq!(parse_interval("12H"), Some(12 * 60 * 60));
+        assert_eq!(parse_interval(" 7d "), Some(7 * 24 * 60 * 60));
+        assert_eq!(parse_interval("off"), Some(0));
+        assert_eq!(parse_interval("0"), Some(0));
+        for invalid in ["", "d", "1w", "0h", "-1d", "1.5h", "daily"] {
+            assert_eq!(parse_interval(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn check_due_respects_interval() {
+        let day = 24 * 60 * 60;
+        let cache = CheckCache {
+            checked_at: 1_000_000,
+            latest: "1.0.0".to_string(),
+        };
+        assert!(check_due(None, 1_000_000, day));
+        assert!(!check_due(Some(&cache), 1_000_000 + day - 1, day));
+        assert!(check_due(Some(&cache), 1_000_000 + day, day));
+        assert!(!check_due(None, 1_000_000, 0));
+    }
+
+    #[test]
+    fn is_newer_compares_versions() {
+        assert!(is_newer("0.3.2", "0.4.0"));
+        assert!(!is_newer("0.4.0", "0.4.0"));
+        assert!(!is_newer("0.4.0", "0.3.9"));
+        assert!(!is_newer("0.4.0", "garbage"));
+    }
+
+    #[test]
+    fn start_is_skipped_offline_or_when_off() {
+        let _lock = env_lock();
+        let data = TempDir::new().unwrap();
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", data.path());
+
+        assert!(start(&Config::default(), None, true).is_none());
+        assert!(start(&Config::default(), Some("off"), false).is_none());
+        assert!(!data.path().join(CACHE_FILE_NAME).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn interval_suppresses_repeated_checks() {
+        let _lock = env_lock();
+        let data = TempDir::new().unwrap();
+        let tools = TempDir::new().unwrap();
+        let calls = tools.path().join("calls");
+        write_executable(
+            &tools.path().join("curl"),
+            &format!(
+                "#!/bin/sh\necho call >> '{}'\necho '{{\"announcement_tag\":\"v99.0.0\"}}'\n",
+                calls.display()
+            ),
+        )
+        .unwrap();
+        let _data_guard = EnvVarGuard::set("SHNOTE_DATA_DIR", data.path());
+        let _path_guard = EnvVarGuard::set("PATH", tools.path());
+        let _proxy_guard = EnvVarGuard::remove("GITHUB_PROXY");
+        let i18n = I18n::new(crate::i18n::Lang::En);
+
+        start(&Config::default(), None, false)
+            .expect("first check is due")
+            .finish(&i18n);
+        let cache = read_cache(&data.path().join(CACHE_FILE_NAME)).unwrap();
+        assert_eq!(cache.latest, "99.0.0");
+
+        assert!(start(&Config::default(), None, false).is_none());
+        assert!(start(&Config::default(), Some("7d"), false).is_none());
+        assert_eq!(fs::read_to_string(&calls).unwrap().lines().count(), 1);
+
+        // Once the interval has passed, the next command checks again
+        let stale = CheckCache {
+            checked_at: cache.checked_at - 2,
+            ..cache
+        };
+        write_cache(&data.path().join(CACHE_FILE_NAME), &stale).unwrap();
+        start(&Config::default(), Some("1s"), false)
+            .expect("interval elapsed")
+            .finish(&i18n);
+        assert_eq!(fs::read_to_string(&calls).unwrap().lines().count(), 2);
+    }
+}
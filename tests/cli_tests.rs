@@ -71,10 +71,106 @@ fn test_run_requires_what_why() {
     shnote_cmd()
         .args(["run", "echo", "test"])
         .assert()
-        .failure()
+        .code(2)
         .stderr(predicate::str::contains("--what"));
 }
 
+#[cfg(unix)]
+#[test]
+fn test_exit_codes_by_failure_class() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+    let run = |args: &[&str]| {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args(["--lang", "en", "--what", "x", "--why", "y"])
+            .args(args)
+            .assert()
+    };
+
+    // Validation failures
+    run(&["run", "--cwd", "/definitely/not/a/dir", "--", "true"]).code(2);
+    run(&["run", "--exit-on", "oops", "--", "true"]).code(2);
+
+    // Command not found / not executable (two words, so no shell is involved)
+    run(&["run", "--", "definitely_not_a_real_command_xyz", "arg"]).code(127);
+    let script = temp_dir.path().join("not-executable.sh");
+    fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+    run(&["run", "--", script.to_str().unwrap(), "arg"]).code(126);
+    run(&[
+        "run",
+        "--exec-prefix",
+        "definitely_not_a_real_wrapper",
+        "--",
+        "true",
+    ])
+    .code(127);
+
+    // Interpreter not found
+    fs::write(
+        temp_dir.path().join(".shnote/config.toml"),
+        "color = false\n\n[paths]\npython = \"/definitely/not/python\"\n",
+    )
+    .unwrap();
+    run(&["py", "-c", "print(1)"]).code(127);
+}
+
+#[test]
+fn test_interpreter_not_found_suggests_a_fix() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\n\n[paths]\npython = \"not_a_python\"\n",
+    )
+    .unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", empty_path.path())
+        .args(["--lang", "en", "--what", "x", "--why", "y", "py", "-c", "1"])
+        .assert()
+        .code(127)
+        .stderr(predicate::str::contains(
+            "interpreter not found: not_a_python",
+        ))
+        .stderr(predicate::str::contains("config: python = not_a_python"))
+        .stderr(predicate::str::contains(
+            "tried: not_a_python, python3, python",
+        ))
+        .stderr(predicate::str::contains("shnote config set python <path>"))
+        .stderr(predicate::str::contains("shnote doctor"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_py_interpreter_override_wins_over_config() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\n\n[paths]\npython = \"/definitely/not/python\"\n",
+    )
+    .unwrap();
+    let interpreter = temp_dir.path().join("fake-python");
+    fs::write(&interpreter, "#!/bin/sh\necho \"override $1 $2\"\n").unwrap();
+    fs::set_permissions(&interpreter, fs::Permissions::from_mode(0o755)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "x", "--why", "y", "py", "--interpreter"])
+        .arg(&interpreter)
+        .args(["-c", "print(1)"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("override -c print(1)"));
+}
+
 #[test]
 fn test_i18n_uses_language_env_when_auto() {
     let temp_dir = TempDir::new().unwrap();
@@ -92,6 +188,23 @@ fn test_i18n_uses_language_env_when_auto() {
         .stderr(predicate::str::contains("需要"));
 }
 
+#[test]
+fn test_i18n_uses_japanese_from_lang_env() {
+    let temp_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("LANG", "ja_JP.UTF-8")
+        .env_remove("SHNOTE_LANG")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env_remove("LANGUAGE")
+        .args(["run", "echo", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("が必要で"));
+}
+
 #[test]
 fn test_i18n_falls_back_to_english_when_env_empty_and_unknown() {
     let temp_dir = TempDir::new().unwrap();
@@ -271,1184 +384,4181 @@ fn test_run_tail_header_prints_after_command_output() {
     assert!(what_pos < why_pos);
 }
 
+#[cfg(unix)]
 #[test]
-fn test_run_without_subcommand_defaults_to_run() {
+fn test_run_output_prefix_prefixes_each_line() {
     let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_config(&temp_dir);
-    shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["--what", "测试", "--why", "验证", "echo", "hello"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("WHAT: 测试"))
-        .stdout(predicate::str::contains("WHY:  验证"))
-        .stdout(predicate::str::contains("hello"));
-}
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\noutput = \"quiet\"\noutput_prefix = \"[{what}] \"\n",
+    )
+    .unwrap();
 
-#[test]
-fn test_run_with_single_string_uses_shell_lc_default() {
-    let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_config(&temp_dir);
     shnote_cmd()
         .env("HOME", temp_dir.path())
         .args([
             "--what",
-            "测试",
+            "build",
             "--why",
-            "验证",
+            "prefix-check",
             "run",
-            "echo 'hi' && echo 'hello'",
+            "printf 'one\\ntwo\\nlast'; echo oops >&2",
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("hi"))
-        .stdout(predicate::str::contains("hello"));
+        .stdout("[build] one\n[build] two\n[build] last")
+        .stderr("[build] oops\n");
 }
 
+#[cfg(unix)]
 #[test]
-fn test_run_without_subcommand_single_string_uses_shell_lc_default() {
+fn test_run_stdin_tty_passthrough_skips_prefix_and_moves_header_to_stderr() {
     let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_config(&temp_dir);
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\nheader_stream = \"stdout\"\nheader_timing = \"head\"\noutput_prefix = \"[x] \"\n",
+    )
+    .unwrap();
+
     shnote_cmd()
         .env("HOME", temp_dir.path())
         .args([
             "--what",
-            "测试",
+            "repl",
             "--why",
-            "验证",
-            "echo 'hi' && echo 'hello'",
+            "passthrough",
+            "run",
+            "--stdin-tty-passthrough",
+            "echo hi",
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("hi"))
-        .stdout(predicate::str::contains("hello"));
+        .stdout("hi\n")
+        .stderr("WHAT: repl\nWHY:  passthrough\n");
 }
 
+#[cfg(unix)]
 #[test]
-fn test_run_with_what_why_auto_routes_header_to_stderr_when_stdout_not_tty() {
+fn test_run_raw_matches_bare_command_output() {
     let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_auto_config(&temp_dir);
+    let data_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "header_stream = \"stdout\"\nheader_timing = \"both\"\noutput_prefix = \"[x] \"\n\n[run]\nexec_prefix = [\"/nonexistent/wrapper\"]\n",
+    )
+    .unwrap();
+    let script = "printf 'one\\ntwo'; printf '\\033[31mred' >&2; exit 3";
 
-    let assert = shnote_cmd()
+    let bare = std::process::Command::new("sh")
+        .args(["-c", script])
+        .output()
+        .unwrap();
+    let raw = shnote_cmd()
         .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
         .args([
-            "--what",
-            "auto-header",
-            "--why",
-            "pipeline-safe",
-            "run",
-            "echo",
-            "hello",
+            "--what", "raw", "--why", "fidelity", "run", "--raw", "--", "sh", "-c", script,
         ])
-        .assert()
-        .success();
+        .output()
+        .unwrap();
 
-    let output = assert.get_output();
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(raw.stdout, bare.stdout);
+    assert_eq!(raw.stderr, bare.stderr);
+    assert_eq!(raw.status.code(), Some(3));
 
-    assert!(stdout.contains("hello"));
-    assert!(!stdout.contains("WHAT: auto-header"));
-    assert!(!stdout.contains("WHY:  pipeline-safe"));
-    assert!(stderr.contains("WHAT: auto-header"));
-    assert!(stderr.contains("WHY:  pipeline-safe"));
+    let history = fs::read_to_string(data_dir.path().join("history.jsonl")).unwrap();
+    assert!(history.contains("\"what\":\"raw\""), "{history}");
+    assert!(history.contains("\"exit_code\":3"), "{history}");
 }
 
+#[cfg(unix)]
 #[test]
-fn test_run_with_header_stream_stdout_flag() {
+fn test_run_group_inserts_pueue_group_argument() {
+    use std::os::unix::fs::PermissionsExt;
+
     let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_auto_config(&temp_dir);
+    write_color_disabled_config(&temp_dir);
+    let tools_dir = TempDir::new().unwrap();
+    let pueue = tools_dir.path().join("pueue");
+    fs::write(
+        &pueue,
+        "#!/bin/sh\nif [ \"$1\" = group ]; then echo '{\"default\":{},\"build\":{}}'; exit 0; fi\necho \"pueue $*\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&pueue, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:/usr/bin:/bin", tools_dir.path().display());
 
-    let assert = shnote_cmd()
+    shnote_cmd()
         .env("HOME", temp_dir.path())
+        .env("PATH", &path)
+        .args(["--what", "w", "--why", "y", "run", "--group", "build"])
+        .args(["pueue", "add", "--", "make", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pueue add -g build -- make all"));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", &path)
         .args([
-            "--header-stream",
-            "stdout",
-            "--what",
-            "force-stdout",
-            "--why",
-            "display-first",
-            "run",
-            "echo",
-            "hello",
+            "--lang", "en", "--what", "w", "--why", "y", "run", "--group", "nope",
         ])
+        .args(["pueue", "add", "make"])
         .assert()
-        .success();
-
-    let output = assert.get_output();
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    assert!(stdout.contains("WHAT: force-stdout"));
-    assert!(stdout.contains("WHY:  display-first"));
-    assert!(stderr.trim().is_empty());
-}
-
-#[test]
-fn test_run_with_header_stream_stderr_flag() {
-    let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_auto_config(&temp_dir);
+        .failure()
+        .stderr(predicate::str::contains(
+            "pueue group 'nope' does not exist",
+        ));
 
-    let assert = shnote_cmd()
+    shnote_cmd()
         .env("HOME", temp_dir.path())
+        .env("PATH", &path)
         .args([
-            "--header-stream",
-            "stderr",
-            "--what",
-            "force-stderr",
-            "--why",
-            "pipeline-safe",
-            "run",
-            "echo",
-            "hello",
+            "--lang", "en", "--what", "w", "--why", "y", "run", "--group", "build",
         ])
+        .args(["echo", "hi"])
         .assert()
-        .success();
-
-    let output = assert.get_output();
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    assert!(stdout.contains("hello"));
-    assert!(!stdout.contains("WHAT: force-stderr"));
-    assert!(stderr.contains("WHAT: force-stderr"));
-    assert!(stderr.contains("WHY:  pipeline-safe"));
+        .failure()
+        .stderr(predicate::str::contains("only applies to `pueue add"));
 }
 
 #[test]
-fn test_run_missing_only_what() {
+fn test_run_confirm_each_needs_terminal_or_yes() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let marker = temp_dir.path().join("ran");
+
     shnote_cmd()
-        .args(["--what", "test", "run", "echo"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "--what", "Touch", "--why", "Gate"])
+        .args(["run", "--confirm-each", "touch"])
+        .arg(&marker)
+        .write_stdin("y\n")
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--why"));
-}
+        .code(2)
+        .stdout(predicate::str::contains("$ touch").not())
+        .stderr(predicate::str::contains("stdin is not a terminal"))
+        .stderr(predicate::str::contains("$ touch").not());
+    assert!(!marker.exists());
 
-#[test]
-fn test_run_missing_only_why() {
     shnote_cmd()
-        .args(["--why", "test", "run", "echo"])
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "run_confirm_each", "true"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
-}
+        .success();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "Touch", "--why", "Gate", "run", "touch"])
+        .arg(&marker)
+        .assert()
+        .failure();
+    assert!(!marker.exists());
 
-#[test]
-fn test_run_without_subcommand_requires_what_why() {
     shnote_cmd()
-        .args(["echo", "test"])
+        .env("HOME", temp_dir.path())
+        .args(["--what", "Touch", "--why", "Gate", "run", "--yes", "touch"])
+        .arg(&marker)
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
+        .success();
+    assert!(marker.exists());
 }
 
-// === py command ===
 #[test]
-fn test_py_requires_what_why() {
-    shnote_cmd()
-        .args(["py", "-c", "print(1)"])
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
+fn test_run_log_file_writes_transcript() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("run.log");
+    write_color_disabled_config(&temp_dir);
+
+    for word in ["hi", "again"] {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args([
+                "--what",
+                "Greet",
+                "--why",
+                "Transcript",
+                "run",
+                "--log-file",
+            ])
+            .arg(&log)
+            .args(["echo", word])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(word));
+    }
+
+    let contents = fs::read_to_string(&log).unwrap();
+    assert_eq!(
+        contents.matches("WHAT: Greet\nWHY:  Transcript\n").count(),
+        2
+    );
+    assert!(contents.contains("$ echo hi\n"), "{contents}");
+    assert!(contents.contains("] out| hi\n"), "{contents}");
+    assert!(contents.contains("] out| again\n"), "{contents}");
+    assert_eq!(contents.matches("exit 0 in ").count(), 2, "{contents}");
 }
 
+#[cfg(unix)]
 #[test]
-fn test_py_requires_source() {
-    shnote_cmd()
-        .args(["--what", "test", "--why", "test", "py"])
-        .assert()
-        .failure()
-        .stderr(
-            predicate::str::contains("source")
-                .or(predicate::str::contains("stdin"))
-                .or(predicate::str::contains("code"))
-                .or(predicate::str::contains("file")),
-        );
+fn test_run_redirect_stdout_writes_file_instead_of_terminal() {
+    let temp_dir = TempDir::new().unwrap();
+    let out = temp_dir.path().join("out.log");
+    let run = |extra: &[&str]| {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args([
+                "--plain",
+                "--what",
+                "w",
+                "--why",
+                "y",
+                "run",
+                "--redirect-stdout",
+            ])
+            .arg(&out)
+            .args(extra)
+            .args(["sh", "-c", "echo out; echo err >&2"])
+            .assert()
+            .success()
+            .stdout("")
+            .stderr(predicate::str::contains("err\n"))
+    };
+
+    run(&[]);
+    run(&["--redirect-append"]);
+    assert_eq!(fs::read_to_string(&out).unwrap(), "out\nout\n");
+    run(&[]);
+    assert_eq!(fs::read_to_string(&out).unwrap(), "out\n");
 }
 
+#[cfg(unix)]
 #[test]
-fn test_py_inline_code() {
+fn test_run_print_pid_matches_the_child() {
     let temp_dir = TempDir::new().unwrap();
-    write_color_disabled_config(&temp_dir);
-    shnote_cmd()
+    let pid_file = temp_dir.path().join("child.pid");
+
+    let assert = shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args([
-            "--what",
-            "测试Python",
-            "--why",
-            "验证",
-            "py",
-            "-c",
-            "print('hello from python')",
-        ])
+        .args(["--plain", "--what", "w", "--why", "y", "run", "--print-pid"])
+        .arg(&pid_file)
+        .args(["sh", "-c", "echo $$"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("WHAT: 测试Python"))
-        .stdout(predicate::str::contains("hello from python"));
+        .success();
+    let printed = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+
+    let written = fs::read_to_string(&pid_file).unwrap();
+    assert!(written.ends_with('\n'));
+    assert_eq!(written.trim(), printed.trim());
+    assert!(written.trim().parse::<u32>().is_ok(), "{written}");
 }
 
 #[test]
-fn test_py_file() {
+fn test_run_redirect_rejects_missing_directory() {
     let temp_dir = TempDir::new().unwrap();
-    let script = temp_dir.path().join("test.py");
-    fs::write(&script, "print('from file')").unwrap();
+    let target = temp_dir.path().join("nope").join("err.log");
 
     shnote_cmd()
+        .env("HOME", temp_dir.path())
         .args([
+            "--lang",
+            "en",
             "--what",
-            "测试",
+            "w",
             "--why",
-            "验证",
-            "py",
-            "-f",
-            script.to_str().unwrap(),
+            "y",
+            "run",
+            "--redirect-stderr",
         ])
+        .arg(&target)
+        .arg("true")
         .assert()
-        .success()
-        .stdout(predicate::str::contains("from file"));
+        .code(2)
+        .stderr(predicate::str::contains("its directory does not exist"));
+    assert!(!target.exists());
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "w",
+            "--why",
+            "y",
+            "run",
+            "--redirect-append",
+            "true",
+        ])
+        .assert()
+        .code(2);
 }
 
-#[cfg(unix)]
 #[test]
-fn test_py_stdin_reads_from_stdin_and_passes_args() {
+fn test_run_capture_on_failure_only_keeps_failed_runs() {
     let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("failures.log");
+    write_color_disabled_config(&temp_dir);
+    let run = |script: &str| {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args(["--what", "Check", "--why", "Debug", "run", "--log-file"])
+            .arg(&log)
+            .args(["--capture-on-failure-only", "sh", "-c", script])
+            .assert()
+    };
+
+    run("echo passing")
+        .success()
+        .stdout(predicate::str::contains("passing"));
+    assert_eq!(fs::read_to_string(&log).unwrap(), "");
+
+    run("echo failing >&2; exit 3").code(3);
+    let contents = fs::read_to_string(&log).unwrap();
+    assert!(!contents.contains("passing"), "{contents}");
+    assert!(contents.contains("WHAT: Check\n"), "{contents}");
+    assert!(contents.contains("] err| failing\n"), "{contents}");
+    assert!(contents.contains("exit 3 in "), "{contents}");
+}
 
-    // Point python to /bin/sh to avoid depending on system python.
+#[test]
+fn test_run_announce_appends_to_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("announce.log");
+    write_color_disabled_config(&temp_dir);
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "python", "/bin/sh"])
+        .args(["config", "set", "announce_to", log.to_str().unwrap()])
         .assert()
         .success();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
         .args([
-            "--what", "test", "--why", "test", "py", "--stdin", "arg0", "arg1",
+            "--what",
+            "Deploy",
+            "--why",
+            "Ship it",
+            "run",
+            "--announce",
+            "true",
         ])
-        .write_stdin("echo stdin-ok\n")
         .assert()
-        .success()
-        .stdout(predicate::str::contains("stdin-ok"));
+        .success();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "Quiet", "--why", "No announce", "run", "true"])
+        .assert()
+        .success();
+
+    let contents = fs::read_to_string(&log).unwrap();
+    assert_eq!(contents.lines().count(), 1, "{contents}");
+    let line: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+    assert_eq!(line["what"], "Deploy");
+    assert_eq!(line["why"], "Ship it");
 }
 
-// === node command ===
 #[test]
-fn test_node_requires_what_why() {
+fn test_run_announce_failure_does_not_fail_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let missing = temp_dir.path().join("missing/dir/announce.log");
+    write_color_disabled_config(&temp_dir);
     shnote_cmd()
-        .args(["node", "-c", "console.log(1)"])
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "announce_to", missing.to_str().unwrap()])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
-}
+        .success();
 
-#[test]
-fn test_node_inline_code() {
     shnote_cmd()
+        .env("HOME", temp_dir.path())
         .args([
+            "--lang",
+            "en",
             "--what",
-            "测试Node",
+            "Deploy",
             "--why",
-            "验证",
-            "node",
-            "-c",
-            "console.log('hello from node')",
+            "Ship it",
+            "run",
+            "--announce",
+            "true",
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("hello from node"));
+        .stderr(predicate::str::contains("failed to announce"));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_node_requires_source() {
-    shnote_cmd()
-        .args(["--what", "test", "--why", "test", "node"])
-        .assert()
-        .failure();
-}
+fn test_run_on_failure_hook_runs_and_exit_code_is_preserved() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
 
-// === pip command ===
-#[test]
-fn test_pip_requires_what_why() {
     shnote_cmd()
-        .args(["pip", "--version"])
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "hook",
+            "--why",
+            "hook-check",
+            "run",
+            "--on-failure",
+            "echo recovering",
+            "--on-success",
+            "echo celebrating",
+            "--",
+            "sh",
+            "-c",
+            "exit 2",
+        ])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
+        .code(2)
+        .stdout(predicate::str::contains("recovering"))
+        .stdout(predicate::str::contains("celebrating").not());
 }
 
+#[cfg(unix)]
 #[test]
-fn test_pip_with_what_why() {
+fn test_run_cpu_limit_reports_limit_kill() {
     let temp_dir = TempDir::new().unwrap();
     write_color_disabled_config(&temp_dir);
+
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--what", "test", "--why", "test", "pip", "--version"])
+        .args([
+            "--lang",
+            "en",
+            "--what",
+            "spin",
+            "--why",
+            "limit-check",
+            "run",
+            "--cpu-limit",
+            "1",
+            "--",
+            "sh",
+            "-c",
+            "while :; do :; done",
+        ])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("WHAT: test"))
-        .stdout(predicate::str::contains("pip"));
+        .failure()
+        .stderr(predicate::str::contains("CPU time limit of 1s exceeded"));
 }
 
-// === npm command ===
 #[test]
-fn test_npm_requires_what_why() {
+fn test_run_rejects_zero_mem_limit() {
     shnote_cmd()
-        .args(["npm", "--version"])
+        .args([
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--mem-limit",
+            "0",
+            "--",
+            "true",
+        ])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
+        .failure();
 }
 
+#[cfg(unix)]
 #[test]
-fn test_npm_with_what_why() {
+fn test_run_exit_on_remaps_exit_code() {
     let temp_dir = TempDir::new().unwrap();
     write_color_disabled_config(&temp_dir);
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--what", "test", "--why", "test", "npm", "--version"])
+        .args([
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--exit-on",
+            "1=>0",
+            "--",
+            "grep",
+            "-q",
+            "zzz",
+            "/dev/null",
+        ])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("WHAT: test"));
-}
-
-// === npx command ===
-#[test]
-fn test_npx_requires_what_why() {
+        .success();
     shnote_cmd()
-        .args(["npx", "--version"])
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what", "x", "--why", "y", "run", "--", "sh", "-c", "exit 3",
+        ])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("--what"));
+        .code(3);
 }
 
+#[cfg(unix)]
 #[test]
-fn test_npx_with_what_why() {
+fn test_run_tty_gives_command_a_terminal() {
     let temp_dir = TempDir::new().unwrap();
     write_color_disabled_config(&temp_dir);
+    // ls-style color detection: only a terminal gets escape codes.
+    let script = r#"if [ -t 1 ]; then printf '\033[32mcolor\033[0m\n'; else echo plain; fi"#;
+
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--what", "test", "--why", "test", "npx", "--version"])
+        .args([
+            "--what", "x", "--why", "y", "run", "--tty", "--", "sh", "-c", script,
+        ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("WHAT: test"));
-}
-
-// === config command ===
-#[test]
-fn test_config_list() {
+        .stdout(predicate::str::contains("\x1b[32mcolor"));
     shnote_cmd()
-        .args(["config", "list"])
+        .env("HOME", temp_dir.path())
+        .args(["--what", "x", "--why", "y", "run", "--", "sh", "-c", script])
         .assert()
         .success()
-        .stdout(predicate::str::contains("python"))
-        .stdout(predicate::str::contains("node"))
-        .stdout(predicate::str::contains("color"))
-        .stdout(predicate::str::contains("what_color"))
-        .stdout(predicate::str::contains("why_color"))
-        .stdout(predicate::str::contains("run_string_shell_mode"))
-        .stdout(predicate::str::contains("header_timing"))
-        .stdout(predicate::str::contains("header_stream"));
+        .stdout(predicate::str::contains("plain"));
 }
 
 #[cfg(unix)]
 #[test]
-fn test_config_errors_when_config_read_fails() {
-    use std::os::unix::fs::PermissionsExt;
-
-    let home_dir = TempDir::new().unwrap();
-    let shnote_dir = home_dir.path().join(".shnote");
-    fs::create_dir_all(&shnote_dir).unwrap();
-
-    let config_path = shnote_dir.join("config.toml");
-    fs::write(&config_path, "not = \"readable\"\n").unwrap();
-    fs::set_permissions(&config_path, fs::Permissions::from_mode(0o000)).unwrap();
+fn test_run_no_tty_detaches_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let script = r#"read line; echo "got:$line""#;
 
     shnote_cmd()
-        .env("HOME", home_dir.path())
-        .args(["--lang", "en", "config", "list"])
+        .env("HOME", temp_dir.path())
+        .args(["--what", "x", "--why", "y", "run", "--", "sh", "-c", script])
+        .write_stdin("hello\n")
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to read config file"));
-}
-
-#[test]
-fn test_config_get() {
+        .stdout(predicate::str::contains("got:hello"));
     shnote_cmd()
-        .args(["config", "get", "python"])
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what", "x", "--why", "y", "run", "--no-tty", "--", "sh", "-c", script,
+        ])
+        .write_stdin("hello\n")
         .assert()
-        .success();
+        .stdout(predicate::str::contains("got:\n"));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_get_shell() {
+fn test_run_args_from_appends_file_arguments() {
     let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let args_file = temp_dir.path().join("args.txt");
+    fs::write(&args_file, "second\n'  third  '\n\"fourth \\\"q\\\"\"\n").unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "shell"])
+        .args(["--what", "x", "--why", "y", "run", "--args-from"])
+        .arg(&args_file)
+        .args(["--", "printf", "[%s]", "first"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("auto"));
-}
-
-#[test]
-fn test_config_get_unknown() {
-    shnote_cmd()
-        .args(["config", "get", "unknown_key"])
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("unknown"));
+        .stdout(predicate::str::contains(
+            "[first][second][  third  ][fourth \"q\"]",
+        ));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_python() {
+fn test_run_binary_safe_encodes_invalid_utf8_stdout() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+    let script = r"printf '\377\376ok'; printf 'note\n' >&2";
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "python", "/usr/bin/python3"])
+        .args(["--what", "x", "--why", "y", "run", "--", "sh", "-c", script])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::eq(&b"\xff\xfeok"[..]));
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "python"])
+        .args([
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--binary-safe",
+            "--",
+            "sh",
+            "-c",
+            script,
+        ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("/usr/bin/python3"));
+        .stdout("//5vaw==\n")
+        .stderr(predicate::str::contains("note"));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_node() {
+fn test_batch_fail_fast_and_keep_going() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+    let steps = temp_dir.path().join("steps.txt");
+    fs::write(&steps, "# build\necho one\nexit 4\necho three\n").unwrap();
+    let batch = |extra: &[&str]| {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args(["--lang", "en", "--what", "x", "--why", "y", "batch"])
+            .args(extra)
+            .arg(&steps)
+            .assert()
+    };
+
+    batch(&[])
+        .code(4)
+        .stdout("one\n")
+        .stderr(predicate::str::contains(
+            "batch: 3 steps, 1 succeeded, 1 failed, 1 skipped",
+        ))
+        .stderr(predicate::str::contains("[2] exit 4 (exit code 4)"))
+        .stderr(predicate::str::contains("[3] echo three (skipped)"));
+
+    let output = batch(&["--keep-going", "--json"])
+        .code(4)
+        .stdout("one\nthree\n")
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let summary: serde_json::Value = stderr
+        .lines()
+        .find_map(|line| serde_json::from_str(line).ok())
+        .expect("JSON summary on stderr");
+    assert_eq!(summary["schema_version"], 1);
+    assert_eq!(summary["total"], 3);
+    assert_eq!(summary["succeeded"], 2);
+    assert_eq!(summary["failed"], 1);
+    assert_eq!(summary["steps"][1]["exit_code"], 4);
+    assert_eq!(summary["steps"][2]["status"], "succeeded");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_batch_parallel_keeps_step_order() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+    let steps = temp_dir.path().join("steps.txt");
+    fs::write(
+        &steps,
+        "sleep 0.4; echo slow; exit 3\necho fast\nsleep 0.1; echo >&2 err; exit 5\necho last\n",
+    )
+    .unwrap();
 
+    let started = std::time::Instant::now();
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "node", "/usr/local/bin/node"])
+        .args(["--lang", "en", "--what", "x", "--why", "y", "batch"])
+        .args(["--parallel", "4", "--keep-going"])
+        .arg(&steps)
         .assert()
-        .success();
+        .code(3)
+        .stdout("slow\nfast\nlast\n")
+        .stderr(predicate::str::starts_with("err\n"))
+        .stderr(predicate::str::contains(
+            "batch: 4 steps, 2 succeeded, 2 failed, 0 skipped",
+        ));
+    assert!(started.elapsed() < std::time::Duration::from_millis(1500));
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "node"])
+        .args(["--what", "x", "--why", "y", "batch", "--parallel", "0"])
+        .arg(&steps)
         .assert()
-        .success()
-        .stdout(predicate::str::contains("/usr/local/bin/node"));
+        .code(2);
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_unknown_key() {
+fn test_batch_parallel_history_records_wall_time_and_output() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
-
-    shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["config", "set", "unknown_key", "value"])
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("unknown"));
-}
-
-#[test]
-fn test_config_set_shell() {
-    let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+    let steps = temp_dir.path().join("steps.txt");
+    fs::write(
+        &steps,
+        "sleep 0.3; echo one\nsleep 0.3; echo two\nsleep 0.3; echo three\n",
+    )
+    .unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "shell", "bash"])
+        .args(["--what", "x", "--why", "y", "--capture", "batch"])
+        .args(["--parallel", "3"])
+        .arg(&steps)
         .assert()
         .success();
+
+    let history = fs::read_to_string(temp_dir.path().join(".shnote/history.jsonl")).unwrap();
+    let record: serde_json::Value = serde_json::from_str(history.trim()).unwrap();
+    // The steps overlap: the run time is not their sum (0.9s)
+    let duration_ms = record["duration_ms"].as_u64().unwrap();
+    assert!((300..800).contains(&duration_ms), "{history}");
+    assert_eq!(record["stdout_excerpt"], "one\ntwo\nthree\n");
 }
 
 #[test]
-fn test_config_set_invalid_shell() {
+fn test_batch_requires_what_why() {
     shnote_cmd()
-        .args(["config", "set", "shell", "invalid"])
+        .args(["batch", "steps.txt"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("invalid"));
+        .code(2)
+        .stderr(predicate::str::contains("--what"));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_language() {
+fn test_run_measure_reports_usage() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    write_color_disabled_auto_config(&temp_dir);
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "language", "zh"])
+        .args([
+            "--lang",
+            "en",
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--measure",
+            "--",
+            "sh",
+            "-c",
+            "exit 3",
+        ])
         .assert()
-        .success();
+        .code(3)
+        .stderr(
+            predicate::str::is_match(
+                r"shnote: max RSS \d+\.\d MiB, CPU \d+\.\d{2}s user, \d+\.\d{2}s system",
+            )
+            .unwrap(),
+        );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_measure_output_size_reports_and_records_byte_counts() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+    let payload = "x".repeat(10_000);
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "language"])
+        .args([
+            "--lang",
+            "en",
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--measure-output-size",
+            "--",
+            "sh",
+            "-c",
+            "printf '%s' \"$0\"; printf 'oops\\n' >&2",
+            &payload,
+        ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("zh"));
+        .stdout(payload.clone())
+        .stderr(predicate::str::contains(
+            "shnote: output 10000 bytes stdout, 5 bytes stderr",
+        ));
+
+    let history = fs::read_to_string(temp_dir.path().join(".shnote/history.jsonl")).unwrap();
+    let record: serde_json::Value = serde_json::from_str(history.trim()).unwrap();
+    assert_eq!(record["stdout_bytes"], 10_000);
+    assert_eq!(record["stderr_bytes"], 5);
+    assert!(record.get("stdout_excerpt").is_none());
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_color() {
+fn test_run_print_env_lists_sorted_redacted_env() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    write_color_disabled_auto_config(&temp_dir);
 
-    shnote_cmd()
+    let output = shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "color", "false"])
-        .assert()
-        .success();
+        .env("SHNOTE_B_API_TOKEN", "s3cret")
+        .env("SHNOTE_A_PLAIN", "visible")
+        .args([
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--print-env",
+            "--",
+            "echo",
+            "ran",
+        ])
+        .output()
+        .unwrap();
 
-    shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["config", "get", "color"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("false"));
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ran\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("s3cret"), "{stderr}");
+    let plain = stderr.find("SHNOTE_A_PLAIN=visible\n").unwrap();
+    let token = stderr.find("SHNOTE_B_API_TOKEN=***\n").unwrap();
+    assert!(plain < token, "{stderr}");
 }
 
 #[test]
-fn test_config_set_what_why_color() {
+fn test_run_args_from_missing_file_fails() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "what_color", "yellow"])
+        .args([
+            "--lang",
+            "en",
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "run",
+            "--args-from",
+            "no-such-args.txt",
+            "echo",
+        ])
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains("no-such-args.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_strict_fails_on_nonzero_unless_allowed() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\noutput = \"quiet\"\n\n[run]\nstrict = true\n",
+    )
+    .unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "why_color", "blue"])
+        .args([
+            "--lang", "en", "--what", "x", "--why", "y", "run", "--", "sh", "-c", "exit 3",
+        ])
         .assert()
-        .success();
-
+        .code(1)
+        .stderr(predicate::str::contains("exited with code 3"));
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "what_color"])
+        .args([
+            "--what",
+            "x",
+            "--why",
+            "y",
+            "--allow-nonzero",
+            "run",
+            "--",
+            "sh",
+            "-c",
+            "exit 3",
+        ])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("yellow"));
-
+        .code(3);
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "why_color"])
+        .args(["--what", "x", "--why", "y", "run", "--", "true"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("blue"));
+        .success();
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_header_stream() {
+fn test_run_on_success_hook_runs_after_success() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    write_color_disabled_config(&temp_dir);
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "header_stream", "stderr"])
+        .args([
+            "--what",
+            "hook",
+            "--why",
+            "hook-check",
+            "run",
+            "--on-success",
+            "echo celebrating",
+            "echo",
+            "done",
+        ])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains("done"))
+        .stdout(predicate::str::contains("celebrating"));
+}
+
+#[test]
+fn test_run_plain_prints_only_command_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "header_stream = \"stdout\"\nheader_timing = \"both\"\noutput_prefix = \"[x] \"\n",
+    )
+    .unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "header_stream"])
+        .args([
+            "--plain",
+            "--what",
+            "plain",
+            "--why",
+            "plain-check",
+            "run",
+            "echo",
+            "hello",
+        ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("stderr"));
+        .stdout("hello\n")
+        .stderr("");
 }
 
 #[test]
-fn test_config_set_invalid_header_stream() {
+fn test_run_once_skips_second_run_with_same_label() {
+    let temp_dir = TempDir::new().unwrap();
+    let data_dir = TempDir::new().unwrap();
+    let run_once = || {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .env("SHNOTE_DATA_DIR", data_dir.path())
+            .args([
+                "--plain", "--lang", "en", "--what", "w", "--why", "y", "run", "--once", "--label",
+                "greet", "echo", "hello",
+            ])
+            .assert()
+            .success()
+    };
+
+    run_once().stdout("hello\n");
+    run_once()
+        .stdout("")
+        .stderr(predicate::str::contains("\"greet\" already succeeded"));
+}
+
+#[test]
+fn test_run_once_requires_label() {
     shnote_cmd()
-        .args(["config", "set", "header_stream", "invalid"])
+        .args(["--what", "w", "--why", "y", "run", "--once", "true"])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("invalid"));
+        .stderr(predicate::str::contains("--label"));
+}
+
+// === --agent-mode contract ===
+fn stderr_json_lines(output: &std::process::Output) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("agent-mode stderr line is JSON"))
+        .collect()
 }
 
 #[test]
-fn test_config_set_header_timing() {
+fn test_agent_mode_header_is_json_on_stderr() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "header_stream = \"stdout\"\nheader_timing = \"both\"\noutput_prefix = \"[x] \"\n",
+    )
+    .unwrap();
 
-    shnote_cmd()
+    let output = shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "header_timing", "both"])
-        .assert()
-        .success();
+        .args([
+            "--agent-mode",
+            "--what",
+            "list",
+            "--why",
+            "check \"layout\"",
+            "run",
+            "echo",
+            "hello",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello\n");
+    let lines = stderr_json_lines(&output);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["type"], "header");
+    assert_eq!(lines[0]["what"], "list");
+    assert_eq!(lines[0]["why"], "check \"layout\"");
+}
 
-    shnote_cmd()
+#[cfg(unix)]
+#[test]
+fn test_agent_mode_passes_through_child_exit_code_and_stderr() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "header_timing"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("both"));
+        .args([
+            "--agent-mode",
+            "--what",
+            "w",
+            "--why",
+            "y",
+            "run",
+            "sh",
+            "-c",
+            "echo out; echo err >&2; exit 3",
+        ])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"out\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut lines = stderr.lines();
+    let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(header["type"], "header");
+    assert_eq!(lines.next(), Some("err"));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_config_set_invalid_header_timing() {
-    shnote_cmd()
-        .args(["config", "set", "header_timing", "invalid"])
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("invalid"));
+fn test_agent_mode_reports_hook_failures_as_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--agent-mode",
+            "--lang",
+            "en",
+            "--what",
+            "w",
+            "--why",
+            "y",
+            "run",
+            "--on-success",
+            "exit 3",
+            "--",
+            "true",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let lines = stderr_json_lines(&output);
+    assert_eq!(lines[0]["type"], "header");
+    let warning = lines.last().unwrap();
+    assert_eq!(warning["type"], "warning");
+    assert!(warning["message"].as_str().unwrap().contains("exit 3"));
 }
 
 #[test]
-fn test_config_set_run_string_shell_mode() {
+fn test_agent_mode_reports_shnote_errors_as_json() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
-
-    shnote_cmd()
+    let output = shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "run_string_shell_mode", "ilc"])
-        .assert()
-        .success();
+        .args([
+            "--agent-mode",
+            "--lang",
+            "en",
+            "--what",
+            "w",
+            "--why",
+            "y",
+            "run",
+            "--cwd",
+            "/nonexistent_dir_xyz",
+            "--",
+            "true",
+        ])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(125));
+    assert!(output.stdout.is_empty());
+    let lines = stderr_json_lines(&output);
+    let error = lines.last().unwrap();
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["exit_code"], 125);
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("nonexistent_dir_xyz"));
+}
 
-    shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["config", "get", "run_string_shell_mode"])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("ilc"));
+#[test]
+fn test_agent_mode_reports_usage_errors_as_json() {
+    for args in [
+        &["--agent-mode", "run", "ls"][..],
+        &["--agent-mode", "--no-such-flag"][..],
+    ] {
+        let output = shnote_cmd().args(args).output().unwrap();
+        assert_eq!(output.status.code(), Some(125));
+        assert!(output.stdout.is_empty());
+        let lines = stderr_json_lines(&output);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["type"], "error");
+    }
 }
 
 #[test]
-fn test_config_set_invalid_run_string_shell_mode() {
+fn test_run_without_subcommand_defaults_to_run() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
     shnote_cmd()
-        .args(["config", "set", "run_string_shell_mode", "invalid"])
+        .env("HOME", temp_dir.path())
+        .args(["--what", "测试", "--why", "验证", "echo", "hello"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("invalid"));
+        .success()
+        .stdout(predicate::str::contains("WHAT: 测试"))
+        .stdout(predicate::str::contains("WHY:  验证"))
+        .stdout(predicate::str::contains("hello"));
 }
 
 #[test]
-fn test_config_set_invalid_label_color() {
+fn test_run_with_single_string_uses_shell_lc_default() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "测试",
+            "--why",
+            "验证",
+            "run",
+            "echo 'hi' && echo 'hello'",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hi"))
+        .stdout(predicate::str::contains("hello"));
+}
+
+#[test]
+fn test_run_without_subcommand_single_string_uses_shell_lc_default() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "测试",
+            "--why",
+            "验证",
+            "echo 'hi' && echo 'hello'",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hi"))
+        .stdout(predicate::str::contains("hello"));
+}
+
+#[test]
+fn test_run_with_what_why_auto_routes_header_to_stderr_when_stdout_not_tty() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+
+    let assert = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "auto-header",
+            "--why",
+            "pipeline-safe",
+            "run",
+            "echo",
+            "hello",
+        ])
+        .assert()
+        .success();
+
+    let output = assert.get_output();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("hello"));
+    assert!(!stdout.contains("WHAT: auto-header"));
+    assert!(!stdout.contains("WHY:  pipeline-safe"));
+    assert!(stderr.contains("WHAT: auto-header"));
+    assert!(stderr.contains("WHY:  pipeline-safe"));
+}
+
+#[test]
+fn test_run_with_header_stream_stdout_flag() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+
+    let assert = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--header-stream",
+            "stdout",
+            "--what",
+            "force-stdout",
+            "--why",
+            "display-first",
+            "run",
+            "echo",
+            "hello",
+        ])
+        .assert()
+        .success();
+
+    let output = assert.get_output();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("WHAT: force-stdout"));
+    assert!(stdout.contains("WHY:  display-first"));
+    assert!(stderr.trim().is_empty());
+}
+
+#[test]
+fn test_run_with_header_stream_stderr_flag() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_auto_config(&temp_dir);
+
+    let assert = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--header-stream",
+            "stderr",
+            "--what",
+            "force-stderr",
+            "--why",
+            "pipeline-safe",
+            "run",
+            "echo",
+            "hello",
+        ])
+        .assert()
+        .success();
+
+    let output = assert.get_output();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("hello"));
+    assert!(!stdout.contains("WHAT: force-stderr"));
+    assert!(stderr.contains("WHAT: force-stderr"));
+    assert!(stderr.contains("WHY:  pipeline-safe"));
+}
+
+#[test]
+fn test_run_missing_only_what() {
+    shnote_cmd()
+        .args(["--what", "test", "run", "echo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--why"));
+}
+
+#[test]
+fn test_run_missing_only_why() {
+    shnote_cmd()
+        .args(["--why", "test", "run", "echo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[test]
+fn test_run_without_subcommand_requires_what_why() {
+    shnote_cmd()
+        .args(["echo", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+// === py command ===
+#[test]
+fn test_py_requires_what_why() {
+    shnote_cmd()
+        .args(["py", "-c", "print(1)"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[test]
+fn test_py_requires_source() {
+    shnote_cmd()
+        .args(["--what", "test", "--why", "test", "py"])
+        .assert()
+        .failure()
+        .stderr(
+            predicate::str::contains("source")
+                .or(predicate::str::contains("stdin"))
+                .or(predicate::str::contains("code"))
+                .or(predicate::str::contains("file")),
+        );
+}
+
+#[test]
+fn test_py_inline_code() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "测试Python",
+            "--why",
+            "验证",
+            "py",
+            "-c",
+            "print('hello from python')",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("WHAT: 测试Python"))
+        .stdout(predicate::str::contains("hello from python"));
+}
+
+#[test]
+fn test_py_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("test.py");
+    fs::write(&script, "print('from file')").unwrap();
+
+    shnote_cmd()
+        .args([
+            "--what",
+            "测试",
+            "--why",
+            "验证",
+            "py",
+            "-f",
+            script.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from file"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_py_stdin_reads_from_stdin_and_passes_args() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // Point python to /bin/sh to avoid depending on system python.
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "python", "/bin/sh"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what", "test", "--why", "test", "py", "--stdin", "arg0", "arg1",
+        ])
+        .write_stdin("echo stdin-ok\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("stdin-ok"));
+}
+
+// === sh command ===
+#[test]
+fn test_sh_requires_what_why() {
+    shnote_cmd()
+        .args(["sh", "-c", "echo hi"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sh_inline_passes_positional_args() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHELL", "/bin/sh")
+        .args(["--what", "test", "--why", "test", "sh", "-c"])
+        .args([
+            "for a in \"$@\"; do echo \"arg:$a\"; done | sort -r",
+            "one",
+            "two",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("arg:two\narg:one\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sh_stdin_runs_script() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHELL", "/bin/sh")
+        .args(["--what", "test", "--why", "test", "sh", "--stdin"])
+        .write_stdin("echo stdin-ok > out.txt && cat out.txt\nexit 3\n")
+        .current_dir(temp_dir.path())
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("stdin-ok"));
+}
+
+// === node command ===
+#[test]
+fn test_node_requires_what_why() {
+    shnote_cmd()
+        .args(["node", "-c", "console.log(1)"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[test]
+fn test_node_inline_code() {
+    shnote_cmd()
+        .args([
+            "--what",
+            "测试Node",
+            "--why",
+            "验证",
+            "node",
+            "-c",
+            "console.log('hello from node')",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hello from node"));
+}
+
+#[test]
+fn test_node_requires_source() {
+    shnote_cmd()
+        .args(["--what", "test", "--why", "test", "node"])
+        .assert()
+        .failure();
+}
+
+// === pip command ===
+#[test]
+fn test_pip_requires_what_why() {
+    shnote_cmd()
+        .args(["pip", "--version"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[test]
+fn test_pip_with_what_why() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "test", "--why", "test", "pip", "--version"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("WHAT: test"))
+        .stdout(predicate::str::contains("pip"));
+}
+
+#[test]
+fn test_pip_rejects_missing_cwd() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let missing = temp_dir.path().join("missing");
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--lang", "en", "--what", "test", "--why", "test", "pip", "--cwd",
+        ])
+        .arg(&missing)
+        .arg("--version")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--cwd is not an existing directory",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_cwd_sets_working_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let work = temp_dir.path().join("work");
+    fs::create_dir(&work).unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "test", "--why", "test", "run", "--cwd"])
+        .arg(&work)
+        .args(["--", "touch", "marker"])
+        .assert()
+        .success();
+    assert!(work.join("marker").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_until_leaves_command_running_after_pattern() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let data_dir = TempDir::new().unwrap();
+
+    let assert = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+        .args(["--until", "Listening on", "--"])
+        .args([
+            "sh",
+            "-c",
+            "echo booting; echo 'Listening on :3000'; sleep 30",
+        ])
+        .timeout(std::time::Duration::from_secs(20))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("booting\nListening on :3000"))
+        .stderr(predicate::str::contains("running in the background"));
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    let pid = stderr
+        .split("(pid ")
+        .nth(1)
+        .and_then(|rest| rest.split(',').next())
+        .unwrap()
+        .to_string();
+    // Still running after shnote returned
+    assert!(std::process::Command::new("kill")
+        .args(["-0", &pid])
+        .status()
+        .unwrap()
+        .success());
+    std::process::Command::new("kill")
+        .arg(&pid)
+        .status()
+        .unwrap();
+    assert!(data_dir.path().join("run-logs").read_dir().unwrap().count() == 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_until_writes_to_configured_log_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\n\n[run]\nlog_dir = \"~/job-logs\"\n",
+    )
+    .unwrap();
+    let data_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+        .args(["--until", "ready", "--", "sh", "-c", "echo ready"])
+        .timeout(std::time::Duration::from_secs(20))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("job-logs"));
+
+    let log_dir = temp_dir.path().join("job-logs");
+    let mode = fs::metadata(&log_dir).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+    let logs: Vec<_> = log_dir
+        .read_dir()
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(logs.len(), 1);
+    let name = logs[0].file_name().unwrap().to_string_lossy().to_string();
+    // run-YYYYMMDD-HHMMSS-<random>.log
+    assert!(
+        name.starts_with("run-20") && name.ends_with(".log"),
+        "{name}"
+    );
+    assert_eq!(name.as_bytes()[12], b'-', "{name}");
+    assert!(fs::read_to_string(&logs[0]).unwrap().contains("ready"));
+    assert!(!data_dir.path().join("run-logs").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_until_times_out() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let data_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+        .args([
+            "--until",
+            "ready",
+            "--until-timeout",
+            "1",
+            "--",
+            "sleep",
+            "30",
+        ])
+        .timeout(std::time::Duration::from_secs(20))
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("did not appear within 1s"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_run_until_timeout_stops_the_whole_process_group() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let data_dir = TempDir::new().unwrap();
+    let pid_file = temp_dir.path().join("grandchild.pid");
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+        .args(["--until", "ready", "--until-timeout", "1", "--", "sh", "-c"])
+        .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()))
+        .timeout(std::time::Duration::from_secs(20))
+        .assert()
+        .code(1);
+
+    let pid = fs::read_to_string(&pid_file).unwrap();
+    let stat_path = format!("/proc/{}/stat", pid.trim());
+    // Gone, or a zombie waiting to be reaped by whoever adopted it
+    let running = (0..20).all(|_| {
+        let alive = fs::read_to_string(&stat_path).is_ok_and(|stat| {
+            stat.rsplit(") ")
+                .next()
+                .is_some_and(|rest| !rest.starts_with('Z'))
+        });
+        if alive {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        alive
+    });
+    assert!(!running, "grandchild {} still running", pid.trim());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_chdir_to_git_root() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let repo = temp_dir.path().join("repo");
+    let nested = repo.join("a/b");
+    fs::create_dir_all(&nested).unwrap();
+    fs::create_dir(repo.join(".git")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(&nested)
+        .args([
+            "--what",
+            "test",
+            "--why",
+            "test",
+            "run",
+            "--chdir-to-git-root",
+        ])
+        .args(["--", "touch", "marker"])
+        .assert()
+        .success();
+    assert!(repo.join("marker").exists());
+    assert!(!nested.join("marker").exists());
+
+    let outside = temp_dir.path().join("outside");
+    fs::create_dir(&outside).unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(&outside)
+        .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+        .args(["--chdir-to-git-root", "--", "true"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("not inside a git repository"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_warn_shell_injection_is_advisory() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\n\n[run]\nwarn_shell_injection = true\n",
+    )
+    .unwrap();
+    let run = |script: &str| {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+            .args(["--", "sh", "-c", script])
+            .assert()
+            .success()
+    };
+
+    run("echo $(echo hi)")
+        .stdout(predicate::str::contains("hi"))
+        .stderr(predicate::str::contains(
+            "warning: the inline shell script uses $(...)",
+        ));
+    run("echo hi").stderr(predicate::str::contains("warning").not());
+}
+
+#[test]
+fn test_history_masks_secrets_in_argv() {
+    let temp_dir = TempDir::new().unwrap();
+    let data_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--what", "login", "--why", "test", "run", "--"])
+        .args(["echo", "--password", "hunter2", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--password hunter2"));
+
+    let history = fs::read_to_string(data_dir.path().join("history.jsonl")).unwrap();
+    assert!(
+        history.contains(r#""argv":["echo","--password","***","--verbose"]"#),
+        "{history}"
+    );
+    assert!(!history.contains("hunter2"), "{history}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_expand_env_expands_values_at_run_time() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    let write_config = |mode: &str| {
+        fs::write(
+            shnote_dir.join("config.toml"),
+            format!(
+                "color = false\nexpand_env = \"{mode}\"\n\n[run]\n\
+                 exec_prefix = [\"env\", \"GREETING=${{SHNOTE_TEST_GREETING}}$$\"]\n"
+            ),
+        )
+        .unwrap();
+    };
+    let run = || {
+        let mut cmd = shnote_cmd();
+        cmd.env("HOME", temp_dir.path())
+            .args(["--lang", "en", "--what", "test", "--why", "test", "run"])
+            .args(["--", "sh", "-c", "echo \"[$GREETING]\""]);
+        cmd
+    };
+
+    write_config("on");
+    run()
+        .env("SHNOTE_TEST_GREETING", "hello")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[hello$]"));
+    run()
+        .env_remove("SHNOTE_TEST_GREETING")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[${SHNOTE_TEST_GREETING}$$]"));
+
+    // `config get` keeps showing what was written
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_TEST_GREETING", "hello")
+        .args(["config", "get", "exec_prefix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("${SHNOTE_TEST_GREETING}"));
+
+    write_config("strict");
+    run()
+        .env_remove("SHNOTE_TEST_GREETING")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "config run.exec_prefix refers to $SHNOTE_TEST_GREETING, which is not set",
+        ));
+}
+
+#[test]
+fn test_config_commands_still_work_when_strict_expansion_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = |args: &[&str]| {
+        let mut cmd = shnote_cmd();
+        cmd.env("HOME", temp_dir.path())
+            .env_remove("NOPE_UNSET")
+            .args(["--lang", "en", "config"])
+            .args(args);
+        cmd
+    };
+
+    config(&["set", "python", "$NOPE_UNSET/py"])
+        .assert()
+        .success();
+    config(&["set", "expand_env", "strict"]).assert().success();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env_remove("NOPE_UNSET")
+        .args(["--lang", "en", "--what", "x", "--why", "y", "run", "true"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("$NOPE_UNSET"));
+
+    config(&["get", "python"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$NOPE_UNSET/py"));
+    config(&["set", "expand_env", "off"]).assert().success();
+    config(&["get", "expand_env"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("off"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_no_cwd_inherit_leaves_current_dir_clean() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let project = temp_dir.path().join("project");
+    fs::create_dir(&project).unwrap();
+
+    let output = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(&project)
+        .args(["--what", "test", "--why", "test", "run", "--no-cwd-inherit"])
+        .args(["--", "sh", "-c", "touch marker && pwd -P"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let child_cwd = std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    assert_ne!(child_cwd, project.canonicalize().unwrap());
+    assert!(!child_cwd.exists());
+    assert!(!project.join("marker").exists());
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "test", "--why", "test", "run", "--no-cwd-inherit"])
+        .args(["--cwd", "/tmp", "--", "true"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+// === npm command ===
+#[test]
+fn test_npm_requires_what_why() {
+    shnote_cmd()
+        .args(["npm", "--version"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[test]
+fn test_npm_with_what_why() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "test", "--why", "test", "npm", "--version"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("WHAT: test"));
+}
+
+// === npx command ===
+#[test]
+fn test_npx_requires_what_why() {
+    shnote_cmd()
+        .args(["npx", "--version"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--what"));
+}
+
+#[test]
+fn test_npx_with_what_why() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "test", "--why", "test", "npx", "--version"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("WHAT: test"));
+}
+
+// === config command ===
+#[test]
+fn test_config_list() {
+    shnote_cmd()
+        .args(["config", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("python"))
+        .stdout(predicate::str::contains("node"))
+        .stdout(predicate::str::contains("color"))
+        .stdout(predicate::str::contains("what_color"))
+        .stdout(predicate::str::contains("why_color"))
+        .stdout(predicate::str::contains("run_string_shell_mode"))
+        .stdout(predicate::str::contains("header_timing"))
+        .stdout(predicate::str::contains("header_stream"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_config_errors_when_config_read_fails() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home_dir = TempDir::new().unwrap();
+    let shnote_dir = home_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+
+    let config_path = shnote_dir.join("config.toml");
+    fs::write(&config_path, "not = \"readable\"\n").unwrap();
+    fs::set_permissions(&config_path, fs::Permissions::from_mode(0o000)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .args(["--lang", "en", "config", "list"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to read config file"));
+}
+
+#[test]
+fn test_config_get() {
+    shnote_cmd()
+        .args(["config", "get", "python"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_config_get_shell() {
+    let temp_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "shell"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("auto"));
+}
+
+#[test]
+fn test_config_get_show_source() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "[paths]\npython = \"py3\"\n",
+    )
+    .unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "config", "get", "python", "--show-source"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("py3 (user config: "))
+        .stdout(predicate::str::contains("config.toml)"));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "config", "get", "node", "--show-source"])
+        .assert()
+        .success()
+        .stdout("node (default)\n");
+}
+
+#[test]
+fn test_config_get_raw_keeps_whitespace() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "[paths]\npython = \" /opt/my python/bin/python3  \"\n",
+    )
+    .unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "python", "--raw"])
+        .assert()
+        .success()
+        .stdout(" /opt/my python/bin/python3  \n");
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "python", "--raw", "--no-newline"])
+        .assert()
+        .success()
+        .stdout(" /opt/my python/bin/python3  ");
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "python", "--no-newline"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_config_get_unknown() {
+    shnote_cmd()
+        .args(["config", "get", "unknown_key"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown"));
+}
+
+#[test]
+fn test_config_set_python() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "python", "/usr/bin/python3"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "python"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/usr/bin/python3"));
+}
+
+#[test]
+fn test_config_set_dry_run_validates_without_saving() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_file = temp_dir.path().join(".shnote").join("config.toml");
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--lang",
+            "en",
+            "config",
+            "set",
+            "history",
+            "FALSE",
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "dry run: history = false is valid (not saved)",
+        ));
+    assert!(!config_file.exists());
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--lang",
+            "en",
+            "config",
+            "set",
+            "language",
+            "fr",
+            "--dry-run",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("fr"));
+    assert!(!config_file.exists());
+}
+
+#[test]
+fn test_config_list_formats() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let plain = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "list"])
+        .output()
+        .unwrap();
+    let explicit = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "list", "--format", "plain"])
+        .output()
+        .unwrap();
+    assert_eq!(plain.stdout, explicit.stdout);
+    let plain = String::from_utf8(plain.stdout).unwrap();
+    assert!(plain.lines().any(|line| line == "header_timing = tail"));
+
+    let table = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "list", "--format", "table"])
+        .output()
+        .unwrap();
+    let table = String::from_utf8(table.stdout).unwrap();
+    assert_eq!(table.lines().count(), plain.lines().count());
+    let value_columns: Vec<_> = table
+        .lines()
+        .filter_map(|line| {
+            let key_end = line.find(' ')?;
+            Some(key_end + line[key_end..].find(|c: char| c != ' ')?)
+        })
+        .collect();
+    assert!(
+        value_columns
+            .iter()
+            .all(|&column| column == value_columns[0]),
+        "{table}"
+    );
+    assert!(table.contains("header_timing"));
+    assert!(!table.contains('\x1b'), "no color when piped");
+}
+
+#[test]
+fn test_config_diff_shows_only_changed_keys() {
+    let temp_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "config", "diff"])
+        .assert()
+        .success()
+        .stdout("all configuration values are the defaults\n");
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "header_timing", "both"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "config", "diff"])
+        .assert()
+        .success()
+        .stdout("header_timing = both (default: tail)\n");
+
+    let output = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "diff", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        diff,
+        serde_json::json!([{ "key": "header_timing", "value": "both", "default": "tail" }])
+    );
+}
+
+#[test]
+fn test_concurrent_config_sets_all_persist() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = [
+        ("python", "/opt/python3"),
+        ("node", "/opt/node"),
+        ("shell", "bash"),
+        ("header_timing", "both"),
+        ("header_stream", "stderr"),
+        ("what_color", "yellow"),
+        ("why_color", "green"),
+        ("output_prefix", "[x] "),
+    ];
+
+    let children: Vec<_> = settings
+        .iter()
+        .map(|(key, value)| {
+            std::process::Command::new(env!("CARGO_BIN_EXE_shnote"))
+                .env("HOME", temp_dir.path())
+                .args(["config", "set", key, value])
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    for (key, value) in settings {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args(["config", "get", key])
+            .assert()
+            .success()
+            .stdout(format!("{value}\n"));
+    }
+    assert!(!temp_dir.path().join(".shnote/config.toml.lock").exists());
+}
+
+#[test]
+fn test_config_readonly_refuses_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_CONFIG_READONLY", "1")
+        .args([
+            "--lang",
+            "en",
+            "config",
+            "set",
+            "python",
+            "/usr/bin/python3",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("read-only"));
+    assert!(!temp_dir.path().join(".shnote/config.toml").exists());
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_CONFIG_READONLY", "1")
+        .args(["config", "get", "python"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("python3"));
+}
+
+#[test]
+fn test_header_labels_follow_config() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+    fs::write(
+        temp_dir.path().join(".shnote/config.toml"),
+        "color = false\nwhat_label = \"auto\"\nwhy_label = \"auto\"\n",
+    )
+    .unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args([
+            "--lang", "zh", "--what", "测试", "--why", "标签", "run", "true",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("做什么: 测试\n为什么: 标签\n"));
+}
+
+#[test]
+fn test_config_set_node() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "node", "/usr/local/bin/node"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/usr/local/bin/node"));
+}
+
+#[test]
+fn test_config_set_unknown_key() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "unknown_key", "value"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown"));
+}
+
+#[test]
+fn test_config_set_shell() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "shell", "bash"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_config_set_invalid_shell() {
+    shnote_cmd()
+        .args(["config", "set", "shell", "invalid"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+#[test]
+fn test_config_set_language() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "language", "zh"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "language"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("zh"));
+}
+
+#[test]
+fn test_config_set_color() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "color", "false"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "color"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("false"));
+}
+
+#[test]
+fn test_config_set_reports_normalized_boolean() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "config", "set", "color", "yes"])
+        .assert()
+        .success()
+        .stdout("config updated: color = true\n");
+}
+
+#[test]
+fn test_config_set_what_why_color() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "what_color", "yellow"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "why_color", "blue"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "what_color"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("yellow"));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "why_color"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("blue"));
+}
+
+#[test]
+fn test_config_set_header_stream() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "header_stream", "stderr"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "header_stream"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("stderr"));
+}
+
+#[test]
+fn test_config_set_invalid_header_stream() {
+    shnote_cmd()
+        .args(["config", "set", "header_stream", "invalid"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+#[test]
+fn test_config_set_header_timing() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "header_timing", "both"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "header_timing"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("both"));
+}
+
+#[test]
+fn test_config_set_invalid_header_timing() {
+    shnote_cmd()
+        .args(["config", "set", "header_timing", "invalid"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+#[test]
+fn test_config_set_run_string_shell_mode() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "run_string_shell_mode", "ilc"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "run_string_shell_mode"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ilc"));
+}
+
+#[test]
+fn test_config_set_invalid_run_string_shell_mode() {
+    shnote_cmd()
+        .args(["config", "set", "run_string_shell_mode", "invalid"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+#[test]
+fn test_config_set_invalid_label_color() {
     shnote_cmd()
         .args(["config", "set", "what_color", "invalid"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("invalid"));
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+#[test]
+fn test_config_set_invalid_language() {
+    shnote_cmd()
+        .args(["config", "set", "language", "invalid"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid"));
+}
+
+#[test]
+fn test_config_path() {
+    shnote_cmd()
+        .args(["config", "path"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("config.toml"));
+}
+
+#[test]
+fn test_config_reset() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
+
+    // Set some config first
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "language", "zh"])
+        .assert()
+        .success();
+
+    // Reset
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "reset"])
+        .assert()
+        .success();
+
+    // Verify reset to default
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "get", "language"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("auto"));
+}
+
+#[test]
+fn test_config_reset_errors_when_home_missing() {
+    shnote_cmd()
+        .env_remove("HOME")
+        .env_remove("USERPROFILE")
+        .args(["--lang", "en", "config", "reset"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to determine home directory",
+        ));
+}
+
+// === init command ===
+#[test]
+fn test_init_set_var_adds_extra_rules() {
+    let temp_dir = TempDir::new().unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args([
+            "--lang",
+            "en",
+            "init",
+            "--rules-lang",
+            "en",
+            "--scope",
+            "project",
+        ])
+        .args([
+            "--set-var",
+            "EXTRA_RULES=Docs: {{DOCS}}",
+            "--set-var",
+            "DOCS=https://wiki.example",
+        ])
+        .arg("agents")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("warning").not());
+    let rules = fs::read_to_string(temp_dir.path().join("AGENTS.md")).unwrap();
+    assert!(
+        rules.contains("\n\nDocs: https://wiki.example\n"),
+        "{rules}"
+    );
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .args(["--lang", "en", "init", "--scope", "project"])
+        .args(["--set-var", "EXTRA_RULES=Ask {{OWNER}}", "agents"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "warning: {{OWNER}} in the rules has no value",
+        ));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "--set-var", "nokey", "agents"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid --set-var: nokey"));
+}
+
+#[test]
+fn test_init_claude() {
+    let temp_dir = TempDir::new().unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", temp_dir.path())
+        .args(["init", "claude"])
+        .assert()
+        .success();
+
+    let rules_file = temp_dir.path().join(".claude/CLAUDE.md");
+    assert!(rules_file.exists());
+    let content = fs::read_to_string(rules_file).unwrap();
+    assert!(content.contains("shnote rules start"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_init_claude_writes_rules_when_claude_new_enough() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tools_dir = TempDir::new().unwrap();
+
+    let claude = tools_dir.path().join("claude");
+    fs::write(&claude, "#!/bin/sh\necho \"Claude Code 2.0.64\"\nexit 0\n").unwrap();
+    fs::set_permissions(&claude, fs::Permissions::from_mode(0o755)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", tools_dir.path())
+        .args(["init", "claude"])
+        .assert()
+        .success();
+
+    let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
+    assert!(rules_file.exists());
+    let content = fs::read_to_string(rules_file).unwrap();
+    assert!(content.contains("shnote"));
+    assert!(content.contains("--what"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_init_all_reports_each_target_and_fails_only_on_write_errors() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tools_dir = TempDir::new().unwrap();
+    for tool in ["codex", "gemini"] {
+        let path = tools_dir.path().join(tool);
+        fs::write(&path, "#!/bin/sh\necho 1.0.0\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let init_all = || {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .env("PATH", tools_dir.path())
+            .args(["--lang", "en", "init", "--all"])
+            .assert()
+    };
+
+    init_all()
+        .success()
+        .stdout(predicate::str::contains("✓ codex: rules written"))
+        .stdout(predicate::str::contains("✓ gemini: rules written"))
+        .stdout(predicate::str::contains(
+            "- claude: skipped (claude not found in PATH)",
+        ))
+        .stdout(predicate::str::contains("- cursor: skipped"));
+    assert!(temp_dir.path().join(".codex/AGENTS.md").exists());
+
+    // A file where ~/.gemini should be makes that write fail, but codex is still updated
+    fs::remove_dir_all(temp_dir.path().join(".gemini")).unwrap();
+    fs::write(temp_dir.path().join(".gemini"), "").unwrap();
+    fs::remove_file(temp_dir.path().join(".codex/AGENTS.md")).unwrap();
+    init_all()
+        .code(1)
+        .stdout(predicate::str::contains("✗ gemini: failed"))
+        .stdout(predicate::str::contains("✓ codex: rules written"));
+    assert!(temp_dir.path().join(".codex/AGENTS.md").exists());
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["init", "--all", "codex"])
+        .assert()
+        .code(2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_init_claude_writes_claude_md_when_claude_old() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let tools_dir = TempDir::new().unwrap();
+
+    let claude = tools_dir.path().join("claude");
+    fs::write(&claude, "#!/bin/sh\necho \"2.0.63\"\nexit 0\n").unwrap();
+    fs::set_permissions(&claude, fs::Permissions::from_mode(0o755)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", tools_dir.path())
+        .args(["init", "claude"])
+        .assert()
+        .success();
+
+    let rules_file = temp_dir.path().join(".claude/CLAUDE.md");
+    assert!(rules_file.exists());
+    let content = fs::read_to_string(rules_file).unwrap();
+    assert!(content.contains("shnote rules start"));
+}
+
+#[test]
+fn test_init_codex() {
+    let temp_dir = TempDir::new().unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["init", "codex"])
+        .assert()
+        .success();
+
+    let rules_file = temp_dir.path().join(".codex/AGENTS.md");
+    assert!(rules_file.exists());
+    let content = fs::read_to_string(rules_file).unwrap();
+    assert!(content.contains("shnote rules start"));
+}
+
+#[test]
+fn test_init_rules_lang_is_independent_of_message_language() {
+    let temp_dir = TempDir::new().unwrap();
+    let rules_file = temp_dir.path().join(".codex/AGENTS.md");
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "--rules-lang", "zh", "codex"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shnote rules written to"));
+    let content = fs::read_to_string(&rules_file).unwrap();
+    assert!(content.contains("# 运行 shell 命令必须使用 shnote 包装"));
+    assert!(!content.contains("# Wrap shell commands with shnote"));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "zh", "init", "codex", "--rules-lang", "en"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shnote 规则"));
+    let content = fs::read_to_string(&rules_file).unwrap();
+    assert!(content.contains("# Wrap shell commands with shnote"));
+    assert!(!content.contains("# 运行 shell 命令必须使用 shnote 包装"));
+}
+
+#[test]
+fn test_init_codex_updates_existing() {
+    let temp_dir = TempDir::new().unwrap();
+    let codex_dir = temp_dir.path().join(".codex");
+    fs::create_dir_all(&codex_dir).unwrap();
+    let rules_file = codex_dir.join("AGENTS.md");
+    fs::write(&rules_file, "Initial content\n").unwrap();
+
+    // First init
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["init", "codex"])
+        .assert()
+        .success();
+
+    // Second init should update, not duplicate
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "zh", "init", "codex"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("更新"));
+
+    let content = fs::read_to_string(rules_file).unwrap();
+    assert_eq!(content.matches("shnote rules start").count(), 1);
+}
+
+#[test]
+fn test_init_gemini() {
+    let temp_dir = TempDir::new().unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["init", "gemini"])
+        .assert()
+        .success();
+
+    let rules_file = temp_dir.path().join(".gemini/GEMINI.md");
+    assert!(rules_file.exists());
+}
+
+#[test]
+fn test_init_claude_errors_when_create_dir_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join(".claude"), "not a dir").unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", temp_dir.path())
+        .args(["--lang", "en", "init", "claude"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to create directory"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_init_claude_errors_when_write_fails() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".claude/rules")).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".claude/rules/shnote.md")).unwrap();
+
+    let tools_dir = TempDir::new().unwrap();
+    let claude = tools_dir.path().join("claude");
+    fs::write(&claude, "#!/bin/sh\necho \"2.0.64\"\nexit 0\n").unwrap();
+    fs::set_permissions(&claude, fs::Permissions::from_mode(0o755)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", tools_dir.path())
+        .args(["--lang", "en", "init", "claude"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to write file"));
+}
+
+#[test]
+fn test_init_codex_errors_when_create_dir_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join(".codex"), "not a dir").unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "codex"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to create directory"));
+}
+
+#[test]
+fn test_init_codex_errors_when_read_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".codex")).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".codex/AGENTS.md")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "codex"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to read file"));
+}
+
+#[test]
+fn test_init_gemini_errors_when_create_dir_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join(".gemini"), "not a dir").unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "gemini"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to create directory"));
+}
+
+#[test]
+fn test_init_gemini_errors_when_read_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".gemini")).unwrap();
+    fs::create_dir_all(temp_dir.path().join(".gemini/GEMINI.md")).unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "gemini"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to read file"));
+}
+
+#[test]
+fn test_init_errors_when_home_missing() {
+    shnote_cmd()
+        .env_remove("HOME")
+        .env_remove("USERPROFILE")
+        .args(["init", "claude"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to determine home directory",
+        ));
+}
+
+#[test]
+fn test_init_codex_errors_when_home_missing() {
+    shnote_cmd()
+        .env_remove("HOME")
+        .env_remove("USERPROFILE")
+        .args(["init", "codex"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to determine home directory",
+        ));
+}
+
+#[test]
+fn test_init_gemini_errors_when_home_missing() {
+    shnote_cmd()
+        .env_remove("HOME")
+        .env_remove("USERPROFILE")
+        .args(["init", "gemini"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to determine home directory",
+        ));
+}
+
+#[test]
+fn test_init_dry_run_does_not_write_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", empty_path.path())
+        .args(["--lang", "en", "init", "codex", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("would create file"))
+        .stdout(predicate::str::contains("--- /dev/null\n+++ "))
+        .stdout(predicate::str::contains("\n+<!-- shnote rules start -->\n"))
+        .stdout(predicate::str::contains("no files were changed"));
+
+    assert!(!temp_dir.path().join(".codex").exists());
+}
+
+#[test]
+fn test_init_list_targets_and_missing_target() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+
+    let assert = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("PATH", empty_path.path())
+        .args(["--lang", "en", "init", "--list-targets"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("~/.codex/AGENTS.md"))
+        .stdout(predicate::str::contains("gemini: not found in PATH"));
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    for target in [
+        "claude", "codex", "gemini", "agents", "copilot", "cursor", "windsurf", "aider",
+    ] {
+        assert!(stdout.lines().any(|line| line == target), "{target}");
+    }
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("init needs a target"));
+}
+
+#[test]
+fn test_init_remove_strips_rules_and_keeps_the_rest() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = TempDir::new().unwrap();
+    let conventions = project_dir.path().join("CONVENTIONS.md");
+    fs::write(&conventions, "# Team conventions\n").unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(project_dir.path())
+        .args(["--lang", "en", "init", "-s", "project", "aider"])
+        .assert()
+        .success();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(project_dir.path())
+        .args(["--lang", "en", "init", "-s", "project", "aider", "--remove"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed shnote rules from"));
+    assert_eq!(
+        fs::read_to_string(&conventions).unwrap(),
+        "# Team conventions\n"
+    );
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(project_dir.path())
+        .args(["--lang", "en", "init", "-s", "project", "aider", "--remove"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing to remove"));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "--all", "--remove"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_test_rules_checks_every_target_and_is_hidden() {
+    let temp_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "test-rules"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ aider (zh)"))
+        .stdout(predicate::str::contains("✓ aider (ja)"))
+        .stdout(predicate::str::contains(
+            "All 24 rule templates render correctly.",
+        ));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("test-rules").not());
+}
+
+#[test]
+fn test_init_cursor_project_scope_writes_mdc_rules() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(project_dir.path())
+        .args(["--lang", "en", "init", "-s", "project", "cursor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shnote.mdc"));
+
+    let content = fs::read_to_string(project_dir.path().join(".cursor/rules/shnote.mdc")).unwrap();
+    assert!(content.starts_with("---\ndescription: "));
+    assert!(content.contains("alwaysApply: true\n---\n"));
+    assert!(content.contains("<!-- shnote rules start -->"));
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(project_dir.path())
+        .args(["--lang", "en", "init", "cursor"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--scope project"));
+}
+
+#[test]
+fn test_init_windsurf_writes_global_rules_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+
+    for _ in 0..2 {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .env("PATH", empty_path.path())
+            .args(["--lang", "en", "init", "windsurf"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("windsurf not found in PATH"))
+            .stdout(predicate::str::contains("global_rules.md"));
+    }
+
+    let content = fs::read_to_string(
+        temp_dir
+            .path()
+            .join(".codeium/windsurf/memories/global_rules.md"),
+    )
+    .unwrap();
+    assert_eq!(content.matches("<!-- shnote rules start -->").count(), 1);
+}
+
+#[test]
+fn test_init_aider_project_scope_keeps_conventions() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+    let conventions = project_dir.path().join("CONVENTIONS.md");
+    fs::write(&conventions, "# Conventions\nPrefer small functions.\n").unwrap();
+
+    for _ in 0..2 {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .env("PATH", empty_path.path())
+            .current_dir(project_dir.path())
+            .args(["--lang", "en", "init", "-s", "project", "aider"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("aider not found in PATH"))
+            .stdout(predicate::str::contains("--read"));
+    }
+
+    let content = fs::read_to_string(&conventions).unwrap();
+    assert!(content.starts_with("# Conventions\nPrefer small functions.\n"));
+    assert_eq!(content.matches("<!-- shnote rules start -->").count(), 1);
+}
+
+#[test]
+fn test_init_agents_project_scope_writes_root_agents_md() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(project_dir.path())
+        .args(["init", "-s", "project", "agents"])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(project_dir.path().join("AGENTS.md")).unwrap();
+    assert!(content.contains("<!-- shnote rules start -->"));
+}
+
+#[test]
+fn test_init_agents_user_scope_writes_config_agents_md() {
+    let temp_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "init", "agents"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".config/AGENTS.md"));
+
+    let content = fs::read_to_string(temp_dir.path().join(".config/AGENTS.md")).unwrap();
+    assert!(content.contains("<!-- shnote rules start -->"));
+}
+
+// === doctor command ===
+#[test]
+fn test_doctor() {
+    shnote_cmd()
+        .arg("doctor")
+        .assert()
+        .code(predicate::in_iter([0, 1])); // May succeed or fail depending on environment
+}
+
+#[cfg(unix)]
+#[test]
+fn test_doctor_success() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home_dir = TempDir::new().unwrap();
+    let tools_dir = TempDir::new().unwrap();
+
+    let python3 = tools_dir.path().join("python3");
+    fs::write(&python3, "#!/bin/sh\necho \"Python 3.0\" >&2\nexit 0\n").unwrap();
+    fs::set_permissions(&python3, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let node = tools_dir.path().join("node");
+    fs::write(&node, "#!/bin/sh\necho \"v1.0\"\nexit 0\n").unwrap();
+    fs::set_permissions(&node, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let bash = tools_dir.path().join("bash");
+    fs::write(&bash, "#!/bin/sh\necho \"bash 1.0\"\nexit 0\n").unwrap();
+    fs::set_permissions(&bash, fs::Permissions::from_mode(0o755)).unwrap();
+
+    // Provide pueue binaries in shnote's bin directory.
+    let bin_dir = home_dir.path().join(".shnote/bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+    let pueue = bin_dir.join("pueue");
+    fs::write(&pueue, "#!/bin/sh\necho \"pueue 4.0\"\nexit 0\n").unwrap();
+    fs::set_permissions(&pueue, fs::Permissions::from_mode(0o755)).unwrap();
+    let pueued = bin_dir.join("pueued");
+    fs::write(&pueued, "#!/bin/sh\necho \"pueued 4.0\"\nexit 0\n").unwrap();
+    fs::set_permissions(&pueued, fs::Permissions::from_mode(0o755)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .env("PATH", tools_dir.path())
+        .env("SHELL", &bash)
+        .args(["--lang", "en", "doctor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All dependencies OK!"));
+}
+
+#[test]
+fn test_doctor_check_rules_drift_exit_code() {
+    let home_dir = TempDir::new().unwrap();
+    let drift = || {
+        shnote_cmd()
+            .env("HOME", home_dir.path())
+            .args(["--lang", "en", "doctor", "--check-rules-drift"])
+            .assert()
+    };
+
+    drift()
+        .success()
+        .stdout(predicate::str::contains("No installed shnote rules found"));
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .args(["--lang", "en", "init", "codex"])
+        .assert()
+        .success();
+    drift()
+        .success()
+        .stdout(predicate::str::contains("codex rules"));
+
+    let agents = home_dir.path().join(".codex/AGENTS.md");
+    let edited = fs::read_to_string(&agents).unwrap().replace(
+        "<!-- shnote rules end -->",
+        "my tweak\n<!-- shnote rules end -->",
+    );
+    fs::write(&agents, edited).unwrap();
+    drift().code(1).stdout(predicate::str::contains(
+        "differs from this version's rules",
+    ));
+
+    let output = shnote_cmd()
+        .env("HOME", home_dir.path())
+        .args(["--lang", "en", "doctor", "--check-rules-drift", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["schema_version"], 1);
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["checks"][0]["name"], "codex rules");
+    assert_eq!(report["checks"][0]["ok"], false);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_doctor_check_agent_reports_tool_and_rules() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home_dir = TempDir::new().unwrap();
+    let bin_dir = TempDir::new().unwrap();
+    let codex = bin_dir.path().join("codex");
+    fs::write(&codex, "#!/bin/sh\necho 'codex-cli 9.9.9'\n").unwrap();
+    fs::set_permissions(&codex, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let doctor_checks = || {
+        let output = shnote_cmd()
+            .env("HOME", home_dir.path())
+            .env("PATH", &path)
+            .args(["--lang", "en", "doctor", "--check", "codex", "--json"])
+            .output()
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["checks"].as_array().unwrap().clone()
+    };
+    let check = |checks: &[serde_json::Value], name: &str| {
+        checks
+            .iter()
+            .find(|check| check["name"] == name)
+            .unwrap_or_else(|| panic!("no {name} check"))
+            .clone()
+    };
+
+    let checks = doctor_checks();
+    assert_eq!(check(&checks, "codex")["version"], "codex-cli 9.9.9");
+    let rules = check(&checks, "codex rules");
+    assert_eq!(rules["ok"], false);
+    assert_eq!(rules["optional"], true);
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .env("PATH", &path)
+        .args(["--lang", "en", "init", "codex"])
+        .assert()
+        .success();
+    let rules = check(&doctor_checks(), "codex rules");
+    assert_eq!(rules["ok"], true);
+    assert_eq!(rules["version"], "up to date");
+
+    let agents = home_dir.path().join(".codex/AGENTS.md");
+    let edited = fs::read_to_string(&agents).unwrap().replace(
+        "<!-- shnote rules end -->",
+        "Local tweak\n<!-- shnote rules end -->",
+    );
+    fs::write(&agents, edited).unwrap();
+    let rules = check(&doctor_checks(), "codex rules");
+    assert_eq!(rules["ok"], false);
+    assert!(rules["error"].as_str().unwrap().contains("differs"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_doctor_failure_exit_code() {
+    let home_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .env("PATH", empty_path.path())
+        .env_remove("SHELL")
+        .args(["--lang", "en", "doctor"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Some dependencies have issues"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_setup_creates_pueue_binaries() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home_dir = TempDir::new().unwrap();
+    let tools_dir = TempDir::new().unwrap();
+
+    // Provide fake curl + shasum in case setup falls back to download path.
+    let curl = tools_dir.path().join("curl");
+    fs::write(
+        &curl,
+        "#!/bin/sh\n\
+dest=\"\"\n\
+while [ \"$#\" -gt 0 ]; do\n\
+  if [ \"$1\" = \"-o\" ]; then\n\
+    dest=\"$2\"\n\
+    break\n\
+  fi\n\
+  shift\n\
+done\n\
+if [ -z \"$dest\" ]; then\n\
+  exit 2\n\
+fi\n\
+echo \"dummy\" > \"$dest\"\n\
+exit 0\n",
+    )
+    .unwrap();
+    fs::set_permissions(&curl, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let shasum = tools_dir.path().join("shasum");
+    // Platform-specific checksums from src/pueue_embed.rs (v4.0.1)
+    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    let (pueue_sha, pueued_sha) = (
+        "4306f593b6a6b6db9d641889e33fe3a2effa6423888b8f82391fa57951ef1a9b",
+        "dc14a7873a4a474ae42e7a6ee5778c2af2d53049182ecaa2d061f4803f04bf23",
+    );
+    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+    let (pueue_sha, pueued_sha) = (
+        "25f07f7e93f916d6189acc11846aab6ebee975b0cc5867cf40a96b5c70f3b55c",
+        "3e50d3bfadd1e417c8561aed2c1f4371605e8002f7fd793f39045719af5436a8",
+    );
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    let (pueue_sha, pueued_sha) = (
+        "16aea6654b3915c6495bb2f456184fd7f3d418de3f74afb5eab04ae953cdfedf",
+        "8a97b176f55929e37cda49577b28b66ea345151adf766b9d8efa8c9d81525a0b",
+    );
+    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+    let (pueue_sha, pueued_sha) = (
+        "666af79b5a0246efa61a8589e51a190e3174bf80ad1c78b264204e7d312d43a9",
+        "8d3811f2ad57ef72ed171f446f19676ef755e189286d1c31a1e478ed57465bdb",
+    );
+    fs::write(
+        &shasum,
+        format!(
+            "#!/bin/sh\n\
+file=\"$3\"\n\
+case \"$file\" in\n\
+  *pueued) echo \"{pueued_sha}  $file\" ;;\n\
+  *pueue) echo \"{pueue_sha}  $file\" ;;\n\
+  *) echo \"{pueue_sha}  $file\" ;;\n\
+esac\n\
+exit 0\n"
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&shasum, fs::Permissions::from_mode(0o755)).unwrap();
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .env("PATH", tools_dir.path())
+        .args(["--lang", "en", "setup"])
+        .assert()
+        .success();
+
+    let bin_dir = home_dir.path().join(".shnote/bin");
+    assert!(bin_dir.join("pueue").exists());
+    assert!(bin_dir.join("pueued").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_setup_errors_when_shnote_home_is_file() {
+    let home_dir = TempDir::new().unwrap();
+    fs::write(home_dir.path().join(".shnote"), "not a dir").unwrap();
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .args(["--lang", "en", "setup"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed"));
+}
+
+#[test]
+fn test_setup_check_only_fails_when_binaries_missing() {
+    let home_dir = TempDir::new().unwrap();
+
+    shnote_cmd()
+        .env("HOME", home_dir.path())
+        .args(["--lang", "en", "setup", "--check-only"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("pueue: not installed"))
+        .stdout(predicate::str::contains("shnote setup"));
+    assert!(!home_dir.path().join(".shnote/bin").exists());
+}
+
+#[test]
+fn test_plain_output_is_ascii_for_init_setup_and_update() {
+    let home_dir = TempDir::new().unwrap();
+    let empty_path = TempDir::new().unwrap();
+    let shnote_dir = home_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    // An unreachable proxy makes `update` fail fast without the network
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "[network]\ngithub_proxy = \"http://127.0.0.1:9/\"\n",
+    )
+    .unwrap();
+
+    let runs: [&[&str]; 3] = [
+        &["--plain", "--lang", "en", "init", "-s", "user", "claude"],
+        &["--plain", "--lang", "en", "setup", "--check-only"],
+        &["--plain", "--lang", "en", "update", "--check"],
+    ];
+    for args in runs {
+        let output = shnote_cmd()
+            .env("HOME", home_dir.path())
+            .env("PATH", empty_path.path())
+            .env("RUST_BACKTRACE", "0")
+            .args(args)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(!stdout.is_empty(), "{args:?} printed nothing");
+        assert!(stdout.is_ascii(), "{args:?} stdout: {stdout}");
+        assert!(stderr.is_ascii(), "{args:?} stderr: {stderr}");
+    }
+}
+
+#[test]
+fn test_setup_confirm_download_refuses_without_terminal() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "[setup]\nconfirm_download = true\n",
+    )
+    .unwrap();
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "setup"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("github.com/Nukesor/pueue"))
+        .stderr(predicate::str::contains("re-run with --yes"));
+}
+
+#[test]
+fn test_doctor_check_locale_reports_source() {
+    let temp_dir = TempDir::new().unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("LC_ALL", "zh_CN.UTF-8")
+        .env_remove("SHNOTE_LANG")
+        .args(["--lang", "en", "doctor", "--check-locale"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("LC_ALL: zh_CN.UTF-8"))
+        .stdout(predicate::str::contains("result: en (source: --lang)"));
+}
+
+#[test]
+fn test_doctor_check_locale_uses_env_when_auto() {
+    let temp_dir = TempDir::new().unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_LANG", "en")
+        .env("LC_ALL", "zh_CN.UTF-8")
+        .args(["doctor", "--check-locale"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(source: env (SHNOTE_LANG))"));
 }
 
 #[test]
-fn test_config_set_invalid_language() {
+fn test_doctor_check_updates_offline_is_skipped() {
+    let temp_dir = TempDir::new().unwrap();
     shnote_cmd()
-        .args(["config", "set", "language", "invalid"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "doctor", "--check-updates", "--offline"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("invalid"));
+        .stdout(predicate::str::contains(
+            "shnote: update check skipped (--offline)",
+        ));
 }
 
 #[test]
-fn test_config_path() {
+fn test_doctor_check_history_reports_malformed_log() {
+    let temp_dir = TempDir::new().unwrap();
+    let data_dir = TempDir::new().unwrap();
+    fs::write(data_dir.path().join("history.jsonl"), "oops\n").unwrap();
     shnote_cmd()
-        .args(["config", "path"])
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "doctor", "--check-history"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("config.toml"));
+        .failure()
+        .stdout(predicate::str::contains(
+            "history: 1 of 1 history lines are malformed",
+        ));
 }
 
 #[test]
-fn test_config_reset() {
+fn test_version_check_interval_is_validated() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".shnote")).unwrap();
-
-    // Set some config first
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "set", "language", "zh"])
+        .args(["--lang", "en", "--version-check-interval", "weekly"])
+        .args(["--what", "w", "--why", "y", "run", "true"])
         .assert()
-        .success();
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid version check interval: weekly",
+        ));
 
-    // Reset
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "reset"])
+        .args([
+            "--lang",
+            "en",
+            "config",
+            "set",
+            "version_check_interval",
+            "1w",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid version check interval"));
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["config", "set", "version_check_interval", "12H"])
         .assert()
         .success();
-
-    // Verify reset to default
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["config", "get", "language"])
+        .args(["config", "get", "version_check_interval"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("auto"));
+        .stdout("12h\n");
 }
 
+// === completions command ===
 #[test]
-fn test_config_reset_errors_when_home_missing() {
+fn test_completions_bash() {
     shnote_cmd()
-        .env_remove("HOME")
-        .env_remove("USERPROFILE")
-        .args(["--lang", "en", "config", "reset"])
+        .args(["completions", "bash"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains(
-            "failed to determine home directory",
-        ));
+        .success()
+        .stdout(predicate::str::contains("shnote"));
 }
 
-// === init command ===
 #[test]
-fn test_init_claude() {
-    let temp_dir = TempDir::new().unwrap();
+fn test_completions_zsh() {
     shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .env("PATH", temp_dir.path())
-        .args(["init", "claude"])
+        .args(["completions", "zsh"])
         .assert()
-        .success();
-
-    let rules_file = temp_dir.path().join(".claude/CLAUDE.md");
-    assert!(rules_file.exists());
-    let content = fs::read_to_string(rules_file).unwrap();
-    assert!(content.contains("shnote rules start"));
+        .success()
+        .stdout(predicate::str::contains("shnote"));
 }
 
-#[cfg(unix)]
 #[test]
-fn test_init_claude_writes_rules_when_claude_new_enough() {
-    use std::os::unix::fs::PermissionsExt;
-
-    let temp_dir = TempDir::new().unwrap();
-    let tools_dir = TempDir::new().unwrap();
-
-    let claude = tools_dir.path().join("claude");
-    fs::write(&claude, "#!/bin/sh\necho \"Claude Code 2.0.64\"\nexit 0\n").unwrap();
-    fs::set_permissions(&claude, fs::Permissions::from_mode(0o755)).unwrap();
-
+fn test_completions_fish() {
     shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .env("PATH", tools_dir.path())
-        .args(["init", "claude"])
+        .args(["completions", "fish"])
         .assert()
-        .success();
-
-    let rules_file = temp_dir.path().join(".claude/rules/shnote.md");
-    assert!(rules_file.exists());
-    let content = fs::read_to_string(rules_file).unwrap();
-    assert!(content.contains("shnote"));
-    assert!(content.contains("--what"));
+        .success()
+        .stdout(predicate::str::contains("shnote"));
 }
 
-#[cfg(unix)]
 #[test]
-fn test_init_claude_writes_claude_md_when_claude_old() {
-    use std::os::unix::fs::PermissionsExt;
-
-    let temp_dir = TempDir::new().unwrap();
-    let tools_dir = TempDir::new().unwrap();
-
-    let claude = tools_dir.path().join("claude");
-    fs::write(&claude, "#!/bin/sh\necho \"2.0.63\"\nexit 0\n").unwrap();
-    fs::set_permissions(&claude, fs::Permissions::from_mode(0o755)).unwrap();
-
+fn test_completions_powershell() {
     shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .env("PATH", tools_dir.path())
-        .args(["init", "claude"])
+        .args(["completions", "powershell"])
         .assert()
         .success();
-
-    let rules_file = temp_dir.path().join(".claude/CLAUDE.md");
-    assert!(rules_file.exists());
-    let content = fs::read_to_string(rules_file).unwrap();
-    assert!(content.contains("shnote rules start"));
 }
 
 #[test]
-fn test_init_codex() {
-    let temp_dir = TempDir::new().unwrap();
+fn test_completions_elvish() {
     shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["init", "codex"])
+        .args(["completions", "elvish"])
         .assert()
-        .success();
-
-    let rules_file = temp_dir.path().join(".codex/AGENTS.md");
-    assert!(rules_file.exists());
-    let content = fs::read_to_string(rules_file).unwrap();
-    assert!(content.contains("shnote rules start"));
+        .success()
+        .stdout(predicate::str::contains("shnote"));
 }
 
 #[test]
-fn test_init_codex_updates_existing() {
-    let temp_dir = TempDir::new().unwrap();
-    let codex_dir = temp_dir.path().join(".codex");
-    fs::create_dir_all(&codex_dir).unwrap();
-    let rules_file = codex_dir.join("AGENTS.md");
-    fs::write(&rules_file, "Initial content\n").unwrap();
-
-    // First init
-    shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["init", "codex"])
-        .assert()
-        .success();
-
-    // Second init should update, not duplicate
+fn test_completions_list() {
     shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["--lang", "zh", "init", "codex"])
+        .args(["completions", "--list"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("更新"));
-
-    let content = fs::read_to_string(rules_file).unwrap();
-    assert_eq!(content.matches("shnote rules start").count(), 1);
+        .stdout("bash\nzsh\nfish\npowershell\nelvish\n");
 }
 
 #[test]
-fn test_init_gemini() {
-    let temp_dir = TempDir::new().unwrap();
+fn test_completions_requires_shell_or_list() {
+    shnote_cmd().args(["completions"]).assert().failure();
     shnote_cmd()
-        .env("HOME", temp_dir.path())
-        .args(["init", "gemini"])
+        .args(["completions", "--list", "bash"])
         .assert()
-        .success();
-
-    let rules_file = temp_dir.path().join(".gemini/GEMINI.md");
-    assert!(rules_file.exists());
+        .failure();
 }
 
+// === info command ===
 #[test]
-fn test_init_claude_errors_when_create_dir_fails() {
+fn test_info_shows_data_dir_override() {
     let temp_dir = TempDir::new().unwrap();
-    fs::write(temp_dir.path().join(".claude"), "not a dir").unwrap();
+    let data_dir = TempDir::new().unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .env("PATH", temp_dir.path())
-        .args(["--lang", "en", "init", "claude"])
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "info"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to create directory"));
+        .success()
+        .stdout(predicate::str::contains(
+            data_dir.path().display().to_string(),
+        ));
 }
 
-#[cfg(unix)]
 #[test]
-fn test_init_claude_errors_when_write_fails() {
-    use std::os::unix::fs::PermissionsExt;
-
+fn test_prune_without_what_why() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".claude/rules")).unwrap();
-    fs::create_dir_all(temp_dir.path().join(".claude/rules/shnote.md")).unwrap();
-
-    let tools_dir = TempDir::new().unwrap();
-    let claude = tools_dir.path().join("claude");
-    fs::write(&claude, "#!/bin/sh\necho \"2.0.64\"\nexit 0\n").unwrap();
-    fs::set_permissions(&claude, fs::Permissions::from_mode(0o755)).unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .env("PATH", tools_dir.path())
-        .args(["--lang", "en", "init", "claude"])
+        .env("TMPDIR", temp_dir.path())
+        .args(["--lang", "en", "prune"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to write file"));
+        .success();
 }
 
+// === history command ===
 #[test]
-fn test_init_codex_errors_when_create_dir_fails() {
+fn test_history_clear_missing_file_succeeds() {
     let temp_dir = TempDir::new().unwrap();
-    fs::write(temp_dir.path().join(".codex"), "not a dir").unwrap();
+    let data_dir = TempDir::new().unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--lang", "en", "init", "codex"])
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "history", "clear"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to create directory"));
+        .success()
+        .stdout(predicate::str::contains("No history to clear"));
 }
 
 #[test]
-fn test_init_codex_errors_when_read_fails() {
+fn test_history_clear_with_yes() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".codex")).unwrap();
-    fs::create_dir_all(temp_dir.path().join(".codex/AGENTS.md")).unwrap();
+    let data_dir = TempDir::new().unwrap();
+    let history = data_dir.path().join("history.jsonl");
+    fs::write(&history, "{}\n").unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--lang", "en", "init", "codex"])
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "history", "clear", "--yes"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to read file"));
+        .success()
+        .stdout(predicate::str::contains("Cleared history"));
+    assert!(!history.exists());
 }
 
 #[test]
-fn test_init_gemini_errors_when_create_dir_fails() {
+fn test_history_clear_prompts_for_confirmation() {
     let temp_dir = TempDir::new().unwrap();
-    fs::write(temp_dir.path().join(".gemini"), "not a dir").unwrap();
+    let data_dir = TempDir::new().unwrap();
+    let history = data_dir.path().join("history.jsonl");
+    fs::write(&history, "{}\n").unwrap();
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--lang", "en", "init", "gemini"])
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "history", "clear"])
+        .write_stdin("n\n")
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to create directory"));
+        .success()
+        .stdout(predicate::str::contains("cancelled"));
+    assert!(history.exists());
+
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", data_dir.path())
+        .args(["--lang", "en", "history", "clear"])
+        .write_stdin("y\n")
+        .assert()
+        .success();
+    assert!(!history.exists());
 }
 
 #[test]
-fn test_init_gemini_errors_when_read_fails() {
+fn test_history_records_commands_and_filters_by_tag() {
     let temp_dir = TempDir::new().unwrap();
-    fs::create_dir_all(temp_dir.path().join(".gemini")).unwrap();
-    fs::create_dir_all(temp_dir.path().join(".gemini/GEMINI.md")).unwrap();
+    write_color_disabled_config(&temp_dir);
+
+    for (what, tag) in [("first", "refactor-auth"), ("second", "docs")] {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args([
+                "--what",
+                what,
+                "--why",
+                "tagging",
+                "--note-tag",
+                tag,
+                "run",
+                "true",
+            ])
+            .assert()
+            .success();
+    }
 
     shnote_cmd()
         .env("HOME", temp_dir.path())
-        .args(["--lang", "en", "init", "gemini"])
+        .args(["--lang", "en", "history", "--tag", "refactor-auth"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed to read file"));
+        .success()
+        .stdout(predicate::str::contains("first — tagging"))
+        .stdout(predicate::str::contains("[refactor-auth]"))
+        .stdout(predicate::str::contains("second").not());
 }
 
 #[test]
-fn test_init_errors_when_home_missing() {
+fn test_history_limit_json_and_since() {
+    let temp_dir = TempDir::new().unwrap();
+    for what in ["one", "two", "three"] {
+        shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args([
+                "--what", what, "--why", "audit", "run", "sh", "-c", "exit 4",
+            ])
+            .assert()
+            .code(4);
+    }
+
     shnote_cmd()
-        .env_remove("HOME")
-        .env_remove("USERPROFILE")
-        .args(["init", "claude"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "history", "--limit", "2"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains(
-            "failed to determine home directory",
-        ));
-}
+        .success()
+        .stdout(predicate::str::contains("#2  "))
+        .stdout(predicate::str::contains("three — audit  (exit=4, "))
+        .stdout(predicate::str::contains("one").not());
+
+    let output = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["history", "--json", "--since", "1h"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["schema_version"], 1);
+    let records = report["records"].as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["what"], "one");
+    assert_eq!(records[0]["exit_code"], 4);
+    assert!(records[0]["duration_ms"].is_u64());
 
-#[test]
-fn test_init_codex_errors_when_home_missing() {
     shnote_cmd()
-        .env_remove("HOME")
-        .env_remove("USERPROFILE")
-        .args(["init", "codex"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "history", "--since", "soon"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains(
-            "failed to determine home directory",
-        ));
+        .code(2)
+        .stderr(predicate::str::contains("invalid --since value: soon"));
 }
 
 #[test]
-fn test_init_gemini_errors_when_home_missing() {
+fn test_history_export_writes_csv() {
+    let temp_dir = TempDir::new().unwrap();
     shnote_cmd()
-        .env_remove("HOME")
-        .env_remove("USERPROFILE")
-        .args(["init", "gemini"])
+        .env("HOME", temp_dir.path())
+        .args(["--what", "say \"hi\", twice", "--why", "audit", "run"])
+        .args(["echo", "a,b"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains(
-            "failed to determine home directory",
-        ));
-}
+        .success();
+
+    let stdout = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["history", "export"])
+        .output()
+        .unwrap()
+        .stdout;
+    let csv = String::from_utf8(stdout).unwrap();
+    let lines: Vec<&str> = csv.split("\r\n").collect();
+    assert_eq!(
+        lines[0],
+        "timestamp,what,why,program,args,cwd,exit_code,duration_ms"
+    );
+    assert!(
+        lines[1].contains(r#","say ""hi"", twice",audit,run,"echo a,b","#),
+        "{csv}"
+    );
+    assert_eq!(lines.len(), 3, "{csv}");
 
-// === doctor command ===
-#[test]
-fn test_doctor() {
+    let file = temp_dir.path().join("history.csv");
     shnote_cmd()
-        .arg("doctor")
+        .env("HOME", temp_dir.path())
+        .args(["history", "--tag", "none", "export", "-o"])
+        .arg(&file)
         .assert()
-        .code(predicate::in_iter([0, 1])); // May succeed or fail depending on environment
+        .success()
+        .stdout("");
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "timestamp,what,why,program,args,cwd,exit_code,duration_ms\r\n"
+    );
 }
 
 #[cfg(unix)]
 #[test]
-fn test_doctor_success() {
-    use std::os::unix::fs::PermissionsExt;
-
-    let home_dir = TempDir::new().unwrap();
-    let tools_dir = TempDir::new().unwrap();
-
-    let python3 = tools_dir.path().join("python3");
-    fs::write(&python3, "#!/bin/sh\necho \"Python 3.0\" >&2\nexit 0\n").unwrap();
-    fs::set_permissions(&python3, fs::Permissions::from_mode(0o755)).unwrap();
-
-    let node = tools_dir.path().join("node");
-    fs::write(&node, "#!/bin/sh\necho \"v1.0\"\nexit 0\n").unwrap();
-    fs::set_permissions(&node, fs::Permissions::from_mode(0o755)).unwrap();
-
-    let bash = tools_dir.path().join("bash");
-    fs::write(&bash, "#!/bin/sh\necho \"bash 1.0\"\nexit 0\n").unwrap();
-    fs::set_permissions(&bash, fs::Permissions::from_mode(0o755)).unwrap();
-
-    // Provide pueue binaries in shnote's bin directory.
-    let bin_dir = home_dir.path().join(".shnote/bin");
-    fs::create_dir_all(&bin_dir).unwrap();
-    let pueue = bin_dir.join("pueue");
-    fs::write(&pueue, "#!/bin/sh\necho \"pueue 4.0\"\nexit 0\n").unwrap();
-    fs::set_permissions(&pueue, fs::Permissions::from_mode(0o755)).unwrap();
-    let pueued = bin_dir.join("pueued");
-    fs::write(&pueued, "#!/bin/sh\necho \"pueued 4.0\"\nexit 0\n").unwrap();
-    fs::set_permissions(&pueued, fs::Permissions::from_mode(0o755)).unwrap();
-
+fn test_history_records_duration_of_killed_command() {
+    let temp_dir = TempDir::new().unwrap();
     shnote_cmd()
-        .env("HOME", home_dir.path())
-        .env("PATH", tools_dir.path())
-        .env("SHELL", &bash)
-        .args(["--lang", "en", "doctor"])
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "w",
+            "--why",
+            "y",
+            "run",
+            "sh",
+            "-c",
+            "sleep 0.2; kill -9 $$",
+        ])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("All dependencies OK!"));
+        .failure();
+
+    let history = fs::read_to_string(temp_dir.path().join(".shnote/history.jsonl")).unwrap();
+    let record: serde_json::Value = serde_json::from_str(history.trim()).unwrap();
+    assert_eq!(record["success"], false);
+    assert!(record["duration_ms"].as_u64().unwrap() >= 200, "{history}");
 }
 
 #[cfg(unix)]
 #[test]
-fn test_doctor_failure_exit_code() {
-    let home_dir = TempDir::new().unwrap();
-    let empty_path = TempDir::new().unwrap();
+fn test_capture_stores_output_tails_in_history() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    fs::write(
+        shnote_dir.join("config.toml"),
+        "color = false\n\n[history]\ncapture_bytes = 4\n",
+    )
+    .unwrap();
+    let script = "printf 'ééé\\n'; echo oops >&2; exit 2";
 
     shnote_cmd()
-        .env("HOME", home_dir.path())
-        .env("PATH", empty_path.path())
-        .env_remove("SHELL")
-        .args(["--lang", "en", "doctor"])
+        .env("HOME", temp_dir.path())
+        .args([
+            "--what",
+            "w",
+            "--why",
+            "y",
+            "--capture",
+            "run",
+            "sh",
+            "-c",
+            script,
+        ])
         .assert()
-        .code(1)
-        .stdout(predicate::str::contains("Some dependencies have issues"));
+        .code(2)
+        .stdout("ééé\n")
+        .stderr(predicate::str::contains("oops\n"));
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--what", "w", "--why", "y", "run", "sh", "-c", script])
+        .assert()
+        .code(2);
+
+    let history = fs::read_to_string(shnote_dir.join("history.jsonl")).unwrap();
+    let records: Vec<serde_json::Value> = history
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // The last 4 bytes would start inside "é", so the excerpt starts after it
+    assert_eq!(records[0]["stdout_excerpt"], "é\n");
+    assert_eq!(records[0]["stderr_excerpt"], "ops\n");
+    assert!(records[1].get("stdout_excerpt").is_none());
 }
 
 #[cfg(unix)]
 #[test]
-fn test_setup_creates_pueue_binaries() {
-    use std::os::unix::fs::PermissionsExt;
+fn test_capture_drains_large_output_on_both_streams_separately() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
+    // Far more than a pipe buffer on each stream, written in alternation:
+    // reading one pipe to the end before the other would block the child.
+    let script = "i=0; while [ $i -lt 2000 ]; do \
+                  printf '%0512d\\n' 0; printf '%0512d\\n' 1 >&2; i=$((i+1)); done; \
+                  echo out-end; echo err-end >&2";
+
+    for config in [
+        "color = false\n\n[history]\ncapture_bytes = 16\n",
+        "color = false\noutput_prefix = \"> \"\n\n[history]\ncapture_bytes = 16\n",
+    ] {
+        fs::write(shnote_dir.join("config.toml"), config).unwrap();
+        let _ = fs::remove_file(shnote_dir.join("history.jsonl"));
+
+        let output = shnote_cmd()
+            .env("HOME", temp_dir.path())
+            .args(["--what", "w", "--why", "y", "--capture", "run"])
+            .args(["sh", "-c", script])
+            .timeout(std::time::Duration::from_secs(60))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{config}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(stdout.matches("0\n").count(), 2000, "{config}");
+        assert!(stdout.trim_end().ends_with("out-end"), "{config}");
+        assert!(!stdout.contains('1'), "{config}");
+        assert!(stderr.contains("err-end"), "{config}");
+
+        let history = fs::read_to_string(shnote_dir.join("history.jsonl")).unwrap();
+        let record: serde_json::Value = serde_json::from_str(history.trim()).unwrap();
+        assert_eq!(record["stdout_excerpt"], "0000000\nout-end\n", "{config}");
+        assert_eq!(record["stderr_excerpt"], "0000001\nerr-end\n", "{config}");
+    }
+}
 
-    let home_dir = TempDir::new().unwrap();
-    let tools_dir = TempDir::new().unwrap();
+#[test]
+fn test_first_run_without_terminal_skips_onboarding() {
+    let temp_dir = TempDir::new().unwrap();
 
-    // Provide fake curl + shasum in case setup falls back to download path.
-    let curl = tools_dir.path().join("curl");
-    fs::write(
-        &curl,
-        "#!/bin/sh\n\
-dest=\"\"\n\
-while [ \"$#\" -gt 0 ]; do\n\
-  if [ \"$1\" = \"-o\" ]; then\n\
-    dest=\"$2\"\n\
-    break\n\
-  fi\n\
-  shift\n\
-done\n\
-if [ -z \"$dest\" ]; then\n\
-  exit 2\n\
-fi\n\
-echo \"dummy\" > \"$dest\"\n\
-exit 0\n",
-    )
-    .unwrap();
-    fs::set_permissions(&curl, fs::Permissions::from_mode(0o755)).unwrap();
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env_remove("SHNOTE_DATA_DIR")
+        .args(["--lang", "en", "--what", "x", "--why", "y", "run", "true"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Welcome").not());
 
-    let shasum = tools_dir.path().join("shasum");
-    // Platform-specific checksums from src/pueue_embed.rs (v4.0.1)
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    let (pueue_sha, pueued_sha) = (
-        "4306f593b6a6b6db9d641889e33fe3a2effa6423888b8f82391fa57951ef1a9b",
-        "dc14a7873a4a474ae42e7a6ee5778c2af2d53049182ecaa2d061f4803f04bf23",
-    );
-    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-    let (pueue_sha, pueued_sha) = (
-        "25f07f7e93f916d6189acc11846aab6ebee975b0cc5867cf40a96b5c70f3b55c",
-        "3e50d3bfadd1e417c8561aed2c1f4371605e8002f7fd793f39045719af5436a8",
-    );
-    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-    let (pueue_sha, pueued_sha) = (
-        "16aea6654b3915c6495bb2f456184fd7f3d418de3f74afb5eab04ae953cdfedf",
-        "8a97b176f55929e37cda49577b28b66ea345151adf766b9d8efa8c9d81525a0b",
-    );
-    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
-    let (pueue_sha, pueued_sha) = (
-        "666af79b5a0246efa61a8589e51a190e3174bf80ad1c78b264204e7d312d43a9",
-        "8d3811f2ad57ef72ed171f446f19676ef755e189286d1c31a1e478ed57465bdb",
-    );
+    assert!(!temp_dir.path().join(".shnote/onboarded").exists());
+}
+
+#[test]
+fn test_history_disabled_records_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    let shnote_dir = temp_dir.path().join(".shnote");
+    fs::create_dir_all(&shnote_dir).unwrap();
     fs::write(
-        &shasum,
-        format!(
-            "#!/bin/sh\n\
-file=\"$3\"\n\
-case \"$file\" in\n\
-  *pueued) echo \"{pueued_sha}  $file\" ;;\n\
-  *pueue) echo \"{pueue_sha}  $file\" ;;\n\
-  *) echo \"{pueue_sha}  $file\" ;;\n\
-esac\n\
-exit 0\n"
-        ),
+        shnote_dir.join("config.toml"),
+        "[history]\nenabled = false\n",
     )
     .unwrap();
-    fs::set_permissions(&shasum, fs::Permissions::from_mode(0o755)).unwrap();
 
     shnote_cmd()
-        .env("HOME", home_dir.path())
-        .env("PATH", tools_dir.path())
-        .args(["--lang", "en", "setup"])
+        .env("HOME", temp_dir.path())
+        .args(["--what", "w", "--why", "y", "run", "true"])
         .assert()
         .success();
+    assert!(!shnote_dir.join("history.jsonl").exists());
 
-    let bin_dir = home_dir.path().join(".shnote/bin");
-    assert!(bin_dir.join("pueue").exists());
-    assert!(bin_dir.join("pueued").exists());
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No history yet"));
 }
 
-#[cfg(unix)]
 #[test]
-fn test_setup_errors_when_shnote_home_is_file() {
-    let home_dir = TempDir::new().unwrap();
-    fs::write(home_dir.path().join(".shnote"), "not a dir").unwrap();
+fn test_history_unwritable_data_dir_warns_and_keeps_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    // A data dir below a regular file can never be created, whoever runs the test
+    let blocker = temp_dir.path().join("blocker");
+    fs::write(&blocker, "").unwrap();
+    let data_dir = blocker.join("data");
+
+    let output = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", &data_dir)
+        .args([
+            "--lang", "en", "--what", "w", "--why", "y", "run", "echo", "ran",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("ran"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("disabled it for this run").count(),
+        1,
+        "{stderr}"
+    );
 
     shnote_cmd()
-        .env("HOME", home_dir.path())
-        .args(["--lang", "en", "setup"])
+        .env("HOME", temp_dir.path())
+        .env("SHNOTE_DATA_DIR", &data_dir)
+        .args(["--what", "w", "--why", "y", "run", "sh", "-c", "exit 3"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("failed"));
+        .code(3);
 }
 
-// === completions command ===
+#[cfg(unix)]
 #[test]
-fn test_completions_bash() {
+fn test_replay_reruns_recorded_command() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let work_dir = TempDir::new().unwrap();
+
     shnote_cmd()
-        .args(["completions", "bash"])
+        .env("HOME", temp_dir.path())
+        .current_dir(work_dir.path())
+        .args([
+            "--what",
+            "mark",
+            "--why",
+            "first",
+            "run",
+            "--",
+            "sh",
+            "-c",
+            "echo ran >> log",
+        ])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("shnote"));
-}
+        .success();
 
-#[test]
-fn test_completions_zsh() {
     shnote_cmd()
-        .args(["completions", "zsh"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "replay", "1", "--reuse-note"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("shnote"));
-}
+        .stdout(predicate::str::contains("WHAT: mark"));
+    assert_eq!(
+        fs::read_to_string(work_dir.path().join("log")).unwrap(),
+        "ran\nran\n"
+    );
 
-#[test]
-fn test_completions_fish() {
     shnote_cmd()
-        .args(["completions", "fish"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "history"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("shnote"));
+        .stdout(predicate::str::contains("#2 "));
 }
 
+#[cfg(unix)]
 #[test]
-fn test_completions_powershell() {
+fn test_history_records_the_directory_the_command_ran_in() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(project.join("sub")).unwrap();
+    let history_cwd = |index: usize| {
+        let history = fs::read_to_string(temp_dir.path().join(".shnote/history.jsonl")).unwrap();
+        let line = history.lines().nth(index).unwrap();
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        record["cwd"].as_str().unwrap().to_string()
+    };
+
     shnote_cmd()
-        .args(["completions", "powershell"])
+        .env("HOME", temp_dir.path())
+        .current_dir(&project)
+        .args(["--what", "w", "--why", "y", "run", "--cwd", "sub"])
+        .args(["--", "sh", "-c", "echo ran >> log"])
+        .assert()
+        .success();
+    assert_eq!(history_cwd(0), project.join("sub").display().to_string());
+
+    // Replay from elsewhere runs in the same directory
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "replay", "1", "--reuse-note"])
         .assert()
         .success();
+    assert_eq!(
+        fs::read_to_string(project.join("sub/log")).unwrap(),
+        "ran\nran\n"
+    );
+
+    let output = shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(&project)
+        .args(["--what", "w", "--why", "y", "run", "--no-cwd-inherit"])
+        .args(["--", "sh", "-c", "echo ran >> log; pwd"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let scratch = stdout.lines().next().unwrap();
+    assert_eq!(history_cwd(2), scratch);
+
+    // The scratch directory is gone, so replay refuses instead of running in
+    // the project
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .current_dir(&project)
+        .args(["--lang", "en", "replay", "3", "--reuse-note"])
+        .assert()
+        .failure();
+    assert!(!project.join("log").exists());
 }
 
 #[test]
-fn test_completions_elvish() {
+fn test_replay_requires_note_and_existing_id() {
+    let temp_dir = TempDir::new().unwrap();
+    write_color_disabled_config(&temp_dir);
+
     shnote_cmd()
-        .args(["completions", "elvish"])
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "replay", "1"])
         .assert()
-        .success()
-        .stdout(predicate::str::contains("shnote"));
+        .failure()
+        .stderr(predicate::str::contains("requires `--what` and `--why`"));
+    shnote_cmd()
+        .env("HOME", temp_dir.path())
+        .args(["--lang", "en", "replay", "1", "--reuse-note"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no history entry #1"));
 }
 
 // === Error cases ===
@@ -1481,6 +4591,17 @@ fn test_py_requires_source_english() {
         .stderr(predicate::str::contains("exactly one of"));
 }
 
+#[test]
+fn test_py_rejects_two_sources() {
+    shnote_cmd()
+        .args([
+            "--lang", "en", "--what", "test", "--why", "test", "py", "-c", "print(1)", "--stdin",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
 #[test]
 fn test_run_nonexistent_command() {
     shnote_cmd()